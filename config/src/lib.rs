@@ -14,7 +14,7 @@ use tracing::info;
 
 /// The application configuration.
 ///
-/// This struct is the central point for the entire application configuration. It holds the [`ServerConfig`] as well as [`DatabaseConfig`]and can be extended with any application-specific configuration settings that will be read from the main `app.toml` and the environment-specific configuration files.
+/// This struct is the central point for the entire application configuration. It holds the [`ServerConfig`], [`DatabaseConfig`], [`AuthConfig`], [`TimelineConfig`] as well as [`ProblemDetectionConfig`] and can be extended with any application-specific configuration settings that will be read from the main `app.toml` and the environment-specific configuration files.
 ///
 /// For any setting that appears in both the `app.toml` and the environment-specific file, the latter will override the former so that default settings can be kept in `app.toml` that are overridden per environment if necessary.
 #[derive(Deserialize, Clone, Debug)]
@@ -23,6 +23,14 @@ pub struct Config {
     pub server: ServerConfig,
     /// the database configuration: [`DatabaseConfig`]
     pub database: DatabaseConfig,
+    /// the authentication configuration: [`AuthConfig`]
+    pub auth: AuthConfig,
+    /// the timeline configuration: [`TimelineConfig`]
+    #[serde(default)]
+    pub timeline: TimelineConfig,
+    /// the problem detection configuration: [`ProblemDetectionConfig`]
+    #[serde(default)]
+    pub problem_detection: ProblemDetectionConfig,
     // add your config settings here…
 }
 
@@ -97,6 +105,65 @@ pub struct DatabaseConfig {
     pub url: String,
 }
 
+/// The authentication configuration.
+///
+/// API clients authenticate with JWT bearer tokens signed with HS256. The secret used to verify those tokens
+/// should never be committed for production, set it via the `APP_AUTH__JWT_SECRET` environment variable instead.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct AuthConfig {
+    /// The secret tokens are signed with.
+    pub jwt_secret: String,
+    /// The expected issuer (`iss` claim) of the tokens. Tokens from any issuer are accepted if not set.
+    #[serde(default)]
+    pub jwt_issuer: Option<String>,
+}
+
+/// The timeline configuration.
+///
+/// Work notes and comments on Incidents, Problems and RFCs can be edited by their author for a while after writing them.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct TimelineConfig {
+    /// Minutes after writing an entry during which its author may still edit it, e.g. 15
+    pub edit_window_minutes: u32,
+}
+
+impl Default for TimelineConfig {
+    fn default() -> Self {
+        Self {
+            edit_window_minutes: 15,
+        }
+    }
+}
+
+/// The problem detection configuration.
+///
+/// A background job periodically groups recent Incidents on the same Configuration Item with similar titles and descriptions, and proposes groups big enough as problem candidates.
+#[derive(Deserialize, Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ProblemDetectionConfig {
+    /// Minutes between runs of the detection, e.g. 60. The detection doesn't run if 0
+    pub interval_minutes: u32,
+    /// Hours before each run during which Incidents are grouped, e.g. 72
+    pub window_hours: u32,
+    /// Least amount of Incidents in a group for it to be proposed, e.g. 3
+    pub min_incidents: u32,
+    /// Least share of words, from 0 to 1, two Incidents must have in common to be grouped, e.g. 0.3
+    pub min_similarity: f64,
+}
+
+impl Default for ProblemDetectionConfig {
+    fn default() -> Self {
+        Self {
+            interval_minutes: 60,
+            window_hours: 72,
+            min_incidents: 3,
+            min_similarity: 0.3,
+        }
+    }
+}
+
 /// Loads the application configuration for a particular environment.
 ///
 /// Depending on the environment, this function will behave differently:
@@ -0,0 +1,74 @@
+CREATE TABLE permissions (
+	name TEXT PRIMARY KEY,
+	description TEXT NOT NULL
+);
+
+CREATE TABLE roles (
+	name TEXT PRIMARY KEY,
+	description TEXT NOT NULL
+);
+
+CREATE TABLE role_permissions (
+	role TEXT NOT NULL,
+	permission TEXT NOT NULL,
+	PRIMARY KEY (role, permission),
+	CONSTRAINT fk_role
+		FOREIGN KEY (role)
+		REFERENCES roles(name)
+		ON DELETE CASCADE
+		ON UPDATE CASCADE,
+	CONSTRAINT fk_permission
+		FOREIGN KEY (permission)
+		REFERENCES permissions(name)
+		ON DELETE CASCADE
+);
+
+-- Subjects are the identifiers carried in the `sub` claim of the bearer tokens.
+CREATE TABLE role_assignments (
+	subject TEXT NOT NULL,
+	role TEXT NOT NULL,
+	PRIMARY KEY (subject, role),
+	CONSTRAINT fk_role
+		FOREIGN KEY (role)
+		REFERENCES roles(name)
+		ON DELETE CASCADE
+		ON UPDATE CASCADE
+);
+
+INSERT INTO permissions (name, description) VALUES
+	('incidents.write', 'Create and update incidents and their relations.'),
+	('incidents.delete', 'Delete incidents.'),
+	('problems.write', 'Create and update problems and their relations.'),
+	('problems.known_error', 'Move a problem to the known error status.'),
+	('problems.close', 'Move a problem to the closed status.'),
+	('problems.delete', 'Delete problems.'),
+	('changes.write', 'Create and update RFCs and their relations.'),
+	('changes.approve', 'Approve an RFC for implementation.'),
+	('changes.close', 'Move an RFC to the closed status.'),
+	('changes.delete', 'Delete RFCs.'),
+	('configitems.write', 'Create and update configuration items and their changes.'),
+	('configitems.delete', 'Delete configuration items.'),
+	('roles.manage', 'Manage roles and role assignments.');
+
+INSERT INTO roles (name, description) VALUES
+	('service_desk_agent', 'Registers and handles incidents.'),
+	('problem_manager', 'Owns the problem management process.'),
+	('change_manager', 'Owns the change management process.'),
+	('cmdb_admin', 'Administers the configuration management database.'),
+	('admin', 'Can do everything.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('service_desk_agent', 'incidents.write'),
+	('service_desk_agent', 'changes.write'),
+	('problem_manager', 'problems.write'),
+	('problem_manager', 'problems.known_error'),
+	('problem_manager', 'problems.close'),
+	('change_manager', 'changes.write'),
+	('change_manager', 'changes.approve'),
+	('change_manager', 'changes.close'),
+	('change_manager', 'configitems.write'),
+	('cmdb_admin', 'configitems.write'),
+	('cmdb_admin', 'configitems.delete');
+
+INSERT INTO role_permissions (role, permission)
+SELECT 'admin', name FROM permissions;
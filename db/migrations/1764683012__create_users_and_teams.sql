@@ -0,0 +1,120 @@
+CREATE TABLE teams (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	name TEXT NOT NULL UNIQUE,
+	description TEXT NOT NULL DEFAULT ''
+);
+
+-- The username of a user is the subject carried in the `sub` claim of their bearer tokens.
+CREATE TABLE users (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	username TEXT NOT NULL UNIQUE,
+	full_name TEXT NOT NULL,
+	email TEXT
+);
+
+CREATE TABLE team_members (
+	team_id uuid NOT NULL,
+	user_id uuid NOT NULL,
+	PRIMARY KEY (team_id, user_id),
+	CONSTRAINT fk_team
+		FOREIGN KEY (team_id)
+		REFERENCES teams(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_user
+		FOREIGN KEY (user_id)
+		REFERENCES users(id)
+		ON DELETE CASCADE
+);
+
+INSERT INTO permissions (name, description) VALUES
+	('users.manage', 'Create, update and delete users.'),
+	('teams.manage', 'Create, update and delete teams and their members.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('admin', 'users.manage'),
+	('admin', 'teams.manage');
+
+-- Migrate the free-text owners, asignees and requesters to teams and users.
+-- Incidents with an asignee but no owner are given to a default group, since an
+-- assignee must belong to the assignment group.
+UPDATE incidents
+SET owner = 'Service Desk'
+WHERE owner IS NULL
+AND asignee IS NOT NULL;
+
+INSERT INTO teams (name)
+SELECT owner FROM incidents WHERE owner IS NOT NULL
+UNION
+SELECT owner FROM configitems WHERE owner IS NOT NULL;
+
+INSERT INTO users (username, full_name)
+SELECT asignee, asignee FROM incidents WHERE asignee IS NOT NULL
+UNION
+SELECT requester, requester FROM rfcs;
+
+INSERT INTO team_members (team_id, user_id)
+SELECT DISTINCT t.id, u.id
+FROM incidents AS i
+INNER JOIN teams AS t
+ON t.name = i.owner
+INNER JOIN users AS u
+ON u.username = i.asignee;
+
+ALTER TABLE incidents
+	ADD COLUMN assignment_group_id uuid,
+	ADD COLUMN assignee_id uuid;
+
+UPDATE incidents AS i
+SET assignment_group_id = t.id
+FROM teams AS t
+WHERE t.name = i.owner;
+
+UPDATE incidents AS i
+SET assignee_id = u.id
+FROM users AS u
+WHERE u.username = i.asignee;
+
+-- Teams can't be deleted while they have incidents assigned. Removing a user from the
+-- assignment group unassigns them from its incidents.
+ALTER TABLE incidents
+	DROP COLUMN owner,
+	DROP COLUMN asignee,
+	ADD CONSTRAINT fk_assignment_group
+		FOREIGN KEY (assignment_group_id)
+		REFERENCES teams(id),
+	ADD CONSTRAINT fk_assignee
+		FOREIGN KEY (assignment_group_id, assignee_id)
+		REFERENCES team_members(team_id, user_id)
+		ON DELETE SET NULL (assignee_id),
+	ADD CONSTRAINT assignee_needs_group
+		CHECK (assignee_id IS NULL OR assignment_group_id IS NOT NULL);
+
+ALTER TABLE configitems
+	ADD COLUMN owner_team_id uuid,
+	ADD CONSTRAINT fk_owner_team
+		FOREIGN KEY (owner_team_id)
+		REFERENCES teams(id)
+		ON DELETE SET NULL;
+
+UPDATE configitems AS c
+SET owner_team_id = t.id
+FROM teams AS t
+WHERE t.name = c.owner;
+
+ALTER TABLE configitems DROP COLUMN owner;
+
+-- Users can't be deleted while they have requested RFCs.
+ALTER TABLE rfcs
+	ADD COLUMN requester_id uuid,
+	ADD CONSTRAINT fk_requester
+		FOREIGN KEY (requester_id)
+		REFERENCES users(id);
+
+UPDATE rfcs AS r
+SET requester_id = u.id
+FROM users AS u
+WHERE u.username = r.requester;
+
+ALTER TABLE rfcs
+	DROP COLUMN requester,
+	ALTER COLUMN requester_id SET NOT NULL;
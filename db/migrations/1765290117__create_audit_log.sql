@@ -0,0 +1,110 @@
+CREATE TYPE audit_entity AS ENUM ('incident', 'configitem', 'problem', 'rfc');
+
+CREATE TYPE audit_action AS ENUM ('create', 'update', 'delete');
+
+-- Append-only log of every mutation of the audited tables. Entries outlive the rows they
+-- describe, so there are no foreign keys to the audited entities.
+CREATE TABLE audit_log (
+	id BIGSERIAL PRIMARY KEY,
+	actor TEXT NOT NULL,
+	occurred_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp(),
+	entity_type audit_entity NOT NULL,
+	entity_id uuid NOT NULL,
+	source TEXT NOT NULL,
+	action audit_action NOT NULL,
+	changes JSONB NOT NULL
+);
+
+CREATE INDEX audit_log_entity_idx ON audit_log (entity_type, entity_id);
+
+CREATE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+	RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+	BEFORE UPDATE OR DELETE OR TRUNCATE ON audit_log
+	FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only();
+
+-- Records the changed columns of a row as `{"column": {"before": ..., "after": ...}}`.
+--
+-- The arguments of the trigger are pairs of entity type and the column holding the ID of
+-- that entity, so that changes to relation tables show up in the history of both sides.
+-- The actor is read from the `app.actor` setting of the transaction, falling back to
+-- `system` for changes made outside of the API.
+CREATE FUNCTION audit_row() RETURNS trigger AS $$
+DECLARE
+	old_row jsonb := CASE WHEN TG_OP = 'INSERT' THEN '{}'::jsonb ELSE to_jsonb(OLD) END;
+	new_row jsonb := CASE WHEN TG_OP = 'DELETE' THEN '{}'::jsonb ELSE to_jsonb(NEW) END;
+	diff jsonb;
+	i int := 0;
+BEGIN
+	SELECT jsonb_object_agg(key, jsonb_build_object(
+		'before', coalesce(old_row -> key, 'null'::jsonb),
+		'after', coalesce(new_row -> key, 'null'::jsonb)
+	))
+	INTO diff
+	FROM jsonb_object_keys(old_row || new_row) AS key
+	WHERE coalesce(old_row -> key, 'null'::jsonb) IS DISTINCT FROM coalesce(new_row -> key, 'null'::jsonb);
+
+	IF diff IS NULL THEN
+		RETURN NULL;
+	END IF;
+
+	WHILE i < TG_NARGS LOOP
+		INSERT INTO audit_log (actor, entity_type, entity_id, source, action, changes)
+		VALUES (
+			coalesce(nullif(current_setting('app.actor', true), ''), 'system'),
+			TG_ARGV[i]::audit_entity,
+			((old_row || new_row) ->> TG_ARGV[i + 1])::uuid,
+			TG_TABLE_NAME,
+			CASE TG_OP
+				WHEN 'INSERT' THEN 'create'::audit_action
+				WHEN 'UPDATE' THEN 'update'::audit_action
+				ELSE 'delete'::audit_action
+			END,
+			diff
+		);
+		i := i + 2;
+	END LOOP;
+
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_incidents
+	AFTER INSERT OR UPDATE OR DELETE ON incidents
+	FOR EACH ROW EXECUTE FUNCTION audit_row('incident', 'id');
+
+CREATE TRIGGER audit_configitems
+	AFTER INSERT OR UPDATE OR DELETE ON configitems
+	FOR EACH ROW EXECUTE FUNCTION audit_row('configitem', 'id');
+
+CREATE TRIGGER audit_problems
+	AFTER INSERT OR UPDATE OR DELETE ON problems
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'id');
+
+CREATE TRIGGER audit_rfcs
+	AFTER INSERT OR UPDATE OR DELETE ON rfcs
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'id');
+
+CREATE TRIGGER audit_incidents_ci_relations
+	AFTER INSERT OR UPDATE OR DELETE ON incidents_ci_relations
+	FOR EACH ROW EXECUTE FUNCTION audit_row('incident', 'incident_id', 'configitem', 'ci_id');
+
+CREATE TRIGGER audit_problem_incident_relations
+	AFTER INSERT OR UPDATE OR DELETE ON problem_incident_relations
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id', 'incident', 'incident_id');
+
+CREATE TRIGGER audit_ci_changes
+	AFTER INSERT OR UPDATE OR DELETE ON ci_changes
+	FOR EACH ROW EXECUTE FUNCTION audit_row('configitem', 'ci_id');
+
+CREATE TRIGGER audit_rfc_incident_relations
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_incident_relations
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id', 'incident', 'incident_id');
+
+CREATE TRIGGER audit_rfc_problem_relations
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_problem_relations
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id', 'problem', 'problem_id');
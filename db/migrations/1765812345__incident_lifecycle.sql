@@ -0,0 +1,42 @@
+-- Replace the incident statuses with the ITIL lifecycle. Open incidents become new, or
+-- assigned if someone is working on them already.
+ALTER TYPE incident_status RENAME TO incident_status_old;
+
+CREATE TYPE incident_status AS ENUM ('new', 'assigned', 'inprogress', 'onhold', 'resolved', 'closed');
+
+ALTER TABLE incidents
+	ALTER COLUMN status TYPE incident_status
+	USING (
+		CASE
+			WHEN status = 'open' AND assignee_id IS NOT NULL THEN 'assigned'
+			WHEN status = 'open' THEN 'new'
+			ELSE status::text
+		END
+	)::incident_status;
+
+DROP TYPE incident_status_old;
+
+CREATE TYPE incident_resolution_code AS ENUM ('solved', 'workaround', 'notreproducible', 'duplicate', 'cancelled');
+
+ALTER TABLE incidents
+	ADD COLUMN hold_reason TEXT,
+	ADD COLUMN resolution_code incident_resolution_code,
+	ADD COLUMN resolution_notes TEXT;
+
+-- `resolved_at` is now maintained by the application: it's set while the incident is
+-- resolved or closed and cleared otherwise.
+UPDATE incidents
+SET resolved_at = NULL
+WHERE status <> 'closed';
+
+UPDATE incidents
+SET resolved_at = COALESCE(resolved_at, created_at),
+	resolution_code = 'solved',
+	resolution_notes = 'Closed before resolution codes were recorded.'
+WHERE status = 'closed';
+
+ALTER TABLE incidents
+	ADD CONSTRAINT hold_needs_reason
+		CHECK (status <> 'onhold' OR hold_reason IS NOT NULL),
+	ADD CONSTRAINT closing_needs_resolution
+		CHECK (status <> 'closed' OR (resolution_code IS NOT NULL AND resolution_notes IS NOT NULL));
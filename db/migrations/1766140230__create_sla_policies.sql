@@ -0,0 +1,82 @@
+CREATE TYPE incident_prio AS ENUM ('critical', 'high', 'moderate', 'low');
+
+-- Business hours are local times in `timezone` on the ISO weekdays (1 = Monday) listed in
+-- `business_days`. Policies without business hours run around the clock.
+CREATE TABLE sla_policies (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	name TEXT NOT NULL UNIQUE,
+	description TEXT NOT NULL DEFAULT '',
+	is_default BOOLEAN NOT NULL DEFAULT false,
+	timezone TEXT NOT NULL DEFAULT 'UTC',
+	business_hours_start TIME,
+	business_hours_end TIME,
+	business_days SMALLINT[] NOT NULL DEFAULT '{1,2,3,4,5,6,7}',
+	CONSTRAINT business_hours_complete
+		CHECK ((business_hours_start IS NULL) = (business_hours_end IS NULL)),
+	CONSTRAINT business_hours_ordered
+		CHECK (business_hours_start < business_hours_end)
+);
+
+-- At most one policy applies to Incidents created without one.
+CREATE UNIQUE INDEX sla_policies_default_idx ON sla_policies (is_default) WHERE is_default;
+
+CREATE TABLE sla_targets (
+	policy_id uuid NOT NULL,
+	priority incident_prio NOT NULL,
+	response_minutes INTEGER NOT NULL CHECK (response_minutes > 0),
+	resolution_minutes INTEGER NOT NULL CHECK (resolution_minutes > 0),
+	PRIMARY KEY (policy_id, priority),
+	CONSTRAINT fk_policy
+		FOREIGN KEY (policy_id)
+		REFERENCES sla_policies(id)
+		ON DELETE CASCADE
+);
+
+CREATE TABLE sla_holidays (
+	policy_id uuid NOT NULL,
+	day DATE NOT NULL,
+	name TEXT NOT NULL,
+	PRIMARY KEY (policy_id, day),
+	CONSTRAINT fk_policy
+		FOREIGN KEY (policy_id)
+		REFERENCES sla_policies(id)
+		ON DELETE CASCADE
+);
+
+INSERT INTO permissions (name, description) VALUES
+	('slapolicies.manage', 'Create, update and delete SLA policies.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('admin', 'slapolicies.manage');
+
+ALTER TABLE incidents
+	ADD COLUMN sla_policy_id uuid,
+	ADD COLUMN responded_at TIMESTAMPTZ,
+	ADD COLUMN response_due_at TIMESTAMPTZ,
+	ADD COLUMN resolution_due_at TIMESTAMPTZ,
+	ADD CONSTRAINT fk_sla_policy
+		FOREIGN KEY (sla_policy_id)
+		REFERENCES sla_policies(id)
+		ON DELETE RESTRICT;
+
+-- Incidents that left `new` before responses were tracked count as answered right away.
+UPDATE incidents
+SET responded_at = created_at
+WHERE status <> 'new';
+
+-- Periods an Incident spent on hold, which don't count towards its SLA. The period of an
+-- Incident that's on hold right now has no end yet.
+CREATE TABLE incident_hold_periods (
+	incident_id uuid NOT NULL,
+	started_at TIMESTAMPTZ NOT NULL,
+	ended_at TIMESTAMPTZ,
+	PRIMARY KEY (incident_id, started_at),
+	CONSTRAINT fk_incident
+		FOREIGN KEY (incident_id)
+		REFERENCES incidents(id)
+		ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX incident_hold_periods_open_idx
+	ON incident_hold_periods (incident_id)
+	WHERE ended_at IS NULL;
@@ -0,0 +1,54 @@
+-- Incidents can be rated on up to five levels of impact and urgency. The priority matrix
+-- decides which of them are in use.
+ALTER TYPE incident_impact ADD VALUE 'critical' BEFORE 'high';
+ALTER TYPE incident_impact ADD VALUE 'minimal' AFTER 'low';
+ALTER TYPE incident_urgency ADD VALUE 'critical' BEFORE 'high';
+ALTER TYPE incident_urgency ADD VALUE 'minimal' AFTER 'low';
+
+-- Priority of the Incidents of each combination of impact and urgency. Only the impacts and
+-- urgencies in the matrix can be used, and every combination of them has a row.
+CREATE TABLE priority_matrix (
+	impact incident_impact NOT NULL,
+	urgency incident_urgency NOT NULL,
+	priority incident_prio NOT NULL,
+	PRIMARY KEY (impact, urgency),
+	-- Referenced by the Incidents, so they follow the changes of priority.
+	UNIQUE (impact, urgency, priority)
+);
+
+-- Same mapping as the one the application used to hard-code.
+INSERT INTO priority_matrix (impact, urgency, priority) VALUES
+	('high', 'high', 'critical'),
+	('high', 'medium', 'high'),
+	('high', 'low', 'moderate'),
+	('medium', 'high', 'high'),
+	('medium', 'medium', 'moderate'),
+	('medium', 'low', 'low'),
+	('low', 'high', 'moderate'),
+	('low', 'medium', 'low'),
+	('low', 'low', 'low');
+
+ALTER TABLE incidents
+	ADD COLUMN priority incident_prio;
+
+UPDATE incidents AS i
+SET priority = m.priority
+FROM priority_matrix AS m
+WHERE m.impact = i.impact
+AND m.urgency = i.urgency;
+
+ALTER TABLE incidents
+	ALTER COLUMN priority SET NOT NULL,
+	ADD CONSTRAINT fk_priority
+		FOREIGN KEY (impact, urgency, priority)
+		REFERENCES priority_matrix(impact, urgency, priority)
+		ON UPDATE CASCADE
+		ON DELETE RESTRICT;
+
+CREATE INDEX incidents_priority_idx ON incidents (priority);
+
+INSERT INTO permissions (name, description) VALUES
+	('prioritymatrix.manage', 'Change the impact and urgency to priority matrix of incidents.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('admin', 'prioritymatrix.manage');
@@ -0,0 +1,48 @@
+CREATE TYPE timeline_entity AS ENUM ('incident', 'problem', 'rfc');
+
+CREATE TYPE timeline_visibility AS ENUM ('internal', 'public');
+
+-- Work notes and comments on Incidents, Problems and RFCs. Replies point to the entry they
+-- answer, which must belong to the same entity.
+CREATE TABLE timeline_entries (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	entity_type timeline_entity NOT NULL,
+	entity_id uuid NOT NULL,
+	parent_id uuid,
+	author TEXT NOT NULL,
+	visibility timeline_visibility NOT NULL,
+	body TEXT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	updated_at TIMESTAMPTZ,
+	CONSTRAINT uq_entity UNIQUE (id, entity_type, entity_id),
+	CONSTRAINT fk_parent
+		FOREIGN KEY (parent_id, entity_type, entity_id)
+		REFERENCES timeline_entries (id, entity_type, entity_id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX timeline_entries_entity_idx ON timeline_entries (entity_type, entity_id, created_at);
+
+-- Entries can't reference their entity with a foreign key, so they are removed along with it
+-- by these triggers. The argument of the trigger is the type of the deleted entity.
+CREATE FUNCTION delete_timeline() RETURNS trigger AS $$
+BEGIN
+	DELETE FROM timeline_entries
+	WHERE entity_type = TG_ARGV[0]::timeline_entity
+	AND entity_id = OLD.id;
+
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER delete_incident_timeline
+	AFTER DELETE ON incidents
+	FOR EACH ROW EXECUTE FUNCTION delete_timeline('incident');
+
+CREATE TRIGGER delete_problem_timeline
+	AFTER DELETE ON problems
+	FOR EACH ROW EXECUTE FUNCTION delete_timeline('problem');
+
+CREATE TRIGGER delete_rfc_timeline
+	AFTER DELETE ON rfcs
+	FOR EACH ROW EXECUTE FUNCTION delete_timeline('rfc');
@@ -0,0 +1,27 @@
+CREATE TYPE ci_relation_type AS ENUM ('dependson', 'runson', 'hostedon', 'connectsto');
+
+-- Directed relations between Configuration Items, read as "source <type> target", e.g.
+-- "app server runs on VM".
+CREATE TABLE ci_relations (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	source_id uuid NOT NULL,
+	target_id uuid NOT NULL,
+	type ci_relation_type NOT NULL,
+	description TEXT NOT NULL DEFAULT '',
+	CONSTRAINT fk_source
+		FOREIGN KEY (source_id)
+		REFERENCES configitems(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_target
+		FOREIGN KEY (target_id)
+		REFERENCES configitems(id)
+		ON DELETE CASCADE,
+	CONSTRAINT uq_relation UNIQUE (source_id, target_id, type),
+	CONSTRAINT ck_not_self CHECK (source_id <> target_id)
+);
+
+CREATE INDEX ci_relations_target_idx ON ci_relations (target_id);
+
+CREATE TRIGGER audit_ci_relations
+	AFTER INSERT OR UPDATE OR DELETE ON ci_relations
+	FOR EACH ROW EXECUTE FUNCTION audit_row('configitem', 'source_id', 'configitem', 'target_id');
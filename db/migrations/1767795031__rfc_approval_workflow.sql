@@ -0,0 +1,72 @@
+CREATE TYPE rfc_type AS ENUM ('standard', 'normal', 'emergency');
+
+ALTER TABLE rfcs
+	ADD COLUMN type rfc_type NOT NULL DEFAULT 'normal';
+
+-- Replace the RFC statuses with the ITIL change lifecycle. Open RFCs are waiting to be
+-- assessed, and RFCs in progress were approved already.
+ALTER TYPE rfcstatus RENAME TO rfcstatus_old;
+
+CREATE TYPE rfcstatus AS ENUM (
+	'draft', 'submitted', 'assessed', 'approved', 'rejected', 'scheduled', 'implemented',
+	'reviewed', 'closed'
+);
+
+ALTER TABLE rfcs
+	ALTER COLUMN status TYPE rfcstatus
+	USING (
+		CASE status
+			WHEN 'open' THEN 'submitted'
+			WHEN 'inprogress' THEN 'scheduled'
+			ELSE status::text
+		END
+	)::rfcstatus;
+
+DROP TYPE rfcstatus_old;
+
+-- Change Advisory Board. Its single row names the team whose members decide on RFCs, and how
+-- many approvals normal changes need.
+CREATE TABLE change_advisory_board (
+	id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+	team_id uuid,
+	quorum INTEGER NOT NULL DEFAULT 1 CHECK (quorum > 0),
+	CONSTRAINT fk_team
+		FOREIGN KEY (team_id)
+		REFERENCES teams(id)
+		ON DELETE SET NULL
+);
+
+INSERT INTO change_advisory_board DEFAULT VALUES;
+
+CREATE TYPE rfc_vote AS ENUM ('approve', 'reject');
+
+-- Decisions of the CAB members on the current submission of an RFC. They're discarded when
+-- the RFC goes back to draft.
+CREATE TABLE rfc_approvals (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	rfc_id uuid NOT NULL,
+	approver_id uuid NOT NULL,
+	vote rfc_vote NOT NULL,
+	comment TEXT NOT NULL,
+	decided_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_approver
+		FOREIGN KEY (approver_id)
+		REFERENCES users(id)
+		ON DELETE CASCADE,
+	CONSTRAINT uq_approver UNIQUE (rfc_id, approver_id)
+);
+
+CREATE TRIGGER audit_rfc_approvals
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_approvals
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id');
+
+INSERT INTO permissions (name, description) VALUES
+	('cab.manage', 'Choose the team acting as change advisory board and its quorum.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('change_manager', 'cab.manage'),
+	('admin', 'cab.manage');
@@ -0,0 +1,37 @@
+-- Planned and actual implementation windows of RFCs. Windows are half-open, so one may end
+-- when the next one starts. Actual windows are open-ended while the change is implemented.
+ALTER TABLE rfcs
+	ADD COLUMN planned_start_at TIMESTAMPTZ,
+	ADD COLUMN planned_end_at TIMESTAMPTZ,
+	ADD COLUMN actual_start_at TIMESTAMPTZ,
+	ADD COLUMN actual_end_at TIMESTAMPTZ,
+	ADD CONSTRAINT ck_planned_window
+		CHECK ((planned_start_at IS NULL) = (planned_end_at IS NULL)),
+	ADD CONSTRAINT ck_planned_window_order
+		CHECK (planned_start_at < planned_end_at),
+	ADD CONSTRAINT ck_actual_window
+		CHECK (actual_end_at IS NULL OR actual_start_at IS NOT NULL),
+	ADD CONSTRAINT ck_actual_window_order
+		CHECK (actual_start_at <= actual_end_at),
+	ADD CONSTRAINT ck_scheduled_window
+		CHECK (status <> 'scheduled' OR planned_start_at IS NOT NULL);
+
+CREATE INDEX rfcs_planned_window_idx ON rfcs (planned_start_at, planned_end_at);
+
+-- Blackout periods, during which only emergency changes can be scheduled.
+CREATE TABLE freeze_periods (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	name VARCHAR(255) NOT NULL,
+	description TEXT NOT NULL,
+	starts_at TIMESTAMPTZ NOT NULL,
+	ends_at TIMESTAMPTZ NOT NULL,
+	CONSTRAINT ck_window_order CHECK (starts_at < ends_at)
+);
+
+CREATE INDEX freeze_periods_window_idx ON freeze_periods (starts_at, ends_at);
+
+INSERT INTO permissions (name, description) VALUES
+	('freezeperiods.manage', 'Create, edit and delete the freeze periods of changes.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('admin', 'freezeperiods.manage');
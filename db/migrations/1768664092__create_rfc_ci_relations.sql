@@ -0,0 +1,25 @@
+-- Configuration Items affected by RFCs.
+CREATE TABLE rfc_ci_relations (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	rfc_id uuid NOT NULL,
+	ci_id uuid NOT NULL,
+	CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_ci
+		FOREIGN KEY (ci_id)
+		REFERENCES configitems(id)
+		ON DELETE CASCADE,
+	CONSTRAINT uq_rfc_ci UNIQUE (rfc_id, ci_id)
+);
+
+CREATE INDEX rfc_ci_relations_ci_idx ON rfc_ci_relations (ci_id);
+
+CREATE TRIGGER audit_rfc_ci_relations
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_ci_relations
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id', 'configitem', 'ci_id');
+
+-- Whether RFCs that conflict with others can be approved.
+ALTER TABLE change_advisory_board
+	ADD COLUMN block_conflicts BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,60 @@
+-- RFC that authorized a change to a Configuration Item. Changes without one are unauthorized.
+-- RFCs can't be deleted while changes trace back to them.
+ALTER TABLE ci_changes
+	ADD COLUMN rfc_id uuid,
+	ADD CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE RESTRICT;
+
+CREATE INDEX ci_changes_rfc_idx ON ci_changes (rfc_id);
+
+-- Same as before, but skipping entities whose ID column is null, so that optional relations
+-- can be audited on both sides.
+CREATE OR REPLACE FUNCTION audit_row() RETURNS trigger AS $$
+DECLARE
+	old_row jsonb := CASE WHEN TG_OP = 'INSERT' THEN '{}'::jsonb ELSE to_jsonb(OLD) END;
+	new_row jsonb := CASE WHEN TG_OP = 'DELETE' THEN '{}'::jsonb ELSE to_jsonb(NEW) END;
+	diff jsonb;
+	i int := 0;
+BEGIN
+	SELECT jsonb_object_agg(key, jsonb_build_object(
+		'before', coalesce(old_row -> key, 'null'::jsonb),
+		'after', coalesce(new_row -> key, 'null'::jsonb)
+	))
+	INTO diff
+	FROM jsonb_object_keys(old_row || new_row) AS key
+	WHERE coalesce(old_row -> key, 'null'::jsonb) IS DISTINCT FROM coalesce(new_row -> key, 'null'::jsonb);
+
+	IF diff IS NULL THEN
+		RETURN NULL;
+	END IF;
+
+	WHILE i < TG_NARGS LOOP
+		IF (old_row || new_row) ->> TG_ARGV[i + 1] IS NOT NULL THEN
+			INSERT INTO audit_log (actor, entity_type, entity_id, source, action, changes)
+			VALUES (
+				coalesce(nullif(current_setting('app.actor', true), ''), 'system'),
+				TG_ARGV[i]::audit_entity,
+				((old_row || new_row) ->> TG_ARGV[i + 1])::uuid,
+				TG_TABLE_NAME,
+				CASE TG_OP
+					WHEN 'INSERT' THEN 'create'::audit_action
+					WHEN 'UPDATE' THEN 'update'::audit_action
+					ELSE 'delete'::audit_action
+				END,
+				diff
+			);
+		END IF;
+		i := i + 2;
+	END LOOP;
+
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER audit_ci_changes ON ci_changes;
+
+CREATE TRIGGER audit_ci_changes
+	AFTER INSERT OR UPDATE OR DELETE ON ci_changes
+	FOR EACH ROW EXECUTE FUNCTION audit_row('configitem', 'ci_id', 'rfc', 'rfc_id');
@@ -0,0 +1,82 @@
+CREATE TYPE risk_category AS ENUM ('low', 'medium', 'high');
+
+-- Plans RFCs need before they're assessed or approved, and the outcome of their risk
+-- assessment.
+ALTER TABLE rfcs
+	ADD COLUMN implementation_plan TEXT,
+	ADD COLUMN backout_plan TEXT,
+	ADD COLUMN test_plan TEXT,
+	ADD COLUMN risk_score INTEGER CHECK (risk_score >= 0),
+	ADD COLUMN risk_category risk_category,
+	ADD CONSTRAINT ck_risk_assessed CHECK ((risk_score IS NULL) = (risk_category IS NULL));
+
+-- Questionnaire assessing the risk of RFCs. Every answer scores its question, multiplied by
+-- the weight of the question.
+CREATE TABLE risk_questions (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	position INTEGER NOT NULL,
+	question TEXT NOT NULL,
+	weight INTEGER NOT NULL CHECK (weight > 0)
+);
+
+CREATE TABLE risk_answers (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	question_id uuid NOT NULL,
+	position INTEGER NOT NULL,
+	answer TEXT NOT NULL,
+	score INTEGER NOT NULL CHECK (score >= 0),
+	CONSTRAINT fk_question
+		FOREIGN KEY (question_id)
+		REFERENCES risk_questions(id)
+		ON DELETE CASCADE
+);
+
+-- Answers given in the risk assessment of RFCs. They're copied from the questionnaire, so
+-- that assessments outlive changes to it.
+CREATE TABLE rfc_risk_responses (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	rfc_id uuid NOT NULL,
+	position INTEGER NOT NULL,
+	question TEXT NOT NULL,
+	weight INTEGER NOT NULL,
+	answer TEXT NOT NULL,
+	score INTEGER NOT NULL,
+	CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX rfc_risk_responses_rfc_idx ON rfc_risk_responses (rfc_id);
+
+CREATE TRIGGER audit_rfc_risk_responses
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_risk_responses
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id');
+
+WITH questions (position, question, weight, answers) AS (
+	VALUES
+		(1, 'How many users does the change affect?', 3,
+			ARRAY['A single team', 'A department', 'The whole organisation']),
+		(2, 'Was the change tested in a non-production environment?', 2,
+			ARRAY['Yes, fully', 'Partially', 'No']),
+		(3, 'Can the change be backed out?', 3,
+			ARRAY['Yes, automatically', 'Yes, manually', 'No']),
+		(4, 'Does the change need a service outage?', 2,
+			ARRAY['No', 'Within the planned window', 'Beyond the planned window'])
+), inserted AS (
+	INSERT INTO risk_questions (position, question, weight)
+	SELECT position, question, weight FROM questions
+	RETURNING id, position
+)
+INSERT INTO risk_answers (question_id, position, answer, score)
+SELECT i.id, a.position, a.answer, (a.position - 1) * 2
+FROM inserted AS i
+JOIN questions AS q ON q.position = i.position
+CROSS JOIN LATERAL unnest(q.answers) WITH ORDINALITY AS a(answer, position);
+
+INSERT INTO permissions (name, description) VALUES
+	('riskquestionnaire.manage', 'Edit the questionnaire assessing the risk of RFCs.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('change_manager', 'riskquestionnaire.manage'),
+	('admin', 'riskquestionnaire.manage');
@@ -0,0 +1,40 @@
+-- How an RFC relates to an Incident. Incidents opened on affected Configuration Items right
+-- after the implementation are suggested as caused by the change until someone confirms it,
+-- or dismisses it by making them merely related.
+CREATE TYPE rfc_incident_relation_type AS ENUM ('related', 'suspected_cause', 'caused');
+
+ALTER TABLE rfc_incident_relations
+	ADD COLUMN relation_type rfc_incident_relation_type NOT NULL DEFAULT 'related';
+
+CREATE INDEX rfc_incident_relations_rfc_idx ON rfc_incident_relations (rfc_id, incident_id);
+
+-- Hours after the implementation of an RFC during which Incidents on the Configuration Items
+-- it affects are suggested as caused by it.
+ALTER TABLE change_advisory_board
+	ADD COLUMN review_window_hours INTEGER NOT NULL DEFAULT 24 CHECK (review_window_hours > 0);
+
+CREATE TYPE rfc_outcome AS ENUM ('successful', 'rolled_back', 'failed');
+
+-- Post-implementation reviews. An RFC has at most one, and can't be reviewed without it.
+CREATE TABLE rfc_reviews (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	rfc_id uuid NOT NULL,
+	reviewer_id uuid NOT NULL,
+	outcome rfc_outcome NOT NULL,
+	caused_incidents BOOLEAN NOT NULL,
+	lessons_learned TEXT NOT NULL,
+	reviewed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_reviewer
+		FOREIGN KEY (reviewer_id)
+		REFERENCES users(id)
+		ON DELETE RESTRICT,
+	CONSTRAINT uq_rfc_review UNIQUE (rfc_id)
+);
+
+CREATE TRIGGER audit_rfc_reviews
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_reviews
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id');
@@ -0,0 +1,49 @@
+-- Known Error Database. A Problem gets a known error record when it enters the known error
+-- status, which service desk agents search once it's published.
+CREATE TABLE known_errors (
+	problem_id uuid PRIMARY KEY,
+	symptoms TEXT NOT NULL,
+	root_cause TEXT NOT NULL,
+	workaround_steps TEXT[] NOT NULL DEFAULT '{}',
+	published BOOLEAN NOT NULL DEFAULT FALSE,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE CASCADE
+);
+
+-- Text of a known error matched by searches, symptoms first.
+CREATE FUNCTION known_error_document(symptoms TEXT, root_cause TEXT) RETURNS tsvector AS $$
+	SELECT setweight(to_tsvector('english', symptoms), 'A')
+		|| setweight(to_tsvector('english', root_cause), 'B');
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE INDEX known_errors_search_idx ON known_errors
+	USING GIN (known_error_document(symptoms, root_cause));
+
+CREATE TRIGGER audit_known_errors
+	AFTER INSERT OR UPDATE OR DELETE ON known_errors
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id');
+
+-- Configuration Items affected by known errors.
+CREATE TABLE known_error_cis (
+	problem_id uuid NOT NULL,
+	ci_id uuid NOT NULL,
+	PRIMARY KEY (problem_id, ci_id),
+	CONSTRAINT fk_known_error
+		FOREIGN KEY (problem_id)
+		REFERENCES known_errors(problem_id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_ci
+		FOREIGN KEY (ci_id)
+		REFERENCES configitems(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX known_error_cis_ci_idx ON known_error_cis (ci_id);
+
+CREATE TRIGGER audit_known_error_cis
+	AFTER INSERT OR UPDATE OR DELETE ON known_error_cis
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id', 'configitem', 'ci_id');
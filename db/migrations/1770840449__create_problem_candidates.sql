@@ -0,0 +1,41 @@
+CREATE TYPE problem_candidate_status AS ENUM ('proposed', 'accepted', 'rejected');
+
+-- Groups of recurring Incidents found by the problem detection, proposed to problem managers
+-- as the symptoms of a yet unknown Problem.
+CREATE TABLE problem_candidates (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	-- Configuration Item the Incidents share.
+	ci_id uuid,
+	title TEXT NOT NULL,
+	status problem_candidate_status NOT NULL DEFAULT 'proposed',
+	-- Problem created when the candidate was accepted.
+	problem_id uuid,
+	detected_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	decided_at TIMESTAMPTZ,
+	CONSTRAINT fk_ci
+		FOREIGN KEY (ci_id)
+		REFERENCES configitems(id)
+		ON DELETE SET NULL,
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE SET NULL
+);
+
+CREATE INDEX problem_candidates_status_idx ON problem_candidates (status, detected_at);
+
+-- An Incident is proposed in one candidate at most, so rejected groups aren't proposed again.
+CREATE TABLE problem_candidate_incidents (
+	candidate_id uuid NOT NULL,
+	incident_id uuid PRIMARY KEY,
+	CONSTRAINT fk_candidate
+		FOREIGN KEY (candidate_id)
+		REFERENCES problem_candidates(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_incident
+		FOREIGN KEY (incident_id)
+		REFERENCES incidents(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX problem_candidate_incidents_candidate_idx ON problem_candidate_incidents (candidate_id);
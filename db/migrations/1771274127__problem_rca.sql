@@ -0,0 +1,117 @@
+-- Managed taxonomy classifying the root causes of Problems.
+CREATE TABLE root_cause_categories (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	name TEXT NOT NULL,
+	description TEXT NOT NULL DEFAULT '',
+	CONSTRAINT uq_root_cause_category_name UNIQUE (name)
+);
+
+INSERT INTO root_cause_categories (name, description) VALUES
+	('Hardware failure', 'A physical component broke or wore out.'),
+	('Software defect', 'A bug in software built or bought by the organization.'),
+	('Configuration error', 'A Configuration Item was set up wrong.'),
+	('Capacity', 'A Configuration Item ran out of resources.'),
+	('Human error', 'Someone made a mistake while operating a service.'),
+	('Process gap', 'A process was missing, unclear or not followed.'),
+	('Third party', 'A supplier or external service failed.');
+
+-- Classification of the root cause of a Problem, the conclusion of its analysis.
+CREATE TABLE problem_root_causes (
+	problem_id uuid PRIMARY KEY,
+	category_id uuid NOT NULL,
+	notes TEXT NOT NULL DEFAULT '',
+	classified_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_category
+		FOREIGN KEY (category_id)
+		REFERENCES root_cause_categories(id)
+		ON DELETE RESTRICT
+);
+
+CREATE TRIGGER audit_problem_root_causes
+	AFTER INSERT OR UPDATE OR DELETE ON problem_root_causes
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id');
+
+-- 5-Whys chains. Each answer is the reason of the previous one, the first answers why the
+-- Problem happened.
+CREATE TABLE problem_whys (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	problem_id uuid NOT NULL,
+	position INTEGER NOT NULL,
+	question TEXT NOT NULL,
+	answer TEXT NOT NULL,
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE CASCADE,
+	CONSTRAINT uq_problem_why_position UNIQUE (problem_id, position)
+);
+
+CREATE TRIGGER audit_problem_whys
+	AFTER INSERT OR UPDATE OR DELETE ON problem_whys
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id');
+
+CREATE TYPE ishikawa_category AS ENUM (
+	'people', 'process', 'technology', 'materials', 'measurement', 'environment'
+);
+
+-- Contributing causes of a Problem, by category of its Ishikawa (fishbone) diagram.
+CREATE TABLE problem_ishikawa_causes (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	problem_id uuid NOT NULL,
+	category ishikawa_category NOT NULL,
+	description TEXT NOT NULL,
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX problem_ishikawa_causes_problem_idx ON problem_ishikawa_causes (problem_id);
+
+CREATE TRIGGER audit_problem_ishikawa_causes
+	AFTER INSERT OR UPDATE OR DELETE ON problem_ishikawa_causes
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id');
+
+-- Events leading to a Problem, each optionally about a Configuration Item, Incident or RFC.
+CREATE TABLE problem_rca_events (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	problem_id uuid NOT NULL,
+	occurred_at TIMESTAMPTZ NOT NULL,
+	description TEXT NOT NULL,
+	ci_id uuid,
+	incident_id uuid,
+	rfc_id uuid,
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_ci
+		FOREIGN KEY (ci_id)
+		REFERENCES configitems(id)
+		ON DELETE SET NULL,
+	CONSTRAINT fk_incident
+		FOREIGN KEY (incident_id)
+		REFERENCES incidents(id)
+		ON DELETE SET NULL,
+	CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE SET NULL
+);
+
+CREATE INDEX problem_rca_events_problem_idx ON problem_rca_events (problem_id, occurred_at);
+
+CREATE TRIGGER audit_problem_rca_events
+	AFTER INSERT OR UPDATE OR DELETE ON problem_rca_events
+	FOR EACH ROW EXECUTE FUNCTION audit_row('problem', 'problem_id');
+
+INSERT INTO permissions (name, description) VALUES
+	('rootcauses.manage', 'Edit the taxonomy classifying the root causes of Problems.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('problem_manager', 'rootcauses.manage'),
+	('admin', 'rootcauses.manage');
@@ -0,0 +1,66 @@
+-- Major incidents follow their own process: an incident commander leads the response and
+-- keeps stakeholders informed at a set cadence.
+ALTER TABLE incidents
+	ADD COLUMN major BOOLEAN NOT NULL DEFAULT FALSE,
+	ADD COLUMN parent_id uuid,
+	ADD CONSTRAINT fk_parent
+		FOREIGN KEY (parent_id)
+		REFERENCES incidents(id)
+		ON DELETE SET NULL,
+	ADD CONSTRAINT parent_not_self
+		CHECK (parent_id <> id);
+
+CREATE INDEX incidents_parent_idx ON incidents (parent_id);
+
+CREATE TABLE major_incidents (
+	incident_id uuid PRIMARY KEY,
+	declared_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	commander_id uuid,
+	update_cadence_minutes INT NOT NULL,
+	CONSTRAINT fk_incident
+		FOREIGN KEY (incident_id)
+		REFERENCES incidents(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_commander
+		FOREIGN KEY (commander_id)
+		REFERENCES users(id)
+		ON DELETE SET NULL,
+	CONSTRAINT positive_cadence
+		CHECK (update_cadence_minutes > 0)
+);
+
+CREATE TRIGGER audit_major_incidents
+	AFTER INSERT OR UPDATE OR DELETE ON major_incidents
+	FOR EACH ROW EXECUTE FUNCTION audit_row('incident', 'incident_id');
+
+-- Updates sent to stakeholders about a major incident.
+CREATE TABLE major_incident_communications (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	incident_id uuid NOT NULL,
+	author TEXT NOT NULL,
+	audience TEXT NOT NULL,
+	message TEXT NOT NULL,
+	sent_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_major_incident
+		FOREIGN KEY (incident_id)
+		REFERENCES major_incidents(incident_id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX major_incident_communications_incident_idx
+	ON major_incident_communications (incident_id, sent_at);
+
+CREATE TRIGGER audit_major_incident_communications
+	AFTER INSERT OR UPDATE OR DELETE ON major_incident_communications
+	FOR EACH ROW EXECUTE FUNCTION audit_row('incident', 'incident_id');
+
+INSERT INTO permissions (name, description) VALUES
+	('incidents.major', 'Declare major incidents and log their communications.');
+
+INSERT INTO roles (name, description) VALUES
+	('incident_commander', 'Leads the response to major incidents.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('incident_commander', 'incidents.write'),
+	('incident_commander', 'incidents.major'),
+	('admin', 'incidents.major');
@@ -0,0 +1,9 @@
+-- Incidents merged into another one are closed as its duplicates.
+ALTER TABLE incidents
+	ADD COLUMN duplicate_of_id uuid,
+	ADD CONSTRAINT fk_duplicate_of
+		FOREIGN KEY (duplicate_of_id)
+		REFERENCES incidents(id)
+		ON DELETE SET NULL,
+	ADD CONSTRAINT duplicate_not_self
+		CHECK (duplicate_of_id <> id);
@@ -0,0 +1,121 @@
+-- Service catalog. Each item is something users can request, with the form fields they fill
+-- in, the team approving the requests, if any, and the tasks fulfilling them.
+CREATE TABLE catalog_items (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	name TEXT NOT NULL,
+	description TEXT NOT NULL DEFAULT '',
+	form_fields JSONB NOT NULL DEFAULT '[]',
+	approval_group_id uuid,
+	fulfilment_group_id uuid,
+	fulfilment_tasks TEXT[] NOT NULL DEFAULT '{}',
+	active BOOLEAN NOT NULL DEFAULT TRUE,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT uq_catalog_item_name UNIQUE (name),
+	CONSTRAINT fk_approval_group
+		FOREIGN KEY (approval_group_id)
+		REFERENCES teams(id)
+		ON DELETE RESTRICT,
+	CONSTRAINT fk_fulfilment_group
+		FOREIGN KEY (fulfilment_group_id)
+		REFERENCES teams(id)
+		ON DELETE RESTRICT
+);
+
+CREATE TYPE service_request_status AS ENUM (
+	'pendingapproval', 'rejected', 'infulfilment', 'fulfilled', 'closed', 'cancelled'
+);
+
+-- Requests for catalog items, kept apart from incidents. `answers` holds the values of the
+-- form fields of the item.
+CREATE TABLE service_requests (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	catalog_item_id uuid NOT NULL,
+	requester_id uuid NOT NULL,
+	status service_request_status NOT NULL,
+	answers JSONB NOT NULL DEFAULT '{}',
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	fulfilled_at TIMESTAMPTZ,
+	CONSTRAINT fk_catalog_item
+		FOREIGN KEY (catalog_item_id)
+		REFERENCES catalog_items(id)
+		ON DELETE RESTRICT,
+	CONSTRAINT fk_requester
+		FOREIGN KEY (requester_id)
+		REFERENCES users(id)
+		ON DELETE RESTRICT
+);
+
+CREATE INDEX service_requests_catalog_item_idx ON service_requests (catalog_item_id);
+CREATE INDEX service_requests_requester_idx ON service_requests (requester_id);
+
+CREATE TYPE service_request_vote AS ENUM ('approve', 'reject');
+
+-- Decisions of the members of the approval group of the item on requests.
+CREATE TABLE service_request_approvals (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	request_id uuid NOT NULL,
+	approver_id uuid NOT NULL,
+	vote service_request_vote NOT NULL,
+	comment TEXT NOT NULL,
+	decided_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_request
+		FOREIGN KEY (request_id)
+		REFERENCES service_requests(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_approver
+		FOREIGN KEY (approver_id)
+		REFERENCES users(id)
+		ON DELETE CASCADE,
+	CONSTRAINT uq_request_approver UNIQUE (request_id, approver_id)
+);
+
+CREATE TYPE service_request_task_status AS ENUM ('open', 'inprogress', 'done', 'skipped');
+
+-- Tasks fulfilling approved requests, copied from the catalog item when fulfilment starts.
+CREATE TABLE service_request_tasks (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	request_id uuid NOT NULL,
+	position INTEGER NOT NULL,
+	title TEXT NOT NULL,
+	status service_request_task_status NOT NULL DEFAULT 'open',
+	assignee_id uuid,
+	completed_at TIMESTAMPTZ,
+	CONSTRAINT fk_request
+		FOREIGN KEY (request_id)
+		REFERENCES service_requests(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_assignee
+		FOREIGN KEY (assignee_id)
+		REFERENCES users(id)
+		ON DELETE SET NULL
+);
+
+CREATE INDEX service_request_tasks_request_idx ON service_request_tasks (request_id, position);
+
+ALTER TYPE audit_entity ADD VALUE 'request';
+
+CREATE TRIGGER audit_service_requests
+	AFTER INSERT OR UPDATE OR DELETE ON service_requests
+	FOR EACH ROW EXECUTE FUNCTION audit_row('request', 'id');
+
+CREATE TRIGGER audit_service_request_approvals
+	AFTER INSERT OR UPDATE OR DELETE ON service_request_approvals
+	FOR EACH ROW EXECUTE FUNCTION audit_row('request', 'request_id');
+
+CREATE TRIGGER audit_service_request_tasks
+	AFTER INSERT OR UPDATE OR DELETE ON service_request_tasks
+	FOR EACH ROW EXECUTE FUNCTION audit_row('request', 'request_id');
+
+INSERT INTO permissions (name, description) VALUES
+	('catalog.manage', 'Manage the items of the service catalog.'),
+	('requests.write', 'Create and update service requests, decide on them and work their tasks.');
+
+INSERT INTO roles (name, description) VALUES
+	('service_catalog_manager', 'Owns the service catalog.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('service_catalog_manager', 'catalog.manage'),
+	('service_catalog_manager', 'requests.write'),
+	('service_desk_agent', 'requests.write'),
+	('admin', 'catalog.manage'),
+	('admin', 'requests.write');
@@ -0,0 +1,144 @@
+-- Managed categories of knowledge articles.
+CREATE TABLE knowledge_categories (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	name TEXT NOT NULL,
+	description TEXT NOT NULL DEFAULT '',
+	CONSTRAINT uq_knowledge_category_name UNIQUE (name)
+);
+
+INSERT INTO knowledge_categories (name, description) VALUES
+	('How-to', 'Steps to get something done.'),
+	('Troubleshooting', 'Diagnosing and fixing a recurring issue.'),
+	('Known error', 'Workaround for a known error, until its Problem is resolved.'),
+	('Reference', 'Facts about a service or Configuration Item.');
+
+CREATE TYPE knowledge_article_status AS ENUM ('draft', 'review', 'published');
+
+-- Knowledge base. `title` and `body` hold the latest version of the article, whose earlier
+-- versions are kept in `knowledge_article_versions`.
+CREATE TABLE knowledge_articles (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	title TEXT NOT NULL,
+	body TEXT NOT NULL DEFAULT '',
+	category_id uuid,
+	status knowledge_article_status NOT NULL DEFAULT 'draft',
+	version INTEGER NOT NULL DEFAULT 1,
+	known_error_id uuid,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	published_at TIMESTAMPTZ,
+	CONSTRAINT fk_category
+		FOREIGN KEY (category_id)
+		REFERENCES knowledge_categories(id)
+		ON DELETE RESTRICT,
+	-- Known error the article was drafted from.
+	CONSTRAINT uq_knowledge_article_known_error UNIQUE (known_error_id),
+	CONSTRAINT fk_known_error
+		FOREIGN KEY (known_error_id)
+		REFERENCES known_errors(problem_id)
+		ON DELETE SET NULL
+);
+
+-- Text of an article matched by searches, title first.
+CREATE FUNCTION knowledge_article_document(title TEXT, body TEXT) RETURNS tsvector AS $$
+	SELECT setweight(to_tsvector('english', title), 'A')
+		|| setweight(to_tsvector('english', body), 'B');
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE INDEX knowledge_articles_search_idx ON knowledge_articles
+	USING GIN (knowledge_article_document(title, body));
+
+CREATE TABLE knowledge_article_versions (
+	article_id uuid NOT NULL,
+	version INTEGER NOT NULL,
+	title TEXT NOT NULL,
+	body TEXT NOT NULL,
+	created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY (article_id, version),
+	CONSTRAINT fk_article
+		FOREIGN KEY (article_id)
+		REFERENCES knowledge_articles(id)
+		ON DELETE CASCADE
+);
+
+-- Configuration Items, Problems and Incidents articles are about.
+CREATE TABLE knowledge_article_cis (
+	article_id uuid NOT NULL,
+	ci_id uuid NOT NULL,
+	PRIMARY KEY (article_id, ci_id),
+	CONSTRAINT fk_article
+		FOREIGN KEY (article_id)
+		REFERENCES knowledge_articles(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_ci
+		FOREIGN KEY (ci_id)
+		REFERENCES configitems(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX knowledge_article_cis_ci_idx ON knowledge_article_cis (ci_id);
+
+CREATE TABLE knowledge_article_problems (
+	article_id uuid NOT NULL,
+	problem_id uuid NOT NULL,
+	PRIMARY KEY (article_id, problem_id),
+	CONSTRAINT fk_article
+		FOREIGN KEY (article_id)
+		REFERENCES knowledge_articles(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_problem
+		FOREIGN KEY (problem_id)
+		REFERENCES problems(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX knowledge_article_problems_problem_idx ON knowledge_article_problems (problem_id);
+
+CREATE TABLE knowledge_article_incidents (
+	article_id uuid NOT NULL,
+	incident_id uuid NOT NULL,
+	PRIMARY KEY (article_id, incident_id),
+	CONSTRAINT fk_article
+		FOREIGN KEY (article_id)
+		REFERENCES knowledge_articles(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_incident
+		FOREIGN KEY (incident_id)
+		REFERENCES incidents(id)
+		ON DELETE CASCADE
+);
+
+CREATE INDEX knowledge_article_incidents_incident_idx ON knowledge_article_incidents (incident_id);
+
+ALTER TYPE audit_entity ADD VALUE 'article';
+
+CREATE TRIGGER audit_knowledge_articles
+	AFTER INSERT OR UPDATE OR DELETE ON knowledge_articles
+	FOR EACH ROW EXECUTE FUNCTION audit_row('article', 'id');
+
+CREATE TRIGGER audit_knowledge_article_cis
+	AFTER INSERT OR UPDATE OR DELETE ON knowledge_article_cis
+	FOR EACH ROW EXECUTE FUNCTION audit_row('article', 'article_id', 'configitem', 'ci_id');
+
+CREATE TRIGGER audit_knowledge_article_problems
+	AFTER INSERT OR UPDATE OR DELETE ON knowledge_article_problems
+	FOR EACH ROW EXECUTE FUNCTION audit_row('article', 'article_id', 'problem', 'problem_id');
+
+CREATE TRIGGER audit_knowledge_article_incidents
+	AFTER INSERT OR UPDATE OR DELETE ON knowledge_article_incidents
+	FOR EACH ROW EXECUTE FUNCTION audit_row('article', 'article_id', 'incident', 'incident_id');
+
+INSERT INTO permissions (name, description) VALUES
+	('knowledge.write', 'Write knowledge articles and submit them for review.'),
+	('knowledge.manage', 'Publish and delete knowledge articles and manage their categories.');
+
+INSERT INTO roles (name, description) VALUES
+	('knowledge_manager', 'Owns the knowledge base.');
+
+INSERT INTO role_permissions (role, permission) VALUES
+	('knowledge_manager', 'knowledge.write'),
+	('knowledge_manager', 'knowledge.manage'),
+	('service_desk_agent', 'knowledge.write'),
+	('problem_manager', 'knowledge.write'),
+	('admin', 'knowledge.write'),
+	('admin', 'knowledge.manage');
@@ -0,0 +1,87 @@
+-- Text of the searchable entities, titles first, then descriptions and causes, then plans,
+-- workarounds and other documentation.
+ALTER TABLE incidents ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+	setweight(to_tsvector('english', title), 'A')
+		|| setweight(to_tsvector('english', description), 'B')
+		|| setweight(to_tsvector('english', coalesce(resolution_notes, '')), 'C')
+) STORED;
+
+CREATE INDEX incidents_search_idx ON incidents USING GIN (search_vector);
+
+ALTER TABLE configitems ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+	setweight(to_tsvector('english', name), 'A')
+		|| setweight(to_tsvector('english', description), 'B')
+		|| setweight(to_tsvector('english', coalesce(type, '')), 'C')
+) STORED;
+
+CREATE INDEX configitems_search_idx ON configitems USING GIN (search_vector);
+
+ALTER TABLE problems ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+	setweight(to_tsvector('english', title), 'A')
+		|| setweight(to_tsvector('english', description), 'B')
+		|| setweight(to_tsvector('english', causes), 'B')
+		|| setweight(to_tsvector('english', coalesce(workarounds, '')), 'C')
+		|| setweight(to_tsvector('english', coalesce(resolutions, '')), 'C')
+) STORED;
+
+CREATE INDEX problems_search_idx ON problems USING GIN (search_vector);
+
+ALTER TABLE rfcs ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+	setweight(to_tsvector('english', title), 'A')
+		|| setweight(to_tsvector('english', description), 'B')
+		|| setweight(to_tsvector('english', coalesce(implementation_plan, '')), 'C')
+		|| setweight(to_tsvector('english', coalesce(backout_plan, '')), 'C')
+		|| setweight(to_tsvector('english', coalesce(test_plan, '')), 'C')
+) STORED;
+
+CREATE INDEX rfcs_search_idx ON rfcs USING GIN (search_vector);
+
+ALTER TABLE ci_changes ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+	setweight(to_tsvector('english', documentation), 'B')
+) STORED;
+
+CREATE INDEX ci_changes_search_idx ON ci_changes USING GIN (search_vector);
+
+-- Same as before, but leaving the search vectors out of the recorded changes, since they
+-- only mirror other columns.
+CREATE OR REPLACE FUNCTION audit_row() RETURNS trigger AS $$
+DECLARE
+	old_row jsonb := CASE WHEN TG_OP = 'INSERT' THEN '{}'::jsonb ELSE to_jsonb(OLD) - 'search_vector' END;
+	new_row jsonb := CASE WHEN TG_OP = 'DELETE' THEN '{}'::jsonb ELSE to_jsonb(NEW) - 'search_vector' END;
+	diff jsonb;
+	i int := 0;
+BEGIN
+	SELECT jsonb_object_agg(key, jsonb_build_object(
+		'before', coalesce(old_row -> key, 'null'::jsonb),
+		'after', coalesce(new_row -> key, 'null'::jsonb)
+	))
+	INTO diff
+	FROM jsonb_object_keys(old_row || new_row) AS key
+	WHERE coalesce(old_row -> key, 'null'::jsonb) IS DISTINCT FROM coalesce(new_row -> key, 'null'::jsonb);
+
+	IF diff IS NULL THEN
+		RETURN NULL;
+	END IF;
+
+	WHILE i < TG_NARGS LOOP
+		IF (old_row || new_row) ->> TG_ARGV[i + 1] IS NOT NULL THEN
+			INSERT INTO audit_log (actor, entity_type, entity_id, source, action, changes)
+			VALUES (
+				coalesce(nullif(current_setting('app.actor', true), ''), 'system'),
+				TG_ARGV[i]::audit_entity,
+				((old_row || new_row) ->> TG_ARGV[i + 1])::uuid,
+				TG_TABLE_NAME,
+				CASE TG_OP
+					WHEN 'INSERT' THEN 'create'::audit_action
+					WHEN 'UPDATE' THEN 'update'::audit_action
+					ELSE 'delete'::audit_action
+				END,
+				diff
+			);
+		END IF;
+		i := i + 2;
+	END LOOP;
+
+	RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,23 @@
+-- Overrides of the freeze periods for normal changes, which can only be scheduled during a
+-- freeze with one. An RFC has at most one, and it's discarded when the RFC goes back to
+-- draft.
+CREATE TABLE rfc_freeze_overrides (
+	id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+	rfc_id uuid NOT NULL,
+	approver_id uuid NOT NULL,
+	justification TEXT NOT NULL,
+	granted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	CONSTRAINT fk_rfc
+		FOREIGN KEY (rfc_id)
+		REFERENCES rfcs(id)
+		ON DELETE CASCADE,
+	CONSTRAINT fk_approver
+		FOREIGN KEY (approver_id)
+		REFERENCES users(id)
+		ON DELETE RESTRICT,
+	CONSTRAINT uq_rfc_freeze_override UNIQUE (rfc_id)
+);
+
+CREATE TRIGGER audit_rfc_freeze_overrides
+	AFTER INSERT OR UPDATE OR DELETE ON rfc_freeze_overrides
+	FOR EACH ROW EXECUTE FUNCTION audit_row('rfc', 'rfc_id');
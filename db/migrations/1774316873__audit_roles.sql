@@ -0,0 +1,25 @@
+-- Roles are identified by their name, but entries of the audit log by a UUID, so the role
+-- tables get one derived from the name. Names can't be changed, so it stays the same for the
+-- life of the role, and a role recreated with the same name continues the same history.
+ALTER TABLE roles
+	ADD COLUMN id uuid NOT NULL GENERATED ALWAYS AS (md5(name)::uuid) STORED;
+
+ALTER TABLE role_permissions
+	ADD COLUMN role_id uuid NOT NULL GENERATED ALWAYS AS (md5(role)::uuid) STORED;
+
+ALTER TABLE role_assignments
+	ADD COLUMN role_id uuid NOT NULL GENERATED ALWAYS AS (md5(role)::uuid) STORED;
+
+ALTER TYPE audit_entity ADD VALUE 'role';
+
+CREATE TRIGGER audit_roles
+	AFTER INSERT OR UPDATE OR DELETE ON roles
+	FOR EACH ROW EXECUTE FUNCTION audit_row('role', 'id');
+
+CREATE TRIGGER audit_role_permissions
+	AFTER INSERT OR UPDATE OR DELETE ON role_permissions
+	FOR EACH ROW EXECUTE FUNCTION audit_row('role', 'role_id');
+
+CREATE TRIGGER audit_role_assignments
+	AFTER INSERT OR UPDATE OR DELETE ON role_assignments
+	FOR EACH ROW EXECUTE FUNCTION audit_row('role', 'role_id');
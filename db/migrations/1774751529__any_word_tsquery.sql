@@ -0,0 +1,6 @@
+-- Query matching the documents with any of the words of `words`, rather than all of them
+-- like `plainto_tsquery`, so that whole texts like the description of an Incident can be
+-- searched for.
+CREATE FUNCTION any_word_tsquery(words TEXT) RETURNS tsquery AS $$
+	SELECT replace(plainto_tsquery('english', words)::text, '&', '|')::tsquery;
+$$ LANGUAGE sql IMMUTABLE;
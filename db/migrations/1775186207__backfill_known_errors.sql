@@ -0,0 +1,19 @@
+-- Problems that were in the known error status before the Known Error Database existed get
+-- their record, filled in like the ones created when a Problem enters the status.
+WITH created AS (
+	INSERT INTO known_errors (problem_id, symptoms, root_cause, workaround_steps)
+	SELECT id, description, causes,
+		CASE
+			WHEN workarounds IS NULL THEN '{}'
+			ELSE ARRAY[workarounds]
+		END
+	FROM problems
+	WHERE status = 'knownerror'
+	ON CONFLICT (problem_id) DO NOTHING
+	RETURNING problem_id
+)
+INSERT INTO known_error_cis (problem_id, ci_id)
+SELECT DISTINCT r.problem_id, c.ci_id
+FROM created
+JOIN problem_incident_relations AS r ON r.problem_id = created.problem_id
+JOIN incidents_ci_relations AS c ON c.incident_id = r.incident_id;
@@ -0,0 +1,11 @@
+-- Incidents that were on hold before hold periods were recorded get an open period. When
+-- they went on hold isn't known, so their clock stops from now on.
+INSERT INTO incident_hold_periods (incident_id, started_at)
+SELECT i.id, now()
+FROM incidents AS i
+WHERE i.status = 'onhold'
+AND NOT EXISTS(
+	SELECT 1 FROM incident_hold_periods AS p
+	WHERE p.incident_id = i.id
+	AND p.ended_at IS NULL
+);
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+#[cfg(feature = "test-helpers")]
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use sqlx::Type;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Kind of entity an [AuditEntry] belongs to.
+#[derive(Serialize, Clone, Copy, Type, Debug, ToSchema)]
+#[sqlx(type_name = "audit_entity", rename_all = "lowercase")]
+#[schema(example = "incident")]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "test-helpers", derive(Deserialize, PartialEq))]
+pub enum AuditEntity {
+    Incident,
+    ConfigItem,
+    Problem,
+    Rfc,
+    Request,
+    Article,
+    Role,
+}
+
+/// Kind of mutation recorded by an [AuditEntry].
+#[derive(Serialize, Clone, Copy, Type, Debug, ToSchema)]
+#[sqlx(type_name = "audit_action", rename_all = "lowercase")]
+#[schema(example = "update")]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "test-helpers", derive(Deserialize, PartialEq))]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Change to an entity or one of its relations, as recorded in the audit log.
+///
+/// Entries are written by database triggers on every insert, update and delete of the
+/// audited tables and can't be modified afterwards.
+#[derive(Serialize, Debug, ToSchema)]
+#[cfg_attr(feature = "test-helpers", derive(Deserialize))]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Subject of the bearer token of the request that made the change, or `system` for
+    /// changes made outside of the API.
+    #[schema(example = "jdoe")]
+    pub actor: String,
+    pub occurred_at: DateTime<Utc>,
+    pub entity_type: AuditEntity,
+    pub entity_id: Uuid,
+    /// Table of the changed row, e.g. `incidents_ci_relations` for a change to a relation.
+    #[schema(example = "incidents")]
+    pub source: String,
+    pub action: AuditAction,
+    /// Changed columns of the row with their values before and after the change.
+    #[schema(value_type = Object, example = json!({"status": {"before": "new", "after": "assigned"}}))]
+    pub changes: serde_json::Value,
+}
+
+/// Record `actor` as the author of the changes made in the current transaction.
+///
+/// The setting only lasts until the end of the transaction, so this has no effect outside
+/// of one (see [crate::audited_transaction]).
+pub async fn set_actor(
+    actor: &str,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    sqlx::query!("SELECT set_config('app.actor', $1, true)", actor)
+        .fetch_one(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Load the audit log of an entity, oldest first. Fails with
+/// [crate::Error::NoRecordFound] if the entity never existed.
+pub async fn load_history(
+    entity_type: AuditEntity,
+    entity_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Vec<AuditEntry>, crate::Error> {
+    let entries = sqlx::query_as!(
+        AuditEntry,
+        "
+        SELECT id, actor, occurred_at, entity_type as \"entity_type: AuditEntity\", entity_id,
+            source, action as \"action: AuditAction\", changes
+        FROM audit_log
+        WHERE entity_type = $1
+        AND entity_id = $2
+        ORDER BY id",
+        entity_type as AuditEntity,
+        entity_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    if entries.is_empty() {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,129 @@
+use crate::entity_helpers;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Change Advisory Board, which decides on RFCs (see [crate::entities::changes::approvals]).
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct ChangeAdvisoryBoard {
+    /// Team whose members decide on RFCs. No RFC can be approved without it.
+    pub team_id: Option<Uuid>,
+    /// Approvals normal changes need. Emergency changes need a single one.
+    #[schema(example = 2)]
+    pub quorum: i32,
+    /// Whether RFCs colliding with others can't be approved until the collisions are
+    /// resolved (see [crate::entities::changes::conflicts]).
+    pub block_conflicts: bool,
+    /// Hours after the implementation of an RFC during which Incidents on the Configuration
+    /// Items it affects are suggested as caused by it (see
+    /// [crate::entities::changes::incident_relations::suggest_causes]).
+    #[schema(example = 24)]
+    pub review_window_hours: i32,
+}
+
+/// Payload for updating the Change Advisory Board.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ChangeAdvisoryBoardUpdateset {
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub team_id: Option<Option<Uuid>>,
+    #[schema(example = 2)]
+    #[validate(range(min = 1, max = 100))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub quorum: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub block_conflicts: Option<Option<bool>>,
+    /// Only affects suggestions made from then on.
+    #[schema(example = 24)]
+    #[validate(range(min = 1, max = 8760))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub review_window_hours: Option<Option<i32>>,
+}
+
+/// Validate that required fields of [ChangeAdvisoryBoardUpdateset] aren't explicitly null.
+fn validate_required_fields(
+    updateset: &ChangeAdvisoryBoardUpdateset,
+) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.quorum)?;
+    entity_helpers::validate_not_null(&updateset.block_conflicts)?;
+    entity_helpers::validate_not_null(&updateset.review_window_hours)?;
+
+    Ok(())
+}
+
+/// Map the errors of writing the Change Advisory Board, which may reference a nonexistent
+/// team.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+pub async fn load(
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<ChangeAdvisoryBoard, crate::Error> {
+    let board = sqlx::query_as!(
+        ChangeAdvisoryBoard,
+        "
+        SELECT team_id, quorum, block_conflicts, review_window_hours
+        FROM change_advisory_board"
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(board)
+}
+
+pub async fn update(
+    updateset: ChangeAdvisoryBoardUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<ChangeAdvisoryBoard, crate::Error> {
+    updateset.validate()?;
+
+    let board = sqlx::query_as!(
+        ChangeAdvisoryBoard,
+        "
+        UPDATE change_advisory_board
+        SET team_id = CASE
+                WHEN $1 THEN team_id
+                ELSE $2
+            END,
+            quorum = COALESCE($3, quorum), block_conflicts = COALESCE($4, block_conflicts),
+            review_window_hours = COALESCE($5, review_window_hours)
+        RETURNING team_id, quorum, block_conflicts, review_window_hours",
+        updateset.team_id.is_none(),
+        updateset.team_id.unwrap_or(None),
+        updateset.quorum.unwrap_or(None),
+        updateset.block_conflicts.unwrap_or(None),
+        updateset.review_window_hours.unwrap_or(None),
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(map_write_error)?;
+
+    Ok(board)
+}
@@ -1,50 +1,162 @@
-use crate::entity_helpers;
+use crate::entities::{configuration, freeze_periods};
+use crate::entity_helpers::{self, Lifecycle};
+use crate::pagination::{self, Page, PageParams, SortOrder};
+use crate::DbPool;
+use risk::RiskCategory;
 use serde::Deserialize;
 use serde::Serialize;
 use sqlx::types::chrono::DateTime;
 use sqlx::types::chrono::Utc;
+use sqlx::PgConnection;
 use sqlx::Postgres;
+use sqlx::QueryBuilder;
 use sqlx::Type;
+use utoipa::IntoParams;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 use validator::ValidationError;
 
+/// Module for the decisions of the Change Advisory Board on RFCs.
+pub mod approvals;
+/// Module for the schedule of RFCs and freeze periods.
+pub mod calendar;
+/// Module for the Configuration Items affected by RFCs.
+pub mod ci_relations;
+/// Module for detecting collisions between RFCs.
+pub mod conflicts;
+/// Module for the overrides of the freeze periods granted to RFCs.
+pub mod freeze_overrides;
+/// Module for the Incidents related to RFCs, and the ones they may have caused.
 pub mod incident_relations;
 pub mod problem_relations;
+/// Module for the post-implementation reviews of RFCs.
+pub mod reviews;
+/// Module for assessing the risk of RFCs.
+pub mod risk;
 
 /// RFC in the database.
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Clone, Debug, Serialize, ToSchema, sqlx::FromRow)]
 #[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
 pub struct RFC {
     pub id: Uuid,
     #[schema(example = "Sales Department OS Update")]
     pub title: String,
+    pub r#type: RFCType,
     pub status: RFCStatus,
     pub created_at: DateTime<Utc>,
     pub finished_at: Option<DateTime<Utc>>,
-    #[schema(example = "Sales Department.")]
-    pub requester: String,
+    /// User who requested the change.
+    pub requester_id: Uuid,
     #[schema(example = "Update sales department workstations to naviOS v25.")]
     pub description: String,
+    /// Start of the window the change is planned to be implemented in.
+    pub planned_start_at: Option<DateTime<Utc>>,
+    /// End of the planned window, exclusive.
+    pub planned_end_at: Option<DateTime<Utc>>,
+    /// When the implementation actually started.
+    pub actual_start_at: Option<DateTime<Utc>>,
+    /// When the implementation actually ended. Null while it's ongoing.
+    pub actual_end_at: Option<DateTime<Utc>>,
+    /// Steps to implement the change.
+    #[schema(example = "Roll out the update to one workstation per hour.")]
+    pub implementation_plan: Option<String>,
+    /// Steps to undo the change if it fails.
+    #[schema(example = "Restore the disk images taken before the update.")]
+    pub backout_plan: Option<String>,
+    /// How to verify that the change works.
+    #[schema(example = "Log in and print a test page from every workstation.")]
+    pub test_plan: Option<String>,
+    /// Score of the risk assessment (see [risk]). Null until the risk is assessed.
+    pub risk_score: Option<i32>,
+    pub risk_category: Option<RiskCategory>,
 }
 
 /// Payload for creating an RFC.
 #[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_initial_status"))]
+#[validate(schema(function = "validate_createset_window"))]
 #[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
 pub struct RFCCreateset {
     #[schema(example = "Sales Department OS Update")]
     #[validate(length(min = 1, max = 255))]
     pub title: String,
+    /// Defaults to `normal`.
+    pub r#type: Option<RFCType>,
+    /// Either `draft` or `submitted`. Defaults to `draft`.
+    #[schema(example = "draft")]
     pub status: Option<RFCStatus>,
     pub created_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
-    #[schema(example = "Sales Department.")]
-    #[validate(length(max = 1024))]
-    pub requester: String,
+    /// User who requests the change.
+    pub requester_id: Uuid,
     #[schema(example = "Update sales department workstations to naviOS v25.")]
     #[validate(length(max = 1024))]
     pub description: String,
+    /// Must be given along with the end of the planned window, or not at all.
+    pub planned_start_at: Option<DateTime<Utc>>,
+    /// End of the planned window, exclusive. Must be after its start.
+    pub planned_end_at: Option<DateTime<Utc>>,
+    /// Required before the RFC is assessed or approved, as are the backout and test plans.
+    #[schema(example = "Roll out the update to one workstation per hour.")]
+    #[validate(length(min = 1, max = 4096))]
+    pub implementation_plan: Option<String>,
+    #[schema(example = "Restore the disk images taken before the update.")]
+    #[validate(length(min = 1, max = 4096))]
+    pub backout_plan: Option<String>,
+    #[schema(example = "Log in and print a test page from every workstation.")]
+    #[validate(length(min = 1, max = 4096))]
+    pub test_plan: Option<String>,
+}
+
+/// Validate that an [RFCCreateset] starts the lifecycle of the RFC, so that it doesn't skip
+/// the approval of the CAB.
+fn validate_initial_status(createset: &RFCCreateset) -> Result<(), ValidationError> {
+    match createset.status {
+        None | Some(RFCStatus::Draft | RFCStatus::Submitted) => Ok(()),
+        Some(_) => Err(ValidationError::new(
+            "RFCs can only be created as drafts or submitted",
+        )),
+    }
+}
+
+/// Validate the planned window of an [RFCCreateset] (see [validate_planned_window]).
+fn validate_createset_window(createset: &RFCCreateset) -> Result<(), ValidationError> {
+    validate_planned_window(createset.planned_start_at, createset.planned_end_at)
+}
+
+/// Validate that a planned window is either unset, or complete and ends after it starts.
+fn validate_planned_window(
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<(), ValidationError> {
+    match (start, end) {
+        (None, None) => Ok(()),
+        (Some(start), Some(end)) if start < end => Ok(()),
+        (Some(_), Some(_)) => Err(ValidationError::new(
+            "The planned window must end after it starts",
+        )),
+        _ => Err(ValidationError::new(
+            "The planned window needs both a start and an end",
+        )),
+    }
+}
+
+/// Validate that an actual window has a start if it has an end, and doesn't end before it
+/// starts.
+fn validate_actual_window(
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<(), ValidationError> {
+    match (start, end) {
+        (None, Some(_)) => Err(ValidationError::new(
+            "The actual window can't end without a start",
+        )),
+        (Some(start), Some(end)) if end < start => Err(ValidationError::new(
+            "The actual window can't end before it starts",
+        )),
+        _ => Ok(()),
+    }
 }
 
 /// Payload for updating an RFC.
@@ -60,6 +172,15 @@ pub struct RFCUpdateset {
         serde(skip_serializing_if = "Option::is_none")
     )]
     pub title: Option<Option<String>>,
+    /// Can only be changed while the RFC is a draft.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub r#type: Option<Option<RFCType>>,
+    /// Must be reachable from the current status (see [RFCStatus::next_for]). Moving to
+    /// `approved` needs the approval of the CAB (see [approvals]).
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
@@ -78,14 +199,13 @@ pub struct RFCUpdateset {
         serde(skip_serializing_if = "Option::is_none")
     )]
     pub finished_at: Option<Option<DateTime<Utc>>>,
-    #[schema(example = "Sales Department")]
-    #[validate(length(max = 1024))]
+    /// User who requests the change.
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub requester: Option<Option<String>>,
+    pub requester_id: Option<Option<Uuid>>,
     #[schema(example = "Update sales department workstations to naviOS v25.")]
     #[validate(length(max = 1024))]
     #[serde(default, with = "::serde_with::rust::double_option")]
@@ -94,37 +214,318 @@ pub struct RFCUpdateset {
         serde(skip_serializing_if = "Option::is_none")
     )]
     pub description: Option<Option<String>>,
+    /// Set both ends of the planned window to null to unplan the RFC. Scheduled RFCs need a
+    /// planned window, which can't overlap a freeze period unless the RFC is an emergency
+    /// change or has an override (see [crate::entities::freeze_periods] and
+    /// [freeze_overrides]).
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub planned_start_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub planned_end_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub actual_start_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub actual_end_at: Option<Option<DateTime<Utc>>>,
+    /// Required before the RFC is assessed or approved, as are the backout and test plans.
+    #[schema(example = "Roll out the update to one workstation per hour.")]
+    #[validate(length(min = 1, max = 4096))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub implementation_plan: Option<Option<String>>,
+    #[schema(example = "Restore the disk images taken before the update.")]
+    #[validate(length(min = 1, max = 4096))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub backout_plan: Option<Option<String>>,
+    #[schema(example = "Log in and print a test page from every workstation.")]
+    #[validate(length(min = 1, max = 4096))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub test_plan: Option<Option<String>>,
 }
 
 /// Validate that required fields of [RFCUpdateset] aren't explicitly null.
 fn validate_required_fields(updateset: &RFCUpdateset) -> Result<(), ValidationError> {
     entity_helpers::validate_not_null(&updateset.title)?;
+    entity_helpers::validate_not_null(&updateset.r#type)?;
     entity_helpers::validate_not_null(&updateset.status)?;
     entity_helpers::validate_not_null(&updateset.created_at)?;
-    entity_helpers::validate_not_null(&updateset.requester)?;
+    entity_helpers::validate_not_null(&updateset.requester_id)?;
     entity_helpers::validate_not_null(&updateset.description)?;
 
     Ok(())
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type)]
+/// ITIL change type, which decides the path of an RFC to implementation.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "rfc_type", rename_all = "lowercase")]
+#[schema(example = "normal")]
+pub enum RFCType {
+    /// Pre-approved, low-risk change. Scheduled right after submission.
+    Standard,
+    /// Assessed and then approved by the CAB, with as many approvals as its risk needs (see
+    /// [RiskCategory]).
+    #[default]
+    Normal,
+    /// Urgent change. Fast-tracked to approval by any single CAB member, with or without
+    /// assessment.
+    Emergency,
+}
+
+impl RFCType {
+    /// Whether RFCs of this type can move from status `from` to `to`, given that the
+    /// transition is part of [RFCStatus::next].
+    fn allows(&self, from: RFCStatus, to: RFCStatus) -> bool {
+        use RFCStatus::*;
+        match self {
+            Self::Standard => !matches!(to, Assessed | Approved | Rejected),
+            Self::Normal => !(from == Submitted && matches!(to, Approved | Rejected | Scheduled)),
+            Self::Emergency => !(from == Submitted && to == Scheduled),
+        }
+    }
+
+    /// Approvals of CAB members an RFC of this type and `risk` needs to be approved, given
+    /// the quorum and amount of members of the CAB. `None` if it needs none.
+    pub fn required_approvals(
+        &self,
+        risk: Option<RiskCategory>,
+        quorum: i32,
+        members: i64,
+    ) -> Option<i64> {
+        match (self, risk) {
+            (Self::Standard, _) => None,
+            (Self::Normal, Some(RiskCategory::Low)) => Some(1),
+            (Self::Normal, Some(RiskCategory::High)) => Some(members.max(quorum.into())),
+            (Self::Normal, _) => Some(quorum.into()),
+            (Self::Emergency, _) => Some(1),
+        }
+    }
+
+    /// Whether the CAB can decide on RFCs of this type that are in `status`.
+    pub fn awaits_decision(&self, status: RFCStatus) -> bool {
+        match self {
+            Self::Standard => false,
+            Self::Normal => status == RFCStatus::Assessed,
+            Self::Emergency => matches!(status, RFCStatus::Submitted | RFCStatus::Assessed),
+        }
+    }
+}
+
+/// Status of an RFC along the ITIL change lifecycle.
+///
+/// Drafts are submitted, assessed and then approved or rejected by the CAB. Approved RFCs
+/// are scheduled, implemented and reviewed before they're closed. The type of the RFC may
+/// shorten the path (see [RFCType]) and [RFCStatus::next_for] gives the exact transitions.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "rfcstatus", rename_all = "lowercase")]
-#[schema(example = "active")]
-#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+#[schema(example = "submitted")]
 pub enum RFCStatus {
-    Open,
-    InProgress,
+    Draft,
+    Submitted,
+    Assessed,
+    Approved,
+    Rejected,
+    Scheduled,
+    Implemented,
+    Reviewed,
     Closed,
 }
 
+impl Lifecycle for RFCStatus {
+    /// Transitions of RFCs of any type. Closing an RFC before implementing it withdraws or
+    /// cancels it.
+    fn next(&self) -> &'static [Self] {
+        use RFCStatus::*;
+        match self {
+            Draft => &[Submitted, Closed],
+            Submitted => &[Draft, Assessed, Approved, Rejected, Scheduled, Closed],
+            Assessed => &[Draft, Approved, Rejected],
+            Approved => &[Scheduled],
+            Rejected => &[Draft, Closed],
+            Scheduled => &[Implemented, Closed],
+            Implemented => &[Reviewed],
+            Reviewed => &[Closed],
+            Closed => &[],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Submitted => "submitted",
+            Self::Assessed => "assessed",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+            Self::Scheduled => "scheduled",
+            Self::Implemented => "implemented",
+            Self::Reviewed => "reviewed",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+impl RFCStatus {
+    /// Statuses RFCs of type `r#type` can move to from `self`.
+    pub fn next_for(&self, r#type: RFCType) -> Vec<Self> {
+        self.next()
+            .iter()
+            .copied()
+            .filter(|to| r#type.allows(*self, *to))
+            .collect()
+    }
+}
+
+/// Query parameters for listing RFCs.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RFCListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Column to sort by. Defaults to `created_at`.
+    #[param(inline)]
+    pub sort_by: Option<RFCSortColumn>,
+    /// Sorting direction. Defaults to `asc`.
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    #[param(inline)]
+    pub r#type: Option<RFCType>,
+    pub status: Option<RFCStatus>,
+    /// Only RFCs requested by this user.
+    pub requester_id: Option<Uuid>,
+    /// Only RFCs created at or after this moment.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only RFCs created before this moment.
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only RFCs finished at or after this moment.
+    pub finished_after: Option<DateTime<Utc>>,
+    /// Only RFCs finished before this moment.
+    pub finished_before: Option<DateTime<Utc>>,
+}
+
+/// Columns RFCs can be sorted by.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(example = "created_at")]
+pub enum RFCSortColumn {
+    #[default]
+    CreatedAt,
+    FinishedAt,
+    PlannedStartAt,
+    Title,
+    Status,
+}
+
+impl RFCSortColumn {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::FinishedAt => "finished_at",
+            Self::PlannedStartAt => "planned_start_at",
+            Self::Title => "title",
+            Self::Status => "status",
+        }
+    }
+}
+
+/// Append the `WHERE` clause matching the filters in [RFCListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a RFCListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(r#type) = params.r#type {
+        builder.push(" AND type = ").push_bind(r#type);
+    }
+    if let Some(status) = params.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(requester_id) = params.requester_id {
+        builder.push(" AND requester_id = ").push_bind(requester_id);
+    }
+    if let Some(created_after) = params.created_after {
+        builder.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = params.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(finished_after) = params.finished_after {
+        builder
+            .push(" AND finished_at >= ")
+            .push_bind(finished_after);
+    }
+    if let Some(finished_before) = params.finished_before {
+        builder
+            .push(" AND finished_at < ")
+            .push_bind(finished_before);
+    }
+}
+
+/// Load one page of RFCs matching the filters in `params`.
+pub async fn load_page(params: RFCListParams, pool: &DbPool) -> Result<Page<RFC>, crate::Error> {
+    params.validate()?;
+
+    let order_by = format!(
+        "{} {}, id",
+        params.sort_by.unwrap_or_default().as_sql(),
+        params.order.unwrap_or_default().as_sql()
+    );
+
+    let mut tx = pool.begin().await?;
+    let rfcs = pagination::load_page::<RFC>(
+        &mut tx,
+        "
+        id, title, type, status, created_at, finished_at, requester_id, description,
+        planned_start_at, planned_end_at, actual_start_at, actual_end_at, implementation_plan,
+        backout_plan, test_plan, risk_score, risk_category",
+        "rfcs",
+        |builder| push_filters(builder, &params),
+        &order_by,
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(rfcs)
+}
+
 pub async fn load_all(
     executor: impl sqlx::Executor<'_, Database = Postgres>,
 ) -> Result<Vec<RFC>, crate::Error> {
     let rfcs = sqlx::query_as!(
         RFC,
         "
-        SELECT id, title, status as \"status: RFCStatus\", created_at, finished_at, requester, description
+        SELECT id, title, type as \"type: RFCType\", status as \"status: RFCStatus\", created_at,
+            finished_at, requester_id, description, planned_start_at, planned_end_at,
+            actual_start_at, actual_end_at,
+            implementation_plan, backout_plan, test_plan, risk_score,
+            risk_category as \"risk_category: RiskCategory\"
         FROM rfcs"
     )
     .fetch_all(executor)
@@ -139,7 +540,11 @@ pub async fn load(
     match sqlx::query_as!(
         RFC,
         "
-        SELECT id, title, status as \"status: RFCStatus\", created_at, finished_at, requester, description
+        SELECT id, title, type as \"type: RFCType\", status as \"status: RFCStatus\", created_at,
+            finished_at, requester_id, description, planned_start_at, planned_end_at,
+            actual_start_at, actual_end_at,
+            implementation_plan, backout_plan, test_plan, risk_score,
+            risk_category as \"risk_category: RiskCategory\"
         FROM rfcs
         WHERE id = $1",
         id
@@ -153,6 +558,16 @@ pub async fn load(
     }
 }
 
+/// Map the errors of writing an RFC, which may reference a nonexistent requester.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
 pub async fn create(
     createset: RFCCreateset,
     executor: impl sqlx::Executor<'_, Database = Postgres>,
@@ -162,60 +577,249 @@ pub async fn create(
     let created_rfc = sqlx::query_as!(
         RFC,
         "
-        INSERT INTO rfcs (title, status, created_at, finished_at, requester, description)
-        VALUES ($1, $2, COALESCE($3, now()), $4, $5, $6)
-        RETURNING id, title, status as \"status: RFCStatus\", created_at, finished_at, requester, description",
+        INSERT INTO rfcs (title, type, status, created_at, finished_at, requester_id, description,
+            planned_start_at, planned_end_at, implementation_plan, backout_plan, test_plan)
+        VALUES ($1, $2, $3, COALESCE($4, now()), $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING id, title, type as \"type: RFCType\", status as \"status: RFCStatus\", created_at,
+            finished_at, requester_id, description, planned_start_at, planned_end_at,
+            actual_start_at, actual_end_at,
+            implementation_plan, backout_plan, test_plan, risk_score,
+            risk_category as \"risk_category: RiskCategory\"",
         createset.title,
-        createset.status.unwrap_or(RFCStatus::Open) as RFCStatus,
+        createset.r#type.unwrap_or_default() as RFCType,
+        createset.status.unwrap_or(RFCStatus::Draft) as RFCStatus,
         createset.created_at,
         createset.finished_at,
-        createset.requester,
+        createset.requester_id,
         createset.description,
+        createset.planned_start_at,
+        createset.planned_end_at,
+        createset.implementation_plan,
+        createset.backout_plan,
+        createset.test_plan,
     )
     .fetch_one(executor)
     .await
-    .map_err(crate::Error::DbError)?;
+    .map_err(map_write_error)?;
 
     Ok(created_rfc)
 }
 
+/// Check that the RFC `id` of type `r#type` can be scheduled in the planned `window`.
+///
+/// Scheduled RFCs need a planned window. Unless they're emergency changes or have an
+/// override (see [freeze_overrides]), it can't overlap a freeze period, which is only
+/// checked when the RFC is `rescheduled` so that freezes set later don't block other
+/// updates.
+async fn check_schedule(
+    id: Uuid,
+    r#type: RFCType,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    rescheduled: bool,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let mut errors = validator::ValidationErrors::new();
+    match window {
+        None => errors.add(
+            "planned_start_at",
+            ValidationError::new("Scheduled RFCs need a planned window"),
+        ),
+        Some((start, end)) if rescheduled && r#type != RFCType::Emergency => {
+            if !freeze_periods::load_overlapping(start, end, &mut *conn)
+                .await?
+                .is_empty()
+                && !freeze_overrides::exists(id, &mut *conn).await?
+            {
+                errors.add(
+                    "planned_start_at",
+                    ValidationError::new(
+                        "Only emergency changes or overridden RFCs can be scheduled during freezes",
+                    ),
+                );
+            }
+        }
+        Some(_) => {}
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors.into())
+}
+
+/// Update an RFC.
+///
+/// Fails with [crate::Error::InvalidTransition] if the new status isn't reachable from the
+/// current one by RFCs of its type, and with [crate::Error::ValidationError] if it's
+/// `assessed`, `approved` or `scheduled` before it's ready (see [risk::check_ready]),
+/// `approved` without the approval of the CAB, or `scheduled` in a window that overlaps a
+/// freeze period while it's neither an emergency change nor has an override (see
+/// [check_schedule]), or `reviewed` without a post-implementation review (see
+/// [reviews::check_ready]). The decisions of the CAB and the override of the freeze
+/// periods are discarded when the RFC goes back to `draft`. When it's `implemented`, its
+/// actual window ends now unless it ended already, a change is recorded on every affected
+/// Configuration Item and the Incidents it may have caused are suggested (see
+/// [incident_relations::suggest_causes]). They're suggested again when the actual window of
+/// an implemented RFC changes.
 pub async fn update(
     id: Uuid,
     updateset: RFCUpdateset,
-    executor: impl sqlx::Executor<'_, Database = Postgres>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<RFC, crate::Error> {
     updateset.validate()?;
 
-    match sqlx::query_as!(
-        RFC,
+    let mut tx = executor.begin().await?;
+
+    let current = sqlx::query!(
+        "
+        SELECT type as \"type: RFCType\", status as \"status: RFCStatus\", planned_start_at,
+            planned_end_at, actual_start_at, actual_end_at
+        FROM rfcs
+        WHERE id = $1
+        FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let mut errors = validator::ValidationErrors::new();
+
+    let r#type = updateset.r#type.flatten().unwrap_or(current.r#type);
+    if r#type != current.r#type && current.status != RFCStatus::Draft {
+        errors.add(
+            "type",
+            ValidationError::new("The type of an RFC can only change while it's a draft"),
+        );
+    }
+
+    let planned_start_at = updateset
+        .planned_start_at
+        .unwrap_or(current.planned_start_at);
+    let planned_end_at = updateset.planned_end_at.unwrap_or(current.planned_end_at);
+    let mut actual_start_at = updateset.actual_start_at.unwrap_or(current.actual_start_at);
+    let mut actual_end_at = updateset.actual_end_at.unwrap_or(current.actual_end_at);
+    let status = updateset.status.flatten().unwrap_or(current.status);
+    // Implemented RFCs always have an actual window, which the Incidents they may have
+    // caused are looked for in.
+    if status == RFCStatus::Implemented && status != current.status && actual_end_at.is_none() {
+        actual_end_at = Some(Utc::now());
+        actual_start_at = actual_start_at.or(actual_end_at);
+    }
+    if let Err(e) = validate_planned_window(planned_start_at, planned_end_at) {
+        errors.add("planned_end_at", e);
+    }
+    if let Err(e) = validate_actual_window(actual_start_at, actual_end_at) {
+        errors.add("actual_end_at", e);
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    entity_helpers::check_transition_among(
+        current.status,
+        status,
+        &current.status.next_for(r#type),
+    )?;
+    if status != current.status {
+        match status {
+            RFCStatus::Approved => approvals::check_approved(id, r#type, &mut tx).await?,
+            RFCStatus::Draft => {
+                approvals::discard(id, &mut *tx).await?;
+                freeze_overrides::discard(id, &mut *tx).await?;
+            }
+            RFCStatus::Reviewed => reviews::check_ready(id, &mut tx).await?,
+            _ => {}
+        }
+    }
+
+    if status == RFCStatus::Scheduled {
+        let rescheduled = status != current.status
+            || planned_start_at != current.planned_start_at
+            || planned_end_at != current.planned_end_at;
+        check_schedule(
+            id,
+            r#type,
+            planned_start_at.zip(planned_end_at),
+            rescheduled,
+            &mut tx,
+        )
+        .await?;
+    }
+
+    sqlx::query!(
         "
         UPDATE rfcs
-        SET title = COALESCE($1, title), status = COALESCE($2, status), created_at = COALESCE($3, created_at),
+        SET title = COALESCE($1, title), type = $2, status = $3, created_at = COALESCE($4, created_at),
             finished_at = CASE
-                WHEN $4 then finished_at
-                ELSE $5
+                WHEN $5 then finished_at
+                ELSE $6
+            END,
+            requester_id = COALESCE($7, requester_id), description = COALESCE($8, description),
+            planned_start_at = $9, planned_end_at = $10, actual_start_at = $11, actual_end_at = $12,
+            implementation_plan = CASE
+                WHEN $13 THEN implementation_plan
+                ELSE $14
+            END,
+            backout_plan = CASE
+                WHEN $15 THEN backout_plan
+                ELSE $16
             END,
-            requester = COALESCE($6, requester), description = COALESCE($7, description)
-        WHERE id = $8
-        RETURNING id, title, status as \"status: RFCStatus\", created_at, finished_at, requester, description",
+            test_plan = CASE
+                WHEN $17 THEN test_plan
+                ELSE $18
+            END
+        WHERE id = $19",
         updateset.title.unwrap_or(None),
-        updateset.status.unwrap_or(None) as Option<RFCStatus>,
+        r#type as RFCType,
+        status as RFCStatus,
         updateset.created_at.unwrap_or(None),
         updateset.finished_at.is_none(),
         updateset.finished_at.unwrap_or(None),
-        updateset.requester.unwrap_or(None),
+        updateset.requester_id.unwrap_or(None),
         updateset.description.unwrap_or(None),
+        planned_start_at,
+        planned_end_at,
+        actual_start_at,
+        actual_end_at,
+        updateset.implementation_plan.is_none(),
+        updateset.implementation_plan.unwrap_or(None),
+        updateset.backout_plan.is_none(),
+        updateset.backout_plan.unwrap_or(None),
+        updateset.test_plan.is_none(),
+        updateset.test_plan.unwrap_or(None),
         id,
     )
-    .fetch_optional(executor)
+    .execute(&mut *tx)
     .await
-    .map_err(crate::Error::DbError)?
+    .map_err(map_write_error)?;
+
+    // Checked once the plans are updated, as they may be given along with the status.
+    if status != current.status
+        && matches!(
+            status,
+            RFCStatus::Assessed | RFCStatus::Approved | RFCStatus::Scheduled
+        )
     {
-        Some(updated_rfc) => Ok(updated_rfc),
-        None => Err(crate::Error::NoRecordFound),
+        risk::check_ready(id, r#type, &mut tx).await?;
     }
+
+    let updated_rfc = load(id, &mut *tx).await?;
+    if status == RFCStatus::Implemented && status != current.status {
+        configuration::changes::create_for_rfc(&updated_rfc, &mut tx).await?;
+        incident_relations::suggest_causes(Some(id), None, &mut tx).await?;
+    } else if matches!(status, RFCStatus::Implemented | RFCStatus::Reviewed)
+        && (actual_start_at != current.actual_start_at || actual_end_at != current.actual_end_at)
+    {
+        incident_relations::suggest_causes(Some(id), None, &mut tx).await?;
+    }
+    tx.commit().await?;
+    Ok(updated_rfc)
 }
 
+/// Delete an RFC. Fails with [crate::Error::ConstraintError] if changes to Configuration
+/// Items trace back to it.
 pub async fn delete(
     id: Uuid,
     executor: impl sqlx::Executor<'_, Database = Postgres>,
@@ -229,7 +833,7 @@ pub async fn delete(
     )
     .fetch_optional(executor)
     .await
-    .map_err(crate::Error::DbError)?
+    .map_err(map_write_error)?
     {
         Some(_) => Ok(()),
         None => Err(crate::Error::NoRecordFound),
@@ -0,0 +1,286 @@
+use super::{conflicts, risk, RFCStatus, RFCType};
+use crate::entities::change_advisory_board;
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "rfc_vote", rename_all = "lowercase")]
+#[schema(example = "approve")]
+pub enum RFCVote {
+    Approve,
+    Reject,
+}
+
+/// Decision of a CAB member on an RFC.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RFCApproval {
+    pub id: Uuid,
+    pub rfc_id: Uuid,
+    /// CAB member who made the decision.
+    pub approver_id: Uuid,
+    pub vote: RFCVote,
+    #[schema(example = "Rollback plan looks solid.")]
+    pub comment: String,
+    pub decided_at: DateTime<Utc>,
+}
+
+/// Payload for deciding on an RFC.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RFCApprovalCreateset {
+    pub vote: RFCVote,
+    #[schema(example = "Rollback plan looks solid.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub comment: String,
+}
+
+async fn check_valid_rfc(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM rfcs WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Map the errors of writing a decision, which may repeat the one of an approver.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_unique_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load the decisions on the current submission of an RFC, oldest first.
+pub async fn load_all(rfc_id: Uuid, pool: &DbPool) -> Result<Vec<RFCApproval>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_rfc(rfc_id, &mut *tx).await?;
+    let approvals = sqlx::query_as!(
+        RFCApproval,
+        "
+        SELECT id, rfc_id, approver_id, vote as \"vote: RFCVote\", comment, decided_at
+        FROM rfc_approvals
+        WHERE rfc_id = $1
+        ORDER BY decided_at, id",
+        rfc_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(approvals)
+}
+
+/// Whether the decisions of the current CAB members approve an RFC of type `r#type`, i.e.
+/// there are enough approvals for its risk and no rejection.
+async fn is_approved(
+    rfc_id: Uuid,
+    r#type: RFCType,
+    conn: &mut PgConnection,
+) -> Result<bool, crate::Error> {
+    let board = change_advisory_board::load(&mut *conn).await?;
+    let rfc = sqlx::query!(
+        "
+        SELECT risk_category as \"risk_category: risk::RiskCategory\",
+            (SELECT COUNT(*) FROM team_members WHERE team_id = $2) AS \"members!\"
+        FROM rfcs
+        WHERE id = $1",
+        rfc_id,
+        board.team_id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let Some(required) = r#type.required_approvals(rfc.risk_category, board.quorum, rfc.members)
+    else {
+        return Ok(false);
+    };
+
+    let votes = sqlx::query!(
+        "
+        SELECT COUNT(*) FILTER (WHERE a.vote = 'approve') AS \"approvals!\",
+            COUNT(*) FILTER (WHERE a.vote = 'reject') AS \"rejections!\"
+        FROM rfc_approvals AS a
+        INNER JOIN team_members AS m
+        ON m.user_id = a.approver_id
+        WHERE a.rfc_id = $1
+        AND m.team_id = $2",
+        rfc_id,
+        board.team_id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(votes.rejections == 0 && votes.approvals >= required)
+}
+
+/// Check that the RFC `rfc_id` doesn't collide with other RFCs, if the CAB doesn't approve
+/// such RFCs. Errors are reported on `field`.
+async fn check_no_conflicts(
+    rfc_id: Uuid,
+    field: &'static str,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let board = change_advisory_board::load(&mut *conn).await?;
+    if !board.block_conflicts || !conflicts::exist(rfc_id, conn).await? {
+        return Ok(());
+    }
+
+    let mut errors = validator::ValidationErrors::new();
+    errors.add(
+        field,
+        ValidationError::new("The RFC collides with other RFCs"),
+    );
+    Err(errors.into())
+}
+
+/// Check that the CAB approves an RFC of type `r#type`, so that it can move to `approved`.
+pub async fn check_approved(
+    rfc_id: Uuid,
+    r#type: RFCType,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    check_no_conflicts(rfc_id, "status", &mut *conn).await?;
+    if is_approved(rfc_id, r#type, conn).await? {
+        return Ok(());
+    }
+
+    let mut errors = validator::ValidationErrors::new();
+    errors.add(
+        "status",
+        ValidationError::new("The RFC lacks the approval of the CAB"),
+    );
+    Err(errors.into())
+}
+
+/// Discard the decisions on an RFC, e.g. when it goes back to draft to be submitted again.
+pub async fn discard(
+    rfc_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        DELETE FROM rfc_approvals
+        WHERE rfc_id = $1",
+        rfc_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Record the decision of the CAB member with username `approver` on an RFC.
+///
+/// Fails with [crate::Error::NotAllowed] if the RFC isn't awaiting a decision (see
+/// [RFCType::awaits_decision]) or `approver` isn't a CAB member, and with
+/// [crate::Error::ValidationError] if it's an approval of an RFC that isn't ready (see
+/// [risk::check_ready]) or collides with others while the CAB blocks them. A rejection
+/// rejects the RFC right away, and the RFC is approved as soon as it has the approvals it
+/// needs.
+pub async fn create(
+    rfc_id: Uuid,
+    approver: &str,
+    createset: RFCApprovalCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RFCApproval, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let rfc = sqlx::query!(
+        "
+        SELECT type as \"type: RFCType\", status as \"status: RFCStatus\"
+        FROM rfcs
+        WHERE id = $1
+        FOR UPDATE",
+        rfc_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if !rfc.r#type.awaits_decision(rfc.status) {
+        return Err(crate::Error::NotAllowed(
+            "The RFC isn't awaiting a decision of the CAB",
+        ));
+    }
+
+    let approver_id = sqlx::query_scalar!(
+        "
+        SELECT u.id
+        FROM users AS u
+        INNER JOIN team_members AS m
+        ON m.user_id = u.id
+        INNER JOIN change_advisory_board AS b
+        ON b.team_id = m.team_id
+        WHERE u.username = $1",
+        approver
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NotAllowed(
+        "Only members of the CAB can decide on RFCs",
+    ))?;
+
+    if createset.vote == RFCVote::Approve {
+        risk::check_ready(rfc_id, rfc.r#type, &mut tx).await?;
+        check_no_conflicts(rfc_id, "vote", &mut tx).await?;
+    }
+
+    let approval = sqlx::query_as!(
+        RFCApproval,
+        "
+        INSERT INTO rfc_approvals (rfc_id, approver_id, vote, comment)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, rfc_id, approver_id, vote as \"vote: RFCVote\", comment, decided_at",
+        rfc_id,
+        approver_id,
+        createset.vote as RFCVote,
+        createset.comment,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+
+    let status = match approval.vote {
+        RFCVote::Reject => Some(RFCStatus::Rejected),
+        RFCVote::Approve if is_approved(rfc_id, rfc.r#type, &mut tx).await? => {
+            Some(RFCStatus::Approved)
+        }
+        RFCVote::Approve => None,
+    };
+    if let Some(status) = status {
+        sqlx::query!(
+            "
+            UPDATE rfcs
+            SET status = $1
+            WHERE id = $2",
+            status as RFCStatus,
+            rfc_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(approval)
+}
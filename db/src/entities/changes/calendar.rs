@@ -0,0 +1,96 @@
+use super::{risk::RiskCategory, RFCStatus, RFCType, RFC};
+use crate::entities::freeze_periods::{self, FreezePeriod};
+use crate::DbPool;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+/// Longest range the calendar can be loaded for, in days.
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+/// Query parameters for loading the change calendar.
+#[derive(Clone, Debug, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[validate(schema(function = "validate_range"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ChangeCalendarParams {
+    /// Start of the range.
+    pub from: DateTime<Utc>,
+    /// End of the range, exclusive. At most a year after its start.
+    pub to: DateTime<Utc>,
+    /// Only RFCs in this status.
+    pub status: Option<RFCStatus>,
+}
+
+/// Validate that the range of [ChangeCalendarParams] ends after it starts, and isn't longer
+/// than [MAX_RANGE_DAYS].
+fn validate_range(params: &ChangeCalendarParams) -> Result<(), ValidationError> {
+    if params.from >= params.to {
+        return Err(ValidationError::new("The range must end after it starts"));
+    }
+    if params.to - params.from > TimeDelta::days(MAX_RANGE_DAYS) {
+        return Err(ValidationError::new(
+            "The range can't be longer than a year",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Schedule of changes in a range of time.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct ChangeCalendar {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// RFCs whose planned or actual implementation window overlaps the range, earliest first.
+    /// Ongoing implementations count as overlapping every range after their start.
+    pub changes: Vec<RFC>,
+    /// Freeze periods overlapping the range, earliest first.
+    pub freeze_periods: Vec<FreezePeriod>,
+}
+
+/// Load the change calendar for the range in `params`.
+pub async fn load(
+    params: ChangeCalendarParams,
+    pool: &DbPool,
+) -> Result<ChangeCalendar, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let changes = sqlx::query_as!(
+        RFC,
+        "
+        SELECT id, title, type as \"type: RFCType\", status as \"status: RFCStatus\", created_at,
+            finished_at, requester_id, description, planned_start_at, planned_end_at,
+            actual_start_at, actual_end_at,
+            implementation_plan, backout_plan, test_plan, risk_score,
+            risk_category as \"risk_category: RiskCategory\"
+        FROM rfcs
+        WHERE (
+            (planned_start_at < $2 AND planned_end_at > $1)
+            OR (actual_start_at < $2 AND COALESCE(actual_end_at, 'infinity') > $1)
+        )
+        AND ($3::rfcstatus IS NULL OR status = $3)
+        ORDER BY COALESCE(actual_start_at, planned_start_at), id",
+        params.from,
+        params.to,
+        params.status as Option<RFCStatus>,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let freeze_periods = freeze_periods::load_overlapping(params.from, params.to, &mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(ChangeCalendar {
+        from: params.from,
+        to: params.to,
+        changes,
+        freeze_periods,
+    })
+}
@@ -0,0 +1,111 @@
+use super::{risk::RiskCategory, RFCStatus, RFCType, RFC};
+use crate::DbPool;
+#[cfg(any(feature = "test-helpers", test))]
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::PgConnection;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Collision of an RFC with another one, planned in an overlapping window on the same
+/// Configuration Item or on directly related ones.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RFCConflict {
+    /// The other RFC.
+    pub rfc: RFC,
+    /// Configuration Item affected by the analysed RFC.
+    pub ci_id: Uuid,
+    /// Configuration Item affected by the other RFC. Either the same one, or one related to
+    /// it by a relation in any direction.
+    pub conflicting_ci_id: Uuid,
+}
+
+/// Pairs of RFC and Configuration Items the RFC `rfc_id` collides on, ordered by the
+/// planned start of the other RFC.
+///
+/// Only RFCs still to be implemented count, and drafts don't as they aren't planned yet.
+async fn find(
+    rfc_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<Vec<(Uuid, Uuid, Uuid)>, crate::Error> {
+    let rows = sqlx::query!(
+        "
+        SELECT o.id AS \"rfc_id!\", mine.ci_id AS \"ci_id!\", theirs.ci_id AS \"conflicting_ci_id!\"
+        FROM rfcs AS r
+        JOIN rfc_ci_relations AS mine ON mine.rfc_id = r.id
+        JOIN rfcs AS o
+            ON o.id <> r.id
+            AND o.planned_start_at < r.planned_end_at
+            AND o.planned_end_at > r.planned_start_at
+        JOIN rfc_ci_relations AS theirs ON theirs.rfc_id = o.id
+        WHERE r.id = $1
+        AND o.status NOT IN ('draft', 'rejected', 'implemented', 'reviewed', 'closed')
+        AND (
+            theirs.ci_id = mine.ci_id
+            OR EXISTS(
+                SELECT 1 FROM ci_relations AS cr
+                WHERE (cr.source_id = mine.ci_id AND cr.target_id = theirs.ci_id)
+                OR (cr.source_id = theirs.ci_id AND cr.target_id = mine.ci_id)
+            )
+        )
+        ORDER BY o.planned_start_at, o.id, mine.ci_id, theirs.ci_id",
+        rfc_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.rfc_id, row.ci_id, row.conflicting_ci_id))
+        .collect())
+}
+
+/// Whether the RFC `rfc_id` collides with any other RFC.
+pub async fn exist(rfc_id: Uuid, conn: &mut PgConnection) -> Result<bool, crate::Error> {
+    Ok(!find(rfc_id, conn).await?.is_empty())
+}
+
+/// Load the collisions of the RFC `rfc_id` with other RFCs, one per pair of colliding
+/// Configuration Items (see [RFCConflict]).
+pub async fn load_all(rfc_id: Uuid, pool: &DbPool) -> Result<Vec<RFCConflict>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    // Fails if the RFC doesn't exist.
+    super::load(rfc_id, &mut *tx).await?;
+
+    let pairs = find(rfc_id, &mut tx).await?;
+    let ids: Vec<Uuid> = pairs.iter().map(|(id, _, _)| *id).collect();
+    let rfcs: HashMap<Uuid, RFC> = sqlx::query_as!(
+        RFC,
+        "
+        SELECT id, title, type as \"type: RFCType\", status as \"status: RFCStatus\", created_at,
+            finished_at, requester_id, description, planned_start_at, planned_end_at,
+            actual_start_at, actual_end_at,
+            implementation_plan, backout_plan, test_plan, risk_score,
+            risk_category as \"risk_category: RiskCategory\"
+        FROM rfcs
+        WHERE id = ANY($1)",
+        &ids
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|rfc| (rfc.id, rfc))
+    .collect();
+
+    tx.commit().await?;
+
+    let conflicts = pairs
+        .into_iter()
+        .filter_map(|(id, ci_id, conflicting_ci_id)| {
+            Some(RFCConflict {
+                rfc: rfcs.get(&id)?.clone(),
+                ci_id,
+                conflicting_ci_id,
+            })
+        })
+        .collect();
+
+    Ok(conflicts)
+}
@@ -0,0 +1,191 @@
+use super::RFCStatus;
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Override of the freeze periods for an RFC, which lets it be scheduled during them.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RFCFreezeOverride {
+    pub id: Uuid,
+    pub rfc_id: Uuid,
+    /// User who last granted the override.
+    pub approver_id: Uuid,
+    #[schema(example = "The payment provider retires the old API before the freeze ends.")]
+    pub justification: String,
+    pub granted_at: DateTime<Utc>,
+}
+
+/// Payload for granting an override of the freeze periods to an RFC.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RFCFreezeOverrideUpdateset {
+    #[schema(example = "The payment provider retires the old API before the freeze ends.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub justification: String,
+}
+
+async fn check_valid_rfc(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM rfcs WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Load the override of the freeze periods of an RFC. Fails with
+/// [crate::Error::NoRecordFound] if the RFC doesn't exist or has no override.
+pub async fn load(rfc_id: Uuid, pool: &DbPool) -> Result<RFCFreezeOverride, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_rfc(rfc_id, &mut *tx).await?;
+    let freeze_override = sqlx::query_as!(
+        RFCFreezeOverride,
+        "
+        SELECT id, rfc_id, approver_id, justification, granted_at
+        FROM rfc_freeze_overrides
+        WHERE rfc_id = $1",
+        rfc_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    tx.commit().await?;
+    Ok(freeze_override)
+}
+
+/// Grant an override of the freeze periods to an RFC on behalf of `approver`, replacing the
+/// current one.
+///
+/// Fails with [crate::Error::NotAllowed] if the RFC is a draft, or if `approver` isn't a
+/// user.
+pub async fn update(
+    rfc_id: Uuid,
+    approver: &str,
+    updateset: RFCFreezeOverrideUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RFCFreezeOverride, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let status = sqlx::query_scalar!(
+        "
+        SELECT status as \"status: RFCStatus\"
+        FROM rfcs
+        WHERE id = $1
+        FOR UPDATE",
+        rfc_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if status == RFCStatus::Draft {
+        return Err(crate::Error::NotAllowed(
+            "Drafts can't override freeze periods",
+        ));
+    }
+
+    let approver_id = sqlx::query_scalar!(
+        "
+        SELECT id
+        FROM users
+        WHERE username = $1",
+        approver
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NotAllowed(
+        "Only users can override freeze periods",
+    ))?;
+
+    let freeze_override = sqlx::query_as!(
+        RFCFreezeOverride,
+        "
+        INSERT INTO rfc_freeze_overrides (rfc_id, approver_id, justification)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (rfc_id) DO UPDATE
+        SET approver_id = EXCLUDED.approver_id, justification = EXCLUDED.justification,
+            granted_at = now()
+        RETURNING id, rfc_id, approver_id, justification, granted_at",
+        rfc_id,
+        approver_id,
+        updateset.justification,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(freeze_override)
+}
+
+/// Withdraw the override of the freeze periods of an RFC. RFCs scheduled already stay
+/// scheduled until they're rescheduled.
+pub async fn delete(
+    rfc_id: Uuid,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let mut tx = executor.begin().await?;
+    check_valid_rfc(rfc_id, &mut *tx).await?;
+    let result = sqlx::query!(
+        "
+        DELETE FROM rfc_freeze_overrides
+        WHERE rfc_id = $1",
+        rfc_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Whether the RFC `rfc_id` has an override of the freeze periods.
+pub async fn exists(rfc_id: Uuid, conn: &mut PgConnection) -> Result<bool, crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM rfc_freeze_overrides WHERE rfc_id = $1)",
+        rfc_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(exists.unwrap_or(false))
+}
+
+/// Discard the override of the freeze periods of an RFC, as it goes back to draft.
+pub async fn discard(
+    rfc_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        DELETE FROM rfc_freeze_overrides
+        WHERE rfc_id = $1",
+        rfc_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
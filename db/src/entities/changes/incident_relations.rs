@@ -1,23 +1,49 @@
 use crate::DbPool;
 use serde::Deserialize;
 use serde::Serialize;
-use sqlx::Postgres;
+use sqlx::{PgConnection, Postgres, Type};
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
+/// How an RFC relates to an Incident.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "rfc_incident_relation_type", rename_all = "snake_case")]
+#[schema(example = "caused")]
+pub enum RFCIncidentRelationType {
+    #[default]
+    Related,
+    /// Suggested as caused by the change, as it was opened on an affected Configuration Item
+    /// right after the implementation (see [suggest_causes]). Confirm it by making it
+    /// `caused`, or dismiss it by making it `related`.
+    SuspectedCause,
+    /// Caused by the change.
+    Caused,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 #[cfg_attr(any(feature = "test-helpers"), derive(Deserialize, PartialEq))]
 pub struct RFCIncidentRelation {
     pub id: Uuid,
     pub rfc_id: Uuid,
     pub incident_id: Uuid,
+    pub relation_type: RFCIncidentRelationType,
 }
 
 #[derive(Clone, Deserialize, ToSchema, Validate)]
 #[cfg_attr(any(feature = "test-helpers"), derive(Serialize))]
 pub struct RFCIncidentCreateset {
     pub incident_id: Uuid,
+    /// Defaults to `related`.
+    pub relation_type: Option<RFCIncidentRelationType>,
+}
+
+/// Payload for updating the relation of an RFC to an Incident.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers"), derive(Serialize))]
+pub struct RFCIncidentUpdateset {
+    pub relation_type: RFCIncidentRelationType,
 }
 
 async fn check_valid_rfc(
@@ -48,7 +74,8 @@ pub async fn load_all(
     let relations = sqlx::query_as!(
         RFCIncidentRelation,
         "
-        SELECT id, rfc_id, incident_id
+        SELECT id, rfc_id, incident_id,
+            relation_type as \"relation_type: RFCIncidentRelationType\"
         FROM rfc_incident_relations
         WHERE rfc_id = $1",
         rfc_id
@@ -63,19 +90,21 @@ pub async fn load_all(
 pub async fn create(
     rfc_id: Uuid,
     createset: RFCIncidentCreateset,
-    pool: &DbPool,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<RFCIncidentRelation, crate::Error> {
     createset.validate()?;
-    let mut tx = pool.begin().await?;
+    let mut tx = executor.begin().await?;
     check_valid_rfc(rfc_id, &mut *tx).await?;
     let created_relation = sqlx::query_as!(
         RFCIncidentRelation,
         "
-        INSERT INTO rfc_incident_relations (rfc_id, incident_id)
-        VALUES ($1, $2)
-        RETURNING id, rfc_id, incident_id",
+        INSERT INTO rfc_incident_relations (rfc_id, incident_id, relation_type)
+        VALUES ($1, $2, $3)
+        RETURNING id, rfc_id, incident_id,
+            relation_type as \"relation_type: RFCIncidentRelationType\"",
         rfc_id,
         createset.incident_id,
+        createset.relation_type.unwrap_or_default() as RFCIncidentRelationType,
     )
     .fetch_one(&mut *tx)
     .await
@@ -90,6 +119,32 @@ pub async fn create(
     Ok(created_relation)
 }
 
+pub async fn update(
+    rfc_id: Uuid,
+    id: Uuid,
+    updateset: RFCIncidentUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<RFCIncidentRelation, crate::Error> {
+    updateset.validate()?;
+
+    sqlx::query_as!(
+        RFCIncidentRelation,
+        "
+        UPDATE rfc_incident_relations
+        SET relation_type = $1
+        WHERE rfc_id = $2
+        AND id = $3
+        RETURNING id, rfc_id, incident_id,
+            relation_type as \"relation_type: RFCIncidentRelationType\"",
+        updateset.relation_type as RFCIncidentRelationType,
+        rfc_id,
+        id,
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
 pub async fn delete(
     rfc_id: Uuid,
     id: Uuid,
@@ -112,3 +167,44 @@ pub async fn delete(
         None => Err(crate::Error::NoRecordFound),
     }
 }
+
+/// Relate Incidents to the implemented RFCs that may have caused them, as
+/// [RFCIncidentRelationType::SuspectedCause]. Only the RFC `rfc_id` or the Incident
+/// `incident_id` are considered, if given.
+///
+/// Incidents are suspected when they're on a Configuration Item affected by an RFC that's
+/// `implemented` or `reviewed`, and were opened between the actual start of its
+/// implementation and the review window of the CAB after its actual end. RFCs without an
+/// actual end aren't considered, and neither are Incidents related to the RFC already.
+pub async fn suggest_causes(
+    rfc_id: Option<Uuid>,
+    incident_id: Option<Uuid>,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        INSERT INTO rfc_incident_relations (rfc_id, incident_id, relation_type)
+        SELECT DISTINCT r.id, i.id, 'suspected_cause'::rfc_incident_relation_type
+        FROM rfcs AS r
+        JOIN rfc_ci_relations AS rc ON rc.rfc_id = r.id
+        JOIN incidents_ci_relations AS ic ON ic.ci_id = rc.ci_id
+        JOIN incidents AS i ON i.id = ic.incident_id
+        CROSS JOIN change_advisory_board AS b
+        WHERE r.status IN ('implemented', 'reviewed')
+        AND i.created_at >= COALESCE(r.actual_start_at, r.actual_end_at)
+        AND i.created_at < r.actual_end_at + make_interval(hours => b.review_window_hours)
+        AND ($1::uuid IS NULL OR r.id = $1)
+        AND ($2::uuid IS NULL OR i.id = $2)
+        AND NOT EXISTS(
+            SELECT 1 FROM rfc_incident_relations AS x
+            WHERE x.rfc_id = r.id
+            AND x.incident_id = i.id
+        )",
+        rfc_id,
+        incident_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -63,10 +63,10 @@ pub async fn load_all(
 pub async fn create(
     rfc_id: Uuid,
     createset: RFCProblemCreateset,
-    pool: &DbPool,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<RFCProblemRelation, crate::Error> {
     createset.validate()?;
-    let mut tx = pool.begin().await?;
+    let mut tx = executor.begin().await?;
     check_valid_rfc(rfc_id, &mut *tx).await?;
     let created_relation = sqlx::query_as!(
         RFCProblemRelation,
@@ -0,0 +1,228 @@
+use super::RFCStatus;
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// How the implementation of an RFC went.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "rfc_outcome", rename_all = "snake_case")]
+#[schema(example = "successful")]
+pub enum RFCOutcome {
+    Successful,
+    /// The change was undone following its backout plan.
+    RolledBack,
+    /// The change didn't achieve its goal, and wasn't undone.
+    Failed,
+}
+
+/// Post-implementation review of an RFC.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RFCReview {
+    pub id: Uuid,
+    pub rfc_id: Uuid,
+    /// User who last recorded the review.
+    pub reviewer_id: Uuid,
+    pub outcome: RFCOutcome,
+    /// Whether the change caused Incidents. The Incidents are related to the RFC as `caused`
+    /// (see [super::incident_relations]).
+    pub caused_incidents: bool,
+    #[schema(example = "Take the disk images the day before, it took longer than planned.")]
+    pub lessons_learned: String,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+/// Payload for recording the post-implementation review of an RFC.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RFCReviewUpdateset {
+    pub outcome: RFCOutcome,
+    /// Must be true if some Incident is related to the RFC as `caused`.
+    pub caused_incidents: bool,
+    #[schema(example = "Take the disk images the day before, it took longer than planned.")]
+    #[validate(length(max = 4096))]
+    pub lessons_learned: String,
+}
+
+async fn check_valid_rfc(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM rfcs WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Load the post-implementation review of an RFC. Fails with [crate::Error::NoRecordFound]
+/// if the RFC doesn't exist or wasn't reviewed yet.
+pub async fn load(rfc_id: Uuid, pool: &DbPool) -> Result<RFCReview, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_rfc(rfc_id, &mut *tx).await?;
+    let review = sqlx::query_as!(
+        RFCReview,
+        "
+        SELECT id, rfc_id, reviewer_id, outcome as \"outcome: RFCOutcome\", caused_incidents,
+            lessons_learned, reviewed_at
+        FROM rfc_reviews
+        WHERE rfc_id = $1",
+        rfc_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    tx.commit().await?;
+    Ok(review)
+}
+
+/// Record the post-implementation review of an RFC by `reviewer`, replacing the current one.
+///
+/// Fails with [crate::Error::NotAllowed] unless the RFC is `implemented` or `reviewed`, or
+/// if `reviewer` isn't a user, and with [crate::Error::ValidationError] if it denies that the
+/// change caused Incidents related to it as `caused`.
+pub async fn update(
+    rfc_id: Uuid,
+    reviewer: &str,
+    updateset: RFCReviewUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RFCReview, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let status = sqlx::query_scalar!(
+        "
+        SELECT status as \"status: RFCStatus\"
+        FROM rfcs
+        WHERE id = $1
+        FOR UPDATE",
+        rfc_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if !matches!(status, RFCStatus::Implemented | RFCStatus::Reviewed) {
+        return Err(crate::Error::NotAllowed(
+            "Only implemented RFCs can be reviewed",
+        ));
+    }
+
+    let reviewer_id = sqlx::query_scalar!(
+        "
+        SELECT id
+        FROM users
+        WHERE username = $1",
+        reviewer
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NotAllowed("Only users can review RFCs"))?;
+
+    if !updateset.caused_incidents && has_caused_incidents(rfc_id, &mut tx).await? {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add(
+            "caused_incidents",
+            ValidationError::new("Some Incident is related to the RFC as caused by it"),
+        );
+        return Err(errors.into());
+    }
+
+    let review = sqlx::query_as!(
+        RFCReview,
+        "
+        INSERT INTO rfc_reviews (rfc_id, reviewer_id, outcome, caused_incidents, lessons_learned)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (rfc_id) DO UPDATE
+        SET reviewer_id = EXCLUDED.reviewer_id, outcome = EXCLUDED.outcome,
+            caused_incidents = EXCLUDED.caused_incidents,
+            lessons_learned = EXCLUDED.lessons_learned, reviewed_at = now()
+        RETURNING id, rfc_id, reviewer_id, outcome as \"outcome: RFCOutcome\", caused_incidents,
+            lessons_learned, reviewed_at",
+        rfc_id,
+        reviewer_id,
+        updateset.outcome as RFCOutcome,
+        updateset.caused_incidents,
+        updateset.lessons_learned,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(review)
+}
+
+/// Whether some Incident is related to the RFC `rfc_id` as `caused`.
+async fn has_caused_incidents(rfc_id: Uuid, conn: &mut PgConnection) -> Result<bool, crate::Error> {
+    let caused = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(
+            SELECT 1 FROM rfc_incident_relations
+            WHERE rfc_id = $1
+            AND relation_type = 'caused'
+        )",
+        rfc_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(caused.unwrap_or(false))
+}
+
+/// Check that an RFC is ready to be `reviewed`: it has a post-implementation review that
+/// agrees with the Incidents related to it as `caused`, and every Incident suggested as
+/// caused by it was confirmed or dismissed.
+pub async fn check_ready(rfc_id: Uuid, conn: &mut PgConnection) -> Result<(), crate::Error> {
+    let rfc = sqlx::query!(
+        "
+        SELECT
+            (SELECT caused_incidents FROM rfc_reviews WHERE rfc_id = $1) AS caused_incidents,
+            EXISTS(
+                SELECT 1 FROM rfc_incident_relations
+                WHERE rfc_id = $1
+                AND relation_type = 'suspected_cause'
+            ) AS \"suspected!\"",
+        rfc_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut errors = validator::ValidationErrors::new();
+    match rfc.caused_incidents {
+        None => errors.add(
+            "review",
+            ValidationError::new("The RFC needs a post-implementation review"),
+        ),
+        Some(false) if has_caused_incidents(rfc_id, conn).await? => errors.add(
+            "review",
+            ValidationError::new("Some Incident is related to the RFC as caused by it"),
+        ),
+        Some(_) => {}
+    }
+    if rfc.suspected {
+        errors.add(
+            "incidents",
+            ValidationError::new("Suggested causes must be confirmed or dismissed"),
+        );
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors.into())
+}
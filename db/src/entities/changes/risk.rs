@@ -0,0 +1,316 @@
+use super::{RFCStatus, RFCType};
+use crate::entities::risk_questionnaire;
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Category of the risk of an RFC, from the share of the highest score of the questionnaire
+/// it gets.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "risk_category", rename_all = "lowercase")]
+#[schema(example = "medium")]
+pub enum RiskCategory {
+    /// Below a third of the highest score. Normal changes need a single approval.
+    Low,
+    /// Below two thirds of the highest score. Normal changes need the quorum of the CAB.
+    Medium,
+    /// Normal changes need the approval of every CAB member, and at least the quorum.
+    High,
+}
+
+impl RiskCategory {
+    /// Category of a `score` out of `max_score`.
+    pub fn of(score: i32, max_score: i32) -> Self {
+        if 3 * score < max_score {
+            Self::Low
+        } else if 3 * score < 2 * max_score {
+            Self::Medium
+        } else {
+            Self::High
+        }
+    }
+}
+
+/// Answer given in the risk assessment of an RFC, as the questionnaire was at the time.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RFCRiskResponse {
+    #[schema(example = "How many users does the change affect?")]
+    pub question: String,
+    #[schema(example = 3)]
+    pub weight: i32,
+    #[schema(example = "The whole organisation")]
+    pub answer: String,
+    /// Score of the answer, before the weight of the question.
+    #[schema(example = 4)]
+    pub score: i32,
+}
+
+/// Risk assessment of an RFC.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RFCRiskAssessment {
+    /// Sum of the weighted scores of the answers. Null until the RFC is assessed.
+    #[schema(example = 14)]
+    pub score: Option<i32>,
+    pub category: Option<RiskCategory>,
+    /// Answers, in the order of the questionnaire.
+    pub responses: Vec<RFCRiskResponse>,
+}
+
+/// Answer to a question of the risk questionnaire.
+#[derive(Clone, Deserialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RFCRiskChoice {
+    pub question_id: Uuid,
+    pub answer_id: Uuid,
+}
+
+/// Payload for assessing the risk of an RFC.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RFCRiskAssessmentUpdateset {
+    /// One answer per question of the current questionnaire.
+    pub answers: Vec<RFCRiskChoice>,
+}
+
+async fn check_valid_rfc(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM rfcs WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+async fn load_assessment(
+    rfc_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<RFCRiskAssessment, crate::Error> {
+    let rfc = sqlx::query!(
+        "
+        SELECT risk_score, risk_category as \"risk_category: RiskCategory\"
+        FROM rfcs
+        WHERE id = $1",
+        rfc_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let responses = sqlx::query_as!(
+        RFCRiskResponse,
+        "
+        SELECT question, weight, answer, score
+        FROM rfc_risk_responses
+        WHERE rfc_id = $1
+        ORDER BY position",
+        rfc_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(RFCRiskAssessment {
+        score: rfc.risk_score,
+        category: rfc.risk_category,
+        responses,
+    })
+}
+
+pub async fn load(rfc_id: Uuid, pool: &DbPool) -> Result<RFCRiskAssessment, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_rfc(rfc_id, &mut *tx).await?;
+    let assessment = load_assessment(rfc_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(assessment)
+}
+
+/// Assess the risk of an RFC, replacing its current assessment.
+///
+/// Fails with [crate::Error::NotAllowed] if the CAB decided on the RFC already, and with
+/// [crate::Error::ValidationError] unless every question of the questionnaire is answered
+/// once with one of its answers.
+pub async fn update(
+    rfc_id: Uuid,
+    updateset: RFCRiskAssessmentUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RFCRiskAssessment, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let status = sqlx::query_scalar!(
+        "
+        SELECT status as \"status: RFCStatus\"
+        FROM rfcs
+        WHERE id = $1
+        FOR UPDATE",
+        rfc_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if !matches!(
+        status,
+        RFCStatus::Draft | RFCStatus::Submitted | RFCStatus::Assessed
+    ) {
+        return Err(crate::Error::NotAllowed(
+            "The risk of an RFC can't change after the CAB decides on it",
+        ));
+    }
+
+    let questionnaire = risk_questionnaire::load(&mut *tx).await?;
+    let mut responses = Vec::new();
+    for question in &questionnaire.questions {
+        let mut choices = updateset
+            .answers
+            .iter()
+            .filter(|c| c.question_id == question.id);
+        let answer = match (choices.next(), choices.next()) {
+            (Some(choice), None) => question.answers.iter().find(|a| a.id == choice.answer_id),
+            _ => None,
+        };
+        let Some(answer) = answer else {
+            let mut errors = validator::ValidationErrors::new();
+            errors.add(
+                "answers",
+                ValidationError::new("Every question needs exactly one of its answers"),
+            );
+            return Err(errors.into());
+        };
+        responses.push((question, answer));
+    }
+    if updateset.answers.len() != responses.len() {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add(
+            "answers",
+            ValidationError::new("Answers to unknown questions"),
+        );
+        return Err(errors.into());
+    }
+
+    let score = responses.iter().map(|(q, a)| q.weight * a.score).sum();
+    let max_score = questionnaire.questions.iter().map(|q| q.max_score()).sum();
+
+    sqlx::query!(
+        "
+        DELETE FROM rfc_risk_responses
+        WHERE rfc_id = $1",
+        rfc_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    for (position, (question, answer)) in (1..).zip(&responses) {
+        sqlx::query!(
+            "
+            INSERT INTO rfc_risk_responses (rfc_id, position, question, weight, answer, score)
+            VALUES ($1, $2, $3, $4, $5, $6)",
+            rfc_id,
+            position,
+            question.question,
+            question.weight,
+            answer.answer,
+            answer.score,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!(
+        "
+        UPDATE rfcs
+        SET risk_score = $1, risk_category = $2
+        WHERE id = $3",
+        score,
+        RiskCategory::of(score, max_score) as RiskCategory,
+        rfc_id,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let assessment = load_assessment(rfc_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(assessment)
+}
+
+/// Check that an RFC of type `r#type` is ready to be assessed or decided on: it has an
+/// implementation, backout and test plan, and unless it's a standard change, its risk was
+/// assessed. Standard changes assessed anyway must be low risk.
+pub async fn check_ready(
+    rfc_id: Uuid,
+    r#type: RFCType,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let rfc = sqlx::query!(
+        "
+        SELECT implementation_plan IS NOT NULL AS \"implementation_plan!\",
+            backout_plan IS NOT NULL AS \"backout_plan!\", test_plan IS NOT NULL AS \"test_plan!\",
+            risk_category as \"risk_category: RiskCategory\"
+        FROM rfcs
+        WHERE id = $1",
+        rfc_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut errors = validator::ValidationErrors::new();
+    let plans = [
+        ("implementation_plan", rfc.implementation_plan),
+        ("backout_plan", rfc.backout_plan),
+        ("test_plan", rfc.test_plan),
+    ];
+    for (field, present) in plans {
+        if !present {
+            errors.add(
+                field,
+                ValidationError::new("Required before the RFC is assessed or approved"),
+            );
+        }
+    }
+    match (r#type, rfc.risk_category) {
+        (RFCType::Standard, None | Some(RiskCategory::Low)) => {}
+        (RFCType::Standard, Some(_)) => errors.add(
+            "risk_category",
+            ValidationError::new("Standard changes must be low risk"),
+        ),
+        (_, None) => errors.add(
+            "risk_category",
+            ValidationError::new("The risk of the RFC must be assessed"),
+        ),
+        (_, Some(_)) => {}
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors.into())
+}
+
+#[cfg(test)]
+mod risk_tests {
+    use super::*;
+
+    #[test]
+    fn test_category_of() {
+        assert_eq!(RiskCategory::of(0, 40), RiskCategory::Low);
+        assert_eq!(RiskCategory::of(13, 40), RiskCategory::Low);
+        assert_eq!(RiskCategory::of(14, 40), RiskCategory::Medium);
+        assert_eq!(RiskCategory::of(26, 40), RiskCategory::Medium);
+        assert_eq!(RiskCategory::of(27, 40), RiskCategory::High);
+        assert_eq!(RiskCategory::of(40, 40), RiskCategory::High);
+    }
+}
@@ -1,10 +1,14 @@
 use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams, SortOrder};
+use crate::DbPool;
 use serde::Deserialize;
 use serde::Serialize;
 use sqlx::types::chrono::DateTime;
 use sqlx::types::chrono::Utc;
 use sqlx::Postgres;
+use sqlx::QueryBuilder;
 use sqlx::Type;
+use utoipa::IntoParams;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
@@ -12,9 +16,13 @@ use validator::ValidationError;
 
 /// Tracking of CI changes.
 pub mod changes;
+/// Business impact analysis of Configuration Items.
+pub mod impact;
+/// Relations between Configuration Items.
+pub mod relations;
 
 /// Configuration Item in the database.
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 #[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
 pub struct ConfigItem {
     pub id: Uuid,
@@ -24,8 +32,8 @@ pub struct ConfigItem {
     pub created_at: DateTime<Utc>,
     #[schema(example = "Workstation")]
     pub r#type: Option<String>,
-    #[schema(example = "IT Department")]
-    pub owner: Option<String>,
+    /// Team owning the Configuration Item.
+    pub owner_team_id: Option<Uuid>,
     #[schema(example = "Retro portable computer.")]
     pub description: String,
 }
@@ -42,9 +50,8 @@ pub struct ConfigItemCreateset {
     #[validate(length(max = 1024))]
     #[schema(example = "Workstation")]
     pub r#type: Option<String>,
-    #[validate(length(max = 1024))]
-    #[schema(example = "IT Department")]
-    pub owner: Option<String>,
+    /// Team owning the Configuration Item.
+    pub owner_team_id: Option<Uuid>,
     #[validate(length(max = 1024))]
     #[schema(example = "Retro portable computer.")]
     pub description: String,
@@ -83,14 +90,13 @@ pub struct ConfigItemUpdateset {
         serde(skip_serializing_if = "Option::is_none")
     )]
     pub r#type: Option<Option<String>>,
-    #[schema(example = "IT Department")]
-    #[validate(length(max = 1024))]
+    /// Team owning the Configuration Item.
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub owner: Option<Option<String>>,
+    pub owner_team_id: Option<Option<Uuid>>,
     #[schema(example = "Retro portable computer.")]
     #[validate(length(max = 1024))]
     #[serde(default, with = "::serde_with::rust::double_option")]
@@ -124,13 +130,112 @@ pub enum CIStatus {
     Retired,
 }
 
+/// Query parameters for listing Configuration Items.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ConfigItemListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Column to sort by. Defaults to `created_at`.
+    #[param(inline)]
+    pub sort_by: Option<ConfigItemSortColumn>,
+    /// Sorting direction. Defaults to `asc`.
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    pub status: Option<CIStatus>,
+    #[param(example = "Workstation")]
+    pub r#type: Option<String>,
+    /// Only Configuration Items owned by this team.
+    pub owner_team_id: Option<Uuid>,
+    /// Only Configuration Items created at or after this moment.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only Configuration Items created before this moment.
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+/// Columns Configuration Items can be sorted by.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(example = "name")]
+pub enum ConfigItemSortColumn {
+    #[default]
+    CreatedAt,
+    Name,
+    Status,
+    Type,
+}
+
+impl ConfigItemSortColumn {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::Name => "name",
+            Self::Status => "status",
+            Self::Type => "type",
+        }
+    }
+}
+
+/// Append the `WHERE` clause matching the filters in [ConfigItemListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a ConfigItemListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(status) = params.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(r#type) = &params.r#type {
+        builder.push(" AND type = ").push_bind(r#type);
+    }
+    if let Some(owner_team_id) = params.owner_team_id {
+        builder
+            .push(" AND owner_team_id = ")
+            .push_bind(owner_team_id);
+    }
+    if let Some(created_after) = params.created_after {
+        builder.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = params.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+}
+
+/// Load one page of Configuration Items matching the filters in `params`.
+pub async fn load_page(
+    params: ConfigItemListParams,
+    pool: &DbPool,
+) -> Result<Page<ConfigItem>, crate::Error> {
+    params.validate()?;
+
+    let order_by = format!(
+        "{} {}, id",
+        params.sort_by.unwrap_or_default().as_sql(),
+        params.order.unwrap_or_default().as_sql()
+    );
+
+    let mut tx = pool.begin().await?;
+    let configitems = pagination::load_page::<ConfigItem>(
+        &mut tx,
+        "id, name, status, created_at, type, owner_team_id, description",
+        "configitems",
+        |builder| push_filters(builder, &params),
+        &order_by,
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(configitems)
+}
+
 pub async fn load_all(
     executor: impl sqlx::Executor<'_, Database = Postgres>,
 ) -> Result<Vec<ConfigItem>, crate::Error> {
     let configitems = sqlx::query_as!(
         ConfigItem,
         "
-        SELECT id, name, status as \"status: CIStatus\", created_at, type, owner, description
+        SELECT id, name, status as \"status: CIStatus\", created_at, type, owner_team_id, description
         FROM configitems"
     )
     .fetch_all(executor)
@@ -145,7 +250,7 @@ pub async fn load(
     match sqlx::query_as!(
         ConfigItem,
         "
-        SELECT id, name, status as \"status: CIStatus\", created_at, type, owner, description
+        SELECT id, name, status as \"status: CIStatus\", created_at, type, owner_team_id, description
         FROM configitems
         WHERE id = $1",
         id
@@ -159,6 +264,16 @@ pub async fn load(
     }
 }
 
+/// Map the errors of writing a Configuration Item, which may reference a nonexistent team.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
 pub async fn create(
     configitem: ConfigItemCreateset,
     executor: impl sqlx::Executor<'_, Database = Postgres>,
@@ -168,19 +283,19 @@ pub async fn create(
     let created_ci = sqlx::query_as!(
         ConfigItem,
         "
-        INSERT INTO configitems (name, status, created_at, type, owner, description)
+        INSERT INTO configitems (name, status, created_at, type, owner_team_id, description)
         VALUES ($1, $2, COALESCE($3, now()), $4, $5, $6)
-        RETURNING id, name, status as \"status: CIStatus\", created_at, type, owner, description",
+        RETURNING id, name, status as \"status: CIStatus\", created_at, type, owner_team_id, description",
         configitem.name,
         configitem.status.unwrap_or(CIStatus::Inactive) as CIStatus,
         configitem.created_at,
         configitem.r#type,
-        configitem.owner,
+        configitem.owner_team_id,
         configitem.description,
     )
     .fetch_one(executor)
     .await
-    .map_err(crate::Error::DbError)?;
+    .map_err(map_write_error)?;
 
     Ok(created_ci)
 }
@@ -201,26 +316,26 @@ pub async fn update(
                 WHEN $4 then type
                 ELSE $5
             END,
-            owner = CASE
-                WHEN $6 then owner
+            owner_team_id = CASE
+                WHEN $6 then owner_team_id
                 ELSE $7
             END,
             description = COALESCE($8, description)
         WHERE id = $9
-        RETURNING id, name, status as \"status: CIStatus\", created_at, type, owner, description",
+        RETURNING id, name, status as \"status: CIStatus\", created_at, type, owner_team_id, description",
         configitem.name.unwrap_or(None),
         configitem.status.unwrap_or(None) as Option<CIStatus>,
         configitem.created_at.unwrap_or(None),
         configitem.r#type.is_none(),
         configitem.r#type.unwrap_or(None),
-        configitem.owner.is_none(),
-        configitem.owner.unwrap_or(None),
+        configitem.owner_team_id.is_none(),
+        configitem.owner_team_id.unwrap_or(None),
         configitem.description.unwrap_or(None),
         id,
     )
     .fetch_optional(executor)
     .await
-    .map_err(crate::Error::DbError)?
+    .map_err(map_write_error)?
     {
         Some(updated_ci) => Ok(updated_ci),
         None => Err(crate::Error::NoRecordFound),
@@ -258,7 +373,7 @@ mod config_item_test {
             status: Some(CIStatus::Active),
             created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
             r#type: Some(String::from("Testing")),
-            owner: Some(String::from("Testing Area")),
+            owner_team_id: Some("67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap()),
             description: String::from("This is a testing configuration item."),
         };
 
@@ -268,7 +383,7 @@ mod config_item_test {
         assert!(json.contains("\"status\":\"active\""));
         assert!(json.contains("\"created_at\":\"2023-09-15T12:34:56Z\""));
         assert!(json.contains("\"type\":\"Testing\""));
-        assert!(json.contains("\"owner\":\"Testing Area\""));
+        assert!(json.contains("\"owner_team_id\":\"67e55044-10b1-426f-9247-bb680e5fe0c8\""));
         assert!(json.contains("\"description\":\"This is a testing configuration item.\""));
     }
 
@@ -279,7 +394,7 @@ mod config_item_test {
             status: None,
             created_at: None,
             r#type: None,
-            owner: None,
+            owner_team_id: None,
             description: String::from("This is a testing configuration item."),
         };
 
@@ -289,7 +404,7 @@ mod config_item_test {
         assert!(json.contains("\"status\":null"));
         assert!(json.contains("\"created_at\":null"));
         assert!(json.contains("\"type\":null"));
-        assert!(json.contains("\"owner\":null"));
+        assert!(json.contains("\"owner_team_id\":null"));
         assert!(json.contains("\"description\":\"This is a testing configuration item.\""));
     }
 
@@ -301,7 +416,7 @@ mod config_item_test {
             "status": "maintenance",
             "created_at": "2023-09-15T12:34:56Z",
             "type": "Testing",
-            "owner": "Test Area",
+            "owner_team_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
             "description": "My desc."
         }"#;
 
@@ -314,7 +429,10 @@ mod config_item_test {
             Some("2023-09-15T12:34:56Z".parse().unwrap())
         );
         assert_eq!(set.r#type, Some(String::from("Testing")));
-        assert_eq!(set.owner, Some(String::from("Test Area")));
+        assert_eq!(
+            set.owner_team_id,
+            Some("67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap())
+        );
         assert_eq!(set.description, String::from("My desc."));
     }
 
@@ -335,7 +453,7 @@ mod config_item_test {
         assert_eq!(changeset.status, None);
         assert_eq!(changeset.created_at, None);
         assert_eq!(changeset.r#type, None);
-        assert_eq!(changeset.owner, None);
+        assert_eq!(changeset.owner_team_id, None);
         assert_eq!(changeset.description, String::from(""));
     }
 
@@ -346,7 +464,9 @@ mod config_item_test {
             status: Some(Some(CIStatus::Active)),
             created_at: Some(Some("2023-09-15T12:34:56Z".parse().unwrap())),
             r#type: Some(Some(String::from("Testing"))),
-            owner: Some(Some(String::from("Testing Area"))),
+            owner_team_id: Some(Some(
+                "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap(),
+            )),
             description: Some(Some(String::from("This is a testing configuration item."))),
         };
 
@@ -356,7 +476,7 @@ mod config_item_test {
         assert!(json.contains("\"status\":\"active\""));
         assert!(json.contains("\"created_at\":\"2023-09-15T12:34:56Z\""));
         assert!(json.contains("\"type\":\"Testing\""));
-        assert!(json.contains("\"owner\":\"Testing Area\""));
+        assert!(json.contains("\"owner_team_id\":\"67e55044-10b1-426f-9247-bb680e5fe0c8\""));
         assert!(json.contains("\"description\":\"This is a testing configuration item.\""));
     }
 
@@ -367,7 +487,7 @@ mod config_item_test {
             status: Some(None),
             created_at: Some(None),
             r#type: Some(None),
-            owner: Some(None),
+            owner_team_id: Some(None),
             description: Some(None),
         };
 
@@ -377,7 +497,7 @@ mod config_item_test {
         assert!(json.contains("\"status\":null"));
         assert!(json.contains("\"created_at\":null"));
         assert!(json.contains("\"type\":null"));
-        assert!(json.contains("\"owner\":null"));
+        assert!(json.contains("\"owner_team_id\":null"));
         assert!(json.contains("\"description\":null"));
     }
 
@@ -388,7 +508,7 @@ mod config_item_test {
             status: None,
             created_at: None,
             r#type: None,
-            owner: None,
+            owner_team_id: None,
             description: None,
         };
 
@@ -405,7 +525,7 @@ mod config_item_test {
             "status": "maintenance",
             "created_at": "2023-09-15T12:34:56Z",
             "type": "Testing",
-            "owner": "Test Area",
+            "owner_team_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
             "description": "My desc."
         }"#;
 
@@ -418,7 +538,12 @@ mod config_item_test {
             Some(Some("2023-09-15T12:34:56Z".parse().unwrap()))
         );
         assert_eq!(set.r#type, Some(Some(String::from("Testing"))));
-        assert_eq!(set.owner, Some(Some(String::from("Test Area"))));
+        assert_eq!(
+            set.owner_team_id,
+            Some(Some(
+                "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap()
+            ))
+        );
         assert_eq!(set.description, Some(Some(String::from("My desc."))));
     }
 
@@ -430,7 +555,7 @@ mod config_item_test {
             "status": null,
             "created_at": null,
             "type": null,
-            "owner": null,
+            "owner_team_id": null,
             "description": null
         }"#;
 
@@ -440,7 +565,7 @@ mod config_item_test {
         assert_eq!(set.status, Some(None));
         assert_eq!(set.created_at, Some(None));
         assert_eq!(set.r#type, Some(None));
-        assert_eq!(set.owner, Some(None));
+        assert_eq!(set.owner_team_id, Some(None));
         assert_eq!(set.description, Some(None));
     }
 
@@ -454,7 +579,7 @@ mod config_item_test {
         assert_eq!(set.status, None);
         assert_eq!(set.created_at, None);
         assert_eq!(set.r#type, None);
-        assert_eq!(set.owner, None);
+        assert_eq!(set.owner_team_id, None);
         assert_eq!(set.description, None);
     }
 
@@ -469,7 +594,7 @@ mod config_item_test {
             id: uuid.clone(),
             created_at: datetime.clone(),
             r#type: Some(String::from("x")),
-            owner: Some(String::from("x")),
+            owner_team_id: Some(uuid),
             description: String::from("x"),
         };
         let ci_2 = ConfigItem {
@@ -478,7 +603,7 @@ mod config_item_test {
             id: uuid.clone(),
             created_at: datetime.clone(),
             r#type: Some(String::from("x")),
-            owner: Some(String::from("x")),
+            owner_team_id: Some(uuid),
             description: String::from("x"),
         };
 
@@ -1,9 +1,11 @@
+use crate::entities::changes::RFC;
 use crate::{entity_helpers, DbPool};
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde::Serialize;
+use sqlx::PgConnection;
 use sqlx::Postgres;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
 
@@ -15,6 +17,10 @@ pub struct CIChange {
     pub implementation_timedate: DateTime<Utc>,
     #[schema(example = "docs.local/changes/ci001/987.pdf")]
     pub documentation: String,
+    /// RFC that authorized the change.
+    pub rfc_id: Option<Uuid>,
+    /// Whether the change was made without an RFC.
+    pub unauthorized: bool,
 }
 
 /// Payload for creating a change record.
@@ -25,6 +31,9 @@ pub struct CIChangeCreateset {
     #[validate(length(max = 1024))]
     #[schema(example = "docs.local/changes/ci001/987.pdf")]
     pub documentation: String,
+    /// RFC that authorized the change. It must be approved and affect the Configuration Item.
+    #[serde(default)]
+    pub rfc_id: Option<Uuid>,
 }
 
 /// Payload for updating a change record.
@@ -46,6 +55,13 @@ pub struct CIChangeUpdateset {
         serde(skip_serializing_if = "Option::is_none")
     )]
     pub documentation: Option<Option<String>>,
+    /// RFC that authorized the change. It must be approved and affect the Configuration Item.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers"),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub rfc_id: Option<Option<Uuid>>,
 }
 
 /// Validate that required fields of [CIChangeUpdateset] aren't explicitly null.
@@ -76,17 +92,67 @@ async fn check_valid_ci(
     Ok(())
 }
 
-pub async fn load_all(ci_id: Uuid, pool: &DbPool) -> Result<Vec<CIChange>, crate::Error> {
+/// Check that the RFC `rfc_id` can authorize changes to the configuration item `ci_id`,
+/// that is, that it was approved and affects the item.
+async fn check_authorizing_rfc(
+    rfc_id: Uuid,
+    ci_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let authorizes = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(
+            SELECT 1 FROM rfcs AS r
+            JOIN rfc_ci_relations AS rc ON rc.rfc_id = r.id
+            WHERE r.id = $1
+            AND rc.ci_id = $2
+            AND r.status IN ('approved', 'scheduled', 'implemented', 'reviewed')
+        )",
+        rfc_id,
+        ci_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !authorizes.unwrap_or(false) {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add(
+            "rfc_id",
+            ValidationError::new("The RFC must be approved and affect the Configuration Item"),
+        );
+        return Err(errors.into());
+    }
+
+    Ok(())
+}
+
+/// Query parameters for listing the changes of a configuration item.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers"), derive(Serialize))]
+pub struct CIChangeListParams {
+    /// Only changes made without (`true`) or with (`false`) an RFC.
+    pub unauthorized: Option<bool>,
+}
+
+pub async fn load_all(
+    ci_id: Uuid,
+    params: CIChangeListParams,
+    pool: &DbPool,
+) -> Result<Vec<CIChange>, crate::Error> {
     let mut tx = pool.begin().await?;
     check_valid_ci(ci_id, &mut *tx).await?;
     let changes = sqlx::query_as!(
         CIChange,
         "
-        SELECT id, ci_id, implementation_timedate, documentation
+        SELECT id, ci_id, implementation_timedate, documentation, rfc_id,
+            rfc_id IS NULL AS \"unauthorized!\"
         FROM ci_changes
         WHERE ci_id = $1
+        AND ($2::boolean IS NULL OR (rfc_id IS NULL) = $2)
         ORDER BY implementation_timedate DESC",
-        ci_id
+        ci_id,
+        params.unauthorized,
     )
     .fetch_all(&mut *tx)
     .await?;
@@ -103,7 +169,8 @@ pub async fn load(
     match sqlx::query_as!(
         CIChange,
         "
-        SELECT id, ci_id, implementation_timedate, documentation
+        SELECT id, ci_id, implementation_timedate, documentation, rfc_id,
+            rfc_id IS NULL AS \"unauthorized!\"
         FROM ci_changes
         WHERE id = $1
         AND ci_id = $2",
@@ -122,20 +189,25 @@ pub async fn load(
 pub async fn create(
     ci_id: Uuid,
     createset: CIChangeCreateset,
-    pool: &DbPool,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<CIChange, crate::Error> {
     createset.validate()?;
-    let mut tx = pool.begin().await?;
+    let mut tx = executor.begin().await?;
     check_valid_ci(ci_id, &mut *tx).await?;
+    if let Some(rfc_id) = createset.rfc_id {
+        check_authorizing_rfc(rfc_id, ci_id, &mut tx).await?;
+    }
     let created_change = sqlx::query_as!(
         CIChange,
         "
-        INSERT INTO ci_changes (ci_id, implementation_timedate, documentation)
-        VALUES ($1, $2, $3)
-        RETURNING id, ci_id, implementation_timedate, documentation",
+        INSERT INTO ci_changes (ci_id, implementation_timedate, documentation, rfc_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, ci_id, implementation_timedate, documentation, rfc_id,
+            rfc_id IS NULL AS \"unauthorized!\"",
         ci_id,
         createset.implementation_timedate,
         createset.documentation,
+        createset.rfc_id,
     )
     .fetch_one(&mut *tx)
     .await
@@ -149,31 +221,43 @@ pub async fn update(
     id: Uuid,
     ci_id: Uuid,
     updateset: CIChangeUpdateset,
-    executor: impl sqlx::Executor<'_, Database = Postgres>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<CIChange, crate::Error> {
     updateset.validate()?;
 
-    match sqlx::query_as!(
+    let mut tx = executor.begin().await?;
+    if let Some(Some(rfc_id)) = updateset.rfc_id {
+        check_authorizing_rfc(rfc_id, ci_id, &mut tx).await?;
+    }
+
+    let updated_change = sqlx::query_as!(
         CIChange,
         "
         UPDATE ci_changes
         SET implementation_timedate = COALESCE($1, implementation_timedate),
-            documentation = COALESCE($2, documentation)
-        WHERE id = $3
-        AND ci_id = $4
-        RETURNING id, ci_id, implementation_timedate, documentation",
+            documentation = COALESCE($2, documentation),
+            rfc_id = CASE
+                WHEN $3 THEN rfc_id
+                ELSE $4
+            END
+        WHERE id = $5
+        AND ci_id = $6
+        RETURNING id, ci_id, implementation_timedate, documentation, rfc_id,
+            rfc_id IS NULL AS \"unauthorized!\"",
         updateset.implementation_timedate.unwrap_or(None),
         updateset.documentation.unwrap_or(None),
+        updateset.rfc_id.is_none(),
+        updateset.rfc_id.unwrap_or(None),
         id,
         ci_id,
     )
-    .fetch_optional(executor)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(crate::Error::DbError)?
-    {
-        Some(updated_change) => Ok(updated_change),
-        None => Err(crate::Error::NoRecordFound),
-    }
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    tx.commit().await?;
+    Ok(updated_change)
 }
 
 pub async fn delete(
@@ -198,3 +282,44 @@ pub async fn delete(
         None => Err(crate::Error::NoRecordFound),
     }
 }
+
+/// Load the changes authorized by the RFC `rfc_id`, latest first.
+pub async fn load_all_for_rfc(rfc_id: Uuid, pool: &DbPool) -> Result<Vec<CIChange>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    // Fails if the RFC doesn't exist.
+    crate::entities::changes::load(rfc_id, &mut *tx).await?;
+    let changes = sqlx::query_as!(
+        CIChange,
+        "
+        SELECT id, ci_id, implementation_timedate, documentation, rfc_id,
+            rfc_id IS NULL AS \"unauthorized!\"
+        FROM ci_changes
+        WHERE rfc_id = $1
+        ORDER BY implementation_timedate DESC, ci_id",
+        rfc_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(changes)
+}
+
+/// Record a change to every configuration item affected by the implemented `rfc`, made at
+/// the end of its implementation or now if it wasn't recorded.
+pub async fn create_for_rfc(rfc: &RFC, conn: &mut PgConnection) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        INSERT INTO ci_changes (ci_id, implementation_timedate, documentation, rfc_id)
+        SELECT ci_id, COALESCE($2, now()), $3, rfc_id
+        FROM rfc_ci_relations
+        WHERE rfc_id = $1",
+        rfc.id,
+        rfc.actual_end_at,
+        rfc.title,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
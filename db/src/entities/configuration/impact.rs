@@ -0,0 +1,212 @@
+use super::changes::CIChange;
+use super::relations::{self, CIGraphDirection, CIGraphNode, CIGraphParams};
+use crate::entities::changes::{risk::RiskCategory, RFCStatus, RFCType, RFC};
+use crate::entities::incidents::{
+    Incident, IncidentImpact, IncidentPrio, IncidentResolutionCode, IncidentStatus, IncidentUrgency,
+};
+use crate::entities::priority_matrix;
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Type of the Configuration Items that count as services, compared case-insensitively.
+pub const SERVICE_TYPE: &str = "service";
+
+/// Query parameters for analysing the impact of a Configuration Item.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CIImpactParams {
+    /// Max amount of relations between the Configuration Item and the affected ones, from 1
+    /// to 10. Defaults to 3.
+    pub depth: Option<i32>,
+}
+
+/// What's affected when a Configuration Item degrades.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "test-helpers", derive(Deserialize))]
+pub struct CIImpact {
+    pub root_id: Uuid,
+    /// Configuration Items depending on the analysed one, directly or not, it included,
+    /// nearest first.
+    pub affected_items: Vec<CIGraphNode>,
+    /// How many of the affected Configuration Items are services.
+    pub affected_services: usize,
+    /// Unresolved Incidents related to any of the affected Configuration Items.
+    pub open_incidents: Vec<Incident>,
+    /// RFCs still to be implemented that are related to Incidents on the affected
+    /// Configuration Items.
+    pub open_rfcs: Vec<RFC>,
+    /// Changes to the affected Configuration Items planned for the future.
+    pub scheduled_changes: Vec<CIChange>,
+    /// Impact to give Incidents on the analysed Configuration Item. Always one in use by
+    /// the priority matrix.
+    pub suggested_impact: IncidentImpact,
+}
+
+/// Impact suggested for `items` affected Configuration Items, `services` of them services,
+/// before taking the priority matrix into account.
+fn raw_impact(items: usize, services: usize) -> IncidentImpact {
+    match (items, services) {
+        (_, 3..) | (25.., _) => IncidentImpact::Critical,
+        (_, 2) | (10.., _) => IncidentImpact::High,
+        (_, 1) | (4.., _) => IncidentImpact::Medium,
+        (2.., _) => IncidentImpact::Low,
+        _ => IncidentImpact::Minimal,
+    }
+}
+
+/// Impact suggested for `items` affected Configuration Items, `services` of them services.
+///
+/// If the priority matrix doesn't use the raw suggestion, it's lowered to the highest impact
+/// in use below it, or raised to the lowest one in use if there's none.
+fn suggest_impact(items: usize, services: usize, in_use: &[IncidentImpact]) -> IncidentImpact {
+    let raw = raw_impact(items, services);
+    // Impacts derive no ordering, but are declared highest first.
+    let rank = |impact: &IncidentImpact| *impact as u8;
+
+    in_use
+        .iter()
+        .filter(|impact| rank(impact) >= rank(&raw))
+        .min_by_key(|impact| rank(impact))
+        .or_else(|| in_use.iter().max_by_key(|impact| rank(impact)))
+        .copied()
+        .unwrap_or(raw)
+}
+
+/// Analyse what's affected when the Configuration Item `ci_id` degrades, i.e. the items
+/// depending on it up to the depth in `params`, and the open work on them.
+pub async fn analyse(
+    ci_id: Uuid,
+    params: CIImpactParams,
+    pool: &DbPool,
+) -> Result<CIImpact, crate::Error> {
+    let graph = relations::walk(
+        ci_id,
+        CIGraphParams {
+            direction: Some(CIGraphDirection::Downstream),
+            depth: params.depth,
+        },
+        pool,
+    )
+    .await?;
+
+    let ids: Vec<Uuid> = graph.nodes.iter().map(|n| n.config_item.id).collect();
+    let affected_services = graph
+        .nodes
+        .iter()
+        .filter(|n| {
+            n.config_item
+                .r#type
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(SERVICE_TYPE))
+        })
+        .count();
+
+    let mut tx = pool.begin().await?;
+    let open_incidents = sqlx::query_as!(
+        Incident,
+        "
+        SELECT i.id, i.title, i.status as \"status: IncidentStatus\", i.created_at, i.resolved_at,
+            i.impact as \"impact: IncidentImpact\", i.urgency as \"urgency: IncidentUrgency\",
+            i.priority as \"priority: IncidentPrio\", i.assignment_group_id, i.assignee_id,
+            i.description, i.hold_reason,
+            i.resolution_code as \"resolution_code: IncidentResolutionCode\", i.resolution_notes,
+            i.sla_policy_id, i.responded_at, i.response_due_at, i.resolution_due_at, i.major,
+            i.parent_id, i.duplicate_of_id
+        FROM incidents AS i
+        WHERE i.status NOT IN ('resolved', 'closed')
+        AND EXISTS(
+            SELECT 1 FROM incidents_ci_relations AS r
+            WHERE r.incident_id = i.id
+            AND r.ci_id = ANY($1)
+        )
+        ORDER BY i.created_at, i.id",
+        &ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let open_rfcs = sqlx::query_as!(
+        RFC,
+        "
+        SELECT c.id, c.title, c.type as \"type: RFCType\", c.status as \"status: RFCStatus\",
+            c.created_at, c.finished_at, c.requester_id, c.description, c.planned_start_at,
+            c.planned_end_at, c.actual_start_at, c.actual_end_at, c.implementation_plan,
+            c.backout_plan, c.test_plan, c.risk_score,
+            c.risk_category as \"risk_category: RiskCategory\"
+        FROM rfcs AS c
+        WHERE c.status NOT IN ('rejected', 'implemented', 'reviewed', 'closed')
+        AND EXISTS(
+            SELECT 1 FROM rfc_incident_relations AS ri
+            JOIN incidents_ci_relations AS r ON r.incident_id = ri.incident_id
+            WHERE ri.rfc_id = c.id
+            AND r.ci_id = ANY($1)
+        )
+        ORDER BY c.created_at, c.id",
+        &ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let scheduled_changes = sqlx::query_as!(
+        CIChange,
+        "
+        SELECT id, ci_id, implementation_timedate, documentation, rfc_id,
+            rfc_id IS NULL AS \"unauthorized!\"
+        FROM ci_changes
+        WHERE ci_id = ANY($1)
+        AND implementation_timedate > now()
+        ORDER BY implementation_timedate, id",
+        &ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let matrix = priority_matrix::load(&mut *tx).await?;
+    tx.commit().await?;
+
+    Ok(CIImpact {
+        root_id: ci_id,
+        suggested_impact: suggest_impact(ids.len(), affected_services, &matrix.impacts),
+        affected_items: graph.nodes,
+        affected_services,
+        open_incidents,
+        open_rfcs,
+        scheduled_changes,
+    })
+}
+
+#[cfg(test)]
+mod impact_tests {
+    use super::*;
+    use IncidentImpact as I;
+
+    #[test]
+    fn test_raw_impact() {
+        assert_eq!(raw_impact(1, 0), I::Minimal);
+        assert_eq!(raw_impact(3, 0), I::Low);
+        assert_eq!(raw_impact(4, 0), I::Medium);
+        assert_eq!(raw_impact(2, 1), I::Medium);
+        assert_eq!(raw_impact(2, 2), I::High);
+        assert_eq!(raw_impact(12, 0), I::High);
+        assert_eq!(raw_impact(3, 3), I::Critical);
+        assert_eq!(raw_impact(30, 0), I::Critical);
+    }
+
+    #[test]
+    fn test_suggest_impact_in_use() {
+        let in_use = [I::High, I::Medium, I::Low];
+
+        // Lowered to the highest impact in use.
+        assert_eq!(suggest_impact(30, 0, &in_use), I::High);
+        assert_eq!(suggest_impact(4, 0, &in_use), I::Medium);
+        // Raised to the lowest impact in use.
+        assert_eq!(suggest_impact(1, 0, &in_use), I::Low);
+        // Lowered past unused impacts.
+        assert_eq!(suggest_impact(12, 0, &[I::Critical, I::Low]), I::Low);
+    }
+}
@@ -0,0 +1,421 @@
+use super::{CIStatus, ConfigItem};
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use sqlx::Type;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+/// Kind of a [CIRelation], read as "source <type> target".
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "ci_relation_type", rename_all = "lowercase")]
+#[schema(example = "runson")]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub enum CIRelationType {
+    DependsOn,
+    RunsOn,
+    HostedOn,
+    ConnectsTo,
+}
+
+impl CIRelationType {
+    /// Whether relations of this kind nest Configuration Items into each other, so that they
+    /// can't form cycles.
+    ///
+    /// Keep in sync with the types checked in [check_no_cycle].
+    pub fn is_hierarchical(&self) -> bool {
+        matches!(self, Self::RunsOn | Self::HostedOn)
+    }
+}
+
+/// Directed relation between two Configuration Items.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct CIRelation {
+    pub id: Uuid,
+    pub source_id: Uuid,
+    pub target_id: Uuid,
+    pub r#type: CIRelationType,
+    #[schema(example = "Production VM of the billing service.")]
+    pub description: String,
+}
+
+/// Payload for relating a Configuration Item (the source) to another one.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CIRelationCreateset {
+    pub target_id: Uuid,
+    pub r#type: CIRelationType,
+    #[validate(length(max = 1024))]
+    #[schema(example = "Production VM of the billing service.")]
+    pub description: Option<String>,
+}
+
+/// Payload for updating a relation between Configuration Items.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CIRelationUpdateset {
+    pub r#type: Option<CIRelationType>,
+    #[validate(length(max = 1024))]
+    #[schema(example = "Production VM of the billing service.")]
+    pub description: Option<String>,
+}
+
+/// Which way to walk the relations of a Configuration Item.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[schema(example = "downstream")]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub enum CIGraphDirection {
+    /// Configuration Items that depend on this one, i.e. the sources of its relations.
+    #[default]
+    Downstream,
+    /// Configuration Items this one depends on, i.e. the targets of its relations.
+    Upstream,
+}
+
+/// Query parameters for walking the relations of a Configuration Item.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CIGraphParams {
+    /// Defaults to `downstream`.
+    #[param(inline)]
+    pub direction: Option<CIGraphDirection>,
+    /// Max amount of relations between the Configuration Item and the ones returned. Defaults
+    /// to 3.
+    #[validate(range(min = 1, max = 10))]
+    pub depth: Option<i32>,
+}
+
+/// Configuration Item reached while walking the relations of another one.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct CIGraphNode {
+    /// Least amount of relations between the Configuration Item walked from and this one.
+    pub depth: i32,
+    pub config_item: ConfigItem,
+}
+
+/// Configuration Items reachable from one of them, with the relations that lead to them.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize))]
+pub struct CIGraph {
+    pub root_id: Uuid,
+    pub direction: CIGraphDirection,
+    /// Reached Configuration Items, the one walked from included, nearest first.
+    pub nodes: Vec<CIGraphNode>,
+    pub relations: Vec<CIRelation>,
+}
+
+/// Default depth of [walk].
+const DEFAULT_DEPTH: i32 = 3;
+
+/// Check if a configuration item with the ID sent as path param exists in the database.
+async fn check_valid_ci(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM configitems WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Map the errors of writing a relation, which may reference a nonexistent Configuration
+/// Item, relate one to itself or repeat an existing relation.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_foreign_key_violation()
+                || dbe.is_unique_violation()
+                || dbe.is_check_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Check that a hierarchical relation from `source_id` to `target_id` wouldn't close a
+/// cycle, ignoring the relation `ignored_id` that is being replaced.
+///
+/// Locks the relations against concurrent writes until the end of the transaction, so that
+/// two relations can't close a cycle together.
+async fn check_no_cycle(
+    source_id: Uuid,
+    target_id: Uuid,
+    ignored_id: Option<Uuid>,
+    conn: &mut sqlx::PgConnection,
+) -> Result<(), crate::Error> {
+    sqlx::query!("LOCK TABLE ci_relations IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *conn)
+        .await?;
+
+    let closes_cycle = sqlx::query_scalar!(
+        "
+        WITH RECURSIVE reachable (ci_id) AS (
+            SELECT $1::uuid
+            UNION
+            SELECT r.target_id
+            FROM reachable
+            JOIN ci_relations r ON r.source_id = reachable.ci_id
+            WHERE r.type IN ('runson', 'hostedon')
+            AND r.id IS DISTINCT FROM $3
+        )
+        SELECT EXISTS(SELECT 1 FROM reachable WHERE ci_id = $2)",
+        target_id,
+        source_id,
+        ignored_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if closes_cycle.unwrap_or(false) {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "target_id",
+            ValidationError::new("The relation would close a cycle of hierarchical relations"),
+        );
+        return Err(errors.into());
+    }
+
+    Ok(())
+}
+
+/// Load the relations of a Configuration Item, both the ones it's the source and the target
+/// of.
+pub async fn load_all(ci_id: Uuid, pool: &DbPool) -> Result<Vec<CIRelation>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_ci(ci_id, &mut *tx).await?;
+    let relations = sqlx::query_as!(
+        CIRelation,
+        "
+        SELECT id, source_id, target_id, type as \"type: CIRelationType\", description
+        FROM ci_relations
+        WHERE source_id = $1
+        OR target_id = $1
+        ORDER BY type, id",
+        ci_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(relations)
+}
+
+/// Load a relation of a Configuration Item, whichever side of it the item is on.
+pub async fn load(
+    id: Uuid,
+    ci_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<CIRelation, crate::Error> {
+    match sqlx::query_as!(
+        CIRelation,
+        "
+        SELECT id, source_id, target_id, type as \"type: CIRelationType\", description
+        FROM ci_relations
+        WHERE id = $1
+        AND (source_id = $2 OR target_id = $2)",
+        id,
+        ci_id
+    )
+    .fetch_optional(executor)
+    .await?
+    {
+        Some(relation) => Ok(relation),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Relate the Configuration Item `source_id` to another one.
+pub async fn create(
+    source_id: Uuid,
+    createset: CIRelationCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<CIRelation, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    check_valid_ci(source_id, &mut *tx).await?;
+    if createset.r#type.is_hierarchical() {
+        check_no_cycle(source_id, createset.target_id, None, &mut tx).await?;
+    }
+    let relation = sqlx::query_as!(
+        CIRelation,
+        "
+        INSERT INTO ci_relations (source_id, target_id, type, description)
+        VALUES ($1, $2, $3, COALESCE($4, ''))
+        RETURNING id, source_id, target_id, type as \"type: CIRelationType\", description",
+        source_id,
+        createset.target_id,
+        createset.r#type as CIRelationType,
+        createset.description,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+
+    tx.commit().await?;
+    Ok(relation)
+}
+
+pub async fn update(
+    id: Uuid,
+    ci_id: Uuid,
+    updateset: CIRelationUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<CIRelation, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    let current = load(id, ci_id, &mut *tx).await?;
+    if let Some(r#type) = updateset.r#type {
+        if r#type.is_hierarchical() && !current.r#type.is_hierarchical() {
+            check_no_cycle(current.source_id, current.target_id, Some(id), &mut tx).await?;
+        }
+    }
+    let relation = sqlx::query_as!(
+        CIRelation,
+        "
+        UPDATE ci_relations
+        SET type = COALESCE($1, type), description = COALESCE($2, description)
+        WHERE id = $3
+        RETURNING id, source_id, target_id, type as \"type: CIRelationType\", description",
+        updateset.r#type as Option<CIRelationType>,
+        updateset.description,
+        id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+
+    tx.commit().await?;
+    Ok(relation)
+}
+
+pub async fn delete(
+    id: Uuid,
+    ci_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM ci_relations
+        WHERE id = $1
+        AND (source_id = $2 OR target_id = $2)
+        RETURNING id",
+        id,
+        ci_id,
+    )
+    .fetch_optional(executor)
+    .await?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Walk the relations of a Configuration Item breadth-first, up to the depth in `params`.
+///
+/// Every reachable Configuration Item is returned once, at the least depth it's reached at,
+/// so cycles of non-hierarchical relations are walked safely.
+pub async fn walk(
+    ci_id: Uuid,
+    params: CIGraphParams,
+    pool: &DbPool,
+) -> Result<CIGraph, crate::Error> {
+    params.validate()?;
+    let direction = params.direction.unwrap_or_default();
+    let downstream = matches!(direction, CIGraphDirection::Downstream);
+
+    let mut tx = pool.begin().await?;
+    check_valid_ci(ci_id, &mut *tx).await?;
+
+    let mut depths = HashMap::from([(ci_id, 0)]);
+    let mut frontier = vec![ci_id];
+    let mut relations = Vec::new();
+    for depth in 1..=params.depth.unwrap_or(DEFAULT_DEPTH) {
+        if frontier.is_empty() {
+            break;
+        }
+
+        let step = sqlx::query_as!(
+            CIRelation,
+            "
+            SELECT id, source_id, target_id, type as \"type: CIRelationType\", description
+            FROM ci_relations
+            WHERE CASE WHEN $1 THEN target_id ELSE source_id END = ANY($2)
+            ORDER BY type, id",
+            downstream,
+            &frontier,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        frontier = Vec::new();
+        for relation in step {
+            let next = if downstream {
+                relation.source_id
+            } else {
+                relation.target_id
+            };
+            if let Entry::Vacant(entry) = depths.entry(next) {
+                entry.insert(depth);
+                frontier.push(next);
+            }
+            relations.push(relation);
+        }
+    }
+
+    let ids: Vec<Uuid> = depths.keys().copied().collect();
+    let config_items = sqlx::query_as!(
+        ConfigItem,
+        "
+        SELECT id, name, status as \"status: CIStatus\", created_at, type, owner_team_id, description
+        FROM configitems
+        WHERE id = ANY($1)",
+        &ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let mut nodes: Vec<CIGraphNode> = config_items
+        .into_iter()
+        .map(|config_item| CIGraphNode {
+            depth: depths[&config_item.id],
+            config_item,
+        })
+        .collect();
+    nodes.sort_by(|a, b| {
+        (a.depth, &a.config_item.name, a.config_item.id).cmp(&(
+            b.depth,
+            &b.config_item.name,
+            b.config_item.id,
+        ))
+    });
+
+    Ok(CIGraph {
+        root_id: ci_id,
+        direction,
+        nodes,
+        relations,
+    })
+}
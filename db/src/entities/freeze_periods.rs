@@ -0,0 +1,288 @@
+use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use sqlx::QueryBuilder;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Freeze period in the database. Only emergency changes and RFCs with an override (see
+/// [crate::entities::changes::freeze_overrides]) can be scheduled during it.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct FreezePeriod {
+    pub id: Uuid,
+    #[schema(example = "Year-end closing")]
+    pub name: String,
+    #[schema(example = "Finance closes the fiscal year. No changes to billing systems.")]
+    pub description: String,
+    pub starts_at: DateTime<Utc>,
+    /// End of the period, exclusive.
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Payload for creating a freeze period.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_createset_window"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct FreezePeriodCreateset {
+    #[schema(example = "Year-end closing")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[schema(example = "Finance closes the fiscal year. No changes to billing systems.")]
+    #[validate(length(max = 1024))]
+    pub description: String,
+    pub starts_at: DateTime<Utc>,
+    /// End of the period, exclusive. Must be after the start.
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Validate that a [FreezePeriodCreateset] ends after it starts.
+fn validate_createset_window(createset: &FreezePeriodCreateset) -> Result<(), ValidationError> {
+    validate_window(createset.starts_at, createset.ends_at)
+}
+
+/// Payload for updating a freeze period.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct FreezePeriodUpdateset {
+    #[schema(example = "Year-end closing")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<Option<String>>,
+    #[schema(example = "Finance closes the fiscal year. No changes to billing systems.")]
+    #[validate(length(max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub starts_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub ends_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// Validate that required fields of [FreezePeriodUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &FreezePeriodUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.name)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+    entity_helpers::validate_not_null(&updateset.starts_at)?;
+    entity_helpers::validate_not_null(&updateset.ends_at)?;
+
+    Ok(())
+}
+
+/// Validate that a window ending at `ends_at` ends after it starts at `starts_at`.
+fn validate_window(
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<(), ValidationError> {
+    if starts_at >= ends_at {
+        return Err(ValidationError::new("The period must end after it starts"));
+    }
+
+    Ok(())
+}
+
+/// Query parameters for listing freeze periods.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct FreezePeriodListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Only freeze periods ending after this moment.
+    pub ends_after: Option<DateTime<Utc>>,
+}
+
+/// Append the `WHERE` clause matching the filters in [FreezePeriodListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a FreezePeriodListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(ends_after) = params.ends_after {
+        builder.push(" AND ends_at > ").push_bind(ends_after);
+    }
+}
+
+/// Load one page of freeze periods matching the filters in `params`, earliest first.
+pub async fn load_page(
+    params: FreezePeriodListParams,
+    pool: &DbPool,
+) -> Result<Page<FreezePeriod>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let periods = pagination::load_page::<FreezePeriod>(
+        &mut tx,
+        "id, name, description, starts_at, ends_at",
+        "freeze_periods",
+        |builder| push_filters(builder, &params),
+        "starts_at, id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(periods)
+}
+
+/// Load the freeze periods overlapping the window from `starts_at` to `ends_at`, earliest
+/// first. Both windows are half-open.
+pub async fn load_overlapping(
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Vec<FreezePeriod>, crate::Error> {
+    let periods = sqlx::query_as!(
+        FreezePeriod,
+        "
+        SELECT id, name, description, starts_at, ends_at
+        FROM freeze_periods
+        WHERE starts_at < $2
+        AND ends_at > $1
+        ORDER BY starts_at, id",
+        starts_at,
+        ends_at,
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(periods)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<FreezePeriod, crate::Error> {
+    match sqlx::query_as!(
+        FreezePeriod,
+        "
+        SELECT id, name, description, starts_at, ends_at
+        FROM freeze_periods
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(period) => Ok(period),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+pub async fn create(
+    createset: FreezePeriodCreateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<FreezePeriod, crate::Error> {
+    createset.validate()?;
+
+    let period = sqlx::query_as!(
+        FreezePeriod,
+        "
+        INSERT INTO freeze_periods (name, description, starts_at, ends_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, name, description, starts_at, ends_at",
+        createset.name,
+        createset.description,
+        createset.starts_at,
+        createset.ends_at,
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(period)
+}
+
+/// Update a freeze period. Changes already scheduled during it aren't affected.
+pub async fn update(
+    id: Uuid,
+    updateset: FreezePeriodUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<FreezePeriod, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let current = sqlx::query!(
+        "
+        SELECT starts_at, ends_at
+        FROM freeze_periods
+        WHERE id = $1
+        FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let starts_at = updateset.starts_at.flatten().unwrap_or(current.starts_at);
+    let ends_at = updateset.ends_at.flatten().unwrap_or(current.ends_at);
+    if let Err(e) = validate_window(starts_at, ends_at) {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add("ends_at", e);
+        return Err(errors.into());
+    }
+
+    let period = sqlx::query_as!(
+        FreezePeriod,
+        "
+        UPDATE freeze_periods
+        SET name = COALESCE($1, name), description = COALESCE($2, description),
+            starts_at = $3, ends_at = $4
+        WHERE id = $5
+        RETURNING id, name, description, starts_at, ends_at",
+        updateset.name.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        starts_at,
+        ends_at,
+        id,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(period)
+}
+
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM freeze_periods
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
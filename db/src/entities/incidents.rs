@@ -1,121 +1,131 @@
-use crate::entity_helpers;
-use serde::ser::SerializeStruct;
+use crate::entities::priority_matrix;
+use crate::entity_helpers::{self, Lifecycle};
+use crate::pagination::{self, Page, PageParams, SortOrder};
+use crate::DbPool;
 use serde::Deserialize;
 use serde::Serialize;
 use sqlx::types::chrono::DateTime;
 use sqlx::types::chrono::Utc;
 use sqlx::Postgres;
+use sqlx::QueryBuilder;
 use sqlx::Type;
-use utoipa::PartialSchema;
+use utoipa::IntoParams;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 use validator::ValidationError;
 
+/// Module for handling the children of Incidents.
+pub mod children;
 /// Module for handling relations between Configuration Items and Incidents.
 pub mod ci_relations;
-
-#[derive(Debug)]
+/// Module for the major incident process.
+pub mod major;
+/// Module for merging duplicate Incidents.
+pub mod merge;
+/// Module for measuring Incidents against their SLA policy.
+pub mod sla;
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 #[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
 pub struct Incident {
     pub id: Uuid,
+    #[schema(example = "Proxy Not Working")]
     pub title: String,
     pub status: IncidentStatus,
     pub created_at: DateTime<Utc>,
+    /// When the Incident was resolved. Set when it moves to `resolved` or `closed`
+    /// and cleared if it's reopened.
     pub resolved_at: Option<DateTime<Utc>>,
     pub impact: IncidentImpact,
     pub urgency: IncidentUrgency,
-    pub owner: Option<String>,
-    pub asignee: Option<String>,
+    /// Priority given to the impact and urgency of the Incident by the priority matrix.
+    pub priority: IncidentPrio,
+    /// Team the Incident is assigned to.
+    pub assignment_group_id: Option<Uuid>,
+    /// User working on the Incident. Always a member of the assignment group.
+    pub assignee_id: Option<Uuid>,
+    #[schema(example = "Proxy server not working. Stopped this morning.")]
     pub description: String,
-}
-
-impl Incident {
-    pub fn priority(&self) -> IncidentPrio {
-        IncidentPrio::from(&self.impact, &self.urgency)
-    }
-}
-
-// Manually implement serialize cause priority is not an actual field.
-impl Serialize for Incident {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let mut state = serializer.serialize_struct("Incident", 10)?;
-        state.serialize_field("id", &self.id)?;
-        state.serialize_field("title", &self.title)?;
-        state.serialize_field("status", &self.status)?;
-        state.serialize_field("created_at", &self.created_at)?;
-        state.serialize_field("resolved_at", &self.resolved_at)?;
-        state.serialize_field("impact", &self.impact)?;
-        state.serialize_field("urgency", &self.urgency)?;
-        state.serialize_field("priority", &self.priority())?;
-        state.serialize_field("owner", &self.owner)?;
-        state.serialize_field("asignee", &self.asignee)?;
-        state.serialize_field("description", &self.description)?;
-        state.end()
-    }
-}
-
-impl ToSchema for Incident {
-    fn name() -> std::borrow::Cow<'static, str> {
-        std::borrow::Cow::Borrowed("Incident")
-    }
-}
-
-impl PartialSchema for Incident {
-    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
-        /// Helper struct for generating the schemas for [Incident].
-        ///
-        /// Because the schema should include the priority, which isn't an actual field,
-        /// I do this workaround.
-        #[derive(ToSchema)]
-        #[allow(dead_code)]
-        struct IncidentSchema {
-            pub id: Uuid,
-            #[schema(example = "Proxy Not Working")]
-            pub title: String,
-            pub status: IncidentStatus,
-            pub created_at: DateTime<Utc>,
-            pub resolved_at: Option<DateTime<Utc>>,
-            pub impact: IncidentImpact,
-            pub urgency: IncidentUrgency,
-            pub priority: IncidentPrio,
-            #[schema(example = "Sales Department")]
-            pub owner: Option<String>,
-            #[schema(example = "Employee 1837")]
-            pub asignee: Option<String>,
-            #[schema(example = "Proxy server not working. Stopped this morning.")]
-            pub description: String,
-        }
-
-        IncidentSchema::schema()
-    }
+    /// Why the Incident is on hold. Only set while it is.
+    #[schema(example = "Waiting for the vendor to ship a replacement.")]
+    pub hold_reason: Option<String>,
+    /// How the Incident was resolved. Cleared if it's reopened.
+    pub resolution_code: Option<IncidentResolutionCode>,
+    #[schema(example = "Restarted the proxy service.")]
+    pub resolution_notes: Option<String>,
+    /// SLA policy the deadlines of the Incident come from.
+    pub sla_policy_id: Option<Uuid>,
+    /// When the Incident first left `new`.
+    pub responded_at: Option<DateTime<Utc>>,
+    /// When the Incident must leave `new`, per its SLA policy.
+    pub response_due_at: Option<DateTime<Utc>>,
+    /// When the Incident must be resolved, per its SLA policy. Moves forward by the
+    /// business time the Incident spends on hold, once it leaves `onhold`.
+    pub resolution_due_at: Option<DateTime<Utc>>,
+    /// Whether the Incident follows the major incident process (see [major]).
+    pub major: bool,
+    /// Incident this one is a child of, like one of many reports of the same outage.
+    pub parent_id: Option<Uuid>,
+    /// Incident this one was merged into as a duplicate (see [merge]).
+    pub duplicate_of_id: Option<Uuid>,
 }
 
 /// Payload for creating an Incident.
 #[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_status_fields"))]
 #[cfg_attr(feature = "test-helpers", derive(Serialize))]
 pub struct IncidentCreateset {
     #[schema(example = "Proxy Not Working")]
     #[validate(length(min = 1, max = 255))]
     pub title: String,
-    #[schema(example = "open")]
+    /// Defaults to `new`. Must be `new` or a status that can follow it, so not `onhold`.
+    #[schema(example = "new")]
     pub status: Option<IncidentStatus>,
     pub created_at: Option<DateTime<Utc>>,
-    pub resolved_at: Option<DateTime<Utc>>,
+    /// Must be in use by the priority matrix, like the urgency.
     pub impact: IncidentImpact,
     pub urgency: IncidentUrgency,
-    #[schema(example = "Sales Department")]
-    #[validate(length(max = 1024))]
-    pub owner: Option<String>,
-    #[schema(example = "Employee 1837")]
-    #[validate(length(max = 1024))]
-    pub asignee: Option<String>,
+    /// Team the Incident is assigned to.
+    pub assignment_group_id: Option<Uuid>,
+    /// User working on the Incident. Must be a member of the assignment group.
+    pub assignee_id: Option<Uuid>,
     #[schema(example = "Proxy server not working. Stopped this morning.")]
     #[validate(length(max = 1024))]
     pub description: String,
+    /// Ignored, as Incidents can't be created on hold.
+    #[schema(example = "Waiting for the vendor to ship a replacement.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub hold_reason: Option<String>,
+    /// Required for the `closed` status, ignored unless `resolved` or `closed`.
+    pub resolution_code: Option<IncidentResolutionCode>,
+    /// Required for the `closed` status, ignored unless `resolved` or `closed`.
+    #[schema(example = "Restarted the proxy service.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub resolution_notes: Option<String>,
+    /// SLA policy of the Incident. Defaults to the default policy, if there's one.
+    pub sla_policy_id: Option<Uuid>,
+    /// Incident this one is a child of. Must not be a child itself.
+    pub parent_id: Option<Uuid>,
+}
+
+/// Validate that an [IncidentCreateset] carries the fields its status requires.
+fn validate_status_fields(createset: &IncidentCreateset) -> Result<(), ValidationError> {
+    let status = createset.status.unwrap_or(IncidentStatus::New);
+    if status != IncidentStatus::New && !IncidentStatus::New.next().contains(&status) {
+        return Err(ValidationError::new(
+            "Incidents can only be created in a status that can follow new",
+        ));
+    }
+    if status == IncidentStatus::Closed
+        && (createset.resolution_code.is_none() || createset.resolution_notes.is_none())
+    {
+        return Err(ValidationError::new(
+            "Closed incidents need a resolution code and notes",
+        ));
+    }
+
+    Ok(())
 }
 
 /// Payload for updating an Incident.
@@ -131,7 +141,8 @@ pub struct IncidentUpdateset {
         serde(skip_serializing_if = "Option::is_none")
     )]
     pub title: Option<Option<String>>,
-    #[schema(example = "open")]
+    /// Must be reachable from the current status (see [IncidentStatus::next]).
+    #[schema(example = "inprogress")]
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
@@ -149,43 +160,75 @@ pub struct IncidentUpdateset {
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub resolved_at: Option<Option<DateTime<Utc>>>,
+    pub impact: Option<Option<IncidentImpact>>,
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub impact: Option<Option<IncidentImpact>>,
+    pub urgency: Option<Option<IncidentUrgency>>,
+    /// Team the Incident is assigned to.
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub urgency: Option<Option<IncidentUrgency>>,
-    #[schema(example = "Sales Department")]
-    #[validate(length(max = 1024))]
+    pub assignment_group_id: Option<Option<Uuid>>,
+    /// User working on the Incident. Must be a member of the assignment group.
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub owner: Option<Option<String>>,
-    #[schema(example = "Employee 1837")]
+    pub assignee_id: Option<Option<Uuid>>,
+    #[schema(example = "Proxy server not working. Stopped this morning.")]
     #[validate(length(max = 1024))]
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub asignee: Option<Option<String>>,
-    #[schema(example = "Proxy server not working. Stopped this morning.")]
-    #[validate(length(max = 1024))]
+    pub description: Option<Option<String>>,
+    /// Why the Incident is on hold. Required to move to `onhold`, cleared when leaving it.
+    #[schema(example = "Waiting for the vendor to ship a replacement.")]
+    #[validate(length(min = 1, max = 1024))]
     #[serde(default, with = "::serde_with::rust::double_option")]
     #[cfg_attr(
         any(feature = "test-helpers", test),
         serde(skip_serializing_if = "Option::is_none")
     )]
-    pub description: Option<Option<String>>,
+    pub hold_reason: Option<Option<String>>,
+    /// Required to move to `closed` unless already set. Cleared if the Incident is reopened.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub resolution_code: Option<Option<IncidentResolutionCode>>,
+    /// Required to move to `closed` unless already set. Cleared if the Incident is reopened.
+    #[schema(example = "Restarted the proxy service.")]
+    #[validate(length(min = 1, max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub resolution_notes: Option<Option<String>>,
+    /// Set to null to stop measuring the Incident against an SLA.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub sla_policy_id: Option<Option<Uuid>>,
+    /// Incident this one is a child of. Must not be a child itself, nor can Incidents with
+    /// children become children. Set to null to detach the Incident from its parent.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub parent_id: Option<Option<Uuid>>,
 }
 
 /// Validate that required fields of [IncidentUpdateset] aren't explicitly null.
@@ -200,63 +243,106 @@ fn validate_required_fields(updateset: &IncidentUpdateset) -> Result<(), Validat
     Ok(())
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type)]
+/// Status of an Incident in its lifecycle.
+///
+/// Incidents start out `new` and move through `assigned`, `inprogress` and `onhold` until
+/// they're `resolved`. Resolved Incidents are either reopened or `closed`, which is final.
+/// See [IncidentStatus::next] for the exact transitions.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "incident_status", rename_all = "lowercase")]
-#[schema(example = "open")]
-#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+#[schema(example = "inprogress")]
 pub enum IncidentStatus {
-    Open,
+    New,
+    Assigned,
     InProgress,
+    OnHold,
+    Resolved,
     Closed,
 }
 
+impl Lifecycle for IncidentStatus {
+    fn next(&self) -> &'static [Self] {
+        use IncidentStatus::*;
+        match self {
+            New => &[Assigned, InProgress, Resolved, Closed],
+            Assigned => &[InProgress, OnHold, Resolved],
+            InProgress => &[Assigned, OnHold, Resolved],
+            OnHold => &[Assigned, InProgress, Resolved],
+            Resolved => &[InProgress, Closed],
+            Closed => &[],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Assigned => "assigned",
+            Self::InProgress => "inprogress",
+            Self::OnHold => "onhold",
+            Self::Resolved => "resolved",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+impl IncidentStatus {
+    /// Whether Incidents in this status count as resolved, i.e. have a `resolved_at`.
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, Self::Resolved | Self::Closed)
+    }
+}
+
+/// How an Incident was resolved.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type)]
 #[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "incident_resolution_code", rename_all = "lowercase")]
+#[schema(example = "solved")]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub enum IncidentResolutionCode {
+    Solved,
+    Workaround,
+    NotReproducible,
+    Duplicate,
+    Cancelled,
+}
+
+/// Levels are sorted highest first. Only the ones in use by the priority matrix can be
+/// given to Incidents.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "incident_impact", rename_all = "lowercase")]
 #[schema(example = "high")]
-#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
 pub enum IncidentImpact {
+    Critical,
     High,
     Medium,
     Low,
+    Minimal,
 }
 
-impl IncidentImpact {
-    pub fn weight(&self) -> u8 {
-        match self {
-            Self::High => 3,
-            Self::Medium => 2,
-            Self::Low => 1,
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type)]
+/// Levels are sorted highest first. Only the ones in use by the priority matrix can be
+/// given to Incidents.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq, PartialOrd, Ord,
+)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "incident_urgency", rename_all = "lowercase")]
 #[schema(example = "high")]
-#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
 pub enum IncidentUrgency {
+    Critical,
     High,
     Medium,
     Low,
+    Minimal,
 }
 
-impl IncidentUrgency {
-    pub fn weight(&self) -> u8 {
-        match self {
-            Self::High => 3,
-            Self::Medium => 2,
-            Self::Low => 1,
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
 #[schema(example = "critical")]
 #[serde(rename_all = "lowercase")]
-#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+#[sqlx(type_name = "incident_prio", rename_all = "lowercase")]
 pub enum IncidentPrio {
     Critical,
     High,
@@ -264,23 +350,232 @@ pub enum IncidentPrio {
     Low,
 }
 
-impl IncidentPrio {
-    pub fn from(impact: &IncidentImpact, urgency: &IncidentUrgency) -> Self {
-        let coef = impact.weight() * urgency.weight();
-        match coef {
-            1 | 2 => IncidentPrio::Low,
-            3 | 4 => IncidentPrio::Moderate,
-            6 => IncidentPrio::High,
-            9 => IncidentPrio::Critical,
-            _ => panic!(
-                "Bad values.\n\timpact: {}\n\turgency: {}",
-                impact.weight(),
-                urgency.weight()
-            ),
+/// Query parameters for listing Incidents.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct IncidentListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Column to sort by. Defaults to `created_at`.
+    #[param(inline)]
+    pub sort_by: Option<IncidentSortColumn>,
+    /// Sorting direction. Defaults to `asc`.
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    pub status: Option<IncidentStatus>,
+    pub impact: Option<IncidentImpact>,
+    pub urgency: Option<IncidentUrgency>,
+    pub priority: Option<IncidentPrio>,
+    /// Only Incidents assigned to this team.
+    pub assignment_group_id: Option<Uuid>,
+    /// Only Incidents assigned to this user.
+    pub assignee_id: Option<Uuid>,
+    /// Only Incidents assigned to the caller.
+    pub assigned_to_me: Option<bool>,
+    /// Only Incidents assigned to any of the teams of the caller.
+    pub assigned_to_my_team: Option<bool>,
+    /// Only Incidents created at or after this moment.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only Incidents created before this moment.
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only Incidents resolved at or after this moment.
+    pub resolved_after: Option<DateTime<Utc>>,
+    /// Only Incidents resolved before this moment.
+    pub resolved_before: Option<DateTime<Utc>>,
+    /// Only Incidents that missed (`true`) or met so far (`false`) their response or
+    /// resolution deadline.
+    pub sla_breached: Option<bool>,
+    /// Only Incidents that will miss a deadline within this many minutes unless someone acts
+    /// on them. Incidents on hold, resolved or already in breach are left out.
+    #[validate(range(min = 1, max = 525600))]
+    pub sla_breaching_within: Option<i32>,
+}
+
+/// Columns Incidents can be sorted by.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(example = "created_at")]
+pub enum IncidentSortColumn {
+    #[default]
+    CreatedAt,
+    ResolvedAt,
+    Title,
+    Status,
+    Impact,
+    Urgency,
+    Priority,
+    ResolutionDueAt,
+}
+
+impl IncidentSortColumn {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::ResolvedAt => "resolved_at",
+            Self::Title => "title",
+            Self::Status => "status",
+            Self::Impact => "impact",
+            Self::Urgency => "urgency",
+            Self::ResolutionDueAt => "resolution_due_at",
+            // Lowest priority first, unlike the order of the values of the enum.
+            Self::Priority => {
+                "(CASE priority WHEN 'critical' THEN 4 WHEN 'high' THEN 3 WHEN 'moderate' THEN 2
+                ELSE 1 END)"
+            }
         }
     }
 }
 
+/// Condition of the Incidents that missed a deadline. The resolution clock stops while an
+/// Incident is on hold, so Incidents on hold only count if their deadline passed before.
+const SLA_BREACHED_SQL: &str = "
+    (COALESCE(response_due_at < COALESCE(responded_at, now()), false)
+    OR COALESCE(resolution_due_at < COALESCE(
+        resolved_at,
+        (SELECT h.started_at
+        FROM incident_hold_periods AS h
+        WHERE h.incident_id = incidents.id
+        AND h.ended_at IS NULL),
+        now()
+    ), false))";
+
+/// Append the `WHERE` clause matching the filters in [IncidentListParams].
+///
+/// `subject` is the username of the caller, for the filters relative to them.
+fn push_filters<'a>(
+    builder: &mut QueryBuilder<'a, Postgres>,
+    params: &'a IncidentListParams,
+    subject: &'a str,
+) {
+    builder.push(" WHERE TRUE");
+    if let Some(status) = params.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(impact) = params.impact {
+        builder.push(" AND impact = ").push_bind(impact);
+    }
+    if let Some(urgency) = params.urgency {
+        builder.push(" AND urgency = ").push_bind(urgency);
+    }
+    if let Some(priority) = params.priority {
+        builder.push(" AND priority = ").push_bind(priority);
+    }
+    if let Some(assignment_group_id) = params.assignment_group_id {
+        builder
+            .push(" AND assignment_group_id = ")
+            .push_bind(assignment_group_id);
+    }
+    if let Some(assignee_id) = params.assignee_id {
+        builder.push(" AND assignee_id = ").push_bind(assignee_id);
+    }
+    if params.assigned_to_me == Some(true) {
+        builder
+            .push(" AND assignee_id IN (SELECT id FROM users WHERE username = ")
+            .push_bind(subject)
+            .push(")");
+    }
+    if params.assigned_to_my_team == Some(true) {
+        builder
+            .push(
+                " AND assignment_group_id IN (
+                SELECT m.team_id
+                FROM team_members AS m
+                INNER JOIN users AS u
+                ON u.id = m.user_id
+                WHERE u.username = ",
+            )
+            .push_bind(subject)
+            .push(")");
+    }
+    if let Some(created_after) = params.created_after {
+        builder.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = params.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(resolved_after) = params.resolved_after {
+        builder
+            .push(" AND resolved_at >= ")
+            .push_bind(resolved_after);
+    }
+    if let Some(resolved_before) = params.resolved_before {
+        builder
+            .push(" AND resolved_at < ")
+            .push_bind(resolved_before);
+    }
+    match params.sla_breached {
+        Some(true) => {
+            builder.push(" AND ").push(SLA_BREACHED_SQL);
+        }
+        Some(false) => {
+            builder.push(" AND NOT ").push(SLA_BREACHED_SQL);
+        }
+        None => {}
+    }
+    if let Some(minutes) = params.sla_breaching_within {
+        builder
+            .push(" AND NOT ")
+            .push(SLA_BREACHED_SQL)
+            .push(" AND status NOT IN ('onhold', 'resolved', 'closed')")
+            .push(
+                " AND ((responded_at IS NULL AND response_due_at < now() + make_interval(mins => ",
+            )
+            .push_bind(minutes)
+            .push(")) OR resolution_due_at < now() + make_interval(mins => ")
+            .push_bind(minutes)
+            .push("))");
+    }
+}
+
+/// Load one page of Incidents matching the filters in `params`, as seen by the caller `subject`.
+pub async fn load_page(
+    params: IncidentListParams,
+    subject: &str,
+    pool: &DbPool,
+) -> Result<Page<Incident>, crate::Error> {
+    params.validate()?;
+
+    let order_by = format!(
+        "{} {}, id",
+        params.sort_by.unwrap_or_default().as_sql(),
+        params.order.unwrap_or_default().as_sql()
+    );
+
+    let mut tx = pool.begin().await?;
+    let incidents = pagination::load_page::<Incident>(
+        &mut tx,
+        "
+        id, title, status, created_at, resolved_at, impact, urgency, priority,
+        assignment_group_id, assignee_id, description, hold_reason, resolution_code,
+        resolution_notes, sla_policy_id, responded_at, response_due_at, resolution_due_at,
+        major, parent_id, duplicate_of_id",
+        "incidents",
+        |builder| push_filters(builder, &params, subject),
+        &order_by,
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(incidents)
+}
+
+/// Map the errors of writing an Incident, which may reference a nonexistent team or parent,
+/// an assignee who isn't a member of the assignment group, or be its own parent.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_foreign_key_violation() || dbe.is_check_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
 pub async fn load_all(
     executor: impl sqlx::Executor<'_, Database = Postgres>,
 ) -> Result<Vec<Incident>, crate::Error> {
@@ -289,7 +584,11 @@ pub async fn load_all(
         "
         SELECT id, title, status as \"status: IncidentStatus\", created_at, resolved_at,
             impact as \"impact: IncidentImpact\", urgency as \"urgency: IncidentUrgency\",
-            owner, asignee, description
+            priority as \"priority: IncidentPrio\", assignment_group_id, assignee_id,
+            description, hold_reason,
+            resolution_code as \"resolution_code: IncidentResolutionCode\", resolution_notes,
+            sla_policy_id, responded_at, response_due_at, resolution_due_at, major, parent_id,
+            duplicate_of_id
         FROM incidents"
     )
     .fetch_all(executor)
@@ -298,21 +597,57 @@ pub async fn load_all(
     Ok(incidents)
 }
 
-pub async fn load_all_by_ci(
+/// Load one page of the Incidents related to the Configuration Item `ci_id`, oldest first.
+pub async fn load_page_by_ci(
     ci_id: Uuid,
+    params: PageParams,
+    pool: &DbPool,
+) -> Result<Page<Incident>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let incidents = pagination::load_page::<Incident>(
+        &mut tx,
+        "
+        i.id, i.title, i.status, i.created_at, i.resolved_at, i.impact, i.urgency, i.priority,
+        i.assignment_group_id, i.assignee_id, i.description, i.hold_reason, i.resolution_code,
+        i.resolution_notes, i.sla_policy_id, i.responded_at, i.response_due_at,
+        i.resolution_due_at, i.major, i.parent_id, i.duplicate_of_id",
+        "
+        incidents AS i
+        INNER JOIN incidents_ci_relations AS r
+        ON i.id = r.incident_id",
+        |builder| {
+            builder.push(" WHERE r.ci_id = ").push_bind(ci_id);
+        },
+        "i.created_at, i.id",
+        params,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(incidents)
+}
+
+/// Load the children of the Incident `parent_id`, oldest first.
+pub async fn load_all_by_parent(
+    parent_id: Uuid,
     executor: impl sqlx::Executor<'_, Database = Postgres>,
 ) -> Result<Vec<Incident>, crate::Error> {
     let incidents = sqlx::query_as!(
         Incident,
         "
-        SELECT i.id, i.title, i.status as \"status: IncidentStatus\", i.created_at, i.resolved_at,
-            i.impact as \"impact: IncidentImpact\", i.urgency as \"urgency: IncidentUrgency\",
-            i.owner, i.asignee, i.description
-        FROM incidents AS i
-        INNER JOIN incidents_ci_relations AS r
-        ON i.id = r.incident_id
-        WHERE r.ci_id = $1",
-        ci_id
+        SELECT id, title, status as \"status: IncidentStatus\", created_at, resolved_at,
+            impact as \"impact: IncidentImpact\", urgency as \"urgency: IncidentUrgency\",
+            priority as \"priority: IncidentPrio\", assignment_group_id, assignee_id,
+            description, hold_reason,
+            resolution_code as \"resolution_code: IncidentResolutionCode\", resolution_notes,
+            sla_policy_id, responded_at, response_due_at, resolution_due_at, major, parent_id,
+            duplicate_of_id
+        FROM incidents
+        WHERE parent_id = $1
+        ORDER BY created_at, id",
+        parent_id
     )
     .fetch_all(executor)
     .await?;
@@ -329,7 +664,11 @@ pub async fn load(
         "
         SELECT id, title, status as \"status: IncidentStatus\", created_at, resolved_at,
             impact as \"impact: IncidentImpact\", urgency as \"urgency: IncidentUrgency\",
-            owner, asignee, description
+            priority as \"priority: IncidentPrio\", assignment_group_id, assignee_id,
+            description, hold_reason,
+            resolution_code as \"resolution_code: IncidentResolutionCode\", resolution_notes,
+            sla_policy_id, responded_at, response_due_at, resolution_due_at, major, parent_id,
+            duplicate_of_id
         FROM incidents
         WHERE id = $1",
         id
@@ -343,89 +682,231 @@ pub async fn load(
     }
 }
 
+/// Create an Incident and compute its priority and SLA deadlines. Unresolved critical
+/// Incidents are major incidents from the moment they were created.
 pub async fn create(
     createset: IncidentCreateset,
-    executor: impl sqlx::Executor<'_, Database = Postgres>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<Incident, crate::Error> {
     createset.validate()?;
 
-    let created_incident = sqlx::query_as!(
-        Incident,
+    let status = createset.status.unwrap_or(IncidentStatus::New);
+    let hold_reason = createset
+        .hold_reason
+        .filter(|_| status == IncidentStatus::OnHold);
+    let (resolution_code, resolution_notes) = if status.is_resolved() {
+        (createset.resolution_code, createset.resolution_notes)
+    } else {
+        (None, None)
+    };
+
+    let mut tx = executor.begin().await?;
+
+    if let Some(parent_id) = createset.parent_id {
+        children::check_parent(None, parent_id, &mut tx).await?;
+    }
+    let priority =
+        priority_matrix::priority_of(createset.impact, createset.urgency, &mut *tx).await?;
+
+    let id = sqlx::query_scalar!(
         "
-        INSERT INTO incidents (title, status, created_at, resolved_at, impact, urgency,
-            owner, asignee, description)
-        VALUES ($1, $2, COALESCE($3, now()), $4, $5, $6, $7, $8, $9)
-        RETURNING id, title, status as \"status: IncidentStatus\", created_at, resolved_at,
-            impact as \"impact: IncidentImpact\", urgency as \"urgency: IncidentUrgency\",
-            owner, asignee, description",
+        INSERT INTO incidents (title, status, created_at, resolved_at, impact, urgency, priority,
+            assignment_group_id, assignee_id, description, hold_reason, resolution_code,
+            resolution_notes, sla_policy_id, responded_at, parent_id)
+        VALUES ($1, $2, COALESCE($3, now()), CASE WHEN $4 THEN now() END, $5, $6, $7, $8, $9,
+            $10, $11, $12, $13, COALESCE($14, (SELECT id FROM sla_policies WHERE is_default)),
+            CASE WHEN $15 THEN COALESCE($3, now()) END, $16)
+        RETURNING id",
         createset.title,
-        createset.status.unwrap_or(IncidentStatus::Open) as IncidentStatus,
+        status as IncidentStatus,
         createset.created_at,
-        createset.resolved_at,
+        status.is_resolved(),
         createset.impact as IncidentImpact,
         createset.urgency as IncidentUrgency,
-        createset.owner,
-        createset.asignee,
+        priority as IncidentPrio,
+        createset.assignment_group_id,
+        createset.assignee_id,
         createset.description,
+        hold_reason,
+        resolution_code as Option<IncidentResolutionCode>,
+        resolution_notes,
+        createset.sla_policy_id,
+        status != IncidentStatus::New,
+        createset.parent_id,
     )
-    .fetch_one(executor)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(crate::Error::DbError)?;
+    .map_err(map_write_error)?;
+    sla::record_transition(id, None, status, &mut *tx).await?;
+    sla::refresh(id, &mut tx).await?;
+    if priority == IncidentPrio::Critical && !status.is_resolved() {
+        major::declare(id, createset.created_at, &mut tx).await?;
+    }
 
+    let created_incident = load(id, &mut *tx).await?;
+    tx.commit().await?;
     Ok(created_incident)
 }
 
+/// Update an Incident, moving it to another status if the updateset has one.
+///
+/// Fails with [crate::Error::InvalidTransition] if the new status isn't reachable from
+/// the current one. `resolved_at` is set when the Incident is resolved or closed, and it's
+/// cleared along with the resolution when it's reopened. The hold reason is cleared when
+/// the Incident leaves `onhold`. `responded_at` is set when the Incident first leaves `new`,
+/// and the priority and SLA deadlines are recomputed. Unresolved Incidents becoming critical
+/// are declared major incidents, and major incidents need a related Problem to be closed.
+/// Resolving an Incident resolves its children too (see [children::resolve]).
 pub async fn update(
     id: Uuid,
     updateset: IncidentUpdateset,
-    executor: impl sqlx::Executor<'_, Database = Postgres>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<Incident, crate::Error> {
     updateset.validate()?;
 
-    match sqlx::query_as!(
-        Incident,
+    let mut tx = executor.begin().await?;
+
+    let current = sqlx::query!(
+        "
+        SELECT status as \"status: IncidentStatus\", impact as \"impact: IncidentImpact\",
+            urgency as \"urgency: IncidentUrgency\", hold_reason,
+            resolution_code as \"resolution_code: IncidentResolutionCode\", resolution_notes,
+            major
+        FROM incidents
+        WHERE id = $1
+        FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let status = updateset.status.flatten().unwrap_or(current.status);
+    entity_helpers::check_transition(current.status, status)?;
+
+    let hold_reason = match updateset.hold_reason {
+        Some(hold_reason) => hold_reason,
+        None => current.hold_reason,
+    }
+    .filter(|_| status == IncidentStatus::OnHold);
+    let (resolution_code, resolution_notes) = if status.is_resolved() {
+        (
+            updateset.resolution_code.unwrap_or(current.resolution_code),
+            updateset
+                .resolution_notes
+                .unwrap_or(current.resolution_notes),
+        )
+    } else {
+        (None, None)
+    };
+
+    let mut errors = validator::ValidationErrors::new();
+    if status == IncidentStatus::OnHold && hold_reason.is_none() {
+        errors.add(
+            "hold_reason",
+            ValidationError::new("Incidents on hold need a hold reason"),
+        );
+    }
+    if status == IncidentStatus::Closed {
+        if resolution_code.is_none() {
+            errors.add(
+                "resolution_code",
+                ValidationError::new("Closed incidents need a resolution code"),
+            );
+        }
+        if resolution_notes.is_none() {
+            errors.add(
+                "resolution_notes",
+                ValidationError::new("Closed incidents need resolution notes"),
+            );
+        }
+        if current.major
+            && current.status != IncidentStatus::Closed
+            && !major::has_problem(id, &mut tx).await?
+        {
+            errors.add(
+                "status",
+                ValidationError::new("Major incidents need a related problem to be closed"),
+            );
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    if let Some(Some(parent_id)) = updateset.parent_id {
+        children::check_parent(Some(id), parent_id, &mut tx).await?;
+    }
+
+    let impact = updateset.impact.flatten().unwrap_or(current.impact);
+    let urgency = updateset.urgency.flatten().unwrap_or(current.urgency);
+    let priority = priority_matrix::priority_of(impact, urgency, &mut *tx).await?;
+
+    sqlx::query!(
         "
         UPDATE incidents
-        SET title = COALESCE($1, title), status = COALESCE($2, status), created_at = COALESCE($3, created_at),
+        SET title = COALESCE($1, title), status = $2, created_at = COALESCE($3, created_at),
             resolved_at = CASE
-                WHEN $4 THEN resolved_at
-                ELSE $5
+                WHEN $4 THEN COALESCE(resolved_at, now())
             END,
-            impact = COALESCE($6, impact), urgency = COALESCE($7, urgency),
-            owner = CASE
-                WHEN $8 THEN owner
+            impact = $5, urgency = $6, priority = $7,
+            assignment_group_id = CASE
+                WHEN $8 THEN assignment_group_id
                 ELSE $9
             END,
-            asignee = CASE
-                WHEN $10 THEN asignee
+            assignee_id = CASE
+                WHEN $10 THEN assignee_id
                 ELSE $11
             END,
-            description = COALESCE($12, description)
-        WHERE id = $13
-        RETURNING id, title, status as \"status: IncidentStatus\", created_at, resolved_at,
-            impact as \"impact: IncidentImpact\", urgency as \"urgency: IncidentUrgency\",
-            owner, asignee, description",
+            description = COALESCE($12, description),
+            hold_reason = $13, resolution_code = $14, resolution_notes = $15,
+            sla_policy_id = CASE
+                WHEN $16 THEN sla_policy_id
+                ELSE $17
+            END,
+            responded_at = COALESCE(responded_at, CASE WHEN $18 THEN now() END),
+            parent_id = CASE
+                WHEN $19 THEN parent_id
+                ELSE $20
+            END
+        WHERE id = $21",
         updateset.title.unwrap_or(None),
-        updateset.status.unwrap_or(None) as Option<IncidentStatus>,
+        status as IncidentStatus,
         updateset.created_at.unwrap_or(None),
-        updateset.resolved_at.is_none(),
-        updateset.resolved_at.unwrap_or(None),
-        updateset.impact.unwrap_or(None) as Option<IncidentImpact>,
-        updateset.urgency.unwrap_or(None) as Option<IncidentUrgency>,
-        updateset.owner.is_none(),
-        updateset.owner.unwrap_or(None),
-        updateset.asignee.is_none(),
-        updateset.asignee.unwrap_or(None),
+        status.is_resolved(),
+        impact as IncidentImpact,
+        urgency as IncidentUrgency,
+        priority as IncidentPrio,
+        updateset.assignment_group_id.is_none(),
+        updateset.assignment_group_id.unwrap_or(None),
+        updateset.assignee_id.is_none(),
+        updateset.assignee_id.unwrap_or(None),
         updateset.description.unwrap_or(None),
+        hold_reason,
+        resolution_code as Option<IncidentResolutionCode>,
+        resolution_notes,
+        updateset.sla_policy_id.is_none(),
+        updateset.sla_policy_id.unwrap_or(None),
+        status != IncidentStatus::New,
+        updateset.parent_id.is_none(),
+        updateset.parent_id.unwrap_or(None),
         id,
     )
-    .fetch_optional(executor)
+    .execute(&mut *tx)
     .await
-    .map_err(crate::Error::DbError)?
-    {
-        Some(updated_incident) => Ok(updated_incident),
-        None => Err(crate::Error::NoRecordFound),
+    .map_err(map_write_error)?;
+    sla::record_transition(id, Some(current.status), status, &mut *tx).await?;
+    sla::refresh(id, &mut tx).await?;
+    if priority == IncidentPrio::Critical && !status.is_resolved() {
+        major::declare(id, None, &mut tx).await?;
+    }
+    if status.is_resolved() && !current.status.is_resolved() {
+        children::resolve(id, resolution_code, resolution_notes.as_deref(), &mut tx).await?;
     }
+
+    let updated_incident = load(id, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(updated_incident)
 }
 
 pub async fn delete(
@@ -447,93 +928,3 @@ pub async fn delete(
         None => Err(crate::Error::NoRecordFound),
     }
 }
-
-#[cfg(test)]
-mod incidents_tests {
-    use super::*;
-    use uuid::uuid;
-
-    fn build_testing_incident() -> Incident {
-        Incident {
-            title: String::from("Test Incident"),
-            id: uuid!("67e55044-10b1-426f-9247-bb680e5fe0c8"),
-            status: IncidentStatus::Open,
-            created_at: Utc::now(),
-            resolved_at: None,
-            impact: IncidentImpact::Low,
-            urgency: IncidentUrgency::Low,
-            owner: Some(String::from("Me")),
-            asignee: Some(String::from("Someone")),
-            description: String::from(""),
-        }
-    }
-
-    #[test]
-    fn test_low_priority() {
-        let mut incidents = Vec::new();
-        incidents.push(build_testing_incident());
-        incidents.push(Incident {
-            impact: IncidentImpact::Medium,
-            ..build_testing_incident()
-        });
-        incidents.push(Incident {
-            urgency: IncidentUrgency::Medium,
-            ..build_testing_incident()
-        });
-
-        for incident in incidents {
-            assert_eq!(incident.priority(), IncidentPrio::Low);
-        }
-    }
-
-    #[test]
-    fn test_moderate_priority() {
-        let mut incidents = Vec::new();
-        incidents.push(Incident {
-            impact: IncidentImpact::High,
-            ..build_testing_incident()
-        });
-        incidents.push(Incident {
-            urgency: IncidentUrgency::High,
-            ..build_testing_incident()
-        });
-        incidents.push(Incident {
-            impact: IncidentImpact::Medium,
-            urgency: IncidentUrgency::Medium,
-            ..build_testing_incident()
-        });
-
-        for incident in incidents {
-            assert_eq!(incident.priority(), IncidentPrio::Moderate);
-        }
-    }
-
-    #[test]
-    fn test_high_priority() {
-        let mut incidents = Vec::new();
-        incidents.push(Incident {
-            impact: IncidentImpact::High,
-            urgency: IncidentUrgency::Medium,
-            ..build_testing_incident()
-        });
-        incidents.push(Incident {
-            impact: IncidentImpact::Medium,
-            urgency: IncidentUrgency::High,
-            ..build_testing_incident()
-        });
-
-        for incident in incidents {
-            assert_eq!(incident.priority(), IncidentPrio::High);
-        }
-    }
-
-    #[test]
-    fn test_critical_priority() {
-        let incident = Incident {
-            impact: IncidentImpact::High,
-            urgency: IncidentUrgency::High,
-            ..build_testing_incident()
-        };
-        assert_eq!(incident.priority(), IncidentPrio::Critical);
-    }
-}
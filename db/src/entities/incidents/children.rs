@@ -0,0 +1,197 @@
+use crate::entities::incidents::{self, sla, Incident, IncidentResolutionCode, IncidentStatus};
+use crate::entity_helpers;
+use crate::DbPool;
+use serde::Deserialize;
+#[cfg(feature = "test-helpers")]
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Payload for making Incidents children of another one.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize))]
+pub struct IncidentChildrenCreateset {
+    /// Incidents to make children. Incidents with children of their own can't be children.
+    #[validate(length(min = 1, max = 500))]
+    pub incident_ids: Vec<Uuid>,
+}
+
+/// Check that the Incident `parent_id` may be the parent of the Incident `id`, or of a new
+/// Incident if there's no `id`.
+///
+/// Incidents are nested one level deep at most: parents can't be children, and Incidents
+/// with children can't become children. Nonexistent parents are left to the foreign key.
+pub(crate) async fn check_parent(
+    id: Option<Uuid>,
+    parent_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let grandparent_id = sqlx::query_scalar!(
+        "
+        SELECT parent_id
+        FROM incidents
+        WHERE id = $1",
+        parent_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .flatten();
+    if grandparent_id.is_some() {
+        return Err(entity_helpers::invalid_field(
+            "parent_id",
+            "Children can't be parents",
+        ));
+    }
+
+    if let Some(id) = id {
+        let has_children = sqlx::query_scalar!(
+            "
+            SELECT EXISTS(SELECT 1 FROM incidents WHERE parent_id = $1)",
+            id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if has_children.unwrap_or(false) {
+            return Err(entity_helpers::invalid_field(
+                "parent_id",
+                "Parents can't be children",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the children of an Incident, oldest first.
+pub async fn load_all(parent_id: Uuid, pool: &DbPool) -> Result<Vec<Incident>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    incidents::load(parent_id, &mut *tx).await?;
+    let children = incidents::load_all_by_parent(parent_id, &mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(children)
+}
+
+/// Make the Incidents in `createset` children of the Incident `parent_id`, and load all of
+/// its children.
+///
+/// Children of other Incidents are moved to this one. Fails with
+/// [crate::Error::ConstraintError] if any of the Incidents doesn't exist.
+pub async fn create(
+    parent_id: Uuid,
+    createset: IncidentChildrenCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Vec<Incident>, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let grandparent_id = sqlx::query_scalar!(
+        "
+        SELECT parent_id
+        FROM incidents
+        WHERE id = $1
+        FOR UPDATE",
+        parent_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if grandparent_id.is_some() {
+        return Err(entity_helpers::invalid_field(
+            "incident_ids",
+            "Children can't be parents",
+        ));
+    }
+    if createset.incident_ids.contains(&parent_id) {
+        return Err(entity_helpers::invalid_field(
+            "incident_ids",
+            "Incidents can't be their own children",
+        ));
+    }
+
+    let has_children = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM incidents WHERE parent_id = ANY($1))",
+        &createset.incident_ids
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if has_children.unwrap_or(false) {
+        return Err(entity_helpers::invalid_field(
+            "incident_ids",
+            "Parents can't be children",
+        ));
+    }
+
+    let linked = sqlx::query_scalar!(
+        "
+        UPDATE incidents
+        SET parent_id = $1
+        WHERE id = ANY($2)
+        RETURNING id",
+        parent_id,
+        &createset.incident_ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut incident_ids = createset.incident_ids;
+    incident_ids.sort();
+    incident_ids.dedup();
+    if linked.len() != incident_ids.len() {
+        return Err(crate::Error::ConstraintError);
+    }
+
+    let children = incidents::load_all_by_parent(parent_id, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(children)
+}
+
+/// Resolve the unresolved children of the Incident `parent_id`, with its resolution.
+///
+/// Children are resolved like [incidents::update] resolves Incidents: `responded_at` and
+/// `resolved_at` are set, the hold reason is cleared and the SLA deadlines are recomputed.
+pub(crate) async fn resolve(
+    parent_id: Uuid,
+    resolution_code: Option<IncidentResolutionCode>,
+    resolution_notes: Option<&str>,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let resolved = sqlx::query!(
+        "
+        WITH children AS (
+            SELECT id, status
+            FROM incidents
+            WHERE parent_id = $1
+            AND status NOT IN ('resolved', 'closed')
+            FOR UPDATE
+        )
+        UPDATE incidents AS i
+        SET status = 'resolved', resolved_at = now(), hold_reason = NULL,
+            resolution_code = $2, resolution_notes = $3,
+            responded_at = COALESCE(i.responded_at, now())
+        FROM children AS c
+        WHERE i.id = c.id
+        RETURNING i.id, c.status as \"status: IncidentStatus\"",
+        parent_id,
+        resolution_code as Option<IncidentResolutionCode>,
+        resolution_notes,
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for child in resolved {
+        sla::record_transition(
+            child.id,
+            Some(child.status),
+            IncidentStatus::Resolved,
+            &mut *conn,
+        )
+        .await?;
+        sla::refresh(child.id, conn).await?;
+    }
+
+    Ok(())
+}
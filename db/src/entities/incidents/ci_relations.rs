@@ -1,3 +1,4 @@
+use crate::entities::changes::incident_relations;
 use crate::DbPool;
 #[cfg(feature = "test-helpers")]
 use serde::Deserialize;
@@ -55,12 +56,14 @@ pub async fn load_all(
     Ok(relations)
 }
 
+/// Relate an Incident to a Configuration Item. The RFCs implemented on it right before the
+/// Incident was opened are suggested as its cause (see [incident_relations::suggest_causes]).
 pub async fn create(
     incident_id: Uuid,
     ci_id: Uuid,
-    pool: &DbPool,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<IncidentCIRelation, crate::Error> {
-    let mut tx = pool.begin().await?;
+    let mut tx = executor.begin().await?;
     check_valid_incident(incident_id, &mut *tx).await?;
     sqlx::query!(
         "
@@ -78,6 +81,7 @@ pub async fn create(
         }
         _ => crate::Error::DbError(e),
     })?;
+    incident_relations::suggest_causes(None, Some(incident_id), &mut tx).await?;
 
     tx.commit().await?;
     Ok(IncidentCIRelation {
@@ -0,0 +1,508 @@
+use crate::entities::changes::{risk::RiskCategory, RFCStatus, RFCType, RFC};
+use crate::entities::configuration::{CIStatus, ConfigItem};
+use crate::entities::incidents::{self, Incident, IncidentPrio, IncidentStatus};
+use crate::entities::problems::{Problem, ProblemStatus};
+use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Minutes between stakeholder updates of major incidents, unless their commander sets
+/// another cadence.
+pub const DEFAULT_UPDATE_CADENCE_MINUTES: i32 = 30;
+
+/// Incident following the major incident process, either because it became critical or
+/// because someone declared it.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct MajorIncident {
+    pub incident_id: Uuid,
+    #[schema(example = "Proxy Not Working")]
+    pub title: String,
+    pub status: IncidentStatus,
+    pub priority: IncidentPrio,
+    pub declared_at: DateTime<Utc>,
+    /// User leading the response to the Incident.
+    pub commander_id: Option<Uuid>,
+    /// Minutes between updates to stakeholders.
+    #[schema(example = 30)]
+    pub update_cadence_minutes: i32,
+    /// When stakeholders were last updated. `null` if they never were.
+    pub last_update_at: Option<DateTime<Utc>>,
+    /// When stakeholders must be updated next, counting from the last update or from the
+    /// declaration. `null` once the Incident is resolved.
+    pub next_update_due_at: Option<DateTime<Utc>>,
+    /// Whether the next update is late.
+    pub update_overdue: bool,
+}
+
+/// Payload for declaring a major incident, or updating one.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct MajorIncidentUpdateset {
+    /// User leading the response to the Incident. Set to null to leave it without commander.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub commander_id: Option<Option<Uuid>>,
+    /// Minutes between updates to stakeholders. Defaults to
+    /// [DEFAULT_UPDATE_CADENCE_MINUTES] when declaring.
+    #[schema(example = 30)]
+    #[validate(range(min = 1, max = 1440))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub update_cadence_minutes: Option<Option<i32>>,
+}
+
+/// Validate that required fields of [MajorIncidentUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &MajorIncidentUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.update_cadence_minutes)?;
+
+    Ok(())
+}
+
+/// Query parameters for listing major incidents.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct MajorIncidentListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Only major incidents not resolved yet (`true`) or resolved (`false`).
+    pub active: Option<bool>,
+    /// Only major incidents whose stakeholders are (`true`) or aren't (`false`) due an update.
+    pub update_overdue: Option<bool>,
+    /// Only major incidents led by this user.
+    pub commander_id: Option<Uuid>,
+}
+
+/// Update sent to the stakeholders of a major incident.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct Communication {
+    pub id: Uuid,
+    pub incident_id: Uuid,
+    /// Subject of the caller who logged the update.
+    #[schema(example = "jdoe")]
+    pub author: String,
+    /// Who the update was sent to.
+    #[schema(example = "Customer support leads")]
+    pub audience: String,
+    #[schema(example = "The proxy is back up for the main office. Branches are next.")]
+    pub message: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Payload for logging an update sent to the stakeholders of a major incident.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CommunicationCreateset {
+    #[schema(example = "Customer support leads")]
+    #[validate(length(min = 1, max = 255))]
+    pub audience: String,
+    #[schema(example = "The proxy is back up for the main office. Branches are next.")]
+    #[validate(length(min = 1, max = 4096))]
+    pub message: String,
+}
+
+/// A major incident along with everything related to it and its children.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize))]
+pub struct MajorIncidentSummary {
+    pub major_incident: MajorIncident,
+    pub incident: Incident,
+    /// Children of the Incident, oldest first.
+    pub children: Vec<Incident>,
+    /// Configuration Items related to the Incident or its children.
+    pub config_items: Vec<ConfigItem>,
+    /// RFCs related to the Incident or its children.
+    pub rfcs: Vec<RFC>,
+    /// Problems related to the Incident or its children.
+    pub problems: Vec<Problem>,
+}
+
+/// Declare the Incident `incident_id` a major incident at `declared_at`, or now, unless it
+/// is one already.
+pub(crate) async fn declare(
+    incident_id: Uuid,
+    declared_at: Option<DateTime<Utc>>,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        INSERT INTO major_incidents (incident_id, declared_at, update_cadence_minutes)
+        VALUES ($1, COALESCE($2, now()), $3)
+        ON CONFLICT (incident_id) DO NOTHING",
+        incident_id,
+        declared_at,
+        DEFAULT_UPDATE_CADENCE_MINUTES,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE incidents
+        SET major = TRUE
+        WHERE id = $1
+        AND NOT major",
+        incident_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether the Incident `incident_id` is related to a Problem.
+pub(crate) async fn has_problem(
+    incident_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<bool, crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM problem_incident_relations WHERE incident_id = $1)",
+        incident_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(exists.unwrap_or(false))
+}
+
+/// Condition of the major incidents whose stakeholders are due an update.
+const UPDATE_OVERDUE_SQL: &str = "
+    (i.status NOT IN ('resolved', 'closed')
+    AND COALESCE(
+        (SELECT max(c.sent_at)
+        FROM major_incident_communications AS c
+        WHERE c.incident_id = m.incident_id),
+        m.declared_at
+    ) + make_interval(mins => m.update_cadence_minutes) < now())";
+
+/// Append the `WHERE` clause matching the filters in [MajorIncidentListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a MajorIncidentListParams) {
+    builder.push(" WHERE TRUE");
+    match params.active {
+        Some(true) => {
+            builder.push(" AND i.status NOT IN ('resolved', 'closed')");
+        }
+        Some(false) => {
+            builder.push(" AND i.status IN ('resolved', 'closed')");
+        }
+        None => {}
+    }
+    match params.update_overdue {
+        Some(true) => {
+            builder.push(" AND ").push(UPDATE_OVERDUE_SQL);
+        }
+        Some(false) => {
+            builder.push(" AND NOT ").push(UPDATE_OVERDUE_SQL);
+        }
+        None => {}
+    }
+    if let Some(commander_id) = params.commander_id {
+        builder
+            .push(" AND m.commander_id = ")
+            .push_bind(commander_id);
+    }
+}
+
+/// Load one page of major incidents matching the filters in `params`, latest declared first.
+pub async fn load_page(
+    params: MajorIncidentListParams,
+    pool: &DbPool,
+) -> Result<Page<MajorIncident>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let major_incidents = pagination::load_page::<MajorIncident>(
+        &mut tx,
+        "
+        m.incident_id, i.title, i.status, i.priority, m.declared_at, m.commander_id,
+        m.update_cadence_minutes, u.last_update_at,
+        CASE
+            WHEN i.status NOT IN ('resolved', 'closed')
+            THEN COALESCE(u.last_update_at, m.declared_at)
+                + make_interval(mins => m.update_cadence_minutes)
+        END AS next_update_due_at,
+        COALESCE(i.status NOT IN ('resolved', 'closed')
+            AND COALESCE(u.last_update_at, m.declared_at)
+                + make_interval(mins => m.update_cadence_minutes) < now(),
+            false) AS update_overdue",
+        "
+        major_incidents AS m
+        INNER JOIN incidents AS i
+        ON i.id = m.incident_id
+        CROSS JOIN LATERAL (
+            SELECT max(c.sent_at) AS last_update_at
+            FROM major_incident_communications AS c
+            WHERE c.incident_id = m.incident_id
+        ) AS u",
+        |builder| push_filters(builder, &params),
+        "m.declared_at DESC, m.incident_id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(major_incidents)
+}
+
+async fn load_major_incident(
+    incident_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<MajorIncident, crate::Error> {
+    sqlx::query_as!(
+        MajorIncident,
+        "
+        SELECT m.incident_id, i.title, i.status as \"status: IncidentStatus\",
+            i.priority as \"priority: IncidentPrio\", m.declared_at, m.commander_id,
+            m.update_cadence_minutes, u.last_update_at,
+            CASE
+                WHEN i.status NOT IN ('resolved', 'closed')
+                THEN COALESCE(u.last_update_at, m.declared_at)
+                    + make_interval(mins => m.update_cadence_minutes)
+            END AS next_update_due_at,
+            COALESCE(i.status NOT IN ('resolved', 'closed')
+                AND COALESCE(u.last_update_at, m.declared_at)
+                    + make_interval(mins => m.update_cadence_minutes) < now(),
+                false) AS \"update_overdue!\"
+        FROM major_incidents AS m
+        INNER JOIN incidents AS i
+        ON i.id = m.incident_id
+        CROSS JOIN LATERAL (
+            SELECT max(c.sent_at) AS last_update_at
+            FROM major_incident_communications AS c
+            WHERE c.incident_id = m.incident_id
+        ) AS u
+        WHERE m.incident_id = $1",
+        incident_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Load a major incident. Fails with [crate::Error::NoRecordFound] if the Incident doesn't
+/// exist or isn't a major incident.
+pub async fn load(incident_id: Uuid, pool: &DbPool) -> Result<MajorIncident, crate::Error> {
+    let mut conn = pool.acquire().await?;
+    load_major_incident(incident_id, &mut conn).await
+}
+
+/// Map the errors of writing a major incident, which may reference a nonexistent commander.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Declare the Incident `incident_id` a major incident, or update it if it's one already.
+///
+/// Resolved Incidents can't be declared major incidents.
+pub async fn update(
+    incident_id: Uuid,
+    updateset: MajorIncidentUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<MajorIncident, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let current = sqlx::query!(
+        "
+        SELECT status as \"status: IncidentStatus\", major
+        FROM incidents
+        WHERE id = $1
+        FOR UPDATE",
+        incident_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    if !current.major && current.status.is_resolved() {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add(
+            "incident_id",
+            ValidationError::new("Resolved incidents can't be declared major incidents"),
+        );
+        return Err(errors.into());
+    }
+
+    declare(incident_id, None, &mut tx).await?;
+    sqlx::query!(
+        "
+        UPDATE major_incidents
+        SET commander_id = CASE
+                WHEN $1 THEN commander_id
+                ELSE $2
+            END,
+            update_cadence_minutes = COALESCE($3, update_cadence_minutes)
+        WHERE incident_id = $4",
+        updateset.commander_id.is_none(),
+        updateset.commander_id.unwrap_or(None),
+        updateset.update_cadence_minutes.unwrap_or(None),
+        incident_id,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+
+    let major_incident = load_major_incident(incident_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(major_incident)
+}
+
+/// Load the updates sent to the stakeholders of a major incident, oldest first.
+pub async fn load_communications(
+    incident_id: Uuid,
+    pool: &DbPool,
+) -> Result<Vec<Communication>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    load_major_incident(incident_id, &mut tx).await?;
+    let communications = sqlx::query_as!(
+        Communication,
+        "
+        SELECT id, incident_id, author, audience, message, sent_at
+        FROM major_incident_communications
+        WHERE incident_id = $1
+        ORDER BY sent_at, id",
+        incident_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(communications)
+}
+
+/// Log an update sent by `author` to the stakeholders of a major incident, which restarts
+/// the clock of its update cadence.
+pub async fn create_communication(
+    incident_id: Uuid,
+    createset: CommunicationCreateset,
+    author: &str,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Communication, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    load_major_incident(incident_id, &mut tx).await?;
+    let communication = sqlx::query_as!(
+        Communication,
+        "
+        INSERT INTO major_incident_communications (incident_id, author, audience, message)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, incident_id, author, audience, message, sent_at",
+        incident_id,
+        author,
+        createset.audience,
+        createset.message,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(communication)
+}
+
+/// Load a major incident along with its children and the Configuration Items, RFCs and
+/// Problems related to it or to them.
+pub async fn load_summary(
+    incident_id: Uuid,
+    pool: &DbPool,
+) -> Result<MajorIncidentSummary, crate::Error> {
+    let mut tx = pool.begin().await?;
+
+    let major_incident = load_major_incident(incident_id, &mut tx).await?;
+    let incident = incidents::load(incident_id, &mut *tx).await?;
+    let children = incidents::load_all_by_parent(incident_id, &mut *tx).await?;
+
+    let config_items = sqlx::query_as!(
+        ConfigItem,
+        "
+        SELECT id, name, status as \"status: CIStatus\", created_at, type, owner_team_id, description
+        FROM configitems
+        WHERE id IN (
+            SELECT r.ci_id
+            FROM incidents_ci_relations AS r
+            INNER JOIN incidents AS i
+            ON i.id = r.incident_id
+            WHERE i.id = $1 OR i.parent_id = $1
+        )
+        ORDER BY name, id",
+        incident_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let rfcs = sqlx::query_as!(
+        RFC,
+        "
+        SELECT id, title, type as \"type: RFCType\", status as \"status: RFCStatus\", created_at,
+            finished_at, requester_id, description, planned_start_at, planned_end_at,
+            actual_start_at, actual_end_at,
+            implementation_plan, backout_plan, test_plan, risk_score,
+            risk_category as \"risk_category: RiskCategory\"
+        FROM rfcs
+        WHERE id IN (
+            SELECT r.rfc_id
+            FROM rfc_incident_relations AS r
+            INNER JOIN incidents AS i
+            ON i.id = r.incident_id
+            WHERE i.id = $1 OR i.parent_id = $1
+        )
+        ORDER BY created_at, id",
+        incident_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let problems = sqlx::query_as!(
+        Problem,
+        "
+        SELECT id, title, status as \"status: ProblemStatus\", detection_timedate,
+            description, causes, workarounds, resolutions
+        FROM problems
+        WHERE id IN (
+            SELECT r.problem_id
+            FROM problem_incident_relations AS r
+            INNER JOIN incidents AS i
+            ON i.id = r.incident_id
+            WHERE i.id = $1 OR i.parent_id = $1
+        )
+        ORDER BY detection_timedate, id",
+        incident_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(MajorIncidentSummary {
+        major_incident,
+        incident,
+        children,
+        config_items,
+        rfcs,
+        problems,
+    })
+}
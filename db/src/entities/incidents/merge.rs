@@ -0,0 +1,227 @@
+use crate::entities::incidents::{self, sla, Incident, IncidentStatus};
+use crate::entity_helpers;
+use serde::Deserialize;
+#[cfg(feature = "test-helpers")]
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Payload for merging an Incident into another one.
+#[derive(Clone, Deserialize, ToSchema)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize))]
+pub struct IncidentMergeset {
+    /// Incident the merged one is a duplicate of, which survives the merge. If it's a child
+    /// of another Incident, the children of the merged one become children of that Incident.
+    pub survivor_id: Uuid,
+}
+
+/// Merge the Incident `id` into the survivor of `mergeset`, as a duplicate of it.
+///
+/// The Configuration Items, Problems and RFCs related to the duplicate and its timeline move
+/// to the survivor, and the duplicate is resolved and then closed with the `duplicate`
+/// resolution code and notes pointing to the survivor. Closing the duplicate doesn't need a
+/// related Problem even if it's a major incident, since its Problems went to the survivor.
+/// Neither Incident can be closed already.
+///
+/// Incidents are nested one level deep at most, so the children of the duplicate move to the
+/// survivor only if it's a top-level Incident. If the survivor is a child, they move to its
+/// parent instead, becoming siblings of the survivor. Merging a parent into one of its
+/// children makes the survivor a top-level Incident, so the other children move to it.
+pub async fn merge(
+    id: Uuid,
+    mergeset: IncidentMergeset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Incident, crate::Error> {
+    let survivor_id = mergeset.survivor_id;
+    if survivor_id == id {
+        return Err(entity_helpers::invalid_field(
+            "survivor_id",
+            "Incidents can't be merged into themselves",
+        ));
+    }
+
+    let mut tx = executor.begin().await?;
+
+    // Lock both Incidents in a fixed order, so concurrent merges don't deadlock.
+    let locked = sqlx::query!(
+        "
+        SELECT id, status as \"status: IncidentStatus\", parent_id
+        FROM incidents
+        WHERE id = $1 OR id = $2
+        ORDER BY id
+        FOR UPDATE",
+        id,
+        survivor_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let duplicate = locked
+        .iter()
+        .find(|i| i.id == id)
+        .ok_or(crate::Error::NoRecordFound)?;
+    let survivor = locked
+        .iter()
+        .find(|i| i.id == survivor_id)
+        .ok_or(crate::Error::ConstraintError)?;
+    if duplicate.status == IncidentStatus::Closed {
+        return Err(entity_helpers::invalid_field(
+            "id",
+            "Closed incidents can't be merged",
+        ));
+    }
+    if survivor.status == IncidentStatus::Closed {
+        return Err(entity_helpers::invalid_field(
+            "survivor_id",
+            "Incidents can't be merged into closed incidents",
+        ));
+    }
+
+    // Relations the survivor has already are dropped rather than moved.
+    sqlx::query!(
+        "
+        DELETE FROM incidents_ci_relations AS d
+        WHERE d.incident_id = $1
+        AND EXISTS(
+            SELECT 1 FROM incidents_ci_relations AS s
+            WHERE s.incident_id = $2
+            AND s.ci_id = d.ci_id
+        )",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE incidents_ci_relations
+        SET incident_id = $2
+        WHERE incident_id = $1",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "
+        DELETE FROM problem_incident_relations AS d
+        WHERE d.incident_id = $1
+        AND EXISTS(
+            SELECT 1 FROM problem_incident_relations AS s
+            WHERE s.incident_id = $2
+            AND s.problem_id = d.problem_id
+        )",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE problem_incident_relations
+        SET incident_id = $2
+        WHERE incident_id = $1",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "
+        DELETE FROM rfc_incident_relations AS d
+        WHERE d.incident_id = $1
+        AND EXISTS(
+            SELECT 1 FROM rfc_incident_relations AS s
+            WHERE s.incident_id = $2
+            AND s.rfc_id = d.rfc_id
+            AND s.relation_type = d.relation_type
+        )",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE rfc_incident_relations
+        SET incident_id = $2
+        WHERE incident_id = $1",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "
+        UPDATE timeline_entries
+        SET entity_id = $2
+        WHERE entity_type = 'incident'
+        AND entity_id = $1",
+        id,
+        survivor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Children of the duplicate go to the survivor, or to its parent if it's a child, so
+    // Incidents stay nested one level deep at most.
+    let survivor_parent_id = if survivor.parent_id == Some(id) {
+        duplicate.parent_id
+    } else {
+        survivor.parent_id
+    };
+    sqlx::query!(
+        "
+        UPDATE incidents
+        SET parent_id = $2
+        WHERE id = $1",
+        survivor_id,
+        survivor_parent_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE incidents
+        SET parent_id = $2
+        WHERE parent_id = $1",
+        id,
+        survivor_parent_id.unwrap_or(survivor_id)
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // The duplicate is resolved and then closed, like any other Incident, so it keeps the
+    // time it was resolved at if it's resolved already.
+    let mut status = duplicate.status;
+    for next in [IncidentStatus::Resolved, IncidentStatus::Closed] {
+        if status == next {
+            continue;
+        }
+        entity_helpers::check_transition(status, next)?;
+        sqlx::query!(
+            "
+            UPDATE incidents
+            SET status = $2, resolved_at = COALESCE(resolved_at, now()), hold_reason = NULL,
+                resolution_code = 'duplicate', resolution_notes = $3, duplicate_of_id = $4,
+                responded_at = COALESCE(responded_at, now())
+            WHERE id = $1",
+            id,
+            next as IncidentStatus,
+            format!("Duplicate of incident {}.", survivor_id),
+            survivor_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sla::record_transition(id, Some(status), next, &mut *tx).await?;
+        status = next;
+    }
+    sla::refresh(id, &mut tx).await?;
+
+    let survivor = incidents::load(survivor_id, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(survivor)
+}
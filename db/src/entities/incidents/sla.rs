@@ -0,0 +1,212 @@
+use crate::entities::incidents::{IncidentPrio, IncidentStatus};
+use crate::entities::sla_policies::{self, BusinessCalendar};
+use chrono::{DateTime, TimeDelta, Utc};
+#[cfg(feature = "test-helpers")]
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::PgConnection;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Standing of an Incident against the targets of its SLA policy.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "test-helpers", derive(Deserialize))]
+pub struct IncidentSla {
+    pub incident_id: Uuid,
+    /// Policy applying to the Incident. Incidents without policy, or whose priority has no
+    /// target in it, have no deadlines and never breach.
+    pub sla_policy_id: Option<Uuid>,
+    pub priority: IncidentPrio,
+    pub response_due_at: Option<DateTime<Utc>>,
+    pub resolution_due_at: Option<DateTime<Utc>>,
+    /// When the Incident first left `new`.
+    pub responded_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Business minutes the Incident has been open, not counting the time on hold.
+    #[schema(example = 95)]
+    pub elapsed_minutes: i64,
+    /// Business minutes the Incident has spent on hold.
+    #[schema(example = 20)]
+    pub paused_minutes: i64,
+    /// Whether the clock is stopped because the Incident is on hold.
+    pub paused: bool,
+    pub response_breached: bool,
+    pub resolution_breached: bool,
+}
+
+/// Periods an Incident spent on hold, oldest first. The period of an Incident on hold
+/// right now has no end.
+async fn load_hold_periods(
+    incident_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Vec<(DateTime<Utc>, Option<DateTime<Utc>>)>, crate::Error> {
+    let periods = sqlx::query!(
+        "
+        SELECT started_at, ended_at
+        FROM incident_hold_periods
+        WHERE incident_id = $1
+        ORDER BY started_at",
+        incident_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(periods
+        .into_iter()
+        .map(|period| (period.started_at, period.ended_at))
+        .collect())
+}
+
+/// Record that an Incident moved from status `from` to `to`, opening or closing its
+/// current hold period.
+pub async fn record_transition(
+    incident_id: Uuid,
+    from: Option<IncidentStatus>,
+    to: IncidentStatus,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let on_hold = |status| status == Some(IncidentStatus::OnHold);
+    if on_hold(from) == on_hold(Some(to)) {
+        return Ok(());
+    }
+
+    if on_hold(Some(to)) {
+        sqlx::query!(
+            "
+            INSERT INTO incident_hold_periods (incident_id, started_at)
+            VALUES ($1, now())",
+            incident_id
+        )
+        .execute(executor)
+        .await?;
+    } else {
+        sqlx::query!(
+            "
+            UPDATE incident_hold_periods
+            SET ended_at = now()
+            WHERE incident_id = $1
+            AND ended_at IS NULL",
+            incident_id
+        )
+        .execute(executor)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Recompute the deadlines of an Incident from its policy, priority and hold periods.
+///
+/// The period the Incident is on hold right now isn't taken into account, so deadlines
+/// move forward when the Incident leaves `onhold` rather than while it's there. Policies
+/// only have reachable targets, but a deadline pushed out of reach by years on hold is left
+/// unset (see [sla_policies::BusinessCalendar::deadline]).
+pub async fn refresh(incident_id: Uuid, conn: &mut PgConnection) -> Result<(), crate::Error> {
+    let incident = sqlx::query!(
+        "
+        SELECT created_at, priority as \"priority: IncidentPrio\", sla_policy_id
+        FROM incidents
+        WHERE id = $1",
+        incident_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let mut response_due_at = None;
+    let mut resolution_due_at = None;
+    if let Some(policy_id) = incident.sla_policy_id {
+        let policy = sla_policies::fetch(policy_id, conn).await?;
+        if let Some(target) = policy.target(incident.priority) {
+            let pauses: Vec<_> = load_hold_periods(incident_id, &mut *conn)
+                .await?
+                .into_iter()
+                .filter_map(|(start, end)| Some((start, end?)))
+                .collect();
+            let calendar = policy.calendar();
+            response_due_at = calendar.deadline(
+                incident.created_at,
+                TimeDelta::minutes(target.response_minutes.into()),
+                &[],
+            );
+            resolution_due_at = calendar.deadline(
+                incident.created_at,
+                TimeDelta::minutes(target.resolution_minutes.into()),
+                &pauses,
+            );
+        }
+    }
+
+    sqlx::query!(
+        "
+        UPDATE incidents
+        SET response_due_at = $1, resolution_due_at = $2
+        WHERE id = $3",
+        response_due_at,
+        resolution_due_at,
+        incident_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Load the standing of an Incident against its SLA as of now.
+pub async fn load(
+    incident_id: Uuid,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<IncidentSla, crate::Error> {
+    let mut conn = executor.acquire().await?;
+
+    let incident = sqlx::query!(
+        "
+        SELECT created_at, priority as \"priority: IncidentPrio\", sla_policy_id, responded_at,
+            resolved_at, response_due_at, resolution_due_at
+        FROM incidents
+        WHERE id = $1",
+        incident_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let calendar = match incident.sla_policy_id {
+        Some(policy_id) => sla_policies::fetch(policy_id, &mut conn).await?.calendar(),
+        None => BusinessCalendar::default(),
+    };
+    let now = Utc::now();
+    let periods = load_hold_periods(incident_id, &mut *conn).await?;
+    let hold_started_at = periods
+        .iter()
+        .find(|(_, end)| end.is_none())
+        .map(|(start, _)| *start);
+    let pauses: Vec<_> = periods
+        .into_iter()
+        .map(|(start, end)| (start, end.unwrap_or(now)))
+        .collect();
+
+    // The resolution clock stops when the Incident is resolved, or while it's on hold.
+    let clock = incident.resolved_at.or(hold_started_at).unwrap_or(now);
+    let end = incident.resolved_at.unwrap_or(now);
+    let elapsed = calendar.elapsed(incident.created_at, end, &pauses);
+    let total = calendar.elapsed(incident.created_at, end, &[]);
+
+    Ok(IncidentSla {
+        incident_id,
+        sla_policy_id: incident.sla_policy_id,
+        priority: incident.priority,
+        response_due_at: incident.response_due_at,
+        resolution_due_at: incident.resolution_due_at,
+        responded_at: incident.responded_at,
+        resolved_at: incident.resolved_at,
+        elapsed_minutes: elapsed.num_minutes(),
+        paused_minutes: (total - elapsed).num_minutes(),
+        paused: hold_started_at.is_some(),
+        response_breached: incident
+            .response_due_at
+            .is_some_and(|due| due < incident.responded_at.unwrap_or(now)),
+        resolution_breached: incident.resolution_due_at.is_some_and(|due| due < clock),
+    })
+}
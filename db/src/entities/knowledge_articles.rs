@@ -0,0 +1,723 @@
+use crate::entities::known_errors::{self, KnownError};
+use crate::entity_helpers::{self, Lifecycle};
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, QueryBuilder, Type};
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+pub mod versions;
+
+/// Article of the knowledge base, written in Markdown.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct KnowledgeArticle {
+    pub id: Uuid,
+    #[schema(example = "Printing large documents")]
+    pub title: String,
+    #[schema(example = "Split the job in smaller ones.")]
+    pub body: String,
+    pub category_id: Option<Uuid>,
+    pub status: KnowledgeArticleStatus,
+    /// Number of the latest version of the title and body, starting at 1 (see
+    /// [versions::KnowledgeArticleVersion]).
+    pub version: i32,
+    /// Known error the article was drafted from, if any.
+    pub known_error_id: Option<Uuid>,
+    /// Configuration Items the article is about.
+    pub ci_ids: Vec<Uuid>,
+    /// Problems the article is about.
+    pub problem_ids: Vec<Uuid>,
+    /// Incidents the article is about.
+    pub incident_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When the article was last published.
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// Status of a knowledge article. Only drafts can be edited, and only published articles are
+/// found by searches.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "knowledge_article_status", rename_all = "lowercase")]
+#[schema(example = "review")]
+pub enum KnowledgeArticleStatus {
+    Draft,
+    Review,
+    Published,
+}
+
+impl Lifecycle for KnowledgeArticleStatus {
+    fn next(&self) -> &'static [Self] {
+        use KnowledgeArticleStatus::*;
+        match self {
+            Draft => &[Review],
+            Review => &[Draft, Published],
+            Published => &[Draft],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Review => "review",
+            Self::Published => "published",
+        }
+    }
+}
+
+/// Payload for creating a knowledge article, which starts as a draft.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnowledgeArticleCreateset {
+    #[schema(example = "Printing large documents")]
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+    #[schema(example = "Split the job in smaller ones.")]
+    #[validate(length(max = 65536))]
+    #[serde(default)]
+    pub body: String,
+    pub category_id: Option<Uuid>,
+    #[serde(default)]
+    pub ci_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub problem_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub incident_ids: Vec<Uuid>,
+}
+
+/// Payload for updating a knowledge article.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnowledgeArticleUpdateset {
+    /// Can only change on drafts, and creates a new version.
+    #[schema(example = "Printing large documents")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub title: Option<Option<String>>,
+    /// Can only change on drafts, and creates a new version.
+    #[schema(example = "Split the job in smaller ones.")]
+    #[validate(length(max = 65536))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub body: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub category_id: Option<Option<Uuid>>,
+    /// Must be reachable from the current status (see [KnowledgeArticleStatus::next]).
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub status: Option<Option<KnowledgeArticleStatus>>,
+    /// Replaces the current Configuration Items.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub ci_ids: Option<Option<Vec<Uuid>>>,
+    /// Replaces the current Problems.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub problem_ids: Option<Option<Vec<Uuid>>>,
+    /// Replaces the current Incidents.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub incident_ids: Option<Option<Vec<Uuid>>>,
+}
+
+/// Validate that required fields of [KnowledgeArticleUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &KnowledgeArticleUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.title)?;
+    entity_helpers::validate_not_null(&updateset.body)?;
+    entity_helpers::validate_not_null(&updateset.status)?;
+    entity_helpers::validate_not_null(&updateset.ci_ids)?;
+    entity_helpers::validate_not_null(&updateset.problem_ids)?;
+    entity_helpers::validate_not_null(&updateset.incident_ids)?;
+
+    Ok(())
+}
+
+/// Query parameters for listing knowledge articles.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnowledgeArticleListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    pub status: Option<KnowledgeArticleStatus>,
+    pub category_id: Option<Uuid>,
+    /// Only articles about this Configuration Item.
+    pub ci_id: Option<Uuid>,
+    /// Only articles about this Problem.
+    pub problem_id: Option<Uuid>,
+    /// Only articles about this Incident.
+    pub incident_id: Option<Uuid>,
+}
+
+/// Append the `WHERE` clause matching the filters in [KnowledgeArticleListParams].
+fn push_filters<'a>(
+    builder: &mut QueryBuilder<'a, Postgres>,
+    params: &'a KnowledgeArticleListParams,
+) {
+    builder.push(" WHERE TRUE");
+    if let Some(status) = params.status {
+        builder.push(" AND a.status = ").push_bind(status);
+    }
+    if let Some(category_id) = params.category_id {
+        builder.push(" AND a.category_id = ").push_bind(category_id);
+    }
+    if let Some(ci_id) = params.ci_id {
+        builder
+            .push(
+                " AND EXISTS(SELECT 1 FROM knowledge_article_cis AS c
+                WHERE c.article_id = a.id AND c.ci_id = ",
+            )
+            .push_bind(ci_id)
+            .push(")");
+    }
+    if let Some(problem_id) = params.problem_id {
+        builder
+            .push(
+                " AND EXISTS(SELECT 1 FROM knowledge_article_problems AS p
+                WHERE p.article_id = a.id AND p.problem_id = ",
+            )
+            .push_bind(problem_id)
+            .push(")");
+    }
+    if let Some(incident_id) = params.incident_id {
+        builder
+            .push(
+                " AND EXISTS(SELECT 1 FROM knowledge_article_incidents AS i
+                WHERE i.article_id = a.id AND i.incident_id = ",
+            )
+            .push_bind(incident_id)
+            .push(")");
+    }
+}
+
+/// Query parameters for searching the published knowledge articles.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[validate(schema(function = "validate_search_text"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnowledgeArticleSearchParams {
+    /// Text to match against the title and body of articles. Any of its words matches.
+    #[validate(length(min = 1, max = 1024))]
+    pub q: Option<String>,
+    /// Rank articles for this Incident instead of `q`: they match its title and description,
+    /// and rank higher when they are about the Incident itself, its Configuration Items or
+    /// its Problems.
+    pub incident_id: Option<Uuid>,
+    /// Max amount of matches. Defaults to 20.
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+}
+
+/// Validate that [KnowledgeArticleSearchParams] give exactly one text to match.
+fn validate_search_text(params: &KnowledgeArticleSearchParams) -> Result<(), ValidationError> {
+    if params.q.is_some() == params.incident_id.is_some() {
+        return Err(ValidationError::new("Search either by text or by Incident"));
+    }
+
+    Ok(())
+}
+
+/// Knowledge article matching a search.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct KnowledgeArticleMatch {
+    /// Relevance of the match. Higher is better.
+    pub rank: f32,
+    pub article: KnowledgeArticle,
+}
+
+/// Map the errors of writing a knowledge article, which may reference nonexistent
+/// categories, Configuration Items, Problems or Incidents.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+async fn load_article(id: Uuid, conn: &mut PgConnection) -> Result<KnowledgeArticle, crate::Error> {
+    sqlx::query_as!(
+        KnowledgeArticle,
+        "
+        SELECT id, title, body, category_id, status as \"status: KnowledgeArticleStatus\",
+            version, known_error_id,
+            ARRAY(
+                SELECT ci_id FROM knowledge_article_cis AS c
+                WHERE c.article_id = a.id
+                ORDER BY ci_id
+            ) AS \"ci_ids!\",
+            ARRAY(
+                SELECT problem_id FROM knowledge_article_problems AS p
+                WHERE p.article_id = a.id
+                ORDER BY problem_id
+            ) AS \"problem_ids!\",
+            ARRAY(
+                SELECT incident_id FROM knowledge_article_incidents AS i
+                WHERE i.article_id = a.id
+                ORDER BY incident_id
+            ) AS \"incident_ids!\",
+            created_at, updated_at, published_at
+        FROM knowledge_articles AS a
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Record the current title and body of the article `id` as its current version.
+async fn save_version(id: Uuid, conn: &mut PgConnection) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        INSERT INTO knowledge_article_versions (article_id, version, title, body)
+        SELECT id, version, title, body
+        FROM knowledge_articles
+        WHERE id = $1",
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Replace the Configuration Items, Problems and Incidents the article `id` is about, leaving
+/// alone those passed as `None`.
+async fn replace_links(
+    id: Uuid,
+    ci_ids: Option<Vec<Uuid>>,
+    problem_ids: Option<Vec<Uuid>>,
+    incident_ids: Option<Vec<Uuid>>,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    if let Some(ci_ids) = ci_ids {
+        sqlx::query!(
+            "
+            DELETE FROM knowledge_article_cis
+            WHERE article_id = $1
+            AND ci_id <> ALL($2)",
+            id,
+            &ci_ids,
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "
+            INSERT INTO knowledge_article_cis (article_id, ci_id)
+            SELECT $1, ci_id FROM UNNEST($2::uuid[]) AS ci_id
+            ON CONFLICT DO NOTHING",
+            id,
+            &ci_ids,
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(map_write_error)?;
+    }
+
+    if let Some(problem_ids) = problem_ids {
+        sqlx::query!(
+            "
+            DELETE FROM knowledge_article_problems
+            WHERE article_id = $1
+            AND problem_id <> ALL($2)",
+            id,
+            &problem_ids,
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "
+            INSERT INTO knowledge_article_problems (article_id, problem_id)
+            SELECT $1, problem_id FROM UNNEST($2::uuid[]) AS problem_id
+            ON CONFLICT DO NOTHING",
+            id,
+            &problem_ids,
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(map_write_error)?;
+    }
+
+    if let Some(incident_ids) = incident_ids {
+        sqlx::query!(
+            "
+            DELETE FROM knowledge_article_incidents
+            WHERE article_id = $1
+            AND incident_id <> ALL($2)",
+            id,
+            &incident_ids,
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query!(
+            "
+            INSERT INTO knowledge_article_incidents (article_id, incident_id)
+            SELECT $1, incident_id FROM UNNEST($2::uuid[]) AS incident_id
+            ON CONFLICT DO NOTHING",
+            id,
+            &incident_ids,
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(map_write_error)?;
+    }
+
+    Ok(())
+}
+
+/// Load one page of knowledge articles matching the filters in `params`, most recently
+/// updated first.
+pub async fn load_page(
+    params: KnowledgeArticleListParams,
+    pool: &DbPool,
+) -> Result<Page<KnowledgeArticle>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let articles = pagination::load_page::<KnowledgeArticle>(
+        &mut tx,
+        "
+        id, title, body, category_id, status, version, known_error_id,
+        ARRAY(
+            SELECT ci_id FROM knowledge_article_cis AS c
+            WHERE c.article_id = a.id
+            ORDER BY ci_id
+        ) AS ci_ids,
+        ARRAY(
+            SELECT problem_id FROM knowledge_article_problems AS p
+            WHERE p.article_id = a.id
+            ORDER BY problem_id
+        ) AS problem_ids,
+        ARRAY(
+            SELECT incident_id FROM knowledge_article_incidents AS i
+            WHERE i.article_id = a.id
+            ORDER BY incident_id
+        ) AS incident_ids,
+        created_at, updated_at, published_at",
+        "knowledge_articles AS a",
+        |builder| push_filters(builder, &params),
+        "updated_at DESC, id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(articles)
+}
+
+pub async fn load(id: Uuid, pool: &DbPool) -> Result<KnowledgeArticle, crate::Error> {
+    let mut tx = pool.begin().await?;
+    let article = load_article(id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(article)
+}
+
+pub async fn create(
+    createset: KnowledgeArticleCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<KnowledgeArticle, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let id = sqlx::query_scalar!(
+        "
+        INSERT INTO knowledge_articles (title, body, category_id)
+        VALUES ($1, $2, $3)
+        RETURNING id",
+        createset.title,
+        createset.body,
+        createset.category_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+    save_version(id, &mut tx).await?;
+    replace_links(
+        id,
+        Some(createset.ci_ids),
+        Some(createset.problem_ids),
+        Some(createset.incident_ids),
+        &mut tx,
+    )
+    .await?;
+
+    let article = load_article(id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(article)
+}
+
+/// Update a knowledge article.
+///
+/// Fails with [crate::Error::InvalidTransition] if the new status isn't reachable from the
+/// current one, and with [crate::Error::NotAllowed] if the title or body change on an article
+/// that isn't a draft after the update. Changing them creates a new version.
+pub async fn update(
+    id: Uuid,
+    updateset: KnowledgeArticleUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<KnowledgeArticle, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let current = sqlx::query!(
+        "
+        SELECT title, body, status as \"status: KnowledgeArticleStatus\"
+        FROM knowledge_articles
+        WHERE id = $1
+        FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let status = updateset.status.flatten().unwrap_or(current.status);
+    entity_helpers::check_transition(current.status, status)?;
+
+    let title = updateset.title.flatten().unwrap_or(current.title.clone());
+    let body = updateset.body.flatten().unwrap_or(current.body.clone());
+    let edited = title != current.title || body != current.body;
+    if edited && status != KnowledgeArticleStatus::Draft {
+        return Err(crate::Error::NotAllowed("Only drafts can be edited"));
+    }
+
+    sqlx::query!(
+        "
+        UPDATE knowledge_articles
+        SET title = $1, body = $2,
+            category_id = CASE
+                WHEN $3 THEN category_id
+                ELSE $4
+            END,
+            status = $5,
+            version = CASE
+                WHEN $6 THEN version + 1
+                ELSE version
+            END,
+            published_at = CASE
+                WHEN $7 THEN now()
+                ELSE published_at
+            END,
+            updated_at = now()
+        WHERE id = $8",
+        title,
+        body,
+        updateset.category_id.is_none(),
+        updateset.category_id.unwrap_or(None),
+        status as KnowledgeArticleStatus,
+        edited,
+        status == KnowledgeArticleStatus::Published && current.status != status,
+        id,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+    if edited {
+        save_version(id, &mut tx).await?;
+    }
+    replace_links(
+        id,
+        updateset.ci_ids.flatten(),
+        updateset.problem_ids.flatten(),
+        updateset.incident_ids.flatten(),
+        &mut tx,
+    )
+    .await?;
+
+    let article = load_article(id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(article)
+}
+
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM knowledge_articles
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Search the published knowledge articles matching `params`, most relevant first.
+pub async fn search(
+    params: KnowledgeArticleSearchParams,
+    pool: &DbPool,
+) -> Result<Vec<KnowledgeArticleMatch>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let text = match (params.q, params.incident_id) {
+        (Some(q), _) => q,
+        (None, Some(incident_id)) => sqlx::query_scalar!(
+            "
+            SELECT title || ' ' || description AS \"text!\"
+            FROM incidents
+            WHERE id = $1",
+            incident_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(crate::Error::NoRecordFound)?,
+        (None, None) => unreachable!("validated by validate_search_text"),
+    };
+
+    // Articles about the Incident, its Configuration Items or its Problems match even without
+    // common words.
+    let rows = sqlx::query!(
+        "
+        WITH query AS (
+            SELECT any_word_tsquery($1) AS q
+        ), scored AS (
+            SELECT a.id,
+                knowledge_article_document(a.title, a.body) @@ query.q AS matches,
+                ts_rank(knowledge_article_document(a.title, a.body), query.q) AS text_rank,
+                CASE WHEN EXISTS(
+                    SELECT 1 FROM knowledge_article_incidents AS i
+                    WHERE i.article_id = a.id
+                    AND i.incident_id = $2
+                ) THEN 1::real ELSE 0::real END
+                + CASE WHEN EXISTS(
+                    SELECT 1 FROM knowledge_article_cis AS c
+                    JOIN incidents_ci_relations AS r ON r.ci_id = c.ci_id
+                    WHERE c.article_id = a.id
+                    AND r.incident_id = $2
+                ) THEN 0.5::real ELSE 0::real END
+                + CASE WHEN EXISTS(
+                    SELECT 1 FROM knowledge_article_problems AS p
+                    JOIN problem_incident_relations AS r ON r.problem_id = p.problem_id
+                    WHERE p.article_id = a.id
+                    AND r.incident_id = $2
+                ) THEN 0.5::real ELSE 0::real END AS boost
+            FROM knowledge_articles AS a
+            CROSS JOIN query
+            WHERE a.status = 'published'
+        )
+        SELECT id, (text_rank + boost)::real AS \"rank!\"
+        FROM scored
+        WHERE matches OR boost > 0
+        ORDER BY 2 DESC, id
+        LIMIT $3",
+        text,
+        params.incident_id,
+        params.limit.unwrap_or(20),
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut matches = Vec::new();
+    for row in rows {
+        matches.push(KnowledgeArticleMatch {
+            rank: row.rank,
+            article: load_article(row.id, &mut tx).await?,
+        });
+    }
+
+    tx.commit().await?;
+    Ok(matches)
+}
+
+/// Markdown body of the article drafted from `known_error`.
+fn known_error_body(known_error: &KnownError) -> String {
+    let mut body = format!(
+        "## Symptoms\n\n{}\n\n## Cause\n\n{}\n",
+        known_error.symptoms, known_error.root_cause
+    );
+    if !known_error.workaround_steps.is_empty() {
+        body.push_str("\n## Workaround\n\n");
+        for (i, step) in known_error.workaround_steps.iter().enumerate() {
+            body.push_str(&format!("{}. {}\n", i + 1, step));
+        }
+    }
+
+    body
+}
+
+/// Draft a knowledge article from the known error of the Problem `problem_id`, unless one was
+/// drafted already.
+///
+/// The article is named after the Problem and is about it and the Configuration Items of the
+/// known error.
+pub(crate) async fn create_for_known_error(
+    problem_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let known_error = known_errors::load_known_error(problem_id, conn).await?;
+
+    let id = sqlx::query_scalar!(
+        "
+        INSERT INTO knowledge_articles (title, body, known_error_id)
+        SELECT title, $1, id
+        FROM problems
+        WHERE id = $2
+        ON CONFLICT (known_error_id) DO NOTHING
+        RETURNING id",
+        known_error_body(&known_error),
+        problem_id,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(id) = id {
+        save_version(id, conn).await?;
+        replace_links(
+            id,
+            Some(known_error.ci_ids),
+            Some(vec![problem_id]),
+            None,
+            conn,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+#[cfg(any(feature = "test-helpers", test))]
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Title and body of a knowledge article as they were at one of its versions.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct KnowledgeArticleVersion {
+    pub article_id: Uuid,
+    /// Number of the version, starting at 1.
+    pub version: i32,
+    #[schema(example = "Printing large documents")]
+    pub title: String,
+    #[schema(example = "Split the job in smaller ones.")]
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Load all the versions of a knowledge article, oldest first.
+pub async fn load_all(
+    article_id: Uuid,
+    pool: &DbPool,
+) -> Result<Vec<KnowledgeArticleVersion>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    super::load_article(article_id, &mut tx).await?;
+    let versions = sqlx::query_as!(
+        KnowledgeArticleVersion,
+        "
+        SELECT article_id, version, title, body, created_at
+        FROM knowledge_article_versions
+        WHERE article_id = $1
+        ORDER BY version",
+        article_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(versions)
+}
+
+pub async fn load(
+    article_id: Uuid,
+    version: i32,
+    pool: &DbPool,
+) -> Result<KnowledgeArticleVersion, crate::Error> {
+    sqlx::query_as!(
+        KnowledgeArticleVersion,
+        "
+        SELECT article_id, version, title, body, created_at
+        FROM knowledge_article_versions
+        WHERE article_id = $1
+        AND version = $2",
+        article_id,
+        version
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
@@ -0,0 +1,176 @@
+use crate::entity_helpers;
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Category of knowledge articles (see [super::knowledge_articles]).
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct KnowledgeCategory {
+    pub id: Uuid,
+    #[schema(example = "Troubleshooting")]
+    pub name: String,
+    #[schema(example = "Diagnosing and fixing a recurring issue.")]
+    pub description: String,
+}
+
+/// Payload for creating a knowledge category.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnowledgeCategoryCreateset {
+    #[schema(example = "Troubleshooting")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[schema(example = "Diagnosing and fixing a recurring issue.")]
+    #[validate(length(max = 1024))]
+    pub description: String,
+}
+
+/// Payload for updating a knowledge category.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnowledgeCategoryUpdateset {
+    #[schema(example = "Troubleshooting")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<Option<String>>,
+    #[schema(example = "Diagnosing and fixing a recurring issue.")]
+    #[validate(length(max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+}
+
+/// Validate that required fields of [KnowledgeCategoryUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &KnowledgeCategoryUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.name)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+
+    Ok(())
+}
+
+/// Map the errors of writing a knowledge category, which may break the uniqueness of names
+/// or leave articles without category.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_unique_violation() || dbe.is_foreign_key_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load all the categories, sorted by name.
+pub async fn load_all(pool: &DbPool) -> Result<Vec<KnowledgeCategory>, crate::Error> {
+    let categories = sqlx::query_as!(
+        KnowledgeCategory,
+        "
+        SELECT id, name, description
+        FROM knowledge_categories
+        ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(categories)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<KnowledgeCategory, crate::Error> {
+    sqlx::query_as!(
+        KnowledgeCategory,
+        "
+        SELECT id, name, description
+        FROM knowledge_categories
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+pub async fn create(
+    createset: KnowledgeCategoryCreateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<KnowledgeCategory, crate::Error> {
+    createset.validate()?;
+
+    let category = sqlx::query_as!(
+        KnowledgeCategory,
+        "
+        INSERT INTO knowledge_categories (name, description)
+        VALUES ($1, $2)
+        RETURNING id, name, description",
+        createset.name,
+        createset.description,
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(map_write_error)?;
+
+    Ok(category)
+}
+
+pub async fn update(
+    id: Uuid,
+    updateset: KnowledgeCategoryUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<KnowledgeCategory, crate::Error> {
+    updateset.validate()?;
+
+    sqlx::query_as!(
+        KnowledgeCategory,
+        "
+        UPDATE knowledge_categories
+        SET name = COALESCE($1, name), description = COALESCE($2, description)
+        WHERE id = $3
+        RETURNING id, name, description",
+        updateset.name.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        id,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Delete a knowledge category. Fails with [crate::Error::ConstraintError] while some
+/// article is filed under it.
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM knowledge_categories
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
@@ -0,0 +1,387 @@
+use crate::entities::knowledge_articles;
+use crate::entities::problems::{incident_relations, ProblemStatus};
+use crate::entity_helpers;
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres};
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Most workaround steps a known error can have.
+pub const MAX_WORKAROUND_STEPS: usize = 50;
+
+/// Known error in the Known Error Database, recorded when its Problem enters the known error
+/// status.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct KnownError {
+    pub problem_id: Uuid,
+    #[schema(example = "Bandwidth doesn't go above 1Mb/s on the main office network.")]
+    pub symptoms: String,
+    #[schema(example = "Router is misconfigured and doesn't do hardware offloading.")]
+    pub root_cause: String,
+    /// Steps to work around the error until the Problem is resolved, in order.
+    #[schema(example = json!(["Connect to the guest network.", "Log in to the VPN."]))]
+    pub workaround_steps: Vec<String>,
+    /// Configuration Items affected by the error.
+    pub ci_ids: Vec<Uuid>,
+    /// Whether service desk agents find the error when searching the database.
+    pub published: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Payload for updating a known error.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[validate(schema(function = "validate_workaround_steps"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnownErrorUpdateset {
+    #[schema(example = "Bandwidth doesn't go above 1Mb/s on the main office network.")]
+    #[validate(length(min = 1, max = 4096))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub symptoms: Option<Option<String>>,
+    #[schema(example = "Router is misconfigured and doesn't do hardware offloading.")]
+    #[validate(length(min = 1, max = 4096))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub root_cause: Option<Option<String>>,
+    /// Replaces the current steps. At most [MAX_WORKAROUND_STEPS], none of them empty.
+    #[schema(example = json!(["Connect to the guest network.", "Log in to the VPN."]))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub workaround_steps: Option<Option<Vec<String>>>,
+    /// Replaces the current Configuration Items.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub ci_ids: Option<Option<Vec<Uuid>>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub published: Option<Option<bool>>,
+    /// When this update publishes the known error, also draft a knowledge article from it,
+    /// unless one was drafted already.
+    #[serde(default)]
+    pub draft_article: bool,
+}
+
+/// Validate that required fields of [KnownErrorUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &KnownErrorUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.symptoms)?;
+    entity_helpers::validate_not_null(&updateset.root_cause)?;
+    entity_helpers::validate_not_null(&updateset.workaround_steps)?;
+    entity_helpers::validate_not_null(&updateset.ci_ids)?;
+    entity_helpers::validate_not_null(&updateset.published)?;
+
+    Ok(())
+}
+
+/// Validate that the workaround steps of a [KnownErrorUpdateset] aren't too many, nor empty
+/// or too long.
+fn validate_workaround_steps(updateset: &KnownErrorUpdateset) -> Result<(), ValidationError> {
+    let Some(Some(steps)) = &updateset.workaround_steps else {
+        return Ok(());
+    };
+    if steps.len() > MAX_WORKAROUND_STEPS {
+        return Err(ValidationError::new("Too many workaround steps"));
+    }
+    if steps.iter().any(|s| s.is_empty() || s.len() > 1024) {
+        return Err(ValidationError::new(
+            "Workaround steps must have between 1 and 1024 characters",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Query parameters for searching the Known Error Database.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[validate(schema(function = "validate_search_text"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct KnownErrorSearchParams {
+    /// Text to match against the symptoms and root cause of known errors. Any of its words
+    /// matches.
+    #[validate(length(min = 1, max = 1024))]
+    pub q: Option<String>,
+    /// Match the title and description of this Incident instead of `q`.
+    pub incident_id: Option<Uuid>,
+    /// Only known errors affecting this Configuration Item.
+    pub ci_id: Option<Uuid>,
+    /// Max amount of matches. Defaults to 20.
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+}
+
+/// Validate that [KnownErrorSearchParams] give exactly one text to match.
+fn validate_search_text(params: &KnownErrorSearchParams) -> Result<(), ValidationError> {
+    if params.q.is_some() == params.incident_id.is_some() {
+        return Err(ValidationError::new("Search either by text or by Incident"));
+    }
+
+    Ok(())
+}
+
+/// Known error matching a search, along with its Problem.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct KnownErrorMatch {
+    #[schema(example = "Low Bandwidth in Office")]
+    pub title: String,
+    pub status: ProblemStatus,
+    /// Relevance of the match. Higher is better.
+    pub rank: f32,
+    pub known_error: KnownError,
+}
+
+/// Record the Problem `problem_id` in the Known Error Database, unless it's there already.
+///
+/// The known error starts unpublished, with the description, causes and workarounds of the
+/// Problem, and affecting the Configuration Items of its Incidents.
+pub async fn create_for_problem(
+    problem_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let created = sqlx::query!(
+        "
+        INSERT INTO known_errors (problem_id, symptoms, root_cause, workaround_steps)
+        SELECT id, description, causes,
+            CASE
+                WHEN workarounds IS NULL THEN '{}'
+                ELSE ARRAY[workarounds]
+            END
+        FROM problems
+        WHERE id = $1
+        ON CONFLICT (problem_id) DO NOTHING
+        RETURNING problem_id",
+        problem_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if created.is_some() {
+        sqlx::query!(
+            "
+            INSERT INTO known_error_cis (problem_id, ci_id)
+            SELECT DISTINCT r.problem_id, c.ci_id
+            FROM problem_incident_relations AS r
+            JOIN incidents_ci_relations AS c ON c.incident_id = r.incident_id
+            WHERE r.problem_id = $1",
+            problem_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn load_known_error(
+    problem_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<KnownError, crate::Error> {
+    sqlx::query_as!(
+        KnownError,
+        "
+        SELECT problem_id, symptoms, root_cause, workaround_steps,
+            ARRAY(
+                SELECT ci_id FROM known_error_cis AS c
+                WHERE c.problem_id = k.problem_id
+                ORDER BY ci_id
+            ) AS \"ci_ids!\",
+            published, created_at, updated_at
+        FROM known_errors AS k
+        WHERE problem_id = $1",
+        problem_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Load the known error of a Problem. Fails with [crate::Error::NoRecordFound] if the Problem
+/// doesn't exist or never was a known error.
+pub async fn load(problem_id: Uuid, pool: &DbPool) -> Result<KnownError, crate::Error> {
+    let mut tx = pool.begin().await?;
+    let known_error = load_known_error(problem_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(known_error)
+}
+
+/// Map the errors of writing a known error, which may reference nonexistent Configuration
+/// Items.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+pub async fn update(
+    problem_id: Uuid,
+    updateset: KnownErrorUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<KnownError, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    sqlx::query!(
+        "
+        UPDATE known_errors
+        SET symptoms = COALESCE($1, symptoms), root_cause = COALESCE($2, root_cause),
+            workaround_steps = COALESCE($3, workaround_steps),
+            published = COALESCE($4, published), updated_at = now()
+        WHERE problem_id = $5
+        RETURNING problem_id",
+        updateset.symptoms.unwrap_or(None),
+        updateset.root_cause.unwrap_or(None),
+        updateset.workaround_steps.unwrap_or(None) as Option<Vec<String>>,
+        updateset.published.unwrap_or(None),
+        problem_id,
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    if let Some(Some(ci_ids)) = updateset.ci_ids {
+        sqlx::query!(
+            "
+            DELETE FROM known_error_cis
+            WHERE problem_id = $1
+            AND ci_id <> ALL($2)",
+            problem_id,
+            &ci_ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "
+            INSERT INTO known_error_cis (problem_id, ci_id)
+            SELECT $1, ci_id FROM UNNEST($2::uuid[]) AS ci_id
+            ON CONFLICT DO NOTHING",
+            problem_id,
+            &ci_ids,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(map_write_error)?;
+    }
+
+    if updateset.draft_article && updateset.published == Some(Some(true)) {
+        knowledge_articles::create_for_known_error(problem_id, &mut tx).await?;
+    }
+
+    let known_error = load_known_error(problem_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(known_error)
+}
+
+/// Search the published known errors matching the text in `params`, most relevant first.
+pub async fn search(
+    params: KnownErrorSearchParams,
+    pool: &DbPool,
+) -> Result<Vec<KnownErrorMatch>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let text = match (params.q, params.incident_id) {
+        (Some(q), _) => q,
+        (None, Some(incident_id)) => sqlx::query_scalar!(
+            "
+            SELECT title || ' ' || description AS \"text!\"
+            FROM incidents
+            WHERE id = $1",
+            incident_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(crate::Error::NoRecordFound)?,
+        (None, None) => unreachable!("validated by validate_search_text"),
+    };
+
+    let rows = sqlx::query!(
+        "
+        WITH query AS (
+            SELECT any_word_tsquery($1) AS q
+        )
+        SELECT p.title, p.status as \"status: ProblemStatus\",
+            ts_rank(known_error_document(k.symptoms, k.root_cause), query.q) AS \"rank!\",
+            k.problem_id
+        FROM known_errors AS k
+        JOIN problems AS p ON p.id = k.problem_id
+        CROSS JOIN query
+        WHERE k.published
+        AND known_error_document(k.symptoms, k.root_cause) @@ query.q
+        AND ($2::uuid IS NULL OR EXISTS(
+            SELECT 1 FROM known_error_cis AS c
+            WHERE c.problem_id = k.problem_id
+            AND c.ci_id = $2
+        ))
+        ORDER BY 3 DESC, k.problem_id
+        LIMIT $3",
+        text,
+        params.ci_id,
+        params.limit.unwrap_or(20),
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut matches = Vec::new();
+    for row in rows {
+        matches.push(KnownErrorMatch {
+            title: row.title,
+            status: row.status,
+            rank: row.rank,
+            known_error: load_known_error(row.problem_id, &mut tx).await?,
+        });
+    }
+
+    tx.commit().await?;
+    Ok(matches)
+}
+
+/// Relate an Incident to the Problem of the known error `problem_id`, and return the known
+/// error so that its workaround can be applied.
+///
+/// Fails with [crate::Error::NoRecordFound] unless the known error is published, and with
+/// [crate::Error::ConstraintError] if the Incident doesn't exist or is related to the
+/// Problem already.
+pub async fn link_incident(
+    problem_id: Uuid,
+    incident_id: Uuid,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<KnownError, crate::Error> {
+    let mut tx = executor.begin().await?;
+
+    let known_error = load_known_error(problem_id, &mut tx).await?;
+    if !known_error.published {
+        return Err(crate::Error::NoRecordFound);
+    }
+    incident_relations::create(problem_id, incident_id, &mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(known_error)
+}
@@ -1,4 +1,22 @@
+pub mod audit;
+pub mod change_advisory_board;
 pub mod changes;
 pub mod configuration;
+pub mod freeze_periods;
 pub mod incidents;
+pub mod knowledge_articles;
+pub mod knowledge_categories;
+pub mod known_errors;
+pub mod priority_matrix;
+pub mod problem_candidates;
 pub mod problems;
+pub mod risk_questionnaire;
+pub mod roles;
+pub mod root_cause_categories;
+pub mod search;
+pub mod service_catalog;
+pub mod service_requests;
+pub mod sla_policies;
+pub mod teams;
+pub mod timeline;
+pub mod users;
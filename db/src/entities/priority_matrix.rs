@@ -0,0 +1,385 @@
+use crate::entities::incidents::{self, IncidentImpact, IncidentPrio, IncidentUrgency};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Priority of the Incidents with a given impact and urgency.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub struct PriorityMatrixEntry {
+    pub impact: IncidentImpact,
+    pub urgency: IncidentUrgency,
+    pub priority: IncidentPrio,
+}
+
+/// Matrix giving Incidents their priority from their impact and urgency.
+///
+/// Only the impacts and urgencies in the matrix can be used, and the matrix has an entry for
+/// every combination of them.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct PriorityMatrix {
+    /// Impacts in use, highest first.
+    #[schema(example = json!(["high", "medium", "low"]))]
+    pub impacts: Vec<IncidentImpact>,
+    /// Urgencies in use, highest first.
+    #[schema(example = json!(["high", "medium", "low"]))]
+    pub urgencies: Vec<IncidentUrgency>,
+    pub entries: Vec<PriorityMatrixEntry>,
+}
+
+impl PriorityMatrix {
+    /// Build the matrix from its entries, sorted by impact and urgency.
+    fn new(mut entries: Vec<PriorityMatrixEntry>) -> Self {
+        entries.sort_by_key(|entry| (entry.impact, entry.urgency));
+        let mut impacts = Vec::new();
+        let mut urgencies = Vec::new();
+        for entry in &entries {
+            if !impacts.contains(&entry.impact) {
+                impacts.push(entry.impact);
+            }
+            if !urgencies.contains(&entry.urgency) {
+                urgencies.push(entry.urgency);
+            }
+        }
+
+        Self {
+            impacts,
+            urgencies,
+            entries,
+        }
+    }
+}
+
+/// Payload for replacing the priority matrix.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_complete"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct PriorityMatrixUpdateset {
+    /// One entry per combination of the impacts and urgencies to use.
+    pub entries: Vec<PriorityMatrixEntry>,
+}
+
+/// Validate that a [PriorityMatrixUpdateset] has exactly one entry per combination of the
+/// impacts and urgencies in it.
+fn validate_complete(updateset: &PriorityMatrixUpdateset) -> Result<(), ValidationError> {
+    let entries = &updateset.entries;
+    if entries.is_empty() {
+        return Err(ValidationError::new(
+            "The priority matrix needs at least one entry",
+        ));
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        if entries[..i]
+            .iter()
+            .any(|e| e.impact == entry.impact && e.urgency == entry.urgency)
+        {
+            return Err(ValidationError::new(
+                "Only one entry per impact and urgency",
+            ));
+        }
+    }
+
+    // Without duplicates, the matrix is complete if it has as many entries as combinations.
+    let matrix = PriorityMatrix::new(entries.clone());
+    if matrix.impacts.len() * matrix.urgencies.len() != entries.len() {
+        return Err(ValidationError::new(
+            "Every impact needs an entry for every urgency",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Map the errors of writing the priority matrix, which may leave out an impact or urgency
+/// that's still used by Incidents.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+async fn load_entries(
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Vec<PriorityMatrixEntry>, crate::Error> {
+    let entries = sqlx::query_as!(
+        PriorityMatrixEntry,
+        "
+        SELECT impact as \"impact: IncidentImpact\", urgency as \"urgency: IncidentUrgency\",
+            priority as \"priority: IncidentPrio\"
+        FROM priority_matrix
+        ORDER BY impact, urgency"
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(entries)
+}
+
+pub async fn load(
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<PriorityMatrix, crate::Error> {
+    Ok(PriorityMatrix::new(load_entries(executor).await?))
+}
+
+/// Priority of the Incidents with the given impact and urgency.
+///
+/// Fails with [crate::Error::ValidationError] if the matrix doesn't use them.
+pub async fn priority_of(
+    impact: IncidentImpact,
+    urgency: IncidentUrgency,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<IncidentPrio, crate::Error> {
+    let priority = sqlx::query_scalar!(
+        "
+        SELECT priority as \"priority: IncidentPrio\"
+        FROM priority_matrix
+        WHERE impact = $1
+        AND urgency = $2",
+        impact as IncidentImpact,
+        urgency as IncidentUrgency,
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    priority.ok_or_else(|| {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add(
+            "impact",
+            ValidationError::new("Impact not in use by the priority matrix"),
+        );
+        errors.add(
+            "urgency",
+            ValidationError::new("Urgency not in use by the priority matrix"),
+        );
+        errors.into()
+    })
+}
+
+/// Replace the priority matrix.
+///
+/// Incidents take the new priority of their impact and urgency, and the SLA deadlines of the
+/// unresolved ones whose priority changes are recomputed. Unresolved Incidents becoming
+/// critical are declared major incidents. Fails with [crate::Error::ConstraintError] if an
+/// impact or urgency left out of the matrix is still used by Incidents.
+pub async fn update(
+    updateset: PriorityMatrixUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<PriorityMatrix, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let current = load_entries(&mut *tx).await?;
+    let find = |entry: &PriorityMatrixEntry| {
+        updateset
+            .entries
+            .iter()
+            .find(|e| e.impact == entry.impact && e.urgency == entry.urgency)
+    };
+
+    // Incidents follow the changes of priority through the `ON UPDATE CASCADE` of their
+    // foreign key to the matrix.
+    for entry in &updateset.entries {
+        sqlx::query!(
+            "
+            INSERT INTO priority_matrix (impact, urgency, priority)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (impact, urgency) DO UPDATE
+            SET priority = EXCLUDED.priority",
+            entry.impact as IncidentImpact,
+            entry.urgency as IncidentUrgency,
+            entry.priority as IncidentPrio,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    for entry in current.iter().filter(|entry| find(entry).is_none()) {
+        sqlx::query!(
+            "
+            DELETE FROM priority_matrix
+            WHERE impact = $1
+            AND urgency = $2",
+            entry.impact as IncidentImpact,
+            entry.urgency as IncidentUrgency,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(map_write_error)?;
+    }
+
+    let changed = current.iter().filter_map(|entry| {
+        find(entry)
+            .filter(|e| e.priority != entry.priority)
+            .map(|e| (entry, e.priority))
+    });
+    for (entry, priority) in changed {
+        let incident_ids = sqlx::query_scalar!(
+            "
+            SELECT id
+            FROM incidents
+            WHERE impact = $1
+            AND urgency = $2
+            AND resolved_at IS NULL",
+            entry.impact as IncidentImpact,
+            entry.urgency as IncidentUrgency,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for incident_id in incident_ids {
+            incidents::sla::refresh(incident_id, &mut tx).await?;
+            if priority == IncidentPrio::Critical {
+                incidents::major::declare(incident_id, None, &mut tx).await?;
+            }
+        }
+    }
+
+    let matrix = load(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod priority_matrix_tests {
+    use super::*;
+
+    fn entry(
+        impact: IncidentImpact,
+        urgency: IncidentUrgency,
+        priority: IncidentPrio,
+    ) -> PriorityMatrixEntry {
+        PriorityMatrixEntry {
+            impact,
+            urgency,
+            priority,
+        }
+    }
+
+    fn build_updateset(
+        impacts: &[IncidentImpact],
+        urgencies: &[IncidentUrgency],
+    ) -> PriorityMatrixUpdateset {
+        let mut entries = Vec::new();
+        for impact in impacts {
+            for urgency in urgencies {
+                entries.push(entry(*impact, *urgency, IncidentPrio::Moderate));
+            }
+        }
+
+        PriorityMatrixUpdateset { entries }
+    }
+
+    #[test]
+    fn test_complete_matrices() {
+        use IncidentImpact as I;
+        use IncidentUrgency as U;
+
+        let updatesets = [
+            build_updateset(&[I::Low], &[U::Low]),
+            build_updateset(&[I::High, I::Medium, I::Low], &[U::High, U::Medium, U::Low]),
+            build_updateset(
+                &[I::Critical, I::High, I::Medium, I::Low],
+                &[U::Critical, U::High, U::Medium, U::Low],
+            ),
+            build_updateset(
+                &[I::Critical, I::High, I::Medium, I::Low, I::Minimal],
+                &[U::Critical, U::High, U::Medium, U::Low, U::Minimal],
+            ),
+            build_updateset(&[I::High, I::Low], &[U::Critical, U::Medium, U::Minimal]),
+        ];
+
+        for updateset in updatesets {
+            assert!(updateset.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_empty_matrix() {
+        let updateset = PriorityMatrixUpdateset { entries: vec![] };
+
+        assert!(updateset.validate().is_err());
+    }
+
+    #[test]
+    fn test_missing_entry() {
+        let mut updateset = build_updateset(
+            &[IncidentImpact::High, IncidentImpact::Low],
+            &[IncidentUrgency::High, IncidentUrgency::Low],
+        );
+        updateset.entries.pop();
+
+        assert!(updateset.validate().is_err());
+    }
+
+    #[test]
+    fn test_duplicate_entry() {
+        let mut updateset = build_updateset(
+            &[IncidentImpact::High, IncidentImpact::Low],
+            &[IncidentUrgency::High],
+        );
+        updateset.entries[1] = entry(
+            IncidentImpact::High,
+            IncidentUrgency::High,
+            IncidentPrio::Low,
+        );
+
+        assert!(updateset.validate().is_err());
+    }
+
+    #[test]
+    fn test_levels_in_order() {
+        let matrix = PriorityMatrix::new(
+            build_updateset(
+                &[IncidentImpact::High, IncidentImpact::Low],
+                &[IncidentUrgency::Critical, IncidentUrgency::Medium],
+            )
+            .entries,
+        );
+
+        assert_eq!(
+            matrix.impacts,
+            vec![IncidentImpact::High, IncidentImpact::Low]
+        );
+        assert_eq!(
+            matrix.urgencies,
+            vec![IncidentUrgency::Critical, IncidentUrgency::Medium]
+        );
+    }
+
+    #[test]
+    fn test_entries_sorted() {
+        let mut entries = build_updateset(
+            &[IncidentImpact::Low, IncidentImpact::High],
+            &[IncidentUrgency::Minimal, IncidentUrgency::Critical],
+        )
+        .entries;
+        entries.reverse();
+
+        let matrix = PriorityMatrix::new(entries);
+
+        let levels: Vec<_> = matrix
+            .entries
+            .iter()
+            .map(|entry| (entry.impact, entry.urgency))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                (IncidentImpact::High, IncidentUrgency::Critical),
+                (IncidentImpact::High, IncidentUrgency::Minimal),
+                (IncidentImpact::Low, IncidentUrgency::Critical),
+                (IncidentImpact::Low, IncidentUrgency::Minimal),
+            ]
+        );
+        assert_eq!(
+            matrix.impacts,
+            vec![IncidentImpact::High, IncidentImpact::Low]
+        );
+    }
+}
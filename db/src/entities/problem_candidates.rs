@@ -0,0 +1,460 @@
+use crate::entities::problems::{self, incident_relations, Problem, ProblemCreateset};
+use crate::entity_helpers::{self, Lifecycle};
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, QueryBuilder, Type};
+use std::collections::HashSet;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Group of recurring Incidents on a Configuration Item, proposed as the symptoms of a yet
+/// unknown Problem.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct ProblemCandidate {
+    pub id: Uuid,
+    /// Configuration Item the Incidents share. `null` if it was deleted since.
+    pub ci_id: Option<Uuid>,
+    /// Title of the earliest Incident of the group.
+    #[schema(example = "Can't connect to the VPN")]
+    pub title: String,
+    pub status: ProblemCandidateStatus,
+    /// Problem created when the candidate was accepted.
+    pub problem_id: Option<Uuid>,
+    /// Incidents of the group, earliest first.
+    pub incident_ids: Vec<Uuid>,
+    pub detected_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[sqlx(type_name = "problem_candidate_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[schema(example = "proposed")]
+pub enum ProblemCandidateStatus {
+    Proposed,
+    Accepted,
+    Rejected,
+}
+
+impl Lifecycle for ProblemCandidateStatus {
+    fn next(&self) -> &'static [Self] {
+        use ProblemCandidateStatus::*;
+        match self {
+            Proposed => &[Accepted, Rejected],
+            Accepted | Rejected => &[],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Proposed => "proposed",
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// Query parameters for listing problem candidates.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ProblemCandidateListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Only candidates with this status.
+    pub status: Option<ProblemCandidateStatus>,
+}
+
+/// Thresholds a group of recurring Incidents must pass to be proposed as a problem candidate.
+#[derive(Clone, Copy, Debug)]
+pub struct DetectionThresholds {
+    /// Only Incidents opened within this long before the detection are grouped.
+    pub window: Duration,
+    /// Least amount of Incidents in a group.
+    pub min_incidents: usize,
+    /// Least share of words, from 0 to 1, two Incidents must have in common to be grouped.
+    pub min_similarity: f64,
+}
+
+/// Incident the detection may group, once per Configuration Item it's related to.
+struct DetectedIncident {
+    ci_id: Uuid,
+    id: Uuid,
+    title: String,
+    /// Stemmed words of its title and description.
+    words: HashSet<String>,
+}
+
+/// Share of words `a` and `b` have in common (their Jaccard index).
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Group `incidents`, sorted by Configuration Item, into the groups passing `thresholds`.
+///
+/// Incidents on the same Configuration Item are grouped when they're similar enough to some
+/// other Incident of the group. An Incident ends up in one group at most, even when related to
+/// several Configuration Items. Groups are returned as indexes into `incidents`.
+fn group(incidents: &[DetectedIncident], thresholds: &DetectionThresholds) -> Vec<Vec<usize>> {
+    let mut used = HashSet::new();
+    let mut accepted = Vec::new();
+    let mut start = 0;
+    while start < incidents.len() {
+        let ci_id = incidents[start].ci_id;
+        let end = incidents[start..]
+            .iter()
+            .position(|i| i.ci_id != ci_id)
+            .map_or(incidents.len(), |len| start + len);
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for index in start..end {
+            if used.contains(&incidents[index].id) {
+                continue;
+            }
+            let words = &incidents[index].words;
+            let similar = groups.iter_mut().find(|group| {
+                group.iter().any(|&other| {
+                    similarity(words, &incidents[other].words) >= thresholds.min_similarity
+                })
+            });
+            match similar {
+                Some(group) => group.push(index),
+                None => groups.push(vec![index]),
+            }
+        }
+
+        for group in groups {
+            if group.len() >= thresholds.min_incidents {
+                used.extend(group.iter().map(|&index| incidents[index].id));
+                accepted.push(group);
+            }
+        }
+        start = end;
+    }
+
+    accepted
+}
+
+/// Look for groups of recurring Incidents and propose each as a problem candidate.
+///
+/// Only Incidents unrelated to any Problem and never proposed before are considered, so
+/// rejected groups aren't proposed again. Returns the new candidates.
+pub async fn detect(
+    thresholds: DetectionThresholds,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Vec<ProblemCandidate>, crate::Error> {
+    let mut tx = executor.begin().await?;
+
+    // Concurrent detections would propose the same Incidents.
+    sqlx::query!("LOCK TABLE problem_candidate_incidents IN SHARE ROW EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await?;
+
+    let incidents: Vec<DetectedIncident> = sqlx::query!(
+        "
+        SELECT r.ci_id, i.id, i.title,
+            tsvector_to_array(to_tsvector('english', i.title || ' ' || i.description))
+                AS \"words!\"
+        FROM incidents_ci_relations AS r
+        JOIN incidents AS i ON i.id = r.incident_id
+        WHERE i.created_at >= $1
+        AND NOT EXISTS (
+            SELECT 1 FROM problem_incident_relations AS p
+            WHERE p.incident_id = i.id
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM problem_candidate_incidents AS c
+            WHERE c.incident_id = i.id
+        )
+        ORDER BY r.ci_id, i.created_at, i.id",
+        Utc::now() - thresholds.window
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|row| DetectedIncident {
+        ci_id: row.ci_id,
+        id: row.id,
+        title: row.title,
+        words: row.words.into_iter().collect(),
+    })
+    .collect();
+
+    let mut candidate_ids = Vec::new();
+    for group in group(&incidents, &thresholds) {
+        let first = &incidents[group[0]];
+        let incident_ids: Vec<Uuid> = group.iter().map(|&index| incidents[index].id).collect();
+        let candidate_id = sqlx::query_scalar!(
+            "
+            INSERT INTO problem_candidates (ci_id, title)
+            VALUES ($1, $2)
+            RETURNING id",
+            first.ci_id,
+            first.title,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "
+            INSERT INTO problem_candidate_incidents (candidate_id, incident_id)
+            SELECT $1, UNNEST($2::uuid[])",
+            candidate_id,
+            &incident_ids,
+        )
+        .execute(&mut *tx)
+        .await?;
+        candidate_ids.push(candidate_id);
+    }
+
+    let mut candidates = Vec::with_capacity(candidate_ids.len());
+    for id in candidate_ids {
+        candidates.push(load_candidate(id, &mut tx).await?);
+    }
+
+    tx.commit().await?;
+    Ok(candidates)
+}
+
+/// Append the `WHERE` clause matching the filters in [ProblemCandidateListParams].
+fn push_filters<'a>(
+    builder: &mut QueryBuilder<'a, Postgres>,
+    params: &'a ProblemCandidateListParams,
+) {
+    builder.push(" WHERE TRUE");
+    if let Some(status) = params.status {
+        builder.push(" AND c.status = ").push_bind(status);
+    }
+}
+
+/// Load one page of problem candidates matching the filters in `params`, latest first.
+pub async fn load_page(
+    params: ProblemCandidateListParams,
+    pool: &DbPool,
+) -> Result<Page<ProblemCandidate>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let candidates = pagination::load_page::<ProblemCandidate>(
+        &mut tx,
+        "
+        c.id, c.ci_id, c.title, c.status, c.problem_id,
+        ARRAY(
+            SELECT ci.incident_id
+            FROM problem_candidate_incidents AS ci
+            JOIN incidents AS i ON i.id = ci.incident_id
+            WHERE ci.candidate_id = c.id
+            ORDER BY i.created_at, i.id
+        ) AS incident_ids,
+        c.detected_at, c.decided_at",
+        "problem_candidates AS c",
+        |builder| push_filters(builder, &params),
+        "c.detected_at DESC, c.id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(candidates)
+}
+
+async fn load_candidate(
+    id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<ProblemCandidate, crate::Error> {
+    sqlx::query_as!(
+        ProblemCandidate,
+        "
+        SELECT c.id, c.ci_id, c.title, c.status as \"status: ProblemCandidateStatus\",
+            c.problem_id,
+            ARRAY(
+                SELECT ci.incident_id
+                FROM problem_candidate_incidents AS ci
+                JOIN incidents AS i ON i.id = ci.incident_id
+                WHERE ci.candidate_id = c.id
+                ORDER BY i.created_at, i.id
+            ) AS \"incident_ids!\",
+            c.detected_at, c.decided_at
+        FROM problem_candidates AS c
+        WHERE c.id = $1",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+pub async fn load(id: Uuid, pool: &DbPool) -> Result<ProblemCandidate, crate::Error> {
+    let mut conn = pool.acquire().await?;
+    load_candidate(id, &mut conn).await
+}
+
+/// Move the problem candidate `id` from `proposed` to `to`. Fails with
+/// [crate::Error::InvalidTransition] if it was decided already.
+async fn decide(
+    id: Uuid,
+    to: ProblemCandidateStatus,
+    problem_id: Option<Uuid>,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    let from = sqlx::query_scalar!(
+        "
+        SELECT status as \"status: ProblemCandidateStatus\"
+        FROM problem_candidates
+        WHERE id = $1
+        FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    // Decided candidates can't be decided again, not even the same way.
+    if from == to {
+        return Err(crate::Error::InvalidTransition {
+            from: from.name(),
+            to: to.name(),
+            allowed: vec![],
+        });
+    }
+    entity_helpers::check_transition(from, to)?;
+
+    sqlx::query!(
+        "
+        UPDATE problem_candidates
+        SET status = $2, problem_id = $3, decided_at = now()
+        WHERE id = $1",
+        id,
+        to as ProblemCandidateStatus,
+        problem_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Accept the problem candidate `id`, creating the Problem `problem` related to all the
+/// Incidents of the candidate.
+pub async fn accept(
+    id: Uuid,
+    problem: ProblemCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Problem, crate::Error> {
+    let mut tx = executor.begin().await?;
+
+    let candidate = load_candidate(id, &mut tx).await?;
+    let created_problem = problems::create(problem, &mut *tx).await?;
+    decide(
+        id,
+        ProblemCandidateStatus::Accepted,
+        Some(created_problem.id),
+        &mut tx,
+    )
+    .await?;
+    for incident_id in candidate.incident_ids {
+        incident_relations::create(created_problem.id, incident_id, &mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    Ok(created_problem)
+}
+
+/// Reject the problem candidate `id`. Its Incidents won't be proposed again.
+pub async fn reject(
+    id: Uuid,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<ProblemCandidate, crate::Error> {
+    let mut tx = executor.begin().await?;
+
+    decide(id, ProblemCandidateStatus::Rejected, None, &mut tx).await?;
+    let candidate = load_candidate(id, &mut tx).await?;
+
+    tx.commit().await?;
+    Ok(candidate)
+}
+
+#[cfg(test)]
+mod problem_candidates_tests {
+    use super::*;
+
+    fn incident(ci_id: Uuid, id: Uuid, text: &str) -> DetectedIncident {
+        DetectedIncident {
+            ci_id,
+            id,
+            title: String::from(text),
+            words: text.split_whitespace().map(String::from).collect(),
+        }
+    }
+
+    fn thresholds(min_incidents: usize) -> DetectionThresholds {
+        DetectionThresholds {
+            window: Duration::hours(72),
+            min_incidents,
+            min_similarity: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_similarity() {
+        let a = incident(Uuid::nil(), Uuid::nil(), "vpn drop connect");
+        let b = incident(Uuid::nil(), Uuid::nil(), "vpn drop slow");
+        let c = incident(Uuid::nil(), Uuid::nil(), "printer jam");
+        let empty = incident(Uuid::nil(), Uuid::nil(), "");
+
+        assert_eq!(similarity(&a.words, &a.words), 1.0);
+        assert_eq!(similarity(&a.words, &b.words), 0.5);
+        assert_eq!(similarity(&a.words, &c.words), 0.0);
+        assert_eq!(similarity(&empty.words, &empty.words), 0.0);
+    }
+
+    #[test]
+    fn test_group() {
+        let (ci_a, ci_b) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let (a1, a2, shared) = (
+            Uuid::from_u128(11),
+            Uuid::from_u128(12),
+            Uuid::from_u128(13),
+        );
+        let (b1, b2, b3) = (
+            Uuid::from_u128(21),
+            Uuid::from_u128(22),
+            Uuid::from_u128(23),
+        );
+        let incidents = [
+            incident(ci_a, a1, "vpn drop connect"),
+            incident(ci_a, shared, "vpn drop connect"),
+            incident(ci_a, a2, "printer jam"),
+            incident(ci_b, shared, "vpn drop connect"),
+            incident(ci_b, b1, "vpn drop slow"),
+            incident(ci_b, b2, "printer jam"),
+            incident(ci_b, b3, "vpn drop connect"),
+        ];
+        let ids = |groups: Vec<Vec<usize>>| -> Vec<Vec<Uuid>> {
+            groups
+                .into_iter()
+                .map(|g| g.into_iter().map(|i| incidents[i].id).collect())
+                .collect()
+        };
+
+        assert_eq!(
+            ids(group(&incidents, &thresholds(2))),
+            vec![vec![a1, shared], vec![b1, b3]]
+        );
+        assert_eq!(
+            ids(group(&incidents, &thresholds(3))),
+            vec![vec![shared, b1, b3]]
+        );
+        assert!(group(&incidents, &thresholds(4)).is_empty());
+    }
+}
@@ -1,17 +1,24 @@
+use crate::entities::known_errors;
+use crate::pagination::{self, Page, PageParams, SortOrder};
+use crate::DbPool;
 use serde::Deserialize;
 use serde::Serialize;
 use sqlx::types::chrono::DateTime;
 use sqlx::types::chrono::Utc;
 use sqlx::Postgres;
+use sqlx::QueryBuilder;
 use sqlx::Type;
+use utoipa::IntoParams;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
 /// Module for handling relations between Incidents and Problems.
 pub mod incident_relations;
+/// Module for handling the root cause analysis of Problems.
+pub mod rca;
 
-#[derive(Serialize, Debug, ToSchema)]
+#[derive(Serialize, Debug, ToSchema, sqlx::FromRow)]
 #[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize))]
 pub struct Problem {
     pub id: Uuid,
@@ -101,6 +108,97 @@ pub struct ProblemUpdateset {
     pub resolutions: Option<Option<String>>,
 }
 
+/// Query parameters for listing Problems.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ProblemListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Column to sort by. Defaults to `detection_timedate`.
+    #[param(inline)]
+    pub sort_by: Option<ProblemSortColumn>,
+    /// Sorting direction. Defaults to `asc`.
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    pub status: Option<ProblemStatus>,
+    /// Only Problems detected at or after this moment.
+    pub detected_after: Option<DateTime<Utc>>,
+    /// Only Problems detected before this moment.
+    pub detected_before: Option<DateTime<Utc>>,
+}
+
+/// Columns Problems can be sorted by.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(example = "detection_timedate")]
+pub enum ProblemSortColumn {
+    #[default]
+    DetectionTimedate,
+    Title,
+    Status,
+}
+
+impl ProblemSortColumn {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::DetectionTimedate => "detection_timedate",
+            Self::Title => "title",
+            Self::Status => "status",
+        }
+    }
+}
+
+/// Append the `WHERE` clause matching the filters in [ProblemListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a ProblemListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(status) = &params.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(detected_after) = params.detected_after {
+        builder
+            .push(" AND detection_timedate >= ")
+            .push_bind(detected_after);
+    }
+    if let Some(detected_before) = params.detected_before {
+        builder
+            .push(" AND detection_timedate < ")
+            .push_bind(detected_before);
+    }
+}
+
+/// Load one page of Problems matching the filters in `params`.
+pub async fn load_page(
+    params: ProblemListParams,
+    pool: &DbPool,
+) -> Result<Page<Problem>, crate::Error> {
+    params.validate()?;
+
+    let order_by = format!(
+        "{} {}, id",
+        params.sort_by.unwrap_or_default().as_sql(),
+        params.order.unwrap_or_default().as_sql()
+    );
+
+    let mut tx = pool.begin().await?;
+    let problems = pagination::load_page::<Problem>(
+        &mut tx,
+        "
+        id, title, status, detection_timedate,
+        description, causes, workarounds, resolutions",
+        "problems",
+        |builder| push_filters(builder, &params),
+        &order_by,
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(problems)
+}
+
 pub async fn load_all(
     executor: impl sqlx::Executor<'_, Database = Postgres>,
 ) -> Result<Vec<Problem>, crate::Error> {
@@ -139,12 +237,16 @@ pub async fn load(
     }
 }
 
+/// Create a Problem. Known errors are recorded in the Known Error Database (see
+/// [known_errors::create_for_problem]).
 pub async fn create(
     problem: ProblemCreateset,
-    executor: impl sqlx::Executor<'_, Database = Postgres>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<Problem, crate::Error> {
     problem.validate()?;
 
+    let mut tx = executor.begin().await?;
+
     let created_problem = sqlx::query_as!(
         Problem,
         "
@@ -161,21 +263,29 @@ pub async fn create(
         problem.workarounds,
         problem.resolutions,
     )
-    .fetch_one(executor)
+    .fetch_one(&mut *tx)
     .await
     .map_err(crate::Error::DbError)?;
 
+    if matches!(created_problem.status, ProblemStatus::KnownError) {
+        known_errors::create_for_problem(created_problem.id, &mut tx).await?;
+    }
+    tx.commit().await?;
     Ok(created_problem)
 }
 
+/// Update a Problem. Problems entering the known error status are recorded in the Known Error
+/// Database (see [known_errors::create_for_problem]).
 pub async fn update(
     id: Uuid,
     problem: ProblemUpdateset,
-    executor: impl sqlx::Executor<'_, Database = Postgres>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<Problem, crate::Error> {
     problem.validate()?;
 
-    match sqlx::query_as!(
+    let mut tx = executor.begin().await?;
+
+    let updated_problem = sqlx::query_as!(
         Problem,
         "
         UPDATE problems
@@ -204,13 +314,16 @@ pub async fn update(
         problem.resolutions.unwrap_or(None),     // 9
         id,                                      // 10
     )
-    .fetch_optional(executor)
+    .fetch_optional(&mut *tx)
     .await
     .map_err(crate::Error::DbError)?
-    {
-        Some(updated_problem) => Ok(updated_problem),
-        None => Err(crate::Error::NoRecordFound),
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    if matches!(updated_problem.status, ProblemStatus::KnownError) {
+        known_errors::create_for_problem(id, &mut tx).await?;
     }
+    tx.commit().await?;
+    Ok(updated_problem)
 }
 
 pub async fn delete(
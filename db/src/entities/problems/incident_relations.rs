@@ -59,9 +59,9 @@ pub async fn load_all(
 pub async fn create(
     problem_id: Uuid,
     incident_id: Uuid,
-    pool: &DbPool,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
 ) -> Result<ProblemIncidentRelation, crate::Error> {
-    let mut tx = pool.begin().await?;
+    let mut tx = executor.begin().await?;
     check_valid_problem(problem_id, &mut *tx).await?;
     sqlx::query!(
         "
@@ -74,7 +74,9 @@ pub async fn create(
     .execute(&mut *tx)
     .await
     .map_err(|e| match e {
-        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_foreign_key_violation() || dbe.is_unique_violation() =>
+        {
             crate::Error::ConstraintError
         }
         _ => crate::Error::DbError(e),
@@ -0,0 +1,569 @@
+use crate::entity_helpers;
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// Most steps a 5-Whys chain can have.
+pub const MAX_WHYS: usize = 10;
+
+/// Root cause analysis of a Problem, with all its artefacts.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RootCauseAnalysis {
+    pub problem_id: Uuid,
+    /// Classification of the root cause. `null` until the analysis concludes.
+    pub root_cause: Option<RootCause>,
+    /// 5-Whys chain, in order.
+    pub whys: Vec<Why>,
+    /// Causes of the Ishikawa diagram, by category.
+    pub causes: Vec<IshikawaCause>,
+    /// Events leading to the Problem, earliest first.
+    pub events: Vec<RCAEvent>,
+}
+
+/// Step of a 5-Whys chain. Each answer is the reason of the previous one.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct Why {
+    #[schema(example = "Why did the VPN drop connections?")]
+    pub question: String,
+    #[schema(example = "The gateway ran out of memory.")]
+    pub answer: String,
+}
+
+/// Step of a 5-Whys chain to record.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct WhyCreateset {
+    #[schema(example = "Why did the VPN drop connections?")]
+    #[validate(length(min = 1, max = 1024))]
+    pub question: String,
+    #[schema(example = "The gateway ran out of memory.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub answer: String,
+}
+
+/// Category of an Ishikawa (fishbone) diagram.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[sqlx(type_name = "ishikawa_category", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[schema(example = "technology")]
+pub enum IshikawaCategory {
+    People,
+    Process,
+    Technology,
+    Materials,
+    Measurement,
+    Environment,
+}
+
+/// Contributing cause of a Problem in its Ishikawa diagram.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct IshikawaCause {
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub category: IshikawaCategory,
+    #[schema(example = "Gateway memory isn't monitored.")]
+    pub description: String,
+}
+
+/// Payload for adding a cause to the Ishikawa diagram of a Problem.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct IshikawaCauseCreateset {
+    pub category: IshikawaCategory,
+    #[schema(example = "Gateway memory isn't monitored.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub description: String,
+}
+
+/// Payload for updating a cause of the Ishikawa diagram of a Problem.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_cause_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct IshikawaCauseUpdateset {
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub category: Option<Option<IshikawaCategory>>,
+    #[schema(example = "Gateway memory isn't monitored.")]
+    #[validate(length(min = 1, max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+}
+
+/// Validate that required fields of [IshikawaCauseUpdateset] aren't explicitly null.
+fn validate_cause_required_fields(
+    updateset: &IshikawaCauseUpdateset,
+) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.category)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+
+    Ok(())
+}
+
+/// Event in the timeline leading to a Problem.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RCAEvent {
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    #[schema(example = "Gateway firmware upgraded.")]
+    pub description: String,
+    /// Configuration Item the event is about.
+    pub ci_id: Option<Uuid>,
+    /// Incident the event is about.
+    pub incident_id: Option<Uuid>,
+    /// RFC the event is about.
+    pub rfc_id: Option<Uuid>,
+}
+
+/// Payload for adding an event to the timeline of a Problem.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RCAEventCreateset {
+    pub occurred_at: DateTime<Utc>,
+    #[schema(example = "Gateway firmware upgraded.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub description: String,
+    pub ci_id: Option<Uuid>,
+    pub incident_id: Option<Uuid>,
+    pub rfc_id: Option<Uuid>,
+}
+
+/// Payload for updating an event of the timeline of a Problem. Links set to `null` are
+/// removed.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_event_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RCAEventUpdateset {
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub occurred_at: Option<Option<DateTime<Utc>>>,
+    #[schema(example = "Gateway firmware upgraded.")]
+    #[validate(length(min = 1, max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub ci_id: Option<Option<Uuid>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub incident_id: Option<Option<Uuid>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub rfc_id: Option<Option<Uuid>>,
+}
+
+/// Validate that required fields of [RCAEventUpdateset] aren't explicitly null.
+fn validate_event_required_fields(updateset: &RCAEventUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.occurred_at)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+
+    Ok(())
+}
+
+/// Classification of the root cause of a Problem in the managed taxonomy (see
+/// [crate::entities::root_cause_categories]).
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RootCause {
+    pub category_id: Uuid,
+    #[schema(example = "Configuration error")]
+    pub category_name: String,
+    #[schema(example = "The gateway was deployed with the default memory limits.")]
+    pub notes: String,
+    pub classified_at: DateTime<Utc>,
+}
+
+/// Payload for classifying the root cause of a Problem, replacing the current classification.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RootCauseUpdateset {
+    pub category_id: Uuid,
+    #[schema(example = "The gateway was deployed with the default memory limits.")]
+    #[validate(length(max = 4096))]
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Check if a problem with the ID sent as path param exists in the database.
+async fn check_valid_problem(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM problems WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Map the errors of writing RCA artefacts, which may link to nonexistent records.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+async fn load_whys(problem_id: Uuid, conn: &mut PgConnection) -> Result<Vec<Why>, crate::Error> {
+    let whys = sqlx::query_as!(
+        Why,
+        "
+        SELECT question, answer
+        FROM problem_whys
+        WHERE problem_id = $1
+        ORDER BY position",
+        problem_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(whys)
+}
+
+/// Load the root cause analysis of a Problem.
+pub async fn load(problem_id: Uuid, pool: &DbPool) -> Result<RootCauseAnalysis, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_problem(problem_id, &mut *tx).await?;
+
+    let root_cause = sqlx::query_as!(
+        RootCause,
+        "
+        SELECT r.category_id, c.name AS category_name, r.notes, r.classified_at
+        FROM problem_root_causes AS r
+        JOIN root_cause_categories AS c ON c.id = r.category_id
+        WHERE r.problem_id = $1",
+        problem_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let whys = load_whys(problem_id, &mut tx).await?;
+
+    let causes = sqlx::query_as!(
+        IshikawaCause,
+        "
+        SELECT id, problem_id, category as \"category: IshikawaCategory\", description
+        FROM problem_ishikawa_causes
+        WHERE problem_id = $1
+        ORDER BY category, description, id",
+        problem_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let events = sqlx::query_as!(
+        RCAEvent,
+        "
+        SELECT id, problem_id, occurred_at, description, ci_id, incident_id, rfc_id
+        FROM problem_rca_events
+        WHERE problem_id = $1
+        ORDER BY occurred_at, id",
+        problem_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(RootCauseAnalysis {
+        problem_id,
+        root_cause,
+        whys,
+        causes,
+        events,
+    })
+}
+
+/// Replace the 5-Whys chain of a Problem with `whys`, at most [MAX_WHYS] of them.
+pub async fn replace_whys(
+    problem_id: Uuid,
+    whys: Vec<WhyCreateset>,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Vec<Why>, crate::Error> {
+    if whys.len() > MAX_WHYS {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add("whys", ValidationError::new("Too many whys"));
+        return Err(errors.into());
+    }
+    for why in &whys {
+        why.validate()?;
+    }
+
+    let mut tx = executor.begin().await?;
+    check_valid_problem(problem_id, &mut *tx).await?;
+
+    sqlx::query!(
+        "
+        DELETE FROM problem_whys
+        WHERE problem_id = $1",
+        problem_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let (questions, answers): (Vec<String>, Vec<String>) =
+        whys.into_iter().map(|w| (w.question, w.answer)).unzip();
+    sqlx::query!(
+        "
+        INSERT INTO problem_whys (problem_id, position, question, answer)
+        SELECT $1, w.position, w.question, w.answer
+        FROM UNNEST($2::text[], $3::text[]) WITH ORDINALITY AS w(question, answer, position)",
+        problem_id,
+        &questions,
+        &answers,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let whys = load_whys(problem_id, &mut tx).await?;
+
+    tx.commit().await?;
+    Ok(whys)
+}
+
+pub async fn create_cause(
+    problem_id: Uuid,
+    createset: IshikawaCauseCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<IshikawaCause, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    check_valid_problem(problem_id, &mut *tx).await?;
+
+    let cause = sqlx::query_as!(
+        IshikawaCause,
+        "
+        INSERT INTO problem_ishikawa_causes (problem_id, category, description)
+        VALUES ($1, $2, $3)
+        RETURNING id, problem_id, category as \"category: IshikawaCategory\", description",
+        problem_id,
+        createset.category as IshikawaCategory,
+        createset.description,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(cause)
+}
+
+pub async fn update_cause(
+    problem_id: Uuid,
+    cause_id: Uuid,
+    updateset: IshikawaCauseUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<IshikawaCause, crate::Error> {
+    updateset.validate()?;
+
+    sqlx::query_as!(
+        IshikawaCause,
+        "
+        UPDATE problem_ishikawa_causes
+        SET category = COALESCE($1, category), description = COALESCE($2, description)
+        WHERE id = $3
+        AND problem_id = $4
+        RETURNING id, problem_id, category as \"category: IshikawaCategory\", description",
+        updateset.category.unwrap_or(None) as Option<IshikawaCategory>,
+        updateset.description.unwrap_or(None),
+        cause_id,
+        problem_id,
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+pub async fn delete_cause(
+    problem_id: Uuid,
+    cause_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        DELETE FROM problem_ishikawa_causes
+        WHERE id = $1
+        AND problem_id = $2
+        RETURNING id",
+        cause_id,
+        problem_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    Ok(())
+}
+
+/// Add an event to the timeline of a Problem. Fails with [crate::Error::ConstraintError] if
+/// it links to a nonexistent Configuration Item, Incident or RFC.
+pub async fn create_event(
+    problem_id: Uuid,
+    createset: RCAEventCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RCAEvent, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    check_valid_problem(problem_id, &mut *tx).await?;
+
+    let event = sqlx::query_as!(
+        RCAEvent,
+        "
+        INSERT INTO problem_rca_events (problem_id, occurred_at, description, ci_id,
+            incident_id, rfc_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, problem_id, occurred_at, description, ci_id, incident_id, rfc_id",
+        problem_id,
+        createset.occurred_at,
+        createset.description,
+        createset.ci_id,
+        createset.incident_id,
+        createset.rfc_id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+
+    tx.commit().await?;
+    Ok(event)
+}
+
+/// Update an event of the timeline of a Problem. Fails with [crate::Error::ConstraintError]
+/// if it links to a nonexistent Configuration Item, Incident or RFC.
+pub async fn update_event(
+    problem_id: Uuid,
+    event_id: Uuid,
+    updateset: RCAEventUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<RCAEvent, crate::Error> {
+    updateset.validate()?;
+
+    sqlx::query_as!(
+        RCAEvent,
+        "
+        UPDATE problem_rca_events
+        SET occurred_at = COALESCE($1, occurred_at),
+            description = COALESCE($2, description),
+            ci_id = CASE WHEN $3 THEN $4 ELSE ci_id END,
+            incident_id = CASE WHEN $5 THEN $6 ELSE incident_id END,
+            rfc_id = CASE WHEN $7 THEN $8 ELSE rfc_id END
+        WHERE id = $9
+        AND problem_id = $10
+        RETURNING id, problem_id, occurred_at, description, ci_id, incident_id, rfc_id",
+        updateset.occurred_at.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        updateset.ci_id.is_some(),
+        updateset.ci_id.flatten(),
+        updateset.incident_id.is_some(),
+        updateset.incident_id.flatten(),
+        updateset.rfc_id.is_some(),
+        updateset.rfc_id.flatten(),
+        event_id,
+        problem_id,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+pub async fn delete_event(
+    problem_id: Uuid,
+    event_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        DELETE FROM problem_rca_events
+        WHERE id = $1
+        AND problem_id = $2
+        RETURNING id",
+        event_id,
+        problem_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    Ok(())
+}
+
+/// Classify the root cause of a Problem, replacing the current classification. Fails with
+/// [crate::Error::ConstraintError] if the category doesn't exist.
+pub async fn classify(
+    problem_id: Uuid,
+    updateset: RootCauseUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RootCause, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    check_valid_problem(problem_id, &mut *tx).await?;
+
+    let root_cause = sqlx::query_as!(
+        RootCause,
+        "
+        WITH classified AS (
+            INSERT INTO problem_root_causes (problem_id, category_id, notes)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (problem_id) DO UPDATE
+            SET category_id = EXCLUDED.category_id, notes = EXCLUDED.notes,
+                classified_at = now()
+            RETURNING category_id, notes, classified_at
+        )
+        SELECT r.category_id, c.name AS category_name, r.notes, r.classified_at
+        FROM classified AS r
+        JOIN root_cause_categories AS c ON c.id = r.category_id",
+        problem_id,
+        updateset.category_id,
+        updateset.notes,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+
+    tx.commit().await?;
+    Ok(root_cause)
+}
@@ -0,0 +1,199 @@
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Answer to a question of the risk questionnaire.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RiskAnswer {
+    pub id: Uuid,
+    #[schema(example = "The whole organisation")]
+    pub answer: String,
+    /// Score of the answer, before the weight of its question.
+    #[schema(example = 4)]
+    pub score: i32,
+}
+
+/// Question of the risk questionnaire.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RiskQuestion {
+    pub id: Uuid,
+    #[schema(example = "How many users does the change affect?")]
+    pub question: String,
+    /// Multiplies the score of the answers.
+    #[schema(example = 3)]
+    pub weight: i32,
+    /// Possible answers, in order.
+    pub answers: Vec<RiskAnswer>,
+}
+
+impl RiskQuestion {
+    /// Highest weighted score an answer to the question can get.
+    pub fn max_score(&self) -> i32 {
+        self.weight * self.answers.iter().map(|a| a.score).max().unwrap_or(0)
+    }
+}
+
+/// Questionnaire assessing the risk of RFCs (see [crate::entities::changes::risk]).
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RiskQuestionnaire {
+    /// Questions, in order.
+    pub questions: Vec<RiskQuestion>,
+}
+
+/// Payload for an answer of [RiskQuestionCreateset].
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RiskAnswerCreateset {
+    #[schema(example = "The whole organisation")]
+    #[validate(length(min = 1, max = 255))]
+    pub answer: String,
+    #[schema(example = 4)]
+    #[validate(range(min = 0, max = 100))]
+    pub score: i32,
+}
+
+/// Payload for a question of [RiskQuestionnaireUpdateset].
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_answers"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RiskQuestionCreateset {
+    #[schema(example = "How many users does the change affect?")]
+    #[validate(length(min = 1, max = 1024))]
+    pub question: String,
+    #[schema(example = 3)]
+    #[validate(range(min = 1, max = 100))]
+    pub weight: i32,
+    /// Possible answers, in order. At least two.
+    #[validate(nested)]
+    pub answers: Vec<RiskAnswerCreateset>,
+}
+
+/// Validate that a [RiskQuestionCreateset] has a choice of answers.
+fn validate_answers(question: &RiskQuestionCreateset) -> Result<(), ValidationError> {
+    if question.answers.len() < 2 {
+        return Err(ValidationError::new("Questions need at least two answers"));
+    }
+
+    Ok(())
+}
+
+/// Payload for replacing the risk questionnaire.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_scored"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RiskQuestionnaireUpdateset {
+    /// Questions, in order. At least one.
+    #[validate(nested)]
+    pub questions: Vec<RiskQuestionCreateset>,
+}
+
+/// Validate that a [RiskQuestionnaireUpdateset] has questions and some answer scores, so
+/// that RFCs can be told apart by their risk.
+fn validate_scored(updateset: &RiskQuestionnaireUpdateset) -> Result<(), ValidationError> {
+    if updateset.questions.is_empty() {
+        return Err(ValidationError::new(
+            "The questionnaire needs at least one question",
+        ));
+    }
+    let scored = updateset
+        .questions
+        .iter()
+        .flat_map(|q| &q.answers)
+        .any(|a| a.score > 0);
+    if !scored {
+        return Err(ValidationError::new(
+            "Some answer of the questionnaire must score",
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn load(
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<RiskQuestionnaire, crate::Error> {
+    let rows = sqlx::query!(
+        "
+        SELECT q.id AS question_id, q.question, q.weight, a.id AS answer_id, a.answer, a.score
+        FROM risk_questions AS q
+        JOIN risk_answers AS a ON a.question_id = q.id
+        ORDER BY q.position, a.position"
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let mut questions: Vec<RiskQuestion> = Vec::new();
+    let mut positions = HashMap::new();
+    for row in rows {
+        let i = *positions.entry(row.question_id).or_insert_with(|| {
+            questions.push(RiskQuestion {
+                id: row.question_id,
+                question: row.question,
+                weight: row.weight,
+                answers: Vec::new(),
+            });
+            questions.len() - 1
+        });
+        questions[i].answers.push(RiskAnswer {
+            id: row.answer_id,
+            answer: row.answer,
+            score: row.score,
+        });
+    }
+
+    Ok(RiskQuestionnaire { questions })
+}
+
+/// Replace the risk questionnaire. RFCs assessed already keep their assessment.
+pub async fn update(
+    updateset: RiskQuestionnaireUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RiskQuestionnaire, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    sqlx::query!("DELETE FROM risk_questions")
+        .execute(&mut *tx)
+        .await?;
+
+    for (position, question) in (1..).zip(&updateset.questions) {
+        let question_id = sqlx::query_scalar!(
+            "
+            INSERT INTO risk_questions (position, question, weight)
+            VALUES ($1, $2, $3)
+            RETURNING id",
+            position,
+            question.question,
+            question.weight,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (position, answer) in (1..).zip(&question.answers) {
+            sqlx::query!(
+                "
+                INSERT INTO risk_answers (question_id, position, answer, score)
+                VALUES ($1, $2, $3, $4)",
+                question_id,
+                position,
+                answer.answer,
+                answer.score,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let questionnaire = load(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(questionnaire)
+}
@@ -0,0 +1,517 @@
+use crate::entities::audit::{self, AuditEntity, AuditEntry};
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Something a caller may be allowed to do.
+///
+/// Permissions are stored in the `permissions` table and granted to subjects through roles.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[schema(example = "incidents.write")]
+pub enum Permission {
+    #[serde(rename = "incidents.write")]
+    IncidentsWrite,
+    #[serde(rename = "incidents.delete")]
+    IncidentsDelete,
+    #[serde(rename = "incidents.major")]
+    IncidentsMajor,
+    #[serde(rename = "problems.write")]
+    ProblemsWrite,
+    #[serde(rename = "problems.known_error")]
+    ProblemsKnownError,
+    #[serde(rename = "problems.close")]
+    ProblemsClose,
+    #[serde(rename = "problems.delete")]
+    ProblemsDelete,
+    #[serde(rename = "changes.write")]
+    ChangesWrite,
+    #[serde(rename = "changes.approve")]
+    ChangesApprove,
+    #[serde(rename = "changes.close")]
+    ChangesClose,
+    #[serde(rename = "changes.delete")]
+    ChangesDelete,
+    #[serde(rename = "configitems.write")]
+    ConfigItemsWrite,
+    #[serde(rename = "configitems.delete")]
+    ConfigItemsDelete,
+    #[serde(rename = "roles.manage")]
+    RolesManage,
+    #[serde(rename = "users.manage")]
+    UsersManage,
+    #[serde(rename = "teams.manage")]
+    TeamsManage,
+    #[serde(rename = "slapolicies.manage")]
+    SlaPoliciesManage,
+    #[serde(rename = "prioritymatrix.manage")]
+    PriorityMatrixManage,
+    #[serde(rename = "cab.manage")]
+    CabManage,
+    #[serde(rename = "freezeperiods.manage")]
+    FreezePeriodsManage,
+    #[serde(rename = "riskquestionnaire.manage")]
+    RiskQuestionnaireManage,
+    #[serde(rename = "rootcauses.manage")]
+    RootCausesManage,
+    #[serde(rename = "catalog.manage")]
+    CatalogManage,
+    #[serde(rename = "requests.write")]
+    RequestsWrite,
+    #[serde(rename = "knowledge.write")]
+    KnowledgeWrite,
+    #[serde(rename = "knowledge.manage")]
+    KnowledgeManage,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 26] = [
+        Self::IncidentsWrite,
+        Self::IncidentsDelete,
+        Self::IncidentsMajor,
+        Self::ProblemsWrite,
+        Self::ProblemsKnownError,
+        Self::ProblemsClose,
+        Self::ProblemsDelete,
+        Self::ChangesWrite,
+        Self::ChangesApprove,
+        Self::ChangesClose,
+        Self::ChangesDelete,
+        Self::ConfigItemsWrite,
+        Self::ConfigItemsDelete,
+        Self::RolesManage,
+        Self::UsersManage,
+        Self::TeamsManage,
+        Self::SlaPoliciesManage,
+        Self::PriorityMatrixManage,
+        Self::CabManage,
+        Self::FreezePeriodsManage,
+        Self::RiskQuestionnaireManage,
+        Self::RootCausesManage,
+        Self::CatalogManage,
+        Self::RequestsWrite,
+        Self::KnowledgeWrite,
+        Self::KnowledgeManage,
+    ];
+
+    /// Name of the permission in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IncidentsWrite => "incidents.write",
+            Self::IncidentsDelete => "incidents.delete",
+            Self::IncidentsMajor => "incidents.major",
+            Self::ProblemsWrite => "problems.write",
+            Self::ProblemsKnownError => "problems.known_error",
+            Self::ProblemsClose => "problems.close",
+            Self::ProblemsDelete => "problems.delete",
+            Self::ChangesWrite => "changes.write",
+            Self::ChangesApprove => "changes.approve",
+            Self::ChangesClose => "changes.close",
+            Self::ChangesDelete => "changes.delete",
+            Self::ConfigItemsWrite => "configitems.write",
+            Self::ConfigItemsDelete => "configitems.delete",
+            Self::RolesManage => "roles.manage",
+            Self::UsersManage => "users.manage",
+            Self::TeamsManage => "teams.manage",
+            Self::SlaPoliciesManage => "slapolicies.manage",
+            Self::PriorityMatrixManage => "prioritymatrix.manage",
+            Self::CabManage => "cab.manage",
+            Self::FreezePeriodsManage => "freezeperiods.manage",
+            Self::RiskQuestionnaireManage => "riskquestionnaire.manage",
+            Self::RootCausesManage => "rootcauses.manage",
+            Self::CatalogManage => "catalog.manage",
+            Self::RequestsWrite => "requests.write",
+            Self::KnowledgeWrite => "knowledge.write",
+            Self::KnowledgeManage => "knowledge.manage",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == name)
+    }
+}
+
+/// Role in the database, with the permissions it grants.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct Role {
+    #[schema(example = "service_desk_agent")]
+    pub name: String,
+    #[schema(example = "Registers and handles incidents.")]
+    pub description: String,
+    pub permissions: Vec<Permission>,
+}
+
+/// Row of the roles query, before parsing the permission names.
+struct RoleRow {
+    name: String,
+    description: String,
+    permissions: Vec<String>,
+}
+
+impl From<RoleRow> for Role {
+    fn from(row: RoleRow) -> Self {
+        Role {
+            name: row.name,
+            description: row.description,
+            // Permissions unknown to this version of the application grant nothing.
+            permissions: row
+                .permissions
+                .iter()
+                .filter_map(|name| Permission::from_name(name))
+                .collect(),
+        }
+    }
+}
+
+/// Payload for creating a Role.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RoleCreateset {
+    #[schema(example = "service_desk_agent")]
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[schema(example = "Registers and handles incidents.")]
+    #[validate(length(max = 1024))]
+    pub description: String,
+    pub permissions: Vec<Permission>,
+}
+
+/// Payload for updating a Role. Permissions, when sent, replace the current ones.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RoleUpdateset {
+    #[schema(example = "Registers and handles incidents.")]
+    #[validate(length(max = 1024))]
+    pub description: Option<String>,
+    pub permissions: Option<Vec<Permission>>,
+}
+
+/// Grant of a Role to a subject.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RoleAssignment {
+    #[schema(example = "jdoe")]
+    pub subject: String,
+    #[schema(example = "service_desk_agent")]
+    pub role: String,
+}
+
+fn permission_names(permissions: &[Permission]) -> Vec<String> {
+    permissions
+        .iter()
+        .map(|p| String::from(p.as_str()))
+        .collect()
+}
+
+pub async fn load_all(
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Vec<Role>, crate::Error> {
+    let roles = sqlx::query_as!(
+        RoleRow,
+        "
+        SELECT r.name, r.description,
+            COALESCE(
+                array_agg(rp.permission ORDER BY rp.permission) FILTER (WHERE rp.permission IS NOT NULL),
+                '{}'
+            ) as \"permissions!\"
+        FROM roles AS r
+        LEFT JOIN role_permissions AS rp
+        ON rp.role = r.name
+        GROUP BY r.name
+        ORDER BY r.name"
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(roles.into_iter().map(Role::from).collect())
+}
+
+pub async fn load(
+    name: &str,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Role, crate::Error> {
+    match sqlx::query_as!(
+        RoleRow,
+        "
+        SELECT r.name, r.description,
+            COALESCE(
+                array_agg(rp.permission ORDER BY rp.permission) FILTER (WHERE rp.permission IS NOT NULL),
+                '{}'
+            ) as \"permissions!\"
+        FROM roles AS r
+        LEFT JOIN role_permissions AS rp
+        ON rp.role = r.name
+        WHERE r.name = $1
+        GROUP BY r.name",
+        name
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(role) => Ok(role.into()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+pub async fn create(
+    createset: RoleCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Role, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    sqlx::query!(
+        "
+        INSERT INTO roles (name, description)
+        VALUES ($1, $2)",
+        createset.name,
+        createset.description,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_unique_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    })?;
+    sqlx::query!(
+        "
+        INSERT INTO role_permissions (role, permission)
+        SELECT $1, UNNEST($2::text[])
+        ON CONFLICT DO NOTHING",
+        createset.name,
+        &permission_names(&createset.permissions),
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let role = load(&createset.name, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(role)
+}
+
+pub async fn update(
+    name: &str,
+    updateset: RoleUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Role, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    if sqlx::query!(
+        "
+        UPDATE roles
+        SET description = COALESCE($1, description)
+        WHERE name = $2
+        RETURNING name",
+        updateset.description,
+        name,
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .is_none()
+    {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    if let Some(permissions) = updateset.permissions {
+        sqlx::query!(
+            "
+            DELETE FROM role_permissions
+            WHERE role = $1",
+            name,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "
+            INSERT INTO role_permissions (role, permission)
+            SELECT $1, UNNEST($2::text[])
+            ON CONFLICT DO NOTHING",
+            name,
+            &permission_names(&permissions),
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let role = load(name, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(role)
+}
+
+pub async fn delete(
+    name: &str,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM roles
+        WHERE name = $1
+        RETURNING name",
+        name
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Load the audit log of a role and its permissions and assignments, oldest first. Fails
+/// with [crate::Error::NoRecordFound] if the role never existed.
+pub async fn load_history(
+    name: &str,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<Vec<AuditEntry>, crate::Error> {
+    let mut conn = executor.acquire().await?;
+    // Same as the `id` column of `roles`, which deleted roles no longer have.
+    let id = sqlx::query_scalar!("SELECT md5($1)::uuid as \"id!\"", name)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    audit::load_history(AuditEntity::Role, id, &mut *conn).await
+}
+
+/// Check if a role with the name sent as path param exists in the database.
+async fn check_valid_role(
+    name: &str,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM roles WHERE name = $1)",
+        name
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+pub async fn load_assignments(
+    role: &str,
+    pool: &DbPool,
+) -> Result<Vec<RoleAssignment>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_role(role, &mut *tx).await?;
+    let assignments = sqlx::query_as!(
+        RoleAssignment,
+        "
+        SELECT subject, role
+        FROM role_assignments
+        WHERE role = $1
+        ORDER BY subject",
+        role
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(assignments)
+}
+
+/// Grant a role to a subject. Granting a role twice has no effect.
+pub async fn assign(
+    role: &str,
+    subject: &str,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<RoleAssignment, crate::Error> {
+    let mut tx = executor.begin().await?;
+    check_valid_role(role, &mut *tx).await?;
+    sqlx::query!(
+        "
+        INSERT INTO role_assignments (subject, role)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING",
+        subject,
+        role,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(RoleAssignment {
+        subject: String::from(subject),
+        role: String::from(role),
+    })
+}
+
+pub async fn unassign(
+    role: &str,
+    subject: &str,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM role_assignments
+        WHERE role = $1
+        AND subject = $2
+        RETURNING subject",
+        role,
+        subject,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Check if any of the roles of `subject` grants `permission`.
+pub async fn has_permission(
+    subject: &str,
+    permission: Permission,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<bool, crate::Error> {
+    let granted = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(
+            SELECT 1
+            FROM role_assignments AS ra
+            INNER JOIN role_permissions AS rp
+            ON rp.role = ra.role
+            WHERE ra.subject = $1
+            AND rp.permission = $2
+        )",
+        subject,
+        permission.as_str(),
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(granted.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod roles_tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_names_round_trip() {
+        for permission in Permission::ALL {
+            assert_eq!(Permission::from_name(permission.as_str()), Some(permission));
+
+            let json = serde_json::to_string(&permission).unwrap();
+            assert_eq!(json, format!("\"{}\"", permission.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_unknown_permission() {
+        assert_eq!(Permission::from_name("unknown.permission"), None);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::entity_helpers;
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Category of the taxonomy classifying the root causes of Problems (see
+/// [super::problems::rca]).
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct RootCauseCategory {
+    pub id: Uuid,
+    #[schema(example = "Configuration error")]
+    pub name: String,
+    #[schema(example = "A Configuration Item was set up wrong.")]
+    pub description: String,
+}
+
+/// Payload for creating a root cause category.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RootCauseCategoryCreateset {
+    #[schema(example = "Configuration error")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[schema(example = "A Configuration Item was set up wrong.")]
+    #[validate(length(max = 1024))]
+    pub description: String,
+}
+
+/// Payload for updating a root cause category.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct RootCauseCategoryUpdateset {
+    #[schema(example = "Configuration error")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<Option<String>>,
+    #[schema(example = "A Configuration Item was set up wrong.")]
+    #[validate(length(max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+}
+
+/// Validate that required fields of [RootCauseCategoryUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &RootCauseCategoryUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.name)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+
+    Ok(())
+}
+
+/// Map the errors of writing a root cause category, which may break the uniqueness of names
+/// or leave Problems without classification.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_unique_violation() || dbe.is_foreign_key_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load the whole taxonomy, sorted by name.
+pub async fn load_all(pool: &DbPool) -> Result<Vec<RootCauseCategory>, crate::Error> {
+    let categories = sqlx::query_as!(
+        RootCauseCategory,
+        "
+        SELECT id, name, description
+        FROM root_cause_categories
+        ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(categories)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<RootCauseCategory, crate::Error> {
+    sqlx::query_as!(
+        RootCauseCategory,
+        "
+        SELECT id, name, description
+        FROM root_cause_categories
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+pub async fn create(
+    createset: RootCauseCategoryCreateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<RootCauseCategory, crate::Error> {
+    createset.validate()?;
+
+    let category = sqlx::query_as!(
+        RootCauseCategory,
+        "
+        INSERT INTO root_cause_categories (name, description)
+        VALUES ($1, $2)
+        RETURNING id, name, description",
+        createset.name,
+        createset.description,
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(map_write_error)?;
+
+    Ok(category)
+}
+
+pub async fn update(
+    id: Uuid,
+    updateset: RootCauseCategoryUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<RootCauseCategory, crate::Error> {
+    updateset.validate()?;
+
+    sqlx::query_as!(
+        RootCauseCategory,
+        "
+        UPDATE root_cause_categories
+        SET name = COALESCE($1, name), description = COALESCE($2, description)
+        WHERE id = $3
+        RETURNING id, name, description",
+        updateset.name.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        id,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Delete a root cause category. Fails with [crate::Error::ConstraintError] while some
+/// Problem is classified under it.
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM root_cause_categories
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
@@ -0,0 +1,172 @@
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Kind of entity a [SearchResult] is.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[schema(example = "incident")]
+pub enum SearchEntityType {
+    Incident,
+    ConfigItem,
+    Problem,
+    Rfc,
+    /// Change made to a Configuration Item.
+    CiChange,
+}
+
+impl SearchEntityType {
+    /// Name of the entity type as it appears in the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incident => "incident",
+            Self::ConfigItem => "configitem",
+            Self::Problem => "problem",
+            Self::Rfc => "rfc",
+            Self::CiChange => "cichange",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::Incident,
+            Self::ConfigItem,
+            Self::Problem,
+            Self::Rfc,
+            Self::CiChange,
+        ]
+        .into_iter()
+        .find(|t| t.as_str() == name)
+    }
+}
+
+/// Query parameters for searching all entities.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct SearchParams {
+    /// Text to search for. Words can be quoted to match phrases, joined by `or` to match any
+    /// of them, and prefixed with `-` to exclude them.
+    #[validate(length(min = 1, max = 1024))]
+    pub q: String,
+    /// Only entities of this type.
+    pub r#type: Option<SearchEntityType>,
+    /// Only entities in this status, as it appears in the API of their type. CI changes have
+    /// no status, so they never match.
+    #[validate(length(min = 1, max = 64))]
+    pub status: Option<String>,
+    /// Max amount of results. Defaults to 20.
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+}
+
+/// Entity matching a search.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct SearchResult {
+    pub r#type: SearchEntityType,
+    pub id: Uuid,
+    /// Configuration Item of CI changes, which are addressed through it.
+    pub ci_id: Option<Uuid>,
+    /// Title of the entity, or name of the Configuration Item of CI changes.
+    #[schema(example = "Printer jams")]
+    pub title: String,
+    #[schema(example = "inprogress")]
+    pub status: Option<String>,
+    /// Fragments of the description and documentation of the entity around the matches,
+    /// which are wrapped in `<mark>` tags, or the start of the description when only the
+    /// title matches. Fragments are separated by ` ... `. The text of the entity is
+    /// HTML-escaped, so the `<mark>` tags are the only markup.
+    #[schema(example = "Printer <mark>jams</mark> with large documents")]
+    pub highlight: String,
+    /// Relevance of the match. Higher is better.
+    pub rank: f32,
+}
+
+/// Search the incidents, Configuration Items, Problems, RFCs and CI changes matching the text
+/// in `params`, most relevant first.
+pub async fn search(
+    params: SearchParams,
+    pool: &DbPool,
+) -> Result<Vec<SearchResult>, crate::Error> {
+    params.validate()?;
+
+    // Highlights are expensive, so they're only made for the matches that are returned.
+    let rows = sqlx::query!(
+        "
+        WITH query AS (
+            SELECT websearch_to_tsquery('english', $1) AS q
+        ), documents AS (
+            SELECT 'incident' AS type, id, NULL::uuid AS ci_id, title, status::text AS status,
+                description || ' ' || coalesce(resolution_notes, '') AS text, search_vector
+            FROM incidents
+            UNION ALL
+            SELECT 'configitem', id, NULL, name, status::text,
+                description || ' ' || coalesce(type, ''), search_vector
+            FROM configitems
+            UNION ALL
+            SELECT 'problem', id, NULL, title, status::text,
+                description || ' ' || causes || ' ' || coalesce(workarounds, '') || ' '
+                    || coalesce(resolutions, ''),
+                search_vector
+            FROM problems
+            UNION ALL
+            SELECT 'rfc', id, NULL, title, status::text,
+                description || ' ' || coalesce(implementation_plan, '') || ' '
+                    || coalesce(backout_plan, '') || ' ' || coalesce(test_plan, ''),
+                search_vector
+            FROM rfcs
+            UNION ALL
+            SELECT 'cichange', c.id, c.ci_id, i.name, NULL, c.documentation, c.search_vector
+            FROM ci_changes AS c
+            JOIN configitems AS i ON i.id = c.ci_id
+        ), matches AS (
+            SELECT d.type, d.id, d.ci_id, d.title, d.status, d.text,
+                ts_rank(d.search_vector, query.q) AS rank
+            FROM documents AS d
+            CROSS JOIN query
+            WHERE d.search_vector @@ query.q
+            AND ($2::text IS NULL OR d.type = $2)
+            AND ($3::text IS NULL OR d.status = $3)
+            ORDER BY rank DESC, d.id
+            LIMIT $4
+        )
+        SELECT m.type AS \"type!\", m.id AS \"id!\", m.ci_id, m.title AS \"title!\", m.status,
+            ts_headline(
+                'english',
+                replace(replace(replace(m.text, '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                query.q,
+                'StartSel=<mark>, StopSel=</mark>, MaxFragments=3, MaxWords=20, MinWords=5'
+            ) AS \"highlight!\",
+            m.rank AS \"rank!\"
+        FROM matches AS m
+        CROSS JOIN query
+        ORDER BY m.rank DESC, m.id",
+        params.q,
+        params.r#type.map(|t| t.as_str()),
+        params.status,
+        params.limit.unwrap_or(20),
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|row| SearchResult {
+            r#type: SearchEntityType::from_name(&row.r#type)
+                .expect("the query only returns known types"),
+            id: row.id,
+            ci_id: row.ci_id,
+            title: row.title,
+            status: row.status,
+            highlight: row.highlight,
+            rank: row.rank,
+        })
+        .collect();
+
+    Ok(results)
+}
@@ -0,0 +1,448 @@
+use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{Postgres, QueryBuilder};
+use std::collections::HashSet;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Most form fields a catalog item can have.
+pub const MAX_FORM_FIELDS: usize = 50;
+/// Most fulfilment tasks a catalog item can have.
+pub const MAX_FULFILMENT_TASKS: usize = 50;
+
+/// Item of the service catalog, something users can request (see
+/// [super::service_requests]).
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct CatalogItem {
+    pub id: Uuid,
+    #[schema(example = "New laptop")]
+    pub name: String,
+    #[schema(example = "A laptop with the standard software installed.")]
+    pub description: String,
+    /// Fields requests for the item fill in, in order.
+    #[schema(value_type = Vec<CatalogFormField>)]
+    pub form_fields: Json<Vec<CatalogFormField>>,
+    /// Team whose members approve requests for the item. Requests need no approval without
+    /// one.
+    pub approval_group_id: Option<Uuid>,
+    /// Team fulfilling requests for the item.
+    pub fulfilment_group_id: Option<Uuid>,
+    /// Tasks fulfilling requests for the item, in order.
+    #[schema(example = json!(["Order the laptop.", "Install the software.", "Hand it over."]))]
+    pub fulfilment_tasks: Vec<String>,
+    /// Whether the item can be requested.
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Field of the form of a catalog item.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_options"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub struct CatalogFormField {
+    /// Key of the value of the field in the answers of requests.
+    #[schema(example = "operating_system")]
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[schema(example = "Operating system")]
+    #[validate(length(min = 1, max = 255))]
+    pub label: String,
+    pub r#type: CatalogFieldType,
+    /// Whether requests must answer the field.
+    #[serde(default)]
+    pub required: bool,
+    /// Values the answer can take. Required for `choice` fields, not allowed otherwise.
+    #[schema(example = json!(["Linux", "Windows"]))]
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// Type of the value of a [CatalogFormField].
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[schema(example = "choice")]
+pub enum CatalogFieldType {
+    Text,
+    Number,
+    Boolean,
+    /// One of the options of the field.
+    Choice,
+}
+
+/// Validate that only `choice` fields have options, and that they do have some.
+fn validate_options(field: &CatalogFormField) -> Result<(), ValidationError> {
+    if (field.r#type == CatalogFieldType::Choice) == field.options.is_empty() {
+        return Err(ValidationError::new(
+            "Choice fields and only them need options",
+        ));
+    }
+    if field.options.iter().any(|o| o.is_empty() || o.len() > 255) {
+        return Err(ValidationError::new(
+            "Options must have between 1 and 255 characters",
+        ));
+    }
+
+    Ok(())
+}
+
+impl CatalogFormField {
+    /// Whether `value` is a valid answer to the field.
+    fn accepts(&self, value: &serde_json::Value) -> bool {
+        match self.r#type {
+            CatalogFieldType::Text => value.as_str().is_some_and(|s| s.len() <= 4096),
+            CatalogFieldType::Number => value.is_number(),
+            CatalogFieldType::Boolean => value.is_boolean(),
+            CatalogFieldType::Choice => value
+                .as_str()
+                .is_some_and(|s| self.options.iter().any(|o| o == s)),
+        }
+    }
+}
+
+/// Validate that the form fields have unique names, and aren't too many.
+fn validate_form_fields(fields: &[CatalogFormField]) -> Result<(), ValidationError> {
+    if fields.len() > MAX_FORM_FIELDS {
+        return Err(ValidationError::new("Too many form fields"));
+    }
+    let mut names = HashSet::new();
+    if !fields.iter().all(|f| names.insert(&f.name)) {
+        return Err(ValidationError::new("Form fields must have unique names"));
+    }
+
+    Ok(())
+}
+
+/// Validate that the fulfilment tasks aren't too many, nor empty or too long.
+fn validate_fulfilment_tasks(tasks: &[String]) -> Result<(), ValidationError> {
+    if tasks.len() > MAX_FULFILMENT_TASKS {
+        return Err(ValidationError::new("Too many fulfilment tasks"));
+    }
+    if tasks.iter().any(|t| t.is_empty() || t.len() > 1024) {
+        return Err(ValidationError::new(
+            "Fulfilment tasks must have between 1 and 1024 characters",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that `answers` fill in the form of a catalog item, i.e. they're an object with valid
+/// values for the fields of the form, including the required ones, and nothing else.
+pub(crate) fn check_answers(
+    fields: &[CatalogFormField],
+    answers: &serde_json::Value,
+) -> Result<(), crate::Error> {
+    let Some(answers) = answers.as_object() else {
+        return Err(entity_helpers::invalid_field(
+            "answers",
+            "Answers must be an object",
+        ));
+    };
+    if answers
+        .keys()
+        .any(|name| !fields.iter().any(|f| &f.name == name))
+    {
+        return Err(entity_helpers::invalid_field(
+            "answers",
+            "Answers must only fill in fields of the form",
+        ));
+    }
+    for field in fields {
+        match answers.get(&field.name) {
+            None | Some(serde_json::Value::Null) if field.required => {
+                return Err(entity_helpers::invalid_field(
+                    "answers",
+                    "Required fields of the form must be answered",
+                ));
+            }
+            Some(value) if !value.is_null() && !field.accepts(value) => {
+                return Err(entity_helpers::invalid_field(
+                    "answers",
+                    "Answers must match the type and options of their fields",
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Payload for creating a catalog item.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CatalogItemCreateset {
+    #[schema(example = "New laptop")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[schema(example = "A laptop with the standard software installed.")]
+    #[validate(length(max = 1024))]
+    #[serde(default)]
+    pub description: String,
+    /// At most [MAX_FORM_FIELDS], with unique names.
+    #[validate(nested, custom(function = "validate_form_fields"))]
+    #[serde(default)]
+    pub form_fields: Vec<CatalogFormField>,
+    /// Team whose members approve requests for the item. Requests need no approval without
+    /// one.
+    pub approval_group_id: Option<Uuid>,
+    pub fulfilment_group_id: Option<Uuid>,
+    /// At most [MAX_FULFILMENT_TASKS], none of them empty.
+    #[schema(example = json!(["Order the laptop.", "Install the software.", "Hand it over."]))]
+    #[validate(custom(function = "validate_fulfilment_tasks"))]
+    #[serde(default)]
+    pub fulfilment_tasks: Vec<String>,
+    /// Defaults to `true`.
+    pub active: Option<bool>,
+}
+
+/// Payload for updating a catalog item. Requests made already keep their answers and tasks.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CatalogItemUpdateset {
+    #[schema(example = "New laptop")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<Option<String>>,
+    #[schema(example = "A laptop with the standard software installed.")]
+    #[validate(length(max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+    /// Replaces the current fields.
+    #[validate(nested, custom(function = "validate_form_fields"))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub form_fields: Option<Option<Vec<CatalogFormField>>>,
+    /// Set to null so that requests for the item need no approval.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub approval_group_id: Option<Option<Uuid>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub fulfilment_group_id: Option<Option<Uuid>>,
+    /// Replaces the current tasks.
+    #[schema(example = json!(["Order the laptop.", "Install the software.", "Hand it over."]))]
+    #[validate(custom(function = "validate_fulfilment_tasks"))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub fulfilment_tasks: Option<Option<Vec<String>>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub active: Option<Option<bool>>,
+}
+
+/// Validate that required fields of [CatalogItemUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &CatalogItemUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.name)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+    entity_helpers::validate_not_null(&updateset.form_fields)?;
+    entity_helpers::validate_not_null(&updateset.fulfilment_tasks)?;
+    entity_helpers::validate_not_null(&updateset.active)?;
+
+    Ok(())
+}
+
+/// Query parameters for listing catalog items.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct CatalogItemListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Only items that can (`true`) or can't (`false`) be requested.
+    pub active: Option<bool>,
+}
+
+/// Append the `WHERE` clause matching the filters in [CatalogItemListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a CatalogItemListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(active) = params.active {
+        builder.push(" AND active = ").push_bind(active);
+    }
+}
+
+/// Map the errors of writing a catalog item, which may break the uniqueness of names,
+/// reference nonexistent teams or leave requests without item.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_unique_violation() || dbe.is_foreign_key_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load one page of catalog items matching the filters in `params`, sorted by name.
+pub async fn load_page(
+    params: CatalogItemListParams,
+    pool: &DbPool,
+) -> Result<Page<CatalogItem>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let items = pagination::load_page::<CatalogItem>(
+        &mut tx,
+        "
+        id, name, description, form_fields, approval_group_id, fulfilment_group_id,
+        fulfilment_tasks, active, created_at",
+        "catalog_items",
+        |builder| push_filters(builder, &params),
+        "name, id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(items)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<CatalogItem, crate::Error> {
+    sqlx::query_as!(
+        CatalogItem,
+        "
+        SELECT id, name, description,
+            form_fields as \"form_fields: Json<Vec<CatalogFormField>>\", approval_group_id,
+            fulfilment_group_id, fulfilment_tasks, active, created_at
+        FROM catalog_items
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+pub async fn create(
+    createset: CatalogItemCreateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<CatalogItem, crate::Error> {
+    createset.validate()?;
+
+    let item = sqlx::query_as!(
+        CatalogItem,
+        "
+        INSERT INTO catalog_items (name, description, form_fields, approval_group_id,
+            fulfilment_group_id, fulfilment_tasks, active)
+        VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, TRUE))
+        RETURNING id, name, description,
+            form_fields as \"form_fields: Json<Vec<CatalogFormField>>\", approval_group_id,
+            fulfilment_group_id, fulfilment_tasks, active, created_at",
+        createset.name,
+        createset.description,
+        Json(createset.form_fields) as Json<Vec<CatalogFormField>>,
+        createset.approval_group_id,
+        createset.fulfilment_group_id,
+        &createset.fulfilment_tasks,
+        createset.active,
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(map_write_error)?;
+
+    Ok(item)
+}
+
+pub async fn update(
+    id: Uuid,
+    updateset: CatalogItemUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<CatalogItem, crate::Error> {
+    updateset.validate()?;
+
+    sqlx::query_as!(
+        CatalogItem,
+        "
+        UPDATE catalog_items
+        SET name = COALESCE($1, name), description = COALESCE($2, description),
+            form_fields = COALESCE($3, form_fields),
+            approval_group_id = CASE
+                WHEN $4 THEN approval_group_id
+                ELSE $5
+            END,
+            fulfilment_group_id = CASE
+                WHEN $6 THEN fulfilment_group_id
+                ELSE $7
+            END,
+            fulfilment_tasks = COALESCE($8, fulfilment_tasks), active = COALESCE($9, active)
+        WHERE id = $10
+        RETURNING id, name, description,
+            form_fields as \"form_fields: Json<Vec<CatalogFormField>>\", approval_group_id,
+            fulfilment_group_id, fulfilment_tasks, active, created_at",
+        updateset.name.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        updateset.form_fields.unwrap_or(None).map(Json) as Option<Json<Vec<CatalogFormField>>>,
+        updateset.approval_group_id.is_none(),
+        updateset.approval_group_id.unwrap_or(None),
+        updateset.fulfilment_group_id.is_none(),
+        updateset.fulfilment_group_id.unwrap_or(None),
+        updateset.fulfilment_tasks.unwrap_or(None) as Option<Vec<String>>,
+        updateset.active.unwrap_or(None),
+        id,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Delete a catalog item. Fails with [crate::Error::ConstraintError] once it has been
+/// requested, so deactivate it instead.
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM catalog_items
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
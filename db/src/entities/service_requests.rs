@@ -0,0 +1,357 @@
+use crate::entities::service_catalog::{self, CatalogFormField};
+use crate::entity_helpers::{self, Lifecycle};
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::{PgConnection, Postgres, QueryBuilder, Type};
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Module for the approval of service requests.
+pub mod approvals;
+/// Module for the tasks fulfilling service requests.
+pub mod tasks;
+
+/// Request for an item of the service catalog, like a new laptop.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct ServiceRequest {
+    pub id: Uuid,
+    pub catalog_item_id: Uuid,
+    /// User the request is for.
+    pub requester_id: Uuid,
+    pub status: ServiceRequestStatus,
+    /// Values of the form fields of the catalog item, by field name.
+    #[schema(value_type = Object, example = json!({"operating_system": "Linux"}))]
+    pub answers: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    /// When the last task of the request was completed.
+    pub fulfilled_at: Option<DateTime<Utc>>,
+}
+
+/// Status of a service request in its lifecycle.
+///
+/// Requests for items with an approval group start out `pendingapproval`, until the group
+/// approves or rejects them (see [approvals]). Approved requests, and requests for items
+/// without approval group, are `infulfilment` until their tasks are completed (see [tasks])
+/// and they're `fulfilled`. These transitions can't be made by hand. Requests can be
+/// `cancelled` before they're fulfilled, and are `closed` afterwards.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "service_request_status", rename_all = "lowercase")]
+#[schema(example = "infulfilment")]
+pub enum ServiceRequestStatus {
+    PendingApproval,
+    Rejected,
+    InFulfilment,
+    Fulfilled,
+    Closed,
+    Cancelled,
+}
+
+impl Lifecycle for ServiceRequestStatus {
+    /// Statuses a request can be moved to by hand.
+    fn next(&self) -> &'static [Self] {
+        use ServiceRequestStatus::*;
+        match self {
+            PendingApproval => &[Cancelled],
+            InFulfilment => &[Cancelled],
+            Fulfilled => &[Closed],
+            Rejected | Closed | Cancelled => &[],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::PendingApproval => "pendingapproval",
+            Self::Rejected => "rejected",
+            Self::InFulfilment => "infulfilment",
+            Self::Fulfilled => "fulfilled",
+            Self::Closed => "closed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Payload for requesting a catalog item.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize))]
+pub struct ServiceRequestCreateset {
+    /// Must be active.
+    pub catalog_item_id: Uuid,
+    /// User the request is for.
+    pub requester_id: Uuid,
+    /// Values of the form fields of the catalog item, by field name. Required fields must be
+    /// answered.
+    #[schema(value_type = Object, example = json!({"operating_system": "Linux"}))]
+    #[serde(default = "empty_answers")]
+    pub answers: serde_json::Value,
+}
+
+fn empty_answers() -> serde_json::Value {
+    serde_json::Value::Object(serde_json::Map::new())
+}
+
+/// Payload for updating a service request.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(feature = "test-helpers", derive(Serialize))]
+pub struct ServiceRequestUpdateset {
+    /// Must be reachable by hand from the current status (see [ServiceRequestStatus]).
+    #[schema(example = "cancelled")]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub status: Option<Option<ServiceRequestStatus>>,
+    /// Replaces the current answers. They can only change while the request is pending
+    /// approval.
+    #[schema(value_type = Option<Object>, example = json!({"operating_system": "Linux"}))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub answers: Option<Option<serde_json::Value>>,
+}
+
+/// Validate that required fields of [ServiceRequestUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &ServiceRequestUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.status)?;
+    entity_helpers::validate_not_null(&updateset.answers)?;
+
+    Ok(())
+}
+
+/// Query parameters for listing service requests.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ServiceRequestListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    pub status: Option<ServiceRequestStatus>,
+    /// Only requests for this catalog item.
+    pub catalog_item_id: Option<Uuid>,
+    /// Only requests for this user.
+    pub requester_id: Option<Uuid>,
+}
+
+/// Append the `WHERE` clause matching the filters in [ServiceRequestListParams].
+fn push_filters<'a>(
+    builder: &mut QueryBuilder<'a, Postgres>,
+    params: &'a ServiceRequestListParams,
+) {
+    builder.push(" WHERE TRUE");
+    if let Some(status) = params.status {
+        builder.push(" AND status = ").push_bind(status);
+    }
+    if let Some(catalog_item_id) = params.catalog_item_id {
+        builder
+            .push(" AND catalog_item_id = ")
+            .push_bind(catalog_item_id);
+    }
+    if let Some(requester_id) = params.requester_id {
+        builder.push(" AND requester_id = ").push_bind(requester_id);
+    }
+}
+
+/// Map the errors of writing a service request, which may reference a nonexistent user.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load one page of service requests matching the filters in `params`, oldest first.
+pub async fn load_page(
+    params: ServiceRequestListParams,
+    pool: &DbPool,
+) -> Result<Page<ServiceRequest>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let requests = pagination::load_page::<ServiceRequest>(
+        &mut tx,
+        "id, catalog_item_id, requester_id, status, answers, created_at, fulfilled_at",
+        "service_requests",
+        |builder| push_filters(builder, &params),
+        "created_at, id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(requests)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<ServiceRequest, crate::Error> {
+    sqlx::query_as!(
+        ServiceRequest,
+        "
+        SELECT id, catalog_item_id, requester_id, status as \"status: ServiceRequestStatus\",
+            answers, created_at, fulfilled_at
+        FROM service_requests
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)
+}
+
+/// Start fulfilling the service request `id` with the tasks of its catalog item. Requests
+/// for items without tasks are fulfilled right away.
+async fn start_fulfilment(id: Uuid, conn: &mut PgConnection) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        INSERT INTO service_request_tasks (request_id, position, title)
+        SELECT r.id, t.position, t.title
+        FROM service_requests AS r
+        JOIN catalog_items AS i ON i.id = r.catalog_item_id
+        CROSS JOIN UNNEST(i.fulfilment_tasks) WITH ORDINALITY AS t(title, position)
+        WHERE r.id = $1",
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "
+        UPDATE service_requests
+        SET status = 'infulfilment'
+        WHERE id = $1",
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    tasks::check_fulfilled(id, conn).await
+}
+
+/// Request a catalog item.
+///
+/// Fails with [crate::Error::ConstraintError] if the item or the requester don't exist, and
+/// with [crate::Error::ValidationError] if the item isn't active or the answers don't fill
+/// in its form (see [CatalogFormField]). Requests for items without approval group go
+/// straight to fulfilment.
+pub async fn create(
+    createset: ServiceRequestCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<ServiceRequest, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let item = match service_catalog::load(createset.catalog_item_id, &mut *tx).await {
+        Err(crate::Error::NoRecordFound) => Err(crate::Error::ConstraintError),
+        result => result,
+    }?;
+    if !item.active {
+        return Err(entity_helpers::invalid_field(
+            "catalog_item_id",
+            "Inactive catalog items can't be requested",
+        ));
+    }
+    service_catalog::check_answers(&item.form_fields, &createset.answers)?;
+
+    let status = if item.approval_group_id.is_some() {
+        ServiceRequestStatus::PendingApproval
+    } else {
+        ServiceRequestStatus::InFulfilment
+    };
+    let id = sqlx::query_scalar!(
+        "
+        INSERT INTO service_requests (catalog_item_id, requester_id, status, answers)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id",
+        createset.catalog_item_id,
+        createset.requester_id,
+        status as ServiceRequestStatus,
+        createset.answers,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+    if status == ServiceRequestStatus::InFulfilment {
+        start_fulfilment(id, &mut tx).await?;
+    }
+
+    let request = load(id, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(request)
+}
+
+/// Update a service request, moving it to another status if the updateset has one.
+///
+/// Fails with [crate::Error::InvalidTransition] if the new status can't be reached by hand
+/// from the current one (see [ServiceRequestStatus]), and with
+/// [crate::Error::ValidationError] if the answers change after the request left
+/// `pendingapproval` or don't fill in the form of its catalog item.
+pub async fn update(
+    id: Uuid,
+    updateset: ServiceRequestUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<ServiceRequest, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let current = sqlx::query!(
+        "
+        SELECT r.status as \"status: ServiceRequestStatus\",
+            i.form_fields as \"form_fields: Json<Vec<CatalogFormField>>\"
+        FROM service_requests AS r
+        JOIN catalog_items AS i ON i.id = r.catalog_item_id
+        WHERE r.id = $1
+        FOR UPDATE OF r",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let status = updateset.status.flatten().unwrap_or(current.status);
+    entity_helpers::check_transition(current.status, status)?;
+
+    let answers = updateset.answers.flatten();
+    if let Some(answers) = &answers {
+        if current.status != ServiceRequestStatus::PendingApproval {
+            return Err(entity_helpers::invalid_field(
+                "answers",
+                "Answers can only change while the request is pending approval",
+            ));
+        }
+        service_catalog::check_answers(&current.form_fields, answers)?;
+    }
+
+    sqlx::query!(
+        "
+        UPDATE service_requests
+        SET status = $1, answers = COALESCE($2, answers)
+        WHERE id = $3",
+        status as ServiceRequestStatus,
+        answers,
+        id,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let request = load(id, &mut *tx).await?;
+    tx.commit().await?;
+    Ok(request)
+}
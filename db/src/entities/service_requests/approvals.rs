@@ -0,0 +1,169 @@
+use super::ServiceRequestStatus;
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{Postgres, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "service_request_vote", rename_all = "lowercase")]
+#[schema(example = "approve")]
+pub enum ServiceRequestVote {
+    Approve,
+    Reject,
+}
+
+/// Decision of a member of the approval group of a catalog item on a request for it.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct ServiceRequestApproval {
+    pub id: Uuid,
+    pub request_id: Uuid,
+    /// Member of the approval group who made the decision.
+    pub approver_id: Uuid,
+    pub vote: ServiceRequestVote,
+    #[schema(example = "Budgeted for this quarter.")]
+    pub comment: String,
+    pub decided_at: DateTime<Utc>,
+}
+
+/// Payload for deciding on a service request.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ServiceRequestApprovalCreateset {
+    pub vote: ServiceRequestVote,
+    #[schema(example = "Budgeted for this quarter.")]
+    #[validate(length(min = 1, max = 1024))]
+    pub comment: String,
+}
+
+async fn check_valid_request(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM service_requests WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Load the decisions on a service request, oldest first.
+pub async fn load_all(
+    request_id: Uuid,
+    pool: &DbPool,
+) -> Result<Vec<ServiceRequestApproval>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_request(request_id, &mut *tx).await?;
+    let approvals = sqlx::query_as!(
+        ServiceRequestApproval,
+        "
+        SELECT id, request_id, approver_id, vote as \"vote: ServiceRequestVote\", comment,
+            decided_at
+        FROM service_request_approvals
+        WHERE request_id = $1
+        ORDER BY decided_at, id",
+        request_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(approvals)
+}
+
+/// Record the decision of the user with username `approver` on a service request.
+///
+/// Fails with [crate::Error::NotAllowed] if the request isn't pending approval or `approver`
+/// isn't a member of the approval group of its catalog item. A single decision settles the
+/// request: an approval starts its fulfilment, and a rejection rejects it.
+pub async fn create(
+    request_id: Uuid,
+    approver: &str,
+    createset: ServiceRequestApprovalCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<ServiceRequestApproval, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let request = sqlx::query!(
+        "
+        SELECT r.status as \"status: ServiceRequestStatus\", i.approval_group_id
+        FROM service_requests AS r
+        JOIN catalog_items AS i ON i.id = r.catalog_item_id
+        WHERE r.id = $1
+        FOR UPDATE OF r",
+        request_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if request.status != ServiceRequestStatus::PendingApproval {
+        return Err(crate::Error::NotAllowed(
+            "The request isn't pending approval",
+        ));
+    }
+
+    let approver_id = sqlx::query_scalar!(
+        "
+        SELECT u.id
+        FROM users AS u
+        INNER JOIN team_members AS m
+        ON m.user_id = u.id
+        WHERE u.username = $1
+        AND m.team_id = $2",
+        approver,
+        request.approval_group_id,
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NotAllowed(
+        "Only members of the approval group can decide on requests",
+    ))?;
+
+    let approval = sqlx::query_as!(
+        ServiceRequestApproval,
+        "
+        INSERT INTO service_request_approvals (request_id, approver_id, vote, comment)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, request_id, approver_id, vote as \"vote: ServiceRequestVote\", comment,
+            decided_at",
+        request_id,
+        approver_id,
+        createset.vote as ServiceRequestVote,
+        createset.comment,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    match approval.vote {
+        ServiceRequestVote::Approve => super::start_fulfilment(request_id, &mut tx).await?,
+        ServiceRequestVote::Reject => {
+            sqlx::query!(
+                "
+                UPDATE service_requests
+                SET status = 'rejected'
+                WHERE id = $1",
+                request_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(approval)
+}
@@ -0,0 +1,232 @@
+use super::ServiceRequestStatus;
+use crate::entity_helpers::{self, Lifecycle};
+use crate::DbPool;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::{PgConnection, Postgres, Type};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Task fulfilling a service request, copied from the tasks of its catalog item.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct ServiceRequestTask {
+    pub id: Uuid,
+    pub request_id: Uuid,
+    /// Order of the task among the tasks of the request, starting at 1.
+    pub position: i32,
+    #[schema(example = "Order the laptop.")]
+    pub title: String,
+    pub status: ServiceRequestTaskStatus,
+    /// User working on the task.
+    pub assignee_id: Option<Uuid>,
+    /// When the task was done or skipped.
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Status of a fulfilment task. Tasks that are `done` or `skipped` are completed, which is
+/// final.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, Type, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "service_request_task_status", rename_all = "lowercase")]
+#[schema(example = "done")]
+pub enum ServiceRequestTaskStatus {
+    Open,
+    InProgress,
+    Done,
+    Skipped,
+}
+
+impl Lifecycle for ServiceRequestTaskStatus {
+    fn next(&self) -> &'static [Self] {
+        use ServiceRequestTaskStatus::*;
+        match self {
+            Open => &[InProgress, Done, Skipped],
+            InProgress => &[Open, Done, Skipped],
+            Done | Skipped => &[],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::InProgress => "inprogress",
+            Self::Done => "done",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+impl ServiceRequestTaskStatus {
+    /// Whether tasks in this status are completed, i.e. have a `completed_at`.
+    pub fn is_completed(&self) -> bool {
+        matches!(self, Self::Done | Self::Skipped)
+    }
+}
+
+/// Payload for updating a fulfilment task.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct ServiceRequestTaskUpdateset {
+    /// Must be reachable from the current status (see [ServiceRequestTaskStatus::next]).
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub status: Option<Option<ServiceRequestTaskStatus>>,
+    /// User working on the task. Set to null to unassign it.
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub assignee_id: Option<Option<Uuid>>,
+}
+
+/// Validate that required fields of [ServiceRequestTaskUpdateset] aren't explicitly null.
+fn validate_required_fields(
+    updateset: &ServiceRequestTaskUpdateset,
+) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.status)?;
+
+    Ok(())
+}
+
+/// Map the errors of writing a task, which may reference a nonexistent assignee.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load the tasks of a service request, in order.
+pub async fn load_all(
+    request_id: Uuid,
+    pool: &DbPool,
+) -> Result<Vec<ServiceRequestTask>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    super::load(request_id, &mut *tx).await?;
+    let tasks = sqlx::query_as!(
+        ServiceRequestTask,
+        "
+        SELECT id, request_id, position, title,
+            status as \"status: ServiceRequestTaskStatus\", assignee_id, completed_at
+        FROM service_request_tasks
+        WHERE request_id = $1
+        ORDER BY position",
+        request_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(tasks)
+}
+
+/// Mark the service request `request_id` as fulfilled if it's in fulfilment and all of its
+/// tasks are completed.
+pub(crate) async fn check_fulfilled(
+    request_id: Uuid,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "
+        UPDATE service_requests
+        SET status = 'fulfilled', fulfilled_at = now()
+        WHERE id = $1
+        AND status = 'infulfilment'
+        AND NOT EXISTS(
+            SELECT 1 FROM service_request_tasks
+            WHERE request_id = $1
+            AND status NOT IN ('done', 'skipped')
+        )",
+        request_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Update a task of the service request `request_id`.
+///
+/// Fails with [crate::Error::NotAllowed] unless the request is in fulfilment, and with
+/// [crate::Error::InvalidTransition] if the new status isn't reachable from the current one.
+/// The request is fulfilled once its last task is completed.
+pub async fn update(
+    request_id: Uuid,
+    id: Uuid,
+    updateset: ServiceRequestTaskUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<ServiceRequestTask, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let request_status = sqlx::query_scalar!(
+        "
+        SELECT status as \"status: ServiceRequestStatus\"
+        FROM service_requests
+        WHERE id = $1
+        FOR UPDATE",
+        request_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    let current_status = sqlx::query_scalar!(
+        "
+        SELECT status as \"status: ServiceRequestTaskStatus\"
+        FROM service_request_tasks
+        WHERE id = $1
+        AND request_id = $2",
+        id,
+        request_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+    if request_status != ServiceRequestStatus::InFulfilment {
+        return Err(crate::Error::NotAllowed("The request isn't in fulfilment"));
+    }
+
+    let status = updateset.status.flatten().unwrap_or(current_status);
+    entity_helpers::check_transition(current_status, status)?;
+
+    let task = sqlx::query_as!(
+        ServiceRequestTask,
+        "
+        UPDATE service_request_tasks
+        SET status = $1,
+            completed_at = CASE
+                WHEN $2 THEN COALESCE(completed_at, now())
+            END,
+            assignee_id = CASE
+                WHEN $3 THEN assignee_id
+                ELSE $4
+            END
+        WHERE id = $5
+        RETURNING id, request_id, position, title,
+            status as \"status: ServiceRequestTaskStatus\", assignee_id, completed_at",
+        status as ServiceRequestTaskStatus,
+        status.is_completed(),
+        updateset.assignee_id.is_none(),
+        updateset.assignee_id.unwrap_or(None),
+        id,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+    check_fulfilled(request_id, &mut tx).await?;
+
+    tx.commit().await?;
+    Ok(task)
+}
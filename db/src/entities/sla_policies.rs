@@ -0,0 +1,926 @@
+use crate::entities::incidents::{self, IncidentPrio};
+use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use chrono::{LocalResult, TimeZone};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::PgConnection;
+use sqlx::Postgres;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// SLA policy in the database: response and resolution targets per Incident priority,
+/// counted in business hours.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct SlaPolicy {
+    pub id: Uuid,
+    #[schema(example = "Standard")]
+    pub name: String,
+    #[schema(example = "Office hours support for internal services.")]
+    pub description: String,
+    /// Whether the policy applies to Incidents created without one.
+    pub is_default: bool,
+    /// IANA time zone of the business hours and holidays.
+    #[schema(example = "Europe/Madrid")]
+    pub timezone: String,
+    /// Start of the business hours. Policies without business hours run around the clock.
+    #[schema(value_type = Option<String>, example = "09:00:00")]
+    pub business_hours_start: Option<NaiveTime>,
+    #[schema(value_type = Option<String>, example = "17:00:00")]
+    pub business_hours_end: Option<NaiveTime>,
+    /// ISO weekdays the business hours apply to, 1 being Monday.
+    #[schema(example = json!([1, 2, 3, 4, 5]))]
+    pub business_days: Vec<i16>,
+    pub targets: Vec<SlaTarget>,
+    /// Days the clock doesn't run.
+    pub holidays: Vec<SlaHoliday>,
+}
+
+/// Targets of an [SlaPolicy] for Incidents of one priority.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub struct SlaTarget {
+    pub priority: IncidentPrio,
+    /// Business minutes until the Incident must leave `new`.
+    #[schema(example = 30)]
+    #[validate(range(min = 1))]
+    pub response_minutes: i32,
+    /// Business minutes until the Incident must be resolved.
+    #[schema(example = 480)]
+    #[validate(range(min = 1))]
+    pub resolution_minutes: i32,
+}
+
+/// Day on which the clock of an [SlaPolicy] doesn't run.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub struct SlaHoliday {
+    pub day: NaiveDate,
+    #[schema(example = "New Year's Day")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+}
+
+/// Payload for creating an SLA policy.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_createset_calendar"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct SlaPolicyCreateset {
+    #[schema(example = "Standard")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[schema(example = "Office hours support for internal services.")]
+    #[validate(length(max = 1024))]
+    pub description: String,
+    /// Make this the default policy, replacing the current one. Defaults to `false`.
+    pub is_default: Option<bool>,
+    /// IANA time zone of the business hours and holidays. Defaults to `UTC`.
+    #[schema(example = "Europe/Madrid")]
+    pub timezone: Option<String>,
+    /// Start of the business hours. Must be given along with the end, or not at all.
+    #[schema(value_type = Option<String>, example = "09:00:00")]
+    pub business_hours_start: Option<NaiveTime>,
+    #[schema(value_type = Option<String>, example = "17:00:00")]
+    pub business_hours_end: Option<NaiveTime>,
+    /// ISO weekdays the business hours apply to, 1 being Monday. Defaults to every day.
+    #[schema(example = json!([1, 2, 3, 4, 5]))]
+    pub business_days: Option<Vec<i16>>,
+    /// At most one target per priority. Incidents of priorities without a target have no SLA.
+    #[validate(nested)]
+    pub targets: Vec<SlaTarget>,
+    #[validate(nested)]
+    #[serde(default)]
+    pub holidays: Vec<SlaHoliday>,
+}
+
+/// Payload for updating an SLA policy. Targets and holidays, when given, replace the
+/// current ones.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_updateset_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct SlaPolicyUpdateset {
+    #[schema(example = "Standard")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<Option<String>>,
+    #[schema(example = "Office hours support for internal services.")]
+    #[validate(length(max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub is_default: Option<Option<bool>>,
+    #[schema(example = "Europe/Madrid")]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub timezone: Option<Option<String>>,
+    /// Set both ends of the business hours to null to run around the clock.
+    #[schema(value_type = Option<String>, example = "09:00:00")]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub business_hours_start: Option<Option<NaiveTime>>,
+    #[schema(value_type = Option<String>, example = "17:00:00")]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub business_hours_end: Option<Option<NaiveTime>>,
+    #[schema(example = json!([1, 2, 3, 4, 5]))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub business_days: Option<Option<Vec<i16>>>,
+    #[validate(nested)]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub targets: Option<Option<Vec<SlaTarget>>>,
+    #[validate(nested)]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub holidays: Option<Option<Vec<SlaHoliday>>>,
+}
+
+/// Validate the time zone, business days and targets of an [SlaPolicyCreateset].
+fn validate_createset_calendar(createset: &SlaPolicyCreateset) -> Result<(), ValidationError> {
+    if let Some(timezone) = &createset.timezone {
+        validate_timezone(timezone)?;
+    }
+    if let Some(business_days) = &createset.business_days {
+        validate_business_days(business_days)?;
+    }
+    validate_targets(&createset.targets)?;
+    validate_holidays(&createset.holidays)?;
+
+    Ok(())
+}
+
+/// Validate that required fields of [SlaPolicyUpdateset] aren't explicitly null, and the
+/// time zone, business days and targets.
+fn validate_updateset_fields(updateset: &SlaPolicyUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.name)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+    entity_helpers::validate_not_null(&updateset.is_default)?;
+    entity_helpers::validate_not_null(&updateset.timezone)?;
+    entity_helpers::validate_not_null(&updateset.business_days)?;
+    entity_helpers::validate_not_null(&updateset.targets)?;
+    entity_helpers::validate_not_null(&updateset.holidays)?;
+    if let Some(Some(timezone)) = &updateset.timezone {
+        validate_timezone(timezone)?;
+    }
+    if let Some(Some(business_days)) = &updateset.business_days {
+        validate_business_days(business_days)?;
+    }
+    if let Some(Some(targets)) = &updateset.targets {
+        validate_targets(targets)?;
+    }
+    if let Some(Some(holidays)) = &updateset.holidays {
+        validate_holidays(holidays)?;
+    }
+
+    Ok(())
+}
+
+fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    match timezone.parse::<Tz>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("Unknown time zone")),
+    }
+}
+
+fn validate_business_days(business_days: &[i16]) -> Result<(), ValidationError> {
+    if business_days.is_empty() {
+        return Err(ValidationError::new(
+            "Policies need at least one business day",
+        ));
+    }
+    if business_days.iter().any(|day| !(1..=7).contains(day)) {
+        return Err(ValidationError::new("Business days go from 1 to 7"));
+    }
+
+    Ok(())
+}
+
+fn validate_targets(targets: &[SlaTarget]) -> Result<(), ValidationError> {
+    for (i, target) in targets.iter().enumerate() {
+        if targets[..i].iter().any(|t| t.priority == target.priority) {
+            return Err(ValidationError::new("Only one target per priority"));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_holidays(holidays: &[SlaHoliday]) -> Result<(), ValidationError> {
+    for (i, holiday) in holidays.iter().enumerate() {
+        if holidays[..i].iter().any(|h| h.day == holiday.day) {
+            return Err(ValidationError::new("Only one holiday per day"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Query parameters for listing SLA policies.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct SlaPolicyListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+}
+
+/// Map the errors of writing an SLA policy, which may break the uniqueness of names or
+/// leave Incidents without policy.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_unique_violation()
+                || dbe.is_foreign_key_violation()
+                || dbe.is_check_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Row of the `sla_policies` table, before loading its targets and holidays.
+#[derive(sqlx::FromRow)]
+struct SlaPolicyRow {
+    id: Uuid,
+    name: String,
+    description: String,
+    is_default: bool,
+    timezone: String,
+    business_hours_start: Option<NaiveTime>,
+    business_hours_end: Option<NaiveTime>,
+    business_days: Vec<i16>,
+}
+
+/// Load the targets and holidays of the policies in `rows`.
+async fn load_details(
+    rows: Vec<SlaPolicyRow>,
+    conn: &mut PgConnection,
+) -> Result<Vec<SlaPolicy>, crate::Error> {
+    let ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+
+    let targets = sqlx::query!(
+        "
+        SELECT policy_id, priority as \"priority: IncidentPrio\", response_minutes,
+            resolution_minutes
+        FROM sla_targets
+        WHERE policy_id = ANY($1)
+        ORDER BY priority",
+        &ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let holidays = sqlx::query!(
+        "
+        SELECT policy_id, day, name
+        FROM sla_holidays
+        WHERE policy_id = ANY($1)
+        ORDER BY day",
+        &ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SlaPolicy {
+            targets: targets
+                .iter()
+                .filter(|t| t.policy_id == row.id)
+                .map(|t| SlaTarget {
+                    priority: t.priority,
+                    response_minutes: t.response_minutes,
+                    resolution_minutes: t.resolution_minutes,
+                })
+                .collect(),
+            holidays: holidays
+                .iter()
+                .filter(|h| h.policy_id == row.id)
+                .map(|h| SlaHoliday {
+                    day: h.day,
+                    name: h.name.clone(),
+                })
+                .collect(),
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            is_default: row.is_default,
+            timezone: row.timezone,
+            business_hours_start: row.business_hours_start,
+            business_hours_end: row.business_hours_end,
+            business_days: row.business_days,
+        })
+        .collect())
+}
+
+/// Replace the targets and holidays of a policy with the given ones.
+async fn save_details(
+    id: Uuid,
+    targets: Option<Vec<SlaTarget>>,
+    holidays: Option<Vec<SlaHoliday>>,
+    conn: &mut PgConnection,
+) -> Result<(), crate::Error> {
+    if let Some(targets) = targets {
+        sqlx::query!("DELETE FROM sla_targets WHERE policy_id = $1", id)
+            .execute(&mut *conn)
+            .await?;
+        for target in targets {
+            sqlx::query!(
+                "
+                INSERT INTO sla_targets (policy_id, priority, response_minutes, resolution_minutes)
+                VALUES ($1, $2, $3, $4)",
+                id,
+                target.priority as IncidentPrio,
+                target.response_minutes,
+                target.resolution_minutes,
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(map_write_error)?;
+        }
+    }
+    if let Some(holidays) = holidays {
+        sqlx::query!("DELETE FROM sla_holidays WHERE policy_id = $1", id)
+            .execute(&mut *conn)
+            .await?;
+        for holiday in holidays {
+            sqlx::query!(
+                "
+                INSERT INTO sla_holidays (policy_id, day, name)
+                VALUES ($1, $2, $3)",
+                id,
+                holiday.day,
+                holiday.name,
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(map_write_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Load one page of SLA policies, sorted by name.
+pub async fn load_page(
+    params: SlaPolicyListParams,
+    pool: &DbPool,
+) -> Result<Page<SlaPolicy>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let mut page = pagination::load_page::<SlaPolicyRow>(
+        &mut tx,
+        "
+        id, name, description, is_default, timezone, business_hours_start,
+        business_hours_end, business_days",
+        "sla_policies",
+        |_| {},
+        "name, id",
+        params.paging,
+    )
+    .await?;
+    let policies = load_details(std::mem::take(&mut page.items), &mut tx).await?;
+
+    tx.commit().await?;
+    Ok(page.with_items(policies))
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<SlaPolicy, crate::Error> {
+    let mut conn = executor.acquire().await?;
+    fetch(id, &mut conn).await
+}
+
+/// Like [load], for callers that already hold a connection.
+pub async fn fetch(id: Uuid, conn: &mut PgConnection) -> Result<SlaPolicy, crate::Error> {
+    let row = sqlx::query_as!(
+        SlaPolicyRow,
+        "
+        SELECT id, name, description, is_default, timezone, business_hours_start,
+            business_hours_end, business_days
+        FROM sla_policies
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(crate::Error::NoRecordFound)?;
+
+    let mut policies = load_details(vec![row], conn).await?;
+    Ok(policies.remove(0))
+}
+
+/// Stop the current default policy from being the default.
+async fn clear_default(
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    sqlx::query!("UPDATE sla_policies SET is_default = false WHERE is_default")
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn create(
+    createset: SlaPolicyCreateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<SlaPolicy, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let is_default = createset.is_default.unwrap_or(false);
+    if is_default {
+        clear_default(&mut *tx).await?;
+    }
+
+    let id = sqlx::query_scalar!(
+        "
+        INSERT INTO sla_policies (name, description, is_default, timezone, business_hours_start,
+            business_hours_end, business_days)
+        VALUES ($1, $2, $3, COALESCE($4, 'UTC'), $5, $6, COALESCE($7, '{1,2,3,4,5,6,7}'::SMALLINT[]))
+        RETURNING id",
+        createset.name,
+        createset.description,
+        is_default,
+        createset.timezone,
+        createset.business_hours_start,
+        createset.business_hours_end,
+        createset.business_days.as_deref(),
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(map_write_error)?;
+    save_details(
+        id,
+        Some(createset.targets),
+        Some(createset.holidays),
+        &mut tx,
+    )
+    .await?;
+
+    let policy = fetch(id, &mut tx).await?;
+    check_reachable(&policy)?;
+    tx.commit().await?;
+    Ok(policy)
+}
+
+/// Update an SLA policy and the deadlines of the unresolved Incidents it applies to.
+pub async fn update(
+    id: Uuid,
+    updateset: SlaPolicyUpdateset,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<SlaPolicy, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+
+    let is_default = updateset.is_default.unwrap_or(None);
+    let business_days = updateset.business_days.unwrap_or(None);
+    if is_default == Some(true) {
+        clear_default(&mut *tx).await?;
+    }
+
+    sqlx::query!(
+        "
+        UPDATE sla_policies
+        SET name = COALESCE($1, name), description = COALESCE($2, description),
+            is_default = COALESCE($3, is_default), timezone = COALESCE($4, timezone),
+            business_hours_start = CASE
+                WHEN $5 THEN business_hours_start
+                ELSE $6
+            END,
+            business_hours_end = CASE
+                WHEN $7 THEN business_hours_end
+                ELSE $8
+            END,
+            business_days = COALESCE($9, business_days)
+        WHERE id = $10
+        RETURNING id",
+        updateset.name.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        is_default,
+        updateset.timezone.unwrap_or(None),
+        updateset.business_hours_start.is_none(),
+        updateset.business_hours_start.unwrap_or(None),
+        updateset.business_hours_end.is_none(),
+        updateset.business_hours_end.unwrap_or(None),
+        business_days.as_deref(),
+        id,
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(map_write_error)?
+    .ok_or(crate::Error::NoRecordFound)?;
+    save_details(
+        id,
+        updateset.targets.unwrap_or(None),
+        updateset.holidays.unwrap_or(None),
+        &mut tx,
+    )
+    .await?;
+    check_reachable(&fetch(id, &mut tx).await?)?;
+
+    let incident_ids = sqlx::query_scalar!(
+        "
+        SELECT id
+        FROM incidents
+        WHERE sla_policy_id = $1
+        AND resolved_at IS NULL",
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for incident_id in incident_ids {
+        incidents::sla::refresh(incident_id, &mut tx).await?;
+    }
+
+    let policy = fetch(id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(policy)
+}
+
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM sla_policies
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Check that every target of `policy` can be met on its business calendar, starting now.
+fn check_reachable(policy: &SlaPolicy) -> Result<(), crate::Error> {
+    let calendar = policy.calendar();
+    let now = Utc::now();
+    let reachable = policy.targets.iter().all(|target| {
+        [target.response_minutes, target.resolution_minutes]
+            .into_iter()
+            .all(|minutes| {
+                calendar
+                    .deadline(now, TimeDelta::minutes(minutes.into()), &[])
+                    .is_some()
+            })
+    });
+    if !reachable {
+        return Err(entity_helpers::invalid_field(
+            "targets",
+            "Targets must be reachable within five years of business hours",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Days past the last pause within which [BusinessCalendar::deadline] must find the
+/// deadline. Calendars always have business hours every week, so this only bounds the work
+/// of looking for deadlines of huge targets on calendars with very few business hours.
+const MAX_CALENDAR_DAYS: u64 = 5 * 366;
+
+/// Business hours of an [SlaPolicy], used to measure time against its targets.
+#[derive(Clone, Debug)]
+pub struct BusinessCalendar {
+    timezone: Tz,
+    hours: Option<(NaiveTime, NaiveTime)>,
+    days: Vec<i16>,
+    holidays: Vec<NaiveDate>,
+}
+
+impl Default for BusinessCalendar {
+    /// Calendar running around the clock.
+    fn default() -> Self {
+        Self {
+            timezone: Tz::UTC,
+            hours: None,
+            days: (1..=7).collect(),
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl SlaPolicy {
+    /// Targets of the policy for Incidents of priority `priority`, if it has any.
+    pub fn target(&self, priority: IncidentPrio) -> Option<&SlaTarget> {
+        self.targets.iter().find(|t| t.priority == priority)
+    }
+
+    pub fn calendar(&self) -> BusinessCalendar {
+        BusinessCalendar {
+            // The time zone is validated on write, but fall back to UTC rather than failing.
+            timezone: self.timezone.parse().unwrap_or(Tz::UTC),
+            hours: self.business_hours_start.zip(self.business_hours_end),
+            days: self.business_days.clone(),
+            holidays: self.holidays.iter().map(|h| h.day).collect(),
+        }
+    }
+}
+
+impl BusinessCalendar {
+    /// Convert a local time of the calendar to UTC. Times skipped by daylight saving
+    /// changes are taken as UTC offset by the zone's offset right before the gap.
+    fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        match self.timezone.from_local_datetime(&local) {
+            LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t.with_timezone(&Utc),
+            LocalResult::None => {
+                let before = self
+                    .timezone
+                    .from_local_datetime(&(local - TimeDelta::hours(1)))
+                    .earliest()
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_else(|| local.and_utc());
+                before + TimeDelta::hours(1)
+            }
+        }
+    }
+
+    /// Business hours of the local day `day`, in UTC.
+    fn business_hours(&self, day: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let weekday = day.weekday().number_from_monday() as i16;
+        if !self.days.contains(&weekday) || self.holidays.contains(&day) {
+            return None;
+        }
+
+        let (start, end) = match self.hours {
+            Some((start, end)) => (day.and_time(start), day.and_time(end)),
+            None => (
+                day.and_time(NaiveTime::MIN),
+                (day + Days::new(1)).and_time(NaiveTime::MIN),
+            ),
+        };
+        Some((self.to_utc(start), self.to_utc(end)))
+    }
+
+    /// Call `f` with the stretches of business time from `from` up to the local day
+    /// `last_day`, in order and leaving out the `pauses`, until it returns `false`.
+    fn for_each_stretch(
+        &self,
+        from: DateTime<Utc>,
+        last_day: NaiveDate,
+        pauses: &[(DateTime<Utc>, DateTime<Utc>)],
+        mut f: impl FnMut(DateTime<Utc>, DateTime<Utc>) -> bool,
+    ) {
+        let first_day = from.with_timezone(&self.timezone).date_naive();
+        for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+            let Some((start, end)) = self.business_hours(day) else {
+                continue;
+            };
+            let mut cursor = start.max(from);
+            while cursor < end {
+                // Skip the pause the cursor is in, or stop right before the next one.
+                let pause = pauses
+                    .iter()
+                    .filter(|(pause_start, pause_end)| *pause_end > cursor && *pause_start < end)
+                    .min_by_key(|(pause_start, _)| *pause_start);
+                match pause {
+                    Some((pause_start, pause_end)) if *pause_start <= cursor => {
+                        cursor = *pause_end;
+                    }
+                    Some((pause_start, pause_end)) => {
+                        if !f(cursor, *pause_start) {
+                            return;
+                        }
+                        cursor = *pause_end;
+                    }
+                    None => {
+                        if !f(cursor, end) {
+                            return;
+                        }
+                        cursor = end;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Business time between `from` and `to`, leaving out the `pauses`.
+    pub fn elapsed(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        pauses: &[(DateTime<Utc>, DateTime<Utc>)],
+    ) -> TimeDelta {
+        let last_day = to.with_timezone(&self.timezone).date_naive();
+        let mut elapsed = TimeDelta::zero();
+        self.for_each_stretch(from, last_day, pauses, |start, end| {
+            if start >= to {
+                return false;
+            }
+            elapsed += end.min(to) - start;
+            end < to
+        });
+
+        elapsed
+    }
+
+    /// Moment at which `amount` of business time has passed since `from`, leaving out the
+    /// `pauses`.
+    ///
+    /// `None` when that's more than [MAX_CALENDAR_DAYS] past `from` and the pauses, i.e. when
+    /// `amount` is out of proportion to the business hours of the calendar. Policies with
+    /// such targets are rejected when they're written.
+    pub fn deadline(
+        &self,
+        from: DateTime<Utc>,
+        amount: TimeDelta,
+        pauses: &[(DateTime<Utc>, DateTime<Utc>)],
+    ) -> Option<DateTime<Utc>> {
+        let last_pause_end = pauses.iter().map(|(_, end)| *end).max().unwrap_or(from);
+        let last_day = from
+            .max(last_pause_end)
+            .with_timezone(&self.timezone)
+            .date_naive()
+            + Days::new(MAX_CALENDAR_DAYS);
+        let mut remaining = amount;
+        let mut deadline = None;
+        self.for_each_stretch(from, last_day, pauses, |start, end| {
+            if end - start >= remaining {
+                deadline = Some(start + remaining);
+                return false;
+            }
+            remaining -= end - start;
+            true
+        });
+
+        deadline
+    }
+}
+
+#[cfg(test)]
+mod sla_policies_tests {
+    use super::*;
+
+    fn office_hours() -> BusinessCalendar {
+        BusinessCalendar {
+            timezone: "Europe/Madrid".parse().unwrap(),
+            hours: Some((
+                NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            )),
+            days: vec![1, 2, 3, 4, 5],
+            // Friday
+            holidays: vec![NaiveDate::from_ymd_opt(2024, 3, 8).unwrap()],
+        }
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_deadline_around_the_clock() {
+        let calendar = BusinessCalendar::default();
+
+        let deadline = calendar.deadline(at("2024-03-01T23:00:00Z"), TimeDelta::hours(4), &[]);
+
+        assert_eq!(deadline.unwrap(), at("2024-03-02T03:00:00Z"));
+    }
+
+    #[test]
+    fn test_deadline_skips_closed_days() {
+        // Thursday 16:00 in Madrid, with Friday off and the weekend in between.
+        let deadline =
+            office_hours().deadline(at("2024-03-07T15:00:00Z"), TimeDelta::hours(2), &[]);
+
+        assert_eq!(deadline.unwrap(), at("2024-03-11T09:00:00Z"));
+    }
+
+    #[test]
+    fn test_deadline_starts_at_opening() {
+        let deadline =
+            office_hours().deadline(at("2024-03-04T05:00:00Z"), TimeDelta::minutes(30), &[]);
+
+        assert_eq!(deadline.unwrap(), at("2024-03-04T08:30:00Z"));
+    }
+
+    #[test]
+    fn test_deadline_skips_pauses() {
+        let pauses = [(at("2024-03-04T09:00:00Z"), at("2024-03-04T10:00:00Z"))];
+
+        let deadline =
+            office_hours().deadline(at("2024-03-04T08:00:00Z"), TimeDelta::hours(2), &pauses);
+
+        assert_eq!(deadline.unwrap(), at("2024-03-04T11:00:00Z"));
+    }
+
+    #[test]
+    fn test_elapsed() {
+        let pauses = [(at("2024-03-04T15:00:00Z"), at("2024-03-05T09:00:00Z"))];
+
+        let elapsed = office_hours().elapsed(
+            at("2024-03-04T14:00:00Z"),
+            at("2024-03-05T10:00:00Z"),
+            &pauses,
+        );
+
+        // An hour on Monday and another one on Tuesday.
+        assert_eq!(elapsed, TimeDelta::hours(2));
+    }
+
+    #[test]
+    fn test_deadline_out_of_reach() {
+        let calendar = BusinessCalendar {
+            hours: Some((
+                NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+            )),
+            days: vec![1],
+            ..office_hours()
+        };
+
+        // An hour a week takes over 19 years to add up to 1000 hours.
+        let deadline = calendar.deadline(at("2024-03-04T08:00:00Z"), TimeDelta::hours(1000), &[]);
+
+        assert_eq!(deadline, None);
+    }
+
+    #[test]
+    fn test_deadline_after_long_pauses() {
+        let pauses = [(at("2024-01-01T00:00:00Z"), at("2034-01-01T00:00:00Z"))];
+
+        let deadline = BusinessCalendar::default().deadline(
+            at("2024-01-01T00:00:00Z"),
+            TimeDelta::hours(1),
+            &pauses,
+        );
+
+        assert_eq!(deadline.unwrap(), at("2034-01-01T01:00:00Z"));
+    }
+
+    #[test]
+    fn test_elapsed_over_years() {
+        let elapsed = BusinessCalendar::default().elapsed(
+            at("2020-01-01T00:00:00Z"),
+            at("2030-01-01T00:00:00Z"),
+            &[],
+        );
+
+        assert_eq!(elapsed, TimeDelta::days(3653));
+    }
+
+    #[test]
+    fn test_elapsed_follows_daylight_saving() {
+        // Clocks move forward on 2024-03-31 in Madrid, so opening is at 07:00 UTC afterwards.
+        let calendar = BusinessCalendar {
+            days: (1..=7).collect(),
+            ..office_hours()
+        };
+
+        let elapsed = calendar.elapsed(at("2024-03-30T16:00:00Z"), at("2024-03-31T08:00:00Z"), &[]);
+
+        assert_eq!(elapsed, TimeDelta::hours(1));
+    }
+}
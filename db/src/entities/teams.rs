@@ -0,0 +1,215 @@
+use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use sqlx::QueryBuilder;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// Module for handling the members of Teams.
+pub mod members;
+
+/// Team in the database. Teams act as assignment groups of Incidents and own CIs.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct Team {
+    pub id: Uuid,
+    #[schema(example = "Network Operations")]
+    pub name: String,
+    #[schema(example = "Handles routers, switches and firewalls.")]
+    pub description: String,
+}
+
+/// Payload for creating a Team.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct TeamCreateset {
+    #[schema(example = "Network Operations")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[schema(example = "Handles routers, switches and firewalls.")]
+    #[validate(length(max = 1024))]
+    pub description: String,
+}
+
+/// Payload for updating a Team.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct TeamUpdateset {
+    #[schema(example = "Network Operations")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<Option<String>>,
+    #[schema(example = "Handles routers, switches and firewalls.")]
+    #[validate(length(max = 1024))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub description: Option<Option<String>>,
+}
+
+/// Validate that required fields of [TeamUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &TeamUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.name)?;
+    entity_helpers::validate_not_null(&updateset.description)?;
+
+    Ok(())
+}
+
+/// Query parameters for listing Teams.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct TeamListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Only Teams this user is a member of.
+    pub member_id: Option<Uuid>,
+}
+
+/// Append the `WHERE` clause matching the filters in [TeamListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a TeamListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(member_id) = params.member_id {
+        builder
+            .push(" AND id IN (SELECT team_id FROM team_members WHERE user_id = ")
+            .push_bind(member_id)
+            .push(")");
+    }
+}
+
+/// Map the errors of writing a Team, which may break the uniqueness of names
+/// or leave Incidents without assignment group.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_unique_violation() || dbe.is_foreign_key_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load one page of Teams matching the filters in `params`, sorted by name.
+pub async fn load_page(params: TeamListParams, pool: &DbPool) -> Result<Page<Team>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let teams = pagination::load_page::<Team>(
+        &mut tx,
+        "id, name, description",
+        "teams",
+        |builder| push_filters(builder, &params),
+        "name, id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(teams)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Team, crate::Error> {
+    match sqlx::query_as!(
+        Team,
+        "
+        SELECT id, name, description
+        FROM teams
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(team) => Ok(team),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+pub async fn create(
+    createset: TeamCreateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Team, crate::Error> {
+    createset.validate()?;
+
+    let team = sqlx::query_as!(
+        Team,
+        "
+        INSERT INTO teams (name, description)
+        VALUES ($1, $2)
+        RETURNING id, name, description",
+        createset.name,
+        createset.description,
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(map_write_error)?;
+
+    Ok(team)
+}
+
+pub async fn update(
+    id: Uuid,
+    updateset: TeamUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<Team, crate::Error> {
+    updateset.validate()?;
+
+    match sqlx::query_as!(
+        Team,
+        "
+        UPDATE teams
+        SET name = COALESCE($1, name), description = COALESCE($2, description)
+        WHERE id = $3
+        RETURNING id, name, description",
+        updateset.name.unwrap_or(None),
+        updateset.description.unwrap_or(None),
+        id,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(team) => Ok(team),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM teams
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
@@ -0,0 +1,109 @@
+use crate::entities::users::User;
+use crate::DbPool;
+#[cfg(feature = "test-helpers")]
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers"), derive(Deserialize))]
+pub struct TeamMember {
+    pub team_id: Uuid,
+    pub user_id: Uuid,
+}
+
+/// Check if a team with the ID sent as path param exists in the database.
+async fn check_valid_team(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = sqlx::query_scalar!(
+        "
+        SELECT EXISTS(SELECT 1 FROM teams WHERE id = $1)",
+        id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+pub async fn load_all(team_id: Uuid, pool: &DbPool) -> Result<Vec<User>, crate::Error> {
+    let mut tx = pool.begin().await?;
+    check_valid_team(team_id, &mut *tx).await?;
+    let members = sqlx::query_as!(
+        User,
+        "
+        SELECT u.id, u.username, u.full_name, u.email
+        FROM users AS u
+        INNER JOIN team_members AS m
+        ON u.id = m.user_id
+        WHERE m.team_id = $1
+        ORDER BY u.username",
+        team_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(members)
+}
+
+/// Add a user to a team. Adding a member twice has no effect.
+pub async fn create(
+    team_id: Uuid,
+    user_id: Uuid,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<TeamMember, crate::Error> {
+    let mut tx = executor.begin().await?;
+    check_valid_team(team_id, &mut *tx).await?;
+    sqlx::query!(
+        "
+        INSERT INTO team_members (team_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING",
+        team_id,
+        user_id,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    })?;
+
+    tx.commit().await?;
+    Ok(TeamMember { team_id, user_id })
+}
+
+/// Remove a user from a team. They are unassigned from the Incidents of the team.
+pub async fn delete(
+    team_id: Uuid,
+    user_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM team_members
+        WHERE team_id = $1
+        AND user_id = $2
+        RETURNING user_id",
+        team_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
@@ -0,0 +1,286 @@
+use crate::pagination::{self, Page, PageParams, SortOrder};
+use crate::DbPool;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use sqlx::QueryBuilder;
+use sqlx::Type;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Kind of entity a [TimelineEntry] belongs to.
+#[derive(Serialize, Clone, Copy, Type, Debug, ToSchema)]
+#[sqlx(type_name = "timeline_entity", rename_all = "lowercase")]
+#[schema(example = "incident")]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub enum TimelineEntity {
+    Incident,
+    Problem,
+    Rfc,
+}
+
+/// Who a [TimelineEntry] is meant for.
+#[derive(Serialize, Deserialize, Clone, Copy, Type, Debug, ToSchema)]
+#[sqlx(type_name = "timeline_visibility", rename_all = "lowercase")]
+#[schema(example = "internal")]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub enum TimelineVisibility {
+    /// Work note, only meant for the agents working on the entity.
+    Internal,
+    /// Comment the customer may see.
+    Public,
+}
+
+/// Work note or comment in the timeline of an Incident, Problem or RFC.
+#[derive(Serialize, Debug, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize))]
+pub struct TimelineEntry {
+    pub id: Uuid,
+    pub entity_type: TimelineEntity,
+    pub entity_id: Uuid,
+    /// Entry this one replies to.
+    pub parent_id: Option<Uuid>,
+    /// Subject of the bearer token of the request that wrote the entry.
+    #[schema(example = "jdoe")]
+    pub author: String,
+    pub visibility: TimelineVisibility,
+    #[schema(example = "Restarted the router, waiting for the user to confirm.")]
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    /// Last time the author edited the entry. `null` if it was never edited.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Validate, Clone, ToSchema)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize))]
+pub struct TimelineEntryCreateset {
+    /// Entry of the same timeline to reply to.
+    pub parent_id: Option<Uuid>,
+    pub visibility: TimelineVisibility,
+    #[schema(example = "Restarted the router, waiting for the user to confirm.")]
+    #[validate(length(min = 1, max = 8192))]
+    pub body: String,
+}
+
+#[derive(Deserialize, Validate, Clone, ToSchema)]
+#[cfg_attr(feature = "test-helpers", derive(Serialize))]
+pub struct TimelineEntryUpdateset {
+    pub visibility: Option<TimelineVisibility>,
+    #[schema(example = "Restarted the router, the user confirmed it works again.")]
+    #[validate(length(min = 1, max = 8192))]
+    pub body: Option<String>,
+}
+
+/// Query parameters for listing the timeline of an entity.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct TimelineListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Sorting direction by creation time. Defaults to `asc`.
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    pub visibility: Option<TimelineVisibility>,
+}
+
+/// Check if the entity a timeline belongs to exists in the database.
+async fn check_valid_entity(
+    entity_type: TimelineEntity,
+    entity_id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    let exists = match entity_type {
+        TimelineEntity::Incident => {
+            sqlx::query_scalar!(
+                "SELECT EXISTS(SELECT 1 FROM incidents WHERE id = $1)",
+                entity_id
+            )
+            .fetch_one(executor)
+            .await?
+        }
+        TimelineEntity::Problem => {
+            sqlx::query_scalar!(
+                "SELECT EXISTS(SELECT 1 FROM problems WHERE id = $1)",
+                entity_id
+            )
+            .fetch_one(executor)
+            .await?
+        }
+        TimelineEntity::Rfc => {
+            sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM rfcs WHERE id = $1)", entity_id)
+                .fetch_one(executor)
+                .await?
+        }
+    };
+
+    if !exists.unwrap_or(false) {
+        return Err(crate::Error::NoRecordFound);
+    }
+
+    Ok(())
+}
+
+/// Append the `WHERE` clause matching the entity and the filters in [TimelineListParams].
+fn push_filters<'a>(
+    builder: &mut QueryBuilder<'a, Postgres>,
+    entity_type: TimelineEntity,
+    entity_id: Uuid,
+    params: &'a TimelineListParams,
+) {
+    builder
+        .push(" WHERE entity_type = ")
+        .push_bind(entity_type)
+        .push(" AND entity_id = ")
+        .push_bind(entity_id);
+    if let Some(visibility) = &params.visibility {
+        builder.push(" AND visibility = ").push_bind(visibility);
+    }
+}
+
+/// Load one page of the timeline of an entity, oldest first unless asked otherwise.
+///
+/// Replies are listed along with the rest of the entries, clients thread them by
+/// `parent_id`.
+pub async fn load_page(
+    entity_type: TimelineEntity,
+    entity_id: Uuid,
+    params: TimelineListParams,
+    pool: &DbPool,
+) -> Result<Page<TimelineEntry>, crate::Error> {
+    params.validate()?;
+
+    let order = params.order.unwrap_or_default().as_sql();
+    let order_by = format!("created_at {order}, id {order}");
+
+    let mut tx = pool.begin().await?;
+    check_valid_entity(entity_type, entity_id, &mut *tx).await?;
+    let entries = pagination::load_page::<TimelineEntry>(
+        &mut tx,
+        "
+        id, entity_type, entity_id, parent_id, author, visibility, body,
+        created_at, updated_at",
+        "timeline_entries",
+        |builder| push_filters(builder, entity_type, entity_id, &params),
+        &order_by,
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(entries)
+}
+
+/// Add an entry written by `author` to the timeline of an entity.
+pub async fn create(
+    entity_type: TimelineEntity,
+    entity_id: Uuid,
+    createset: TimelineEntryCreateset,
+    author: &str,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<TimelineEntry, crate::Error> {
+    createset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    check_valid_entity(entity_type, entity_id, &mut *tx).await?;
+    let entry = sqlx::query_as!(
+        TimelineEntry,
+        "
+        INSERT INTO timeline_entries (entity_type, entity_id, parent_id, author, visibility, body)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, entity_type as \"entity_type: TimelineEntity\", entity_id, parent_id,
+            author, visibility as \"visibility: TimelineVisibility\", body,
+            created_at, updated_at",
+        entity_type as TimelineEntity,
+        entity_id,
+        createset.parent_id,
+        author,
+        createset.visibility as TimelineVisibility,
+        createset.body,
+    )
+    .fetch_one(&mut *tx)
+    .await
+    // The parent doesn't exist or belongs to another timeline.
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref dbe) if dbe.is_foreign_key_violation() => {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    })?;
+
+    tx.commit().await?;
+    Ok(entry)
+}
+
+/// Edit an entry of the timeline of an entity on behalf of `author`.
+///
+/// Only the author of the entry may edit it, and only until `edit_window` has passed since
+/// it was written. Fails with [crate::Error::NotAllowed] otherwise.
+pub async fn update(
+    entity_type: TimelineEntity,
+    entity_id: Uuid,
+    id: Uuid,
+    updateset: TimelineEntryUpdateset,
+    author: &str,
+    edit_window: Duration,
+    executor: impl sqlx::Acquire<'_, Database = Postgres>,
+) -> Result<TimelineEntry, crate::Error> {
+    updateset.validate()?;
+
+    let mut tx = executor.begin().await?;
+    let Some(current) = sqlx::query!(
+        "
+        SELECT author, created_at
+        FROM timeline_entries
+        WHERE id = $1
+        AND entity_type = $2
+        AND entity_id = $3
+        FOR UPDATE",
+        id,
+        entity_type as TimelineEntity,
+        entity_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Err(crate::Error::NoRecordFound);
+    };
+
+    if current.author != author {
+        return Err(crate::Error::NotAllowed(
+            "Only the author of an entry may edit it",
+        ));
+    }
+    if Utc::now() - current.created_at > edit_window {
+        return Err(crate::Error::NotAllowed(
+            "The entry can no longer be edited",
+        ));
+    }
+
+    let entry = sqlx::query_as!(
+        TimelineEntry,
+        "
+        UPDATE timeline_entries
+        SET visibility = COALESCE($1, visibility), body = COALESCE($2, body),
+            updated_at = now()
+        WHERE id = $3
+        RETURNING id, entity_type as \"entity_type: TimelineEntity\", entity_id, parent_id,
+            author, visibility as \"visibility: TimelineVisibility\", body,
+            created_at, updated_at",
+        updateset.visibility as Option<TimelineVisibility>,
+        updateset.body,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(entry)
+}
@@ -0,0 +1,282 @@
+use crate::entity_helpers;
+use crate::pagination::{self, Page, PageParams};
+use crate::DbPool;
+use serde::Deserialize;
+use serde::Serialize;
+use sqlx::Postgres;
+use sqlx::QueryBuilder;
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use validator::ValidationError;
+
+/// User in the database.
+///
+/// The `username` of a user is the subject of their bearer tokens.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize, PartialEq))]
+pub struct User {
+    pub id: Uuid,
+    #[schema(example = "jdoe")]
+    pub username: String,
+    #[schema(example = "John Doe")]
+    pub full_name: String,
+    #[schema(example = "jdoe@example.com")]
+    pub email: Option<String>,
+}
+
+/// Payload for creating a User.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct UserCreateset {
+    #[schema(example = "jdoe")]
+    #[validate(length(min = 1, max = 255))]
+    pub username: String,
+    #[schema(example = "John Doe")]
+    #[validate(length(min = 1, max = 255))]
+    pub full_name: String,
+    #[schema(example = "jdoe@example.com")]
+    #[validate(email)]
+    pub email: Option<String>,
+}
+
+/// Payload for updating a User.
+#[derive(Clone, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_required_fields"))]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct UserUpdateset {
+    #[schema(example = "jdoe")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub username: Option<Option<String>>,
+    #[schema(example = "John Doe")]
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub full_name: Option<Option<String>>,
+    #[schema(example = "jdoe@example.com")]
+    #[validate(email)]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    #[cfg_attr(
+        any(feature = "test-helpers", test),
+        serde(skip_serializing_if = "Option::is_none")
+    )]
+    pub email: Option<Option<String>>,
+}
+
+/// Validate that required fields of [UserUpdateset] aren't explicitly null.
+fn validate_required_fields(updateset: &UserUpdateset) -> Result<(), ValidationError> {
+    entity_helpers::validate_not_null(&updateset.username)?;
+    entity_helpers::validate_not_null(&updateset.full_name)?;
+
+    Ok(())
+}
+
+/// Query parameters for listing Users.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct UserListParams {
+    #[serde(flatten)]
+    #[param(ignore)]
+    #[validate(nested)]
+    pub paging: PageParams,
+    /// Only members of this team.
+    pub team_id: Option<Uuid>,
+}
+
+/// Append the `WHERE` clause matching the filters in [UserListParams].
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, params: &'a UserListParams) {
+    builder.push(" WHERE TRUE");
+    if let Some(team_id) = params.team_id {
+        builder
+            .push(" AND id IN (SELECT user_id FROM team_members WHERE team_id = ")
+            .push_bind(team_id)
+            .push(")");
+    }
+}
+
+/// Map the errors of writing a User, which may break the uniqueness of usernames
+/// or leave RFCs without requester.
+fn map_write_error(e: sqlx::Error) -> crate::Error {
+    match e {
+        sqlx::Error::Database(ref dbe)
+            if dbe.is_unique_violation() || dbe.is_foreign_key_violation() =>
+        {
+            crate::Error::ConstraintError
+        }
+        _ => crate::Error::DbError(e),
+    }
+}
+
+/// Load one page of Users matching the filters in `params`, sorted by username.
+pub async fn load_page(params: UserListParams, pool: &DbPool) -> Result<Page<User>, crate::Error> {
+    params.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let users = pagination::load_page::<User>(
+        &mut tx,
+        "id, username, full_name, email",
+        "users",
+        |builder| push_filters(builder, &params),
+        "username, id",
+        params.paging,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(users)
+}
+
+pub async fn load(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<User, crate::Error> {
+    match sqlx::query_as!(
+        User,
+        "
+        SELECT id, username, full_name, email
+        FROM users
+        WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(user) => Ok(user),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+/// Load the User whose username is the subject of a bearer token.
+pub async fn load_by_username(
+    username: &str,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<User, crate::Error> {
+    match sqlx::query_as!(
+        User,
+        "
+        SELECT id, username, full_name, email
+        FROM users
+        WHERE username = $1",
+        username
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(crate::Error::DbError)?
+    {
+        Some(user) => Ok(user),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+pub async fn create(
+    createset: UserCreateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<User, crate::Error> {
+    createset.validate()?;
+
+    let user = sqlx::query_as!(
+        User,
+        "
+        INSERT INTO users (username, full_name, email)
+        VALUES ($1, $2, $3)
+        RETURNING id, username, full_name, email",
+        createset.username,
+        createset.full_name,
+        createset.email,
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(map_write_error)?;
+
+    Ok(user)
+}
+
+pub async fn update(
+    id: Uuid,
+    updateset: UserUpdateset,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<User, crate::Error> {
+    updateset.validate()?;
+
+    match sqlx::query_as!(
+        User,
+        "
+        UPDATE users
+        SET username = COALESCE($1, username), full_name = COALESCE($2, full_name),
+            email = CASE
+                WHEN $3 THEN email
+                ELSE $4
+            END
+        WHERE id = $5
+        RETURNING id, username, full_name, email",
+        updateset.username.unwrap_or(None),
+        updateset.full_name.unwrap_or(None),
+        updateset.email.is_none(),
+        updateset.email.unwrap_or(None),
+        id,
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(user) => Ok(user),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+pub async fn delete(
+    id: Uuid,
+    executor: impl sqlx::Executor<'_, Database = Postgres>,
+) -> Result<(), crate::Error> {
+    match sqlx::query!(
+        "
+        DELETE FROM users
+        WHERE id = $1
+        RETURNING id",
+        id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(map_write_error)?
+    {
+        Some(_) => Ok(()),
+        None => Err(crate::Error::NoRecordFound),
+    }
+}
+
+#[cfg(test)]
+mod users_tests {
+    use super::*;
+
+    #[test]
+    fn test_createset_invalid_email() {
+        let createset = UserCreateset {
+            username: String::from("jdoe"),
+            full_name: String::from("John Doe"),
+            email: Some(String::from("not an email")),
+        };
+
+        assert!(createset.validate().is_err());
+    }
+
+    #[test]
+    fn test_updateset_null_username() {
+        let json = r#"{ "username": null, "email": null }"#;
+        let set: UserUpdateset = serde_json::from_str(json).unwrap();
+
+        assert_eq!(set.username, Some(None));
+        assert_eq!(set.email, Some(None));
+        assert!(set.validate().is_err());
+    }
+}
@@ -7,3 +7,43 @@ pub fn validate_not_null<T>(field: &Option<Option<T>>) -> Result<(), ValidationE
 
     Ok(())
 }
+
+/// Validation error of the single field `field`, described by `message`.
+pub fn invalid_field(field: &'static str, message: &'static str) -> crate::Error {
+    let mut errors = validator::ValidationErrors::new();
+    errors.add(field, ValidationError::new(message));
+    errors.into()
+}
+
+/// Statuses of an entity that can only move along certain transitions.
+pub trait Lifecycle: Copy + PartialEq + 'static {
+    /// Statuses that can directly follow `self`.
+    fn next(&self) -> &'static [Self];
+    /// Name of the status as it appears in the API.
+    fn name(&self) -> &'static str;
+}
+
+/// Check that an entity can move from status `from` to `to`. Staying in the same status is
+/// always allowed.
+pub fn check_transition<S: Lifecycle>(from: S, to: S) -> Result<(), crate::Error> {
+    check_transition_among(from, to, from.next())
+}
+
+/// Check that an entity can move from status `from` to `to`, when only the statuses in
+/// `allowed` can follow `from`, e.g. because they depend on other fields of the entity.
+/// Staying in the same status is always allowed.
+pub fn check_transition_among<S: Lifecycle>(
+    from: S,
+    to: S,
+    allowed: &[S],
+) -> Result<(), crate::Error> {
+    if from == to || allowed.contains(&to) {
+        return Ok(());
+    }
+
+    Err(crate::Error::InvalidTransition {
+        from: from.name(),
+        to: to.name(),
+        allowed: allowed.iter().map(Lifecycle::name).collect(),
+    })
+}
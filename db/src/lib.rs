@@ -11,6 +11,8 @@ pub use sqlx::postgres::PgPool as DbPool;
 pub mod entities;
 /// Helper functions for entities.
 pub mod entity_helpers;
+/// Pagination, sorting and related helpers for list queries.
+pub mod pagination;
 
 /// Starts a new database transaction.
 ///
@@ -38,6 +40,22 @@ pub async fn transaction(
     Ok(tx)
 }
 
+/// Starts a new database transaction on behalf of `actor`.
+///
+/// `actor` is recorded as the author of the changes made through the transaction in the audit
+/// log (see [`entities::audit`]).
+pub async fn audited_transaction(
+    db_pool: &DbPool,
+    actor: &str,
+) -> Result<Transaction<'static, Postgres>, anyhow::Error> {
+    let mut tx = transaction(db_pool).await?;
+    entities::audit::set_actor(actor, &mut *tx)
+        .await
+        .context("Failed to set the actor of transaction")?;
+
+    Ok(tx)
+}
+
 /// Errors that can occur as a result of a data layer operation.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -56,6 +74,19 @@ pub enum Error {
     /// Request violated database constraints.
     #[error("constraint violation")]
     ConstraintError,
+    /// The status of an entity can't move to the requested one (see
+    /// [entity_helpers::Lifecycle]).
+    #[error("invalid transition from {from} to {to}")]
+    InvalidTransition {
+        from: &'static str,
+        to: &'static str,
+        /// Statuses the entity can move to instead.
+        allowed: Vec<&'static str>,
+    },
+    /// The caller may not modify the record, e.g. someone else's timeline entry (see
+    /// [entities::timeline]).
+    #[error("not allowed: {0}")]
+    NotAllowed(&'static str),
 }
 
 /// Creates a connection pool to the database specified in the passed [`itil-back-config::DatabaseConfig`]
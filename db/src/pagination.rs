@@ -0,0 +1,213 @@
+use serde::Deserialize;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgConnection, Postgres, QueryBuilder};
+use utoipa::IntoParams;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Amount of records returned per page when the client doesn't ask for a specific limit.
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+/// Biggest page a client may ask for.
+pub const MAX_PAGE_LIMIT: i64 = 500;
+/// Highest page number a client may ask for, which keeps offsets far from overflowing.
+pub const MAX_PAGE: i64 = 1_000_000;
+
+/// One page of a list of records.
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Deserialize))]
+pub struct Page<T> {
+    /// Records in this page.
+    pub items: Vec<T>,
+    /// Amount of records matching the filters, across all pages.
+    #[schema(example = 137)]
+    pub total: i64,
+    /// Number of this page, starting at 1.
+    #[schema(example = 1)]
+    pub page: i64,
+    /// Max amount of records per page.
+    #[schema(example = 50)]
+    pub limit: i64,
+    /// Number of the page that follows this one. `null` when this is the last page.
+    #[schema(example = 2)]
+    pub next_page: Option<i64>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: i64, request: PageRequest) -> Self {
+        let next_page = if request.page.saturating_mul(request.limit) < total {
+            Some(request.page + 1)
+        } else {
+            None
+        };
+
+        Self {
+            items,
+            total,
+            page: request.page,
+            limit: request.limit,
+            next_page,
+        }
+    }
+
+    /// Replace the records of this page, e.g. with the result of loading their details.
+    pub fn with_items<U>(self, items: Vec<U>) -> Page<U> {
+        Page {
+            items,
+            total: self.total,
+            page: self.page,
+            limit: self.limit,
+            next_page: self.next_page,
+        }
+    }
+}
+
+/// Query parameters of all the paginated lists, which flatten it into their own parameters.
+/// Controllers document it next to them, e.g. `params(IncidentListParams, PageParams)`.
+#[serde_as]
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams, ToSchema, Validate)]
+#[into_params(parameter_in = Query)]
+#[cfg_attr(any(feature = "test-helpers", test), derive(Serialize))]
+pub struct PageParams {
+    // Flattened fields only get strings out of query strings, hence the explicit parsing.
+    /// Page to return, starting at 1.
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[validate(range(min = 1, max = MAX_PAGE))]
+    pub page: Option<i64>,
+    /// Max amount of records per page.
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[validate(range(min = 1, max = MAX_PAGE_LIMIT))]
+    pub limit: Option<i64>,
+}
+
+/// Sorting direction of a list.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[schema(example = "desc")]
+#[cfg_attr(any(feature = "test-helpers", test), derive(PartialEq))]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+}
+
+/// Page requested by the client, with defaults already applied.
+#[derive(Clone, Copy, Debug)]
+pub struct PageRequest {
+    pub page: i64,
+    pub limit: i64,
+}
+
+impl PageRequest {
+    pub fn new(page: Option<i64>, limit: Option<i64>) -> Self {
+        Self {
+            page: page.unwrap_or(1),
+            limit: limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+        }
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page - 1).saturating_mul(self.limit)
+    }
+
+    /// Append the `LIMIT` and `OFFSET` clauses for this page to a query.
+    pub fn push_to(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push(" LIMIT ");
+        builder.push_bind(self.limit);
+        builder.push(" OFFSET ");
+        builder.push_bind(self.offset());
+    }
+}
+
+impl From<PageParams> for PageRequest {
+    fn from(params: PageParams) -> Self {
+        Self::new(params.page, params.limit)
+    }
+}
+
+/// Load one page of the rows of `from` matching the `WHERE` clause appended by
+/// `push_filters`, together with the amount of matching rows across all pages.
+///
+/// `order_by` must sort the rows in a total order, e.g. by ending with the primary key, so
+/// that consecutive pages don't overlap.
+pub async fn load_page<'a, T>(
+    conn: &mut PgConnection,
+    columns: &str,
+    from: &str,
+    push_filters: impl Fn(&mut QueryBuilder<'a, Postgres>),
+    order_by: &str,
+    params: PageParams,
+) -> Result<Page<T>, crate::Error>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    let request = PageRequest::from(params);
+
+    let mut count_query = QueryBuilder::new(format!("SELECT COUNT(*) FROM {from}"));
+    push_filters(&mut count_query);
+    let total: i64 = count_query
+        .build_query_scalar()
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let mut select_query = QueryBuilder::new(format!("SELECT {columns} FROM {from}"));
+    push_filters(&mut select_query);
+    select_query.push(" ORDER BY ").push(order_by);
+    request.push_to(&mut select_query);
+    let items = select_query
+        .build_query_as::<T>()
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(Page::new(items, total, request))
+}
+
+#[cfg(test)]
+mod pagination_tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let request = PageRequest::new(None, None);
+
+        assert_eq!(request.page, 1);
+        assert_eq!(request.limit, DEFAULT_PAGE_LIMIT);
+        assert_eq!(request.offset(), 0);
+    }
+
+    #[test]
+    fn test_offset() {
+        let request = PageRequest::new(Some(3), Some(20));
+
+        assert_eq!(request.offset(), 40);
+
+        let request = PageRequest::new(Some(i64::MAX), Some(MAX_PAGE_LIMIT));
+
+        assert_eq!(request.offset(), i64::MAX);
+    }
+
+    #[test]
+    fn test_next_page() {
+        let page = Page::new(vec![1, 2], 5, PageRequest::new(Some(1), Some(2)));
+        assert_eq!(page.next_page, Some(2));
+
+        let page = Page::new(vec![5], 5, PageRequest::new(Some(3), Some(2)));
+        assert_eq!(page.next_page, None);
+
+        let page = Page::<i32>::new(vec![], 0, PageRequest::new(None, None));
+        assert_eq!(page.next_page, None);
+
+        let page = Page::<i32>::new(vec![], 5, PageRequest::new(Some(i64::MAX / 2), Some(3)));
+        assert_eq!(page.next_page, None);
+    }
+}
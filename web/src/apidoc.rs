@@ -1,22 +1,111 @@
 use itil_back_db::entities::incidents::IncidentPrio;
-use utoipa::OpenApi;
+use utoipa::openapi::response::ResponseBuilder;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 
 pub const CONFIG_ITEMS_TAG: &str = "configitems";
 pub const INCIDENTS_TAG: &str = "incidents";
+pub const MAJOR_INCIDENTS_TAG: &str = "majorincidents";
 pub const PROBLEMS_TAG: &str = "problems";
+pub const KNOWN_ERRORS_TAG: &str = "knownerrors";
+pub const KNOWLEDGE_ARTICLES_TAG: &str = "articles";
+pub const KNOWLEDGE_CATEGORIES_TAG: &str = "articlecategories";
+pub const PROBLEM_CANDIDATES_TAG: &str = "problemcandidates";
+pub const ROOT_CAUSE_CATEGORIES_TAG: &str = "rootcausecategories";
 pub const CHANGES_TAG: &str = "changes";
+pub const CAB_TAG: &str = "cab";
+pub const FREEZE_PERIODS_TAG: &str = "freezeperiods";
+pub const RISK_QUESTIONNAIRE_TAG: &str = "riskquestionnaire";
+pub const SERVICE_CATALOG_TAG: &str = "catalog";
+pub const SERVICE_REQUESTS_TAG: &str = "requests";
+pub const SEARCH_TAG: &str = "search";
+pub const ROLES_TAG: &str = "roles";
+pub const USERS_TAG: &str = "users";
+pub const TEAMS_TAG: &str = "teams";
+pub const SLA_POLICIES_TAG: &str = "slapolicies";
+pub const PRIORITY_MATRIX_TAG: &str = "prioritymatrix";
+
+/// Name of the security scheme for JWT bearer tokens.
+pub const BEARER_AUTH: &str = "bearer_auth";
 
 #[derive(OpenApi)]
 #[openapi(
     tags(
         (name = CONFIG_ITEMS_TAG, description = "Configuration Management Endpoints"),
         (name = INCIDENTS_TAG, description = "Incident Management Endpoints"),
+        (name = MAJOR_INCIDENTS_TAG, description = "Major Incident Management Endpoints"),
         (name = PROBLEMS_TAG, description = "Problem Management Endpoints"),
+        (name = KNOWN_ERRORS_TAG, description = "Known Error Database Endpoints"),
+        (name = KNOWLEDGE_ARTICLES_TAG, description = "Knowledge Base Endpoints"),
+        (name = KNOWLEDGE_CATEGORIES_TAG, description = "Knowledge Article Category Endpoints"),
+        (name = PROBLEM_CANDIDATES_TAG, description = "Problem Candidate Endpoints"),
+        (name = ROOT_CAUSE_CATEGORIES_TAG, description = "Root Cause Taxonomy Endpoints"),
         (name = CHANGES_TAG, description = "Changes Management Endpoints"),
+        (name = CAB_TAG, description = "Change Advisory Board Endpoints"),
+        (name = FREEZE_PERIODS_TAG, description = "Change Freeze Period Endpoints"),
+        (name = RISK_QUESTIONNAIRE_TAG, description = "Change Risk Questionnaire Endpoints"),
+        (name = SERVICE_CATALOG_TAG, description = "Service Catalog Endpoints"),
+        (name = SERVICE_REQUESTS_TAG, description = "Service Request Fulfilment Endpoints"),
+        (name = SEARCH_TAG, description = "Full-Text Search Endpoints"),
+        (name = ROLES_TAG, description = "Roles and Permissions Endpoints"),
+        (name = USERS_TAG, description = "User Management Endpoints"),
+        (name = TEAMS_TAG, description = "Team (Assignment Group) Management Endpoints"),
+        (name = SLA_POLICIES_TAG, description = "SLA Policy Management Endpoints"),
+        (name = PRIORITY_MATRIX_TAG, description = "Incident Priority Matrix Endpoints"),
     ),
     components(
         // Manually add the schema so it generates it.
         schemas(IncidentPrio,)
-    )
+    ),
+    modifiers(&SecurityAddon),
+    security(("bearer_auth" = []))
 )]
 pub struct ApiDoc;
+
+/// Registers the bearer token security scheme, so Swagger UI can send tokens.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Documents the 401 response of every operation that requires a bearer token.
+///
+/// This can't be done in a [Modify] because paths are only added to the
+/// document once the routes are registered.
+pub fn add_unauthorized_responses(openapi: &mut utoipa::openapi::OpenApi) {
+    for path_item in openapi.paths.paths.values_mut() {
+        for operation in [
+            &mut path_item.get,
+            &mut path_item.put,
+            &mut path_item.post,
+            &mut path_item.delete,
+            &mut path_item.patch,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            // Public operations override the global security requirement.
+            if operation.security.is_some() {
+                continue;
+            }
+            operation.responses.responses.insert(
+                String::from("401"),
+                ResponseBuilder::new()
+                    .description("Missing or invalid bearer token.")
+                    .into(),
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::change_advisory_board::{
+    self, ChangeAdvisoryBoard, ChangeAdvisoryBoardUpdateset,
+};
+use tracing::info;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    responses(
+        (status = OK,
+            body = ChangeAdvisoryBoard,
+            description = "OK"
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CAB_TAG
+)]
+pub async fn read_cab(
+    State(app_state): State<SharedAppState>,
+) -> Result<Json<ChangeAdvisoryBoard>, Error> {
+    let board = change_advisory_board::load(&app_state.db_pool).await?;
+
+    info!("responding with {:?}", board);
+
+    Ok(Json(board))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "",
+    request_body(
+        content = ChangeAdvisoryBoardUpdateset,
+        description = "Team acting as CAB, approvals normal changes need and whether RFCs colliding with others can be approved.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = ChangeAdvisoryBoard,
+            description = "CAB updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or references a nonexistent team."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CAB_TAG
+)]
+pub async fn update_cab(
+    Authorized { principal, .. }: Authorized<can::CabManage>,
+    State(app_state): State<SharedAppState>,
+    Json(updateset): Json<ChangeAdvisoryBoardUpdateset>,
+) -> Result<Json<ChangeAdvisoryBoard>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let board = change_advisory_board::update(updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(board))
+}
@@ -1,11 +1,40 @@
+use crate::middlewares::authorization::{authorize, can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
-use axum::{extract::Path, extract::State, http::StatusCode, Json};
-use itil_back_db::entities::changes::{self, RFCCreateset, RFCUpdateset, RFC};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::{self, AuditEntity, AuditEntry};
+use itil_back_db::entities::changes::{
+    self, RFCCreateset, RFCListParams, RFCStatus, RFCUpdateset, RFC,
+};
+use itil_back_db::entities::roles::Permission;
+use itil_back_db::pagination::{Page, PageParams};
 use tracing::info;
 use uuid::Uuid;
 
+pub mod approvals;
+pub mod calendar;
+pub mod ci_changes;
+pub mod ci_relations;
+pub mod conflicts;
+pub mod freeze_overrides;
 pub mod incident_relations;
 pub mod problem_relations;
+pub mod reviews;
+pub mod risk;
+pub mod timeline;
+
+/// Permission needed, besides [`Permission::ChangesWrite`], to move an RFC to `status`.
+///
+/// Approving an RFC also needs the approval of the CAB, which usually moves it to
+/// `approved` by itself (see [`approvals`]).
+fn status_permission(status: RFCStatus) -> Option<Permission> {
+    match status {
+        RFCStatus::Approved | RFCStatus::Rejected => Some(Permission::ChangesApprove),
+        RFCStatus::Closed => Some(Permission::ChangesClose),
+        _ => None,
+    }
+}
 
 #[axum::debug_handler]
 #[utoipa::path(post,
@@ -22,7 +51,10 @@ pub mod problem_relations;
             content_type = "application/json"
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations, references a nonexistent user or isn't a draft or submitted."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -31,20 +63,33 @@ pub mod problem_relations;
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn create_rfc(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
     State(app_state): State<SharedAppState>,
     Json(createset): Json<RFCCreateset>,
 ) -> Result<(StatusCode, Json<RFC>), Error> {
-    let rfc = changes::create(createset, &app_state.db_pool).await?;
+    if let Some(permission) = createset.status.and_then(status_permission) {
+        authorize(&principal, permission, &app_state.db_pool).await?;
+    }
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let rfc = changes::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(rfc)))
 }
 
 #[axum::debug_handler]
 #[utoipa::path(get,
     path = "",
+    params(RFCListParams, PageParams),
     responses(
         (status = OK,
-            body = Vec<RFC>,
-            description = "List of RFCs."
+            body = Page<RFC>,
+            description = "Page of RFCs."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -54,12 +99,13 @@ pub async fn create_rfc(
 )]
 pub async fn read_all_rfcs(
     State(app_state): State<SharedAppState>,
-) -> Result<Json<Vec<RFC>>, Error> {
-    let rfcs = changes::load_all(&app_state.db_pool).await?;
+    Query(params): Query<RFCListParams>,
+) -> Result<Json<Page<RFC>>, Error> {
+    let page = changes::load_page(params, &app_state.db_pool).await?;
 
-    info!("responding with {:?}", rfcs);
+    info!("responding with {:?}", page);
 
-    Ok(Json(rfcs))
+    Ok(Json(page))
 }
 
 #[axum::debug_handler]
@@ -87,6 +133,34 @@ pub async fn read_one_rfc(
     Ok(Json(rfc))
 }
 
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the RFC and its relations, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "RFC never existed."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_rfc_history(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = audit::load_history(AuditEntity::Rfc, id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
 #[axum::debug_handler]
 #[utoipa::path(put,
     path = "/{id}",
@@ -102,11 +176,17 @@ pub async fn read_one_rfc(
             content_type = "application/json"
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations, references a nonexistent user, changes the type of an RFC that isn't a draft, assesses, approves or schedules an RFC without its plans or risk assessment, approves it without the approval of the CAB or while it collides with other RFCs and the CAB blocks collisions, schedules it without a planned window or, unless it's an emergency change or has an override of the freeze periods, during a freeze period, or reviews it without a post-implementation review or while Incidents suggested as caused by it are pending."
         ),
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = CONFLICT,
+            description = "The RFC can't move from its current status to the requested one, given its type. The body lists the allowed next statuses."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -114,11 +194,17 @@ pub async fn read_one_rfc(
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn update_rfc(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
     Json(updateset): Json<RFCUpdateset>,
 ) -> Result<Json<RFC>, Error> {
-    let rfc = changes::update(id, updateset, &app_state.db_pool).await?;
+    if let Some(permission) = updateset.status.flatten().and_then(status_permission) {
+        authorize(&principal, permission, &app_state.db_pool).await?;
+    }
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let rfc = changes::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(rfc))
 }
 
@@ -132,6 +218,12 @@ pub async fn update_rfc(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Changes to Configuration Items trace back to the RFC."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -139,9 +231,12 @@ pub async fn update_rfc(
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn delete_rfc(
+    Authorized { principal, .. }: Authorized<can::ChangesDelete>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, Error> {
-    changes::delete(id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    changes::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,77 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::changes::approvals::{self, RFCApproval, RFCApprovalCreateset};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/approvals",
+    request_body(
+        content = RFCApprovalCreateset,
+        description = "Decision of the caller, a CAB member, on the RFC. A rejection rejects the RFC, and it's approved once it has the approvals its type needs.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = RFCApproval,
+            description = "Decision recorded successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, the caller already decided on the RFC, or approves it without its plans or risk assessment, or while it collides with other RFCs and the CAB blocks collisions."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission, isn't a CAB member, or the RFC isn't awaiting a decision."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn create_rfc_approval(
+    Authorized { principal, .. }: Authorized<can::ChangesApprove>,
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+    Json(createset): Json<RFCApprovalCreateset>,
+) -> Result<(StatusCode, Json<RFCApproval>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let approval = approvals::create(rfc_id, &principal.subject, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(approval)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/approvals",
+    responses(
+        (status = OK,
+            body = Vec<RFCApproval>,
+            description = "Decisions on the current submission of the RFC, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_all_rfc_approvals(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<Vec<RFCApproval>>, Error> {
+    let approvals = approvals::load_all(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", approvals);
+
+    Ok(Json(approvals))
+}
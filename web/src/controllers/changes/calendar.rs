@@ -0,0 +1,36 @@
+use crate::{apidoc, error::Error, state::SharedAppState};
+use axum::{extract::Query, extract::State, Json};
+use itil_back_db::entities::changes::calendar::{self, ChangeCalendar, ChangeCalendarParams};
+use tracing::info;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/calendar",
+    params(ChangeCalendarParams),
+    responses(
+        (status = OK,
+            body = ChangeCalendar,
+            description = "RFCs planned or implemented in the range, and the freeze periods in it."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_change_calendar(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<ChangeCalendarParams>,
+) -> Result<Json<ChangeCalendar>, Error> {
+    let calendar = calendar::load(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", calendar);
+
+    Ok(Json(calendar))
+}
@@ -0,0 +1,33 @@
+use crate::{apidoc, error::Error, state::SharedAppState};
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::entities::configuration::changes::{self, CIChange};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/cichanges",
+    responses(
+        (status = OK,
+            body = Vec<CIChange>,
+            description = "Changes to Configuration Items authorized by the RFC."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_all_rfc_ci_changes(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<Vec<CIChange>>, Error> {
+    let changes = changes::load_all_for_rfc(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", changes);
+
+    Ok(Json(changes))
+}
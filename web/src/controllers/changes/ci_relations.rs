@@ -0,0 +1,107 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::changes::ci_relations::{self, RFCCICreateset, RFCCIRelation};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/configitems",
+    request_body(
+        content = RFCCICreateset,
+        description = "Configuration Item affected by the RFC.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = RFCCIRelation,
+            description = "Relation created successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, references a nonexistent Configuration Item or it's related already."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn create_rfc_ci_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+    Json(createset): Json<RFCCICreateset>,
+) -> Result<(StatusCode, Json<RFCCIRelation>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = ci_relations::create(rfc_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(relation)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/configitems",
+    responses(
+        (status = OK,
+            body = Vec<RFCCIRelation>,
+            description = "List of relations."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_all_rfc_ci_relations(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<Vec<RFCCIRelation>>, Error> {
+    let relations = ci_relations::load_all(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", relations);
+
+    Ok(Json(relations))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}/configitems/{relation_id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Relation deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn delete_rfc_ci_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((rfc_id, relation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    ci_relations::delete(rfc_id, relation_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
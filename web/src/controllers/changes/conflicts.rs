@@ -0,0 +1,33 @@
+use crate::{apidoc, error::Error, state::SharedAppState};
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::entities::changes::conflicts::{self, RFCConflict};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/conflicts",
+    responses(
+        (status = OK,
+            body = Vec<RFCConflict>,
+            description = "RFCs planned in an overlapping window on the same or directly related Configuration Items, one entry per pair of colliding items."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_rfc_conflicts(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<Vec<RFCConflict>>, Error> {
+    let conflicts = conflicts::load_all(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", conflicts);
+
+    Ok(Json(conflicts))
+}
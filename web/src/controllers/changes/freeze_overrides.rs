@@ -0,0 +1,110 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::changes::freeze_overrides::{
+    self, RFCFreezeOverride, RFCFreezeOverrideUpdateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/freezeoverride",
+    responses(
+        (status = OK,
+            body = RFCFreezeOverride,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist, or the RFC has no override."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_rfc_freeze_override(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<RFCFreezeOverride>, Error> {
+    let freeze_override = freeze_overrides::load(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", freeze_override);
+
+    Ok(Json(freeze_override))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/freezeoverride",
+    request_body(
+        content = RFCFreezeOverrideUpdateset,
+        description = "Justification of the override, replacing the current one. The RFC can then be scheduled during freeze periods.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RFCFreezeOverride,
+            description = "Override granted successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission or isn't a user, or the RFC is a draft."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn update_rfc_freeze_override(
+    Authorized { principal, .. }: Authorized<can::ChangesApprove>,
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+    Json(updateset): Json<RFCFreezeOverrideUpdateset>,
+) -> Result<Json<RFCFreezeOverride>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let freeze_override =
+        freeze_overrides::update(rfc_id, &principal.subject, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(freeze_override))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}/freezeoverride",
+    responses(
+        (status = NO_CONTENT,
+            description = "Override withdrawn successfully. Scheduled RFCs stay scheduled until they're rescheduled.",
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist, or the RFC has no override."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn delete_rfc_freeze_override(
+    Authorized { principal, .. }: Authorized<can::ChangesApprove>,
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    freeze_overrides::delete(rfc_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
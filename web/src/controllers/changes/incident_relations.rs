@@ -1,7 +1,10 @@
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
 use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
 use itil_back_db::entities::changes::incident_relations::{
-    self, RFCIncidentCreateset, RFCIncidentRelation,
+    self, RFCIncidentCreateset, RFCIncidentRelation, RFCIncidentUpdateset,
 };
 use tracing::info;
 use uuid::Uuid;
@@ -26,6 +29,9 @@ use uuid::Uuid;
         (status = UNPROCESSABLE_ENTITY,
             description = "Request body didn't pass validations."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -33,11 +39,14 @@ use uuid::Uuid;
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn create_rfc_incident_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
     State(app_state): State<SharedAppState>,
     Path(rfc_id): Path<Uuid>,
     Json(createset): Json<RFCIncidentCreateset>,
 ) -> Result<(StatusCode, Json<RFCIncidentRelation>), Error> {
-    let relation = incident_relations::create(rfc_id, createset, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = incident_relations::create(rfc_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(relation)))
 }
 
@@ -69,6 +78,44 @@ pub async fn read_all_rfc_incident_relations(
     Ok(Json(changes))
 }
 
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/incidents/{relation_id}",
+    request_body(
+        content = RFCIncidentUpdateset,
+        description = "Relation data to update in the database. Confirm a suggested cause by making it `caused`, or dismiss it by making it `related`.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RFCIncidentRelation,
+            description = "Relation updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn update_rfc_incident_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((rfc_id, relation_id)): Path<(Uuid, Uuid)>,
+    Json(updateset): Json<RFCIncidentUpdateset>,
+) -> Result<Json<RFCIncidentRelation>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = incident_relations::update(rfc_id, relation_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(relation))
+}
+
 #[axum::debug_handler]
 #[utoipa::path(delete,
     path = "/{id}/incidents/{relation_id}",
@@ -79,6 +126,9 @@ pub async fn read_all_rfc_incident_relations(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -86,9 +136,12 @@ pub async fn read_all_rfc_incident_relations(
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn delete_rfc_incident_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
     State(app_state): State<SharedAppState>,
     Path((rfc_id, relation_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, Error> {
-    incident_relations::delete(rfc_id, relation_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    incident_relations::delete(rfc_id, relation_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,5 +1,8 @@
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
 use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
 use itil_back_db::entities::changes::problem_relations::{
     self, RFCProblemCreateset, RFCProblemRelation,
 };
@@ -26,6 +29,9 @@ use uuid::Uuid;
         (status = UNPROCESSABLE_ENTITY,
             description = "Request body didn't pass validations."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -33,11 +39,14 @@ use uuid::Uuid;
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn create_rfc_problem_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
     State(app_state): State<SharedAppState>,
     Path(rfc_id): Path<Uuid>,
     Json(createset): Json<RFCProblemCreateset>,
 ) -> Result<(StatusCode, Json<RFCProblemRelation>), Error> {
-    let relation = problem_relations::create(rfc_id, createset, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = problem_relations::create(rfc_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(relation)))
 }
 
@@ -79,6 +88,9 @@ pub async fn read_all_rfc_problem_relations(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -86,9 +98,12 @@ pub async fn read_all_rfc_problem_relations(
     tag = apidoc::CHANGES_TAG
 )]
 pub async fn delete_rfc_problem_relation(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
     State(app_state): State<SharedAppState>,
     Path((rfc_id, relation_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, Error> {
-    problem_relations::delete(rfc_id, relation_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    problem_relations::delete(rfc_id, relation_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,77 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::changes::reviews::{self, RFCReview, RFCReviewUpdateset};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/review",
+    responses(
+        (status = OK,
+            body = RFCReview,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist, or the RFC wasn't reviewed yet."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_rfc_review(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<RFCReview>, Error> {
+    let review = reviews::load(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", review);
+
+    Ok(Json(review))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/review",
+    request_body(
+        content = RFCReviewUpdateset,
+        description = "Post-implementation review, replacing the current one.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RFCReview,
+            description = "RFC reviewed successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or denies that the change caused Incidents related to it as caused."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission or isn't a user, or the RFC isn't implemented."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn update_rfc_review(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+    Json(updateset): Json<RFCReviewUpdateset>,
+) -> Result<Json<RFCReview>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let review = reviews::update(rfc_id, &principal.subject, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(review))
+}
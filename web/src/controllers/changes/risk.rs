@@ -0,0 +1,77 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::changes::risk::{self, RFCRiskAssessment, RFCRiskAssessmentUpdateset};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/risk",
+    responses(
+        (status = OK,
+            body = RFCRiskAssessment,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn read_rfc_risk_assessment(
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+) -> Result<Json<RFCRiskAssessment>, Error> {
+    let assessment = risk::load(rfc_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", assessment);
+
+    Ok(Json(assessment))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/risk",
+    request_body(
+        content = RFCRiskAssessmentUpdateset,
+        description = "Answers to the risk questionnaire, replacing the current assessment.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RFCRiskAssessment,
+            description = "RFC assessed successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body doesn't answer every question of the questionnaire once with one of its answers."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission, or the CAB decided on the RFC already."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CHANGES_TAG
+)]
+pub async fn update_rfc_risk_assessment(
+    Authorized { principal, .. }: Authorized<can::ChangesWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(rfc_id): Path<Uuid>,
+    Json(updateset): Json<RFCRiskAssessmentUpdateset>,
+) -> Result<Json<RFCRiskAssessment>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let assessment = risk::update(rfc_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(assessment))
+}
@@ -1,12 +1,19 @@
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
-use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::{self, AuditEntity, AuditEntry};
+use itil_back_db::entities::configuration::impact::{self, CIImpact, CIImpactParams};
 use itil_back_db::entities::configuration::{
-    self, ConfigItem, ConfigItemCreateset, ConfigItemUpdateset,
+    self, ConfigItem, ConfigItemCreateset, ConfigItemListParams, ConfigItemUpdateset,
 };
+use itil_back_db::pagination::{Page, PageParams};
 use tracing::info;
 use uuid::Uuid;
 
 pub mod changes;
+pub mod relations;
 
 #[axum::debug_handler]
 #[utoipa::path(post,
@@ -23,7 +30,10 @@ pub mod changes;
             content_type = "application/json"
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations or references a nonexistent team."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -32,20 +42,30 @@ pub mod changes;
     tag = apidoc::CONFIG_ITEMS_TAG
 )]
 pub async fn create_ci(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
     State(app_state): State<SharedAppState>,
     Json(configitem): Json<ConfigItemCreateset>,
 ) -> Result<(StatusCode, Json<ConfigItem>), Error> {
-    let configitem = configuration::create(configitem, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let configitem = configuration::create(configitem, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(configitem)))
 }
 
 #[axum::debug_handler]
 #[utoipa::path(get,
     path = "",
+    params(ConfigItemListParams, PageParams),
     responses(
         (status = OK,
-            body = Vec<ConfigItem>,
-            description = "List of Configuration Items."
+            body = Page<ConfigItem>,
+            description = "Page of Configuration Items."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -55,12 +75,13 @@ pub async fn create_ci(
 )]
 pub async fn read_all_ci(
     State(app_state): State<SharedAppState>,
-) -> Result<Json<Vec<ConfigItem>>, Error> {
-    let configitems = configuration::load_all(&app_state.db_pool).await?;
+    Query(params): Query<ConfigItemListParams>,
+) -> Result<Json<Page<ConfigItem>>, Error> {
+    let page = configuration::load_page(params, &app_state.db_pool).await?;
 
-    info!("responding with {:?}", configitems);
+    info!("responding with {:?}", page);
 
-    Ok(Json(configitems))
+    Ok(Json(page))
 }
 
 #[axum::debug_handler]
@@ -88,6 +109,70 @@ pub async fn read_one_ci(
     Ok(Json(configitem))
 }
 
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the CI and its relations, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "CI never existed."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn read_ci_history(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = audit::load_history(AuditEntity::ConfigItem, id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/impact",
+    params(CIImpactParams),
+    responses(
+        (status = OK,
+            body = CIImpact,
+            description = "Configuration Items affected if this one degrades, the open work on them and a suggested Incident impact."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn read_ci_impact(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<CIImpactParams>,
+) -> Result<Json<CIImpact>, Error> {
+    let impact = impact::analyse(id, params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", impact);
+
+    Ok(Json(impact))
+}
+
 #[axum::debug_handler]
 #[utoipa::path(put,
     path = "/{id}",
@@ -103,11 +188,14 @@ pub async fn read_one_ci(
             content_type = "application/json"
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations or references a nonexistent team."
         ),
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -115,11 +203,14 @@ pub async fn read_one_ci(
     tag = apidoc::CONFIG_ITEMS_TAG
 )]
 pub async fn update_ci(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
     Json(configitem): Json<ConfigItemUpdateset>,
 ) -> Result<Json<ConfigItem>, Error> {
-    let configitem = configuration::update(id, configitem, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let configitem = configuration::update(id, configitem, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(configitem))
 }
 
@@ -133,6 +224,9 @@ pub async fn update_ci(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -140,9 +234,12 @@ pub async fn update_ci(
     tag = apidoc::CONFIG_ITEMS_TAG
 )]
 pub async fn delete_ci(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsDelete>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, Error> {
-    configuration::delete(id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    configuration::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,7 +1,10 @@
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
-use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
 use itil_back_db::entities::configuration::changes::{
-    self, CIChange, CIChangeCreateset, CIChangeUpdateset,
+    self, CIChange, CIChangeCreateset, CIChangeListParams, CIChangeUpdateset,
 };
 use tracing::info;
 use uuid::Uuid;
@@ -24,7 +27,10 @@ use uuid::Uuid;
             description = "Resource doesn't exist."
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations or the RFC can't authorize the change."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -33,22 +39,29 @@ use uuid::Uuid;
     tag = apidoc::CONFIG_ITEMS_TAG
 )]
 pub async fn create_ci_change(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
     State(app_state): State<SharedAppState>,
     Path(ci_id): Path<Uuid>,
     Json(createset): Json<CIChangeCreateset>,
 ) -> Result<(StatusCode, Json<CIChange>), Error> {
-    let relation = changes::create(ci_id, createset, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = changes::create(ci_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(relation)))
 }
 
 #[axum::debug_handler]
 #[utoipa::path(get,
     path = "/{id}/changes",
+    params(CIChangeListParams),
     responses(
         (status = OK,
             body = Vec<CIChange>,
             description = "List of changes."
         ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
         (status = NOT_FOUND,
             description = "Resource doesn't exist."
         ),
@@ -61,8 +74,9 @@ pub async fn create_ci_change(
 pub async fn read_all_ci_changes(
     State(app_state): State<SharedAppState>,
     Path(ci_id): Path<Uuid>,
+    Query(params): Query<CIChangeListParams>,
 ) -> Result<Json<Vec<CIChange>>, Error> {
-    let changes = changes::load_all(ci_id, &app_state.db_pool).await?;
+    let changes = changes::load_all(ci_id, params, &app_state.db_pool).await?;
 
     info!("responding with {:?}", changes);
 
@@ -111,6 +125,12 @@ pub async fn read_one_ci_change(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the RFC can't authorize the change."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -118,11 +138,14 @@ pub async fn read_one_ci_change(
     tag = apidoc::CONFIG_ITEMS_TAG
 )]
 pub async fn update_ci_change(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
     State(app_state): State<SharedAppState>,
     Path((ci_id, change_id)): Path<(Uuid, Uuid)>,
     Json(updateset): Json<CIChangeUpdateset>,
 ) -> Result<Json<CIChange>, Error> {
-    let change = changes::update(change_id, ci_id, updateset, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let change = changes::update(change_id, ci_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(change))
 }
 
@@ -136,6 +159,9 @@ pub async fn update_ci_change(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -143,9 +169,12 @@ pub async fn update_ci_change(
     tag = apidoc::CONFIG_ITEMS_TAG
 )]
 pub async fn delete_ci_change(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
     State(app_state): State<SharedAppState>,
     Path((ci_id, change_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, Error> {
-    changes::delete(change_id, ci_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    changes::delete(change_id, ci_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
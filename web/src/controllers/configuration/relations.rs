@@ -0,0 +1,211 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::configuration::relations::{
+    self, CIGraph, CIGraphParams, CIRelation, CIRelationCreateset, CIRelationUpdateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/relations",
+    request_body(
+        content = CIRelationCreateset,
+        description = "Relation from this Configuration Item to another one.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = CIRelation,
+            description = "Relation created successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, the target doesn't exist or is the same Configuration Item, the relation already exists, or it would close a cycle of hierarchical relations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn create_ci_relation(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(ci_id): Path<Uuid>,
+    Json(createset): Json<CIRelationCreateset>,
+) -> Result<(StatusCode, Json<CIRelation>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = relations::create(ci_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(relation)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/relations",
+    responses(
+        (status = OK,
+            body = Vec<CIRelation>,
+            description = "Relations the Configuration Item is the source or the target of."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn read_all_ci_relations(
+    State(app_state): State<SharedAppState>,
+    Path(ci_id): Path<Uuid>,
+) -> Result<Json<Vec<CIRelation>>, Error> {
+    let relations = relations::load_all(ci_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", relations);
+
+    Ok(Json(relations))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/relations/{relation_id}",
+    responses(
+        (status = OK,
+            body = CIRelation,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn read_one_ci_relation(
+    State(app_state): State<SharedAppState>,
+    Path((ci_id, relation_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<CIRelation>, Error> {
+    let relation = relations::load(relation_id, ci_id, &app_state.db_pool).await?;
+    Ok(Json(relation))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/relations/{relation_id}",
+    request_body(
+        content = CIRelationUpdateset,
+        description = "Relation data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = CIRelation,
+            description = "Relation updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, the relation already exists with the new type, or it would close a cycle of hierarchical relations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn update_ci_relation(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((ci_id, relation_id)): Path<(Uuid, Uuid)>,
+    Json(updateset): Json<CIRelationUpdateset>,
+) -> Result<Json<CIRelation>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = relations::update(relation_id, ci_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(relation))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}/relations/{relation_id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Relation deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn delete_ci_relation(
+    Authorized { principal, .. }: Authorized<can::ConfigItemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((ci_id, relation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    relations::delete(relation_id, ci_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/graph",
+    params(CIGraphParams),
+    responses(
+        (status = OK,
+            body = CIGraph,
+            description = "Configuration Items reachable through relations, in the requested direction and up to the requested depth."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::CONFIG_ITEMS_TAG
+)]
+pub async fn read_ci_graph(
+    State(app_state): State<SharedAppState>,
+    Path(ci_id): Path<Uuid>,
+    Query(params): Query<CIGraphParams>,
+) -> Result<Json<CIGraph>, Error> {
+    let graph = relations::walk(ci_id, params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", graph);
+
+    Ok(Json(graph))
+}
@@ -0,0 +1,176 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::freeze_periods::{
+    self, FreezePeriod, FreezePeriodCreateset, FreezePeriodListParams, FreezePeriodUpdateset,
+};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = FreezePeriodCreateset,
+        description = "Freeze period to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = FreezePeriod,
+            description = "Freeze period created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::FREEZE_PERIODS_TAG
+)]
+pub async fn create_freeze_period(
+    Authorized { principal, .. }: Authorized<can::FreezePeriodsManage>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<FreezePeriodCreateset>,
+) -> Result<(StatusCode, Json<FreezePeriod>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let period = freeze_periods::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(period)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(FreezePeriodListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<FreezePeriod>,
+            description = "Page of freeze periods."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::FREEZE_PERIODS_TAG
+)]
+pub async fn read_all_freeze_periods(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<FreezePeriodListParams>,
+) -> Result<Json<Page<FreezePeriod>>, Error> {
+    let page = freeze_periods::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = FreezePeriod,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::FREEZE_PERIODS_TAG
+)]
+pub async fn read_one_freeze_period(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FreezePeriod>, Error> {
+    let period = freeze_periods::load(id, &app_state.db_pool).await?;
+    Ok(Json(period))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = FreezePeriodUpdateset,
+        description = "Freeze period data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = FreezePeriod,
+            description = "Freeze period updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::FREEZE_PERIODS_TAG
+)]
+pub async fn update_freeze_period(
+    Authorized { principal, .. }: Authorized<can::FreezePeriodsManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<FreezePeriodUpdateset>,
+) -> Result<Json<FreezePeriod>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let period = freeze_periods::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(period))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Freeze period deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::FREEZE_PERIODS_TAG
+)]
+pub async fn delete_freeze_period(
+    Authorized { principal, .. }: Authorized<can::FreezePeriodsManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    freeze_periods::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -4,6 +4,7 @@ use axum::http::StatusCode;
 #[utoipa::path(
     method(get, head),
     path = "/api/health",
+    security(()),
     responses(
         (status = OK, description = "Success")
     )
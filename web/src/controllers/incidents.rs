@@ -1,11 +1,25 @@
+use crate::middlewares::auth::Principal;
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
-use axum::{extract::Path, extract::State, http::StatusCode, Json};
-use itil_back_db::entities::incidents::{self, Incident, IncidentCreateset, IncidentUpdateset};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::{self, AuditEntity, AuditEntry};
+use itil_back_db::entities::incidents::{
+    self, sla::IncidentSla, Incident, IncidentCreateset, IncidentListParams, IncidentUpdateset,
+};
+use itil_back_db::pagination::{Page, PageParams};
 use tracing::info;
 use uuid::Uuid;
 
+/// Controllers for parent and child Incidents.
+pub mod children;
 /// Controllers for Incident-CI relations.
 pub mod ci_relations;
+/// Controllers for merging duplicate Incidents.
+pub mod merge;
+/// Controllers for the timeline of Incidents.
+pub mod timeline;
 
 #[axum::debug_handler]
 #[utoipa::path(post,
@@ -22,7 +36,10 @@ pub mod ci_relations;
             content_type = "application/json"
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations, references a nonexistent team, user or parent, the assignee isn't a member of the assignment group, or the status lacks its hold reason or resolution."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -31,20 +48,30 @@ pub mod ci_relations;
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn create_incident(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
     State(app_state): State<SharedAppState>,
     Json(createset): Json<IncidentCreateset>,
 ) -> Result<(StatusCode, Json<Incident>), Error> {
-    let incident = incidents::create(createset, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let incident = incidents::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(incident)))
 }
 
 #[axum::debug_handler]
 #[utoipa::path(get,
     path = "",
+    params(IncidentListParams, PageParams),
     responses(
         (status = OK,
-            body = Vec<Incident>,
-            description = "List of Incidents."
+            body = Page<Incident>,
+            description = "Page of Incidents."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -53,22 +80,31 @@ pub async fn create_incident(
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn read_all_incidents(
+    principal: Principal,
     State(app_state): State<SharedAppState>,
-) -> Result<Json<Vec<Incident>>, Error> {
-    let incidents = incidents::load_all(&app_state.db_pool).await?;
+    Query(params): Query<IncidentListParams>,
+) -> Result<Json<Page<Incident>>, Error> {
+    let page = incidents::load_page(params, &principal.subject, &app_state.db_pool).await?;
 
-    info!("responding with {:?}", incidents);
+    info!("responding with {:?}", page);
 
-    Ok(Json(incidents))
+    Ok(Json(page))
 }
 
 #[axum::debug_handler]
 #[utoipa::path(get,
     path = "/byconfigitem/{id}",
+    params(PageParams),
     responses(
         (status = OK,
-            body = Vec<Incident>,
-            description = "List of Incidents."
+            body = Page<Incident>,
+            description = "Page of the Incidents related to the Configuration Item, oldest first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -79,12 +115,13 @@ pub async fn read_all_incidents(
 pub async fn read_all_incidents_by_ci(
     State(app_state): State<SharedAppState>,
     Path(ci_id): Path<Uuid>,
-) -> Result<Json<Vec<Incident>>, Error> {
-    let incidents = incidents::load_all_by_ci(ci_id, &app_state.db_pool).await?;
+    Query(params): Query<PageParams>,
+) -> Result<Json<Page<Incident>>, Error> {
+    let page = incidents::load_page_by_ci(ci_id, params, &app_state.db_pool).await?;
 
-    info!("responding with {:?}", incidents);
+    info!("responding with {:?}", page);
 
-    Ok(Json(incidents))
+    Ok(Json(page))
 }
 
 #[axum::debug_handler]
@@ -112,6 +149,62 @@ pub async fn read_one_incident(
     Ok(Json(incident))
 }
 
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the Incident and its relations, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Incident never existed."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn read_incident_history(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = audit::load_history(AuditEntity::Incident, id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/sla",
+    responses(
+        (status = OK,
+            body = IncidentSla,
+            description = "Deadlines, elapsed time and breaches of the Incident as of now."
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn read_incident_sla(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<IncidentSla>, Error> {
+    let sla = incidents::sla::load(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", sla);
+
+    Ok(Json(sla))
+}
+
 #[axum::debug_handler]
 #[utoipa::path(put,
     path = "/{id}",
@@ -127,11 +220,17 @@ pub async fn read_one_incident(
             content_type = "application/json"
         ),
         (status = UNPROCESSABLE_ENTITY,
-            description = "Request body didn't pass validations."
+            description = "Request body didn't pass validations, references a nonexistent team, user or parent, the assignee isn't a member of the assignment group, the new status lacks its hold reason or resolution, or a major incident is closed without related Problem."
+        ),
+        (status = CONFLICT,
+            description = "The Incident can't move from its current status to the requested one. The body lists the allowed next statuses."
         ),
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -139,11 +238,14 @@ pub async fn read_one_incident(
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn update_incident(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
     Json(updateset): Json<IncidentUpdateset>,
 ) -> Result<Json<Incident>, Error> {
-    let incident = incidents::update(id, updateset, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let incident = incidents::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(incident))
 }
 
@@ -157,6 +259,9 @@ pub async fn update_incident(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -164,9 +269,12 @@ pub async fn update_incident(
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn delete_incident(
+    Authorized { principal, .. }: Authorized<can::IncidentsDelete>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, Error> {
-    incidents::delete(id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    incidents::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
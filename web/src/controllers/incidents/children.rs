@@ -0,0 +1,80 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::incidents::{
+    children::{self, IncidentChildrenCreateset},
+    Incident,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/children",
+    request_body(
+        content = IncidentChildrenCreateset,
+        description = "Incidents to make children of the Incident.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = Vec<Incident>,
+            description = "Incidents linked successfully, responding with all the children of the Incident.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Incident doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, references a nonexistent Incident, or the Incidents would be nested more than one level deep."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn create_incident_children(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(incident_id): Path<Uuid>,
+    Json(createset): Json<IncidentChildrenCreateset>,
+) -> Result<(StatusCode, Json<Vec<Incident>>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let children = children::create(incident_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(children)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/children",
+    responses(
+        (status = OK,
+            body = Vec<Incident>,
+            description = "Children of the Incident, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Incident doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn read_incident_children(
+    State(app_state): State<SharedAppState>,
+    Path(incident_id): Path<Uuid>,
+) -> Result<Json<Vec<Incident>>, Error> {
+    let children = children::load_all(incident_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", children);
+
+    Ok(Json(children))
+}
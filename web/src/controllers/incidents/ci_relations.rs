@@ -1,5 +1,8 @@
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
 use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
 use itil_back_db::entities::incidents::ci_relations::{self, IncidentCIRelation};
 use serde::Deserialize;
 #[cfg(feature = "test-helpers")]
@@ -40,6 +43,9 @@ pub struct ModifyIncidentCIRelation {
         (status = UNPROCESSABLE_ENTITY,
             description = "Request body didn't pass validations."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -47,11 +53,14 @@ pub struct ModifyIncidentCIRelation {
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn create_incident_ci_relation(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
     State(app_state): State<SharedAppState>,
     Path(incident_id): Path<Uuid>,
     Json(request): Json<RelateCIRequest>,
 ) -> Result<(StatusCode, Json<IncidentCIRelation>), Error> {
-    let relation = ci_relations::create(incident_id, request.ci_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = ci_relations::create(incident_id, request.ci_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(relation)))
 }
 
@@ -100,6 +109,9 @@ pub async fn read_all_incident_ci_relations(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -107,12 +119,14 @@ pub async fn read_all_incident_ci_relations(
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn update_incident_ci_relation(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
     State(app_state): State<SharedAppState>,
     Path((incident_id, ci_id)): Path<(Uuid, Uuid)>,
     Json(request): Json<ModifyIncidentCIRelation>,
 ) -> Result<Json<IncidentCIRelation>, Error> {
-    let relation =
-        ci_relations::update(incident_id, ci_id, request.description, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = ci_relations::update(incident_id, ci_id, request.description, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(relation))
 }
 
@@ -126,6 +140,9 @@ pub async fn update_incident_ci_relation(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -133,9 +150,12 @@ pub async fn update_incident_ci_relation(
     tag = apidoc::INCIDENTS_TAG
 )]
 pub async fn delete_incident_ci_relation(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
     State(app_state): State<SharedAppState>,
     Path((incident_id, ci_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, Error> {
-    ci_relations::delete(incident_id, ci_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    ci_relations::delete(incident_id, ci_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
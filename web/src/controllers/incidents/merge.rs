@@ -0,0 +1,51 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::incidents::{
+    merge::{self, IncidentMergeset},
+    Incident,
+};
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/merge",
+    request_body(
+        content = IncidentMergeset,
+        description = "Incident the Incident is a duplicate of. The children of the Incident move to the surviving Incident or, if it's a child of another Incident, to that parent. Merging a parent into one of its children makes the child a top-level Incident.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = Incident,
+            description = "Incident merged successfully, responding with the surviving Incident.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Incident doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Surviving Incident doesn't exist or is the Incident itself, or either Incident is closed."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn merge_incident(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(incident_id): Path<Uuid>,
+    Json(mergeset): Json<IncidentMergeset>,
+) -> Result<Json<Incident>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let survivor = merge::merge(incident_id, mergeset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(survivor))
+}
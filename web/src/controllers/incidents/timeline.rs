@@ -0,0 +1,154 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use chrono::Duration;
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::timeline::{
+    self, TimelineEntity, TimelineEntry, TimelineEntryCreateset, TimelineEntryUpdateset,
+    TimelineListParams,
+};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/timeline",
+    request_body(
+        content = TimelineEntryCreateset,
+        description = "Work note or comment to add to the timeline of the Incident.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = TimelineEntry,
+            description = "Entry created successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Incident doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or the entry replied to isn't part of the timeline."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn create_incident_timeline_entry(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(incident_id): Path<Uuid>,
+    Json(createset): Json<TimelineEntryCreateset>,
+) -> Result<(StatusCode, Json<TimelineEntry>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let entry = timeline::create(
+        TimelineEntity::Incident,
+        incident_id,
+        createset,
+        &principal.subject,
+        &mut *tx,
+    )
+    .await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/timeline",
+    params(TimelineListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<TimelineEntry>,
+            description = "Page of the timeline of the Incident."
+        ),
+        (status = NOT_FOUND,
+            description = "Incident doesn't exist."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn read_incident_timeline(
+    State(app_state): State<SharedAppState>,
+    Path(incident_id): Path<Uuid>,
+    Query(params): Query<TimelineListParams>,
+) -> Result<Json<Page<TimelineEntry>>, Error> {
+    let page = timeline::load_page(
+        TimelineEntity::Incident,
+        incident_id,
+        params,
+        &app_state.db_pool,
+    )
+    .await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/timeline/{entry_id}",
+    request_body(
+        content = TimelineEntryUpdateset,
+        description = "Entry data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = TimelineEntry,
+            description = "Entry updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission, isn't the author of the entry, or the time to edit it is over."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::INCIDENTS_TAG
+)]
+pub async fn update_incident_timeline_entry(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((incident_id, entry_id)): Path<(Uuid, Uuid)>,
+    Json(updateset): Json<TimelineEntryUpdateset>,
+) -> Result<Json<TimelineEntry>, Error> {
+    let edit_window = Duration::minutes(app_state.timeline.edit_window_minutes.into());
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let entry = timeline::update(
+        TimelineEntity::Incident,
+        incident_id,
+        entry_id,
+        updateset,
+        &principal.subject,
+        edit_window,
+        &mut *tx,
+    )
+    .await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(entry))
+}
@@ -0,0 +1,265 @@
+use crate::middlewares::authorization::{authorize, can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::{self, AuditEntity, AuditEntry};
+use itil_back_db::entities::knowledge_articles::{
+    self, KnowledgeArticle, KnowledgeArticleCreateset, KnowledgeArticleListParams,
+    KnowledgeArticleMatch, KnowledgeArticleSearchParams, KnowledgeArticleStatus,
+    KnowledgeArticleUpdateset,
+};
+use itil_back_db::entities::roles::Permission;
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+/// Controllers for the versions of knowledge articles.
+pub mod versions;
+
+/// Permission needed, besides [`Permission::KnowledgeWrite`], to move an article to `status`.
+fn status_permission(status: &KnowledgeArticleStatus) -> Option<Permission> {
+    match status {
+        KnowledgeArticleStatus::Published => Some(Permission::KnowledgeManage),
+        KnowledgeArticleStatus::Draft | KnowledgeArticleStatus::Review => None,
+    }
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = KnowledgeArticleCreateset,
+        description = "Knowledge article to create in the database, as a draft.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = KnowledgeArticle,
+            description = "Knowledge article created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or the category or some linked entity doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn create_knowledge_article(
+    Authorized { principal, .. }: Authorized<can::KnowledgeWrite>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<KnowledgeArticleCreateset>,
+) -> Result<(StatusCode, Json<KnowledgeArticle>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let article = knowledge_articles::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(article)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(KnowledgeArticleListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<KnowledgeArticle>,
+            description = "Page of knowledge articles, most recently updated first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn read_all_knowledge_articles(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<KnowledgeArticleListParams>,
+) -> Result<Json<Page<KnowledgeArticle>>, Error> {
+    let page = knowledge_articles::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/search",
+    params(KnowledgeArticleSearchParams),
+    responses(
+        (status = OK,
+            body = Vec<KnowledgeArticleMatch>,
+            description = "Published knowledge articles matching the search, most relevant first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = NOT_FOUND,
+            description = "The Incident to match doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations, or give either no text to match or both a text and an Incident."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn search_knowledge_articles(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<KnowledgeArticleSearchParams>,
+) -> Result<Json<Vec<KnowledgeArticleMatch>>, Error> {
+    let matches = knowledge_articles::search(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", matches);
+
+    Ok(Json(matches))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = KnowledgeArticle,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn read_one_knowledge_article(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KnowledgeArticle>, Error> {
+    let article = knowledge_articles::load(id, &app_state.db_pool).await?;
+    Ok(Json(article))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the knowledge article and its links, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Knowledge article never existed."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn read_knowledge_article_history(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = audit::load_history(AuditEntity::Article, id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = KnowledgeArticleUpdateset,
+        description = "Knowledge article data to update in the database. Publishing needs the `knowledge.manage` permission too.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = KnowledgeArticle,
+            description = "Knowledge article updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = CONFLICT,
+            description = "The knowledge article can't move from its current status to the requested one. The body lists the allowed next statuses."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or the category or some linked entity doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission, or the title or body change on an article that isn't a draft."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn update_knowledge_article(
+    Authorized { principal, .. }: Authorized<can::KnowledgeWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<KnowledgeArticleUpdateset>,
+) -> Result<Json<KnowledgeArticle>, Error> {
+    if let Some(permission) = updateset
+        .status
+        .flatten()
+        .as_ref()
+        .and_then(status_permission)
+    {
+        authorize(&principal, permission, &app_state.db_pool).await?;
+    }
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let article = knowledge_articles::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(article))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Knowledge article deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn delete_knowledge_article(
+    Authorized { principal, .. }: Authorized<can::KnowledgeManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    knowledge_articles::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,58 @@
+use crate::{apidoc, error::Error, state::SharedAppState};
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::entities::knowledge_articles::versions::{self, KnowledgeArticleVersion};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/versions",
+    responses(
+        (status = OK,
+            body = Vec<KnowledgeArticleVersion>,
+            description = "Versions of the knowledge article, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn read_all_knowledge_article_versions(
+    State(app_state): State<SharedAppState>,
+    Path(article_id): Path<Uuid>,
+) -> Result<Json<Vec<KnowledgeArticleVersion>>, Error> {
+    let versions = versions::load_all(article_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", versions);
+
+    Ok(Json(versions))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/versions/{version}",
+    responses(
+        (status = OK,
+            body = KnowledgeArticleVersion,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_ARTICLES_TAG
+)]
+pub async fn read_one_knowledge_article_version(
+    State(app_state): State<SharedAppState>,
+    Path((article_id, version)): Path<(Uuid, i32)>,
+) -> Result<Json<KnowledgeArticleVersion>, Error> {
+    let version = versions::load(article_id, version, &app_state.db_pool).await?;
+    Ok(Json(version))
+}
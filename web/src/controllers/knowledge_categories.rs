@@ -0,0 +1,173 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::knowledge_categories::{
+    self, KnowledgeCategory, KnowledgeCategoryCreateset, KnowledgeCategoryUpdateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = KnowledgeCategoryCreateset,
+        description = "Knowledge category to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = KnowledgeCategory,
+            description = "Knowledge category created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the name is taken."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_CATEGORIES_TAG
+)]
+pub async fn create_knowledge_category(
+    Authorized { principal, .. }: Authorized<can::KnowledgeManage>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<KnowledgeCategoryCreateset>,
+) -> Result<(StatusCode, Json<KnowledgeCategory>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let category = knowledge_categories::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    responses(
+        (status = OK,
+            body = Vec<KnowledgeCategory>,
+            description = "All the categories, sorted by name."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_CATEGORIES_TAG
+)]
+pub async fn read_all_knowledge_categories(
+    State(app_state): State<SharedAppState>,
+) -> Result<Json<Vec<KnowledgeCategory>>, Error> {
+    let categories = knowledge_categories::load_all(&app_state.db_pool).await?;
+
+    info!("responding with {:?}", categories);
+
+    Ok(Json(categories))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = KnowledgeCategory,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_CATEGORIES_TAG
+)]
+pub async fn read_one_knowledge_category(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KnowledgeCategory>, Error> {
+    let category = knowledge_categories::load(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", category);
+
+    Ok(Json(category))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = KnowledgeCategoryUpdateset,
+        description = "Knowledge category data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = KnowledgeCategory,
+            description = "Knowledge category updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the name is taken."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_CATEGORIES_TAG
+)]
+pub async fn update_knowledge_category(
+    Authorized { principal, .. }: Authorized<can::KnowledgeManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<KnowledgeCategoryUpdateset>,
+) -> Result<Json<KnowledgeCategory>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let category = knowledge_categories::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(category))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Knowledge category deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Some article is still filed under the category."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWLEDGE_CATEGORIES_TAG
+)]
+pub async fn delete_knowledge_category(
+    Authorized { principal, .. }: Authorized<can::KnowledgeManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    knowledge_categories::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,91 @@
+use crate::controllers::problems::incident_relations::CreateIncidentRelation;
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::known_errors::{
+    self, KnownError, KnownErrorMatch, KnownErrorSearchParams,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(KnownErrorSearchParams),
+    responses(
+        (status = OK,
+            body = Vec<KnownErrorMatch>,
+            description = "Published known errors matching the search, most relevant first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = NOT_FOUND,
+            description = "The Incident to match doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations, or give either no text to match or both a text and an Incident."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWN_ERRORS_TAG
+)]
+pub async fn search_known_errors(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<KnownErrorSearchParams>,
+) -> Result<Json<Vec<KnownErrorMatch>>, Error> {
+    let matches = known_errors::search(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", matches);
+
+    Ok(Json(matches))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/incidents",
+    params(
+        ("id" = Uuid, Path, description = "ID of the Problem of the known error."),
+    ),
+    request_body(
+        content = CreateIncidentRelation,
+        description = "Incident to relate to the Problem of the known error.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = KnownError,
+            description = "Incident related to the Problem successfully. Responds with the known error, to apply its workaround.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "The known error doesn't exist or isn't published."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "The Incident doesn't exist, or is related to the Problem already."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::KNOWN_ERRORS_TAG
+)]
+pub async fn link_known_error_incident(
+    Authorized { principal, .. }: Authorized<can::IncidentsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+    Json(request): Json<CreateIncidentRelation>,
+) -> Result<(StatusCode, Json<KnownError>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let known_error =
+        known_errors::link_incident(problem_id, request.incident_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(known_error)))
+}
@@ -0,0 +1,211 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::incidents::major::{
+    self, Communication, CommunicationCreateset, MajorIncident, MajorIncidentListParams,
+    MajorIncidentSummary, MajorIncidentUpdateset,
+};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(MajorIncidentListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<MajorIncident>,
+            description = "Page of major incidents, latest declared first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::MAJOR_INCIDENTS_TAG
+)]
+pub async fn read_all_major_incidents(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<MajorIncidentListParams>,
+) -> Result<Json<Page<MajorIncident>>, Error> {
+    let page = major::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = MajorIncident,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "The Incident doesn't exist or isn't a major incident."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::MAJOR_INCIDENTS_TAG
+)]
+pub async fn read_one_major_incident(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MajorIncident>, Error> {
+    let major_incident = major::load(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", major_incident);
+
+    Ok(Json(major_incident))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = MajorIncidentUpdateset,
+        description = "Commander and update cadence of the major incident.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = MajorIncident,
+            description = "Incident declared a major incident, or major incident updated.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, references a nonexistent user, or the Incident is resolved and not a major incident yet."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::MAJOR_INCIDENTS_TAG
+)]
+pub async fn update_major_incident(
+    Authorized { principal, .. }: Authorized<can::IncidentsMajor>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<MajorIncidentUpdateset>,
+) -> Result<Json<MajorIncident>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let major_incident = major::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(major_incident))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/summary",
+    responses(
+        (status = OK,
+            body = MajorIncidentSummary,
+            description = "Major incident with its children and related records."
+        ),
+        (status = NOT_FOUND,
+            description = "The Incident doesn't exist or isn't a major incident."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::MAJOR_INCIDENTS_TAG
+)]
+pub async fn read_major_incident_summary(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MajorIncidentSummary>, Error> {
+    let summary = major::load_summary(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", summary);
+
+    Ok(Json(summary))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/communications",
+    responses(
+        (status = OK,
+            body = Vec<Communication>,
+            description = "Updates sent to the stakeholders, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "The Incident doesn't exist or isn't a major incident."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::MAJOR_INCIDENTS_TAG
+)]
+pub async fn read_major_incident_communications(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<Communication>>, Error> {
+    let communications = major::load_communications(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", communications);
+
+    Ok(Json(communications))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/communications",
+    request_body(
+        content = CommunicationCreateset,
+        description = "Update sent to the stakeholders.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = Communication,
+            description = "Update logged. The next one is due after the update cadence.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "The Incident doesn't exist or isn't a major incident."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::MAJOR_INCIDENTS_TAG
+)]
+pub async fn create_major_incident_communication(
+    Authorized { principal, .. }: Authorized<can::IncidentsMajor>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(createset): Json<CommunicationCreateset>,
+) -> Result<(StatusCode, Json<Communication>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let communication =
+        major::create_communication(id, createset, &principal.subject, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(communication)))
+}
@@ -0,0 +1,68 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::priority_matrix::{self, PriorityMatrix, PriorityMatrixUpdateset};
+use tracing::info;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    responses(
+        (status = OK,
+            body = PriorityMatrix,
+            description = "OK"
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PRIORITY_MATRIX_TAG
+)]
+pub async fn read_priority_matrix(
+    State(app_state): State<SharedAppState>,
+) -> Result<Json<PriorityMatrix>, Error> {
+    let matrix = priority_matrix::load(&app_state.db_pool).await?;
+
+    info!("responding with {:?}", matrix);
+
+    Ok(Json(matrix))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "",
+    request_body(
+        content = PriorityMatrixUpdateset,
+        description = "Priority matrix replacing the current one. Incidents take the new priorities and the SLA deadlines of the unresolved ones are recomputed.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = PriorityMatrix,
+            description = "Priority matrix updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or leaves out an impact or urgency still used by Incidents."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PRIORITY_MATRIX_TAG
+)]
+pub async fn update_priority_matrix(
+    Authorized { principal, .. }: Authorized<can::PriorityMatrixManage>,
+    State(app_state): State<SharedAppState>,
+    Json(updateset): Json<PriorityMatrixUpdateset>,
+) -> Result<Json<PriorityMatrix>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let matrix = priority_matrix::update(updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(matrix))
+}
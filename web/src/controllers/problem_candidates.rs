@@ -0,0 +1,155 @@
+use crate::controllers::problems::status_permission;
+use crate::middlewares::authorization::{authorize, can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::problem_candidates::{
+    self, ProblemCandidate, ProblemCandidateListParams,
+};
+use itil_back_db::entities::problems::{Problem, ProblemCreateset};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(ProblemCandidateListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<ProblemCandidate>,
+            description = "Page of problem candidates, latest first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEM_CANDIDATES_TAG
+)]
+pub async fn read_all_problem_candidates(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<ProblemCandidateListParams>,
+) -> Result<Json<Page<ProblemCandidate>>, Error> {
+    let page = problem_candidates::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = ProblemCandidate,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEM_CANDIDATES_TAG
+)]
+pub async fn read_one_problem_candidate(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProblemCandidate>, Error> {
+    let candidate = problem_candidates::load(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", candidate);
+
+    Ok(Json(candidate))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/accept",
+    request_body(
+        content = ProblemCreateset,
+        description = "Problem to create for the Incidents of the candidate.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = Problem,
+            description = "Problem created and related to the Incidents of the candidate.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = CONFLICT,
+            description = "The candidate was accepted or rejected already."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEM_CANDIDATES_TAG
+)]
+pub async fn accept_problem_candidate(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(problem): Json<ProblemCreateset>,
+) -> Result<(StatusCode, Json<Problem>), Error> {
+    if let Some(permission) = problem.status.as_ref().and_then(status_permission) {
+        authorize(&principal, permission, &app_state.db_pool).await?;
+    }
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let problem = problem_candidates::accept(id, problem, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(problem)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/reject",
+    responses(
+        (status = OK,
+            body = ProblemCandidate,
+            description = "Candidate rejected. Its Incidents won't be proposed again.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = CONFLICT,
+            description = "The candidate was accepted or rejected already."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEM_CANDIDATES_TAG
+)]
+pub async fn reject_problem_candidate(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProblemCandidate>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let candidate = problem_candidates::reject(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(candidate))
+}
@@ -1,10 +1,30 @@
+use crate::middlewares::authorization::{authorize, can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
-use axum::{extract::Path, extract::State, http::StatusCode, Json};
-use itil_back_db::entities::problems::{self, Problem, ProblemCreateset, ProblemUpdateset};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::{self, AuditEntity, AuditEntry};
+use itil_back_db::entities::problems::{
+    self, Problem, ProblemCreateset, ProblemListParams, ProblemStatus, ProblemUpdateset,
+};
+use itil_back_db::entities::roles::Permission;
+use itil_back_db::pagination::{Page, PageParams};
 use tracing::info;
 use uuid::Uuid;
 
 pub mod incident_relations;
+pub mod known_error;
+pub mod rca;
+pub mod timeline;
+
+/// Permission needed, besides [`Permission::ProblemsWrite`], to move a Problem to `status`.
+pub(crate) fn status_permission(status: &ProblemStatus) -> Option<Permission> {
+    match status {
+        ProblemStatus::KnownError => Some(Permission::ProblemsKnownError),
+        ProblemStatus::Closed => Some(Permission::ProblemsClose),
+        ProblemStatus::Open | ProblemStatus::Resolved => None,
+    }
+}
 
 #[axum::debug_handler]
 #[utoipa::path(post,
@@ -23,6 +43,9 @@ pub mod incident_relations;
         (status = UNPROCESSABLE_ENTITY,
             description = "Request body didn't pass validations."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -30,20 +53,33 @@ pub mod incident_relations;
     tag = apidoc::PROBLEMS_TAG
 )]
 pub async fn create_problem(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
     State(app_state): State<SharedAppState>,
     Json(problem): Json<ProblemCreateset>,
 ) -> Result<(StatusCode, Json<Problem>), Error> {
-    let problem = problems::create(problem, &app_state.db_pool).await?;
+    if let Some(permission) = problem.status.as_ref().and_then(status_permission) {
+        authorize(&principal, permission, &app_state.db_pool).await?;
+    }
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let problem = problems::create(problem, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(problem)))
 }
 
 #[axum::debug_handler]
 #[utoipa::path(get,
     path = "",
+    params(ProblemListParams, PageParams),
     responses(
         (status = OK,
-            body = Vec<Problem>,
-            description = "List of Problems."
+            body = Page<Problem>,
+            description = "Page of Problems."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
         ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
@@ -53,12 +89,13 @@ pub async fn create_problem(
 )]
 pub async fn read_all_problems(
     State(app_state): State<SharedAppState>,
-) -> Result<Json<Vec<Problem>>, Error> {
-    let problems = problems::load_all(&app_state.db_pool).await?;
+    Query(params): Query<ProblemListParams>,
+) -> Result<Json<Page<Problem>>, Error> {
+    let page = problems::load_page(params, &app_state.db_pool).await?;
 
-    info!("responding with {:?}", problems);
+    info!("responding with {:?}", page);
 
-    Ok(Json(problems))
+    Ok(Json(page))
 }
 
 #[axum::debug_handler]
@@ -86,6 +123,34 @@ pub async fn read_one_problem(
     Ok(Json(problem))
 }
 
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the Problem and its relations, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Problem never existed."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn read_problem_history(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = audit::load_history(AuditEntity::Problem, id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
 #[axum::debug_handler]
 #[utoipa::path(put,
     path = "/{id}",
@@ -106,6 +171,9 @@ pub async fn read_one_problem(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -113,11 +181,17 @@ pub async fn read_one_problem(
     tag = apidoc::PROBLEMS_TAG
 )]
 pub async fn update_problem(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
     Json(problem): Json<ProblemUpdateset>,
 ) -> Result<Json<Problem>, Error> {
-    let problem = problems::update(id, problem, &app_state.db_pool).await?;
+    if let Some(permission) = problem.status.as_ref().and_then(status_permission) {
+        authorize(&principal, permission, &app_state.db_pool).await?;
+    }
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let problem = problems::update(id, problem, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(problem))
 }
 
@@ -131,6 +205,9 @@ pub async fn update_problem(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -138,9 +215,12 @@ pub async fn update_problem(
     tag = apidoc::PROBLEMS_TAG
 )]
 pub async fn delete_problem(
+    Authorized { principal, .. }: Authorized<can::ProblemsDelete>,
     State(app_state): State<SharedAppState>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, Error> {
-    problems::delete(id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    problems::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,5 +1,8 @@
+use crate::middlewares::authorization::{can, Authorized};
 use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
 use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
 use itil_back_db::entities::problems::incident_relations::{self, ProblemIncidentRelation};
 use serde::Deserialize;
 #[cfg(feature = "test-helpers")]
@@ -40,6 +43,9 @@ pub struct UpdateIncidentRelation {
         (status = UNPROCESSABLE_ENTITY,
             description = "Request body didn't pass validations."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -47,12 +53,14 @@ pub struct UpdateIncidentRelation {
     tag = apidoc::PROBLEMS_TAG
 )]
 pub async fn create_problem_incident_relation(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
     State(app_state): State<SharedAppState>,
     Path(problem_id): Path<Uuid>,
     Json(request): Json<CreateIncidentRelation>,
 ) -> Result<(StatusCode, Json<ProblemIncidentRelation>), Error> {
-    let relation =
-        incident_relations::create(problem_id, request.incident_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation = incident_relations::create(problem_id, request.incident_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok((StatusCode::CREATED, Json(relation)))
 }
 
@@ -101,6 +109,9 @@ pub async fn read_all_problem_incident_relations(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -108,17 +119,15 @@ pub async fn read_all_problem_incident_relations(
     tag = apidoc::PROBLEMS_TAG
 )]
 pub async fn update_problem_incident_relation(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
     State(app_state): State<SharedAppState>,
     Path((problem_id, incident_id)): Path<(Uuid, Uuid)>,
     Json(request): Json<UpdateIncidentRelation>,
 ) -> Result<Json<ProblemIncidentRelation>, Error> {
-    let relation = incident_relations::update(
-        problem_id,
-        incident_id,
-        request.description,
-        &app_state.db_pool,
-    )
-    .await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let relation =
+        incident_relations::update(problem_id, incident_id, request.description, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(Json(relation))
 }
 
@@ -132,6 +141,9 @@ pub async fn update_problem_incident_relation(
         (status = NOT_FOUND,
             description = "Record not found in database."
         ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
         (status = INTERNAL_SERVER_ERROR,
             description = "Database error."
         )
@@ -139,9 +151,12 @@ pub async fn update_problem_incident_relation(
     tag = apidoc::PROBLEMS_TAG
 )]
 pub async fn delete_problem_incident_relation(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
     State(app_state): State<SharedAppState>,
     Path((problem_id, incident_id)): Path<(Uuid, Uuid)>,
 ) -> Result<StatusCode, Error> {
-    incident_relations::delete(problem_id, incident_id, &app_state.db_pool).await?;
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    incident_relations::delete(problem_id, incident_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
     Ok(StatusCode::NO_CONTENT)
 }
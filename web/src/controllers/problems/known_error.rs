@@ -0,0 +1,77 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::known_errors::{self, KnownError, KnownErrorUpdateset};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/knownerror",
+    responses(
+        (status = OK,
+            body = KnownError,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist, or the Problem never was a known error."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn read_problem_known_error(
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+) -> Result<Json<KnownError>, Error> {
+    let known_error = known_errors::load(problem_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", known_error);
+
+    Ok(Json(known_error))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/knownerror",
+    request_body(
+        content = KnownErrorUpdateset,
+        description = "Known error data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = KnownError,
+            description = "Known error updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist, or the Problem never was a known error."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or references a nonexistent Configuration Item."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn update_problem_known_error(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+    Json(updateset): Json<KnownErrorUpdateset>,
+) -> Result<Json<KnownError>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let known_error = known_errors::update(problem_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(known_error))
+}
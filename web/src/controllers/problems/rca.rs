@@ -0,0 +1,346 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::problems::rca::{
+    self, IshikawaCause, IshikawaCauseCreateset, IshikawaCauseUpdateset, RCAEvent,
+    RCAEventCreateset, RCAEventUpdateset, RootCause, RootCauseAnalysis, RootCauseUpdateset, Why,
+    WhyCreateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/rca",
+    responses(
+        (status = OK,
+            body = RootCauseAnalysis,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn read_problem_rca(
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+) -> Result<Json<RootCauseAnalysis>, Error> {
+    let analysis = rca::load(problem_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", analysis);
+
+    Ok(Json(analysis))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/rca/whys",
+    request_body(
+        content = Vec<WhyCreateset>,
+        description = "5-Whys chain replacing the current one, in order.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = Vec<Why>,
+            description = "5-Whys chain replaced successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or has too many whys."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn update_problem_whys(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+    Json(whys): Json<Vec<WhyCreateset>>,
+) -> Result<Json<Vec<Why>>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let whys = rca::replace_whys(problem_id, whys, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(whys))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/rca/causes",
+    request_body(
+        content = IshikawaCauseCreateset,
+        description = "Cause to add to the Ishikawa diagram of the Problem.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = IshikawaCause,
+            description = "Cause added successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn create_problem_cause(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+    Json(createset): Json<IshikawaCauseCreateset>,
+) -> Result<(StatusCode, Json<IshikawaCause>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let cause = rca::create_cause(problem_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(cause)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/rca/causes/{cause_id}",
+    request_body(
+        content = IshikawaCauseUpdateset,
+        description = "Cause data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = IshikawaCause,
+            description = "Cause updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn update_problem_cause(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((problem_id, cause_id)): Path<(Uuid, Uuid)>,
+    Json(updateset): Json<IshikawaCauseUpdateset>,
+) -> Result<Json<IshikawaCause>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let cause = rca::update_cause(problem_id, cause_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(cause))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}/rca/causes/{cause_id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Cause deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn delete_problem_cause(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((problem_id, cause_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    rca::delete_cause(problem_id, cause_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/rca/events",
+    request_body(
+        content = RCAEventCreateset,
+        description = "Event to add to the timeline of the Problem.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = RCAEvent,
+            description = "Event added successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or links to a nonexistent Configuration Item, Incident or RFC."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn create_problem_rca_event(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+    Json(createset): Json<RCAEventCreateset>,
+) -> Result<(StatusCode, Json<RCAEvent>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let event = rca::create_event(problem_id, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(event)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/rca/events/{event_id}",
+    request_body(
+        content = RCAEventUpdateset,
+        description = "Event data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RCAEvent,
+            description = "Event updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or links to a nonexistent Configuration Item, Incident or RFC."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn update_problem_rca_event(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((problem_id, event_id)): Path<(Uuid, Uuid)>,
+    Json(updateset): Json<RCAEventUpdateset>,
+) -> Result<Json<RCAEvent>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let event = rca::update_event(problem_id, event_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(event))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}/rca/events/{event_id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Event deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn delete_problem_rca_event(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((problem_id, event_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    rca::delete_event(problem_id, event_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/rca/rootcause",
+    request_body(
+        content = RootCauseUpdateset,
+        description = "Classification of the root cause, replacing the current one.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RootCause,
+            description = "Root cause classified successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or the category doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::PROBLEMS_TAG
+)]
+pub async fn update_problem_root_cause(
+    Authorized { principal, .. }: Authorized<can::ProblemsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(problem_id): Path<Uuid>,
+    Json(updateset): Json<RootCauseUpdateset>,
+) -> Result<Json<RootCause>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let root_cause = rca::classify(problem_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(root_cause))
+}
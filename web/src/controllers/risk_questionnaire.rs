@@ -0,0 +1,70 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::risk_questionnaire::{
+    self, RiskQuestionnaire, RiskQuestionnaireUpdateset,
+};
+use tracing::info;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    responses(
+        (status = OK,
+            body = RiskQuestionnaire,
+            description = "OK"
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::RISK_QUESTIONNAIRE_TAG
+)]
+pub async fn read_risk_questionnaire(
+    State(app_state): State<SharedAppState>,
+) -> Result<Json<RiskQuestionnaire>, Error> {
+    let questionnaire = risk_questionnaire::load(&app_state.db_pool).await?;
+
+    info!("responding with {:?}", questionnaire);
+
+    Ok(Json(questionnaire))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "",
+    request_body(
+        content = RiskQuestionnaireUpdateset,
+        description = "Questionnaire replacing the current one. RFCs assessed already keep their assessment.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RiskQuestionnaire,
+            description = "Questionnaire updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::RISK_QUESTIONNAIRE_TAG
+)]
+pub async fn update_risk_questionnaire(
+    Authorized { principal, .. }: Authorized<can::RiskQuestionnaireManage>,
+    State(app_state): State<SharedAppState>,
+    Json(updateset): Json<RiskQuestionnaireUpdateset>,
+) -> Result<Json<RiskQuestionnaire>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let questionnaire = risk_questionnaire::update(updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(questionnaire))
+}
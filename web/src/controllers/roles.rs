@@ -0,0 +1,299 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::AuditEntry;
+use itil_back_db::entities::roles::{self, Role, RoleAssignment, RoleCreateset, RoleUpdateset};
+use tracing::info;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = RoleCreateset,
+        description = "Role to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = Role,
+            description = "Role created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or a role with that name already exists."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn create_role(
+    Authorized { principal, .. }: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<RoleCreateset>,
+) -> Result<(StatusCode, Json<Role>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let role = roles::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(role)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    responses(
+        (status = OK,
+            body = [Role],
+            description = "All Roles."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn read_all_roles(
+    _: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+) -> Result<Json<Vec<Role>>, Error> {
+    let roles = roles::load_all(&app_state.db_pool).await?;
+
+    info!("responding with {:?}", roles);
+
+    Ok(Json(roles))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{name}",
+    responses(
+        (status = OK,
+            body = Role,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn read_one_role(
+    _: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Role>, Error> {
+    let role = roles::load(&name, &app_state.db_pool).await?;
+    Ok(Json(role))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{name}",
+    request_body(
+        content = RoleUpdateset,
+        description = "Role data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = Role,
+            description = "Role updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn update_role(
+    Authorized { principal, .. }: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(name): Path<String>,
+    Json(updateset): Json<RoleUpdateset>,
+) -> Result<Json<Role>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let role = roles::update(&name, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(role))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{name}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Role deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn delete_role(
+    Authorized { principal, .. }: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    roles::delete(&name, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{name}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the Role, its permissions and assignments, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Role never existed."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn read_role_history(
+    _: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = roles::load_history(&name, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{name}/assignments",
+    responses(
+        (status = OK,
+            body = [RoleAssignment],
+            description = "Subjects the Role is granted to."
+        ),
+        (status = NOT_FOUND,
+            description = "Role not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn read_all_role_assignments(
+    _: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<RoleAssignment>>, Error> {
+    let assignments = roles::load_assignments(&name, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", assignments);
+
+    Ok(Json(assignments))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{name}/assignments/{subject}",
+    responses(
+        (status = OK,
+            body = RoleAssignment,
+            description = "Role granted to the subject.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Role not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn create_role_assignment(
+    Authorized { principal, .. }: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path((name, subject)): Path<(String, String)>,
+) -> Result<Json<RoleAssignment>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let assignment = roles::assign(&name, &subject, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(assignment))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{name}/assignments/{subject}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Role revoked from the subject.",
+        ),
+        (status = NOT_FOUND,
+            description = "Assignment not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROLES_TAG
+)]
+pub async fn delete_role_assignment(
+    Authorized { principal, .. }: Authorized<can::RolesManage>,
+    State(app_state): State<SharedAppState>,
+    Path((name, subject)): Path<(String, String)>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    roles::unassign(&name, &subject, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,173 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::root_cause_categories::{
+    self, RootCauseCategory, RootCauseCategoryCreateset, RootCauseCategoryUpdateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = RootCauseCategoryCreateset,
+        description = "Root cause category to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = RootCauseCategory,
+            description = "Root cause category created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the name is taken."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROOT_CAUSE_CATEGORIES_TAG
+)]
+pub async fn create_root_cause_category(
+    Authorized { principal, .. }: Authorized<can::RootCausesManage>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<RootCauseCategoryCreateset>,
+) -> Result<(StatusCode, Json<RootCauseCategory>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let category = root_cause_categories::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    responses(
+        (status = OK,
+            body = Vec<RootCauseCategory>,
+            description = "The whole taxonomy, sorted by name."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROOT_CAUSE_CATEGORIES_TAG
+)]
+pub async fn read_all_root_cause_categories(
+    State(app_state): State<SharedAppState>,
+) -> Result<Json<Vec<RootCauseCategory>>, Error> {
+    let categories = root_cause_categories::load_all(&app_state.db_pool).await?;
+
+    info!("responding with {:?}", categories);
+
+    Ok(Json(categories))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = RootCauseCategory,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROOT_CAUSE_CATEGORIES_TAG
+)]
+pub async fn read_one_root_cause_category(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RootCauseCategory>, Error> {
+    let category = root_cause_categories::load(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", category);
+
+    Ok(Json(category))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = RootCauseCategoryUpdateset,
+        description = "Root cause category data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = RootCauseCategory,
+            description = "Root cause category updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the name is taken."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROOT_CAUSE_CATEGORIES_TAG
+)]
+pub async fn update_root_cause_category(
+    Authorized { principal, .. }: Authorized<can::RootCausesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<RootCauseCategoryUpdateset>,
+) -> Result<Json<RootCauseCategory>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let category = root_cause_categories::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(category))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Root cause category deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Some Problem is still classified under the category."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::ROOT_CAUSE_CATEGORIES_TAG
+)]
+pub async fn delete_root_cause_category(
+    Authorized { principal, .. }: Authorized<can::RootCausesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    root_cause_categories::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
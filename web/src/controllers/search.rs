@@ -0,0 +1,36 @@
+use crate::{apidoc, error::Error, state::SharedAppState};
+use axum::{extract::Query, extract::State, Json};
+use itil_back_db::entities::search::{self, SearchParams, SearchResult};
+use tracing::info;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(SearchParams),
+    responses(
+        (status = OK,
+            body = Vec<SearchResult>,
+            description = "Incidents, Configuration Items, Problems, RFCs and CI changes matching the search, most relevant first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SEARCH_TAG
+)]
+pub async fn search_entities(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchResult>>, Error> {
+    let results = search::search(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", results);
+
+    Ok(Json(results))
+}
@@ -0,0 +1,182 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::service_catalog::{
+    self, CatalogItem, CatalogItemCreateset, CatalogItemListParams, CatalogItemUpdateset,
+};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = CatalogItemCreateset,
+        description = "Catalog item to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = CatalogItem,
+            description = "Catalog item created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, the name is taken or a team doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_CATALOG_TAG
+)]
+pub async fn create_catalog_item(
+    Authorized { principal, .. }: Authorized<can::CatalogManage>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<CatalogItemCreateset>,
+) -> Result<(StatusCode, Json<CatalogItem>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let item = service_catalog::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(item)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(CatalogItemListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<CatalogItem>,
+            description = "Page of catalog items, sorted by name."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_CATALOG_TAG
+)]
+pub async fn read_all_catalog_items(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<CatalogItemListParams>,
+) -> Result<Json<Page<CatalogItem>>, Error> {
+    let page = service_catalog::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = CatalogItem,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_CATALOG_TAG
+)]
+pub async fn read_one_catalog_item(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CatalogItem>, Error> {
+    let item = service_catalog::load(id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", item);
+
+    Ok(Json(item))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = CatalogItemUpdateset,
+        description = "Catalog item data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = CatalogItem,
+            description = "Catalog item updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, the name is taken or a team doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_CATALOG_TAG
+)]
+pub async fn update_catalog_item(
+    Authorized { principal, .. }: Authorized<can::CatalogManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<CatalogItemUpdateset>,
+) -> Result<Json<CatalogItem>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let item = service_catalog::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(item))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "Catalog item deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "The item has been requested, so it can only be deactivated."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_CATALOG_TAG
+)]
+pub async fn delete_catalog_item(
+    Authorized { principal, .. }: Authorized<can::CatalogManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    service_catalog::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,184 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::audit::{self, AuditEntity, AuditEntry};
+use itil_back_db::entities::service_requests::{
+    self, ServiceRequest, ServiceRequestCreateset, ServiceRequestListParams,
+    ServiceRequestUpdateset,
+};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+/// Controllers for the approval of service requests.
+pub mod approvals;
+/// Controllers for the tasks fulfilling service requests.
+pub mod tasks;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = ServiceRequestCreateset,
+        description = "Request for a catalog item to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = ServiceRequest,
+            description = "Service request created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, the answers don't fill in the form of the item, the item is inactive, or it or the requester don't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn create_service_request(
+    Authorized { principal, .. }: Authorized<can::RequestsWrite>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<ServiceRequestCreateset>,
+) -> Result<(StatusCode, Json<ServiceRequest>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let request = service_requests::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(request)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(ServiceRequestListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<ServiceRequest>,
+            description = "Page of service requests, oldest first."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn read_all_service_requests(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<ServiceRequestListParams>,
+) -> Result<Json<Page<ServiceRequest>>, Error> {
+    let page = service_requests::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = ServiceRequest,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn read_one_service_request(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ServiceRequest>, Error> {
+    let request = service_requests::load(id, &app_state.db_pool).await?;
+    Ok(Json(request))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/history",
+    responses(
+        (status = OK,
+            body = Vec<AuditEntry>,
+            description = "Changes to the service request, its approvals and tasks, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Service request never existed."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn read_service_request_history(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<AuditEntry>>, Error> {
+    let history = audit::load_history(AuditEntity::Request, id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", history);
+
+    Ok(Json(history))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = ServiceRequestUpdateset,
+        description = "Service request data to update in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = ServiceRequest,
+            description = "Service request updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = CONFLICT,
+            description = "The status can't be reached by hand from the current one."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations, or the answers don't fill in the form of the item or change after the request left pendingapproval."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn update_service_request(
+    Authorized { principal, .. }: Authorized<can::RequestsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<ServiceRequestUpdateset>,
+) -> Result<Json<ServiceRequest>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let request = service_requests::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(request))
+}
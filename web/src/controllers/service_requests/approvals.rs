@@ -0,0 +1,79 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::service_requests::approvals::{
+    self, ServiceRequestApproval, ServiceRequestApprovalCreateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "/{id}/approvals",
+    request_body(
+        content = ServiceRequestApprovalCreateset,
+        description = "Decision of the caller, a member of the approval group of the catalog item, on the request. An approval starts the fulfilment of the request, and a rejection rejects it.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = ServiceRequestApproval,
+            description = "Decision recorded successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission, isn't a member of the approval group, or the request isn't pending approval."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn create_service_request_approval(
+    Authorized { principal, .. }: Authorized<can::RequestsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path(request_id): Path<Uuid>,
+    Json(createset): Json<ServiceRequestApprovalCreateset>,
+) -> Result<(StatusCode, Json<ServiceRequestApproval>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let approval = approvals::create(request_id, &principal.subject, createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(approval)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/approvals",
+    responses(
+        (status = OK,
+            body = Vec<ServiceRequestApproval>,
+            description = "Decisions on the request, oldest first."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn read_all_service_request_approvals(
+    State(app_state): State<SharedAppState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<Vec<ServiceRequestApproval>>, Error> {
+    let approvals = approvals::load_all(request_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", approvals);
+
+    Ok(Json(approvals))
+}
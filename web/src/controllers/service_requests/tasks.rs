@@ -0,0 +1,82 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::service_requests::tasks::{
+    self, ServiceRequestTask, ServiceRequestTaskUpdateset,
+};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/tasks",
+    responses(
+        (status = OK,
+            body = Vec<ServiceRequestTask>,
+            description = "Tasks fulfilling the request, in order. Empty until the request is approved."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn read_all_service_request_tasks(
+    State(app_state): State<SharedAppState>,
+    Path(request_id): Path<Uuid>,
+) -> Result<Json<Vec<ServiceRequestTask>>, Error> {
+    let tasks = tasks::load_all(request_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", tasks);
+
+    Ok(Json(tasks))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/tasks/{task_id}",
+    request_body(
+        content = ServiceRequestTaskUpdateset,
+        description = "Task data to update in the database. The request is fulfilled once its last task is done or skipped.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = ServiceRequestTask,
+            description = "Task updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = CONFLICT,
+            description = "The status can't be reached from the current one."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the assignee doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission, or the request isn't in fulfilment."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SERVICE_REQUESTS_TAG
+)]
+pub async fn update_service_request_task(
+    Authorized { principal, .. }: Authorized<can::RequestsWrite>,
+    State(app_state): State<SharedAppState>,
+    Path((request_id, task_id)): Path<(Uuid, Uuid)>,
+    Json(updateset): Json<ServiceRequestTaskUpdateset>,
+) -> Result<Json<ServiceRequestTask>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let task = tasks::update(request_id, task_id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(task))
+}
@@ -0,0 +1,179 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::Query, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::sla_policies::{
+    self, SlaPolicy, SlaPolicyCreateset, SlaPolicyListParams, SlaPolicyUpdateset,
+};
+use itil_back_db::pagination::{Page, PageParams};
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(post,
+    path = "",
+    request_body(
+        content = SlaPolicyCreateset,
+        description = "SLA policy to create in the database.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = CREATED,
+            body = SlaPolicy,
+            description = "SLA policy created successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the name is taken."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SLA_POLICIES_TAG
+)]
+pub async fn create_sla_policy(
+    Authorized { principal, .. }: Authorized<can::SlaPoliciesManage>,
+    State(app_state): State<SharedAppState>,
+    Json(createset): Json<SlaPolicyCreateset>,
+) -> Result<(StatusCode, Json<SlaPolicy>), Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let policy = sla_policies::create(createset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok((StatusCode::CREATED, Json(policy)))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "",
+    params(SlaPolicyListParams, PageParams),
+    responses(
+        (status = OK,
+            body = Page<SlaPolicy>,
+            description = "Page of SLA policies."
+        ),
+        (status = BAD_REQUEST,
+            description = "Query parameters couldn't be parsed."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Query parameters didn't pass validations."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SLA_POLICIES_TAG
+)]
+pub async fn read_all_sla_policies(
+    State(app_state): State<SharedAppState>,
+    Query(params): Query<SlaPolicyListParams>,
+) -> Result<Json<Page<SlaPolicy>>, Error> {
+    let page = sla_policies::load_page(params, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", page);
+
+    Ok(Json(page))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}",
+    responses(
+        (status = OK,
+            body = SlaPolicy,
+            description = "OK"
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SLA_POLICIES_TAG
+)]
+pub async fn read_one_sla_policy(
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SlaPolicy>, Error> {
+    let policy = sla_policies::load(id, &app_state.db_pool).await?;
+    Ok(Json(policy))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}",
+    request_body(
+        content = SlaPolicyUpdateset,
+        description = "SLA policy data to update in the database. The deadlines of the unresolved Incidents under the policy are recomputed.",
+        content_type = "application/json",
+    ),
+    responses(
+        (status = OK,
+            body = SlaPolicy,
+            description = "SLA policy updated successfully.",
+            content_type = "application/json"
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "Request body didn't pass validations or the name is taken."
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SLA_POLICIES_TAG
+)]
+pub async fn update_sla_policy(
+    Authorized { principal, .. }: Authorized<can::SlaPoliciesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+    Json(updateset): Json<SlaPolicyUpdateset>,
+) -> Result<Json<SlaPolicy>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let policy = sla_policies::update(id, updateset, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(policy))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "SLA policy deleted successfully.",
+        ),
+        (status = NOT_FOUND,
+            description = "Record not found in database."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "SLA policy still applies to Incidents."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::SLA_POLICIES_TAG
+)]
+pub async fn delete_sla_policy(
+    Authorized { principal, .. }: Authorized<can::SlaPoliciesManage>,
+    State(app_state): State<SharedAppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    sla_policies::delete(id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,102 @@
+use crate::middlewares::authorization::{can, Authorized};
+use crate::{apidoc, error::Error, state::SharedAppState};
+use anyhow::Context;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use itil_back_db::audited_transaction;
+use itil_back_db::entities::teams::members::{self, TeamMember};
+use itil_back_db::entities::users::User;
+use tracing::info;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+#[utoipa::path(put,
+    path = "/{id}/members/{user_id}",
+    responses(
+        (status = OK,
+            body = TeamMember,
+            description = "User added to the Team.",
+            content_type = "application/json"
+        ),
+        (status = NOT_FOUND,
+            description = "Team doesn't exist."
+        ),
+        (status = UNPROCESSABLE_ENTITY,
+            description = "User doesn't exist."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::TEAMS_TAG
+)]
+pub async fn create_team_member(
+    Authorized { principal, .. }: Authorized<can::TeamsManage>,
+    State(app_state): State<SharedAppState>,
+    Path((team_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<TeamMember>, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    let member = members::create(team_id, user_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(Json(member))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(get,
+    path = "/{id}/members",
+    responses(
+        (status = OK,
+            body = Vec<User>,
+            description = "Members of the Team."
+        ),
+        (status = NOT_FOUND,
+            description = "Resource doesn't exist."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::TEAMS_TAG
+)]
+pub async fn read_all_team_members(
+    State(app_state): State<SharedAppState>,
+    Path(team_id): Path<Uuid>,
+) -> Result<Json<Vec<User>>, Error> {
+    let members = members::load_all(team_id, &app_state.db_pool).await?;
+
+    info!("responding with {:?}", members);
+
+    Ok(Json(members))
+}
+
+#[axum::debug_handler]
+#[utoipa::path(delete,
+    path = "/{id}/members/{user_id}",
+    responses(
+        (status = NO_CONTENT,
+            description = "User removed from the Team. They are unassigned from its Incidents.",
+        ),
+        (status = NOT_FOUND,
+            description = "User isn't a member of the Team."
+        ),
+        (status = FORBIDDEN,
+            description = "Caller lacks the required permission."
+        ),
+        (status = INTERNAL_SERVER_ERROR,
+            description = "Database error."
+        )
+    ),
+    tag = apidoc::TEAMS_TAG
+)]
+pub async fn delete_team_member(
+    Authorized { principal, .. }: Authorized<can::TeamsManage>,
+    State(app_state): State<SharedAppState>,
+    Path((team_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, Error> {
+    let mut tx = audited_transaction(&app_state.db_pool, &principal.subject).await?;
+    members::delete(team_id, user_id, &mut *tx).await?;
+    tx.commit().await.context("Failed to commit transaction")?;
+    Ok(StatusCode::NO_CONTENT)
+}
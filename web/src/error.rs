@@ -1,4 +1,10 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use itil_back_db::entities::roles::Permission;
+use serde::Serialize;
 use std::fmt::{Debug, Display};
 
 /// Error type that encapsultes anything that can go wrong
@@ -9,6 +15,12 @@ pub enum Error {
     /// Errors that can occur as a result of a data layer operation.
     #[error("Database error")]
     Database(#[from] itil_back_db::Error),
+    /// The request lacks a valid bearer token.
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// The caller isn't granted the permission required for the request.
+    #[error("Forbidden")]
+    Forbidden(Permission),
     /// Any other error. Handled as an Internal Server Error.
     #[error("Error: {0}")]
     Other(#[from] anyhow::Error),
@@ -26,12 +38,65 @@ impl IntoResponse for Error {
             Error::Database(itil_back_db::Error::ValidationError(e)) => {
                 validation_error(e).into_response()
             }
+            Error::Database(itil_back_db::Error::InvalidTransition { from, to, allowed }) => (
+                StatusCode::CONFLICT,
+                Json(InvalidTransitionBody {
+                    error: format!("Can't move from {from} to {to}"),
+                    from,
+                    to,
+                    allowed,
+                }),
+            )
+                .into_response(),
+            Error::Database(itil_back_db::Error::NotAllowed(reason)) => (
+                StatusCode::FORBIDDEN,
+                Json(NotAllowedBody {
+                    error: String::from(reason),
+                }),
+            )
+                .into_response(),
             Error::Database(itil_back_db::Error::DbError(e)) => internal_error(e).into_response(),
+            Error::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+            )
+                .into_response(),
+            Error::Forbidden(permission) => (
+                StatusCode::FORBIDDEN,
+                Json(ForbiddenBody {
+                    error: format!("Missing permission {}", permission.as_str()),
+                    permission,
+                }),
+            )
+                .into_response(),
             Error::Other(e) => internal_error(e).into_response(),
         }
     }
 }
 
+/// Body of 403 Forbidden responses.
+#[derive(Serialize)]
+struct ForbiddenBody {
+    error: String,
+    permission: Permission,
+}
+
+/// Body of 403 Forbidden responses to modifications the caller isn't allowed to make
+/// regardless of their permissions.
+#[derive(Serialize)]
+struct NotAllowedBody {
+    error: String,
+}
+
+/// Body of 409 Conflict responses to status changes not allowed by the lifecycle of an entity.
+#[derive(Serialize)]
+struct InvalidTransitionBody {
+    error: String,
+    from: &'static str,
+    to: &'static str,
+    allowed: Vec<&'static str>,
+}
+
 /// Helper function to create an internal error response while
 /// taking care to log the error itself.
 fn internal_error<E>(e: E) -> StatusCode
@@ -0,0 +1,38 @@
+use chrono::Duration;
+use itil_back_config::ProblemDetectionConfig;
+use itil_back_db::entities::problem_candidates::{self, DetectionThresholds};
+use itil_back_db::DbPool;
+use tokio::time::{self, MissedTickBehavior};
+use tracing::{error, info};
+
+/// Spawns the problem detection (see [`problem_candidates::detect`]), which runs every
+/// `config.interval_minutes` for as long as the application does.
+pub fn spawn_problem_detection(db_pool: DbPool, config: ProblemDetectionConfig) {
+    if config.interval_minutes == 0 {
+        info!("Problem detection is disabled");
+        return;
+    }
+
+    let thresholds = DetectionThresholds {
+        window: Duration::hours(config.window_hours.into()),
+        min_incidents: config.min_incidents as usize,
+        min_similarity: config.min_similarity,
+    };
+    let period = std::time::Duration::from_secs(u64::from(config.interval_minutes) * 60);
+
+    tokio::spawn(async move {
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match problem_candidates::detect(thresholds, &db_pool).await {
+                Ok(candidates) => info!("Proposed {} problem candidates", candidates.len()),
+                Err(e) => error!(
+                    error.msg = %e,
+                    error.error_chain = ?e,
+                    "Problem detection failed"
+                ),
+            }
+        }
+    });
+}
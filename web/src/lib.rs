@@ -14,6 +14,8 @@ pub mod apidoc;
 pub mod controllers;
 /// Contains the application's error type and related conversion implementation.
 pub mod error;
+/// Background jobs that run alongside the server.
+pub mod jobs;
 /// Middlewares that incoming requests are passed through before being passed to [`controllers`].
 pub mod middlewares;
 /// Contains the application's route definitions.
@@ -30,13 +32,15 @@ pub mod state;
 /// 1. Determine the environment the application is running in (see [`itil_back_config::get_env`])
 /// 2. Load the configuration (see [`itil_back_config::load_config`])
 /// 3. Initialize the application state (see [`state::init_app_state`])
-/// 4. Initialize the application's router (see [`routes::init_routes`])
-/// 5. Boot the application and start listening for requests on the configured interface and port
+/// 4. Start the background jobs (see [`jobs`])
+/// 5. Initialize the application's router (see [`routes::init_routes`])
+/// 6. Boot the application and start listening for requests on the configured interface and port
 pub async fn run() -> anyhow::Result<()> {
     let env = get_env().context("Cannot get environment!")?;
     let config: Config = load_config(&env).context("Cannot load config!")?;
 
     let app_state = state::init_app_state(config.clone()).await;
+    jobs::spawn_problem_detection(app_state.db_pool.clone(), config.problem_detection.clone());
     let app = routes::init_routes(app_state);
 
     let addr = config.server.addr();
@@ -0,0 +1,91 @@
+use crate::{error::Error, state::SharedAppState};
+use axum::body::Body;
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{self, request::Parts, Request},
+    middleware::Next,
+    response::Response,
+};
+use itil_back_config::AuthConfig;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+/// Claims carried by the JWT bearer tokens.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Claims {
+    /// Identifier of the authenticated user.
+    pub sub: String,
+    /// Expiration time as a UNIX timestamp.
+    pub exp: u64,
+    /// Issuer of the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+}
+
+/// The authenticated caller of a request.
+///
+/// Handlers behind the [`authenticate`] middleware can take it as an extractor:
+/// ```
+/// pub async fn handler(principal: Principal) -> String {
+///     principal.subject
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Principal {
+    /// Identifier of the user, taken from the `sub` claim of the token.
+    pub subject: String,
+}
+
+impl<S> FromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or(Error::Unauthorized)
+    }
+}
+
+/// Verify a bearer token and return its claims.
+pub fn verify_token(token: &str, config: &AuthConfig) -> Result<Claims, Error> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    if let Some(issuer) = &config.jwt_issuer {
+        validation.set_issuer(&[issuer]);
+    }
+
+    let key = DecodingKey::from_secret(config.jwt_secret.as_bytes());
+    match decode::<Claims>(token, &key, &validation) {
+        Ok(data) => Ok(data.claims),
+        Err(e) => {
+            tracing::info!(err.msg = %e, "Rejected bearer token");
+            Err(Error::Unauthorized)
+        }
+    }
+}
+
+/// Reject requests without a valid bearer token and make the [`Principal`] available to handlers.
+#[tracing::instrument(skip_all)]
+pub async fn authenticate(
+    State(app_state): State<SharedAppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, Error> {
+    let token = req
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(Error::Unauthorized)?;
+
+    let claims = verify_token(token, &app_state.auth)?;
+    req.extensions_mut().insert(Principal {
+        subject: claims.sub,
+    });
+
+    Ok(next.run(req).await)
+}
@@ -1 +1,4 @@
-
+/// Authentication of API clients via JWT bearer tokens.
+pub mod auth;
+/// Authorization of authenticated callers via roles and permissions.
+pub mod authorization;
@@ -1,14 +1,22 @@
 use crate::{
+    apidoc,
     apidoc::ApiDoc,
     controllers::{
+        change_advisory_board,
         changes::{self},
-        configuration, health,
+        configuration, freeze_periods, health,
         incidents::{self},
+        knowledge_articles::{self},
+        knowledge_categories, known_errors, major_incidents, priority_matrix, problem_candidates,
         problems::{self},
+        risk_questionnaire, roles, root_cause_categories, search, service_catalog,
+        service_requests::{self},
+        sla_policies, teams, users,
     },
+    middlewares::auth,
     state::AppState,
 };
-use axum::Router;
+use axum::{middleware, Router};
 use tower_http::cors::CorsLayer;
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
@@ -20,16 +28,41 @@ use std::sync::Arc;
 /// Initializes the application's routes.
 ///
 /// This function maps paths (e.g. "/greet") and HTTP methods (e.g. "GET") to functions in [`crate::controllers`] as well as includes middlewares defined in [`crate::middlewares`] into the routing layer (see [`axum::Router`]).
+///
+/// Every route requires a valid bearer token (see [`auth::authenticate`]), except for the health check and the API documentation.
+/// Controllers that modify records additionally require a permission of the caller (see [`crate::middlewares::authorization`]).
 pub fn init_routes(app_state: AppState) -> Router {
     let shared_app_state = Arc::new(app_state);
-    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(health::health))
+    let (router, mut api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
         .nest("/api/incidents", incidents_router())
+        .nest("/api/majorincidents", major_incidents_router())
         .nest("/api/configitems", configitems_router())
         .nest("/api/problems", problems_router())
+        .nest("/api/knownerrors", known_errors_router())
+        .nest("/api/articles", knowledge_articles_router())
+        .nest("/api/articlecategories", knowledge_categories_router())
+        .nest("/api/problemcandidates", problem_candidates_router())
+        .nest("/api/rootcausecategories", root_cause_categories_router())
         .nest("/api/changes", changes_router())
+        .nest("/api/roles", roles_router())
+        .nest("/api/users", users_router())
+        .nest("/api/teams", teams_router())
+        .nest("/api/slapolicies", sla_policies_router())
+        .nest("/api/prioritymatrix", priority_matrix_router())
+        .nest("/api/cab", cab_router())
+        .nest("/api/freezeperiods", freeze_periods_router())
+        .nest("/api/riskquestionnaire", risk_questionnaire_router())
+        .nest("/api/catalog", service_catalog_router())
+        .nest("/api/requests", service_requests_router())
+        .nest("/api/search", search_router())
+        .route_layer(middleware::from_fn_with_state(
+            shared_app_state.clone(),
+            auth::authenticate,
+        ))
+        .routes(routes!(health::health))
         .with_state(shared_app_state)
         .split_for_parts();
+    apidoc::add_unauthorized_responses(&mut api);
 
     let cors = CorsLayer::permissive();
     router
@@ -52,6 +85,8 @@ fn configitems_router() -> OpenApiRouter<Arc<AppState>> {
             configuration::update_ci,
             configuration::delete_ci,
         ))
+        .routes(routes!(configuration::read_ci_history,))
+        .routes(routes!(configuration::read_ci_impact,))
         .routes(routes!(
             configuration::changes::create_ci_change,
             configuration::changes::read_all_ci_changes,
@@ -61,6 +96,16 @@ fn configitems_router() -> OpenApiRouter<Arc<AppState>> {
             configuration::changes::update_ci_change,
             configuration::changes::delete_ci_change,
         ))
+        .routes(routes!(
+            configuration::relations::create_ci_relation,
+            configuration::relations::read_all_ci_relations,
+        ))
+        .routes(routes!(
+            configuration::relations::read_one_ci_relation,
+            configuration::relations::update_ci_relation,
+            configuration::relations::delete_ci_relation,
+        ))
+        .routes(routes!(configuration::relations::read_ci_graph,))
 }
 
 fn incidents_router() -> OpenApiRouter<Arc<AppState>> {
@@ -74,6 +119,8 @@ fn incidents_router() -> OpenApiRouter<Arc<AppState>> {
             incidents::update_incident,
             incidents::delete_incident,
         ))
+        .routes(routes!(incidents::read_incident_history,))
+        .routes(routes!(incidents::read_incident_sla,))
         .routes(routes!(
             incidents::ci_relations::create_incident_ci_relation,
             incidents::ci_relations::read_all_incident_ci_relations,
@@ -83,6 +130,30 @@ fn incidents_router() -> OpenApiRouter<Arc<AppState>> {
             incidents::ci_relations::delete_incident_ci_relation,
         ))
         .routes(routes!(incidents::read_all_incidents_by_ci,))
+        .routes(routes!(
+            incidents::timeline::create_incident_timeline_entry,
+            incidents::timeline::read_incident_timeline,
+        ))
+        .routes(routes!(incidents::timeline::update_incident_timeline_entry,))
+        .routes(routes!(
+            incidents::children::create_incident_children,
+            incidents::children::read_incident_children,
+        ))
+        .routes(routes!(incidents::merge::merge_incident,))
+}
+
+fn major_incidents_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(major_incidents::read_all_major_incidents,))
+        .routes(routes!(
+            major_incidents::read_one_major_incident,
+            major_incidents::update_major_incident,
+        ))
+        .routes(routes!(major_incidents::read_major_incident_summary,))
+        .routes(routes!(
+            major_incidents::create_major_incident_communication,
+            major_incidents::read_major_incident_communications,
+        ))
 }
 
 fn problems_router() -> OpenApiRouter<Arc<AppState>> {
@@ -96,6 +167,24 @@ fn problems_router() -> OpenApiRouter<Arc<AppState>> {
             problems::update_problem,
             problems::delete_problem,
         ))
+        .routes(routes!(problems::read_problem_history,))
+        .routes(routes!(problems::rca::read_problem_rca,))
+        .routes(routes!(problems::rca::update_problem_whys,))
+        .routes(routes!(problems::rca::create_problem_cause,))
+        .routes(routes!(
+            problems::rca::update_problem_cause,
+            problems::rca::delete_problem_cause,
+        ))
+        .routes(routes!(problems::rca::create_problem_rca_event,))
+        .routes(routes!(
+            problems::rca::update_problem_rca_event,
+            problems::rca::delete_problem_rca_event,
+        ))
+        .routes(routes!(problems::rca::update_problem_root_cause,))
+        .routes(routes!(
+            problems::known_error::read_problem_known_error,
+            problems::known_error::update_problem_known_error,
+        ))
         .routes(routes!(
             problems::incident_relations::create_problem_incident_relation,
             problems::incident_relations::read_all_problem_incident_relations,
@@ -104,6 +193,72 @@ fn problems_router() -> OpenApiRouter<Arc<AppState>> {
             problems::incident_relations::update_problem_incident_relation,
             problems::incident_relations::delete_problem_incident_relation,
         ))
+        .routes(routes!(
+            problems::timeline::create_problem_timeline_entry,
+            problems::timeline::read_problem_timeline,
+        ))
+        .routes(routes!(problems::timeline::update_problem_timeline_entry,))
+}
+
+fn known_errors_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(known_errors::search_known_errors,))
+        .routes(routes!(known_errors::link_known_error_incident,))
+}
+
+fn knowledge_articles_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            knowledge_articles::create_knowledge_article,
+            knowledge_articles::read_all_knowledge_articles,
+        ))
+        .routes(routes!(knowledge_articles::search_knowledge_articles,))
+        .routes(routes!(
+            knowledge_articles::read_one_knowledge_article,
+            knowledge_articles::update_knowledge_article,
+            knowledge_articles::delete_knowledge_article,
+        ))
+        .routes(routes!(knowledge_articles::read_knowledge_article_history,))
+        .routes(routes!(
+            knowledge_articles::versions::read_all_knowledge_article_versions,
+        ))
+        .routes(routes!(
+            knowledge_articles::versions::read_one_knowledge_article_version,
+        ))
+}
+
+fn knowledge_categories_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            knowledge_categories::create_knowledge_category,
+            knowledge_categories::read_all_knowledge_categories,
+        ))
+        .routes(routes!(
+            knowledge_categories::read_one_knowledge_category,
+            knowledge_categories::update_knowledge_category,
+            knowledge_categories::delete_knowledge_category,
+        ))
+}
+
+fn problem_candidates_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(problem_candidates::read_all_problem_candidates,))
+        .routes(routes!(problem_candidates::read_one_problem_candidate,))
+        .routes(routes!(problem_candidates::accept_problem_candidate,))
+        .routes(routes!(problem_candidates::reject_problem_candidate,))
+}
+
+fn root_cause_categories_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            root_cause_categories::create_root_cause_category,
+            root_cause_categories::read_all_root_cause_categories,
+        ))
+        .routes(routes!(
+            root_cause_categories::read_one_root_cause_category,
+            root_cause_categories::update_root_cause_category,
+            root_cause_categories::delete_root_cause_category,
+        ))
 }
 
 fn changes_router() -> OpenApiRouter<Arc<AppState>> {
@@ -114,11 +269,34 @@ fn changes_router() -> OpenApiRouter<Arc<AppState>> {
             changes::update_rfc,
             changes::delete_rfc,
         ))
+        .routes(routes!(changes::read_rfc_history,))
+        .routes(routes!(changes::calendar::read_change_calendar,))
+        .routes(routes!(changes::ci_changes::read_all_rfc_ci_changes,))
+        .routes(routes!(
+            changes::ci_relations::create_rfc_ci_relation,
+            changes::ci_relations::read_all_rfc_ci_relations,
+        ))
+        .routes(routes!(changes::ci_relations::delete_rfc_ci_relation,))
+        .routes(routes!(changes::conflicts::read_rfc_conflicts,))
+        .routes(routes!(
+            changes::risk::read_rfc_risk_assessment,
+            changes::risk::update_rfc_risk_assessment,
+        ))
+        .routes(routes!(
+            changes::freeze_overrides::read_rfc_freeze_override,
+            changes::freeze_overrides::update_rfc_freeze_override,
+            changes::freeze_overrides::delete_rfc_freeze_override,
+        ))
+        .routes(routes!(
+            changes::reviews::read_rfc_review,
+            changes::reviews::update_rfc_review,
+        ))
         .routes(routes!(
             changes::incident_relations::create_rfc_incident_relation,
             changes::incident_relations::read_all_rfc_incident_relations,
         ))
         .routes(routes!(
+            changes::incident_relations::update_rfc_incident_relation,
             changes::incident_relations::delete_rfc_incident_relation,
         ))
         .routes(routes!(
@@ -128,4 +306,141 @@ fn changes_router() -> OpenApiRouter<Arc<AppState>> {
         .routes(routes!(
             changes::problem_relations::delete_rfc_problem_relation,
         ))
+        .routes(routes!(
+            changes::timeline::create_rfc_timeline_entry,
+            changes::timeline::read_rfc_timeline,
+        ))
+        .routes(routes!(changes::timeline::update_rfc_timeline_entry,))
+        .routes(routes!(
+            changes::approvals::create_rfc_approval,
+            changes::approvals::read_all_rfc_approvals,
+        ))
+}
+
+fn roles_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(roles::create_role, roles::read_all_roles,))
+        .routes(routes!(
+            roles::read_one_role,
+            roles::update_role,
+            roles::delete_role,
+        ))
+        .routes(routes!(roles::read_role_history,))
+        .routes(routes!(roles::read_all_role_assignments,))
+        .routes(routes!(
+            roles::create_role_assignment,
+            roles::delete_role_assignment,
+        ))
+}
+
+fn users_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(users::create_user, users::read_all_users,))
+        .routes(routes!(
+            users::read_one_user,
+            users::update_user,
+            users::delete_user,
+        ))
+}
+
+fn teams_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(teams::create_team, teams::read_all_teams,))
+        .routes(routes!(
+            teams::read_one_team,
+            teams::update_team,
+            teams::delete_team,
+        ))
+        .routes(routes!(teams::members::read_all_team_members,))
+        .routes(routes!(
+            teams::members::create_team_member,
+            teams::members::delete_team_member,
+        ))
+}
+
+fn sla_policies_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            sla_policies::create_sla_policy,
+            sla_policies::read_all_sla_policies,
+        ))
+        .routes(routes!(
+            sla_policies::read_one_sla_policy,
+            sla_policies::update_sla_policy,
+            sla_policies::delete_sla_policy,
+        ))
+}
+
+fn priority_matrix_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new().routes(routes!(
+        priority_matrix::read_priority_matrix,
+        priority_matrix::update_priority_matrix,
+    ))
+}
+
+fn risk_questionnaire_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new().routes(routes!(
+        risk_questionnaire::read_risk_questionnaire,
+        risk_questionnaire::update_risk_questionnaire,
+    ))
+}
+
+fn cab_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new().routes(routes!(
+        change_advisory_board::read_cab,
+        change_advisory_board::update_cab,
+    ))
+}
+
+fn freeze_periods_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            freeze_periods::create_freeze_period,
+            freeze_periods::read_all_freeze_periods,
+        ))
+        .routes(routes!(
+            freeze_periods::read_one_freeze_period,
+            freeze_periods::update_freeze_period,
+            freeze_periods::delete_freeze_period,
+        ))
+}
+
+fn service_catalog_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            service_catalog::create_catalog_item,
+            service_catalog::read_all_catalog_items,
+        ))
+        .routes(routes!(
+            service_catalog::read_one_catalog_item,
+            service_catalog::update_catalog_item,
+            service_catalog::delete_catalog_item,
+        ))
+}
+
+fn service_requests_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(
+            service_requests::create_service_request,
+            service_requests::read_all_service_requests,
+        ))
+        .routes(routes!(
+            service_requests::read_one_service_request,
+            service_requests::update_service_request,
+        ))
+        .routes(routes!(service_requests::read_service_request_history,))
+        .routes(routes!(
+            service_requests::approvals::create_service_request_approval,
+            service_requests::approvals::read_all_service_request_approvals,
+        ))
+        .routes(routes!(
+            service_requests::tasks::read_all_service_request_tasks,
+        ))
+        .routes(routes!(
+            service_requests::tasks::update_service_request_task,
+        ))
+}
+
+fn search_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new().routes(routes!(search::search_entities))
 }
@@ -1,4 +1,4 @@
-use itil_back_config::Config;
+use itil_back_config::{AuthConfig, Config, TimelineConfig};
 use itil_back_db::{connect_pool, DbPool};
 use std::sync::Arc;
 
@@ -6,6 +6,10 @@ use std::sync::Arc;
 pub struct AppState {
     /// The database pool that's used to get a connection to the application's database (see [`itil_back_db::DbPool`]).
     pub db_pool: DbPool,
+    /// The settings used to authenticate API clients (see [`itil_back_config::AuthConfig`]).
+    pub auth: AuthConfig,
+    /// The settings of the timelines of Incidents, Problems and RFCs (see [`itil_back_config::TimelineConfig`]).
+    pub timeline: TimelineConfig,
 }
 
 /// The application's state as it is shared across the application, e.g. in controllers and middlewares.
@@ -21,5 +25,9 @@ pub async fn init_app_state(config: Config) -> AppState {
         .await
         .expect("Could not connect to database!");
 
-    AppState { db_pool }
+    AppState {
+        db_pool,
+        auth: config.auth,
+        timeline: config.timeline,
+    }
 }
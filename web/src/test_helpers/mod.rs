@@ -1,3 +1,4 @@
+use crate::middlewares::auth::Claims;
 use crate::routes::init_routes;
 use crate::state::AppState;
 use axum::{
@@ -6,15 +7,23 @@ use axum::{
     response::Response,
     Router,
 };
-use hyper::header::{HeaderMap, HeaderName};
-use itil_back_config::{load_config, Config, Environment};
+use hyper::header::{self, HeaderMap, HeaderName};
+use itil_back_config::{load_config, AuthConfig, Config, Environment};
 use itil_back_db::{
+    entities::roles,
     test_helpers::{setup_db, teardown_db},
     DbPool,
 };
+use jsonwebtoken::{encode, EncodingKey, Header};
 use std::cell::OnceCell;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tower::ServiceExt;
 
+/// Subject of the token that [`TestApp`] sends by default.
+///
+/// It is granted the `admin` role, so it holds every permission.
+pub const TEST_SUBJECT: &str = "tester";
+
 /// A request that a test sends to the application.
 ///
 /// TestRequests are constructed via the test context (see[`DbTestContext`]).
@@ -73,6 +82,21 @@ impl TestRequest {
         self
     }
 
+    /// Sends the request with the passed bearer token instead of the one for [`TEST_SUBJECT`].
+    ///
+    /// Valid tokens are signed with the secret configured for the test environment (see [`DbTestContext::token_for`]).
+    #[allow(unused)]
+    pub fn token(self, token: &str) -> Self {
+        self.header(header::AUTHORIZATION, &format!("Bearer {}", token))
+    }
+
+    /// Sends the request without a bearer token.
+    #[allow(unused)]
+    pub fn unauthenticated(mut self) -> Self {
+        self.headers.remove(header::AUTHORIZATION);
+        self
+    }
+
     /// Sets the body for the request.
     ///
     /// Example:
@@ -122,6 +146,42 @@ impl RouterExt for Router {
     }
 }
 
+/// The application under test.
+///
+/// Requests made through it are authenticated as [`TEST_SUBJECT`] unless stated otherwise (see [`TestRequest::token`] and [`TestRequest::unauthenticated`]).
+#[derive(Clone)]
+pub struct TestApp {
+    router: Router,
+    token: String,
+}
+
+impl RouterExt for TestApp {
+    #[allow(unused)]
+    fn request(&self, uri: &str) -> TestRequest {
+        TestRequest::new(self.router.clone(), uri).token(&self.token)
+    }
+}
+
+/// Creates a token for `subject` that expires in an hour, signed with the secret in `config`.
+pub fn build_token(subject: &str, config: &AuthConfig) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Clock went backwards")
+        .as_secs();
+    let claims = Claims {
+        sub: String::from(subject),
+        exp: now + 3600,
+        iss: config.jwt_issuer.clone(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+    .expect("Failed to encode token")
+}
+
 /// Testing convenience functions for [`axum::body::Body`].
 pub trait BodyExt {
     /// Returns the body as raw bytes.
@@ -197,9 +257,19 @@ impl BodyExt for Body {
 /// ```
 pub struct DbTestContext {
     /// The application that is being tested.
-    pub app: Router,
+    pub app: TestApp,
     /// A connection pool connected to the same database that the application that is being tested uses as well.
     pub db_pool: DbPool,
+    /// The authentication settings of the application that is being tested.
+    pub auth: AuthConfig,
+}
+
+impl DbTestContext {
+    /// Creates a valid token for `subject`, to be sent with [`TestRequest::token`].
+    #[allow(unused)]
+    pub fn token_for(&self, subject: &str) -> String {
+        build_token(subject, &self.auth)
+    }
 }
 
 /// Sets up a test and returns a [`DbTestContext`] configured for the particular test case.
@@ -213,14 +283,24 @@ pub async fn setup() -> DbTestContext {
     let config = init_config.get_or_init(|| load_config(&Environment::Test).unwrap());
 
     let test_db_pool = setup_db(&config.database).await;
+    roles::assign("admin", TEST_SUBJECT, &test_db_pool)
+        .await
+        .expect("Could not grant the admin role to the test subject!");
 
-    let app = init_routes(AppState {
+    let router = init_routes(AppState {
         db_pool: test_db_pool.clone(),
+        auth: config.auth.clone(),
+        timeline: config.timeline.clone(),
     });
+    let app = TestApp {
+        router,
+        token: build_token(TEST_SUBJECT, &config.auth),
+    };
 
     DbTestContext {
         app,
         db_pool: test_db_pool,
+        auth: config.auth.clone(),
     }
 }
 
@@ -0,0 +1,174 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    audit::{AuditAction, AuditEntity, AuditEntry},
+    configuration::{self, ConfigItemCreateset},
+    incidents::{self, IncidentCreateset, IncidentImpact, IncidentUrgency},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_incident_createset() -> IncidentCreateset {
+    IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: None,
+        created_at: None,
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    }
+}
+
+async fn read_history(context: &DbTestContext, path: &str) -> Vec<AuditEntry> {
+    let response = context.app.request(path).send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    response.into_body().into_json::<Vec<AuditEntry>>().await
+}
+
+#[db_test]
+async fn test_history_of_updates(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .body(Body::from(json!(create_incident_createset()).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    let incident = response
+        .into_body()
+        .into_json::<incidents::Incident>()
+        .await;
+
+    for payload in [
+        json!({ "title": "Updated Incident" }),
+        // Updates that change nothing aren't recorded.
+        json!({ "title": "Updated Incident" }),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/incidents/{}", incident.id))
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+    }
+
+    let history = read_history(context, &format!("/api/incidents/{}/history", incident.id)).await;
+
+    assert_that!(history, len(eq(2)));
+    let (created, updated) = (&history[0], &history[1]);
+    assert_that!(created.action, eq(AuditAction::Create));
+    assert_that!(created.actor, eq(TEST_SUBJECT));
+    assert_that!(created.entity_type, eq(AuditEntity::Incident));
+    assert_that!(created.entity_id, eq(incident.id));
+    assert_that!(created.changes["title"]["before"], eq(&json!(null)));
+    assert_that!(
+        created.changes["title"]["after"],
+        eq(&json!("Testing Incident"))
+    );
+    assert_that!(updated.action, eq(AuditAction::Update));
+    assert_that!(updated.actor, eq(TEST_SUBJECT));
+    assert_that!(updated.source, eq("incidents"));
+    assert_that!(
+        updated.changes,
+        eq(&json!({ "title": { "before": "Testing Incident", "after": "Updated Incident" } }))
+    );
+}
+
+#[db_test]
+async fn test_history_of_relations(context: &DbTestContext) {
+    let incident = incidents::create(create_incident_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let ci = configuration::create(
+        ConfigItemCreateset {
+            name: String::from("Testing Configuration Item"),
+            status: None,
+            created_at: None,
+            r#type: None,
+            owner_team_id: None,
+            description: String::from("This is a fictional item made for testing."),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/configitems", incident.id))
+        .method(Method::POST)
+        .body(Body::from(json!({ "ci_id": ci.id }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let incident_history =
+        read_history(context, &format!("/api/incidents/{}/history", incident.id)).await;
+    let ci_history = read_history(context, &format!("/api/configitems/{}/history", ci.id)).await;
+
+    // Changes made outside of the API are attributed to `system`.
+    assert_that!(incident_history[0].actor, eq("system"));
+    for history in [&incident_history, &ci_history] {
+        assert_that!(history, len(eq(2)));
+        assert_that!(history[1].source, eq("incidents_ci_relations"));
+        assert_that!(history[1].action, eq(AuditAction::Create));
+        assert_that!(history[1].actor, eq(TEST_SUBJECT));
+    }
+}
+
+#[db_test]
+async fn test_history_outlives_entity(context: &DbTestContext) {
+    let incident = incidents::create(create_incident_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", incident.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let history = read_history(context, &format!("/api/incidents/{}/history", incident.id)).await;
+
+    assert_that!(history, len(eq(2)));
+    assert_that!(history[1].action, eq(AuditAction::Delete));
+    assert_that!(
+        history[1].changes["title"]["before"],
+        eq(&json!("Testing Incident"))
+    );
+    assert_that!(history[1].changes["title"]["after"], eq(&json!(null)));
+}
+
+#[db_test]
+async fn test_history_nonexistent(context: &DbTestContext) {
+    for entity in ["incidents", "configitems", "problems", "changes"] {
+        let response = context
+            .app
+            .request(&format!("/api/{entity}/{}/history", Uuid::new_v4()))
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,93 @@
+use googletest::prelude::*;
+use hyper::{header, StatusCode};
+use itil_back_config::AuthConfig;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{build_token, DbTestContext, RouterExt};
+
+#[db_test]
+async fn test_health_is_public(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/health")
+        .unauthenticated()
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_apidoc_is_public(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/apidoc/openapi.json")
+        .unauthenticated()
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_missing_token(context: &DbTestContext) {
+    for uri in [
+        "/api/incidents",
+        "/api/configitems",
+        "/api/problems",
+        "/api/changes",
+    ] {
+        let response = context.app.request(uri).unauthenticated().send().await;
+
+        assert_that!(response.status(), eq(StatusCode::UNAUTHORIZED));
+        assert_that!(
+            response.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            eq("Bearer")
+        );
+    }
+}
+
+#[db_test]
+async fn test_malformed_token(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/incidents")
+        .token("not-a-jwt")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNAUTHORIZED));
+}
+
+#[db_test]
+async fn test_token_signed_with_other_secret(context: &DbTestContext) {
+    let token = build_token(
+        "intruder",
+        &AuthConfig {
+            jwt_secret: String::from("not-the-right-secret"),
+            jwt_issuer: None,
+        },
+    );
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .token(&token)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNAUTHORIZED));
+}
+
+#[db_test]
+async fn test_valid_token(context: &DbTestContext) {
+    let token = context.token_for("someone");
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .token(&token)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
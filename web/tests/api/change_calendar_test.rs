@@ -0,0 +1,440 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::{DateTime, Utc};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    change_advisory_board::{self, ChangeAdvisoryBoardUpdateset},
+    changes::{
+        self,
+        approvals::{self, RFCApprovalCreateset, RFCVote},
+        calendar::ChangeCalendar,
+        freeze_overrides::RFCFreezeOverride,
+        risk::{RFCRiskAssessmentUpdateset, RFCRiskChoice},
+        RFCCreateset, RFCStatus, RFCType,
+    },
+    freeze_periods::{self, FreezePeriodCreateset},
+    risk_questionnaire,
+    teams::{self, members, TeamCreateset},
+    users::{self, UserCreateset},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
+use serde_json::json;
+use uuid::Uuid;
+
+fn at(moment: &str) -> DateTime<Utc> {
+    moment.parse().unwrap()
+}
+
+/// Assess the risk of `rfc_id` picking the answer at `answer` of every question, `1` being
+/// medium risk with the default questionnaire.
+async fn assess_risk(context: &DbTestContext, rfc_id: Uuid, answer: usize) {
+    let questionnaire = risk_questionnaire::load(&context.db_pool).await.unwrap();
+    let answers = questionnaire
+        .questions
+        .iter()
+        .map(|q| RFCRiskChoice {
+            question_id: q.id,
+            answer_id: q.answers[answer].id,
+        })
+        .collect();
+
+    changes::risk::update(
+        rfc_id,
+        RFCRiskAssessmentUpdateset { answers },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+}
+
+async fn post_rfc(context: &DbTestContext, r#type: RFCType, planned: Option<(&str, &str)>) -> Uuid {
+    let requester = users::create(
+        UserCreateset {
+            username: format!("requester-{}", Uuid::new_v4()),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let createset = RFCCreateset {
+        title: String::from("Testing RFC"),
+        r#type: Some(r#type),
+        status: Some(RFCStatus::Submitted),
+        created_at: None,
+        finished_at: None,
+        requester_id: requester.id,
+        description: String::from("This is a fictional RFC made for testing."),
+        planned_start_at: planned.map(|(start, _)| at(start)),
+        planned_end_at: planned.map(|(_, end)| at(end)),
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
+    };
+
+    let rfc_id = changes::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id;
+    if r#type != RFCType::Standard {
+        assess_risk(context, rfc_id, 1).await;
+    }
+
+    rfc_id
+}
+
+async fn post_freeze(context: &DbTestContext, starts_at: &str, ends_at: &str) -> Uuid {
+    let createset = FreezePeriodCreateset {
+        name: String::from("Year-end closing"),
+        description: String::from("Finance closes the fiscal year."),
+        starts_at: at(starts_at),
+        ends_at: at(ends_at),
+    };
+
+    freeze_periods::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+/// Make a team with the user `username` the CAB.
+async fn post_cab_member(context: &DbTestContext, username: &str) {
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("CAB"),
+            description: String::from("Change Advisory Board"),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let member = users::create(
+        UserCreateset {
+            username: String::from(username),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    members::create(team.id, member.id, &context.db_pool)
+        .await
+        .unwrap();
+    change_advisory_board::update(
+        ChangeAdvisoryBoardUpdateset {
+            team_id: Some(Some(team.id)),
+            quorum: None,
+            block_conflicts: None,
+            review_window_hours: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+}
+
+async fn approve(context: &DbTestContext, rfc_id: Uuid, approver: &str) {
+    approvals::create(
+        rfc_id,
+        approver,
+        RFCApprovalCreateset {
+            vote: RFCVote::Approve,
+            comment: String::from("Has to be done now."),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+}
+
+async fn put_rfc(context: &DbTestContext, rfc_id: Uuid, payload: serde_json::Value) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+#[db_test]
+async fn test_schedule_needs_window(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Standard, None).await;
+
+    let status = put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await;
+    assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let status = put_rfc(
+        context,
+        rfc_id,
+        json!({
+            "status": "scheduled",
+            "planned_start_at": "2030-03-01T22:00:00Z",
+            "planned_end_at": "2030-03-02T02:00:00Z",
+        }),
+    )
+    .await;
+    assert_that!(status, eq(StatusCode::OK));
+
+    // Scheduled RFCs can't be unplanned.
+    let status = put_rfc(
+        context,
+        rfc_id,
+        json!({ "planned_start_at": null, "planned_end_at": null }),
+    )
+    .await;
+    assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_schedule_during_freeze(context: &DbTestContext) {
+    post_freeze(context, "2030-12-20T00:00:00Z", "2031-01-05T00:00:00Z").await;
+    let rfc_id = post_rfc(
+        context,
+        RFCType::Standard,
+        Some(("2030-12-31T22:00:00Z", "2031-01-01T02:00:00Z")),
+    )
+    .await;
+
+    let status = put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await;
+    assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Windows are half-open, so ending right as the freeze starts is fine.
+    let status = put_rfc(
+        context,
+        rfc_id,
+        json!({
+            "status": "scheduled",
+            "planned_start_at": "2030-12-19T22:00:00Z",
+            "planned_end_at": "2030-12-20T00:00:00Z",
+        }),
+    )
+    .await;
+    assert_that!(status, eq(StatusCode::OK));
+
+    // Nor can it be moved into the freeze once scheduled.
+    let status = put_rfc(
+        context,
+        rfc_id,
+        json!({ "planned_end_at": "2030-12-20T01:00:00Z" }),
+    )
+    .await;
+    assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_freeze_after_scheduling(context: &DbTestContext) {
+    let rfc_id = post_rfc(
+        context,
+        RFCType::Standard,
+        Some(("2030-12-31T22:00:00Z", "2031-01-01T02:00:00Z")),
+    )
+    .await;
+    let status = put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await;
+    assert_that!(status, eq(StatusCode::OK));
+    post_freeze(context, "2030-12-20T00:00:00Z", "2031-01-05T00:00:00Z").await;
+
+    // Changes scheduled before the freeze can still be updated without rescheduling them.
+    let status = put_rfc(context, rfc_id, json!({ "title": "Renamed RFC" })).await;
+    assert_that!(status, eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_emergency_overrides_freeze(context: &DbTestContext) {
+    post_freeze(context, "2030-12-20T00:00:00Z", "2031-01-05T00:00:00Z").await;
+    post_cab_member(context, "alice").await;
+    let rfc_id = post_rfc(
+        context,
+        RFCType::Emergency,
+        Some(("2030-12-31T22:00:00Z", "2031-01-01T02:00:00Z")),
+    )
+    .await;
+    approve(context, rfc_id, "alice").await;
+
+    let status = put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await;
+    assert_that!(status, eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_override_freeze(context: &DbTestContext) {
+    post_freeze(context, "2030-12-20T00:00:00Z", "2031-01-05T00:00:00Z").await;
+    post_cab_member(context, TEST_SUBJECT).await;
+    let rfc_id = post_rfc(
+        context,
+        RFCType::Normal,
+        Some(("2030-12-31T22:00:00Z", "2031-01-01T02:00:00Z")),
+    )
+    .await;
+    let override_url = format!("/api/changes/{}/freezeoverride", rfc_id);
+    let justification = json!({ "justification": "The old API is retired on new year." });
+
+    let status = put_rfc(context, rfc_id, json!({ "status": "assessed" })).await;
+    assert_that!(status, eq(StatusCode::OK));
+    approve(context, rfc_id, TEST_SUBJECT).await;
+
+    let status = put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await;
+    assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = context
+        .app
+        .request(&override_url)
+        .method(Method::PUT)
+        .body(Body::from(justification.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let freeze_override = response.into_body().into_json::<RFCFreezeOverride>().await;
+    assert_that!(freeze_override.rfc_id, eq(rfc_id));
+
+    let status = put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await;
+    assert_that!(status, eq(StatusCode::OK));
+
+    // Once withdrawn, the RFC stays scheduled but can't be rescheduled into the freeze.
+    let response = context
+        .app
+        .request(&override_url)
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+    let response = context.app.request(&override_url).send().await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let status = put_rfc(
+        context,
+        rfc_id,
+        json!({ "planned_end_at": "2031-01-01T03:00:00Z" }),
+    )
+    .await;
+    assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_override_freeze_invalid(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Normal, None).await;
+    let override_url = format!("/api/changes/{}/freezeoverride", rfc_id);
+    let justification = json!({ "justification": "The old API is retired on new year." });
+
+    // The tester isn't a user yet.
+    let response = context
+        .app
+        .request(&override_url)
+        .method(Method::PUT)
+        .body(Body::from(justification.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+
+    let response = context
+        .app
+        .request(&override_url)
+        .method(Method::PUT)
+        .body(Body::from(json!({ "justification": "" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/freezeoverride", Uuid::new_v4()))
+        .method(Method::PUT)
+        .body(Body::from(justification.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_calendar(context: &DbTestContext) {
+    let freeze = post_freeze(context, "2030-12-20T00:00:00Z", "2031-01-05T00:00:00Z").await;
+    post_freeze(context, "2031-06-01T00:00:00Z", "2031-06-02T00:00:00Z").await;
+    let late = post_rfc(
+        context,
+        RFCType::Standard,
+        Some(("2030-12-15T22:00:00Z", "2030-12-16T02:00:00Z")),
+    )
+    .await;
+    let early = post_rfc(
+        context,
+        RFCType::Normal,
+        Some(("2030-12-01T22:00:00Z", "2030-12-02T02:00:00Z")),
+    )
+    .await;
+    post_rfc(
+        context,
+        RFCType::Normal,
+        Some(("2031-02-01T22:00:00Z", "2031-02-02T02:00:00Z")),
+    )
+    .await;
+    post_rfc(context, RFCType::Normal, None).await;
+    // Still being implemented, so it's in the calendar although planned before the range.
+    let ongoing = post_rfc(
+        context,
+        RFCType::Standard,
+        Some(("2030-11-01T22:00:00Z", "2030-11-02T02:00:00Z")),
+    )
+    .await;
+    let status = put_rfc(
+        context,
+        ongoing,
+        json!({ "status": "scheduled", "actual_start_at": "2030-11-01T22:00:00Z" }),
+    )
+    .await;
+    assert_that!(status, eq(StatusCode::OK));
+
+    let response = context
+        .app
+        .request("/api/changes/calendar?from=2030-12-01T00:00:00Z&to=2031-01-01T00:00:00Z")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let calendar = response.into_body().into_json::<ChangeCalendar>().await;
+    let changes: Vec<Uuid> = calendar.changes.iter().map(|c| c.id).collect();
+    assert_that!(changes, elements_are![eq(&ongoing), eq(&early), eq(&late)]);
+    let freezes: Vec<Uuid> = calendar.freeze_periods.iter().map(|f| f.id).collect();
+    assert_that!(freezes, elements_are![eq(&freeze)]);
+
+    let response = context
+        .app
+        .request(
+            "/api/changes/calendar?from=2030-12-01T00:00:00Z&to=2031-01-01T00:00:00Z&status=scheduled",
+        )
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let calendar = response.into_body().into_json::<ChangeCalendar>().await;
+    assert_that!(calendar.changes, len(eq(1)));
+}
+
+#[db_test]
+async fn test_calendar_invalid_range(context: &DbTestContext) {
+    for query in [
+        "from=2031-01-01T00:00:00Z&to=2030-12-01T00:00:00Z",
+        "from=2030-01-01T00:00:00Z&to=2032-01-01T00:00:00Z",
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/changes/calendar?{}", query))
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context.app.request("/api/changes/calendar").send().await;
+    assert_that!(response.status(), eq(StatusCode::BAD_REQUEST));
+}
@@ -7,40 +7,96 @@ use googletest::prelude::*;
 use hyper::StatusCode;
 use itil_back_db::entities::{
     self,
-    changes::{self, RFCCreateset, RFCStatus, RFCUpdateset, RFC},
+    changes::{
+        self,
+        risk::{RFCRiskAssessmentUpdateset, RFCRiskChoice},
+        RFCCreateset, RFCStatus, RFCType, RFCUpdateset, RFC,
+    },
+    risk_questionnaire,
+    users::{self, UserCreateset},
 };
+use itil_back_db::pagination::Page;
 use itil_back_macros::db_test;
 use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
 use serde_json::json;
 use uuid::Uuid;
 
-fn create_basic_createset() -> RFCCreateset {
+async fn post_user(context: &DbTestContext, username: &str) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(username),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    let user = users::create(createset, &context.db_pool).await.unwrap();
+
+    user.id
+}
+
+/// Assess the risk of `rfc_id` picking the answer at `answer` of every question, `1` being
+/// medium risk with the default questionnaire.
+async fn assess_risk(context: &DbTestContext, rfc_id: Uuid, answer: usize) {
+    let questionnaire = risk_questionnaire::load(&context.db_pool).await.unwrap();
+    let answers = questionnaire
+        .questions
+        .iter()
+        .map(|q| RFCRiskChoice {
+            question_id: q.id,
+            answer_id: q.answers[answer].id,
+        })
+        .collect();
+
+    changes::risk::update(
+        rfc_id,
+        RFCRiskAssessmentUpdateset { answers },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+}
+
+fn create_basic_createset(requester_id: Uuid) -> RFCCreateset {
     RFCCreateset {
         title: String::from("Testing RFC"),
-        status: Some(RFCStatus::Open),
+        r#type: Some(RFCType::Normal),
+        status: Some(RFCStatus::Submitted),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
         finished_at: Some("2023-10-15T12:34:50Z".parse().unwrap()),
-        requester: String::from("Testing Department"),
+        requester_id,
         description: String::from("This is a fictional RFC made for testing."),
+        planned_start_at: Some("2030-01-01T22:00:00Z".parse().unwrap()),
+        planned_end_at: Some("2030-01-02T02:00:00Z".parse().unwrap()),
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
     }
 }
 
-fn create_basic_updateset() -> RFCUpdateset {
+fn create_basic_updateset(requester_id: Uuid) -> RFCUpdateset {
     RFCUpdateset {
         title: Some(Some(String::from("Updated RFC"))),
-        status: Some(Some(RFCStatus::InProgress)),
+        r#type: None,
+        status: Some(Some(RFCStatus::Assessed)),
         created_at: Some(Some("2023-09-15T12:34:58Z".parse().unwrap())),
         finished_at: Some(Some("2023-11-15T12:34:58Z".parse().unwrap())),
-        requester: Some(Some(String::from("Update Department"))),
+        requester_id: Some(Some(requester_id)),
         description: Some(Some(String::from(
             "This is a fictional RFC made for updating.",
         ))),
+        planned_start_at: Some(Some("2030-02-01T22:00:00Z".parse().unwrap())),
+        planned_end_at: Some(Some("2030-02-02T02:00:00Z".parse().unwrap())),
+        actual_start_at: None,
+        actual_end_at: None,
+        implementation_plan: None,
+        backout_plan: None,
+        test_plan: None,
     }
 }
 
 #[db_test]
 async fn test_create_invalid(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let mut sets = Vec::new();
     sets.push(RFCCreateset {
         title: String::from(""),
@@ -51,13 +107,25 @@ async fn test_create_invalid(context: &DbTestContext) {
         ..createset.clone()
     });
     sets.push(entities::changes::RFCCreateset {
-        requester: String::from(&"x".repeat(1025)),
+        requester_id: Uuid::new_v4(),
         ..createset.clone()
     });
     sets.push(entities::changes::RFCCreateset {
         description: String::from(&"x".repeat(1025)),
         ..createset.clone()
     });
+    sets.push(RFCCreateset {
+        status: Some(RFCStatus::Approved),
+        ..createset.clone()
+    });
+    sets.push(RFCCreateset {
+        planned_end_at: None,
+        ..createset.clone()
+    });
+    sets.push(RFCCreateset {
+        planned_end_at: createset.planned_start_at,
+        ..createset.clone()
+    });
 
     for set in sets {
         let payload = json!(set);
@@ -93,7 +161,8 @@ async fn test_create_bad_payload(context: &DbTestContext) {
 
 #[db_test]
 async fn test_create_success(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let payload = json!(createset);
 
     let response = context
@@ -112,7 +181,7 @@ async fn test_create_success(context: &DbTestContext) {
     assert_that!(rfc.status, eq(createset.status.unwrap()));
     assert_that!(rfc.created_at, eq(createset.created_at.unwrap()));
     assert_that!(rfc.finished_at, eq(createset.finished_at));
-    assert_that!(rfc.requester, eq(&createset.requester));
+    assert_that!(rfc.requester_id, eq(createset.requester_id));
     assert_that!(rfc.description, eq(&createset.description));
 
     let rfcs = changes::load_all(&context.db_pool).await.unwrap();
@@ -121,9 +190,10 @@ async fn test_create_success(context: &DbTestContext) {
 
 #[db_test]
 async fn test_create_no_creation_date(context: &DbTestContext) {
+    let requester_id = post_user(context, "requester").await;
     let createset = RFCCreateset {
         created_at: None,
-        ..create_basic_createset()
+        ..create_basic_createset(requester_id)
     };
     let payload = json!(createset);
 
@@ -147,7 +217,8 @@ async fn test_create_no_creation_date(context: &DbTestContext) {
 
 #[db_test]
 async fn test_create_border_success(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let mut sets = Vec::new();
     sets.push(RFCCreateset {
         title: String::from("x"),
@@ -158,6 +229,7 @@ async fn test_create_border_success(context: &DbTestContext) {
         ..createset.clone()
     });
     sets.push(RFCCreateset {
+        r#type: None,
         status: None,
         ..createset.clone()
     });
@@ -165,14 +237,6 @@ async fn test_create_border_success(context: &DbTestContext) {
         finished_at: None,
         ..createset.clone()
     });
-    sets.push(RFCCreateset {
-        requester: String::from(""),
-        ..createset.clone()
-    });
-    sets.push(RFCCreateset {
-        requester: String::from(&"x".repeat(1024)),
-        ..createset.clone()
-    });
     sets.push(RFCCreateset {
         description: String::from(""),
         ..createset.clone()
@@ -200,20 +264,18 @@ async fn test_create_border_success(context: &DbTestContext) {
 
 #[db_test]
 async fn test_status(context: &DbTestContext) {
-    let createset = create_basic_createset();
-    let mut sets = Vec::new();
-    sets.push(changes::RFCCreateset {
-        status: Some(RFCStatus::Open),
-        ..createset.clone()
-    });
-    sets.push(RFCCreateset {
-        status: Some(RFCStatus::InProgress),
-        ..createset.clone()
-    });
-    sets.push(RFCCreateset {
-        status: Some(RFCStatus::Closed),
-        ..createset.clone()
-    });
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
+    let sets = vec![
+        RFCCreateset {
+            status: Some(RFCStatus::Draft),
+            ..createset.clone()
+        },
+        RFCCreateset {
+            status: Some(RFCStatus::Submitted),
+            ..createset.clone()
+        },
+    ];
 
     for set in sets {
         let payload = json!(set);
@@ -231,20 +293,207 @@ async fn test_status(context: &DbTestContext) {
         let rfc = response.into_body().into_json::<RFC>().await;
         assert_that!(rfc.status, eq(set.status.unwrap()));
     }
+
+    let response = context
+        .app
+        .request("/api/changes")
+        .method(Method::POST)
+        .body(Body::from(
+            json!(RFCCreateset {
+                r#type: None,
+                status: None,
+                ..createset
+            })
+            .to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let rfc = response.into_body().into_json::<RFC>().await;
+    assert_that!(rfc.status, eq(RFCStatus::Draft));
+    assert_that!(rfc.r#type, eq(RFCType::Normal));
+}
+
+/// Move `rfc_id` to `status`, returning the response status.
+async fn put_status(context: &DbTestContext, rfc_id: Uuid, status: RFCStatus) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "status": status }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+#[db_test]
+async fn test_lifecycle_standard(context: &DbTestContext) {
+    let requester_id = post_user(context, "requester").await;
+    let createset = RFCCreateset {
+        r#type: Some(RFCType::Standard),
+        ..create_basic_createset(requester_id)
+    };
+    let rfc = changes::create(createset, &context.db_pool).await.unwrap();
+
+    // Standard changes are preapproved.
+    assert_that!(
+        put_status(context, rfc.id, RFCStatus::Assessed).await,
+        eq(StatusCode::CONFLICT)
+    );
+    for status in [RFCStatus::Scheduled, RFCStatus::Implemented] {
+        assert_that!(
+            put_status(context, rfc.id, status).await,
+            eq(StatusCode::OK)
+        );
+    }
+
+    // Reviewed once there's a post-implementation review.
+    assert_that!(
+        put_status(context, rfc.id, RFCStatus::Reviewed).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+    let review = changes::reviews::RFCReviewUpdateset {
+        outcome: changes::reviews::RFCOutcome::Successful,
+        caused_incidents: false,
+        lessons_learned: String::from("None."),
+    };
+    changes::reviews::update(rfc.id, "requester", review, &context.db_pool)
+        .await
+        .unwrap();
+    for status in [RFCStatus::Reviewed, RFCStatus::Closed] {
+        assert_that!(
+            put_status(context, rfc.id, status).await,
+            eq(StatusCode::OK)
+        );
+    }
+
+    let rfc_after = changes::load(rfc.id, &context.db_pool).await.unwrap();
+    assert_that!(rfc_after.status, eq(RFCStatus::Closed));
+}
+
+#[db_test]
+async fn test_lifecycle_normal(context: &DbTestContext) {
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
+    let rfc = changes::create(createset, &context.db_pool).await.unwrap();
+    assess_risk(context, rfc.id, 1).await;
+
+    // Normal changes can't skip the assessment and approval.
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "status": "scheduled" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CONFLICT));
+    let body = response.into_body().into_json::<serde_json::Value>().await;
+    assert_that!(body["allowed"], eq(&json!(["draft", "assessed", "closed"])));
+
+    assert_that!(
+        put_status(context, rfc.id, RFCStatus::Assessed).await,
+        eq(StatusCode::OK)
+    );
+    // Without the approval of the CAB.
+    assert_that!(
+        put_status(context, rfc.id, RFCStatus::Approved).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+    assert_that!(
+        put_status(context, rfc.id, RFCStatus::Rejected).await,
+        eq(StatusCode::OK)
+    );
+}
+
+#[db_test]
+async fn test_update_type(context: &DbTestContext) {
+    let requester_id = post_user(context, "requester").await;
+    let createset = RFCCreateset {
+        status: Some(RFCStatus::Draft),
+        ..create_basic_createset(requester_id)
+    };
+    let rfc = changes::create(createset, &context.db_pool).await.unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc.id))
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({ "type": "emergency", "status": "submitted" }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let rfc: RFC = response.into_body().into_json::<RFC>().await;
+    assert_that!(rfc.r#type, eq(RFCType::Emergency));
+
+    // Only drafts can change their type.
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "type": "standard" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
 }
 
 #[db_test]
 async fn test_read_all(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset, &context.db_pool).await.unwrap();
 
     let response = context.app.request("/api/changes").send().await;
 
     assert_that!(response.status(), eq(StatusCode::OK));
 
-    let rfcs: Vec<RFC> = response.into_body().into_json::<Vec<RFC>>().await;
-    assert_that!(rfcs, len(eq(1)));
-    assert_that!(rfcs.first().unwrap(), eq(&rfc));
+    let page: Page<RFC> = response.into_body().into_json::<Page<RFC>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items, len(eq(1)));
+    assert_that!(page.items.first().unwrap(), eq(&rfc));
+}
+
+#[db_test]
+async fn test_read_all_filtered(context: &DbTestContext) {
+    let requester_id = post_user(context, "requester").await;
+    let other_requester_id = post_user(context, "other_requester").await;
+    let createset = create_basic_createset(requester_id);
+    changes::create(createset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+    let other = changes::create(
+        RFCCreateset {
+            r#type: Some(RFCType::Standard),
+            status: Some(RFCStatus::Draft),
+            requester_id: other_requester_id,
+            ..createset
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/changes?status=draft&type=standard&requester_id={}",
+            other_requester_id
+        ))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<RFC> = response.into_body().into_json::<Page<RFC>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items.first().unwrap(), eq(&other));
 }
 
 #[db_test]
@@ -260,7 +509,8 @@ async fn test_read_one_nonexistent(context: &DbTestContext) {
 
 #[db_test]
 async fn test_read_one_success(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset, &context.db_pool).await.unwrap();
 
     let response = context
@@ -277,10 +527,12 @@ async fn test_read_one_success(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_invalid(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let other_requester_id = post_user(context, "other_requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset, &context.db_pool).await.unwrap();
 
-    let updateset = create_basic_updateset();
+    let updateset = create_basic_updateset(other_requester_id);
     let mut sets = Vec::new();
     sets.push(RFCUpdateset {
         title: Some(Some(String::from(""))),
@@ -291,13 +543,30 @@ async fn test_update_invalid(context: &DbTestContext) {
         ..updateset.clone()
     });
     sets.push(RFCUpdateset {
-        requester: Some(Some(String::from(&"x".repeat(1025)))),
+        requester_id: Some(Some(Uuid::new_v4())),
         ..updateset.clone()
     });
     sets.push(RFCUpdateset {
         description: Some(Some(String::from(&"x".repeat(1025)))),
         ..updateset.clone()
     });
+    sets.push(RFCUpdateset {
+        planned_end_at: Some(Some("2030-02-01T21:00:00Z".parse().unwrap())),
+        ..updateset.clone()
+    });
+    sets.push(RFCUpdateset {
+        planned_end_at: Some(None),
+        ..updateset.clone()
+    });
+    sets.push(RFCUpdateset {
+        actual_end_at: Some(Some("2030-02-02T01:00:00Z".parse().unwrap())),
+        ..updateset.clone()
+    });
+    sets.push(RFCUpdateset {
+        actual_start_at: Some(Some("2030-02-02T01:00:00Z".parse().unwrap())),
+        actual_end_at: Some(Some("2030-02-01T23:00:00Z".parse().unwrap())),
+        ..updateset.clone()
+    });
 
     for set in sets {
         let payload = json!(set);
@@ -320,15 +589,21 @@ async fn test_update_invalid(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_invalid_nulls(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let other_requester_id = post_user(context, "other_requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset, &context.db_pool).await.unwrap();
 
-    let updateset = create_basic_updateset();
+    let updateset = create_basic_updateset(other_requester_id);
     let mut sets = Vec::new();
     sets.push(RFCUpdateset {
         title: Some(None),
         ..updateset.clone()
     });
+    sets.push(RFCUpdateset {
+        r#type: Some(None),
+        ..updateset.clone()
+    });
     sets.push(RFCUpdateset {
         status: Some(None),
         ..updateset.clone()
@@ -338,7 +613,7 @@ async fn test_update_invalid_nulls(context: &DbTestContext) {
         ..updateset.clone()
     });
     sets.push(RFCUpdateset {
-        requester: Some(None),
+        requester_id: Some(None),
         ..updateset.clone()
     });
     sets.push(RFCUpdateset {
@@ -364,7 +639,8 @@ async fn test_update_invalid_nulls(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_nonexistent(context: &DbTestContext) {
-    let updateset = create_basic_updateset();
+    let other_requester_id = post_user(context, "other_requester").await;
+    let updateset = create_basic_updateset(other_requester_id);
     let payload = json!(updateset);
 
     let response = context
@@ -381,10 +657,13 @@ async fn test_update_nonexistent(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_success(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let other_requester_id = post_user(context, "other_requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset, &context.db_pool).await.unwrap();
+    assess_risk(context, rfc.id, 1).await;
 
-    let updateset = create_basic_updateset();
+    let updateset = create_basic_updateset(other_requester_id);
     let payload = json!(updateset);
 
     let response = context
@@ -403,11 +682,19 @@ async fn test_update_success(context: &DbTestContext) {
     assert_that!(rfc.status, eq(updateset.status.unwrap().unwrap()));
     assert_that!(rfc.created_at, eq(updateset.created_at.unwrap().unwrap()));
     assert_that!(rfc.finished_at, eq(updateset.finished_at.unwrap()));
-    assert_that!(rfc.requester, eq(&updateset.requester.unwrap().unwrap()));
+    assert_that!(
+        rfc.requester_id,
+        eq(updateset.requester_id.unwrap().unwrap())
+    );
     assert_that!(
         rfc.description,
         eq(&updateset.description.unwrap().unwrap())
     );
+    assert_that!(
+        rfc.planned_start_at,
+        eq(updateset.planned_start_at.unwrap())
+    );
+    assert_that!(rfc.planned_end_at, eq(updateset.planned_end_at.unwrap()));
 
     let rfc_after = changes::load(rfc.id, &context.db_pool).await.unwrap();
     assert_that!(rfc_after, eq(&rfc));
@@ -415,12 +702,17 @@ async fn test_update_success(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_set_nulls(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let other_requester_id = post_user(context, "other_requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset, &context.db_pool).await.unwrap();
+    assess_risk(context, rfc.id, 1).await;
 
     let updateset = RFCUpdateset {
         finished_at: Some(None),
-        ..create_basic_updateset()
+        planned_start_at: Some(None),
+        planned_end_at: Some(None),
+        ..create_basic_updateset(other_requester_id)
     };
     let payload = json!(updateset);
 
@@ -436,20 +728,30 @@ async fn test_update_set_nulls(context: &DbTestContext) {
     assert_that!(response.status(), eq(StatusCode::OK));
     let rfc_after: RFC = response.into_body().into_json::<RFC>().await;
     assert!(rfc_after.finished_at.is_none());
+    assert!(rfc_after.planned_start_at.is_none());
 }
 
 #[db_test]
 async fn test_update_nothing(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc_before = changes::create(createset, &context.db_pool).await.unwrap();
 
     let updateset = RFCUpdateset {
         title: None,
+        r#type: None,
         status: None,
         created_at: None,
         finished_at: None,
-        requester: None,
+        requester_id: None,
         description: None,
+        planned_start_at: None,
+        planned_end_at: None,
+        actual_start_at: None,
+        actual_end_at: None,
+        implementation_plan: None,
+        backout_plan: None,
+        test_plan: None,
     };
     let payload = json!(updateset);
 
@@ -481,7 +783,8 @@ async fn test_delete_nonexistent(context: &DbTestContext) {
 
 #[db_test]
 async fn test_delete_success(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let requester_id = post_user(context, "requester").await;
+    let createset = create_basic_createset(requester_id);
     let rfc = changes::create(createset.clone(), &context.db_pool)
         .await
         .unwrap();
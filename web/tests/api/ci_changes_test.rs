@@ -2,11 +2,14 @@ use axum::{
     body::Body,
     http::{self, Method},
 };
+use chrono::{DateTime, Utc};
 use googletest::prelude::*;
 use hyper::StatusCode;
 use itil_back_db::entities::{
     self,
+    changes::{ci_relations::RFCCICreateset, RFCCreateset, RFCStatus, RFCType, RFCUpdateset},
     configuration::changes::{self, CIChange, CIChangeCreateset, CIChangeUpdateset},
+    users::UserCreateset,
 };
 use itil_back_macros::db_test;
 use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
@@ -19,7 +22,7 @@ async fn post_ci(context: &DbTestContext) -> Uuid {
         status: Some(entities::configuration::CIStatus::Active),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
         r#type: Some(String::from("Test CI")),
-        owner: Some(String::from("Me")),
+        owner_team_id: None,
         description: String::from("I'm for testing"),
     };
 
@@ -30,10 +33,74 @@ async fn post_ci(context: &DbTestContext) -> Uuid {
     ci.id
 }
 
+/// Create a standard RFC affecting the configuration item `ci_id` and move it to `status`.
+async fn post_rfc(context: &DbTestContext, ci_id: Uuid, status: RFCStatus) -> Uuid {
+    let requester = entities::users::create(
+        UserCreateset {
+            username: String::from("requester"),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let rfc = entities::changes::create(
+        RFCCreateset {
+            title: String::from("Upgrade the database"),
+            r#type: Some(RFCType::Standard),
+            status: Some(RFCStatus::Submitted),
+            created_at: None,
+            finished_at: None,
+            requester_id: requester.id,
+            description: String::from("This is a fictional RFC made for testing."),
+            planned_start_at: Some("2030-03-01T22:00:00Z".parse().unwrap()),
+            planned_end_at: Some("2030-03-02T02:00:00Z".parse().unwrap()),
+            implementation_plan: Some(String::from("Roll out the change.")),
+            backout_plan: Some(String::from("Undo the change.")),
+            test_plan: Some(String::from("Check that it works.")),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::changes::ci_relations::create(rfc.id, RFCCICreateset { ci_id }, &context.db_pool)
+        .await
+        .unwrap();
+
+    for status in [RFCStatus::Scheduled, status] {
+        entities::changes::update(rfc.id, update_status(status), &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    rfc.id
+}
+
+fn update_status(status: RFCStatus) -> RFCUpdateset {
+    RFCUpdateset {
+        title: None,
+        r#type: None,
+        status: Some(Some(status)),
+        created_at: None,
+        finished_at: None,
+        requester_id: None,
+        description: None,
+        planned_start_at: None,
+        planned_end_at: None,
+        actual_start_at: None,
+        actual_end_at: None,
+        implementation_plan: None,
+        backout_plan: None,
+        test_plan: None,
+    }
+}
+
 fn create_basic_createset() -> CIChangeCreateset {
     CIChangeCreateset {
         implementation_timedate: "2023-09-15T12:34:56Z".parse().unwrap(),
         documentation: String::from("docs.local/testing/001.pdf"),
+        rfc_id: None,
     }
 }
 
@@ -41,6 +108,7 @@ fn create_basic_updateset() -> CIChangeUpdateset {
     CIChangeUpdateset {
         implementation_timedate: Some(Some("2023-09-14T12:34:36Z".parse().unwrap())),
         documentation: Some(Some(String::from("docs.local/testing/002.pdf"))),
+        rfc_id: None,
     }
 }
 
@@ -115,6 +183,87 @@ async fn test_create_success(context: &DbTestContext) {
         eq(createset.implementation_timedate)
     );
     assert_that!(change.documentation, eq(&createset.documentation));
+    assert_that!(change.rfc_id, none());
+    assert_that!(change.unauthorized, eq(true));
+}
+
+#[db_test]
+async fn test_create_with_rfc(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id, RFCStatus::Scheduled).await;
+
+    let createset = CIChangeCreateset {
+        rfc_id: Some(rfc_id),
+        ..create_basic_createset()
+    };
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/changes", ci_id))
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let change = response.into_body().into_json::<CIChange>().await;
+    assert_that!(change.rfc_id, some(eq(rfc_id)));
+    assert_that!(change.unauthorized, eq(false));
+}
+
+#[db_test]
+async fn test_create_invalid_rfc(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let other_ci_id = post_ci(context).await;
+    // Affects another item.
+    let rfc_id = post_rfc(context, other_ci_id, RFCStatus::Scheduled).await;
+    // Not approved.
+    let requester = entities::users::load_by_username("requester", &context.db_pool)
+        .await
+        .unwrap();
+    let withdrawn = entities::changes::create(
+        RFCCreateset {
+            title: String::from("Withdrawn RFC"),
+            r#type: Some(RFCType::Standard),
+            status: Some(RFCStatus::Draft),
+            created_at: None,
+            finished_at: None,
+            requester_id: requester.id,
+            description: String::from("This is a fictional RFC made for testing."),
+            planned_start_at: None,
+            planned_end_at: None,
+            implementation_plan: Some(String::from("Roll out the change.")),
+            backout_plan: Some(String::from("Undo the change.")),
+            test_plan: Some(String::from("Check that it works.")),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::changes::ci_relations::create(
+        withdrawn.id,
+        RFCCICreateset { ci_id },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    for rfc_id in [rfc_id, withdrawn.id, Uuid::new_v4()] {
+        let createset = CIChangeCreateset {
+            rfc_id: Some(rfc_id),
+            ..create_basic_createset()
+        };
+        let response = context
+            .app
+            .request(&format!("/api/configitems/{}/changes", ci_id))
+            .method(Method::POST)
+            .body(Body::from(json!(createset).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
 }
 
 #[db_test]
@@ -156,6 +305,98 @@ async fn test_read_all(context: &DbTestContext) {
     assert_that!(changes.first().unwrap().ci_id, eq(ci_id));
 }
 
+#[db_test]
+async fn test_read_all_unauthorized(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id, RFCStatus::Scheduled).await;
+    let unauthorized = changes::create(ci_id, create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let authorized = changes::create(
+        ci_id,
+        CIChangeCreateset {
+            rfc_id: Some(rfc_id),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    for (filter, expected) in [(true, &unauthorized), (false, &authorized)] {
+        let response = context
+            .app
+            .request(&format!(
+                "/api/configitems/{}/changes?unauthorized={}",
+                ci_id, filter
+            ))
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+        let changes: Vec<CIChange> = response.into_body().into_json().await;
+        assert_that!(changes, elements_are![eq(expected)]);
+    }
+}
+
+#[db_test]
+async fn test_rfc_implementation(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id, RFCStatus::Scheduled).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({
+                "status": "implemented",
+                "actual_start_at": "2030-03-01T22:10:00Z",
+                "actual_end_at": "2030-03-01T23:30:00Z",
+            })
+            .to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/cichanges", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let changes: Vec<CIChange> = response.into_body().into_json().await;
+    assert_that!(changes, len(eq(1)));
+    let change = changes.first().unwrap();
+    assert_that!(change.ci_id, eq(ci_id));
+    assert_that!(change.rfc_id, some(eq(rfc_id)));
+    assert_that!(change.unauthorized, eq(false));
+    assert_that!(
+        change.implementation_timedate,
+        eq("2030-03-01T23:30:00Z".parse::<DateTime<Utc>>().unwrap())
+    );
+
+    // Changes trace back to it.
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_rfc_ci_changes_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/cichanges", Uuid::new_v4()))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
 #[db_test]
 async fn test_update_nonexistent_bad_change(context: &DbTestContext) {
     let ci_id = post_ci(context).await;
@@ -282,6 +523,7 @@ async fn test_update_nothing(context: &DbTestContext) {
     let updateset = CIChangeUpdateset {
         implementation_timedate: None,
         documentation: None,
+        rfc_id: None,
     };
     let payload = json!(updateset);
 
@@ -302,6 +544,41 @@ async fn test_update_nothing(context: &DbTestContext) {
     assert_that!(change_after, eq(&change_before));
 }
 
+#[db_test]
+async fn test_update_rfc(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id, RFCStatus::Scheduled).await;
+    let change = changes::create(ci_id, create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/changes/{}", ci_id, change.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "rfc_id": rfc_id }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let change: CIChange = response.into_body().into_json().await;
+    assert_that!(change.rfc_id, some(eq(rfc_id)));
+    assert_that!(change.unauthorized, eq(false));
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/changes/{}", ci_id, change.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "rfc_id": null }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let change: CIChange = response.into_body().into_json().await;
+    assert_that!(change.rfc_id, none());
+    assert_that!(change.unauthorized, eq(true));
+}
+
 #[db_test]
 async fn test_delete_nonexistent(context: &DbTestContext) {
     let response = context
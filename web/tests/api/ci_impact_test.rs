@@ -0,0 +1,250 @@
+use chrono::{Duration, Utc};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    changes::{incident_relations::RFCIncidentCreateset, RFCStatus, RFCType, RFCUpdateset},
+    configuration::{
+        changes::CIChangeCreateset,
+        impact::CIImpact,
+        relations::{CIRelationCreateset, CIRelationType},
+        CIStatus, ConfigItemCreateset,
+    },
+    incidents::{IncidentImpact, IncidentResolutionCode, IncidentStatus},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use uuid::Uuid;
+
+async fn post_ci(context: &DbTestContext, name: &str, r#type: &str) -> Uuid {
+    let createset = ConfigItemCreateset {
+        name: String::from(name),
+        status: Some(CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: Some(String::from(r#type)),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn depends_on(context: &DbTestContext, source_id: Uuid, target_id: Uuid) {
+    let createset = CIRelationCreateset {
+        target_id,
+        r#type: CIRelationType::DependsOn,
+        description: None,
+    };
+
+    entities::configuration::relations::create(source_id, createset, &context.db_pool)
+        .await
+        .unwrap();
+}
+
+async fn post_incident(context: &DbTestContext, ci_id: Uuid, status: IncidentStatus) -> Uuid {
+    let resolved = status.is_resolved();
+    let createset = entities::incidents::IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: Some(status),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: IncidentImpact::Low,
+        urgency: entities::incidents::IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: resolved.then_some(IncidentResolutionCode::Solved),
+        resolution_notes: resolved.then(|| String::from("Fixed")),
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    let incident = entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+    entities::incidents::ci_relations::create(incident.id, ci_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    incident.id
+}
+
+/// Create a standard RFC related to `incident_id`, and move it through `statuses`.
+async fn post_rfc(context: &DbTestContext, incident_id: Uuid, statuses: &[RFCStatus]) -> Uuid {
+    let requester = entities::users::create(
+        entities::users::UserCreateset {
+            username: format!("dev-{}", Uuid::new_v4()),
+            full_name: String::from("Me the dev"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let createset = entities::changes::RFCCreateset {
+        title: String::from("RFC for Testing"),
+        r#type: Some(RFCType::Standard),
+        status: Some(RFCStatus::Submitted),
+        created_at: None,
+        finished_at: None,
+        requester_id: requester.id,
+        description: String::from("This is a fake rfc made for testing."),
+        planned_start_at: Some(Utc::now() + Duration::days(1)),
+        planned_end_at: Some(Utc::now() + Duration::days(2)),
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
+    };
+
+    let rfc = entities::changes::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+    entities::changes::incident_relations::create(
+        rfc.id,
+        RFCIncidentCreateset {
+            incident_id,
+            relation_type: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    for status in statuses {
+        let updateset = RFCUpdateset {
+            title: None,
+            r#type: None,
+            status: Some(Some(*status)),
+            created_at: None,
+            finished_at: None,
+            requester_id: None,
+            description: None,
+            planned_start_at: None,
+            planned_end_at: None,
+            actual_start_at: None,
+            actual_end_at: None,
+            implementation_plan: None,
+            backout_plan: None,
+            test_plan: None,
+        };
+        entities::changes::update(rfc.id, updateset, &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    rfc.id
+}
+
+async fn post_ci_change(context: &DbTestContext, ci_id: Uuid, in_days: i64) -> Uuid {
+    let createset = CIChangeCreateset {
+        implementation_timedate: Utc::now() + Duration::days(in_days),
+        documentation: String::from("docs.local/changes/test.pdf"),
+        rfc_id: None,
+    };
+
+    entities::configuration::changes::create(ci_id, createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn get_impact(context: &DbTestContext, uri: &str) -> CIImpact {
+    let response = context.app.request(uri).send().await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    response.into_body().into_json::<CIImpact>().await
+}
+
+#[db_test]
+async fn test_impact(context: &DbTestContext) {
+    let db = post_ci(context, "Database", "database").await;
+    let billing = post_ci(context, "Billing", "Service").await;
+    let shop = post_ci(context, "Shop", "service").await;
+    let printer = post_ci(context, "Printer", "printer").await;
+    depends_on(context, billing, db).await;
+    depends_on(context, shop, billing).await;
+    // The database doesn't depend on the printer, so it's not affected.
+    depends_on(context, db, printer).await;
+
+    let open_incident = post_incident(context, billing, IncidentStatus::InProgress).await;
+    let resolved_incident = post_incident(context, shop, IncidentStatus::Resolved).await;
+    post_incident(context, printer, IncidentStatus::New).await;
+    let open_rfc = post_rfc(context, open_incident, &[]).await;
+    post_rfc(context, open_incident, &[RFCStatus::Closed]).await;
+    post_rfc(
+        context,
+        open_incident,
+        &[RFCStatus::Scheduled, RFCStatus::Implemented],
+    )
+    .await;
+    let other_rfc = post_rfc(context, resolved_incident, &[RFCStatus::Scheduled]).await;
+    let scheduled = post_ci_change(context, db, 2).await;
+    post_ci_change(context, db, -2).await;
+
+    let impact = get_impact(context, &format!("/api/configitems/{}/impact", db)).await;
+
+    assert_that!(impact.root_id, eq(db));
+    let items: Vec<(Uuid, i32)> = impact
+        .affected_items
+        .iter()
+        .map(|n| (n.config_item.id, n.depth))
+        .collect();
+    assert_that!(
+        items,
+        elements_are![eq(&(db, 0)), eq(&(billing, 1)), eq(&(shop, 2))]
+    );
+    assert_that!(impact.affected_services, eq(2));
+    let incidents: Vec<Uuid> = impact.open_incidents.iter().map(|i| i.id).collect();
+    assert_that!(incidents, elements_are![eq(&open_incident)]);
+    let rfcs: Vec<Uuid> = impact.open_rfcs.iter().map(|r| r.id).collect();
+    assert_that!(rfcs, unordered_elements_are![eq(&open_rfc), eq(&other_rfc)]);
+    let changes: Vec<Uuid> = impact.scheduled_changes.iter().map(|c| c.id).collect();
+    assert_that!(changes, elements_are![eq(&scheduled)]);
+    assert_that!(impact.suggested_impact, eq(IncidentImpact::High));
+
+    let impact = get_impact(context, &format!("/api/configitems/{}/impact?depth=1", db)).await;
+
+    assert_that!(impact.affected_items, len(eq(2)));
+    assert_that!(impact.affected_services, eq(1));
+    assert_that!(impact.suggested_impact, eq(IncidentImpact::Medium));
+}
+
+#[db_test]
+async fn test_impact_isolated(context: &DbTestContext) {
+    let printer = post_ci(context, "Printer", "printer").await;
+
+    let impact = get_impact(context, &format!("/api/configitems/{}/impact", printer)).await;
+
+    assert_that!(impact.affected_items, len(eq(1)));
+    assert_that!(impact.open_incidents, is_empty());
+    // Minimal isn't in use by the default priority matrix.
+    assert_that!(impact.suggested_impact, eq(IncidentImpact::Low));
+}
+
+#[db_test]
+async fn test_impact_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/impact", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_impact_invalid_depth(context: &DbTestContext) {
+    let printer = post_ci(context, "Printer", "printer").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/impact?depth=11", printer))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
@@ -0,0 +1,351 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::configuration::{
+    self,
+    relations::{self, CIGraph, CIGraphDirection, CIRelation, CIRelationCreateset, CIRelationType},
+    CIStatus, ConfigItemCreateset,
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_ci(context: &DbTestContext, name: &str) -> Uuid {
+    let createset = ConfigItemCreateset {
+        name: String::from(name),
+        status: Some(CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: None,
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn relate(
+    context: &DbTestContext,
+    source_id: Uuid,
+    target_id: Uuid,
+    r#type: CIRelationType,
+) -> CIRelation {
+    let createset = CIRelationCreateset {
+        target_id,
+        r#type,
+        description: None,
+    };
+
+    relations::create(source_id, createset, &context.db_pool)
+        .await
+        .unwrap()
+}
+
+async fn post_relation(
+    context: &DbTestContext,
+    source_id: Uuid,
+    payload: serde_json::Value,
+) -> axum::response::Response {
+    context
+        .app
+        .request(&format!("/api/configitems/{}/relations", source_id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+
+    let response = post_relation(
+        context,
+        app,
+        json!({"target_id": vm, "type": "runson", "description": "Production"}),
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let relation = response.into_body().into_json::<CIRelation>().await;
+    assert_that!(relation.source_id, eq(app));
+    assert_that!(relation.target_id, eq(vm));
+    assert_that!(relation.r#type, eq(CIRelationType::RunsOn));
+    assert_that!(relation.description, eq("Production"));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+    relate(context, app, vm, CIRelationType::RunsOn).await;
+
+    for payload in [
+        // Unknown target.
+        json!({"target_id": Uuid::new_v4(), "type": "dependson"}),
+        // Related to itself.
+        json!({"target_id": app, "type": "dependson"}),
+        // Already related.
+        json!({"target_id": vm, "type": "runson"}),
+        json!({"target_id": vm, "type": "poweredby"}),
+    ] {
+        let response = post_relation(context, app, payload).await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_nonexistent_source(context: &DbTestContext) {
+    let vm = post_ci(context, "VM").await;
+
+    let response = post_relation(
+        context,
+        Uuid::new_v4(),
+        json!({"target_id": vm, "type": "runson"}),
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_create_cycle(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+    let cluster = post_ci(context, "Cluster").await;
+    relate(context, app, vm, CIRelationType::RunsOn).await;
+    relate(context, vm, cluster, CIRelationType::HostedOn).await;
+
+    let response = post_relation(
+        context,
+        cluster,
+        json!({"target_id": app, "type": "hostedon"}),
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Non-hierarchical relations may go both ways.
+    let response = post_relation(
+        context,
+        cluster,
+        json!({"target_id": app, "type": "connectsto"}),
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+}
+
+#[db_test]
+async fn test_update_cycle(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+    relate(context, app, vm, CIRelationType::RunsOn).await;
+    let back = relate(context, vm, app, CIRelationType::ConnectsTo).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/relations/{}", vm, back.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({"type": "runson"}).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_read_all(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+    let db = post_ci(context, "Database").await;
+    relate(context, app, vm, CIRelationType::RunsOn).await;
+    relate(context, app, db, CIRelationType::DependsOn).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/relations", vm))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let vm_relations = response.into_body().into_json::<Vec<CIRelation>>().await;
+    assert_that!(vm_relations, len(eq(1)));
+    assert_that!(vm_relations[0].source_id, eq(app));
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/relations", app))
+        .send()
+        .await;
+
+    let app_relations = response.into_body().into_json::<Vec<CIRelation>>().await;
+    assert_that!(app_relations, len(eq(2)));
+}
+
+#[db_test]
+async fn test_update_and_delete(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let db = post_ci(context, "Database").await;
+    let relation = relate(context, app, db, CIRelationType::ConnectsTo).await;
+    let uri = format!("/api/configitems/{}/relations/{}", db, relation.id);
+
+    let response = context
+        .app
+        .request(&uri)
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({"type": "dependson", "description": "Reads and writes"}).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<CIRelation>().await;
+    assert_that!(updated.r#type, eq(CIRelationType::DependsOn));
+    assert_that!(updated.description, eq("Reads and writes"));
+
+    let response = context
+        .app
+        .request(&uri)
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let response = context.app.request(&uri).send().await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_read_one_of_other_ci(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let db = post_ci(context, "Database").await;
+    let other = post_ci(context, "Printer").await;
+    let relation = relate(context, app, db, CIRelationType::DependsOn).await;
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/configitems/{}/relations/{}",
+            other, relation.id
+        ))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_delete_ci_removes_relations(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+    relate(context, app, vm, CIRelationType::RunsOn).await;
+
+    configuration::delete(vm, &context.db_pool).await.unwrap();
+
+    let app_relations = relations::load_all(app, &context.db_pool).await.unwrap();
+    assert_that!(app_relations, is_empty());
+}
+
+#[db_test]
+async fn test_graph(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+    let cluster = post_ci(context, "Cluster").await;
+    let lb = post_ci(context, "Load balancer").await;
+    relate(context, app, vm, CIRelationType::RunsOn).await;
+    relate(context, vm, cluster, CIRelationType::HostedOn).await;
+    relate(context, lb, app, CIRelationType::DependsOn).await;
+    // Peer links may form cycles, which must not be walked twice.
+    relate(context, cluster, lb, CIRelationType::ConnectsTo).await;
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/configitems/{}/graph?direction=upstream&depth=2",
+            app
+        ))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let graph = response.into_body().into_json::<CIGraph>().await;
+    assert_that!(graph.direction, eq(CIGraphDirection::Upstream));
+    let nodes: Vec<(Uuid, i32)> = graph
+        .nodes
+        .iter()
+        .map(|n| (n.config_item.id, n.depth))
+        .collect();
+    assert_that!(
+        nodes,
+        elements_are![eq(&(app, 0)), eq(&(vm, 1)), eq(&(cluster, 2))]
+    );
+    assert_that!(graph.relations, len(eq(2)));
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/graph", cluster))
+        .send()
+        .await;
+
+    let graph = response.into_body().into_json::<CIGraph>().await;
+    assert_that!(graph.direction, eq(CIGraphDirection::Downstream));
+    let nodes: Vec<(Uuid, i32)> = graph
+        .nodes
+        .iter()
+        .map(|n| (n.config_item.id, n.depth))
+        .collect();
+    assert_that!(
+        nodes,
+        elements_are![eq(&(cluster, 0)), eq(&(vm, 1)), eq(&(app, 2)), eq(&(lb, 3))]
+    );
+    assert_that!(graph.relations, len(eq(3)));
+}
+
+#[db_test]
+async fn test_graph_invalid_depth(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/graph?depth=0", app))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_create_needs_configitems_write(context: &DbTestContext) {
+    let app = post_ci(context, "App server").await;
+    let vm = post_ci(context, "VM").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}/relations", app))
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(
+            json!({"target_id": vm, "type": "runson"}).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
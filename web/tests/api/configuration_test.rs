@@ -8,7 +8,9 @@ use hyper::StatusCode;
 use itil_back_db::entities::{
     self,
     configuration::{self, CIStatus, ConfigItem, ConfigItemCreateset, ConfigItemUpdateset},
+    teams::{self, TeamCreateset},
 };
+use itil_back_db::pagination::Page;
 use itil_back_macros::db_test;
 use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
 use serde_json::json;
@@ -20,7 +22,7 @@ fn create_basic_createset() -> ConfigItemCreateset {
         status: Some(CIStatus::Maintenance),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
         r#type: Some(String::from("Testing Item")),
-        owner: Some(String::from("Testing Department")),
+        owner_team_id: None,
         description: String::from("This is a fictional item made for testing."),
     }
 }
@@ -31,7 +33,7 @@ fn create_basic_updateset() -> ConfigItemUpdateset {
         status: Some(Some(CIStatus::Testing)),
         created_at: Some(Some("2023-09-15T12:34:58Z".parse().unwrap())),
         r#type: Some(Some(String::from("Updating Item"))),
-        owner: Some(Some(String::from("Update Department"))),
+        owner_team_id: Some(None),
         description: Some(Some(String::from(
             "This is a fictional item made for updating.",
         ))),
@@ -55,7 +57,7 @@ async fn test_create_invalid(context: &DbTestContext) {
         ..createset.clone()
     });
     sets.push(entities::configuration::ConfigItemCreateset {
-        owner: Some(String::from(&"x".repeat(1025))),
+        owner_team_id: Some(Uuid::new_v4()),
         ..createset.clone()
     });
     sets.push(entities::configuration::ConfigItemCreateset {
@@ -116,7 +118,7 @@ async fn test_create_success(context: &DbTestContext) {
     assert_that!(ci.status, eq(createset.status.unwrap()));
     assert_that!(ci.created_at, eq(createset.created_at.unwrap()));
     assert_that!(ci.r#type, eq(&createset.r#type));
-    assert_that!(ci.owner, eq(&createset.owner));
+    assert_that!(ci.owner_team_id, eq(createset.owner_team_id));
     assert_that!(ci.description, eq(&createset.description));
 
     let configitems = configuration::load_all(&context.db_pool).await.unwrap();
@@ -177,18 +179,6 @@ async fn test_create_border_success(context: &DbTestContext) {
         r#type: Some(String::from("")),
         ..createset.clone()
     });
-    sets.push(ConfigItemCreateset {
-        owner: None,
-        ..createset.clone()
-    });
-    sets.push(ConfigItemCreateset {
-        owner: Some(String::from("")),
-        ..createset.clone()
-    });
-    sets.push(ConfigItemCreateset {
-        owner: Some(String::from(&"x".repeat(1024))),
-        ..createset.clone()
-    });
     sets.push(ConfigItemCreateset {
         description: String::from(""),
         ..createset.clone()
@@ -268,9 +258,38 @@ async fn test_read_all(context: &DbTestContext) {
 
     assert_that!(response.status(), eq(StatusCode::OK));
 
-    let configitems: Vec<ConfigItem> = response.into_body().into_json::<Vec<ConfigItem>>().await;
-    assert_that!(configitems, len(eq(1)));
-    assert_that!(configitems.first().unwrap(), eq(&ci));
+    let page: Page<ConfigItem> = response.into_body().into_json::<Page<ConfigItem>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items, len(eq(1)));
+    assert_that!(page.items.first().unwrap(), eq(&ci));
+}
+
+#[db_test]
+async fn test_read_all_sorted_by_name(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    for name in ["b", "c", "a"] {
+        configuration::create(
+            ConfigItemCreateset {
+                name: String::from(name),
+                ..createset.clone()
+            },
+            &context.db_pool,
+        )
+        .await
+        .unwrap();
+    }
+
+    let response = context
+        .app
+        .request("/api/configitems?sort_by=name&order=desc")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<ConfigItem> = response.into_body().into_json::<Page<ConfigItem>>().await;
+    let names: Vec<&str> = page.items.iter().map(|ci| ci.name.as_str()).collect();
+    assert_that!(names, eq(&vec!["c", "b", "a"]));
 }
 
 #[db_test]
@@ -325,7 +344,7 @@ async fn test_update_invalid(context: &DbTestContext) {
         ..updateset.clone()
     });
     sets.push(ConfigItemUpdateset {
-        owner: Some(Some(String::from(&"x".repeat(1025)))),
+        owner_team_id: Some(Some(Uuid::new_v4())),
         ..updateset.clone()
     });
     sets.push(ConfigItemUpdateset {
@@ -437,7 +456,7 @@ async fn test_update_success(context: &DbTestContext) {
     assert_that!(ci.status, eq(updateset.status.unwrap().unwrap()));
     assert_that!(ci.created_at, eq(updateset.created_at.unwrap().unwrap()));
     assert_that!(ci.r#type, eq(&updateset.r#type.unwrap()));
-    assert_that!(ci.owner, eq(&updateset.owner.unwrap()));
+    assert_that!(ci.owner_team_id, eq(updateset.owner_team_id.unwrap()));
     assert_that!(ci.description, eq(&updateset.description.unwrap().unwrap()));
 
     let ci_after = configuration::load(ci.id, &context.db_pool).await.unwrap();
@@ -446,14 +465,26 @@ async fn test_update_success(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_set_nulls(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("Networking"),
+            description: String::from(""),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let createset = ConfigItemCreateset {
+        owner_team_id: Some(team.id),
+        ..create_basic_createset()
+    };
     let ci = configuration::create(createset, &context.db_pool)
         .await
         .unwrap();
 
     let updateset = ConfigItemUpdateset {
         r#type: Some(None),
-        owner: Some(None),
+        owner_team_id: Some(None),
         ..create_basic_updateset()
     };
     let payload = json!(updateset);
@@ -470,7 +501,7 @@ async fn test_update_set_nulls(context: &DbTestContext) {
     assert_that!(response.status(), eq(StatusCode::OK));
     let ci_after: ConfigItem = response.into_body().into_json::<ConfigItem>().await;
     assert!(ci_after.r#type.is_none());
-    assert!(ci_after.owner.is_none());
+    assert!(ci_after.owner_team_id.is_none());
 }
 
 #[db_test]
@@ -485,7 +516,7 @@ async fn test_update_nothing(context: &DbTestContext) {
         status: None,
         created_at: None,
         r#type: None,
-        owner: None,
+        owner_team_id: None,
         description: None,
     };
     let payload = json!(updateset);
@@ -0,0 +1,200 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::freeze_periods::{
+    self, FreezePeriod, FreezePeriodCreateset, FreezePeriodUpdateset,
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> FreezePeriodCreateset {
+    FreezePeriodCreateset {
+        name: String::from("Year-end closing"),
+        description: String::from("Finance closes the fiscal year."),
+        starts_at: "2030-12-20T00:00:00Z".parse().unwrap(),
+        ends_at: "2031-01-05T00:00:00Z".parse().unwrap(),
+    }
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+
+    let response = context
+        .app
+        .request("/api/freezeperiods")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let period = response.into_body().into_json::<FreezePeriod>().await;
+    assert_that!(period.name, eq(&createset.name));
+    assert_that!(period.starts_at, eq(createset.starts_at));
+    assert_that!(period.ends_at, eq(createset.ends_at));
+
+    let period_read = freeze_periods::load(period.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(period_read, eq(&period));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    let sets = vec![
+        FreezePeriodCreateset {
+            name: String::from(""),
+            ..createset.clone()
+        },
+        FreezePeriodCreateset {
+            ends_at: createset.starts_at,
+            ..createset.clone()
+        },
+    ];
+
+    for set in sets {
+        let response = context
+            .app
+            .request("/api/freezeperiods")
+            .method(Method::POST)
+            .body(Body::from(json!(set).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_forbidden(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/freezeperiods")
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!(create_basic_createset()).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_read_all_ending_after(context: &DbTestContext) {
+    let past = freeze_periods::create(
+        FreezePeriodCreateset {
+            starts_at: "2020-12-20T00:00:00Z".parse().unwrap(),
+            ends_at: "2021-01-05T00:00:00Z".parse().unwrap(),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let upcoming = freeze_periods::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context.app.request("/api/freezeperiods").send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response.into_body().into_json::<Page<FreezePeriod>>().await;
+    assert_that!(page.items, elements_are![eq(&past), eq(&upcoming)]);
+
+    let response = context
+        .app
+        .request("/api/freezeperiods?ends_after=2025-01-01T00:00:00Z")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response.into_body().into_json::<Page<FreezePeriod>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items, elements_are![eq(&upcoming)]);
+}
+
+#[db_test]
+async fn test_update(context: &DbTestContext) {
+    let period = freeze_periods::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let updateset = FreezePeriodUpdateset {
+        name: Some(Some(String::from("Extended closing"))),
+        description: None,
+        starts_at: None,
+        ends_at: Some(Some("2031-01-10T00:00:00Z".parse().unwrap())),
+    };
+    let response = context
+        .app
+        .request(&format!("/api/freezeperiods/{}", period.id))
+        .method(Method::PUT)
+        .body(Body::from(json!(updateset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<FreezePeriod>().await;
+    assert_that!(updated.name, eq("Extended closing"));
+    assert_that!(updated.starts_at, eq(period.starts_at));
+    assert_that!(updated.ends_at, eq(updateset.ends_at.unwrap().unwrap()));
+
+    // Starting after the current end.
+    for payload in [
+        json!({ "starts_at": "2031-02-01T00:00:00Z" }),
+        json!({ "ends_at": null }),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/freezeperiods/{}", period.id))
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_update_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/freezeperiods/{}", Uuid::new_v4()))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "name": "Nope" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_delete(context: &DbTestContext) {
+    let period = freeze_periods::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/freezeperiods/{}", period.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = freeze_periods::load(period.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
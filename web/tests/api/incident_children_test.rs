@@ -0,0 +1,302 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    incidents::{
+        children::IncidentChildrenCreateset, Incident, IncidentCreateset, IncidentImpact,
+        IncidentResolutionCode, IncidentStatus, IncidentUrgency,
+    },
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> IncidentCreateset {
+    IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: Some(IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("This is a fictional incident made for testing."),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    }
+}
+
+async fn post_incident(context: &DbTestContext) -> Uuid {
+    entities::incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_children(
+    context: &DbTestContext,
+    parent_id: Uuid,
+    incident_ids: Vec<Uuid>,
+) -> axum::response::Response {
+    let payload = json!(IncidentChildrenCreateset { incident_ids });
+
+    context
+        .app
+        .request(&format!("/api/incidents/{}/children", parent_id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn read_incident(context: &DbTestContext, id: Uuid) -> Incident {
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    response.into_body().into_json::<Incident>().await
+}
+
+#[db_test]
+async fn test_create_children_success(context: &DbTestContext) {
+    let parent_id = post_incident(context).await;
+    let first_id = post_incident(context).await;
+    let second_id = post_incident(context).await;
+
+    let response = post_children(context, parent_id, vec![first_id, second_id, first_id]).await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let children = response.into_body().into_json::<Vec<Incident>>().await;
+    let child_ids: Vec<Uuid> = children.iter().map(|c| c.id).collect();
+    assert_that!(
+        child_ids,
+        unordered_elements_are![eq(&first_id), eq(&second_id)]
+    );
+    assert_that!(
+        children,
+        each(field!(Incident.parent_id, some(eq(&parent_id))))
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/children", parent_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let children = response.into_body().into_json::<Vec<Incident>>().await;
+    assert_that!(children, len(eq(2)));
+}
+
+#[db_test]
+async fn test_create_children_moves_children_of_other_incidents(context: &DbTestContext) {
+    let old_parent_id = post_incident(context).await;
+    let new_parent_id = post_incident(context).await;
+    let child_id = post_incident(context).await;
+
+    let response = post_children(context, old_parent_id, vec![child_id]).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let response = post_children(context, new_parent_id, vec![child_id]).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let child = read_incident(context, child_id).await;
+    assert_that!(child.parent_id, some(eq(new_parent_id)));
+    let children = entities::incidents::children::load_all(old_parent_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(children, is_empty());
+}
+
+#[db_test]
+async fn test_create_children_nonexistent_parent(context: &DbTestContext) {
+    let child_id = post_incident(context).await;
+
+    let response = post_children(context, Uuid::new_v4(), vec![child_id]).await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_create_children_invalid(context: &DbTestContext) {
+    let parent_id = post_incident(context).await;
+    let child_id = post_incident(context).await;
+
+    // No children at all.
+    let response = post_children(context, parent_id, vec![]).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Nonexistent children.
+    let response = post_children(context, parent_id, vec![child_id, Uuid::new_v4()]).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // The parent itself.
+    let response = post_children(context, parent_id, vec![child_id, parent_id]).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Nothing got linked.
+    let child = read_incident(context, child_id).await;
+    assert_that!(child.parent_id, none());
+}
+
+#[db_test]
+async fn test_create_children_rejects_nesting(context: &DbTestContext) {
+    let parent_id = post_incident(context).await;
+    let child_id = post_incident(context).await;
+    let other_id = post_incident(context).await;
+    let response = post_children(context, parent_id, vec![child_id]).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    // Children can't be parents.
+    let response = post_children(context, child_id, vec![other_id]).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Parents can't be children.
+    let response = post_children(context, other_id, vec![parent_id]).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let other = read_incident(context, other_id).await;
+    assert_that!(other.parent_id, none());
+    let parent = read_incident(context, parent_id).await;
+    assert_that!(parent.parent_id, none());
+}
+
+#[db_test]
+async fn test_parent_id_rejects_nesting(context: &DbTestContext) {
+    let parent_id = post_incident(context).await;
+    let child_id = post_incident(context).await;
+    let other_id = post_incident(context).await;
+    let response = post_children(context, parent_id, vec![child_id]).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    // New Incidents can't be children of children.
+    let payload = json!(IncidentCreateset {
+        parent_id: Some(child_id),
+        ..create_basic_createset()
+    });
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Parents can't become children.
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", parent_id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "parent_id": other_id }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Nonexistent parents.
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", other_id))
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({ "parent_id": Uuid::new_v4() }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Other Incidents may still be linked.
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", other_id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "parent_id": parent_id }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let other = response.into_body().into_json::<Incident>().await;
+    assert_that!(other.parent_id, some(eq(parent_id)));
+}
+
+#[db_test]
+async fn test_read_children_nonexistent_parent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/children", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_resolving_parent_resolves_children(context: &DbTestContext) {
+    let parent_id = post_incident(context).await;
+    let open_id = post_incident(context).await;
+    let closed = entities::incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::Closed),
+            resolution_code: Some(IncidentResolutionCode::Cancelled),
+            resolution_notes: Some(String::from("Reported by mistake.")),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let response = post_children(context, parent_id, vec![open_id, closed.id]).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let payload = json!({
+        "status": "resolved",
+        "resolution_code": "workaround",
+        "resolution_notes": "Rerouted traffic to the backup link.",
+    });
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", parent_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let open = read_incident(context, open_id).await;
+    assert_that!(
+        open,
+        matches_pattern!(Incident {
+            status: eq(&IncidentStatus::Resolved),
+            resolved_at: some(anything()),
+            resolution_code: some(eq(&IncidentResolutionCode::Workaround)),
+            resolution_notes: some(eq("Rerouted traffic to the backup link.")),
+            ..
+        })
+    );
+
+    // Children resolved already are left as they were.
+    let closed = read_incident(context, closed.id).await;
+    assert_that!(
+        closed,
+        matches_pattern!(Incident {
+            status: eq(&IncidentStatus::Closed),
+            resolution_code: some(eq(&IncidentResolutionCode::Cancelled)),
+            resolution_notes: some(eq("Reported by mistake.")),
+            ..
+        })
+    );
+}
@@ -0,0 +1,412 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    audit::{AuditAction, AuditEntity},
+    changes::incident_relations::RFCIncidentCreateset,
+    incidents::{
+        children::IncidentChildrenCreateset, merge::IncidentMergeset, Incident, IncidentCreateset,
+        IncidentImpact, IncidentResolutionCode, IncidentStatus, IncidentUrgency,
+    },
+    timeline::{TimelineEntity, TimelineEntry, TimelineEntryCreateset, TimelineVisibility},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> IncidentCreateset {
+    IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: Some(IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("This is a fictional incident made for testing."),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    }
+}
+
+async fn post_incident(context: &DbTestContext) -> Uuid {
+    entities::incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_ci(context: &DbTestContext) -> Uuid {
+    let createset = entities::configuration::ConfigItemCreateset {
+        name: String::from("Testing CI for merging Incidents"),
+        status: Some(entities::configuration::CIStatus::Active),
+        created_at: None,
+        r#type: Some(String::from("Test CI")),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_problem(context: &DbTestContext) -> Uuid {
+    let createset = entities::problems::ProblemCreateset {
+        title: String::from("Problem for Testing"),
+        status: Some(entities::problems::ProblemStatus::Open),
+        detection_timedate: None,
+        description: String::from("This is a fake problem made for testing."),
+        causes: String::from("I need to test this."),
+        workarounds: None,
+        resolutions: None,
+    };
+
+    entities::problems::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_rfc(context: &DbTestContext) -> Uuid {
+    let requester = entities::users::create(
+        entities::users::UserCreateset {
+            username: format!("dev-{}", Uuid::new_v4()),
+            full_name: String::from("Me the dev"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let createset = entities::changes::RFCCreateset {
+        title: String::from("RFC for Testing"),
+        r#type: None,
+        status: Some(entities::changes::RFCStatus::Submitted),
+        created_at: None,
+        finished_at: None,
+        requester_id: requester.id,
+        description: String::from("This is a fake rfc made for testing."),
+        planned_start_at: None,
+        planned_end_at: None,
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
+    };
+
+    entities::changes::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn merge(context: &DbTestContext, id: Uuid, survivor_id: Uuid) -> axum::response::Response {
+    let payload = json!(IncidentMergeset { survivor_id });
+
+    context
+        .app
+        .request(&format!("/api/incidents/{}/merge", id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn read_incident(context: &DbTestContext, id: Uuid) -> Incident {
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    response.into_body().into_json::<Incident>().await
+}
+
+#[db_test]
+async fn test_merge_success(context: &DbTestContext) {
+    let duplicate_id = post_incident(context).await;
+    let survivor_id = post_incident(context).await;
+
+    let shared_ci_id = post_ci(context).await;
+    let ci_id = post_ci(context).await;
+    for incident_id in [duplicate_id, survivor_id] {
+        entities::incidents::ci_relations::create(incident_id, shared_ci_id, &context.db_pool)
+            .await
+            .unwrap();
+    }
+    entities::incidents::ci_relations::create(duplicate_id, ci_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let problem_id = post_problem(context).await;
+    entities::problems::incident_relations::create(problem_id, duplicate_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let rfc_id = post_rfc(context).await;
+    entities::changes::incident_relations::create(
+        rfc_id,
+        RFCIncidentCreateset {
+            incident_id: duplicate_id,
+            relation_type: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    entities::timeline::create(
+        TimelineEntity::Incident,
+        duplicate_id,
+        TimelineEntryCreateset {
+            parent_id: None,
+            visibility: TimelineVisibility::Internal,
+            body: String::from("Restarted the router"),
+        },
+        TEST_SUBJECT,
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = merge(context, duplicate_id, survivor_id).await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let survivor = response.into_body().into_json::<Incident>().await;
+    assert_that!(survivor.id, eq(survivor_id));
+    assert_that!(survivor.status, eq(IncidentStatus::InProgress));
+
+    let duplicate = read_incident(context, duplicate_id).await;
+    assert_that!(
+        duplicate,
+        matches_pattern!(Incident {
+            status: eq(&IncidentStatus::Closed),
+            resolved_at: some(anything()),
+            resolution_code: some(eq(&IncidentResolutionCode::Duplicate)),
+            resolution_notes: some(contains_substring(survivor_id.to_string())),
+            duplicate_of_id: some(eq(&survivor_id)),
+            ..
+        })
+    );
+
+    let ci_ids: Vec<Uuid> =
+        entities::incidents::ci_relations::load_all(survivor_id, &context.db_pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.ci_id)
+            .collect();
+    assert_that!(
+        ci_ids,
+        unordered_elements_are![eq(&shared_ci_id), eq(&ci_id)]
+    );
+    let relations = entities::incidents::ci_relations::load_all(duplicate_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(relations, is_empty());
+
+    let incident_ids: Vec<Uuid> =
+        entities::problems::incident_relations::load_all(problem_id, &context.db_pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.incident_id)
+            .collect();
+    assert_that!(incident_ids, elements_are![eq(&survivor_id)]);
+
+    let incident_ids: Vec<Uuid> =
+        entities::changes::incident_relations::load_all(rfc_id, &context.db_pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.incident_id)
+            .collect();
+    assert_that!(incident_ids, elements_are![eq(&survivor_id)]);
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/timeline", survivor_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let timeline = response
+        .into_body()
+        .into_json::<Page<TimelineEntry>>()
+        .await;
+    let bodies: Vec<String> = timeline.items.into_iter().map(|e| e.body).collect();
+    assert_that!(bodies, contains(eq("Restarted the router")));
+}
+
+#[db_test]
+async fn test_merge_moves_children(context: &DbTestContext) {
+    let duplicate_id = post_incident(context).await;
+    let survivor_id = post_incident(context).await;
+    let child_id = post_incident(context).await;
+    entities::incidents::children::create(
+        duplicate_id,
+        IncidentChildrenCreateset {
+            incident_ids: vec![child_id, survivor_id],
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = merge(context, duplicate_id, survivor_id).await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let survivor = response.into_body().into_json::<Incident>().await;
+    assert_that!(survivor.parent_id, none());
+    let child = read_incident(context, child_id).await;
+    assert_that!(child.parent_id, some(eq(survivor_id)));
+}
+
+#[db_test]
+async fn test_merge_into_child(context: &DbTestContext) {
+    let parent_id = post_incident(context).await;
+    let survivor_id = post_incident(context).await;
+    let duplicate_id = post_incident(context).await;
+    let child_id = post_incident(context).await;
+    entities::incidents::children::create(
+        parent_id,
+        IncidentChildrenCreateset {
+            incident_ids: vec![survivor_id],
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::incidents::children::create(
+        duplicate_id,
+        IncidentChildrenCreateset {
+            incident_ids: vec![child_id],
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = merge(context, duplicate_id, survivor_id).await;
+
+    // The survivor stays a child, so the children of the duplicate become its siblings.
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let survivor = response.into_body().into_json::<Incident>().await;
+    assert_that!(survivor.parent_id, some(eq(parent_id)));
+    let child = read_incident(context, child_id).await;
+    assert_that!(child.parent_id, some(eq(parent_id)));
+}
+
+#[db_test]
+async fn test_merge_resolves_then_closes(context: &DbTestContext) {
+    let duplicate_id = post_incident(context).await;
+    let survivor_id = post_incident(context).await;
+
+    let response = merge(context, duplicate_id, survivor_id).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let statuses: Vec<serde_json::Value> =
+        entities::audit::load_history(AuditEntity::Incident, duplicate_id, &context.db_pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.source == "incidents" && e.action == AuditAction::Update)
+            .map(|e| e.changes["status"].clone())
+            .collect();
+    assert_that!(
+        statuses,
+        elements_are![
+            eq(&json!({ "before": "inprogress", "after": "resolved" })),
+            eq(&json!({ "before": "resolved", "after": "closed" })),
+        ]
+    );
+}
+
+#[db_test]
+async fn test_merge_keeps_resolved_at(context: &DbTestContext) {
+    let duplicate = entities::incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::Resolved),
+            resolution_code: Some(IncidentResolutionCode::Solved),
+            resolution_notes: Some(String::from("Restarted the router.")),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let survivor_id = post_incident(context).await;
+
+    let response = merge(context, duplicate.id, survivor_id).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let merged = read_incident(context, duplicate.id).await;
+    assert_that!(
+        merged,
+        matches_pattern!(Incident {
+            status: eq(&IncidentStatus::Closed),
+            resolved_at: eq(&duplicate.resolved_at),
+            resolution_code: some(eq(&IncidentResolutionCode::Duplicate)),
+            resolution_notes: some(contains_substring(survivor_id.to_string())),
+            ..
+        })
+    );
+}
+
+#[db_test]
+async fn test_merge_invalid(context: &DbTestContext) {
+    let incident_id = post_incident(context).await;
+    let closed_id = entities::incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::Closed),
+            resolution_code: Some(IncidentResolutionCode::Cancelled),
+            resolution_notes: Some(String::from("Reported by mistake.")),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap()
+    .id;
+
+    // Into itself.
+    let response = merge(context, incident_id, incident_id).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Into a nonexistent Incident.
+    let response = merge(context, incident_id, Uuid::new_v4()).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Into a closed Incident.
+    let response = merge(context, incident_id, closed_id).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // A closed Incident.
+    let response = merge(context, closed_id, incident_id).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let incident = read_incident(context, incident_id).await;
+    assert_that!(incident.status, eq(IncidentStatus::InProgress));
+    assert_that!(incident.duplicate_of_id, none());
+}
+
+#[db_test]
+async fn test_merge_nonexistent_incident(context: &DbTestContext) {
+    let survivor_id = post_incident(context).await;
+
+    let response = merge(context, Uuid::new_v4(), survivor_id).await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
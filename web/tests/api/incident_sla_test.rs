@@ -0,0 +1,301 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::{DateTime, Duration, Utc};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    incidents::{
+        self, sla::IncidentSla, Incident, IncidentCreateset, IncidentImpact, IncidentPrio,
+        IncidentUrgency,
+    },
+    sla_policies::{self, SlaPolicy, SlaPolicyCreateset, SlaTarget},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Default policy running around the clock, with targets for low priority Incidents.
+async fn post_policy(context: &DbTestContext) -> SlaPolicy {
+    let createset = SlaPolicyCreateset {
+        name: String::from("Around the clock"),
+        description: String::from(""),
+        is_default: Some(true),
+        timezone: None,
+        business_hours_start: None,
+        business_hours_end: None,
+        business_days: None,
+        targets: vec![SlaTarget {
+            priority: IncidentPrio::Low,
+            response_minutes: 30,
+            resolution_minutes: 240,
+        }],
+        holidays: vec![],
+    };
+
+    sla_policies::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+}
+
+fn at(timestamp: &str) -> DateTime<Utc> {
+    timestamp.parse().unwrap()
+}
+
+fn create_basic_createset() -> IncidentCreateset {
+    IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: None,
+        created_at: None,
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    }
+}
+
+async fn read_sla(context: &DbTestContext, id: Uuid) -> IncidentSla {
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{id}/sla"))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    response.into_body().into_json::<IncidentSla>().await
+}
+
+async fn put_status(context: &DbTestContext, id: Uuid, payload: serde_json::Value) {
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{id}"))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_deadlines_from_default_policy(context: &DbTestContext) {
+    let policy = post_policy(context).await;
+    let createset = IncidentCreateset {
+        created_at: Some(at("2024-03-01T10:00:00Z")),
+        ..create_basic_createset()
+    };
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let incident = response.into_body().into_json::<Incident>().await;
+    assert_that!(incident.sla_policy_id, some(eq(policy.id)));
+    assert_that!(
+        incident.response_due_at,
+        some(eq(at("2024-03-01T10:30:00Z")))
+    );
+    assert_that!(
+        incident.resolution_due_at,
+        some(eq(at("2024-03-01T14:00:00Z")))
+    );
+
+    let sla = read_sla(context, incident.id).await;
+    assert_that!(sla.response_breached, eq(true));
+    assert_that!(sla.resolution_breached, eq(true));
+    assert_that!(sla.paused, eq(false));
+}
+
+#[db_test]
+async fn test_no_policy(context: &DbTestContext) {
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    assert_that!(incident.sla_policy_id, none());
+    assert_that!(incident.response_due_at, none());
+
+    let sla = read_sla(context, incident.id).await;
+    assert_that!(sla.response_breached, eq(false));
+    assert_that!(sla.resolution_breached, eq(false));
+}
+
+#[db_test]
+async fn test_priority_without_target(context: &DbTestContext) {
+    post_policy(context).await;
+    let incident = incidents::create(
+        IncidentCreateset {
+            impact: IncidentImpact::High,
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    assert_that!(incident.sla_policy_id, some(anything()));
+    assert_that!(incident.response_due_at, none());
+    assert_that!(incident.resolution_due_at, none());
+}
+
+#[db_test]
+async fn test_response(context: &DbTestContext) {
+    post_policy(context).await;
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(incident.responded_at, none());
+
+    put_status(context, incident.id, json!({ "status": "assigned" })).await;
+
+    let sla = read_sla(context, incident.id).await;
+    assert_that!(sla.responded_at, some(anything()));
+    assert_that!(sla.response_breached, eq(false));
+}
+
+#[db_test]
+async fn test_hold_pauses_clock(context: &DbTestContext) {
+    post_policy(context).await;
+    let incident = incidents::create(
+        IncidentCreateset {
+            created_at: Some(Utc::now() - Duration::hours(1)),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    put_status(context, incident.id, json!({ "status": "inprogress" })).await;
+
+    put_status(
+        context,
+        incident.id,
+        json!({ "status": "onhold", "hold_reason": "Waiting for the vendor." }),
+    )
+    .await;
+    let on_hold = read_sla(context, incident.id).await;
+    assert_that!(on_hold.paused, eq(true));
+    assert_that!(on_hold.elapsed_minutes, eq(60));
+    assert_that!(on_hold.resolution_due_at, eq(incident.resolution_due_at));
+
+    put_status(context, incident.id, json!({ "status": "inprogress" })).await;
+    let resumed = read_sla(context, incident.id).await;
+    assert_that!(resumed.paused, eq(false));
+    // The time on hold moves the deadline forward.
+    assert_that!(
+        resumed.resolution_due_at.unwrap(),
+        gt(incident.resolution_due_at.unwrap())
+    );
+}
+
+#[db_test]
+async fn test_policy_update_moves_deadlines(context: &DbTestContext) {
+    let policy = post_policy(context).await;
+    let incident = incidents::create(
+        IncidentCreateset {
+            created_at: Some(at("2024-03-01T10:00:00Z")),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", policy.id))
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({
+                "targets": [
+                    { "priority": "low", "response_minutes": 60, "resolution_minutes": 480 }
+                ]
+            })
+            .to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let incident_after = incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(
+        incident_after.resolution_due_at,
+        some(eq(at("2024-03-01T18:00:00Z")))
+    );
+}
+
+#[db_test]
+async fn test_read_all_breached(context: &DbTestContext) {
+    post_policy(context).await;
+    let breached = incidents::create(
+        IncidentCreateset {
+            created_at: Some(Utc::now() - Duration::hours(5)),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let on_time = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    for (query, expected) in [
+        ("sla_breached=true", vec![breached.id]),
+        ("sla_breached=false", vec![on_time.id]),
+        ("sla_breaching_within=45", vec![on_time.id]),
+        ("sla_breaching_within=10", vec![]),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/incidents?{query}"))
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+
+        let page = response.into_body().into_json::<Page<Incident>>().await;
+        let ids: Vec<Uuid> = page.items.iter().map(|incident| incident.id).collect();
+        assert_that!(ids, eq(&expected));
+    }
+}
+
+#[db_test]
+async fn test_read_all_breaching_invalid(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/incidents?sla_breaching_within=0")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_sla_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/sla", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
@@ -11,6 +11,7 @@ use itil_back_db::entities::{
         Incident,
     },
 };
+use itil_back_db::pagination::Page;
 use itil_back_macros::db_test;
 use itil_back_web::{
     controllers::incidents::ci_relations::{ModifyIncidentCIRelation, RelateCIRequest},
@@ -25,7 +26,7 @@ async fn post_ci(context: &DbTestContext) -> Uuid {
         status: Some(entities::configuration::CIStatus::Active),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
         r#type: Some(String::from("Test CI")),
-        owner: Some(String::from("Me")),
+        owner_team_id: None,
         description: String::from("I'm for testing"),
     };
 
@@ -41,12 +42,16 @@ async fn post_incident(context: &DbTestContext) -> Uuid {
         title: String::from("Testing Incident"),
         status: Some(entities::incidents::IncidentStatus::InProgress),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
-        resolved_at: None,
         impact: entities::incidents::IncidentImpact::Low,
         urgency: entities::incidents::IncidentUrgency::Low,
-        owner: Some(String::from("Me")),
-        asignee: Some(String::from("Employee 420")),
+        assignment_group_id: None,
+        assignee_id: None,
         description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
     };
 
     let incident = entities::incidents::create(changeset, &context.db_pool)
@@ -326,14 +331,19 @@ async fn test_read_all_by_ci(context: &DbTestContext) {
         .await;
 
     assert_that!(response.status(), eq(StatusCode::OK));
-    let incidents: Vec<Incident> = response.into_body().into_json::<Vec<Incident>>().await;
-    assert_that!(incidents, len(eq(0)));
+    let page = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.items, len(eq(0)));
+    assert_that!(page.total, eq(0));
 
-    let incident_id = post_incident(context).await;
     let ci_id = post_ci(context).await;
-    ci_relations::create(incident_id, ci_id, &context.db_pool)
-        .await
-        .unwrap();
+    let mut incident_ids = Vec::new();
+    for _ in 0..2 {
+        let incident_id = post_incident(context).await;
+        ci_relations::create(incident_id, ci_id, &context.db_pool)
+            .await
+            .unwrap();
+        incident_ids.push(incident_id);
+    }
 
     let response = context
         .app
@@ -342,7 +352,40 @@ async fn test_read_all_by_ci(context: &DbTestContext) {
         .await;
 
     assert_that!(response.status(), eq(StatusCode::OK));
-    let incidents: Vec<Incident> = response.into_body().into_json::<Vec<Incident>>().await;
-    assert_that!(incidents, len(eq(1)));
-    assert_that!(incidents.first().unwrap().id, eq(incident_id));
+    let page = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.total, eq(2));
+    let ids: Vec<Uuid> = page.items.iter().map(|i| i.id).collect();
+    assert_that!(
+        ids,
+        unordered_elements_are![eq(&incident_ids[0]), eq(&incident_ids[1])]
+    );
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents/byconfigitem/{}?page=2&limit=1",
+            ci_id
+        ))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.items, len(eq(1)));
+    assert_that!(page.total, eq(2));
+    assert_that!(page.next_page, none());
+}
+
+#[db_test]
+async fn test_read_all_by_ci_invalid_page(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents/byconfigitem/{}?limit=0",
+            Uuid::new_v4()
+        ))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
 }
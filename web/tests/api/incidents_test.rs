@@ -5,42 +5,81 @@ use axum::{
 use chrono::{Duration, Utc};
 use googletest::prelude::*;
 use hyper::StatusCode;
-use itil_back_db::entities::incidents::{
-    self, Incident, IncidentCreateset, IncidentImpact, IncidentStatus, IncidentUpdateset,
-    IncidentUrgency,
+use itil_back_db::entities::{
+    incidents::{
+        self, Incident, IncidentCreateset, IncidentImpact, IncidentPrio, IncidentResolutionCode,
+        IncidentStatus, IncidentUpdateset, IncidentUrgency,
+    },
+    teams::{self, TeamCreateset},
+    users::{self, UserCreateset},
 };
+use itil_back_db::pagination::Page;
 use itil_back_macros::db_test;
-use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
 use serde_json::json;
 use uuid::Uuid;
 
+async fn post_team(context: &DbTestContext, name: &str) -> Uuid {
+    let createset = TeamCreateset {
+        name: String::from(name),
+        description: String::from(""),
+    };
+
+    let team = teams::create(createset, &context.db_pool).await.unwrap();
+
+    team.id
+}
+
+async fn post_member(context: &DbTestContext, team_id: Uuid, username: &str) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(username),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    let user = users::create(createset, &context.db_pool).await.unwrap();
+    teams::members::create(team_id, user.id, &context.db_pool)
+        .await
+        .unwrap();
+
+    user.id
+}
+
 fn create_basic_createset() -> IncidentCreateset {
     IncidentCreateset {
         title: String::from("Testing Incident"),
         status: Some(IncidentStatus::InProgress),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
-        resolved_at: None,
         impact: IncidentImpact::Low,
         urgency: IncidentUrgency::Low,
-        owner: Some(String::from("Testing Department")),
-        asignee: Some(String::from("Employee 123")),
+        assignment_group_id: None,
+        assignee_id: None,
         description: String::from("This is a fictional incident made for testing."),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
     }
 }
 
 fn create_basic_updateset() -> IncidentUpdateset {
     IncidentUpdateset {
         title: Some(Some(String::from("Updated Incident"))),
-        status: Some(Some(IncidentStatus::Closed)),
+        status: Some(Some(IncidentStatus::Resolved)),
         created_at: Some(Some("2023-09-15T12:34:58Z".parse().unwrap())),
-        resolved_at: Some(Some("2023-10-15T12:34:50Z".parse().unwrap())),
         impact: Some(Some(IncidentImpact::Medium)),
         urgency: Some(Some(IncidentUrgency::Medium)),
-        owner: Some(Some(String::from("Update Department"))),
-        asignee: Some(Some(String::from("Employee 321"))),
+        assignment_group_id: Some(None),
+        assignee_id: Some(None),
         description: Some(Some(String::from(
             "This is a fictional incident made for updating.",
         ))),
+        hold_reason: None,
+        resolution_code: Some(Some(IncidentResolutionCode::Solved)),
+        resolution_notes: Some(Some(String::from("Restarted the proxy service."))),
+        sla_policy_id: None,
+        parent_id: None,
     }
 }
 
@@ -57,13 +96,29 @@ async fn test_create_invalid(context: &DbTestContext) {
         ..createset.clone()
     });
     sets.push(IncidentCreateset {
-        owner: Some(String::from(&"x".repeat(1025))),
+        assignment_group_id: Some(Uuid::new_v4()),
         ..createset.clone()
     });
     sets.push(IncidentCreateset {
         description: String::from(&"x".repeat(1025)),
         ..createset.clone()
     });
+    // Can't follow new.
+    sets.push(IncidentCreateset {
+        status: Some(IncidentStatus::OnHold),
+        hold_reason: Some(String::from("Waiting for the vendor.")),
+        ..createset.clone()
+    });
+    sets.push(IncidentCreateset {
+        status: Some(IncidentStatus::Closed),
+        resolution_code: Some(IncidentResolutionCode::Solved),
+        ..createset.clone()
+    });
+    // Not in use by the default priority matrix.
+    sets.push(IncidentCreateset {
+        impact: IncidentImpact::Critical,
+        ..createset.clone()
+    });
 
     for set in sets {
         let payload = json!(set);
@@ -117,11 +172,14 @@ async fn test_create_success(context: &DbTestContext) {
     assert_that!(incident.title, eq(&createset.title));
     assert_that!(incident.status, eq(createset.status.unwrap()));
     assert_that!(incident.created_at, eq(createset.created_at.unwrap()));
-    assert_that!(incident.resolved_at, eq(createset.resolved_at));
+    assert_that!(incident.resolved_at, none());
     assert_that!(incident.impact, eq(createset.impact));
     assert_that!(incident.urgency, eq(createset.urgency));
-    assert_that!(incident.owner, eq(&createset.owner));
-    assert_that!(incident.asignee, eq(&createset.asignee));
+    assert_that!(
+        incident.assignment_group_id,
+        eq(createset.assignment_group_id)
+    );
+    assert_that!(incident.assignee_id, eq(createset.assignee_id));
     assert_that!(incident.description, eq(&createset.description));
 
     let incidents = incidents::load_all(&context.db_pool).await.unwrap();
@@ -170,30 +228,6 @@ async fn test_create_border_success(context: &DbTestContext) {
         status: None,
         ..createset.clone()
     });
-    sets.push(IncidentCreateset {
-        owner: None,
-        ..createset.clone()
-    });
-    sets.push(IncidentCreateset {
-        owner: Some(String::from("")),
-        ..createset.clone()
-    });
-    sets.push(IncidentCreateset {
-        owner: Some(String::from(&"x".repeat(1024))),
-        ..createset.clone()
-    });
-    sets.push(IncidentCreateset {
-        asignee: None,
-        ..createset.clone()
-    });
-    sets.push(IncidentCreateset {
-        asignee: Some(String::from("")),
-        ..createset.clone()
-    });
-    sets.push(IncidentCreateset {
-        asignee: Some(String::from(&"x".repeat(1024))),
-        ..createset.clone()
-    });
     sets.push(IncidentCreateset {
         description: String::from(""),
         ..createset.clone()
@@ -228,11 +262,13 @@ async fn test_status(context: &DbTestContext) {
         ..createset.clone()
     });
     sets.push(IncidentCreateset {
-        status: Some(IncidentStatus::Open),
+        status: Some(IncidentStatus::New),
         ..createset.clone()
     });
     sets.push(IncidentCreateset {
         status: Some(IncidentStatus::Closed),
+        resolution_code: Some(IncidentResolutionCode::Duplicate),
+        resolution_notes: Some(String::from("Same as the one reported this morning.")),
         ..createset.clone()
     });
 
@@ -335,9 +371,183 @@ async fn test_read_all(context: &DbTestContext) {
 
     assert_that!(response.status(), eq(StatusCode::OK));
 
-    let incidents: Vec<Incident> = response.into_body().into_json::<Vec<Incident>>().await;
-    assert_that!(incidents, len(eq(1)));
-    assert_that!(incidents.first().unwrap(), eq(&incident));
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.next_page, none());
+    assert_that!(page.items, len(eq(1)));
+    assert_that!(page.items.first().unwrap(), eq(&incident));
+}
+
+#[db_test]
+async fn test_read_all_paginated(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    for _ in 0..5 {
+        incidents::create(createset.clone(), &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    let response = context
+        .app
+        .request("/api/incidents?page=2&limit=2")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.total, eq(5));
+    assert_that!(page.page, eq(2));
+    assert_that!(page.limit, eq(2));
+    assert_that!(page.next_page, some(eq(3)));
+    assert_that!(page.items, len(eq(2)));
+
+    let response = context
+        .app
+        .request("/api/incidents?page=3&limit=2")
+        .send()
+        .await;
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.next_page, none());
+    assert_that!(page.items, len(eq(1)));
+}
+
+#[db_test]
+async fn test_read_all_invalid_params(context: &DbTestContext) {
+    for uri in [
+        "/api/incidents?page=0",
+        "/api/incidents?page=1000001",
+        "/api/incidents?page=9223372036854775807",
+        "/api/incidents?limit=0",
+        "/api/incidents?limit=501",
+    ] {
+        let response = context.app.request(uri).send().await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context
+        .app
+        .request("/api/incidents?status=unknown")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::BAD_REQUEST));
+}
+
+#[db_test]
+async fn test_read_all_filtered(context: &DbTestContext) {
+    let team_id = post_team(context, "Networking").await;
+    let createset = create_basic_createset();
+    incidents::create(createset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+    let expected = incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::New),
+            assignment_group_id: Some(team_id),
+            created_at: Some("2024-03-01T10:00:00Z".parse().unwrap()),
+            ..createset.clone()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::New),
+            created_at: Some("2024-03-01T10:00:00Z".parse().unwrap()),
+            ..createset
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents?status=new&assignment_group_id={team_id}\
+                &created_after=2024-01-01T00:00:00Z&created_before=2025-01-01T00:00:00Z"
+        ))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items.first().unwrap(), eq(&expected));
+}
+
+#[db_test]
+async fn test_read_all_filtered_by_priority(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    incidents::create(createset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+    let expected = incidents::create(
+        IncidentCreateset {
+            impact: IncidentImpact::High,
+            urgency: IncidentUrgency::Medium,
+            ..createset
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request("/api/incidents?priority=high")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.items, elements_are![eq(&expected)]);
+}
+
+#[db_test]
+async fn test_read_all_sorted_by_priority(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    for (impact, urgency) in [
+        (IncidentImpact::Medium, IncidentUrgency::Medium),
+        (IncidentImpact::High, IncidentUrgency::High),
+        (IncidentImpact::Low, IncidentUrgency::Low),
+    ] {
+        incidents::create(
+            IncidentCreateset {
+                impact,
+                urgency,
+                ..createset.clone()
+            },
+            &context.db_pool,
+        )
+        .await
+        .unwrap();
+    }
+
+    let response = context
+        .app
+        .request("/api/incidents?sort_by=priority&order=desc")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    let priorities: Vec<IncidentPrio> = page.items.iter().map(|i| i.priority).collect();
+    assert_that!(
+        priorities,
+        eq(&vec![
+            IncidentPrio::Critical,
+            IncidentPrio::Moderate,
+            IncidentPrio::Low
+        ])
+    );
 }
 
 #[db_test]
@@ -388,11 +598,11 @@ async fn test_update_invalid(context: &DbTestContext) {
         ..updateset.clone()
     });
     sets.push(IncidentUpdateset {
-        owner: Some(Some(String::from(&"x".repeat(1025)))),
+        assignment_group_id: Some(Some(Uuid::new_v4())),
         ..updateset.clone()
     });
     sets.push(IncidentUpdateset {
-        asignee: Some(Some(String::from(&"x".repeat(1025)))),
+        assignee_id: Some(Some(Uuid::new_v4())),
         ..updateset.clone()
     });
     sets.push(IncidentUpdateset {
@@ -515,15 +725,26 @@ async fn test_update_success(context: &DbTestContext) {
         incident.created_at,
         eq(updateset.created_at.unwrap().unwrap())
     );
-    assert_that!(incident.resolved_at, eq(updateset.resolved_at.unwrap()));
+    assert_that!(incident.resolved_at, some(anything()));
     assert_that!(incident.impact, eq(updateset.impact.unwrap().unwrap()));
     assert_that!(incident.urgency, eq(updateset.urgency.unwrap().unwrap()));
-    assert_that!(incident.owner, eq(&updateset.owner.unwrap()));
-    assert_that!(incident.asignee, eq(&updateset.asignee.unwrap()));
+    assert_that!(
+        incident.assignment_group_id,
+        eq(updateset.assignment_group_id.unwrap())
+    );
+    assert_that!(incident.assignee_id, eq(updateset.assignee_id.unwrap()));
     assert_that!(
         incident.description,
         eq(&updateset.description.unwrap().unwrap())
     );
+    assert_that!(
+        incident.resolution_code,
+        eq(updateset.resolution_code.unwrap())
+    );
+    assert_that!(
+        incident.resolution_notes,
+        eq(&updateset.resolution_notes.unwrap())
+    );
 
     let incident_after = incidents::load(incident.id, &context.db_pool)
         .await
@@ -533,15 +754,20 @@ async fn test_update_success(context: &DbTestContext) {
 
 #[db_test]
 async fn test_update_set_nulls(context: &DbTestContext) {
-    let createset = create_basic_createset();
+    let team_id = post_team(context, "Networking").await;
+    let user_id = post_member(context, team_id, "jdoe").await;
+    let createset = IncidentCreateset {
+        assignment_group_id: Some(team_id),
+        assignee_id: Some(user_id),
+        ..create_basic_createset()
+    };
     let incident = incidents::create(createset, &context.db_pool)
         .await
         .unwrap();
 
     let updateset = IncidentUpdateset {
-        resolved_at: Some(None),
-        owner: Some(None),
-        asignee: Some(None),
+        assignment_group_id: Some(None),
+        assignee_id: Some(None),
         ..create_basic_updateset()
     };
     let payload = json!(updateset);
@@ -557,9 +783,8 @@ async fn test_update_set_nulls(context: &DbTestContext) {
 
     assert_that!(response.status(), eq(StatusCode::OK));
     let incident_after: Incident = response.into_body().into_json::<Incident>().await;
-    assert!(incident_after.resolved_at.is_none());
-    assert!(incident_after.owner.is_none());
-    assert!(incident_after.asignee.is_none());
+    assert!(incident_after.assignment_group_id.is_none());
+    assert!(incident_after.assignee_id.is_none());
 }
 
 #[db_test]
@@ -573,12 +798,16 @@ async fn test_update_nothing(context: &DbTestContext) {
         title: None,
         status: None,
         created_at: None,
-        resolved_at: None,
         impact: None,
         urgency: None,
-        owner: None,
-        asignee: None,
+        assignment_group_id: None,
+        assignee_id: None,
         description: None,
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
     };
     let payload = json!(updateset);
 
@@ -596,6 +825,187 @@ async fn test_update_nothing(context: &DbTestContext) {
     assert_that!(incident_after, eq(&incident_before));
 }
 
+async fn put_incident(
+    context: &DbTestContext,
+    id: Uuid,
+    payload: serde_json::Value,
+) -> axum::response::Response {
+    context
+        .app
+        .request(&format!("/api/incidents/{id}"))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+#[db_test]
+async fn test_update_lifecycle(context: &DbTestContext) {
+    let incident = incidents::create(
+        IncidentCreateset {
+            status: None,
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_that!(incident.status, eq(IncidentStatus::New));
+
+    let response = put_incident(context, incident.id, json!({ "status": "assigned" })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let response = put_incident(
+        context,
+        incident.id,
+        json!({ "status": "onhold", "hold_reason": "Waiting for the vendor." }),
+    )
+    .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let on_hold = response.into_body().into_json::<Incident>().await;
+    assert_that!(on_hold.hold_reason, some(eq("Waiting for the vendor.")));
+
+    let response = put_incident(context, incident.id, json!({ "status": "inprogress" })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let in_progress = response.into_body().into_json::<Incident>().await;
+    assert_that!(in_progress.hold_reason, none());
+
+    let response = put_incident(
+        context,
+        incident.id,
+        json!({
+            "status": "resolved",
+            "resolution_code": "workaround",
+            "resolution_notes": "Routed traffic around the proxy."
+        }),
+    )
+    .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let resolved = response.into_body().into_json::<Incident>().await;
+    assert_that!(resolved.resolved_at, some(anything()));
+    assert_that!(
+        resolved.resolution_code,
+        some(eq(IncidentResolutionCode::Workaround))
+    );
+
+    // Reopening clears the resolution.
+    let response = put_incident(context, incident.id, json!({ "status": "inprogress" })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let reopened = response.into_body().into_json::<Incident>().await;
+    assert_that!(reopened.resolved_at, none());
+    assert_that!(reopened.resolution_code, none());
+    assert_that!(reopened.resolution_notes, none());
+
+    for payload in [
+        json!({ "status": "resolved" }),
+        json!({
+            "status": "closed",
+            "resolution_code": "solved",
+            "resolution_notes": "Replaced the proxy."
+        }),
+    ] {
+        let response = put_incident(context, incident.id, payload).await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+    }
+
+    let closed = incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(closed.status, eq(IncidentStatus::Closed));
+    assert_that!(closed.resolved_at, some(anything()));
+    assert_that!(closed.resolution_notes, some(eq("Replaced the proxy.")));
+}
+
+#[db_test]
+async fn test_update_invalid_transition(context: &DbTestContext) {
+    let incident = incidents::create(
+        IncidentCreateset {
+            status: None,
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = put_incident(
+        context,
+        incident.id,
+        json!({ "status": "onhold", "hold_reason": "Waiting for the vendor." }),
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::CONFLICT));
+    let body = response.into_body().into_json::<serde_json::Value>().await;
+    assert_that!(body["from"], eq(&json!("new")));
+    assert_that!(body["to"], eq(&json!("onhold")));
+    assert_that!(
+        body["allowed"],
+        eq(&json!(["assigned", "inprogress", "resolved", "closed"]))
+    );
+
+    let incident_after = incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(incident_after, eq(&incident));
+}
+
+#[db_test]
+async fn test_update_closed_is_final(context: &DbTestContext) {
+    let incident = incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::Closed),
+            resolution_code: Some(IncidentResolutionCode::Cancelled),
+            resolution_notes: Some(String::from("Reported by mistake.")),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_that!(incident.resolved_at, some(anything()));
+
+    let response = put_incident(context, incident.id, json!({ "status": "inprogress" })).await;
+
+    assert_that!(response.status(), eq(StatusCode::CONFLICT));
+    let body = response.into_body().into_json::<serde_json::Value>().await;
+    assert_that!(body["allowed"], eq(&json!([])));
+}
+
+#[db_test]
+async fn test_update_missing_status_fields(context: &DbTestContext) {
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let resolved = incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::Resolved),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    for (id, payload) in [
+        (incident.id, json!({ "status": "onhold" })),
+        (
+            incident.id,
+            json!({ "status": "onhold", "hold_reason": null }),
+        ),
+        (resolved.id, json!({ "status": "closed" })),
+        (
+            resolved.id,
+            json!({ "status": "closed", "resolution_code": "solved" }),
+        ),
+    ] {
+        let response = put_incident(context, id, payload).await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
 #[db_test]
 async fn test_delete_nonexistent(context: &DbTestContext) {
     let response = context
@@ -627,3 +1037,157 @@ async fn test_delete_success(context: &DbTestContext) {
     let result = incidents::load(incident.id, &context.db_pool).await;
     assert_that!(result, err(anything()));
 }
+
+#[db_test]
+async fn test_assign_success(context: &DbTestContext) {
+    let team_id = post_team(context, "Networking").await;
+    let user_id = post_member(context, team_id, "jdoe").await;
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let updateset = IncidentUpdateset {
+        assignment_group_id: Some(Some(team_id)),
+        assignee_id: Some(Some(user_id)),
+        ..create_basic_updateset()
+    };
+    let payload = json!(updateset);
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", incident.id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let incident_after: Incident = response.into_body().into_json::<Incident>().await;
+    assert_that!(incident_after.assignment_group_id, some(eq(team_id)));
+    assert_that!(incident_after.assignee_id, some(eq(user_id)));
+}
+
+#[db_test]
+async fn test_assign_invalid(context: &DbTestContext) {
+    let team_id = post_team(context, "Networking").await;
+    let other_team_id = post_team(context, "Databases").await;
+    let user_id = post_member(context, other_team_id, "jdoe").await;
+
+    let createset = create_basic_createset();
+    let sets = vec![
+        // Assignee not belonging to the assignment group.
+        IncidentCreateset {
+            assignment_group_id: Some(team_id),
+            assignee_id: Some(user_id),
+            ..createset.clone()
+        },
+        // Assignee without an assignment group.
+        IncidentCreateset {
+            assignee_id: Some(user_id),
+            ..createset
+        },
+    ];
+
+    for set in sets {
+        let payload = json!(set);
+
+        let response = context
+            .app
+            .request("/api/incidents")
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let incidents = incidents::load_all(&context.db_pool).await.unwrap();
+    assert_that!(incidents, is_empty());
+}
+
+#[db_test]
+async fn test_read_all_assigned_to_me(context: &DbTestContext) {
+    let team_id = post_team(context, "Networking").await;
+    let tester_id = post_member(context, team_id, TEST_SUBJECT).await;
+    let other_id = post_member(context, team_id, "jdoe").await;
+    let createset = IncidentCreateset {
+        assignment_group_id: Some(team_id),
+        ..create_basic_createset()
+    };
+    let expected = incidents::create(
+        IncidentCreateset {
+            assignee_id: Some(tester_id),
+            ..createset.clone()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    incidents::create(
+        IncidentCreateset {
+            assignee_id: Some(other_id),
+            ..createset.clone()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request("/api/incidents?assigned_to_me=true")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items.first().unwrap(), eq(&expected));
+}
+
+#[db_test]
+async fn test_read_all_assigned_to_my_team(context: &DbTestContext) {
+    let team_id = post_team(context, "Networking").await;
+    let other_team_id = post_team(context, "Databases").await;
+    post_member(context, team_id, TEST_SUBJECT).await;
+    let expected = incidents::create(
+        IncidentCreateset {
+            assignment_group_id: Some(team_id),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    incidents::create(
+        IncidentCreateset {
+            assignment_group_id: Some(other_team_id),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request("/api/incidents?assigned_to_my_team=true")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Incident> = response.into_body().into_json::<Page<Incident>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items.first().unwrap(), eq(&expected));
+}
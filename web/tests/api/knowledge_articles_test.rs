@@ -0,0 +1,660 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    audit::AuditEntry,
+    knowledge_articles::{
+        self, versions::KnowledgeArticleVersion, KnowledgeArticle, KnowledgeArticleCreateset,
+        KnowledgeArticleMatch, KnowledgeArticleStatus, KnowledgeArticleUpdateset,
+    },
+    knowledge_categories, roles,
+    users::{self, UserCreateset},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_ci(context: &DbTestContext) -> Uuid {
+    let createset = entities::configuration::ConfigItemCreateset {
+        name: String::from("Testing CI for Knowledge Articles"),
+        status: Some(entities::configuration::CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: Some(String::from("Test CI")),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    let ci = entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    ci.id
+}
+
+async fn post_incident(context: &DbTestContext, title: &str, description: &str) -> Uuid {
+    let createset = entities::incidents::IncidentCreateset {
+        title: String::from(title),
+        status: Some(entities::incidents::IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: entities::incidents::IncidentImpact::Low,
+        urgency: entities::incidents::IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from(description),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    let incident = entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    incident.id
+}
+
+async fn post_problem(context: &DbTestContext, status: entities::problems::ProblemStatus) -> Uuid {
+    let createset = entities::problems::ProblemCreateset {
+        title: String::from("Printer Jams"),
+        status: Some(status),
+        detection_timedate: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        description: String::from("Printer jams with large documents."),
+        causes: String::from("Spooler runs out of memory."),
+        workarounds: Some(String::from("Split the job in smaller ones.")),
+        resolutions: None,
+    };
+
+    let problem = entities::problems::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    problem.id
+}
+
+fn create_basic_createset() -> KnowledgeArticleCreateset {
+    KnowledgeArticleCreateset {
+        title: String::from("Printing large documents"),
+        body: String::from("Split the job in smaller ones."),
+        category_id: None,
+        ci_ids: vec![],
+        problem_ids: vec![],
+        incident_ids: vec![],
+    }
+}
+
+/// Create an article and publish it right away.
+async fn post_published_article(
+    context: &DbTestContext,
+    createset: KnowledgeArticleCreateset,
+) -> Uuid {
+    let article = knowledge_articles::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+    for status in [
+        KnowledgeArticleStatus::Review,
+        KnowledgeArticleStatus::Published,
+    ] {
+        let updateset = KnowledgeArticleUpdateset {
+            title: None,
+            body: None,
+            category_id: None,
+            status: Some(Some(status)),
+            ci_ids: None,
+            problem_ids: None,
+            incident_ids: None,
+        };
+        knowledge_articles::update(article.id, updateset, &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    article.id
+}
+
+async fn put_article(
+    context: &DbTestContext,
+    id: Uuid,
+    payload: serde_json::Value,
+) -> axum::response::Response {
+    context
+        .app
+        .request(&format!("/api/articles/{}", id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn search(context: &DbTestContext, query: &str) -> Vec<KnowledgeArticleMatch> {
+    let response = context
+        .app
+        .request(&format!("/api/articles/search?{}", query))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    response
+        .into_body()
+        .into_json::<Vec<KnowledgeArticleMatch>>()
+        .await
+}
+
+fn matched_ids(matches: &[KnowledgeArticleMatch]) -> Vec<Uuid> {
+    matches.iter().map(|m| m.article.id).collect()
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let category = knowledge_categories::load_all(&context.db_pool)
+        .await
+        .unwrap()
+        .remove(0);
+    let payload = json!(KnowledgeArticleCreateset {
+        category_id: Some(category.id),
+        ci_ids: vec![ci_id],
+        ..create_basic_createset()
+    });
+
+    let response = context
+        .app
+        .request("/api/articles")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let article = response.into_body().into_json::<KnowledgeArticle>().await;
+    assert_that!(
+        article,
+        matches_pattern!(KnowledgeArticle {
+            title: eq("Printing large documents"),
+            category_id: some(eq(&category.id)),
+            status: eq(&KnowledgeArticleStatus::Draft),
+            version: eq(&1),
+            ci_ids: elements_are![eq(&ci_id)],
+            published_at: none(),
+            ..
+        })
+    );
+
+    let article_after = knowledge_articles::load(article.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(article_after, eq(&article));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    for payload in [
+        json!(KnowledgeArticleCreateset {
+            title: String::from(""),
+            ..create_basic_createset()
+        }),
+        json!(KnowledgeArticleCreateset {
+            category_id: Some(Uuid::new_v4()),
+            ..create_basic_createset()
+        }),
+        json!(KnowledgeArticleCreateset {
+            ci_ids: vec![Uuid::new_v4()],
+            ..create_basic_createset()
+        }),
+        json!(KnowledgeArticleCreateset {
+            problem_ids: vec![Uuid::new_v4()],
+            ..create_basic_createset()
+        }),
+        json!(KnowledgeArticleCreateset {
+            incident_ids: vec![Uuid::new_v4()],
+            ..create_basic_createset()
+        }),
+    ] {
+        let response = context
+            .app
+            .request("/api/articles")
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_versions(context: &DbTestContext) {
+    let article = knowledge_articles::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = put_article(
+        context,
+        article.id,
+        json!({ "body": "Split the job in smaller ones, or print from the server." }),
+    )
+    .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<KnowledgeArticle>().await;
+    assert_that!(updated.version, eq(2));
+
+    // Changing only the links doesn't make a new version.
+    let ci_id = post_ci(context).await;
+    let response = put_article(context, article.id, json!({ "ci_ids": [ci_id] })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<KnowledgeArticle>().await;
+    assert_that!(updated.version, eq(2));
+    assert_that!(updated.ci_ids, elements_are![eq(&ci_id)]);
+
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}/versions", article.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let versions = response
+        .into_body()
+        .into_json::<Vec<KnowledgeArticleVersion>>()
+        .await;
+    assert_that!(
+        versions,
+        elements_are![
+            matches_pattern!(KnowledgeArticleVersion {
+                version: eq(&1),
+                body: eq("Split the job in smaller ones."),
+                ..
+            }),
+            matches_pattern!(KnowledgeArticleVersion {
+                version: eq(&2),
+                body: eq("Split the job in smaller ones, or print from the server."),
+                ..
+            }),
+        ]
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}/versions/1", article.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let version = response
+        .into_body()
+        .into_json::<KnowledgeArticleVersion>()
+        .await;
+    assert_that!(version, eq(&versions[0]));
+
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}/versions/3", article.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_workflow(context: &DbTestContext) {
+    let article = knowledge_articles::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    // Drafts are reviewed before being published.
+    let response = put_article(context, article.id, json!({ "status": "published" })).await;
+    assert_that!(response.status(), eq(StatusCode::CONFLICT));
+    let response = put_article(context, article.id, json!({ "status": "review" })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    // Only drafts can be edited.
+    let response = put_article(context, article.id, json!({ "title": "Printing" })).await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+
+    // Agents can't publish.
+    users::create(
+        UserCreateset {
+            username: String::from("agent"),
+            full_name: String::from("Service Desk Agent"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    roles::assign("service_desk_agent", "agent", &context.db_pool)
+        .await
+        .unwrap();
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}", article.id))
+        .method(Method::PUT)
+        .token(&context.token_for("agent"))
+        .body(Body::from(json!({ "status": "published" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    let body = response.into_body().into_json::<serde_json::Value>().await;
+    assert_that!(body["permission"], eq(&json!("knowledge.manage")));
+
+    let response = put_article(context, article.id, json!({ "status": "published" })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let published = response.into_body().into_json::<KnowledgeArticle>().await;
+    assert_that!(published.status, eq(KnowledgeArticleStatus::Published));
+    assert_that!(published.published_at, some(anything()));
+
+    // Published articles go back to draft to be edited, in one step.
+    let response = put_article(
+        context,
+        article.id,
+        json!({ "status": "draft", "title": "Printing large documents on office printers" }),
+    )
+    .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let draft = response.into_body().into_json::<KnowledgeArticle>().await;
+    assert_that!(
+        draft,
+        matches_pattern!(KnowledgeArticle {
+            title: eq("Printing large documents on office printers"),
+            status: eq(&KnowledgeArticleStatus::Draft),
+            version: eq(&2),
+            published_at: eq(&published.published_at),
+            ..
+        })
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}/history", article.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let history = response.into_body().into_json::<Vec<AuditEntry>>().await;
+    assert_that!(history, len(eq(4)));
+}
+
+#[db_test]
+async fn test_update_invalid(context: &DbTestContext) {
+    let article = knowledge_articles::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    for payload in [
+        json!({ "title": null }),
+        json!({ "title": "" }),
+        json!({ "status": null }),
+        json!({ "ci_ids": null }),
+        json!({ "category_id": Uuid::new_v4() }),
+        json!({ "incident_ids": [Uuid::new_v4()] }),
+    ] {
+        let response = put_article(context, article.id, payload).await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_read_all_filters(context: &DbTestContext) {
+    let incident_id = post_incident(context, "Printer jam", "Paper is stuck.").await;
+    let draft = knowledge_articles::create(
+        KnowledgeArticleCreateset {
+            incident_ids: vec![incident_id],
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let published = post_published_article(context, create_basic_createset()).await;
+
+    let response = context
+        .app
+        .request("/api/articles?status=published")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response
+        .into_body()
+        .into_json::<Page<KnowledgeArticle>>()
+        .await;
+    assert_that!(
+        page.items.iter().map(|a| a.id).collect::<Vec<_>>(),
+        elements_are![eq(&published)]
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/articles?incident_id={}", incident_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response
+        .into_body()
+        .into_json::<Page<KnowledgeArticle>>()
+        .await;
+    assert_that!(
+        page.items.iter().map(|a| a.id).collect::<Vec<_>>(),
+        elements_are![eq(&draft.id)]
+    );
+}
+
+#[db_test]
+async fn test_search_by_text(context: &DbTestContext) {
+    let printers = post_published_article(context, create_basic_createset()).await;
+    post_published_article(
+        context,
+        KnowledgeArticleCreateset {
+            title: String::from("Resetting passwords"),
+            body: String::from("Use the self-service portal."),
+            ..create_basic_createset()
+        },
+    )
+    .await;
+    // Drafts aren't found.
+    knowledge_articles::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let matches = search(context, "q=printing").await;
+    assert_that!(matched_ids(&matches), elements_are![eq(&printers)]);
+    assert_that!(matches[0].rank, gt(0.0));
+}
+
+#[db_test]
+async fn test_search_for_incident(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let incident_id =
+        post_incident(context, "Printing fails", "Large documents never print.").await;
+    entities::incidents::ci_relations::create(incident_id, ci_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let about_incident = post_published_article(
+        context,
+        KnowledgeArticleCreateset {
+            title: String::from("Office printer is offline"),
+            body: String::from("Turn it off and on again."),
+            incident_ids: vec![incident_id],
+            ..create_basic_createset()
+        },
+    )
+    .await;
+    let about_ci = post_published_article(
+        context,
+        KnowledgeArticleCreateset {
+            title: String::from("Office printer is offline"),
+            body: String::from("Check the network cable."),
+            ci_ids: vec![ci_id],
+            ..create_basic_createset()
+        },
+    )
+    .await;
+    let matching_text = post_published_article(context, create_basic_createset()).await;
+    post_published_article(
+        context,
+        KnowledgeArticleCreateset {
+            title: String::from("Resetting passwords"),
+            body: String::from("Use the self-service portal."),
+            ..create_basic_createset()
+        },
+    )
+    .await;
+
+    let matches = search(context, &format!("incident_id={}", incident_id)).await;
+    assert_that!(
+        matched_ids(&matches),
+        elements_are![eq(&about_incident), eq(&about_ci), eq(&matching_text)]
+    );
+}
+
+#[db_test]
+async fn test_search_invalid(context: &DbTestContext) {
+    for query in ["", &format!("q=printer&incident_id={}", Uuid::new_v4())] {
+        let response = context
+            .app
+            .request(&format!("/api/articles/search?{}", query))
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/articles/search?incident_id={}",
+            Uuid::new_v4()
+        ))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_draft_from_known_error(context: &DbTestContext) {
+    let problem_id = post_problem(context, entities::problems::ProblemStatus::KnownError).await;
+
+    // Only publishing drafts an article.
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/knownerror", problem_id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "draft_article": true }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let response = context
+        .app
+        .request(&format!("/api/articles?problem_id={}", problem_id))
+        .send()
+        .await;
+    let page = response
+        .into_body()
+        .into_json::<Page<KnowledgeArticle>>()
+        .await;
+    assert_that!(page.items, is_empty());
+
+    for _ in 0..2 {
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}/knownerror", problem_id))
+            .method(Method::PUT)
+            .body(Body::from(
+                json!({ "published": true, "draft_article": true }).to_string(),
+            ))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+    }
+
+    let response = context
+        .app
+        .request(&format!("/api/articles?problem_id={}", problem_id))
+        .send()
+        .await;
+    let page = response
+        .into_body()
+        .into_json::<Page<KnowledgeArticle>>()
+        .await;
+    assert_that!(
+        page.items,
+        elements_are![matches_pattern!(KnowledgeArticle {
+            title: eq("Printer Jams"),
+            body: eq("## Symptoms\n\nPrinter jams with large documents.\n\n## Cause\n\nSpooler runs out of memory.\n\n## Workaround\n\n1. Split the job in smaller ones.\n"),
+            status: eq(&KnowledgeArticleStatus::Draft),
+            known_error_id: some(eq(&problem_id)),
+            problem_ids: elements_are![eq(&problem_id)],
+            ..
+        })]
+    );
+}
+
+#[db_test]
+async fn test_delete(context: &DbTestContext) {
+    let category = knowledge_categories::load_all(&context.db_pool)
+        .await
+        .unwrap()
+        .remove(0);
+    let article = knowledge_articles::create(
+        KnowledgeArticleCreateset {
+            category_id: Some(category.id),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    // Categories can't be deleted while articles are filed under them.
+    let response = context
+        .app
+        .request(&format!("/api/articlecategories/{}", category.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}", article.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = knowledge_articles::load(article.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_nonexistent(context: &DbTestContext) {
+    let id = Uuid::new_v4();
+
+    for uri in [
+        format!("/api/articles/{}", id),
+        format!("/api/articles/{}/versions", id),
+        format!("/api/articles/{}/versions/1", id),
+    ] {
+        let response = context.app.request(&uri).send().await;
+        assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+    }
+
+    let response = put_article(context, id, json!({ "title": "Anything" })).await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let response = context
+        .app
+        .request(&format!("/api/articles/{}", id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
@@ -0,0 +1,486 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    known_errors::{self, KnownError, KnownErrorMatch, KnownErrorUpdateset},
+    problems::incident_relations::{self, ProblemIncidentRelation},
+};
+use itil_back_macros::db_test;
+use itil_back_web::{
+    controllers::problems::incident_relations::CreateIncidentRelation,
+    test_helpers::{BodyExt, DbTestContext, RouterExt},
+};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_ci(context: &DbTestContext) -> Uuid {
+    let createset = entities::configuration::ConfigItemCreateset {
+        name: String::from("Testing CI for Known Errors"),
+        status: Some(entities::configuration::CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: Some(String::from("Test CI")),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    let ci = entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    ci.id
+}
+
+async fn post_incident(context: &DbTestContext, title: &str, description: &str) -> Uuid {
+    let createset = entities::incidents::IncidentCreateset {
+        title: String::from(title),
+        status: Some(entities::incidents::IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: entities::incidents::IncidentImpact::Low,
+        urgency: entities::incidents::IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from(description),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    let incident = entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    incident.id
+}
+
+async fn post_problem(
+    context: &DbTestContext,
+    status: entities::problems::ProblemStatus,
+    description: &str,
+    causes: &str,
+) -> Uuid {
+    let createset = entities::problems::ProblemCreateset {
+        title: String::from("Problem for Testing"),
+        status: Some(status),
+        detection_timedate: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        description: String::from(description),
+        causes: String::from(causes),
+        workarounds: Some(String::from("docs.local/workarounds/testing.pdf")),
+        resolutions: None,
+    };
+
+    let problem = entities::problems::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    problem.id
+}
+
+async fn post_published_known_error(
+    context: &DbTestContext,
+    description: &str,
+    causes: &str,
+) -> Uuid {
+    let problem_id = post_problem(
+        context,
+        entities::problems::ProblemStatus::KnownError,
+        description,
+        causes,
+    )
+    .await;
+
+    let updateset = KnownErrorUpdateset {
+        symptoms: None,
+        root_cause: None,
+        workaround_steps: None,
+        ci_ids: None,
+        published: Some(Some(true)),
+        draft_article: false,
+    };
+    known_errors::update(problem_id, updateset, &context.db_pool)
+        .await
+        .unwrap();
+
+    problem_id
+}
+
+async fn search(context: &DbTestContext, query: &str) -> Vec<KnownErrorMatch> {
+    let response = context
+        .app
+        .request(&format!("/api/knownerrors?{}", query))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    response
+        .into_body()
+        .into_json::<Vec<KnownErrorMatch>>()
+        .await
+}
+
+fn matched_ids(matches: &[KnownErrorMatch]) -> Vec<Uuid> {
+    matches.iter().map(|m| m.known_error.problem_id).collect()
+}
+
+#[db_test]
+async fn test_read_not_known_error(context: &DbTestContext) {
+    let problem_id = post_problem(
+        context,
+        entities::problems::ProblemStatus::Open,
+        "Printers don't print.",
+        "Nobody knows yet.",
+    )
+    .await;
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/knownerror", problem_id))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_created_on_known_error_status(context: &DbTestContext) {
+    let problem_id = post_problem(
+        context,
+        entities::problems::ProblemStatus::Open,
+        "Printers don't print.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+    let incident_id = post_incident(context, "Can't print", "Nothing comes out.").await;
+    let ci_id = post_ci(context).await;
+    entities::incidents::ci_relations::create(incident_id, ci_id, &context.db_pool)
+        .await
+        .unwrap();
+    incident_relations::create(problem_id, incident_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let payload = json!({ "status": "knownerror" });
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}", problem_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/knownerror", problem_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let known_error = response.into_body().into_json::<KnownError>().await;
+    assert_that!(
+        known_error,
+        matches_pattern!(KnownError {
+            problem_id: eq(&problem_id),
+            symptoms: eq("Printers don't print."),
+            root_cause: eq("The print spooler crashes on big jobs."),
+            workaround_steps: elements_are![eq("docs.local/workarounds/testing.pdf")],
+            ci_ids: elements_are![eq(&ci_id)],
+            published: eq(&false),
+            ..
+        })
+    );
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let problem_id = post_problem(
+        context,
+        entities::problems::ProblemStatus::KnownError,
+        "Printers don't print.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+    let ci_id = post_ci(context).await;
+
+    let payload = json!(KnownErrorUpdateset {
+        symptoms: None,
+        root_cause: None,
+        workaround_steps: Some(Some(vec![
+            String::from("Split the job in smaller ones."),
+            String::from("Restart the spooler if it hangs."),
+        ])),
+        ci_ids: Some(Some(vec![ci_id])),
+        published: Some(Some(true)),
+        draft_article: false,
+    });
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/knownerror", problem_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let known_error = response.into_body().into_json::<KnownError>().await;
+    assert_that!(
+        known_error,
+        matches_pattern!(KnownError {
+            symptoms: eq("Printers don't print."),
+            workaround_steps: elements_are![
+                eq("Split the job in smaller ones."),
+                eq("Restart the spooler if it hangs.")
+            ],
+            ci_ids: elements_are![eq(&ci_id)],
+            published: eq(&true),
+            ..
+        })
+    );
+
+    let known_error_after = known_errors::load(problem_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(known_error_after, eq(&known_error));
+}
+
+#[db_test]
+async fn test_update_invalid(context: &DbTestContext) {
+    let problem_id = post_problem(
+        context,
+        entities::problems::ProblemStatus::KnownError,
+        "Printers don't print.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+
+    let invalid_payloads = [
+        json!({ "workaround_steps": ["Restart the spooler.", ""] }),
+        json!({ "workaround_steps": null }),
+        json!({ "symptoms": "" }),
+        json!({ "ci_ids": [Uuid::new_v4()] }),
+    ];
+
+    for payload in invalid_payloads {
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}/knownerror", problem_id))
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let known_error_after = known_errors::load(problem_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(
+        known_error_after.workaround_steps,
+        elements_are![eq("docs.local/workarounds/testing.pdf")]
+    );
+}
+
+#[db_test]
+async fn test_update_nonexistent(context: &DbTestContext) {
+    let payload = json!({ "published": true });
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/knownerror", Uuid::new_v4()))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_search_ranked(context: &DbTestContext) {
+    let printers = post_published_known_error(
+        context,
+        "Printers don't print big documents.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+    let network = post_published_known_error(
+        context,
+        "Network is slow in the main office.",
+        "Router doesn't do hardware offloading for big transfers.",
+    )
+    .await;
+    post_problem(
+        context,
+        entities::problems::ProblemStatus::KnownError,
+        "Printers are out of toner.",
+        "Nobody orders toner.",
+    )
+    .await;
+
+    let matches = search(context, "q=printer+spooler+slow").await;
+    assert_that!(
+        matched_ids(&matches),
+        elements_are![eq(&printers), eq(&network)]
+    );
+    assert_that!(matches[0].rank, gt(matches[1].rank));
+
+    let matches = search(context, "q=printing").await;
+    assert_that!(matched_ids(&matches), elements_are![eq(&printers)]);
+
+    let matches = search(context, "q=toner").await;
+    assert_that!(matches, is_empty());
+}
+
+#[db_test]
+async fn test_search_by_ci(context: &DbTestContext) {
+    let printers =
+        post_published_known_error(context, "Printers are slow.", "The print spooler crashes.")
+            .await;
+    post_published_known_error(context, "Network is slow.", "Router is misconfigured.").await;
+    let ci_id = post_ci(context).await;
+    let updateset = KnownErrorUpdateset {
+        symptoms: None,
+        root_cause: None,
+        workaround_steps: None,
+        ci_ids: Some(Some(vec![ci_id])),
+        published: None,
+        draft_article: false,
+    };
+    known_errors::update(printers, updateset, &context.db_pool)
+        .await
+        .unwrap();
+
+    let matches = search(context, &format!("q=slow&ci_id={}", ci_id)).await;
+    assert_that!(matched_ids(&matches), elements_are![eq(&printers)]);
+}
+
+#[db_test]
+async fn test_search_by_incident(context: &DbTestContext) {
+    let printers = post_published_known_error(
+        context,
+        "Printers don't print.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+    post_published_known_error(context, "Network is slow.", "Router is misconfigured.").await;
+    let incident_id = post_incident(
+        context,
+        "Can't print the report",
+        "The spooler hangs when I send it.",
+    )
+    .await;
+
+    let matches = search(context, &format!("incident_id={}", incident_id)).await;
+    assert_that!(matched_ids(&matches), elements_are![eq(&printers)]);
+
+    let response = context
+        .app
+        .request(&format!("/api/knownerrors?incident_id={}", Uuid::new_v4()))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_search_invalid(context: &DbTestContext) {
+    let incident_id = post_incident(context, "Can't print", "Nothing comes out.").await;
+
+    for query in [
+        String::from(""),
+        format!("q=printer&incident_id={}", incident_id),
+        String::from("q=printer&limit=0"),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/knownerrors?{}", query))
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_link_incident(context: &DbTestContext) {
+    let problem_id = post_published_known_error(
+        context,
+        "Printers don't print.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+    let incident_id = post_incident(context, "Can't print", "Nothing comes out.").await;
+
+    let payload = json!(CreateIncidentRelation { incident_id });
+    let response = context
+        .app
+        .request(&format!("/api/knownerrors/{}/incidents", problem_id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let known_error = response.into_body().into_json::<KnownError>().await;
+    assert_that!(known_error.problem_id, eq(problem_id));
+
+    let relations = incident_relations::load_all(problem_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(
+        relations,
+        elements_are![matches_pattern!(ProblemIncidentRelation {
+            incident_id: eq(&incident_id),
+            ..
+        })]
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/knownerrors/{}/incidents", problem_id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_link_incident_unpublished(context: &DbTestContext) {
+    let problem_id = post_problem(
+        context,
+        entities::problems::ProblemStatus::KnownError,
+        "Printers don't print.",
+        "The print spooler crashes on big jobs.",
+    )
+    .await;
+    let incident_id = post_incident(context, "Can't print", "Nothing comes out.").await;
+
+    let payload = json!(CreateIncidentRelation { incident_id });
+    let response = context
+        .app
+        .request(&format!("/api/knownerrors/{}/incidents", problem_id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let relations = incident_relations::load_all(problem_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(relations, is_empty());
+}
@@ -1,10 +1,39 @@
 #![allow(missing_docs)]
+mod audit_test;
+mod auth_test;
+mod change_calendar_test;
 mod changes_test;
 mod ci_changes_test;
+mod ci_impact_test;
+mod ci_relations_test;
 mod configuration_test;
+mod freeze_periods_test;
+mod incident_children_test;
+mod incident_merge_test;
+mod incident_sla_test;
 mod incidents_ci_relations_test;
 mod incidents_test;
+mod knowledge_articles_test;
+mod knowledge_categories_test;
+mod known_errors_test;
+mod major_incidents_test;
+mod priority_matrix_test;
+mod problem_candidates_test;
 mod problem_incident_relations_test;
+mod problem_rca_test;
 mod problems_test;
+mod rfc_approvals_test;
+mod rfc_conflicts_test;
 mod rfc_incident_relations_test;
 mod rfc_problem_relations_test;
+mod rfc_reviews_test;
+mod rfc_risk_test;
+mod roles_test;
+mod root_cause_categories_test;
+mod search_test;
+mod service_catalog_test;
+mod service_requests_test;
+mod sla_policies_test;
+mod teams_test;
+mod timeline_test;
+mod users_test;
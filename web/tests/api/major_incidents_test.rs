@@ -0,0 +1,516 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    incidents::major::{
+        Communication, CommunicationCreateset, MajorIncident, MajorIncidentSummary,
+        DEFAULT_UPDATE_CADENCE_MINUTES,
+    },
+    incidents::{Incident, IncidentCreateset, IncidentImpact, IncidentStatus, IncidentUrgency},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> IncidentCreateset {
+    IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: Some(IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("This is a fictional incident made for testing."),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    }
+}
+
+async fn post_incident(context: &DbTestContext, createset: IncidentCreateset) -> Incident {
+    entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+}
+
+async fn post_user(context: &DbTestContext) -> Uuid {
+    let user = entities::users::create(
+        entities::users::UserCreateset {
+            username: format!("commander-{}", Uuid::new_v4()),
+            full_name: String::from("Incident Commander"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    user.id
+}
+
+async fn declare(
+    context: &DbTestContext,
+    incident_id: Uuid,
+    payload: serde_json::Value,
+) -> axum::response::Response {
+    context
+        .app
+        .request(&format!("/api/majorincidents/{}", incident_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn read_major_incident(context: &DbTestContext, incident_id: Uuid) -> MajorIncident {
+    let response = context
+        .app
+        .request(&format!("/api/majorincidents/{}", incident_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    response.into_body().into_json::<MajorIncident>().await
+}
+
+/// Create an Incident that became a major incident two hours ago, so its first update is
+/// overdue.
+async fn post_overdue_incident(context: &DbTestContext) -> Incident {
+    post_incident(
+        context,
+        IncidentCreateset {
+            created_at: Some(Utc::now() - TimeDelta::hours(2)),
+            impact: IncidentImpact::High,
+            urgency: IncidentUrgency::High,
+            ..create_basic_createset()
+        },
+    )
+    .await
+}
+
+#[db_test]
+async fn test_critical_incidents_become_major(context: &DbTestContext) {
+    let incident = post_incident(
+        context,
+        IncidentCreateset {
+            created_at: None,
+            impact: IncidentImpact::High,
+            urgency: IncidentUrgency::High,
+            ..create_basic_createset()
+        },
+    )
+    .await;
+    assert_that!(incident.major, eq(true));
+
+    let major_incident = read_major_incident(context, incident.id).await;
+    assert_that!(
+        major_incident,
+        matches_pattern!(MajorIncident {
+            incident_id: eq(&incident.id),
+            commander_id: none(),
+            update_cadence_minutes: eq(&DEFAULT_UPDATE_CADENCE_MINUTES),
+            last_update_at: none(),
+            next_update_due_at: some(eq(&(major_incident.declared_at
+                + TimeDelta::minutes(DEFAULT_UPDATE_CADENCE_MINUTES.into())))),
+            update_overdue: eq(&false),
+            ..
+        })
+    );
+
+    // Incidents becoming critical later on are declared too.
+    let incident = post_incident(context, create_basic_createset()).await;
+    assert_that!(incident.major, eq(false));
+    let payload = json!({ "impact": "high", "urgency": "high" });
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", incident.id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let incident = response.into_body().into_json::<Incident>().await;
+    assert_that!(incident.major, eq(true));
+}
+
+#[db_test]
+async fn test_declare(context: &DbTestContext) {
+    let incident = post_incident(context, create_basic_createset()).await;
+    let commander_id = post_user(context).await;
+
+    let response = declare(
+        context,
+        incident.id,
+        json!({ "commander_id": commander_id, "update_cadence_minutes": 15 }),
+    )
+    .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let major_incident = response.into_body().into_json::<MajorIncident>().await;
+    assert_that!(
+        major_incident,
+        matches_pattern!(MajorIncident {
+            incident_id: eq(&incident.id),
+            commander_id: some(eq(&commander_id)),
+            update_cadence_minutes: eq(&15),
+            ..
+        })
+    );
+
+    let incident_after = entities::incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(incident_after.major, eq(true));
+
+    let response = declare(context, incident.id, json!({ "commander_id": null })).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<MajorIncident>().await;
+    assert_that!(
+        updated,
+        matches_pattern!(MajorIncident {
+            commander_id: none(),
+            update_cadence_minutes: eq(&15),
+            declared_at: eq(&major_incident.declared_at),
+            ..
+        })
+    );
+}
+
+#[db_test]
+async fn test_declare_invalid(context: &DbTestContext) {
+    let incident = post_incident(context, create_basic_createset()).await;
+
+    for payload in [
+        json!({ "commander_id": Uuid::new_v4() }),
+        json!({ "update_cadence_minutes": 0 }),
+        json!({ "update_cadence_minutes": null }),
+    ] {
+        let response = declare(context, incident.id, payload).await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let resolved = post_incident(
+        context,
+        IncidentCreateset {
+            status: Some(IncidentStatus::Resolved),
+            ..create_basic_createset()
+        },
+    )
+    .await;
+    let response = declare(context, resolved.id, json!({})).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = declare(context, Uuid::new_v4(), json!({})).await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let incident_after = entities::incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(incident_after.major, eq(false));
+}
+
+#[db_test]
+async fn test_read_not_major(context: &DbTestContext) {
+    let incident = post_incident(context, create_basic_createset()).await;
+
+    for uri in [
+        format!("/api/majorincidents/{}", incident.id),
+        format!("/api/majorincidents/{}/summary", incident.id),
+        format!("/api/majorincidents/{}/communications", incident.id),
+        format!("/api/majorincidents/{}", Uuid::new_v4()),
+    ] {
+        let response = context.app.request(&uri).send().await;
+
+        assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+    }
+}
+
+#[db_test]
+async fn test_communications(context: &DbTestContext) {
+    let incident = post_overdue_incident(context).await;
+    declare(
+        context,
+        incident.id,
+        json!({ "update_cadence_minutes": 60 }),
+    )
+    .await;
+
+    let major_incident = read_major_incident(context, incident.id).await;
+    assert_that!(major_incident.update_overdue, eq(true));
+
+    let mut communications = vec![];
+    for audience in ["Customer support leads", "Executives"] {
+        let payload = json!(CommunicationCreateset {
+            audience: String::from(audience),
+            message: String::from("The proxy is back up for the main office."),
+        });
+        let response = context
+            .app
+            .request(&format!(
+                "/api/majorincidents/{}/communications",
+                incident.id
+            ))
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::CREATED));
+        communications.push(response.into_body().into_json::<Communication>().await);
+    }
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/majorincidents/{}/communications",
+            incident.id
+        ))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let read = response.into_body().into_json::<Vec<Communication>>().await;
+    assert_that!(read, eq(&communications));
+
+    let last_update_at: DateTime<Utc> = communications[1].sent_at;
+    let major_incident = read_major_incident(context, incident.id).await;
+    assert_that!(
+        major_incident,
+        matches_pattern!(MajorIncident {
+            last_update_at: some(eq(&last_update_at)),
+            next_update_due_at: some(eq(&(last_update_at + TimeDelta::minutes(60)))),
+            update_overdue: eq(&false),
+            ..
+        })
+    );
+}
+
+#[db_test]
+async fn test_create_communication_invalid(context: &DbTestContext) {
+    let incident = post_incident(context, create_basic_createset()).await;
+
+    let payload = json!({ "audience": "Executives", "message": "All good." });
+    let response = context
+        .app
+        .request(&format!(
+            "/api/majorincidents/{}/communications",
+            incident.id
+        ))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    declare(context, incident.id, json!({})).await;
+    let payload = json!({ "audience": "", "message": "All good." });
+    let response = context
+        .app
+        .request(&format!(
+            "/api/majorincidents/{}/communications",
+            incident.id
+        ))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_read_all(context: &DbTestContext) {
+    let overdue = post_overdue_incident(context).await;
+    let recent = post_incident(context, create_basic_createset()).await;
+    declare(context, recent.id, json!({})).await;
+    let resolved = post_incident(context, create_basic_createset()).await;
+    declare(context, resolved.id, json!({})).await;
+    entities::incidents::update(
+        resolved.id,
+        serde_json::from_value(json!({ "status": "resolved" })).unwrap(),
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    post_incident(context, create_basic_createset()).await;
+
+    for (query, expected) in [
+        ("", vec![resolved.id, recent.id, overdue.id]),
+        ("?active=true", vec![recent.id, overdue.id]),
+        ("?active=false", vec![resolved.id]),
+        ("?update_overdue=true", vec![overdue.id]),
+        ("?update_overdue=false", vec![resolved.id, recent.id]),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/majorincidents{}", query))
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+
+        let page = response
+            .into_body()
+            .into_json::<Page<MajorIncident>>()
+            .await;
+        let ids: Vec<Uuid> = page.items.iter().map(|m| m.incident_id).collect();
+        assert_that!(ids, eq(&expected), "query {}", query);
+    }
+}
+
+#[db_test]
+async fn test_closing_needs_problem(context: &DbTestContext) {
+    let incident = post_incident(context, create_basic_createset()).await;
+    declare(context, incident.id, json!({})).await;
+    entities::incidents::update(
+        incident.id,
+        serde_json::from_value(json!({ "status": "resolved" })).unwrap(),
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let payload = json!({
+        "status": "closed",
+        "resolution_code": "solved",
+        "resolution_notes": "Restarted the proxy service.",
+    });
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", incident.id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let problem = entities::problems::create(
+        entities::problems::ProblemCreateset {
+            title: String::from("Proxy keeps crashing"),
+            status: None,
+            detection_timedate: None,
+            description: String::from("The proxy crashed during the major incident."),
+            causes: String::from("Unknown yet."),
+            workarounds: None,
+            resolutions: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::problems::incident_relations::create(problem.id, incident.id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}", incident.id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_summary(context: &DbTestContext) {
+    let incident = post_incident(context, create_basic_createset()).await;
+    declare(context, incident.id, json!({})).await;
+    let child = post_incident(
+        context,
+        IncidentCreateset {
+            parent_id: Some(incident.id),
+            ..create_basic_createset()
+        },
+    )
+    .await;
+    let unrelated = post_incident(context, create_basic_createset()).await;
+
+    let ci = entities::configuration::create(
+        entities::configuration::ConfigItemCreateset {
+            name: String::from("Main office proxy"),
+            status: None,
+            created_at: None,
+            r#type: None,
+            owner_team_id: None,
+            description: String::from("Proxy of the main office."),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::incidents::ci_relations::create(child.id, ci.id, &context.db_pool)
+        .await
+        .unwrap();
+    entities::incidents::ci_relations::create(unrelated.id, ci.id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let requester = post_user(context).await;
+    let rfc = entities::changes::create(
+        entities::changes::RFCCreateset {
+            title: String::from("Upgrade the proxy"),
+            r#type: None,
+            status: Some(entities::changes::RFCStatus::Submitted),
+            created_at: None,
+            finished_at: None,
+            requester_id: requester,
+            description: String::from("Upgrade the proxy to the latest version."),
+            planned_start_at: None,
+            planned_end_at: None,
+            implementation_plan: Some(String::from("Roll out the change.")),
+            backout_plan: Some(String::from("Undo the change.")),
+            test_plan: Some(String::from("Check that it works.")),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::changes::incident_relations::create(
+        rfc.id,
+        entities::changes::incident_relations::RFCIncidentCreateset {
+            incident_id: incident.id,
+            relation_type: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/majorincidents/{}/summary", incident.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let summary = response
+        .into_body()
+        .into_json::<MajorIncidentSummary>()
+        .await;
+    assert_that!(summary.major_incident.incident_id, eq(incident.id));
+    assert_that!(summary.incident.id, eq(incident.id));
+    assert_that!(
+        summary.children.iter().map(|i| i.id).collect::<Vec<_>>(),
+        elements_are![eq(&child.id)]
+    );
+    assert_that!(summary.config_items, elements_are![eq(&ci)]);
+    assert_that!(
+        summary.rfcs.iter().map(|r| r.id).collect::<Vec<_>>(),
+        elements_are![eq(&rfc.id)]
+    );
+    assert_that!(summary.problems, is_empty());
+}
@@ -0,0 +1,285 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::{DateTime, Utc};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    incidents::{
+        self, major, IncidentCreateset, IncidentImpact, IncidentPrio, IncidentStatus,
+        IncidentUrgency,
+    },
+    priority_matrix::{PriorityMatrix, PriorityMatrixEntry, PriorityMatrixUpdateset},
+    sla_policies::{self, SlaPolicyCreateset, SlaTarget},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+
+fn create_basic_createset() -> IncidentCreateset {
+    IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: None,
+        created_at: Some("2024-03-01T10:00:00Z".parse().unwrap()),
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    }
+}
+
+/// Matrix with every level of impact and urgency, giving every Incident the same priority.
+fn build_updateset(priority: IncidentPrio) -> PriorityMatrixUpdateset {
+    use IncidentImpact as I;
+    use IncidentUrgency as U;
+
+    let mut entries = Vec::new();
+    for impact in [I::Critical, I::High, I::Medium, I::Low, I::Minimal] {
+        for urgency in [U::Critical, U::High, U::Medium, U::Low, U::Minimal] {
+            entries.push(PriorityMatrixEntry {
+                impact,
+                urgency,
+                priority,
+            });
+        }
+    }
+
+    PriorityMatrixUpdateset { entries }
+}
+
+async fn put_matrix(
+    context: &DbTestContext,
+    payload: serde_json::Value,
+) -> axum::response::Response {
+    context
+        .app
+        .request("/api/prioritymatrix")
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+#[db_test]
+async fn test_read_default(context: &DbTestContext) {
+    let response = context.app.request("/api/prioritymatrix").send().await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let matrix = response.into_body().into_json::<PriorityMatrix>().await;
+    assert_that!(
+        matrix.impacts,
+        eq(&vec![
+            IncidentImpact::High,
+            IncidentImpact::Medium,
+            IncidentImpact::Low
+        ])
+    );
+    assert_that!(
+        matrix.urgencies,
+        eq(&vec![
+            IncidentUrgency::High,
+            IncidentUrgency::Medium,
+            IncidentUrgency::Low
+        ])
+    );
+    assert_that!(matrix.entries, len(eq(9)));
+}
+
+#[db_test]
+async fn test_default_priorities(context: &DbTestContext) {
+    use IncidentImpact as I;
+    use IncidentPrio as P;
+    use IncidentUrgency as U;
+
+    for (impact, urgency, expected) in [
+        (I::Low, U::Low, P::Low),
+        (I::Medium, U::Low, P::Low),
+        (I::Low, U::Medium, P::Low),
+        (I::High, U::Low, P::Moderate),
+        (I::Low, U::High, P::Moderate),
+        (I::Medium, U::Medium, P::Moderate),
+        (I::High, U::Medium, P::High),
+        (I::Medium, U::High, P::High),
+        (I::High, U::High, P::Critical),
+    ] {
+        let incident = incidents::create(
+            IncidentCreateset {
+                impact,
+                urgency,
+                ..create_basic_createset()
+            },
+            &context.db_pool,
+        )
+        .await
+        .unwrap();
+
+        assert_that!(incident.priority, eq(expected));
+    }
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let updateset = build_updateset(IncidentPrio::High);
+
+    let response = put_matrix(context, json!(updateset)).await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let matrix = response.into_body().into_json::<PriorityMatrix>().await;
+    assert_that!(matrix.impacts, len(eq(5)));
+    assert_that!(matrix.urgencies, len(eq(5)));
+    assert_that!(matrix.entries, len(eq(25)));
+
+    // Existing Incidents follow the matrix, and new levels can be used.
+    let incident_after = incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(incident_after.priority, eq(IncidentPrio::High));
+    let critical = incidents::create(
+        IncidentCreateset {
+            impact: IncidentImpact::Critical,
+            urgency: IncidentUrgency::Minimal,
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_that!(critical.priority, eq(IncidentPrio::High));
+}
+
+#[db_test]
+async fn test_update_moves_deadlines(context: &DbTestContext) {
+    let policy = SlaPolicyCreateset {
+        name: String::from("Around the clock"),
+        description: String::from(""),
+        is_default: Some(true),
+        timezone: None,
+        business_hours_start: None,
+        business_hours_end: None,
+        business_days: None,
+        targets: vec![
+            SlaTarget {
+                priority: IncidentPrio::Low,
+                response_minutes: 30,
+                resolution_minutes: 240,
+            },
+            SlaTarget {
+                priority: IncidentPrio::High,
+                response_minutes: 15,
+                resolution_minutes: 60,
+            },
+        ],
+        holidays: vec![],
+    };
+    sla_policies::create(policy, &context.db_pool)
+        .await
+        .unwrap();
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = put_matrix(context, json!(build_updateset(IncidentPrio::High))).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let incident_after = incidents::load(incident.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(
+        incident_after.resolution_due_at,
+        some(eq("2024-03-01T11:00:00Z".parse::<DateTime<Utc>>().unwrap()))
+    );
+}
+
+#[db_test]
+async fn test_update_declares_major_incidents(context: &DbTestContext) {
+    let incident = incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let resolved = incidents::create(
+        IncidentCreateset {
+            status: Some(IncidentStatus::Resolved),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = put_matrix(context, json!(build_updateset(IncidentPrio::Critical))).await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let major_incident = major::load(incident.id, &context.db_pool).await.unwrap();
+    assert_that!(major_incident.priority, eq(IncidentPrio::Critical));
+    let result = major::load(resolved.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_update_invalid(context: &DbTestContext) {
+    let mut incomplete = build_updateset(IncidentPrio::Low);
+    incomplete.entries.pop();
+    let mut duplicated = build_updateset(IncidentPrio::Low);
+    duplicated.entries[1] = duplicated.entries[0].clone();
+
+    for payload in [
+        json!(incomplete),
+        json!(duplicated),
+        json!(PriorityMatrixUpdateset { entries: vec![] }),
+    ] {
+        let response = put_matrix(context, payload).await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_update_drops_level_in_use(context: &DbTestContext) {
+    incidents::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let updateset = PriorityMatrixUpdateset {
+        entries: vec![PriorityMatrixEntry {
+            impact: IncidentImpact::High,
+            urgency: IncidentUrgency::High,
+            priority: IncidentPrio::Critical,
+        }],
+    };
+
+    let response = put_matrix(context, json!(updateset)).await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = context.app.request("/api/prioritymatrix").send().await;
+    let matrix = response.into_body().into_json::<PriorityMatrix>().await;
+    assert_that!(matrix.entries, len(eq(9)));
+}
+
+#[db_test]
+async fn test_update_needs_prioritymatrix_manage(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/prioritymatrix")
+        .method(Method::PUT)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(
+            json!(build_updateset(IncidentPrio::Low)).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
@@ -0,0 +1,403 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::{Duration, Utc};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    problem_candidates::{self, DetectionThresholds, ProblemCandidate, ProblemCandidateStatus},
+    problems::{
+        incident_relations::{self, ProblemIncidentRelation},
+        Problem, ProblemCreateset,
+    },
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+const THRESHOLDS: DetectionThresholds = DetectionThresholds {
+    window: Duration::hours(72),
+    min_incidents: 3,
+    min_similarity: 0.3,
+};
+
+async fn post_ci(context: &DbTestContext) -> Uuid {
+    let createset = entities::configuration::ConfigItemCreateset {
+        name: String::from("VPN Gateway"),
+        status: Some(entities::configuration::CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: Some(String::from("Test CI")),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    let ci = entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    ci.id
+}
+
+async fn post_incident(
+    context: &DbTestContext,
+    ci_id: Uuid,
+    title: &str,
+    description: &str,
+    hours_ago: i64,
+) -> Uuid {
+    let createset = entities::incidents::IncidentCreateset {
+        title: String::from(title),
+        status: Some(entities::incidents::IncidentStatus::InProgress),
+        created_at: Some(Utc::now() - Duration::hours(hours_ago)),
+        impact: entities::incidents::IncidentImpact::Low,
+        urgency: entities::incidents::IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from(description),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    let incident = entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+    entities::incidents::ci_relations::create(incident.id, ci_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    incident.id
+}
+
+/// Post three recurring Incidents on `ci_id`, earliest first.
+async fn post_recurring_incidents(context: &DbTestContext, ci_id: Uuid) -> [Uuid; 3] {
+    [
+        post_incident(
+            context,
+            ci_id,
+            "VPN connection drops",
+            "The VPN connection drops after a few minutes.",
+            30,
+        )
+        .await,
+        post_incident(
+            context,
+            ci_id,
+            "VPN keeps dropping",
+            "My VPN connection drops all the time.",
+            20,
+        )
+        .await,
+        post_incident(
+            context,
+            ci_id,
+            "VPN disconnects",
+            "VPN connection drops while working.",
+            10,
+        )
+        .await,
+    ]
+}
+
+fn create_basic_createset() -> ProblemCreateset {
+    ProblemCreateset {
+        title: String::from("VPN drops connections"),
+        status: None,
+        detection_timedate: None,
+        description: String::from("VPN connections drop every few minutes."),
+        causes: String::from("Unknown yet."),
+        workarounds: None,
+        resolutions: None,
+    }
+}
+
+#[db_test]
+async fn test_detect(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let incident_ids = post_recurring_incidents(context, ci_id).await;
+    post_incident(context, ci_id, "Printer out of toner", "No toner left.", 5).await;
+
+    let candidates = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap();
+
+    assert_that!(
+        candidates,
+        elements_are![matches_pattern!(ProblemCandidate {
+            ci_id: some(eq(&ci_id)),
+            title: eq("VPN connection drops"),
+            status: eq(&ProblemCandidateStatus::Proposed),
+            problem_id: none(),
+            incident_ids: eq(&incident_ids),
+            decided_at: none(),
+            ..
+        })]
+    );
+
+    let candidates = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(candidates, is_empty());
+}
+
+#[db_test]
+async fn test_detect_below_thresholds(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let other_ci_id = post_ci(context).await;
+    post_incident(
+        context,
+        ci_id,
+        "VPN connection drops",
+        "The VPN connection drops after a few minutes.",
+        30,
+    )
+    .await;
+    post_incident(
+        context,
+        ci_id,
+        "VPN keeps dropping",
+        "My VPN connection drops all the time.",
+        20,
+    )
+    .await;
+    post_incident(
+        context,
+        other_ci_id,
+        "VPN disconnects",
+        "VPN connection drops while working.",
+        10,
+    )
+    .await;
+    post_incident(
+        context,
+        ci_id,
+        "VPN disconnects",
+        "VPN connection drops while working.",
+        24 * 7,
+    )
+    .await;
+
+    let candidates = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap();
+
+    assert_that!(candidates, is_empty());
+}
+
+#[db_test]
+async fn test_detect_skips_related_incidents(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let incident_ids = post_recurring_incidents(context, ci_id).await;
+    let problem = entities::problems::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    incident_relations::create(problem.id, incident_ids[0], &context.db_pool)
+        .await
+        .unwrap();
+
+    let candidates = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap();
+
+    assert_that!(candidates, is_empty());
+}
+
+#[db_test]
+async fn test_read_all(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    post_recurring_incidents(context, ci_id).await;
+    let candidate = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap()
+        .remove(0);
+
+    let response = context
+        .app
+        .request("/api/problemcandidates?status=proposed")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page = response
+        .into_body()
+        .into_json::<Page<ProblemCandidate>>()
+        .await;
+    assert_that!(page.items, elements_are![eq(&candidate)]);
+
+    let response = context
+        .app
+        .request("/api/problemcandidates?status=accepted")
+        .send()
+        .await;
+    let page = response
+        .into_body()
+        .into_json::<Page<ProblemCandidate>>()
+        .await;
+    assert_that!(page.items, is_empty());
+}
+
+#[db_test]
+async fn test_read_one_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/problemcandidates/{}", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_accept(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let incident_ids = post_recurring_incidents(context, ci_id).await;
+    let candidate = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap()
+        .remove(0);
+
+    let payload = json!(create_basic_createset());
+    let response = context
+        .app
+        .request(&format!("/api/problemcandidates/{}/accept", candidate.id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let problem = response.into_body().into_json::<Problem>().await;
+    assert_that!(problem.title, eq("VPN drops connections"));
+
+    let relations = incident_relations::load_all(problem.id, &context.db_pool)
+        .await
+        .unwrap();
+    let related_ids: Vec<Uuid> = relations
+        .iter()
+        .map(|r: &ProblemIncidentRelation| r.incident_id)
+        .collect();
+    assert_that!(
+        related_ids,
+        unordered_elements_are![
+            eq(&incident_ids[0]),
+            eq(&incident_ids[1]),
+            eq(&incident_ids[2])
+        ]
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/problemcandidates/{}", candidate.id))
+        .send()
+        .await;
+    let candidate_after = response.into_body().into_json::<ProblemCandidate>().await;
+    assert_that!(
+        candidate_after,
+        matches_pattern!(ProblemCandidate {
+            status: eq(&ProblemCandidateStatus::Accepted),
+            problem_id: some(eq(&problem.id)),
+            decided_at: some(anything()),
+            ..
+        })
+    );
+
+    for action in ["accept", "reject"] {
+        let response = context
+            .app
+            .request(&format!(
+                "/api/problemcandidates/{}/{}",
+                candidate.id, action
+            ))
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::CONFLICT));
+    }
+}
+
+#[db_test]
+async fn test_accept_invalid(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    post_recurring_incidents(context, ci_id).await;
+    let candidate = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap()
+        .remove(0);
+
+    let payload = json!(ProblemCreateset {
+        title: String::from(""),
+        ..create_basic_createset()
+    });
+    let response = context
+        .app
+        .request(&format!("/api/problemcandidates/{}/accept", candidate.id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let candidate_after = problem_candidates::load(candidate.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(candidate_after, eq(&candidate));
+}
+
+#[db_test]
+async fn test_accept_nonexistent(context: &DbTestContext) {
+    let payload = json!(create_basic_createset());
+    let response = context
+        .app
+        .request(&format!("/api/problemcandidates/{}/accept", Uuid::new_v4()))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_reject(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    post_recurring_incidents(context, ci_id).await;
+    let candidate = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap()
+        .remove(0);
+
+    let response = context
+        .app
+        .request(&format!("/api/problemcandidates/{}/reject", candidate.id))
+        .method(Method::POST)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let candidate_after = response.into_body().into_json::<ProblemCandidate>().await;
+    assert_that!(
+        candidate_after,
+        matches_pattern!(ProblemCandidate {
+            status: eq(&ProblemCandidateStatus::Rejected),
+            problem_id: none(),
+            decided_at: some(anything()),
+            ..
+        })
+    );
+
+    let candidates = problem_candidates::detect(THRESHOLDS, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(candidates, is_empty());
+}
@@ -21,12 +21,16 @@ async fn post_incident(context: &DbTestContext) -> Uuid {
         title: String::from("Testing Incident"),
         status: Some(entities::incidents::IncidentStatus::InProgress),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
-        resolved_at: None,
         impact: entities::incidents::IncidentImpact::Low,
         urgency: entities::incidents::IncidentUrgency::Low,
-        owner: Some(String::from("Me")),
-        asignee: Some(String::from("Employee 567")),
+        assignment_group_id: None,
+        assignee_id: None,
         description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
     };
 
     let incident = entities::incidents::create(createset, &context.db_pool)
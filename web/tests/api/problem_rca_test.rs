@@ -0,0 +1,460 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    audit::AuditEntry,
+    problems::rca::{
+        IshikawaCategory, IshikawaCause, IshikawaCauseCreateset, RCAEvent, RCAEventCreateset,
+        RootCause, RootCauseAnalysis, RootCauseUpdateset, Why, WhyCreateset, MAX_WHYS,
+    },
+    root_cause_categories,
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_problem(context: &DbTestContext) -> Uuid {
+    let createset = entities::problems::ProblemCreateset {
+        title: String::from("Problem for Testing"),
+        status: Some(entities::problems::ProblemStatus::Open),
+        detection_timedate: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        description: String::from("This is a fake problem made for testing."),
+        causes: String::from("I need to test this."),
+        workarounds: None,
+        resolutions: None,
+    };
+
+    let problem = entities::problems::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    problem.id
+}
+
+async fn post_ci(context: &DbTestContext) -> Uuid {
+    let createset = entities::configuration::ConfigItemCreateset {
+        name: String::from("Testing CI for RCA"),
+        status: Some(entities::configuration::CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: Some(String::from("Test CI")),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    let ci = entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    ci.id
+}
+
+async fn post_incident(context: &DbTestContext) -> Uuid {
+    let createset = entities::incidents::IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: Some(entities::incidents::IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: entities::incidents::IncidentImpact::Low,
+        urgency: entities::incidents::IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    let incident = entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    incident.id
+}
+
+async fn read_rca(context: &DbTestContext, problem_id: Uuid) -> RootCauseAnalysis {
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/rca", problem_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    response.into_body().into_json::<RootCauseAnalysis>().await
+}
+
+fn why(question: &str, answer: &str) -> WhyCreateset {
+    WhyCreateset {
+        question: String::from(question),
+        answer: String::from(answer),
+    }
+}
+
+#[db_test]
+async fn test_read_empty(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+
+    let rca = read_rca(context, problem_id).await;
+
+    assert_that!(
+        rca,
+        eq(&RootCauseAnalysis {
+            problem_id,
+            root_cause: None,
+            whys: vec![],
+            causes: vec![],
+            events: vec![],
+        })
+    );
+}
+
+#[db_test]
+async fn test_read_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/rca", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_replace_whys(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+
+    for whys in [
+        vec![
+            why("Why did the VPN drop?", "The gateway crashed."),
+            why("Why did the gateway crash?", "It ran out of memory."),
+            why(
+                "Why did it run out of memory?",
+                "Its limits are the defaults.",
+            ),
+        ],
+        vec![
+            why("Why did the VPN drop?", "The gateway rebooted."),
+            why("Why did the gateway reboot?", "A firmware upgrade."),
+        ],
+    ] {
+        let payload = json!(whys);
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}/rca/whys", problem_id))
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+
+        let replaced = response.into_body().into_json::<Vec<Why>>().await;
+        let expected: Vec<Why> = whys
+            .into_iter()
+            .map(|w| Why {
+                question: w.question,
+                answer: w.answer,
+            })
+            .collect();
+        assert_that!(replaced, eq(&expected));
+    }
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(
+        rca.whys,
+        elements_are![
+            field!(Why.answer, eq("The gateway rebooted.")),
+            field!(Why.answer, eq("A firmware upgrade."))
+        ]
+    );
+}
+
+#[db_test]
+async fn test_replace_whys_invalid(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+
+    for payload in [
+        json!(vec![why("Why?", "Because."); MAX_WHYS + 1]),
+        json!([why("Why?", "")]),
+        json!([{ "question": "Why?" }]),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}/rca/whys", problem_id))
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(rca.whys, is_empty());
+}
+
+#[db_test]
+async fn test_causes(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+
+    let payload = json!(IshikawaCauseCreateset {
+        category: IshikawaCategory::Technology,
+        description: String::from("Gateway memory isn't monitored."),
+    });
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/rca/causes", problem_id))
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let cause = response.into_body().into_json::<IshikawaCause>().await;
+
+    let payload = json!({ "category": "process" });
+    let response = context
+        .app
+        .request(&format!(
+            "/api/problems/{}/rca/causes/{}",
+            problem_id, cause.id
+        ))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(
+        rca.causes,
+        elements_are![matches_pattern!(IshikawaCause {
+            id: eq(&cause.id),
+            category: eq(&IshikawaCategory::Process),
+            description: eq("Gateway memory isn't monitored."),
+            ..
+        })]
+    );
+
+    // Causes can only be reached through their Problem.
+    let other_problem_id = post_problem(context).await;
+    let response = context
+        .app
+        .request(&format!(
+            "/api/problems/{}/rca/causes/{}",
+            other_problem_id, cause.id
+        ))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/problems/{}/rca/causes/{}",
+            problem_id, cause.id
+        ))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(rca.causes, is_empty());
+}
+
+#[db_test]
+async fn test_events(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+    let ci_id = post_ci(context).await;
+    let incident_id = post_incident(context).await;
+
+    let mut events = vec![];
+    for createset in [
+        RCAEventCreateset {
+            occurred_at: "2023-09-15T13:00:00Z".parse().unwrap(),
+            description: String::from("VPN users report drops."),
+            ci_id: None,
+            incident_id: Some(incident_id),
+            rfc_id: None,
+        },
+        RCAEventCreateset {
+            occurred_at: "2023-09-15T12:00:00Z".parse().unwrap(),
+            description: String::from("Gateway firmware upgraded."),
+            ci_id: Some(ci_id),
+            incident_id: None,
+            rfc_id: None,
+        },
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}/rca/events", problem_id))
+            .method(Method::POST)
+            .body(Body::from(json!(createset).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::CREATED));
+        events.push(response.into_body().into_json::<RCAEvent>().await);
+    }
+
+    let payload = json!({ "incident_id": null, "ci_id": ci_id });
+    let response = context
+        .app
+        .request(&format!(
+            "/api/problems/{}/rca/events/{}",
+            problem_id, events[0].id
+        ))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(
+        rca.events,
+        elements_are![
+            matches_pattern!(RCAEvent {
+                id: eq(&events[1].id),
+                ci_id: some(eq(&ci_id)),
+                ..
+            }),
+            matches_pattern!(RCAEvent {
+                id: eq(&events[0].id),
+                description: eq("VPN users report drops."),
+                ci_id: some(eq(&ci_id)),
+                incident_id: none(),
+                ..
+            })
+        ]
+    );
+}
+
+#[db_test]
+async fn test_events_invalid(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+
+    for payload in [
+        json!({
+            "occurred_at": "2023-09-15T12:00:00Z",
+            "description": "Gateway firmware upgraded.",
+            "rfc_id": Uuid::new_v4(),
+        }),
+        json!({
+            "occurred_at": "2023-09-15T12:00:00Z",
+            "description": "",
+        }),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}/rca/events", problem_id))
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(rca.events, is_empty());
+}
+
+#[db_test]
+async fn test_classify_root_cause(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+    let categories = root_cause_categories::load_all(&context.db_pool)
+        .await
+        .unwrap();
+    let category = categories
+        .iter()
+        .find(|c| c.name == "Configuration error")
+        .unwrap();
+
+    let payload = json!(RootCauseUpdateset {
+        category_id: category.id,
+        notes: String::from("The gateway was deployed with the default memory limits."),
+    });
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/rca/rootcause", problem_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let root_cause = response.into_body().into_json::<RootCause>().await;
+    assert_that!(
+        root_cause,
+        matches_pattern!(RootCause {
+            category_id: eq(&category.id),
+            category_name: eq("Configuration error"),
+            notes: eq("The gateway was deployed with the default memory limits."),
+            ..
+        })
+    );
+
+    let rca = read_rca(context, problem_id).await;
+    assert_that!(rca.root_cause, some(eq(&root_cause)));
+
+    // Categories classifying Problems can't be deleted.
+    let response = context
+        .app
+        .request(&format!("/api/rootcausecategories/{}", category.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let history = context
+        .app
+        .request(&format!("/api/problems/{}/history", problem_id))
+        .send()
+        .await
+        .into_body()
+        .into_json::<Vec<AuditEntry>>()
+        .await;
+    assert_that!(
+        history,
+        contains(field!(AuditEntry.source, eq("problem_root_causes")))
+    );
+}
+
+#[db_test]
+async fn test_classify_root_cause_invalid(context: &DbTestContext) {
+    let problem_id = post_problem(context).await;
+
+    let payload = json!({ "category_id": Uuid::new_v4() });
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/rca/rootcause", problem_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/rca/rootcause", Uuid::new_v4()))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
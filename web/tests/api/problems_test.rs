@@ -7,6 +7,7 @@ use hyper::StatusCode;
 use itil_back_db::entities::problems::{
     self, Problem, ProblemCreateset, ProblemStatus, ProblemUpdateset,
 };
+use itil_back_db::pagination::Page;
 use itil_back_macros::db_test;
 use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
 use serde_json::json;
@@ -185,9 +186,43 @@ async fn test_read_all(context: &DbTestContext) {
 
     assert_that!(response.status(), eq(StatusCode::OK));
 
-    let problems: Vec<Problem> = response.into_body().into_json::<Vec<Problem>>().await;
-    assert_that!(problems, len(eq(1)));
-    assert_that!(problems.first().unwrap().title, eq(&changeset.title));
+    let page: Page<Problem> = response.into_body().into_json::<Page<Problem>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items, len(eq(1)));
+    assert_that!(page.items.first().unwrap().title, eq(&changeset.title));
+}
+
+#[db_test]
+async fn test_read_all_filtered_by_detection(context: &DbTestContext) {
+    let changeset = create_basic_createset();
+    problems::create(changeset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+    problems::create(
+        ProblemCreateset {
+            title: String::from("Recent Problem"),
+            detection_timedate: Some("2024-01-10T00:00:00Z".parse().unwrap()),
+            ..changeset
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request("/api/problems?detected_after=2024-01-01T00:00:00Z")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page: Page<Problem> = response.into_body().into_json::<Page<Problem>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(
+        page.items.first().unwrap().title,
+        eq(&String::from("Recent Problem"))
+    );
 }
 
 #[db_test]
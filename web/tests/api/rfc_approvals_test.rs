@@ -0,0 +1,345 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    change_advisory_board::ChangeAdvisoryBoard,
+    changes::{
+        self,
+        approvals::RFCApproval,
+        risk::{RFCRiskAssessmentUpdateset, RFCRiskChoice},
+        RFCCreateset, RFCStatus, RFCType,
+    },
+    risk_questionnaire, roles,
+    teams::{self, members, TeamCreateset},
+    users::{self, UserCreateset},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_user(context: &DbTestContext, username: &str) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(username),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    users::create(createset, &context.db_pool).await.unwrap().id
+}
+
+/// Makes a team of change managers named after `usernames` the CAB, with quorum `quorum`,
+/// and returns their tokens.
+async fn setup_cab(context: &DbTestContext, usernames: &[&str], quorum: i32) -> Vec<String> {
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("CAB"),
+            description: String::from("Change Advisory Board"),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let mut tokens = Vec::new();
+    for username in usernames {
+        let user_id = post_user(context, username).await;
+        members::create(team.id, user_id, &context.db_pool)
+            .await
+            .unwrap();
+        roles::assign("change_manager", username, &context.db_pool)
+            .await
+            .unwrap();
+        tokens.push(context.token_for(username));
+    }
+
+    let response = context
+        .app
+        .request("/api/cab")
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({ "team_id": team.id, "quorum": quorum }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    tokens
+}
+
+/// Assess the risk of `rfc_id` picking the answer at `answer` of every question, `1` being
+/// medium risk with the default questionnaire.
+async fn assess_risk(context: &DbTestContext, rfc_id: Uuid, answer: usize) {
+    let questionnaire = risk_questionnaire::load(&context.db_pool).await.unwrap();
+    let answers = questionnaire
+        .questions
+        .iter()
+        .map(|q| RFCRiskChoice {
+            question_id: q.id,
+            answer_id: q.answers[answer].id,
+        })
+        .collect();
+
+    changes::risk::update(
+        rfc_id,
+        RFCRiskAssessmentUpdateset { answers },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+}
+
+async fn post_rfc(context: &DbTestContext, r#type: RFCType, status: RFCStatus) -> Uuid {
+    let requester_id = post_user(context, "requester").await;
+    let createset = RFCCreateset {
+        title: String::from("Testing RFC"),
+        r#type: Some(r#type),
+        status: Some(status),
+        created_at: None,
+        finished_at: None,
+        requester_id,
+        description: String::from("This is a fictional RFC made for testing."),
+        planned_start_at: Some("2030-01-01T22:00:00Z".parse().unwrap()),
+        planned_end_at: Some("2030-01-02T02:00:00Z".parse().unwrap()),
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
+    };
+
+    let rfc_id = changes::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id;
+    if r#type != RFCType::Standard {
+        assess_risk(context, rfc_id, 1).await;
+    }
+
+    rfc_id
+}
+
+async fn post_vote(
+    context: &DbTestContext,
+    rfc_id: Uuid,
+    token: &str,
+    vote: &str,
+) -> axum::response::Response {
+    context
+        .app
+        .request(&format!("/api/changes/{}/approvals", rfc_id))
+        .method(Method::POST)
+        .token(token)
+        .body(Body::from(
+            json!({ "vote": vote, "comment": "Looks fine." }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn put_status(context: &DbTestContext, rfc_id: Uuid, status: &str) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "status": status }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+#[db_test]
+async fn test_quorum_approves(context: &DbTestContext) {
+    let tokens = setup_cab(context, &["alice", "bob"], 2).await;
+    let rfc_id = post_rfc(context, RFCType::Normal, RFCStatus::Submitted).await;
+
+    // Normal changes are assessed before the CAB decides on them.
+    let response = post_vote(context, rfc_id, &tokens[0], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    assert_that!(
+        put_status(context, rfc_id, "assessed").await,
+        eq(StatusCode::OK)
+    );
+
+    let response = post_vote(context, rfc_id, &tokens[0], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let approval = response.into_body().into_json::<RFCApproval>().await;
+    assert_that!(approval.rfc_id, eq(rfc_id));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Assessed));
+
+    // The same member can't decide twice.
+    let response = post_vote(context, rfc_id, &tokens[0], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = post_vote(context, rfc_id, &tokens[1], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Approved));
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/approvals", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let approvals = response.into_body().into_json::<Vec<RFCApproval>>().await;
+    assert_that!(approvals, len(eq(2)));
+
+    assert_that!(
+        put_status(context, rfc_id, "scheduled").await,
+        eq(StatusCode::OK)
+    );
+}
+
+#[db_test]
+async fn test_rejection(context: &DbTestContext) {
+    let tokens = setup_cab(context, &["alice", "bob"], 1).await;
+    let rfc_id = post_rfc(context, RFCType::Normal, RFCStatus::Submitted).await;
+    assert_that!(
+        put_status(context, rfc_id, "assessed").await,
+        eq(StatusCode::OK)
+    );
+
+    let response = post_vote(context, rfc_id, &tokens[0], "reject").await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Rejected));
+
+    // Reworking the RFC discards the decisions on it.
+    assert_that!(
+        put_status(context, rfc_id, "draft").await,
+        eq(StatusCode::OK)
+    );
+    let approvals = changes::approvals::load_all(rfc_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(approvals, is_empty());
+}
+
+#[db_test]
+async fn test_emergency_fast_track(context: &DbTestContext) {
+    let tokens = setup_cab(context, &["alice", "bob", "carol"], 3).await;
+    let rfc_id = post_rfc(context, RFCType::Emergency, RFCStatus::Submitted).await;
+
+    // A single approval is enough, straight from submitted.
+    let response = post_vote(context, rfc_id, &tokens[2], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Approved));
+}
+
+#[db_test]
+async fn test_standard_needs_no_approval(context: &DbTestContext) {
+    let tokens = setup_cab(context, &["alice"], 1).await;
+    let rfc_id = post_rfc(context, RFCType::Standard, RFCStatus::Submitted).await;
+
+    let response = post_vote(context, rfc_id, &tokens[0], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    assert_that!(
+        put_status(context, rfc_id, "scheduled").await,
+        eq(StatusCode::OK)
+    );
+}
+
+#[db_test]
+async fn test_non_member_forbidden(context: &DbTestContext) {
+    setup_cab(context, &["alice"], 1).await;
+    post_user(context, "mallory").await;
+    roles::assign("change_manager", "mallory", &context.db_pool)
+        .await
+        .unwrap();
+    let rfc_id = post_rfc(context, RFCType::Emergency, RFCStatus::Submitted).await;
+
+    let response = post_vote(context, rfc_id, &context.token_for("mallory"), "approve").await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Submitted));
+}
+
+#[db_test]
+async fn test_member_needs_changes_approve(context: &DbTestContext) {
+    setup_cab(context, &["alice"], 1).await;
+    let response = context.app.request("/api/cab").send().await;
+    let cab = response
+        .into_body()
+        .into_json::<ChangeAdvisoryBoard>()
+        .await;
+    // A service desk agent may write RFCs, but not approve or reject them.
+    let user_id = post_user(context, "agent").await;
+    members::create(cab.team_id.unwrap(), user_id, &context.db_pool)
+        .await
+        .unwrap();
+    roles::assign("service_desk_agent", "agent", &context.db_pool)
+        .await
+        .unwrap();
+    let rfc_id = post_rfc(context, RFCType::Emergency, RFCStatus::Submitted).await;
+
+    for vote in ["approve", "reject"] {
+        let response = post_vote(context, rfc_id, &context.token_for("agent"), vote).await;
+        assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    }
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Submitted));
+}
+
+#[db_test]
+async fn test_manual_approval_needs_cab(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Emergency, RFCStatus::Submitted).await;
+
+    assert_that!(
+        put_status(context, rfc_id, "approved").await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+}
+
+#[db_test]
+async fn test_vote_nonexistent(context: &DbTestContext) {
+    let tokens = setup_cab(context, &["alice"], 1).await;
+
+    let response = post_vote(context, Uuid::new_v4(), &tokens[0], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_cab(context: &DbTestContext) {
+    let response = context.app.request("/api/cab").send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let board = response
+        .into_body()
+        .into_json::<ChangeAdvisoryBoard>()
+        .await;
+    assert_that!(board.team_id, none());
+    assert_that!(board.quorum, eq(1));
+
+    for payload in [
+        json!({ "quorum": 0 }),
+        json!({ "quorum": null }),
+        json!({ "team_id": Uuid::new_v4() }),
+    ] {
+        let response = context
+            .app
+            .request("/api/cab")
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context
+        .app
+        .request("/api/cab")
+        .method(Method::PUT)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!({ "quorum": 2 }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
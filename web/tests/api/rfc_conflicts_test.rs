@@ -0,0 +1,355 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    changes::{
+        self,
+        ci_relations::{self, RFCCICreateset, RFCCIRelation},
+        conflicts::RFCConflict,
+        risk::{RFCRiskAssessmentUpdateset, RFCRiskChoice},
+        RFCCreateset, RFCStatus, RFCType, RFCUpdateset,
+    },
+    configuration::{
+        self,
+        relations::{CIRelationCreateset, CIRelationType},
+        ConfigItemCreateset,
+    },
+    risk_questionnaire, roles,
+    teams::{self, members, TeamCreateset},
+    users::{self, UserCreateset},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_ci(context: &DbTestContext, name: &str) -> Uuid {
+    let createset = ConfigItemCreateset {
+        name: String::from(name),
+        status: None,
+        created_at: None,
+        r#type: None,
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+/// Assess the risk of `rfc_id` picking the answer at `answer` of every question, `1` being
+/// medium risk with the default questionnaire.
+async fn assess_risk(context: &DbTestContext, rfc_id: Uuid, answer: usize) {
+    let questionnaire = risk_questionnaire::load(&context.db_pool).await.unwrap();
+    let answers = questionnaire
+        .questions
+        .iter()
+        .map(|q| RFCRiskChoice {
+            question_id: q.id,
+            answer_id: q.answers[answer].id,
+        })
+        .collect();
+
+    changes::risk::update(
+        rfc_id,
+        RFCRiskAssessmentUpdateset { answers },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+}
+
+/// Create a submitted RFC planned from `start` to `end` that affects `ci_ids`.
+async fn post_rfc(
+    context: &DbTestContext,
+    r#type: RFCType,
+    (start, end): (&str, &str),
+    ci_ids: &[Uuid],
+) -> Uuid {
+    let requester = users::create(
+        UserCreateset {
+            username: format!("requester-{}", Uuid::new_v4()),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let createset = RFCCreateset {
+        title: String::from("Testing RFC"),
+        r#type: Some(r#type),
+        status: Some(RFCStatus::Submitted),
+        created_at: None,
+        finished_at: None,
+        requester_id: requester.id,
+        description: String::from("This is a fictional RFC made for testing."),
+        planned_start_at: Some(start.parse().unwrap()),
+        planned_end_at: Some(end.parse().unwrap()),
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
+    };
+    let rfc = changes::create(createset, &context.db_pool).await.unwrap();
+    if r#type != RFCType::Standard {
+        assess_risk(context, rfc.id, 1).await;
+    }
+
+    for ci_id in ci_ids {
+        ci_relations::create(rfc.id, RFCCICreateset { ci_id: *ci_id }, &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    rfc.id
+}
+
+async fn get_conflicts(context: &DbTestContext, rfc_id: Uuid) -> Vec<RFCConflict> {
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/conflicts", rfc_id))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    response.into_body().into_json::<Vec<RFCConflict>>().await
+}
+
+const WINDOW: (&str, &str) = ("2030-03-01T22:00:00Z", "2030-03-02T02:00:00Z");
+
+#[db_test]
+async fn test_ci_relations(context: &DbTestContext) {
+    let ci = post_ci(context, "Database").await;
+    let rfc_id = post_rfc(context, RFCType::Normal, WINDOW, &[]).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/configitems", rfc_id))
+        .method(Method::POST)
+        .body(Body::from(json!({ "ci_id": ci }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let relation = response.into_body().into_json::<RFCCIRelation>().await;
+    assert_that!(relation.ci_id, eq(ci));
+
+    // Already related, and nonexistent.
+    for ci_id in [ci, Uuid::new_v4()] {
+        let response = context
+            .app
+            .request(&format!("/api/changes/{}/configitems", rfc_id))
+            .method(Method::POST)
+            .body(Body::from(json!({ "ci_id": ci_id }).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/configitems", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let relations = response.into_body().into_json::<Vec<RFCCIRelation>>().await;
+    assert_that!(relations, elements_are![eq(&relation)]);
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/changes/{}/configitems/{}",
+            rfc_id, relation.id
+        ))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+    let relations = ci_relations::load_all(rfc_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(relations, is_empty());
+}
+
+#[db_test]
+async fn test_conflicts(context: &DbTestContext) {
+    let db = post_ci(context, "Database").await;
+    let app = post_ci(context, "Billing").await;
+    let printer = post_ci(context, "Printer").await;
+    configuration::relations::create(
+        app,
+        CIRelationCreateset {
+            target_id: db,
+            r#type: CIRelationType::DependsOn,
+            description: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let rfc_id = post_rfc(context, RFCType::Normal, WINDOW, &[db]).await;
+    let same_ci = post_rfc(
+        context,
+        RFCType::Normal,
+        ("2030-03-02T01:00:00Z", "2030-03-02T03:00:00Z"),
+        &[db],
+    )
+    .await;
+    let dependent_ci = post_rfc(
+        context,
+        RFCType::Normal,
+        ("2030-03-01T21:00:00Z", "2030-03-01T23:00:00Z"),
+        &[app, printer],
+    )
+    .await;
+    // Unrelated item.
+    post_rfc(context, RFCType::Normal, WINDOW, &[printer]).await;
+    // Right after the window.
+    let after = post_rfc(
+        context,
+        RFCType::Normal,
+        ("2030-03-02T02:00:00Z", "2030-03-02T04:00:00Z"),
+        &[db],
+    )
+    .await;
+    // Withdrawn.
+    let closed = post_rfc(context, RFCType::Normal, WINDOW, &[db]).await;
+    changes::update(
+        closed,
+        RFCUpdateset {
+            title: None,
+            r#type: None,
+            status: Some(Some(RFCStatus::Closed)),
+            created_at: None,
+            finished_at: None,
+            requester_id: None,
+            description: None,
+            planned_start_at: None,
+            planned_end_at: None,
+            actual_start_at: None,
+            actual_end_at: None,
+            implementation_plan: None,
+            backout_plan: None,
+            test_plan: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let conflicts = get_conflicts(context, rfc_id).await;
+
+    let pairs: Vec<(Uuid, Uuid, Uuid)> = conflicts
+        .iter()
+        .map(|c| (c.rfc.id, c.ci_id, c.conflicting_ci_id))
+        .collect();
+    assert_that!(
+        pairs,
+        elements_are![eq(&(dependent_ci, db, app)), eq(&(same_ci, db, db))]
+    );
+
+    // Conflicts go both ways.
+    let conflicts = get_conflicts(context, same_ci).await;
+    let rfcs: Vec<Uuid> = conflicts.iter().map(|c| c.rfc.id).collect();
+    assert_that!(rfcs, elements_are![eq(&rfc_id), eq(&after)]);
+}
+
+#[db_test]
+async fn test_conflicts_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/conflicts", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_conflicts_block_approval(context: &DbTestContext) {
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("CAB"),
+            description: String::from("Change Advisory Board"),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let member = users::create(
+        UserCreateset {
+            username: String::from("alice"),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    members::create(team.id, member.id, &context.db_pool)
+        .await
+        .unwrap();
+    roles::assign("change_manager", "alice", &context.db_pool)
+        .await
+        .unwrap();
+    let response = context
+        .app
+        .request("/api/cab")
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({ "team_id": team.id, "block_conflicts": true }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let db = post_ci(context, "Database").await;
+    let rfc_id = post_rfc(context, RFCType::Emergency, WINDOW, &[db]).await;
+    post_rfc(context, RFCType::Normal, WINDOW, &[db]).await;
+
+    let vote = |context: &DbTestContext| {
+        context
+            .app
+            .request(&format!("/api/changes/{}/approvals", rfc_id))
+            .method(Method::POST)
+            .token(&context.token_for("alice"))
+            .body(Body::from(
+                json!({ "vote": "approve", "comment": "Go ahead." }).to_string(),
+            ))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+    };
+
+    let response = vote(context).await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // Resolve the conflict by moving the RFC out of the window.
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({
+                "planned_start_at": "2030-03-05T22:00:00Z",
+                "planned_end_at": "2030-03-06T02:00:00Z",
+            })
+            .to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let response = vote(context).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Approved));
+}
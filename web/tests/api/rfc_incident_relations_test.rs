@@ -18,12 +18,16 @@ async fn post_incident(context: &DbTestContext) -> Uuid {
         title: String::from("Testing Incident"),
         status: Some(entities::incidents::IncidentStatus::InProgress),
         created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
-        resolved_at: None,
         impact: entities::incidents::IncidentImpact::Low,
         urgency: entities::incidents::IncidentUrgency::Low,
-        owner: Some(String::from("Me")),
-        asignee: Some(String::from("Employee 567")),
+        assignment_group_id: None,
+        assignee_id: None,
         description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
     };
 
     let incident = entities::incidents::create(createset, &context.db_pool)
@@ -34,13 +38,30 @@ async fn post_incident(context: &DbTestContext) -> Uuid {
 }
 
 async fn post_rfc(context: &DbTestContext) -> Uuid {
+    let requester = entities::users::create(
+        entities::users::UserCreateset {
+            username: format!("dev-{}", Uuid::new_v4()),
+            full_name: String::from("Me the dev"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
     let createset = entities::changes::RFCCreateset {
         title: String::from("RFC for Testing"),
-        status: Some(entities::changes::RFCStatus::Open),
+        r#type: None,
+        status: Some(entities::changes::RFCStatus::Submitted),
         created_at: None,
         finished_at: None,
-        requester: String::from("Me the dev"),
+        requester_id: requester.id,
         description: String::from("This is a fake rfc made for testing."),
+        planned_start_at: None,
+        planned_end_at: None,
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
     };
 
     let rfc = entities::changes::create(createset, &context.db_pool)
@@ -54,7 +75,10 @@ async fn post_rfc(context: &DbTestContext) -> Uuid {
 async fn test_create_invalid_bad_rfc(context: &DbTestContext) {
     let incident_id = post_incident(context).await;
 
-    let request = RFCIncidentCreateset { incident_id };
+    let request = RFCIncidentCreateset {
+        incident_id,
+        relation_type: None,
+    };
 
     let payload = json!(request);
 
@@ -76,6 +100,7 @@ async fn test_create_invalid_bad_incident(context: &DbTestContext) {
 
     let request = RFCIncidentCreateset {
         incident_id: Uuid::new_v4(),
+        relation_type: None,
     };
 
     let payload = json!(request);
@@ -96,6 +121,7 @@ async fn test_create_invalid_bad_incident(context: &DbTestContext) {
 async fn test_create_invalid_both_bad(context: &DbTestContext) {
     let request = RFCIncidentCreateset {
         incident_id: Uuid::new_v4(),
+        relation_type: None,
     };
 
     let payload = json!(request);
@@ -117,7 +143,10 @@ async fn test_create_success(context: &DbTestContext) {
     let rfc_id = post_rfc(context).await;
     let incident_id = post_incident(context).await;
 
-    let request = RFCIncidentCreateset { incident_id };
+    let request = RFCIncidentCreateset {
+        incident_id,
+        relation_type: None,
+    };
 
     let payload = json!(request);
 
@@ -163,7 +192,10 @@ async fn test_read_all(context: &DbTestContext) {
     let relations: Vec<RFCIncidentRelation> = response.into_body().into_json().await;
     assert_that!(relations, len(eq(0)));
 
-    let createset = RFCIncidentCreateset { incident_id };
+    let createset = RFCIncidentCreateset {
+        incident_id,
+        relation_type: None,
+    };
     incident_relations::create(rfc_id, createset, &context.db_pool)
         .await
         .unwrap();
@@ -217,7 +249,10 @@ async fn test_delete_nonexistent_bad_incident(context: &DbTestContext) {
 async fn test_delete_success(context: &DbTestContext) {
     let rfc_id = post_rfc(context).await;
     let incident_id = post_incident(context).await;
-    let createset = RFCIncidentCreateset { incident_id };
+    let createset = RFCIncidentCreateset {
+        incident_id,
+        relation_type: None,
+    };
     let relation = incident_relations::create(rfc_id, createset, &context.db_pool)
         .await
         .unwrap();
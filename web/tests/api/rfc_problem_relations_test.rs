@@ -32,13 +32,30 @@ async fn post_problem(context: &DbTestContext) -> Uuid {
 }
 
 async fn post_rfc(context: &DbTestContext) -> Uuid {
+    let requester = entities::users::create(
+        entities::users::UserCreateset {
+            username: format!("dev-{}", Uuid::new_v4()),
+            full_name: String::from("Me the dev"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
     let createset = entities::changes::RFCCreateset {
         title: String::from("RFC for Testing"),
-        status: Some(entities::changes::RFCStatus::Open),
+        r#type: None,
+        status: Some(entities::changes::RFCStatus::Submitted),
         created_at: None,
         finished_at: None,
-        requester: String::from("Me the dev"),
+        requester_id: requester.id,
         description: String::from("This is a fake rfc made for testing."),
+        planned_start_at: None,
+        planned_end_at: None,
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
     };
 
     let rfc = entities::changes::create(createset, &context.db_pool)
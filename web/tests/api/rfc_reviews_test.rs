@@ -0,0 +1,499 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::Utc;
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    changes::{
+        ci_relations::RFCCICreateset,
+        incident_relations::{self, RFCIncidentRelation, RFCIncidentRelationType},
+        reviews::{RFCOutcome, RFCReview},
+        RFCCreateset, RFCStatus, RFCType, RFCUpdateset,
+    },
+    users::UserCreateset,
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn post_ci(context: &DbTestContext) -> Uuid {
+    let changeset = entities::configuration::ConfigItemCreateset {
+        name: String::from("Database Server"),
+        status: Some(entities::configuration::CIStatus::Active),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        r#type: Some(String::from("Server")),
+        owner_team_id: None,
+        description: String::from("I'm for testing"),
+    };
+
+    let ci = entities::configuration::create(changeset, &context.db_pool)
+        .await
+        .unwrap();
+
+    ci.id
+}
+
+/// Create an incident opened at `created_at`, on the configuration item `ci_id` if given.
+async fn post_incident(context: &DbTestContext, created_at: &str, ci_id: Option<Uuid>) -> Uuid {
+    let createset = entities::incidents::IncidentCreateset {
+        title: String::from("Database is slow"),
+        status: None,
+        created_at: Some(created_at.parse().unwrap()),
+        impact: entities::incidents::IncidentImpact::Low,
+        urgency: entities::incidents::IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Queries take seconds."),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+    let incident = entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    if let Some(ci_id) = ci_id {
+        entities::incidents::ci_relations::create(incident.id, ci_id, &context.db_pool)
+            .await
+            .unwrap();
+    }
+
+    incident.id
+}
+
+/// Create a scheduled standard RFC affecting the configuration item `ci_id`.
+async fn post_rfc(context: &DbTestContext, ci_id: Uuid) -> Uuid {
+    let requester = entities::users::create(
+        UserCreateset {
+            username: format!("requester-{}", Uuid::new_v4()),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let rfc = entities::changes::create(
+        RFCCreateset {
+            title: String::from("Upgrade the database"),
+            r#type: Some(RFCType::Standard),
+            status: Some(RFCStatus::Submitted),
+            created_at: None,
+            finished_at: None,
+            requester_id: requester.id,
+            description: String::from("This is a fictional RFC made for testing."),
+            planned_start_at: Some("2030-03-01T22:00:00Z".parse().unwrap()),
+            planned_end_at: Some("2030-03-02T02:00:00Z".parse().unwrap()),
+            implementation_plan: Some(String::from("Roll out the change.")),
+            backout_plan: Some(String::from("Undo the change.")),
+            test_plan: Some(String::from("Check that it works.")),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    entities::changes::ci_relations::create(rfc.id, RFCCICreateset { ci_id }, &context.db_pool)
+        .await
+        .unwrap();
+    entities::changes::update(
+        rfc.id,
+        RFCUpdateset {
+            title: None,
+            r#type: None,
+            status: Some(Some(RFCStatus::Scheduled)),
+            created_at: None,
+            finished_at: None,
+            requester_id: None,
+            description: None,
+            planned_start_at: None,
+            planned_end_at: None,
+            actual_start_at: None,
+            actual_end_at: None,
+            implementation_plan: None,
+            backout_plan: None,
+            test_plan: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    rfc.id
+}
+
+async fn put_rfc(context: &DbTestContext, rfc_id: Uuid, payload: Value) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+/// Implement the RFC from 22:10 to 23:30 on 2030-03-01.
+async fn implement(context: &DbTestContext, rfc_id: Uuid) {
+    let payload = json!({
+        "status": "implemented",
+        "actual_start_at": "2030-03-01T22:10:00Z",
+        "actual_end_at": "2030-03-01T23:30:00Z",
+    });
+    assert_that!(put_rfc(context, rfc_id, payload).await, eq(StatusCode::OK));
+}
+
+async fn put_review(context: &DbTestContext, rfc_id: Uuid, payload: Value) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}/review", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+async fn read_relations(context: &DbTestContext, rfc_id: Uuid) -> Vec<RFCIncidentRelation> {
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/incidents", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    response.into_body().into_json().await
+}
+
+async fn post_tester(context: &DbTestContext) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(TEST_SUBJECT),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    entities::users::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+#[db_test]
+async fn test_suggest_on_incident(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+
+    // Linked before the implementation, so suggested by it.
+    post_incident(context, "2030-03-01T23:45:00Z", Some(ci_id)).await;
+    implement(context, rfc_id).await;
+    assert_that!(read_relations(context, rfc_id).await, len(eq(1)));
+
+    let during = post_incident(context, "2030-03-01T22:30:00Z", Some(ci_id)).await;
+    let after = post_incident(context, "2030-03-02T20:00:00Z", Some(ci_id)).await;
+    // Before the implementation, after the review window and on another CI.
+    post_incident(context, "2030-03-01T22:00:00Z", Some(ci_id)).await;
+    post_incident(context, "2030-03-03T00:00:00Z", Some(ci_id)).await;
+    post_incident(
+        context,
+        "2030-03-02T00:00:00Z",
+        Some(post_ci(context).await),
+    )
+    .await;
+
+    let relations = read_relations(context, rfc_id).await;
+    assert_that!(relations, len(eq(3)));
+    let incident_ids: Vec<Uuid> = relations.iter().map(|r| r.incident_id).collect();
+    assert_that!(incident_ids, superset_of([&during, &after]));
+    for relation in relations {
+        assert_that!(
+            relation.relation_type,
+            eq(RFCIncidentRelationType::SuspectedCause)
+        );
+    }
+}
+
+#[db_test]
+async fn test_suggest_on_implementation(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+    let suspected = post_incident(context, "2030-03-02T10:00:00Z", Some(ci_id)).await;
+    let related = post_incident(context, "2030-03-02T11:00:00Z", Some(ci_id)).await;
+    post_incident(context, "2030-03-02T12:00:00Z", None).await;
+    incident_relations::create(
+        rfc_id,
+        incident_relations::RFCIncidentCreateset {
+            incident_id: related,
+            relation_type: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    implement(context, rfc_id).await;
+
+    let relations = read_relations(context, rfc_id).await;
+    assert_that!(relations, len(eq(2)));
+    let suggested: Vec<Uuid> = relations
+        .iter()
+        .filter(|r| r.relation_type == RFCIncidentRelationType::SuspectedCause)
+        .map(|r| r.incident_id)
+        .collect();
+    assert_that!(suggested, elements_are![eq(&suspected)]);
+}
+
+#[db_test]
+async fn test_suggest_without_actual_end(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+
+    let payload = json!({ "status": "implemented" });
+    assert_that!(put_rfc(context, rfc_id, payload).await, eq(StatusCode::OK));
+
+    // The implementation ends when the RFC is implemented, so Incidents follow it.
+    let rfc = entities::changes::load(rfc_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(rfc.actual_end_at, some(anything()));
+    assert_that!(rfc.actual_start_at, eq(rfc.actual_end_at));
+    let suspected = post_incident(context, &Utc::now().to_rfc3339(), Some(ci_id)).await;
+
+    let relations = read_relations(context, rfc_id).await;
+    assert_that!(
+        relations,
+        elements_are![field!(RFCIncidentRelation.incident_id, eq(&suspected))]
+    );
+}
+
+#[db_test]
+async fn test_suggest_on_actual_window_change(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+    let suspected = post_incident(context, "2030-03-01T22:05:00Z", Some(ci_id)).await;
+    implement(context, rfc_id).await;
+    assert_that!(read_relations(context, rfc_id).await, is_empty());
+
+    let payload = json!({ "actual_start_at": "2030-03-01T22:00:00Z" });
+    assert_that!(put_rfc(context, rfc_id, payload).await, eq(StatusCode::OK));
+
+    let relations = read_relations(context, rfc_id).await;
+    assert_that!(
+        relations,
+        elements_are![field!(RFCIncidentRelation.incident_id, eq(&suspected))]
+    );
+}
+
+#[db_test]
+async fn test_review_window(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/cab")
+        .method(Method::PUT)
+        .body(Body::from(json!({ "review_window_hours": 48 }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+    implement(context, rfc_id).await;
+    post_incident(context, "2030-03-03T20:00:00Z", Some(ci_id)).await;
+    post_incident(context, "2030-03-04T00:00:00Z", Some(ci_id)).await;
+
+    assert_that!(read_relations(context, rfc_id).await, len(eq(1)));
+}
+
+#[db_test]
+async fn test_review_lifecycle(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+    let review = json!({
+        "outcome": "rolled_back",
+        "caused_incidents": false,
+        "lessons_learned": "Test the upgrade on a copy of the data first.",
+    });
+
+    // Not implemented yet.
+    assert_that!(
+        put_review(context, rfc_id, review.clone()).await,
+        eq(StatusCode::FORBIDDEN)
+    );
+
+    implement(context, rfc_id).await;
+    let incident_id = post_incident(context, "2030-03-02T10:00:00Z", Some(ci_id)).await;
+
+    // Neither a review nor a decision on the suggestion.
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "reviewed" })).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+
+    // Reviewers must be users.
+    assert_that!(
+        put_review(context, rfc_id, review.clone()).await,
+        eq(StatusCode::FORBIDDEN)
+    );
+    let reviewer_id = post_tester(context).await;
+    assert_that!(
+        put_review(context, rfc_id, review.clone()).await,
+        eq(StatusCode::OK)
+    );
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "reviewed" })).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+
+    // Confirm the suggestion.
+    let relation = read_relations(context, rfc_id).await.pop().unwrap();
+    assert_that!(relation.incident_id, eq(incident_id));
+    let response = context
+        .app
+        .request(&format!(
+            "/api/changes/{}/incidents/{}",
+            rfc_id, relation.id
+        ))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "relation_type": "caused" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let relation = response
+        .into_body()
+        .into_json::<RFCIncidentRelation>()
+        .await;
+    assert_that!(relation.relation_type, eq(RFCIncidentRelationType::Caused));
+
+    // The review denies it.
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "reviewed" })).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+    assert_that!(
+        put_review(context, rfc_id, review).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+
+    let review = json!({
+        "outcome": "rolled_back",
+        "caused_incidents": true,
+        "lessons_learned": "Test the upgrade on a copy of the data first.",
+    });
+    assert_that!(
+        put_review(context, rfc_id, review).await,
+        eq(StatusCode::OK)
+    );
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "reviewed" })).await,
+        eq(StatusCode::OK)
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/review", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let review = response.into_body().into_json::<RFCReview>().await;
+    assert_that!(review.rfc_id, eq(rfc_id));
+    assert_that!(review.reviewer_id, eq(reviewer_id));
+    assert_that!(review.outcome, eq(RFCOutcome::RolledBack));
+    assert_that!(review.caused_incidents, eq(true));
+
+    // Closed RFCs can't be reviewed again.
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "closed" })).await,
+        eq(StatusCode::OK)
+    );
+    let review = json!({
+        "outcome": "failed",
+        "caused_incidents": true,
+        "lessons_learned": "",
+    });
+    assert_that!(
+        put_review(context, rfc_id, review).await,
+        eq(StatusCode::FORBIDDEN)
+    );
+}
+
+#[db_test]
+async fn test_dismiss_suggestion(context: &DbTestContext) {
+    let ci_id = post_ci(context).await;
+    let rfc_id = post_rfc(context, ci_id).await;
+    implement(context, rfc_id).await;
+    post_incident(context, "2030-03-02T10:00:00Z", Some(ci_id)).await;
+    post_tester(context).await;
+
+    let relation = read_relations(context, rfc_id).await.pop().unwrap();
+    let response = context
+        .app
+        .request(&format!(
+            "/api/changes/{}/incidents/{}",
+            rfc_id, relation.id
+        ))
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({ "relation_type": "related" }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let review = json!({
+        "outcome": "successful",
+        "caused_incidents": false,
+        "lessons_learned": "",
+    });
+    assert_that!(
+        put_review(context, rfc_id, review).await,
+        eq(StatusCode::OK)
+    );
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "reviewed" })).await,
+        eq(StatusCode::OK)
+    );
+}
+
+#[db_test]
+async fn test_read_review_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/review", Uuid::new_v4()))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let rfc_id = post_rfc(context, post_ci(context).await).await;
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/review", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_update_relation_nonexistent(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, post_ci(context).await).await;
+    let response = context
+        .app
+        .request(&format!(
+            "/api/changes/{}/incidents/{}",
+            rfc_id,
+            Uuid::new_v4()
+        ))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "relation_type": "caused" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
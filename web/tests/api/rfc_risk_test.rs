@@ -0,0 +1,424 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    changes::{
+        self,
+        risk::{RFCRiskAssessment, RiskCategory},
+        RFCCreateset, RFCStatus, RFCType, RFC,
+    },
+    risk_questionnaire::{self, RiskQuestionnaire},
+    roles,
+    teams::{self, members, TeamCreateset},
+    users::{self, UserCreateset},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn post_user(context: &DbTestContext, username: &str) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(username),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    users::create(createset, &context.db_pool).await.unwrap().id
+}
+
+/// Makes a team of change managers named after `usernames` the CAB, with quorum `quorum`,
+/// and returns their tokens.
+async fn setup_cab(context: &DbTestContext, usernames: &[&str], quorum: i32) -> Vec<String> {
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("CAB"),
+            description: String::from("Change Advisory Board"),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let mut tokens = Vec::new();
+    for username in usernames {
+        let user_id = post_user(context, username).await;
+        members::create(team.id, user_id, &context.db_pool)
+            .await
+            .unwrap();
+        roles::assign("change_manager", username, &context.db_pool)
+            .await
+            .unwrap();
+        tokens.push(context.token_for(username));
+    }
+
+    let response = context
+        .app
+        .request("/api/cab")
+        .method(Method::PUT)
+        .body(Body::from(
+            json!({ "team_id": team.id, "quorum": quorum }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    tokens
+}
+
+async fn post_rfc(context: &DbTestContext, r#type: RFCType) -> Uuid {
+    let requester_id = post_user(context, &format!("requester-{}", Uuid::new_v4())).await;
+    let createset = RFCCreateset {
+        title: String::from("Testing RFC"),
+        r#type: Some(r#type),
+        status: Some(RFCStatus::Submitted),
+        created_at: None,
+        finished_at: None,
+        requester_id,
+        description: String::from("This is a fictional RFC made for testing."),
+        planned_start_at: Some("2030-01-01T22:00:00Z".parse().unwrap()),
+        planned_end_at: Some("2030-01-02T02:00:00Z".parse().unwrap()),
+        implementation_plan: Some(String::from("Roll out the change.")),
+        backout_plan: Some(String::from("Undo the change.")),
+        test_plan: Some(String::from("Check that it works.")),
+    };
+
+    changes::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+/// Answers of the current questionnaire picking the answer at `answer` of every question.
+async fn answers(context: &DbTestContext, answer: usize) -> Value {
+    let questionnaire = risk_questionnaire::load(&context.db_pool).await.unwrap();
+    let answers: Vec<Value> = questionnaire
+        .questions
+        .iter()
+        .map(|q| json!({ "question_id": q.id, "answer_id": q.answers[answer].id }))
+        .collect();
+
+    json!({ "answers": answers })
+}
+
+async fn put_risk(context: &DbTestContext, rfc_id: Uuid, payload: &Value) -> (StatusCode, Value) {
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/risk", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    let status = response.status();
+    let body = if status == StatusCode::OK {
+        response.into_body().into_json::<Value>().await
+    } else {
+        Value::Null
+    };
+
+    (status, body)
+}
+
+async fn put_rfc(context: &DbTestContext, rfc_id: Uuid, payload: Value) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}", rfc_id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+async fn post_approval(context: &DbTestContext, rfc_id: Uuid, token: &str) -> StatusCode {
+    context
+        .app
+        .request(&format!("/api/changes/{}/approvals", rfc_id))
+        .method(Method::POST)
+        .token(token)
+        .body(Body::from(
+            json!({ "vote": "approve", "comment": "Looks fine." }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+#[db_test]
+async fn test_questionnaire(context: &DbTestContext) {
+    let response = context.app.request("/api/riskquestionnaire").send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let questionnaire = response.into_body().into_json::<RiskQuestionnaire>().await;
+    assert_that!(questionnaire.questions, len(eq(4)));
+
+    let payload = json!({
+        "questions": [{
+            "question": "Is it Friday?",
+            "weight": 5,
+            "answers": [{ "answer": "No", "score": 0 }, { "answer": "Yes", "score": 1 }],
+        }],
+    });
+    let response = context
+        .app
+        .request("/api/riskquestionnaire")
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let questionnaire = response.into_body().into_json::<RiskQuestionnaire>().await;
+    assert_that!(questionnaire.questions, len(eq(1)));
+    let question = questionnaire.questions.first().unwrap();
+    assert_that!(question.weight, eq(5));
+    assert_that!(question.max_score(), eq(5));
+    let answers: Vec<&str> = question.answers.iter().map(|a| a.answer.as_str()).collect();
+    assert_that!(answers, elements_are![eq(&"No"), eq(&"Yes")]);
+
+    assert_that!(
+        risk_questionnaire::load(&context.db_pool).await.unwrap(),
+        eq(&questionnaire)
+    );
+}
+
+#[db_test]
+async fn test_questionnaire_invalid(context: &DbTestContext) {
+    let payloads = [
+        json!({ "questions": [] }),
+        // A single answer.
+        json!({ "questions": [{
+            "question": "Is it Friday?",
+            "weight": 1,
+            "answers": [{ "answer": "Yes", "score": 1 }],
+        }] }),
+        // Nothing scores.
+        json!({ "questions": [{
+            "question": "Is it Friday?",
+            "weight": 1,
+            "answers": [{ "answer": "No", "score": 0 }, { "answer": "Yes", "score": 0 }],
+        }] }),
+        json!({ "questions": [{
+            "question": "Is it Friday?",
+            "weight": 0,
+            "answers": [{ "answer": "No", "score": 0 }, { "answer": "Yes", "score": 1 }],
+        }] }),
+    ];
+
+    for payload in payloads {
+        let response = context
+            .app
+            .request("/api/riskquestionnaire")
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_questionnaire_forbidden(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/riskquestionnaire")
+        .method(Method::PUT)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!({ "questions": [] }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_assessment(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Normal).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}/risk", rfc_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let assessment = response.into_body().into_json::<RFCRiskAssessment>().await;
+    assert_that!(assessment.category, none());
+    assert_that!(assessment.responses, is_empty());
+
+    // The highest answers of the default questionnaire.
+    let (status, body) = put_risk(context, rfc_id, &answers(context, 2).await).await;
+    assert_that!(status, eq(StatusCode::OK));
+    let assessment: RFCRiskAssessment = serde_json::from_value(body).unwrap();
+    assert_that!(assessment.score, some(eq(40)));
+    assert_that!(assessment.category, some(eq(RiskCategory::High)));
+    assert_that!(assessment.responses, len(eq(4)));
+
+    // Reassessed.
+    let (status, body) = put_risk(context, rfc_id, &answers(context, 0).await).await;
+    assert_that!(status, eq(StatusCode::OK));
+    let assessment: RFCRiskAssessment = serde_json::from_value(body).unwrap();
+    assert_that!(assessment.score, some(eq(0)));
+    assert_that!(assessment.category, some(eq(RiskCategory::Low)));
+
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.risk_score, some(eq(0)));
+    assert_that!(rfc.risk_category, some(eq(RiskCategory::Low)));
+
+    // Assessments outlive changes to the questionnaire.
+    let payload = json!({
+        "questions": [{
+            "question": "Is it Friday?",
+            "weight": 1,
+            "answers": [{ "answer": "No", "score": 0 }, { "answer": "Yes", "score": 1 }],
+        }],
+    });
+    let response = context
+        .app
+        .request("/api/riskquestionnaire")
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let assessment_after = changes::risk::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(assessment_after, eq(&assessment));
+}
+
+#[db_test]
+async fn test_assessment_invalid(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Normal).await;
+    let questionnaire = risk_questionnaire::load(&context.db_pool).await.unwrap();
+    let mut choices: Vec<Value> = questionnaire
+        .questions
+        .iter()
+        .map(|q| json!({ "question_id": q.id, "answer_id": q.answers[0].id }))
+        .collect();
+
+    let mut payloads = vec![json!({ "answers": choices[1..] })];
+    // Answer of another question.
+    let first = &questionnaire.questions[0];
+    choices[0] = json!({
+        "question_id": first.id,
+        "answer_id": questionnaire.questions[1].answers[0].id,
+    });
+    payloads.push(json!({ "answers": choices }));
+    // Answered twice.
+    choices[0] = json!({ "question_id": first.id, "answer_id": first.answers[0].id });
+    choices.push(json!({ "question_id": first.id, "answer_id": first.answers[1].id }));
+    payloads.push(json!({ "answers": choices }));
+    // Unknown question.
+    choices.pop();
+    choices.push(json!({ "question_id": Uuid::new_v4(), "answer_id": Uuid::new_v4() }));
+    payloads.push(json!({ "answers": choices }));
+
+    for payload in payloads {
+        let (status, _) = put_risk(context, rfc_id, &payload).await;
+        assert_that!(status, eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let (status, _) = put_risk(context, Uuid::new_v4(), &answers(context, 0).await).await;
+    assert_that!(status, eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_ready_for_assessment(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Normal).await;
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "backout_plan": null })).await,
+        eq(StatusCode::OK)
+    );
+
+    // Neither the backout plan nor the risk assessment.
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "assessed" })).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+    let (status, _) = put_risk(context, rfc_id, &answers(context, 1).await).await;
+    assert_that!(status, eq(StatusCode::OK));
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "assessed" })).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+
+    // The plan can come along with the status.
+    let payload = json!({ "status": "assessed", "backout_plan": "Restore the backup." });
+    assert_that!(put_rfc(context, rfc_id, payload).await, eq(StatusCode::OK));
+    let rfc = changes::load(rfc_id, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Assessed));
+    assert_that!(rfc.backout_plan, some(eq("Restore the backup.")));
+}
+
+#[db_test]
+async fn test_standard_must_be_low_risk(context: &DbTestContext) {
+    let rfc_id = post_rfc(context, RFCType::Standard).await;
+    let (status, _) = put_risk(context, rfc_id, &answers(context, 2).await).await;
+    assert_that!(status, eq(StatusCode::OK));
+
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+
+    let (status, _) = put_risk(context, rfc_id, &answers(context, 0).await).await;
+    assert_that!(status, eq(StatusCode::OK));
+    assert_that!(
+        put_rfc(context, rfc_id, json!({ "status": "scheduled" })).await,
+        eq(StatusCode::OK)
+    );
+
+    // Not after the change is scheduled.
+    let (status, _) = put_risk(context, rfc_id, &answers(context, 2).await).await;
+    assert_that!(status, eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_risk_drives_approvals(context: &DbTestContext) {
+    let tokens = setup_cab(context, &["alice", "bob", "carol"], 2).await;
+
+    // Low risk changes need a single approval.
+    let low = post_rfc(context, RFCType::Normal).await;
+    put_risk(context, low, &answers(context, 0).await).await;
+    assert_that!(
+        put_rfc(context, low, json!({ "status": "assessed" })).await,
+        eq(StatusCode::OK)
+    );
+    assert_that!(
+        post_approval(context, low, &tokens[0]).await,
+        eq(StatusCode::CREATED)
+    );
+    let rfc = changes::load(low, &context.db_pool).await.unwrap();
+    assert_that!(rfc.status, eq(RFCStatus::Approved));
+
+    // High risk changes need every CAB member.
+    let high = post_rfc(context, RFCType::Normal).await;
+    put_risk(context, high, &answers(context, 2).await).await;
+    assert_that!(
+        put_rfc(context, high, json!({ "status": "assessed" })).await,
+        eq(StatusCode::OK)
+    );
+    let mut statuses = vec![];
+    for token in &tokens {
+        assert_that!(
+            post_approval(context, high, token).await,
+            eq(StatusCode::CREATED)
+        );
+        let rfc: RFC = changes::load(high, &context.db_pool).await.unwrap();
+        statuses.push(rfc.status);
+    }
+    assert_that!(
+        statuses,
+        elements_are![
+            eq(&RFCStatus::Assessed),
+            eq(&RFCStatus::Assessed),
+            eq(&RFCStatus::Approved)
+        ]
+    );
+}
@@ -0,0 +1,631 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::audit::{AuditAction, AuditEntity, AuditEntry};
+use itil_back_db::entities::changes::{self, RFCCreateset, RFCStatus, RFCType};
+use itil_back_db::entities::configuration::{self, ConfigItemCreateset};
+use itil_back_db::entities::problems::{self, ProblemCreateset};
+use itil_back_db::entities::roles::{
+    self, Permission, Role, RoleAssignment, RoleCreateset, RoleUpdateset,
+};
+use itil_back_db::entities::users::{self, UserCreateset};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
+use serde_json::{json, Value};
+
+fn create_basic_createset() -> RoleCreateset {
+    RoleCreateset {
+        name: String::from("testing_role"),
+        description: String::from("Role made for testing."),
+        permissions: vec![Permission::IncidentsWrite, Permission::ProblemsWrite],
+    }
+}
+
+/// Returns a token for `subject`, granted only the role `role`.
+async fn token_with_role(context: &DbTestContext, subject: &str, role: &str) -> String {
+    roles::assign(role, subject, &context.db_pool)
+        .await
+        .unwrap();
+    context.token_for(subject)
+}
+
+#[db_test]
+async fn test_forbidden_body(context: &DbTestContext) {
+    let payload = json!({
+        "title": "Forbidden Incident",
+        "impact": "low",
+        "urgency": "low",
+        "description": "Nobody may create this.",
+    });
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+
+    let body = response.into_body().into_json::<Value>().await;
+    assert_that!(body["permission"], eq(&json!("incidents.write")));
+    assert_that!(
+        body["error"].as_str(),
+        some(contains_substring("incidents.write"))
+    );
+}
+
+#[db_test]
+async fn test_reads_need_no_permission(context: &DbTestContext) {
+    let token = context.token_for("nobody");
+    for uri in [
+        "/api/incidents",
+        "/api/configitems",
+        "/api/problems",
+        "/api/changes",
+    ] {
+        let response = context.app.request(uri).token(&token).send().await;
+
+        assert_that!(response.status(), eq(StatusCode::OK));
+    }
+}
+
+#[db_test]
+async fn test_service_desk_agent_creates_incidents(context: &DbTestContext) {
+    let token = token_with_role(context, "agent", "service_desk_agent").await;
+    let payload = json!({
+        "title": "Agent Incident",
+        "impact": "low",
+        "urgency": "low",
+        "description": "Registered by the service desk.",
+    });
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .token(&token)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+}
+
+#[db_test]
+async fn test_only_problem_managers_move_to_known_error(context: &DbTestContext) {
+    let problem = problems::create(
+        ProblemCreateset {
+            title: String::from("Problem for Testing"),
+            status: None,
+            detection_timedate: None,
+            description: String::from("This is a fake problem made for testing."),
+            causes: String::from("I need to test this."),
+            workarounds: None,
+            resolutions: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    roles::create(
+        RoleCreateset {
+            name: String::from("problem_analyst"),
+            description: String::from("Records problems."),
+            permissions: vec![Permission::ProblemsWrite],
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let analyst = token_with_role(context, "analyst", "problem_analyst").await;
+    let manager = token_with_role(context, "manager", "problem_manager").await;
+
+    for (status, permission) in [
+        ("knownerror", "problems.known_error"),
+        ("closed", "problems.close"),
+    ] {
+        let response = context
+            .app
+            .request(&format!("/api/problems/{}", problem.id))
+            .method(Method::PUT)
+            .token(&analyst)
+            .body(Body::from(json!({ "status": status }).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+        let body = response.into_body().into_json::<Value>().await;
+        assert_that!(body["permission"], eq(&json!(permission)));
+    }
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}", problem.id))
+        .method(Method::PUT)
+        .token(&analyst)
+        .body(Body::from(json!({ "status": "resolved" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}", problem.id))
+        .method(Method::PUT)
+        .token(&manager)
+        .body(Body::from(json!({ "status": "knownerror" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_only_change_managers_approve_rfcs(context: &DbTestContext) {
+    let requester = users::create(
+        UserCreateset {
+            username: String::from("requester"),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let rfc = changes::create(
+        RFCCreateset {
+            title: String::from("Testing RFC"),
+            r#type: Some(RFCType::Emergency),
+            status: Some(RFCStatus::Submitted),
+            created_at: None,
+            finished_at: None,
+            requester_id: requester.id,
+            description: String::from("This is a fictional RFC made for testing."),
+            planned_start_at: None,
+            planned_end_at: None,
+            implementation_plan: Some(String::from("Roll out the change.")),
+            backout_plan: Some(String::from("Undo the change.")),
+            test_plan: Some(String::from("Check that it works.")),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let agent = token_with_role(context, "agent", "service_desk_agent").await;
+    let manager = token_with_role(context, "manager", "change_manager").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/changes/{}", rfc.id))
+        .method(Method::PUT)
+        .token(&agent)
+        .body(Body::from(json!({ "status": "rejected" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    let body = response.into_body().into_json::<Value>().await;
+    assert_that!(body["permission"], eq(&json!("changes.approve")));
+
+    for status in ["rejected", "closed"] {
+        let response = context
+            .app
+            .request(&format!("/api/changes/{}", rfc.id))
+            .method(Method::PUT)
+            .token(&manager)
+            .body(Body::from(json!({ "status": status }).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::OK));
+    }
+}
+
+#[db_test]
+async fn test_only_cmdb_admins_delete_cis(context: &DbTestContext) {
+    let ci = configuration::create(
+        ConfigItemCreateset {
+            name: String::from("Testing Configuration Item"),
+            status: None,
+            created_at: None,
+            r#type: None,
+            owner_team_id: None,
+            description: String::from("This is a fictional item made for testing."),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let manager = token_with_role(context, "manager", "change_manager").await;
+    let cmdb_admin = token_with_role(context, "cmdb", "cmdb_admin").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}", ci.id))
+        .method(Method::DELETE)
+        .token(&manager)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+
+    let response = context
+        .app
+        .request(&format!("/api/configitems/{}", ci.id))
+        .method(Method::DELETE)
+        .token(&cmdb_admin)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+}
+
+#[db_test]
+async fn test_only_incident_commanders_declare_major_incidents(context: &DbTestContext) {
+    let agent = token_with_role(context, "agent", "service_desk_agent").await;
+    let commander = token_with_role(context, "commander", "incident_commander").await;
+    let payload = json!({
+        "title": "Agent Incident",
+        "impact": "low",
+        "urgency": "low",
+        "description": "Registered by the service desk.",
+    });
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .token(&agent)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    let incident = response.into_body().into_json::<Value>().await;
+    let uri = format!("/api/majorincidents/{}", incident["id"].as_str().unwrap());
+
+    let response = context
+        .app
+        .request(&uri)
+        .method(Method::PUT)
+        .token(&agent)
+        .body(Body::from(json!({}).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    let body = response.into_body().into_json::<Value>().await;
+    assert_that!(body["permission"], eq(&json!("incidents.major")));
+
+    let response = context
+        .app
+        .request(&uri)
+        .method(Method::PUT)
+        .token(&commander)
+        .body(Body::from(json!({}).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+}
+
+#[db_test]
+async fn test_roles_need_roles_manage(context: &DbTestContext) {
+    let token = token_with_role(context, "agent", "service_desk_agent").await;
+
+    let response = context.app.request("/api/roles").token(&token).send().await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_read_all(context: &DbTestContext) {
+    let response = context.app.request("/api/roles").send().await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let roles = response.into_body().into_json::<Vec<Role>>().await;
+    let admin = roles.iter().find(|role| role.name == "admin").unwrap();
+    assert_that!(admin.permissions, len(eq(Permission::ALL.len())));
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+
+    let response = context
+        .app
+        .request("/api/roles")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let role = response.into_body().into_json::<Role>().await;
+    assert_that!(role.name, eq(&createset.name));
+    assert_that!(role.description, eq(&createset.description));
+    assert_that!(
+        role.permissions,
+        unordered_elements_are![
+            eq(&Permission::IncidentsWrite),
+            eq(&Permission::ProblemsWrite)
+        ]
+    );
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    for payload in [
+        json!({ "name": "", "description": "", "permissions": [] }),
+        json!({ "name": "admin", "description": "Duplicate.", "permissions": [] }),
+    ] {
+        let response = context
+            .app
+            .request("/api/roles")
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context
+        .app
+        .request("/api/roles")
+        .method(Method::POST)
+        .body(Body::from(
+            json!({ "name": "x", "description": "", "permissions": ["everything"] }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    roles::create(createset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let updateset = RoleUpdateset {
+        description: None,
+        permissions: Some(vec![Permission::ChangesWrite]),
+    };
+    let response = context
+        .app
+        .request(&format!("/api/roles/{}", createset.name))
+        .method(Method::PUT)
+        .body(Body::from(json!(updateset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let role = roles::load(&createset.name, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(role.description, eq(&createset.description));
+    assert_that!(
+        role.permissions,
+        elements_are![eq(&Permission::ChangesWrite)]
+    );
+}
+
+#[db_test]
+async fn test_update_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/roles/nonexistent")
+        .method(Method::PUT)
+        .body(Body::from(json!({ "description": "None." }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_delete_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    roles::create(createset.clone(), &context.db_pool)
+        .await
+        .unwrap();
+    roles::assign(&createset.name, "someone", &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/roles/{}", createset.name))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = roles::load(&createset.name, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+    let granted = roles::has_permission("someone", Permission::IncidentsWrite, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(granted, eq(false));
+}
+
+#[db_test]
+async fn test_history(context: &DbTestContext) {
+    let createset = create_basic_createset();
+    let response = context
+        .app
+        .request("/api/roles")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let response = context
+        .app
+        .request(&format!(
+            "/api/roles/{}/assignments/someone",
+            createset.name
+        ))
+        .method(Method::PUT)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let response = context
+        .app
+        .request(&format!("/api/roles/{}", createset.name))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    // The history outlives the role, like for every other audited entity.
+    let response = context
+        .app
+        .request(&format!("/api/roles/{}/history", createset.name))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let history = response.into_body().into_json::<Vec<AuditEntry>>().await;
+
+    let entries: Vec<_> = history
+        .iter()
+        .map(|entry| (entry.source.as_str(), entry.action))
+        .collect();
+    assert_that!(
+        entries,
+        elements_are![
+            eq(&("roles", AuditAction::Create)),
+            eq(&("role_permissions", AuditAction::Create)),
+            eq(&("role_permissions", AuditAction::Create)),
+            eq(&("role_assignments", AuditAction::Create)),
+            eq(&("roles", AuditAction::Delete)),
+            eq(&("role_permissions", AuditAction::Delete)),
+            eq(&("role_permissions", AuditAction::Delete)),
+            eq(&("role_assignments", AuditAction::Delete)),
+        ]
+    );
+    assert_that!(
+        history,
+        each(all!(
+            field!(AuditEntry.actor, eq(TEST_SUBJECT)),
+            field!(AuditEntry.entity_type, eq(&AuditEntity::Role)),
+            field!(AuditEntry.entity_id, eq(&history[0].entity_id)),
+        ))
+    );
+    assert_that!(
+        history[3].changes["subject"]["after"],
+        eq(&json!("someone"))
+    );
+}
+
+#[db_test]
+async fn test_history_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/roles/nonexistent/history")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_assign_and_unassign(context: &DbTestContext) {
+    let token = context.token_for("newcomer");
+    let payload = json!({
+        "title": "Newcomer Incident",
+        "impact": "low",
+        "urgency": "low",
+        "description": "Created once the role is granted.",
+    });
+
+    let response = context
+        .app
+        .request("/api/roles/service_desk_agent/assignments/newcomer")
+        .method(Method::PUT)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let assignment = response.into_body().into_json::<RoleAssignment>().await;
+    assert_that!(
+        assignment,
+        eq(&RoleAssignment {
+            subject: String::from("newcomer"),
+            role: String::from("service_desk_agent"),
+        })
+    );
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .token(&token)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let response = context
+        .app
+        .request("/api/roles/service_desk_agent/assignments")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let assignments = response
+        .into_body()
+        .into_json::<Vec<RoleAssignment>>()
+        .await;
+    assert_that!(assignments, len(eq(1)));
+
+    let response = context
+        .app
+        .request("/api/roles/service_desk_agent/assignments/newcomer")
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let response = context
+        .app
+        .request("/api/incidents")
+        .method(Method::POST)
+        .token(&token)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_assign_nonexistent_role(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/roles/nonexistent/assignments/someone")
+        .method(Method::PUT)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
@@ -0,0 +1,147 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::root_cause_categories::{
+    self, RootCauseCategory, RootCauseCategoryCreateset,
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> RootCauseCategoryCreateset {
+    RootCauseCategoryCreateset {
+        name: String::from("Security breach"),
+        description: String::from("Someone attacked a service."),
+    }
+}
+
+#[db_test]
+async fn test_read_all(context: &DbTestContext) {
+    let response = context.app.request("/api/rootcausecategories").send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let categories = response
+        .into_body()
+        .into_json::<Vec<RootCauseCategory>>()
+        .await;
+    assert_that!(
+        categories,
+        contains(field!(RootCauseCategory.name, eq("Software defect")))
+    );
+    let mut names: Vec<&str> = categories.iter().map(|c| c.name.as_str()).collect();
+    names.sort();
+    assert_that!(
+        categories
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>(),
+        eq(&names)
+    );
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let payload = json!(create_basic_createset());
+
+    let response = context
+        .app
+        .request("/api/rootcausecategories")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let category = response.into_body().into_json::<RootCauseCategory>().await;
+    assert_that!(category.name, eq("Security breach"));
+
+    let category_after = root_cause_categories::load(category.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(category_after, eq(&category));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    for payload in [
+        json!(RootCauseCategoryCreateset {
+            name: String::from(""),
+            ..create_basic_createset()
+        }),
+        json!(RootCauseCategoryCreateset {
+            name: String::from("Software defect"),
+            ..create_basic_createset()
+        }),
+    ] {
+        let response = context
+            .app
+            .request("/api/rootcausecategories")
+            .method(Method::POST)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_update_and_delete(context: &DbTestContext) {
+    let category = root_cause_categories::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let payload = json!({ "description": "Someone broke into a service." });
+    let response = context
+        .app
+        .request(&format!("/api/rootcausecategories/{}", category.id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let updated = response.into_body().into_json::<RootCauseCategory>().await;
+    assert_that!(
+        updated,
+        matches_pattern!(RootCauseCategory {
+            name: eq("Security breach"),
+            description: eq("Someone broke into a service."),
+            ..
+        })
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/rootcausecategories/{}", category.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = root_cause_categories::load(category.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_update_nonexistent(context: &DbTestContext) {
+    let payload = json!({ "name": "Anything" });
+
+    let response = context
+        .app
+        .request(&format!("/api/rootcausecategories/{}", Uuid::new_v4()))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
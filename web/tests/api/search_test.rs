@@ -0,0 +1,308 @@
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    self,
+    audit::AuditEntry,
+    changes::{RFCCreateset, RFCStatus},
+    configuration::{changes::CIChangeCreateset, CIStatus, ConfigItemCreateset},
+    incidents::{IncidentCreateset, IncidentImpact, IncidentStatus, IncidentUrgency},
+    problems::{ProblemCreateset, ProblemStatus},
+    search::{SearchEntityType, SearchResult},
+    users::{self, UserCreateset},
+};
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use uuid::Uuid;
+
+async fn post_incident(context: &DbTestContext, title: &str, description: &str) -> Uuid {
+    let createset = IncidentCreateset {
+        title: String::from(title),
+        status: Some(IncidentStatus::InProgress),
+        created_at: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from(description),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    entities::incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_ci(context: &DbTestContext, name: &str) -> Uuid {
+    let createset = ConfigItemCreateset {
+        name: String::from(name),
+        status: Some(CIStatus::Active),
+        created_at: None,
+        r#type: Some(String::from("Printer")),
+        owner_team_id: None,
+        description: String::from("Shared printer of the second floor."),
+    };
+
+    entities::configuration::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_problem(context: &DbTestContext) -> Uuid {
+    let createset = ProblemCreateset {
+        title: String::from("Printer jams"),
+        status: Some(ProblemStatus::Open),
+        detection_timedate: Some("2023-09-15T12:34:56Z".parse().unwrap()),
+        description: String::from("Printer jams with large documents."),
+        causes: String::from("The spooler runs out of memory."),
+        workarounds: None,
+        resolutions: None,
+    };
+
+    entities::problems::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_rfc(context: &DbTestContext) -> Uuid {
+    let requester = users::create(
+        UserCreateset {
+            username: format!("requester-{}", Uuid::new_v4()),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let createset = RFCCreateset {
+        title: String::from("Replace the printer"),
+        r#type: None,
+        status: Some(RFCStatus::Draft),
+        created_at: None,
+        finished_at: None,
+        requester_id: requester.id,
+        description: String::from("The current printer can't be fixed."),
+        planned_start_at: None,
+        planned_end_at: None,
+        implementation_plan: Some(String::from("Install the new spooler.")),
+        backout_plan: None,
+        test_plan: None,
+    };
+
+    entities::changes::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_ci_change(context: &DbTestContext, ci_id: Uuid) -> Uuid {
+    let createset = CIChangeCreateset {
+        implementation_timedate: "2023-09-15T12:34:56Z".parse().unwrap(),
+        documentation: String::from("Restarted the spooler."),
+        rfc_id: None,
+    };
+
+    entities::configuration::changes::create(ci_id, createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn search(context: &DbTestContext, query: &str) -> Vec<SearchResult> {
+    let response = context
+        .app
+        .request(&format!("/api/search?{}", query))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    response.into_body().into_json::<Vec<SearchResult>>().await
+}
+
+#[db_test]
+async fn test_search_all_types(context: &DbTestContext) {
+    let incident_id = post_incident(context, "Printing fails", "Restarted the spooler.").await;
+    let ci_id = post_ci(context, "Spooler server").await;
+    let problem_id = post_problem(context).await;
+    let rfc_id = post_rfc(context).await;
+    let ci_change_id = post_ci_change(context, ci_id).await;
+    post_incident(context, "Password expired", "Reset the password.").await;
+
+    let results = search(context, "q=spooler").await;
+    assert_that!(
+        results,
+        unordered_elements_are![
+            matches_pattern!(SearchResult {
+                r#type: eq(&SearchEntityType::Incident),
+                id: eq(&incident_id),
+                ci_id: none(),
+                title: eq("Printing fails"),
+                status: some(eq("inprogress")),
+                ..
+            }),
+            matches_pattern!(SearchResult {
+                r#type: eq(&SearchEntityType::ConfigItem),
+                id: eq(&ci_id),
+                status: some(eq("active")),
+                ..
+            }),
+            matches_pattern!(SearchResult {
+                r#type: eq(&SearchEntityType::Problem),
+                id: eq(&problem_id),
+                status: some(eq("open")),
+                ..
+            }),
+            matches_pattern!(SearchResult {
+                r#type: eq(&SearchEntityType::Rfc),
+                id: eq(&rfc_id),
+                status: some(eq("draft")),
+                ..
+            }),
+            matches_pattern!(SearchResult {
+                r#type: eq(&SearchEntityType::CiChange),
+                id: eq(&ci_change_id),
+                ci_id: some(eq(&ci_id)),
+                title: eq("Spooler server"),
+                status: none(),
+                ..
+            }),
+        ]
+    );
+}
+
+#[db_test]
+async fn test_search_rank_and_highlight(context: &DbTestContext) {
+    let in_description = post_incident(
+        context,
+        "Printing fails",
+        "Nobody can print. The toner was empty.",
+    )
+    .await;
+    let in_title = post_incident(context, "Toner empty", "Replaced the toner.").await;
+
+    let results = search(context, "q=toner").await;
+    assert_that!(
+        results.iter().map(|r| r.id).collect::<Vec<_>>(),
+        elements_are![eq(&in_title), eq(&in_description)]
+    );
+    assert_that!(results[0].rank, gt(results[1].rank));
+    assert_that!(
+        results[1].highlight,
+        eq("Nobody can print. The <mark>toner</mark> was empty")
+    );
+}
+
+#[db_test]
+async fn test_search_highlight_escapes_html(context: &DbTestContext) {
+    post_incident(
+        context,
+        "Portal defaced",
+        "The toner page shows <script>alert(1)</script> & more.",
+    )
+    .await;
+
+    let results = search(context, "q=toner").await;
+    assert_that!(
+        results,
+        elements_are![field!(
+            SearchResult.highlight,
+            eq("<mark>toner</mark> page shows &lt;script&gt;alert(1)&lt;/script&gt; &amp; more")
+        )]
+    );
+}
+
+#[db_test]
+async fn test_search_filters(context: &DbTestContext) {
+    let incident_id = post_incident(context, "Printing fails", "Restarted the spooler.").await;
+    let ci_id = post_ci(context, "Spooler server").await;
+    let problem_id = post_problem(context).await;
+    post_ci_change(context, ci_id).await;
+
+    let results = search(context, "q=spooler&type=problem").await;
+    assert_that!(
+        results.iter().map(|r| r.id).collect::<Vec<_>>(),
+        elements_are![eq(&problem_id)]
+    );
+
+    let results = search(context, "q=spooler&status=inprogress").await;
+    assert_that!(
+        results.iter().map(|r| r.id).collect::<Vec<_>>(),
+        elements_are![eq(&incident_id)]
+    );
+
+    let results = search(context, "q=spooler&type=incident&status=open").await;
+    assert_that!(results, is_empty());
+
+    let results = search(context, "q=spooler&limit=2").await;
+    assert_that!(results, len(eq(2)));
+}
+
+#[db_test]
+async fn test_search_phrases_and_exclusions(context: &DbTestContext) {
+    let restarted = post_incident(context, "Printing fails", "Restarted the spooler.").await;
+    post_incident(
+        context,
+        "Printing is slow",
+        "The spooler was restarted by someone.",
+    )
+    .await;
+    post_incident(context, "Spooler crashed", "Nothing to do.").await;
+
+    let results = search(context, "q=%22restarted+the+spooler%22").await;
+    assert_that!(
+        results.iter().map(|r| r.id).collect::<Vec<_>>(),
+        elements_are![eq(&restarted)]
+    );
+
+    let results = search(context, "q=spooler+-restarted").await;
+    assert_that!(
+        results.iter().map(|r| r.title.as_str()).collect::<Vec<_>>(),
+        elements_are![eq(&"Spooler crashed")]
+    );
+}
+
+#[db_test]
+async fn test_search_invalid(context: &DbTestContext) {
+    for query in ["q=", "q=spooler&limit=0", "q=spooler&status="] {
+        let response = context
+            .app
+            .request(&format!("/api/search?{}", query))
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    for query in ["", "q=spooler&type=knownerror"] {
+        let response = context
+            .app
+            .request(&format!("/api/search?{}", query))
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::BAD_REQUEST));
+    }
+}
+
+#[db_test]
+async fn test_history_leaves_out_search_vector(context: &DbTestContext) {
+    let incident_id = post_incident(context, "Printing fails", "Restarted the spooler.").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/history", incident_id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let history = response.into_body().into_json::<Vec<AuditEntry>>().await;
+    assert_that!(history, not(is_empty()));
+    for entry in history {
+        assert_that!(entry.changes.get("search_vector"), none());
+    }
+}
@@ -0,0 +1,291 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    roles,
+    service_catalog::{
+        self, CatalogFieldType, CatalogFormField, CatalogItem, CatalogItemCreateset,
+    },
+    service_requests::{self, ServiceRequestCreateset},
+    users::{self, UserCreateset},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> CatalogItemCreateset {
+    CatalogItemCreateset {
+        name: String::from("New laptop"),
+        description: String::from("A laptop for a new hire."),
+        form_fields: vec![
+            CatalogFormField {
+                name: String::from("operating_system"),
+                label: String::from("Operating system"),
+                r#type: CatalogFieldType::Choice,
+                required: true,
+                options: vec![String::from("Linux"), String::from("Windows")],
+            },
+            CatalogFormField {
+                name: String::from("notes"),
+                label: String::from("Notes"),
+                r#type: CatalogFieldType::Text,
+                required: false,
+                options: vec![],
+            },
+        ],
+        approval_group_id: None,
+        fulfilment_group_id: None,
+        fulfilment_tasks: vec![String::from("Order the laptop.")],
+        active: None,
+    }
+}
+
+async fn post_item(
+    context: &DbTestContext,
+    payload: serde_json::Value,
+) -> axum::response::Response {
+    context
+        .app
+        .request("/api/catalog")
+        .method(Method::POST)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let response = post_item(context, json!(create_basic_createset())).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let item = response.into_body().into_json::<CatalogItem>().await;
+    assert_that!(
+        item,
+        matches_pattern!(CatalogItem {
+            name: eq("New laptop"),
+            fulfilment_tasks: elements_are![eq("Order the laptop.")],
+            active: eq(&true),
+            ..
+        })
+    );
+    assert_that!(
+        item.form_fields.0,
+        eq(&create_basic_createset().form_fields)
+    );
+
+    let item_after = service_catalog::load(item.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(item_after, eq(&item));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    service_catalog::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let field = json!({ "name": "size", "label": "Size", "type": "text" });
+
+    for payload in [
+        // Duplicate name.
+        json!(create_basic_createset()),
+        json!({ "name": "" }),
+        json!({ "name": "Phone", "form_fields": [field, field] }),
+        json!({ "name": "Phone", "form_fields": [{ "name": "size", "label": "Size", "type": "choice" }] }),
+        json!({ "name": "Phone", "form_fields": [{ "name": "size", "label": "Size", "type": "number", "options": ["1"] }] }),
+        json!({ "name": "Phone", "fulfilment_tasks": [""] }),
+        json!({ "name": "Phone", "approval_group_id": Uuid::new_v4() }),
+    ] {
+        let response = post_item(context, payload).await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_needs_permission(context: &DbTestContext) {
+    users::create(
+        UserCreateset {
+            username: String::from("agent"),
+            full_name: String::from("Service Desk Agent"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    roles::assign("service_desk_agent", "agent", &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request("/api/catalog")
+        .method(Method::POST)
+        .token(&context.token_for("agent"))
+        .body(Body::from(json!(create_basic_createset()).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    let body = response.into_body().into_json::<serde_json::Value>().await;
+    assert_that!(body["permission"], eq(&json!("catalog.manage")));
+}
+
+#[db_test]
+async fn test_read_all_filters_active(context: &DbTestContext) {
+    let active = service_catalog::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let inactive = service_catalog::create(
+        CatalogItemCreateset {
+            name: String::from("Desk phone"),
+            active: Some(false),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context.app.request("/api/catalog").send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response.into_body().into_json::<Page<CatalogItem>>().await;
+    assert_that!(
+        page.items.iter().map(|i| i.id).collect::<Vec<_>>(),
+        elements_are![eq(&inactive.id), eq(&active.id)]
+    );
+
+    let response = context.app.request("/api/catalog?active=true").send().await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response.into_body().into_json::<Page<CatalogItem>>().await;
+    assert_that!(
+        page.items.iter().map(|i| i.id).collect::<Vec<_>>(),
+        elements_are![eq(&active.id)]
+    );
+}
+
+#[db_test]
+async fn test_update_and_delete(context: &DbTestContext) {
+    let item = service_catalog::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let payload = json!({
+        "fulfilment_tasks": ["Order the laptop.", "Install the laptop."],
+        "active": false,
+    });
+    let response = context
+        .app
+        .request(&format!("/api/catalog/{}", item.id))
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<CatalogItem>().await;
+    assert_that!(
+        updated,
+        matches_pattern!(CatalogItem {
+            name: eq("New laptop"),
+            fulfilment_tasks: elements_are![eq("Order the laptop."), eq("Install the laptop.")],
+            active: eq(&false),
+            ..
+        })
+    );
+
+    for payload in [json!({ "name": null }), json!({ "form_fields": null })] {
+        let response = context
+            .app
+            .request(&format!("/api/catalog/{}", item.id))
+            .method(Method::PUT)
+            .body(Body::from(payload.to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+
+    let response = context
+        .app
+        .request(&format!("/api/catalog/{}", item.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = service_catalog::load(item.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_delete_requested(context: &DbTestContext) {
+    let item = service_catalog::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let requester_id = users::create(
+        UserCreateset {
+            username: String::from("requester"),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap()
+    .id;
+    service_requests::create(
+        ServiceRequestCreateset {
+            catalog_item_id: item.id,
+            requester_id,
+            answers: json!({ "operating_system": "Linux" }),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/catalog/{}", item.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_nonexistent(context: &DbTestContext) {
+    let id = Uuid::new_v4();
+
+    let response = context
+        .app
+        .request(&format!("/api/catalog/{}", id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let response = context
+        .app
+        .request(&format!("/api/catalog/{}", id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "name": "Anything" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let response = context
+        .app
+        .request(&format!("/api/catalog/{}", id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
@@ -0,0 +1,471 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    audit::AuditEntry,
+    roles,
+    service_catalog::{self, CatalogFieldType, CatalogFormField, CatalogItemCreateset},
+    service_requests::{
+        self,
+        approvals::ServiceRequestApproval,
+        tasks::{self, ServiceRequestTask, ServiceRequestTaskStatus},
+        ServiceRequest, ServiceRequestStatus,
+    },
+    teams::{self, members, TeamCreateset},
+    users::{self, UserCreateset},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn post_user(context: &DbTestContext, username: &str) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(username),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    users::create(createset, &context.db_pool).await.unwrap().id
+}
+
+/// Makes a team of service desk agents named after `usernames` and returns its id and their
+/// tokens.
+async fn post_team(context: &DbTestContext, usernames: &[&str]) -> (Uuid, Vec<String>) {
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("Approvers"),
+            description: String::from("Approve requests for laptops."),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let mut tokens = Vec::new();
+    for username in usernames {
+        let user_id = post_user(context, username).await;
+        members::create(team.id, user_id, &context.db_pool)
+            .await
+            .unwrap();
+        roles::assign("service_desk_agent", username, &context.db_pool)
+            .await
+            .unwrap();
+        tokens.push(context.token_for(username));
+    }
+
+    (team.id, tokens)
+}
+
+async fn post_item(context: &DbTestContext, approval_group_id: Option<Uuid>) -> Uuid {
+    let createset = CatalogItemCreateset {
+        name: String::from("New laptop"),
+        description: String::from("A laptop for a new hire."),
+        form_fields: vec![
+            CatalogFormField {
+                name: String::from("operating_system"),
+                label: String::from("Operating system"),
+                r#type: CatalogFieldType::Choice,
+                required: true,
+                options: vec![String::from("Linux"), String::from("Windows")],
+            },
+            CatalogFormField {
+                name: String::from("memory"),
+                label: String::from("Memory (GB)"),
+                r#type: CatalogFieldType::Number,
+                required: false,
+                options: vec![],
+            },
+        ],
+        approval_group_id,
+        fulfilment_group_id: None,
+        fulfilment_tasks: vec![
+            String::from("Order the laptop."),
+            String::from("Install the laptop."),
+        ],
+        active: None,
+    };
+
+    service_catalog::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn post_request(
+    context: &DbTestContext,
+    item_id: Uuid,
+    answers: serde_json::Value,
+) -> axum::response::Response {
+    let requester_id = post_user(context, &format!("requester-{}", Uuid::new_v4())).await;
+    context
+        .app
+        .request("/api/requests")
+        .method(Method::POST)
+        .body(Body::from(
+            json!({
+                "catalog_item_id": item_id,
+                "requester_id": requester_id,
+                "answers": answers,
+            })
+            .to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn post_vote(
+    context: &DbTestContext,
+    request_id: Uuid,
+    token: &str,
+    vote: &str,
+) -> axum::response::Response {
+    context
+        .app
+        .request(&format!("/api/requests/{}/approvals", request_id))
+        .method(Method::POST)
+        .token(token)
+        .body(Body::from(
+            json!({ "vote": vote, "comment": "Budgeted for this quarter." }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+async fn put(context: &DbTestContext, uri: &str, payload: serde_json::Value) -> StatusCode {
+    context
+        .app
+        .request(uri)
+        .method(Method::PUT)
+        .body(Body::from(payload.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+        .status()
+}
+
+#[db_test]
+async fn test_fulfilment_without_approval(context: &DbTestContext) {
+    let item_id = post_item(context, None).await;
+
+    let response = post_request(context, item_id, json!({ "operating_system": "Linux" })).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let request = response.into_body().into_json::<ServiceRequest>().await;
+    assert_that!(request.status, eq(ServiceRequestStatus::InFulfilment));
+
+    let response = context
+        .app
+        .request(&format!("/api/requests/{}/tasks", request.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let tasks = response
+        .into_body()
+        .into_json::<Vec<ServiceRequestTask>>()
+        .await;
+    assert_that!(
+        tasks,
+        elements_are![
+            matches_pattern!(ServiceRequestTask {
+                position: eq(&1),
+                title: eq("Order the laptop."),
+                status: eq(&ServiceRequestTaskStatus::Open),
+                ..
+            }),
+            matches_pattern!(ServiceRequestTask {
+                position: eq(&2),
+                title: eq("Install the laptop."),
+                status: eq(&ServiceRequestTaskStatus::Open),
+                ..
+            }),
+        ]
+    );
+
+    let uri = format!("/api/requests/{}/tasks/{}", request.id, tasks[0].id);
+    assert_that!(
+        put(context, &uri, json!({ "status": "done" })).await,
+        eq(StatusCode::OK)
+    );
+    // Completed tasks can't be reopened.
+    assert_that!(
+        put(context, &uri, json!({ "status": "open" })).await,
+        eq(StatusCode::CONFLICT)
+    );
+    let request_after = service_requests::load(request.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(request_after.status, eq(ServiceRequestStatus::InFulfilment));
+
+    let uri = format!("/api/requests/{}/tasks/{}", request.id, tasks[1].id);
+    assert_that!(
+        put(context, &uri, json!({ "status": "skipped" })).await,
+        eq(StatusCode::OK)
+    );
+    let request_after = service_requests::load(request.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(request_after.status, eq(ServiceRequestStatus::Fulfilled));
+    assert_that!(request_after.fulfilled_at, some(anything()));
+    let tasks = tasks::load_all(request.id, &context.db_pool).await.unwrap();
+    assert_that!(
+        tasks,
+        each(field!(ServiceRequestTask.completed_at, some(anything())))
+    );
+
+    // Tasks of fulfilled requests are frozen, and fulfilled requests can only be closed.
+    assert_that!(
+        put(context, &uri, json!({ "assignee_id": null })).await,
+        eq(StatusCode::FORBIDDEN)
+    );
+    let uri = format!("/api/requests/{}", request.id);
+    assert_that!(
+        put(context, &uri, json!({ "status": "cancelled" })).await,
+        eq(StatusCode::CONFLICT)
+    );
+    assert_that!(
+        put(context, &uri, json!({ "status": "closed" })).await,
+        eq(StatusCode::OK)
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/requests/{}/history", request.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let history = response.into_body().into_json::<Vec<AuditEntry>>().await;
+    assert_that!(history, not(is_empty()));
+}
+
+#[db_test]
+async fn test_approval(context: &DbTestContext) {
+    let (team_id, tokens) = post_team(context, &["alice"]).await;
+    let item_id = post_item(context, Some(team_id)).await;
+    let response = post_request(context, item_id, json!({ "operating_system": "Linux" })).await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let request = response.into_body().into_json::<ServiceRequest>().await;
+    assert_that!(request.status, eq(ServiceRequestStatus::PendingApproval));
+
+    let tasks = tasks::load_all(request.id, &context.db_pool).await.unwrap();
+    assert_that!(tasks, is_empty());
+
+    // Answers can change while the request is pending approval.
+    let uri = format!("/api/requests/{}", request.id);
+    assert_that!(
+        put(
+            context,
+            &uri,
+            json!({ "answers": { "operating_system": "Windows", "memory": 32 } })
+        )
+        .await,
+        eq(StatusCode::OK)
+    );
+
+    let response = post_vote(context, request.id, &tokens[0], "approve").await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let approval = response
+        .into_body()
+        .into_json::<ServiceRequestApproval>()
+        .await;
+    assert_that!(approval.request_id, eq(request.id));
+
+    let request_after = service_requests::load(request.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(request_after.status, eq(ServiceRequestStatus::InFulfilment));
+    assert_that!(
+        request_after.answers,
+        eq(&json!({ "operating_system": "Windows", "memory": 32 }))
+    );
+    let tasks = tasks::load_all(request.id, &context.db_pool).await.unwrap();
+    assert_that!(tasks, len(eq(2)));
+
+    // Once approved, the request is settled and its answers are frozen.
+    let response = post_vote(context, request.id, &tokens[0], "reject").await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+    assert_that!(
+        put(
+            context,
+            &uri,
+            json!({ "answers": { "operating_system": "Linux" } })
+        )
+        .await,
+        eq(StatusCode::UNPROCESSABLE_ENTITY)
+    );
+
+    let response = context
+        .app
+        .request(&format!("/api/requests/{}/approvals", request.id))
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let approvals = response
+        .into_body()
+        .into_json::<Vec<ServiceRequestApproval>>()
+        .await;
+    assert_that!(approvals, len(eq(1)));
+
+    // Requests in fulfilment can still be cancelled.
+    assert_that!(
+        put(context, &uri, json!({ "status": "cancelled" })).await,
+        eq(StatusCode::OK)
+    );
+}
+
+#[db_test]
+async fn test_rejection(context: &DbTestContext) {
+    let (team_id, tokens) = post_team(context, &["alice"]).await;
+    let item_id = post_item(context, Some(team_id)).await;
+    let response = post_request(context, item_id, json!({ "operating_system": "Linux" })).await;
+    let request = response.into_body().into_json::<ServiceRequest>().await;
+
+    let response = post_vote(context, request.id, &tokens[0], "reject").await;
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let request_after = service_requests::load(request.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(request_after.status, eq(ServiceRequestStatus::Rejected));
+    let tasks = tasks::load_all(request.id, &context.db_pool).await.unwrap();
+    assert_that!(tasks, is_empty());
+
+    // Rejection is final.
+    assert_that!(
+        put(
+            context,
+            &format!("/api/requests/{}", request.id),
+            json!({ "status": "cancelled" })
+        )
+        .await,
+        eq(StatusCode::CONFLICT)
+    );
+}
+
+#[db_test]
+async fn test_approval_by_non_member(context: &DbTestContext) {
+    let (team_id, _) = post_team(context, &["alice"]).await;
+    let item_id = post_item(context, Some(team_id)).await;
+    let response = post_request(context, item_id, json!({ "operating_system": "Linux" })).await;
+    let request = response.into_body().into_json::<ServiceRequest>().await;
+
+    post_user(context, "bob").await;
+    roles::assign("service_desk_agent", "bob", &context.db_pool)
+        .await
+        .unwrap();
+    let response = post_vote(context, request.id, &context.token_for("bob"), "approve").await;
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+
+    let request_after = service_requests::load(request.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(
+        request_after.status,
+        eq(ServiceRequestStatus::PendingApproval)
+    );
+}
+
+#[db_test]
+async fn test_create_invalid_answers(context: &DbTestContext) {
+    let item_id = post_item(context, None).await;
+
+    for answers in [
+        json!([]),
+        json!({}),
+        json!({ "operating_system": "BeOS" }),
+        json!({ "operating_system": "Linux", "memory": "lots" }),
+        json!({ "operating_system": "Linux", "colour": "black" }),
+    ] {
+        let response = post_request(context, item_id, answers).await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_inactive_or_nonexistent_item(context: &DbTestContext) {
+    let item_id = post_item(context, None).await;
+    assert_that!(
+        put(
+            context,
+            &format!("/api/catalog/{}", item_id),
+            json!({ "active": false })
+        )
+        .await,
+        eq(StatusCode::OK)
+    );
+
+    for item_id in [item_id, Uuid::new_v4()] {
+        let response = post_request(context, item_id, json!({ "operating_system": "Linux" })).await;
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_read_all_filters_status(context: &DbTestContext) {
+    let (team_id, _) = post_team(context, &["alice"]).await;
+    let pending_item_id = post_item(context, Some(team_id)).await;
+    let pending = post_request(
+        context,
+        pending_item_id,
+        json!({ "operating_system": "Linux" }),
+    )
+    .await
+    .into_body()
+    .into_json::<ServiceRequest>()
+    .await;
+
+    let response = context
+        .app
+        .request("/api/requests?status=pendingapproval")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response
+        .into_body()
+        .into_json::<Page<ServiceRequest>>()
+        .await;
+    assert_that!(
+        page.items.iter().map(|r| r.id).collect::<Vec<_>>(),
+        elements_are![eq(&pending.id)]
+    );
+
+    let response = context
+        .app
+        .request("/api/requests?status=infulfilment")
+        .send()
+        .await;
+    let page = response
+        .into_body()
+        .into_json::<Page<ServiceRequest>>()
+        .await;
+    assert_that!(page.items, is_empty());
+}
+
+#[db_test]
+async fn test_nonexistent(context: &DbTestContext) {
+    let id = Uuid::new_v4();
+
+    for uri in [
+        format!("/api/requests/{}", id),
+        format!("/api/requests/{}/approvals", id),
+        format!("/api/requests/{}/tasks", id),
+    ] {
+        let response = context.app.request(&uri).send().await;
+        assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+    }
+
+    assert_that!(
+        put(
+            context,
+            &format!("/api/requests/{}/tasks/{}", id, Uuid::new_v4()),
+            json!({ "status": "done" })
+        )
+        .await,
+        eq(StatusCode::NOT_FOUND)
+    );
+}
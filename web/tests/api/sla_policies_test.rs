@@ -0,0 +1,439 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::NaiveTime;
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    incidents::{self, IncidentCreateset, IncidentImpact, IncidentPrio, IncidentUrgency},
+    sla_policies::{
+        self, SlaHoliday, SlaPolicy, SlaPolicyCreateset, SlaPolicyUpdateset, SlaTarget,
+    },
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> SlaPolicyCreateset {
+    SlaPolicyCreateset {
+        name: String::from("Standard"),
+        description: String::from("Office hours support for internal services."),
+        is_default: None,
+        timezone: Some(String::from("Europe/Madrid")),
+        business_hours_start: NaiveTime::from_hms_opt(9, 0, 0),
+        business_hours_end: NaiveTime::from_hms_opt(17, 0, 0),
+        business_days: Some(vec![1, 2, 3, 4, 5]),
+        targets: vec![
+            SlaTarget {
+                priority: IncidentPrio::Critical,
+                response_minutes: 15,
+                resolution_minutes: 240,
+            },
+            SlaTarget {
+                priority: IncidentPrio::Low,
+                response_minutes: 240,
+                resolution_minutes: 2400,
+            },
+        ],
+        holidays: vec![SlaHoliday {
+            day: "2025-01-01".parse().unwrap(),
+            name: String::from("New Year's Day"),
+        }],
+    }
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+
+    let response = context
+        .app
+        .request("/api/slapolicies")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let policy = response.into_body().into_json::<SlaPolicy>().await;
+    assert_that!(policy.name, eq(&createset.name));
+    assert_that!(policy.is_default, eq(false));
+    assert_that!(policy.timezone, eq("Europe/Madrid"));
+    assert_that!(
+        policy.business_hours_start,
+        eq(createset.business_hours_start)
+    );
+    assert_that!(policy.business_days, eq(&createset.business_days.unwrap()));
+    assert_that!(policy.targets, eq(&createset.targets));
+    assert_that!(policy.holidays, eq(&createset.holidays));
+
+    let policy_after = sla_policies::load(policy.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(policy_after, eq(&policy));
+}
+
+#[db_test]
+async fn test_create_defaults(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/slapolicies")
+        .method(Method::POST)
+        .body(Body::from(
+            json!({ "name": "Around the clock", "description": "", "targets": [] }).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let policy = response.into_body().into_json::<SlaPolicy>().await;
+    assert_that!(policy.timezone, eq("UTC"));
+    assert_that!(policy.business_hours_start, none());
+    assert_that!(policy.business_days, eq(&vec![1, 2, 3, 4, 5, 6, 7]));
+    assert_that!(policy.holidays, is_empty());
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    sla_policies::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let createset = SlaPolicyCreateset {
+        name: String::from("Premium"),
+        ..create_basic_createset()
+    };
+    let mut targets = createset.targets.clone();
+    targets.push(SlaTarget {
+        priority: IncidentPrio::Low,
+        response_minutes: 30,
+        resolution_minutes: 60,
+    });
+    let sets = vec![
+        // Name already taken.
+        create_basic_createset(),
+        SlaPolicyCreateset {
+            timezone: Some(String::from("Mars/Olympus_Mons")),
+            ..createset.clone()
+        },
+        SlaPolicyCreateset {
+            business_days: Some(vec![]),
+            ..createset.clone()
+        },
+        SlaPolicyCreateset {
+            business_days: Some(vec![0, 1]),
+            ..createset.clone()
+        },
+        SlaPolicyCreateset {
+            business_hours_end: None,
+            ..createset.clone()
+        },
+        SlaPolicyCreateset {
+            business_hours_end: NaiveTime::from_hms_opt(8, 0, 0),
+            ..createset.clone()
+        },
+        SlaPolicyCreateset {
+            targets,
+            ..createset.clone()
+        },
+        SlaPolicyCreateset {
+            targets: vec![SlaTarget {
+                priority: IncidentPrio::High,
+                response_minutes: 0,
+                resolution_minutes: 60,
+            }],
+            ..createset.clone()
+        },
+    ];
+
+    for set in sets {
+        let response = context
+            .app
+            .request("/api/slapolicies")
+            .method(Method::POST)
+            .body(Body::from(json!(set).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_needs_slapolicies_manage(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/slapolicies")
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!(create_basic_createset()).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_read_all(context: &DbTestContext) {
+    for name in ["Standard", "Premium"] {
+        sla_policies::create(
+            SlaPolicyCreateset {
+                name: String::from(name),
+                ..create_basic_createset()
+            },
+            &context.db_pool,
+        )
+        .await
+        .unwrap();
+    }
+
+    let response = context.app.request("/api/slapolicies").send().await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page = response.into_body().into_json::<Page<SlaPolicy>>().await;
+    assert_that!(page.total, eq(2));
+    let names: Vec<&str> = page.items.iter().map(|p| p.name.as_str()).collect();
+    assert_that!(names, elements_are![eq(&"Premium"), eq(&"Standard")]);
+    assert_that!(page.items[0].targets, len(eq(2)));
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let policy = sla_policies::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let updateset = SlaPolicyUpdateset {
+        name: None,
+        description: None,
+        is_default: None,
+        timezone: None,
+        business_hours_start: Some(None),
+        business_hours_end: Some(None),
+        business_days: None,
+        targets: Some(Some(vec![SlaTarget {
+            priority: IncidentPrio::High,
+            response_minutes: 30,
+            resolution_minutes: 480,
+        }])),
+        holidays: None,
+    };
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", policy.id))
+        .method(Method::PUT)
+        .body(Body::from(json!(updateset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let policy_after = response.into_body().into_json::<SlaPolicy>().await;
+    assert_that!(policy_after.name, eq(&policy.name));
+    assert_that!(policy_after.business_hours_start, none());
+    assert_that!(policy_after.business_hours_end, none());
+    assert_that!(
+        policy_after.targets,
+        eq(&updateset.targets.unwrap().unwrap())
+    );
+    assert_that!(policy_after.holidays, eq(&policy.holidays));
+}
+
+#[db_test]
+async fn test_update_default(context: &DbTestContext) {
+    let standard = sla_policies::create(
+        SlaPolicyCreateset {
+            is_default: Some(true),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let premium = sla_policies::create(
+        SlaPolicyCreateset {
+            name: String::from("Premium"),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", premium.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "is_default": true }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let standard_after = sla_policies::load(standard.id, &context.db_pool)
+        .await
+        .unwrap();
+    let premium_after = sla_policies::load(premium.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(standard_after.is_default, eq(false));
+    assert_that!(premium_after.is_default, eq(true));
+}
+
+#[db_test]
+async fn test_unreachable_targets(context: &DbTestContext) {
+    // An hour a week takes over 19 years to add up to 1000 hours.
+    let createset = SlaPolicyCreateset {
+        business_hours_start: NaiveTime::from_hms_opt(9, 0, 0),
+        business_hours_end: NaiveTime::from_hms_opt(10, 0, 0),
+        business_days: Some(vec![1]),
+        targets: vec![SlaTarget {
+            priority: IncidentPrio::Low,
+            response_minutes: 60,
+            resolution_minutes: 60_000,
+        }],
+        ..create_basic_createset()
+    };
+    let response = context
+        .app
+        .request("/api/slapolicies")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let policy = sla_policies::create(
+        SlaPolicyCreateset {
+            is_default: Some(true),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let updateset = json!({
+        "business_hours_start": "09:00:00",
+        "business_hours_end": "10:00:00",
+        "business_days": [1],
+        "targets": createset.targets,
+    });
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", policy.id))
+        .method(Method::PUT)
+        .body(Body::from(updateset.to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    // The default policy is left as it was, so Incidents can still be created.
+    let policy_after = sla_policies::load(policy.id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(policy_after.business_days, eq(&policy.business_days));
+    let incident = incidents::create(
+        IncidentCreateset {
+            title: String::from("Testing Incident"),
+            status: None,
+            created_at: None,
+            impact: IncidentImpact::Low,
+            urgency: IncidentUrgency::Low,
+            assignment_group_id: None,
+            assignee_id: None,
+            description: String::from("Testing yay!!"),
+            hold_reason: None,
+            resolution_code: None,
+            resolution_notes: None,
+            sla_policy_id: None,
+            parent_id: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    assert_that!(incident.sla_policy_id, some(eq(policy.id)));
+}
+
+#[db_test]
+async fn test_update_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", Uuid::new_v4()))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "name": "Premium" }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_delete_success(context: &DbTestContext) {
+    let policy = sla_policies::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", policy.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = sla_policies::load(policy.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_delete_in_use(context: &DbTestContext) {
+    let policy = sla_policies::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    incidents::create(
+        IncidentCreateset {
+            title: String::from("Testing Incident"),
+            status: None,
+            created_at: None,
+            impact: IncidentImpact::Low,
+            urgency: IncidentUrgency::Low,
+            assignment_group_id: None,
+            assignee_id: None,
+            description: String::from("Testing yay!!"),
+            hold_reason: None,
+            resolution_code: None,
+            resolution_notes: None,
+            sla_policy_id: Some(policy.id),
+            parent_id: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/slapolicies/{}", policy.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
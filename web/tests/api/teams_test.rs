@@ -0,0 +1,315 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    incidents::{self, IncidentCreateset, IncidentImpact, IncidentUrgency},
+    teams::{self, members::TeamMember, Team, TeamCreateset, TeamUpdateset},
+    users::{self, User, UserCreateset},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> TeamCreateset {
+    TeamCreateset {
+        name: String::from("Networking"),
+        description: String::from("Handles routers, switches and firewalls."),
+    }
+}
+
+async fn post_user(context: &DbTestContext, username: &str) -> Uuid {
+    let createset = UserCreateset {
+        username: String::from(username),
+        full_name: String::from("Testing User"),
+        email: None,
+    };
+
+    let user = users::create(createset, &context.db_pool).await.unwrap();
+
+    user.id
+}
+
+async fn post_incident(context: &DbTestContext, team_id: Uuid, user_id: Option<Uuid>) -> Uuid {
+    let createset = IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: None,
+        created_at: None,
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: Some(team_id),
+        assignee_id: user_id,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    let incident = incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap();
+
+    incident.id
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+
+    let response = context
+        .app
+        .request("/api/teams")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let team = response.into_body().into_json::<Team>().await;
+    assert_that!(team.name, eq(&createset.name));
+    assert_that!(team.description, eq(&createset.description));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let createset = create_basic_createset();
+    let mut sets = Vec::new();
+    sets.push(TeamCreateset {
+        name: String::from(""),
+        ..createset.clone()
+    });
+    sets.push(TeamCreateset {
+        name: String::from("Databases"),
+        description: String::from(&"x".repeat(1025)),
+    });
+    // Name already taken.
+    sets.push(createset);
+
+    for set in sets {
+        let response = context
+            .app
+            .request("/api/teams")
+            .method(Method::POST)
+            .body(Body::from(json!(set).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_needs_teams_manage(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/teams")
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!(create_basic_createset()).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_read_all_filtered(context: &DbTestContext) {
+    let user_id = post_user(context, "jdoe").await;
+    let expected = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    teams::create(
+        TeamCreateset {
+            name: String::from("Databases"),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    teams::members::create(expected.id, user_id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/teams?member_id={user_id}"))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page = response.into_body().into_json::<Page<Team>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items, elements_are![eq(&expected)]);
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let team = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let updateset = TeamUpdateset {
+        name: Some(Some(String::from("Network Operations"))),
+        description: None,
+    };
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}", team.id))
+        .method(Method::PUT)
+        .body(Body::from(json!(updateset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let team_after = teams::load(team.id, &context.db_pool).await.unwrap();
+    assert_that!(team_after.name, eq("Network Operations"));
+    assert_that!(team_after.description, eq(&team.description));
+}
+
+#[db_test]
+async fn test_delete_success(context: &DbTestContext) {
+    let team = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}", team.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = teams::load(team.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_delete_with_incidents(context: &DbTestContext) {
+    let team = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    post_incident(context, team.id, None).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}", team.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_members(context: &DbTestContext) {
+    let team = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let user_id = post_user(context, "jdoe").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}/members/{user_id}", team.id))
+        .method(Method::PUT)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let member = response.into_body().into_json::<TeamMember>().await;
+    assert_that!(member.team_id, eq(team.id));
+    assert_that!(member.user_id, eq(user_id));
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}/members", team.id))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let members = response.into_body().into_json::<Vec<User>>().await;
+    let ids: Vec<Uuid> = members.iter().map(|user| user.id).collect();
+    assert_that!(ids, elements_are![eq(&user_id)]);
+}
+
+#[db_test]
+async fn test_members_invalid(context: &DbTestContext) {
+    let team = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let user_id = post_user(context, "jdoe").await;
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}/members/{user_id}", Uuid::new_v4()))
+        .method(Method::PUT)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/teams/{}/members/{}",
+            team.id,
+            Uuid::new_v4()
+        ))
+        .method(Method::PUT)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}/members/{user_id}", team.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_remove_member_unassigns(context: &DbTestContext) {
+    let team = teams::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    let user_id = post_user(context, "jdoe").await;
+    teams::members::create(team.id, user_id, &context.db_pool)
+        .await
+        .unwrap();
+    let incident_id = post_incident(context, team.id, Some(user_id)).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/teams/{}/members/{user_id}", team.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let incident = incidents::load(incident_id, &context.db_pool)
+        .await
+        .unwrap();
+    assert_that!(incident.assignment_group_id, some(eq(team.id)));
+    assert_that!(incident.assignee_id, none());
+}
@@ -0,0 +1,422 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use chrono::Duration;
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    changes::{self, RFCCreateset},
+    incidents::{self, IncidentCreateset, IncidentImpact, IncidentUrgency},
+    problems::{self, ProblemCreateset},
+    roles,
+    timeline::{
+        self, TimelineEntity, TimelineEntry, TimelineEntryCreateset, TimelineEntryUpdateset,
+        TimelineVisibility,
+    },
+    users::{self, UserCreateset},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt, TEST_SUBJECT};
+use serde_json::json;
+use uuid::Uuid;
+
+async fn create_incident(context: &DbTestContext) -> Uuid {
+    let createset = IncidentCreateset {
+        title: String::from("Testing Incident"),
+        status: None,
+        created_at: None,
+        impact: IncidentImpact::Low,
+        urgency: IncidentUrgency::Low,
+        assignment_group_id: None,
+        assignee_id: None,
+        description: String::from("Testing yay!!"),
+        hold_reason: None,
+        resolution_code: None,
+        resolution_notes: None,
+        sla_policy_id: None,
+        parent_id: None,
+    };
+
+    incidents::create(createset, &context.db_pool)
+        .await
+        .unwrap()
+        .id
+}
+
+fn note(body: &str) -> TimelineEntryCreateset {
+    TimelineEntryCreateset {
+        parent_id: None,
+        visibility: TimelineVisibility::Internal,
+        body: String::from(body),
+    }
+}
+
+async fn post_entry(
+    context: &DbTestContext,
+    uri: &str,
+    createset: TimelineEntryCreateset,
+) -> axum::response::Response {
+    context
+        .app
+        .request(uri)
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    let uri = format!("/api/incidents/{}/timeline", incident_id);
+
+    let response = post_entry(context, &uri, note("Restarted the router")).await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let entry = response.into_body().into_json::<TimelineEntry>().await;
+    assert_that!(entry.entity_type, eq(TimelineEntity::Incident));
+    assert_that!(entry.entity_id, eq(incident_id));
+    assert_that!(entry.author, eq(TEST_SUBJECT));
+    assert_that!(entry.visibility, eq(TimelineVisibility::Internal));
+    assert_that!(entry.body, eq("Restarted the router"));
+    assert_that!(entry.updated_at, none());
+}
+
+#[db_test]
+async fn test_create_nonexistent_entity(context: &DbTestContext) {
+    let uri = format!("/api/incidents/{}/timeline", Uuid::new_v4());
+
+    let response = post_entry(context, &uri, note("Lost note")).await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    let uri = format!("/api/incidents/{}/timeline", incident_id);
+
+    let response = post_entry(context, &uri, note("")).await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_reply(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    let other_incident_id = create_incident(context).await;
+    let parent = timeline::create(
+        TimelineEntity::Incident,
+        incident_id,
+        note("Is the router on?"),
+        TEST_SUBJECT,
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let reply = TimelineEntryCreateset {
+        parent_id: Some(parent.id),
+        visibility: TimelineVisibility::Public,
+        body: String::from("It is now."),
+    };
+
+    let response = post_entry(
+        context,
+        &format!("/api/incidents/{}/timeline", incident_id),
+        reply.clone(),
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+    let entry = response.into_body().into_json::<TimelineEntry>().await;
+    assert_that!(entry.parent_id, some(eq(parent.id)));
+
+    // Replies can't cross timelines.
+    let response = post_entry(
+        context,
+        &format!("/api/incidents/{}/timeline", other_incident_id),
+        reply,
+    )
+    .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_read_paginated(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    for (body, visibility) in [
+        ("First", TimelineVisibility::Internal),
+        ("Second", TimelineVisibility::Public),
+        ("Third", TimelineVisibility::Internal),
+    ] {
+        timeline::create(
+            TimelineEntity::Incident,
+            incident_id,
+            TimelineEntryCreateset {
+                visibility,
+                ..note(body)
+            },
+            TEST_SUBJECT,
+            &context.db_pool,
+        )
+        .await
+        .unwrap();
+    }
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/timeline?limit=2", incident_id))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let page = response
+        .into_body()
+        .into_json::<Page<TimelineEntry>>()
+        .await;
+    assert_that!(page.total, eq(3));
+    assert_that!(page.next_page, some(eq(2)));
+    let bodies: Vec<String> = page.items.into_iter().map(|e| e.body).collect();
+    assert_that!(bodies, elements_are![eq("First"), eq("Second")]);
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents/{}/timeline?visibility=public",
+            incident_id
+        ))
+        .send()
+        .await;
+
+    let page = response
+        .into_body()
+        .into_json::<Page<TimelineEntry>>()
+        .await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items[0].body, eq("Second"));
+}
+
+#[db_test]
+async fn test_read_nonexistent_entity(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/problems/{}/timeline", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_problem_and_rfc_timelines(context: &DbTestContext) {
+    let problem = problems::create(
+        ProblemCreateset {
+            title: String::from("Problem for Testing"),
+            status: None,
+            detection_timedate: None,
+            description: String::from("This is a fake problem made for testing."),
+            causes: String::from("I need to test this."),
+            workarounds: None,
+            resolutions: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let requester = users::create(
+        UserCreateset {
+            username: String::from("requester"),
+            full_name: String::from("Testing User"),
+            email: None,
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let rfc = changes::create(
+        RFCCreateset {
+            title: String::from("Testing RFC"),
+            r#type: None,
+            status: None,
+            created_at: None,
+            finished_at: None,
+            requester_id: requester.id,
+            description: String::from("This is a fictional RFC made for testing."),
+            planned_start_at: None,
+            planned_end_at: None,
+            implementation_plan: Some(String::from("Roll out the change.")),
+            backout_plan: Some(String::from("Undo the change.")),
+            test_plan: Some(String::from("Check that it works.")),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    for (uri, entity_type) in [
+        (
+            format!("/api/problems/{}/timeline", problem.id),
+            TimelineEntity::Problem,
+        ),
+        (
+            format!("/api/changes/{}/timeline", rfc.id),
+            TimelineEntity::Rfc,
+        ),
+    ] {
+        let response = post_entry(context, &uri, note("Looking into it")).await;
+
+        assert_that!(response.status(), eq(StatusCode::CREATED));
+        let entry = response.into_body().into_json::<TimelineEntry>().await;
+        assert_that!(entry.entity_type, eq(entity_type));
+
+        let response = context.app.request(&uri).send().await;
+        let page = response
+            .into_body()
+            .into_json::<Page<TimelineEntry>>()
+            .await;
+        assert_that!(page.total, eq(1));
+    }
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    let entry = timeline::create(
+        TimelineEntity::Incident,
+        incident_id,
+        note("Restarted the routr"),
+        TEST_SUBJECT,
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let updateset = TimelineEntryUpdateset {
+        visibility: Some(TimelineVisibility::Public),
+        body: Some(String::from("Restarted the router")),
+    };
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents/{}/timeline/{}",
+            incident_id, entry.id
+        ))
+        .method(Method::PUT)
+        .body(Body::from(json!(updateset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+    let updated = response.into_body().into_json::<TimelineEntry>().await;
+    assert_that!(updated.body, eq("Restarted the router"));
+    assert_that!(updated.visibility, eq(TimelineVisibility::Public));
+    assert_that!(updated.updated_at, some(anything()));
+}
+
+#[db_test]
+async fn test_update_someone_elses_entry(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    let entry = timeline::create(
+        TimelineEntity::Incident,
+        incident_id,
+        note("Restarted the router"),
+        TEST_SUBJECT,
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    // Even an admin can't edit the words of someone else.
+    roles::assign("admin", "colleague", &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents/{}/timeline/{}",
+            incident_id, entry.id
+        ))
+        .method(Method::PUT)
+        .token(&context.token_for("colleague"))
+        .body(Body::from(
+            json!({"body": "The router was never restarted"}).to_string(),
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_update_after_edit_window(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+    let entry = timeline::create(
+        TimelineEntity::Incident,
+        incident_id,
+        note("Restarted the router"),
+        TEST_SUBJECT,
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+
+    let result = timeline::update(
+        TimelineEntity::Incident,
+        incident_id,
+        entry.id,
+        TimelineEntryUpdateset {
+            visibility: None,
+            body: Some(String::from("Too late")),
+        },
+        TEST_SUBJECT,
+        Duration::zero(),
+        &context.db_pool,
+    )
+    .await;
+
+    assert!(matches!(result, Err(itil_back_db::Error::NotAllowed(_))));
+}
+
+#[db_test]
+async fn test_update_nonexistent(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+
+    let response = context
+        .app
+        .request(&format!(
+            "/api/incidents/{}/timeline/{}",
+            incident_id,
+            Uuid::new_v4()
+        ))
+        .method(Method::PUT)
+        .body(Body::from(json!({"body": "Nothing here"}).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_create_needs_incidents_write(context: &DbTestContext) {
+    let incident_id = create_incident(context).await;
+
+    let response = context
+        .app
+        .request(&format!("/api/incidents/{}/timeline", incident_id))
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!(note("Sneaky note")).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
@@ -0,0 +1,227 @@
+use axum::{
+    body::Body,
+    http::{self, Method},
+};
+use googletest::prelude::*;
+use hyper::StatusCode;
+use itil_back_db::entities::{
+    teams::{self, TeamCreateset},
+    users::{self, User, UserCreateset, UserUpdateset},
+};
+use itil_back_db::pagination::Page;
+use itil_back_macros::db_test;
+use itil_back_web::test_helpers::{BodyExt, DbTestContext, RouterExt};
+use serde_json::json;
+use uuid::Uuid;
+
+fn create_basic_createset() -> UserCreateset {
+    UserCreateset {
+        username: String::from("jdoe"),
+        full_name: String::from("John Doe"),
+        email: Some(String::from("jdoe@example.com")),
+    }
+}
+
+#[db_test]
+async fn test_create_success(context: &DbTestContext) {
+    let createset = create_basic_createset();
+
+    let response = context
+        .app
+        .request("/api/users")
+        .method(Method::POST)
+        .body(Body::from(json!(createset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::CREATED));
+
+    let user = response.into_body().into_json::<User>().await;
+    assert_that!(user.username, eq(&createset.username));
+    assert_that!(user.full_name, eq(&createset.full_name));
+    assert_that!(user.email, eq(&createset.email));
+}
+
+#[db_test]
+async fn test_create_invalid(context: &DbTestContext) {
+    users::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let createset = create_basic_createset();
+    let mut sets = Vec::new();
+    sets.push(UserCreateset {
+        username: String::from(""),
+        ..createset.clone()
+    });
+    sets.push(UserCreateset {
+        username: String::from("other"),
+        full_name: String::from(&"x".repeat(256)),
+        ..createset.clone()
+    });
+    sets.push(UserCreateset {
+        username: String::from("other"),
+        email: Some(String::from("not an email")),
+        ..createset.clone()
+    });
+    // Username already taken.
+    sets.push(createset);
+
+    for set in sets {
+        let response = context
+            .app
+            .request("/api/users")
+            .method(Method::POST)
+            .body(Body::from(json!(set).to_string()))
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .send()
+            .await;
+
+        assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+    }
+}
+
+#[db_test]
+async fn test_create_needs_users_manage(context: &DbTestContext) {
+    let response = context
+        .app
+        .request("/api/users")
+        .method(Method::POST)
+        .token(&context.token_for("nobody"))
+        .body(Body::from(json!(create_basic_createset()).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::FORBIDDEN));
+}
+
+#[db_test]
+async fn test_read_all_filtered(context: &DbTestContext) {
+    let team = teams::create(
+        TeamCreateset {
+            name: String::from("Networking"),
+            description: String::from(""),
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    let expected = users::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+    users::create(
+        UserCreateset {
+            username: String::from("other"),
+            ..create_basic_createset()
+        },
+        &context.db_pool,
+    )
+    .await
+    .unwrap();
+    teams::members::create(team.id, expected.id, &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/users?team_id={}", team.id))
+        .token(&context.token_for("nobody"))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let page = response.into_body().into_json::<Page<User>>().await;
+    assert_that!(page.total, eq(1));
+    assert_that!(page.items, elements_are![eq(&expected)]);
+}
+
+#[db_test]
+async fn test_read_one_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/users/{}", Uuid::new_v4()))
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}
+
+#[db_test]
+async fn test_update_success(context: &DbTestContext) {
+    let user = users::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let updateset = UserUpdateset {
+        username: None,
+        full_name: Some(Some(String::from("Jane Doe"))),
+        email: Some(None),
+    };
+    let response = context
+        .app
+        .request(&format!("/api/users/{}", user.id))
+        .method(Method::PUT)
+        .body(Body::from(json!(updateset).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::OK));
+
+    let user_after = users::load(user.id, &context.db_pool).await.unwrap();
+    assert_that!(user_after.username, eq(&user.username));
+    assert_that!(user_after.full_name, eq("Jane Doe"));
+    assert_that!(user_after.email, none());
+}
+
+#[db_test]
+async fn test_update_set_required_null(context: &DbTestContext) {
+    let user = users::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/users/{}", user.id))
+        .method(Method::PUT)
+        .body(Body::from(json!({ "username": null }).to_string()))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::UNPROCESSABLE_ENTITY));
+}
+
+#[db_test]
+async fn test_delete_success(context: &DbTestContext) {
+    let user = users::create(create_basic_createset(), &context.db_pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .app
+        .request(&format!("/api/users/{}", user.id))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NO_CONTENT));
+
+    let result = users::load(user.id, &context.db_pool).await;
+    assert_that!(result, err(anything()));
+}
+
+#[db_test]
+async fn test_delete_nonexistent(context: &DbTestContext) {
+    let response = context
+        .app
+        .request(&format!("/api/users/{}", Uuid::new_v4()))
+        .method(Method::DELETE)
+        .send()
+        .await;
+
+    assert_that!(response.status(), eq(StatusCode::NOT_FOUND));
+}